@@ -125,8 +125,8 @@ impl Problem<Neat> for MemoryTest {
         let mut total = 0.0;
         for (ins, outs) in self.input.iter().zip(self.output.iter()) {
             match model.forward(&ins) {
-                Some(guess) => total += (guess[0] - outs[0]).powf(2.0),
-                None => panic!("Error in training NEAT")
+                Ok(guess) => total += (guess[0] - outs[0]).powf(2.0),
+                Err(e) => panic!("Error in training NEAT: {}", e)
             }
         }
         total /= self.input.len() as f32;
@@ -137,7 +137,9 @@ fn build_simulation(name: &str) -> Option<RadiateDto> {
                 species_target: 5
             })
             .stagnation(10)
-            .genocide(vec![Genocide::KillWorst(0.9)]);
+            .genocide(vec![Genocide::KillWorst(0.9)])
+            .verify(0.05, 1e-4, Some(3))
+            .batch(1.0, 50, true);
 
     // Build network, inputs, answers for the named problem.
     let (net, inputs, answers) = match name.to_uppercase().as_str() {
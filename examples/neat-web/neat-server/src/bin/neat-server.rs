@@ -21,7 +21,7 @@ use rocket_contrib::json::{Json, JsonValue};
 
 use neat_server::*;
 
-fn work_to_json(work: Option<WorkUnit>) -> JsonValue {
+fn work_to_json(work: Vec<WorkUnit>) -> JsonValue {
     json!(GetWorkResp {
         work: work,
     })
@@ -49,34 +49,38 @@ impl SimStorage {
         Some(id)
     }
 
-    pub fn get_work(&self, id: Option<&str>) -> JsonValue {
+    pub fn get_work(&self, id: Option<&str>, worker: WorkerInfo) -> JsonValue {
         if let Some(id) = id {
             // check simulation for more queued work
             let sim = self.get(&id);
             if let Some(sim) = sim {
                 let mut sim = sim.write().unwrap();
-                return work_to_json(sim.get_work());
+                return work_to_json(sim.get_work(worker));
             }
         } else {
             // find simulation with queued work.
             for sim in self.simulations.read().unwrap().values() {
                 let mut sim = sim.write().unwrap();
-                if let Some(work) = sim.get_work() {
-                    return work_to_json(Some(work));
+                let work = sim.get_work(worker);
+                if !work.is_empty() {
+                    return work_to_json(work);
                 }
             }
         }
-        work_to_json(None)
+        work_to_json(Vec::new())
     }
 
-    pub fn work_results(&self, id: &str, result: GetWorkResult, get_work: bool) -> Option<JsonValue> {
+    pub fn work_results(&self, id: &str, results: Vec<GetWorkResult>, worker: WorkerInfo, get_work: bool) -> Option<JsonValue> {
         if let Some(sim) = self.get(&id) {
             let mut sim = sim.write().unwrap();
-            sim.work_results(result);
+            sim.check_in(worker);
+            for result in results {
+                sim.work_results(result);
+            }
             if get_work {
-                Some(work_to_json(sim.get_work()))
+                Some(work_to_json(sim.get_work(worker)))
             } else {
-                Some(work_to_json(None))
+                Some(work_to_json(Vec::new()))
             }
         } else {
             None
@@ -101,6 +105,7 @@ fn main() {
             get_sim_status,
             get_sim_training_set,
             get_solution,
+            get_base,
             sim_get_work,
             work_results,
             new_sim,
@@ -110,14 +115,23 @@ fn main() {
 }
 
 
-#[get("/get_work")]
-fn get_work(sims: State<SimStorage>) -> JsonValue {
-    sims.get_work(None)
+/// workers say who they are so their results can be verified (see `VerifyDto`) and
+/// how many members a second they score so their batches can be sized (see `BatchDto`)
+fn parse_worker(worker: Option<String>, throughput: Option<f32>) -> WorkerInfo {
+    WorkerInfo {
+        id: worker.and_then(|worker| Uuid::parse_str(&worker).ok()),
+        throughput,
+    }
+}
+
+#[get("/get_work?<worker>&<throughput>")]
+fn get_work(sims: State<SimStorage>, worker: Option<String>, throughput: Option<f32>) -> JsonValue {
+    sims.get_work(None, parse_worker(worker, throughput))
 }
 
-#[get("/simulations/<id>/get_work")]
-fn sim_get_work(sims: State<SimStorage>, id: String) -> JsonValue {
-    sims.get_work(Some(&id))
+#[get("/simulations/<id>/get_work?<worker>&<throughput>")]
+fn sim_get_work(sims: State<SimStorage>, id: String, worker: Option<String>, throughput: Option<f32>) -> JsonValue {
+    sims.get_work(Some(&id), parse_worker(worker, throughput))
 }
 
 #[get("/simulations/<id>")]
@@ -140,6 +154,17 @@ fn get_solution(sims: State<SimStorage>, id: String) -> Option<JsonValue> {
     }
 }
 
+#[get("/simulations/<id>/base")]
+fn get_base(sims: State<SimStorage>, id: String) -> Option<JsonValue> {
+    let sim = sims.get(&id)?;
+    let mut sim = sim.write().unwrap();
+    let base = sim.get_base();
+    Some(json!(BaseResp {
+        curr_gen: sim.curr_gen(),
+        base: base.map(|base| (*base).clone()),
+    }))
+}
+
 #[get("/simulations/<id>/training_set")]
 fn get_sim_training_set(sims: State<SimStorage>, id: String) -> Option<JsonValue> {
     if let Some(sim) = sims.get(&id) {
@@ -150,9 +175,9 @@ fn get_sim_training_set(sims: State<SimStorage>, id: String) -> Option<JsonValue
     }
 }
 
-#[post("/simulations/<id>/work_results?<get_work>", format = "json", data = "<result>")]
-fn work_results(sims: State<SimStorage>, id: String, get_work: bool, result: Json<GetWorkResult>) -> Option<JsonValue> {
-    sims.work_results(&id, result.0, get_work)
+#[post("/simulations/<id>/work_results?<get_work>&<worker>&<throughput>", format = "json", data = "<results>")]
+fn work_results(sims: State<SimStorage>, id: String, get_work: bool, worker: Option<String>, throughput: Option<f32>, results: Json<Vec<GetWorkResult>>) -> Option<JsonValue> {
+    sims.work_results(&id, results.0, parse_worker(worker, throughput), get_work)
 }
 
 #[post("/simulations", format = "json", data = "<radiate>")]
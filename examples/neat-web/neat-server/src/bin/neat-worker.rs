@@ -7,7 +7,7 @@ extern crate serde_json;
 extern crate serde_derive;
 extern crate reqwest;
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::io::{self, Write};
 
 use std::sync::Arc;
@@ -15,6 +15,8 @@ use std::collections::{HashMap, hash_map::Entry};
 
 use uuid::Uuid;
 
+use serde_json::Value;
+
 use env_logger;
 use anyhow::Result;
 
@@ -34,6 +36,8 @@ fn flush() {
 #[derive(Debug, Default, Clone)]
 struct CacheSimData {
     training_data: Arc<RwLock<HashMap<Uuid, Arc<TrainingSet>>>>,
+    // the base member of each simulation's latest generation, see `Simulation::get_base`
+    bases: Arc<RwLock<HashMap<Uuid, (usize, Arc<Value>)>>>,
 }
 
 impl CacheSimData {
@@ -64,34 +68,69 @@ impl CacheSimData {
             },
         }
     }
+
+    /// get the base member a generation's work units were sent as deltas from, None if
+    /// the simulation has already moved on from the generation.
+    pub async fn get_base(&self, base_url: &str, id: Uuid, gen: usize) -> Result<Option<Arc<Value>>> {
+        let mut bases = self.bases.write().await;
+        match bases.get(&id) {
+            Some((cached, base)) if *cached == gen => return Ok(Some(base.clone())),
+            _ => {},
+        }
+        let url = format!("{}/simulations/{}/base", base_url, id);
+        // Work around reqwest issue with "Connection: close", don't re-use client.
+        let client = reqwest::Client::new();
+        let resp = client.get(&url)
+          .send().await?
+          .json::<BaseResp>().await?;
+        match resp.base {
+            Some(base) if resp.curr_gen == gen => {
+                let base = Arc::new(base);
+                bases.insert(id, (gen, base.clone()));
+                Ok(Some(base))
+            },
+            _ => Ok(None),
+        }
+    }
 }
 
-async fn work_results(base_url: &str, id: Uuid, mut work: WorkUnit, fitness: Option<f32>) -> Result<Option<WorkUnit>> {
-    let result = GetWorkResult {
-        id: work.id,
-        curr_gen: work.curr_gen,
-        task: work.task,
-        member: work.member.take(),
-        fitness,
-    };
-    let url = format!("{}/simulations/{}/work_results?get_work=true", base_url, id);
+/// who the worker is and, once it has scored a batch, how many members a second it scores.
+fn worker_query(worker: Uuid, throughput: Option<f32>) -> String {
+    match throughput {
+        Some(throughput) => format!("worker={}&throughput={}", worker, throughput),
+        None => format!("worker={}", worker),
+    }
+}
+
+async fn work_results(base_url: &str, id: Uuid, worker: Uuid, throughput: Option<f32>, work: Vec<(WorkUnit, Option<f32>)>) -> Result<Vec<WorkUnit>> {
+    let results = work.into_iter()
+        .map(|(mut work, fitness)| GetWorkResult {
+            id: work.id,
+            curr_gen: work.curr_gen,
+            task: work.task,
+            member: work.member.take(),
+            fitness,
+            worker: Some(worker),
+        })
+        .collect::<Vec<_>>();
+    let url = format!("{}/simulations/{}/work_results?get_work=true&{}", base_url, id, worker_query(worker, throughput));
     // Work around reqwest issue with "Connection: close", don't re-use client.
     let client = reqwest::Client::new();
 
     // upload work results and request more work
     let resp = client.post(&url)
-      .json(&result)
+      .json(&results)
       .send().await?
       .json::<GetWorkResp>().await?;
 
     Ok(resp.work)
 }
 
-async fn get_work(base_url: &str, id: Option<Uuid>) -> Result<Option<WorkUnit>> {
+async fn get_work(base_url: &str, id: Option<Uuid>, worker: Uuid, throughput: Option<f32>) -> Result<Vec<WorkUnit>> {
     let url = if let Some(sim_id) = id {
-        format!("{}/simulations/{}/get_work", base_url, sim_id)
+        format!("{}/simulations/{}/get_work?{}", base_url, sim_id, worker_query(worker, throughput))
     } else {
-        format!("{}/get_work", base_url)
+        format!("{}/get_work?{}", base_url, worker_query(worker, throughput))
     };
 
     // Work around reqwest issue with "Connection: close", don't re-use client.
@@ -103,8 +142,8 @@ async fn get_work(base_url: &str, id: Option<Uuid>) -> Result<Option<WorkUnit>>
     Ok(resp.work)
 }
 
-fn do_cal_fitness(work: &mut WorkUnit, data: &TrainingSet) -> Option<f32> {
-    if let Some(mut member) = work.member.take() {
+fn do_cal_fitness(work: &mut WorkUnit, data: &TrainingSet, base: Option<&Value>) -> Option<f32> {
+    if let Some(mut member) = work.take_member(base) {
         Some(data.solve(&mut member))
     } else {
         None
@@ -122,11 +161,11 @@ fn do_training(work: &mut WorkUnit, data: &TrainingSet) {
     }
 }
 
-async fn do_work_unit(mut work: WorkUnit, data: Arc<TrainingSet>) -> Result<(WorkUnit, Option<f32>)> {
+async fn do_work_unit(mut work: WorkUnit, data: Arc<TrainingSet>, base: Option<Arc<Value>>) -> Result<(WorkUnit, Option<f32>)> {
     Ok(task::spawn_blocking(move || {
         let fitness = match work.task {
             SimTaskType::CalFitness => {
-                do_cal_fitness(&mut work, &data)
+                do_cal_fitness(&mut work, &data, base.as_deref())
             },
             SimTaskType::TrainBest => {
                 do_training(&mut work, &data);
@@ -137,38 +176,53 @@ async fn do_work_unit(mut work: WorkUnit, data: Arc<TrainingSet>) -> Result<(Wor
     }).await?)
 }
 
-async fn do_work(cache: &CacheSimData, base_url: &str, work: WorkUnit) -> Result<usize> {
-    // get problem data for simulation.
-    let sim_id = work.sim_id;
+async fn do_work(cache: &CacheSimData, base_url: &str, worker: Uuid, throughput: &mut Option<f32>, work: Vec<WorkUnit>) -> Result<usize> {
+    // get problem data for simulation, every unit of a batch is from the same one.
+    let sim_id = work[0].sim_id;
     let data = cache.get_sim_data(base_url, sim_id).await?;
 
-    let mut work_count = 1;
+    let mut work_count = 0;
 
     let mut next_work = work;
-    loop {
-        let (work, fitness) = do_work_unit(next_work, data.clone()).await?;
+    while !next_work.is_empty() {
+        let start = Instant::now();
+        let size = next_work.len();
+        let scoring = next_work.iter().all(|work| work.task == SimTaskType::CalFitness);
+        let mut results = Vec::with_capacity(size);
+        for work in next_work.drain(..) {
+            let base = match work.delta {
+                Some(_) => cache.get_base(base_url, sim_id, work.curr_gen).await?,
+                None => None,
+            };
+            results.push(do_work_unit(work, data.clone(), base).await?);
 
-        print!("*");
-        flush();
-        // upload work results and get more work.
-        if let Some(new_work) = work_results(base_url, sim_id, work, fitness).await? {
-            next_work = new_work;
-            work_count += 1;
-        } else {
-            break;
+            print!("*");
+            flush();
+        }
+        // only scoring counts towards throughput, training the best takes as long as it takes.
+        if scoring {
+            let rate = size as f32 / start.elapsed().as_secs_f32().max(1e-6);
+            *throughput = Some(throughput.map_or(rate, |old| 0.7 * old + 0.3 * rate));
         }
+        work_count += size;
+        // upload work results and get more work.
+        next_work = work_results(base_url, sim_id, worker, *throughput, results).await?;
     }
     Ok(work_count)
 }
 
 async fn worker(id: usize, cache: CacheSimData, base_url: String) -> Result<()> {
+    // identifies this worker to the server so its results can be verified.
+    let worker_id = Uuid::new_v4();
+    // members scored a second, reported so the server can size this worker's batches.
+    let mut throughput = None;
     let mut sleep_time = 2000;
     loop {
-        let work = get_work(&base_url, None).await?;
-        if let Some(work) = work {
+        let work = get_work(&base_url, None, worker_id, throughput).await?;
+        if !work.is_empty() {
             // reset sleep time
             sleep_time = 200;
-            let count = do_work(&cache, &base_url, work).await?;
+            let count = do_work(&cache, &base_url, worker_id, &mut throughput, work).await?;
             println!("worker({}) count = {}", id, count);
         } else {
             delay_for(Duration::from_millis(sleep_time)).await;
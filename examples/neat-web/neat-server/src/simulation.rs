@@ -3,13 +3,18 @@ extern crate radiate_web;
 extern crate serde;
 extern crate serde_derive;
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use serde::{Serialize, Deserialize};
 
+use serde_json::Value;
+
 use uuid::Uuid;
 
 use radiate::prelude::*;
+use radiate::rng;
 use radiate_web::prelude::*;
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -69,8 +74,8 @@ impl Problem<Neat> for TrainingSet {
         let mut total = 0.0;
         for (ins, outs) in self.inputs.iter().zip(self.answers.iter()) {
             match model.forward(&ins) {
-                Some(guess) => total += (guess[0] - outs[0]).powf(2.0),
-                None => panic!("Error in training NEAT")
+                Ok(guess) => total += (guess[0] - outs[0]).powf(2.0),
+                Err(e) => panic!("Error in training NEAT: {}", e)
             }
         }
         self.answers.len() as f32 - total
@@ -90,11 +95,27 @@ pub enum WorkStatus {
     Finished(Duration),
 }
 
+/// a fitness a worker sent back for a task which is being verified.
+#[derive(Debug, Clone, Copy)]
+pub struct Opinion {
+    pub worker: Uuid,
+    pub fitness: f32,
+    pub at: Instant,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct SimTask {
     pub task: SimTaskType,
     pub status: WorkStatus,
     pub member_idx: Option<usize>,
+    // scored by two different workers, see `VerifyDto`
+    pub verify: bool,
+    // the first of those two results
+    pub first: Option<Opinion>,
+    // the worker running the task.
+    pub holder: Option<Uuid>,
+    // taken over by a faster worker, see `BatchDto`
+    pub stolen: bool,
 }
 
 impl SimTask {
@@ -103,12 +124,65 @@ impl SimTask {
             task,
             status: WorkStatus::Queued,
             member_idx,
+            verify: false,
+            first: None,
+            holder: None,
+            stolen: false,
         }
     }
 
     pub fn reset(&mut self) {
         self.status = WorkStatus::Queued;
+        self.verify = false;
+        self.first = None;
+        self.holder = None;
+        self.stolen = false;
     }
+
+    /// hand the task to a worker.
+    fn start(&mut self, worker: Option<Uuid>) {
+        self.status = WorkStatus::Running(Instant::now());
+        self.holder = worker;
+    }
+
+    /// whether the task is waiting on a second opinion from anyone but this worker.
+    pub fn waits_on_other(&self, worker: Option<Uuid>) -> bool {
+        self.first.map_or(false, |first| Some(first.worker) == worker)
+    }
+}
+
+/// How a worker's results have held up when they were verified, how fast it says it is and
+/// how many tasks it took over from slower workers.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WorkerRecord {
+    pub results: usize,
+    pub verified: usize,
+    pub mismatched: usize,
+    pub quarantined: bool,
+    pub throughput: Option<f32>,
+    pub stolen: usize,
+    #[serde(skip)]
+    pub last_seen: Option<Instant>,
+}
+
+/// A result a worker sent back which the server scored differently, see `Simulation::referee`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mismatch {
+    pub curr_gen: usize,
+    pub worker: Uuid,
+    pub member_idx: Option<usize>,
+    pub fitness: f32,
+    // what the server scored the member.
+    pub expected: f32,
+    // whether this is the mismatch the worker was quarantined for.
+    pub quarantined: bool,
+}
+
+/// Who is asking for work (or sending results) and how many members a second it says it scores.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WorkerInfo {
+    pub id: Option<Uuid>,
+    pub throughput: Option<f32>,
 }
 
 impl Default for SimTask {
@@ -125,12 +199,34 @@ pub struct WorkUnit {
     pub task: SimTaskType,
     pub member_idx: Option<usize>,
     pub member: Option<Neat>,
+    // the member as a delta from the generation's base (see `Simulation::get_base`), sent instead of the whole member.
+    #[serde(default)]
+    pub delta: Option<Delta>,
     pub train: Option<TrainDto>,
 }
 
+impl WorkUnit {
+    /// the member of the work unit, patching its delta onto the generation's base if it was sent as one.
+    pub fn take_member(&mut self, base: Option<&Value>) -> Option<Neat> {
+        match (self.member.take(), self.delta.take(), base) {
+            (Some(member), _, _) => Some(member),
+            (None, Some(delta), Some(base)) => serde_json::from_value(compress::patch(base, &delta)).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// the member each generation's work units are sent as a delta from.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BaseResp {
+    pub curr_gen: usize,
+    pub base: Option<Value>,
+}
+
+/// a batch of work units, see `BatchDto`.  Without batching there is at most one.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetWorkResp {
-    pub work: Option<WorkUnit>,
+    pub work: Vec<WorkUnit>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -140,6 +236,9 @@ pub struct GetWorkResult {
     pub task: SimTaskType,
     pub member: Option<Neat>,
     pub fitness: Option<f32>,
+    // which worker sent the results, results without one can't be verified.
+    #[serde(default)]
+    pub worker: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -157,6 +256,10 @@ pub struct SimulationStatus {
     pub curr_fitness: Option<f32>,
     pub last_gen_elapsed: Option<Duration>,
     pub solution: Option<Neat>,
+    pub workers: HashMap<Uuid, WorkerRecord>,
+    pub mismatches: Vec<Mismatch>,
+    // tasks the server did itself because no trusted worker was left to give them to.
+    pub server_scored: usize,
 }
 
 pub struct Simulation {
@@ -173,6 +276,18 @@ pub struct Simulation {
     last_finished: Instant,
     work_expire_timeout: Duration,
 
+    // redundant evaluation
+    verify: Option<VerifyDto>,
+    workers: HashMap<Uuid, WorkerRecord>,
+    mismatches: Vec<Mismatch>,
+    server_scored: usize,
+
+    // batch sizing and work stealing
+    batch: Option<BatchDto>,
+
+    // the generation's base member to send the rest as deltas from.
+    base: Option<(usize, Arc<Value>)>,
+
     // generations (evolving)
     curr_gen: usize,
     curr_gen_start: Instant,
@@ -241,7 +356,7 @@ impl Simulation {
             population = population.survivor_criteria(survivor_criteria);
         }
 
-        Some(Self {
+        let mut sim = Self {
             id: Uuid::new_v4(),
             status: Status::Evolving,
             population,
@@ -250,9 +365,20 @@ impl Simulation {
             work_queued: size as usize,
             work_running: 0,
             work_expired: 0,
-            work_expire_timeout: Duration::from_secs(5),
+            // the last task of a batch isn't started until the rest are done.
+            work_expire_timeout: Duration::from_secs(5) + pop.batch.as_ref()
+                .map_or(Duration::from_secs(0), |batch| Duration::from_secs_f32(batch.seconds)),
             last_finished: Instant::now(),
 
+            verify: pop.verify,
+            workers: HashMap::new(),
+            mismatches: Vec::new(),
+            server_scored: 0,
+
+            batch: pop.batch,
+
+            base: None,
+
             curr_gen: 0,
             curr_gen_start: Instant::now(),
             last_gen_elapsed: None,
@@ -266,7 +392,10 @@ impl Simulation {
 
             train,
             data,
-        })
+        };
+        // pick the members of the first generation to verify.
+        sim.reset_work();
+        Some(sim)
     }
 
     /// simulation's uuid
@@ -274,6 +403,11 @@ impl Simulation {
         self.id
     }
 
+    /// the generation being evolved.
+    pub fn curr_gen(&self) -> usize {
+        self.curr_gen
+    }
+
     /// get the simulation's training set.
     pub fn get_training_set(&self) -> &TrainingSet {
         &self.data
@@ -288,6 +422,9 @@ impl Simulation {
           last_gen_elapsed: self.last_gen_elapsed,
           curr_fitness: self.curr_fitness,
           solution: None,
+          workers: self.workers.clone(),
+          mismatches: self.mismatches.clone(),
+          server_scored: self.server_scored,
         };
         if self.status == Status::Finished {
             status.solution = self.solution.clone();
@@ -332,10 +469,19 @@ impl Simulation {
         }
     }
 
-    /// Prepare the work queue for the next generation.
+    /// check if a worker has been quarantined.
+    pub fn is_quarantined(&self, worker: Option<Uuid>) -> bool {
+        worker.and_then(|worker| self.workers.get(&worker))
+            .map_or(false, |record| record.quarantined)
+    }
+
+    /// Prepare the work queue for the next generation, picking which members to verify.
     fn reset_work(&mut self) {
+        let rate = self.verify.as_ref().map_or(0.0, |verify| verify.rate);
+        let mut r = rng::thread();
         for work in self.work.iter_mut() {
             work.reset();
+            work.verify = work.task == SimTaskType::CalFitness && rng::chance(&mut r, rate);
         }
         self.work_queued = self.work.len();
         self.work_running = 0;
@@ -346,6 +492,11 @@ impl Simulation {
     fn finished_work(&mut self) {
         // update number of running jobs.
         self.work_running -= 1;
+        self.end_if_finished();
+    }
+
+    /// end the generation (or training) once every task has finished.
+    fn end_if_finished(&mut self) {
         self.last_finished = Instant::now();
         // check if all queued & running jobs have finished.
         if self.work_running == 0 && self.work_queued == 0 {
@@ -371,49 +522,225 @@ impl Simulation {
             // results for old generation.
             return;
         }
+        // results from a quarantined worker can't be trusted, the task expires and goes to another worker.
+        if self.is_quarantined(result.worker) {
+            return;
+        }
+        if let Some(worker) = result.worker {
+            self.workers.entry(worker).or_default().results += 1;
+        }
 
-        if let Some(work) = self.work.get_mut(result.id) {
-            // check if the results is for the correct task type.
-            if work.task != result.task {
-                // old results from generation, ignore.
-                return;
-            }
-            // check if work has already finished.
-            match work.status {
-                WorkStatus::Queued => {
-                    // re-scheduled?
-                },
-                WorkStatus::Running(start) => {
-                    work.status = WorkStatus::Finished(start.elapsed());
-                    match work.task {
-                        SimTaskType::CalFitness => {
-                            // get member
-                            let member = work.member_idx
-                              .and_then(|idx| self.member_mut(idx));
-                            if let Some(member) = member {
-                                // update fitness
-                                if let Some(fitness) = result.fitness {
-                                    member.set_fitness(fitness);
-                                }
-                                // update member Genome
-                                if let Some(new_member) = result.member {
-                                    member.update_member(new_member);
-                                }
-                            }
+        let work = match self.work.get(result.id) {
+            Some(work) => *work,
+            None => return,
+        };
+        // check if the results is for the correct task type.
+        if work.task != result.task {
+            // old results from generation, ignore.
+            return;
+        }
+        // check if work has already finished.
+        let start = match work.status {
+            WorkStatus::Running(start) => start,
+            // queued: re-scheduled?  finished: work has already finished.  Ignore old results.
+            _ => return,
+        };
+        match work.task {
+            SimTaskType::CalFitness => {
+                let mut fitness = result.fitness;
+                if work.verify {
+                    match (work.first, result.worker, result.fitness) {
+                        // the first opinion, queue the task again for another worker to give the second.
+                        (None, Some(worker), Some(fitness)) => {
+                            let task = &mut self.work[result.id];
+                            task.first = Some(Opinion { worker, fitness, at: Instant::now() });
+                            task.status = WorkStatus::Queued;
+                            task.holder = None;
+                            task.stolen = false;
+                            self.work_running -= 1;
+                            self.work_queued += 1;
+                            return;
                         },
-                        SimTaskType::TrainBest => {
-                            if let Some(new_member) = result.member {
-                                self.solution = Some(new_member);
-                            }
+                        // the task expired and came back to the same worker, which proves nothing.
+                        (Some(first), Some(worker), _) if first.worker == worker => return,
+                        (Some(first), Some(worker), Some(second)) => {
+                            fitness = self.compare(result.id, first, Opinion { worker, fitness: second, at: Instant::now() });
                         },
+                        // a worker which doesn't say who it is can't be verified.
+                        _ => {},
+                    }
+                }
+                self.work[result.id].status = WorkStatus::Finished(start.elapsed());
+                // get member
+                let member = work.member_idx
+                  .and_then(|idx| self.member_mut(idx));
+                if let Some(member) = member {
+                    // update fitness
+                    if let Some(fitness) = fitness {
+                        member.set_fitness(fitness);
+                    }
+                    // update member Genome
+                    if let Some(new_member) = result.member {
+                        member.update_member(new_member);
+                    }
+                }
+            },
+            SimTaskType::TrainBest => {
+                self.work[result.id].status = WorkStatus::Finished(start.elapsed());
+                if let Some(new_member) = result.member {
+                    self.solution = Some(new_member);
+                }
+            },
+        }
+        // check if generation has finished.
+        self.finished_work();
+    }
+
+    /// Compare the two opinions of a verified task.  If they agree within tolerance the first is
+    /// taken, otherwise the server scores the member itself to find out which worker was wrong.
+    fn compare(&mut self, id: usize, first: Opinion, second: Opinion) -> Option<f32> {
+        let tolerance = self.verify.as_ref().map_or(0.0, |verify| verify.tolerance);
+        if (first.fitness - second.fitness).abs() <= tolerance {
+            for opinion in &[first, second] {
+                self.workers.entry(opinion.worker).or_default().verified += 1;
+            }
+            return Some(first.fitness);
+        }
+        self.referee(id, &[first, second])
+    }
+
+    /// Score a task's member on the server, counting a mismatch against every worker whose
+    /// opinion is out of tolerance and quarantining those with too many.  Each mismatch is kept
+    /// for the simulation's status.
+    fn referee(&mut self, id: usize, opinions: &[Opinion]) -> Option<f32> {
+        let mut member = self.work[id].member_idx
+            .and_then(|idx| self.member(idx))
+            .map(|cont| cont.member.read().unwrap().clone())?;
+        let fitness = self.data.solve(&mut member);
+        let (tolerance, quarantine_after) = self.verify.as_ref()
+            .map_or((0.0, None), |verify| (verify.tolerance, verify.quarantine_after));
+        for opinion in opinions {
+            let record = self.workers.entry(opinion.worker).or_default();
+            if (opinion.fitness - fitness).abs() <= tolerance {
+                record.verified += 1;
+                continue;
+            }
+            record.mismatched += 1;
+            let quarantined = !record.quarantined && quarantine_after.map_or(false, |limit| record.mismatched >= limit);
+            record.quarantined |= quarantined;
+            self.mismatches.push(Mismatch {
+                curr_gen: self.curr_gen,
+                worker: opinion.worker,
+                member_idx: self.work[id].member_idx,
+                fitness: opinion.fitness,
+                expected: fitness,
+                quarantined,
+            });
+        }
+        Some(fitness)
+    }
+
+    /// A task waiting on a second opinion nobody has come to give (ie: there is only one worker)
+    /// is checked by the server itself once it has waited as long as it takes work to expire.
+    fn check_stalled(&mut self) {
+        let timeout = self.work_expire_timeout;
+        let stalled = self.work.iter()
+            .enumerate()
+            .filter(|(_, work)| work.status == WorkStatus::Queued && work.first.map_or(false, |first| first.at.elapsed() > timeout))
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        for id in stalled {
+            // the generation may have ended (and the work been reset) checking an earlier task.
+            let first = match self.work[id].first {
+                Some(first) if self.work[id].status == WorkStatus::Queued => first,
+                _ => continue,
+            };
+            let fitness = self.referee(id, &[first]);
+            self.work[id].status = WorkStatus::Finished(first.at.elapsed());
+            self.work_queued -= 1;
+            let member = self.work[id].member_idx.and_then(|idx| self.member_mut(idx));
+            if let (Some(member), Some(fitness)) = (member, fitness) {
+                member.set_fitness(fitness);
+            }
+            self.end_if_finished();
+        }
+    }
+
+    /// whether a worker which isn't quarantined has asked for work or sent results recently.
+    pub fn has_trusted_worker(&self) -> bool {
+        let recent = self.work_expire_timeout;
+        self.workers.values()
+            .any(|record| !record.quarantined && record.last_seen.map_or(false, |seen| seen.elapsed() < recent))
+    }
+
+    /// Once no trusted worker is left the server does every task left in the generation (or the
+    /// training) itself, otherwise the tasks would go back and forth between quarantined workers,
+    /// whose results are ignored, and the generation would never end.
+    fn score_on_server(&mut self) {
+        if self.has_trusted_worker() || self.status == Status::Finished {
+            return;
+        }
+        let left = self.work.iter()
+            .enumerate()
+            .filter(|(_, work)| !matches!(work.status, WorkStatus::Finished(_)))
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        if left.is_empty() {
+            return;
+        }
+        for id in left {
+            let work = self.work[id];
+            let start = match work.status {
+                WorkStatus::Running(start) => {
+                    self.work_running -= 1;
+                    start
+                },
+                _ => {
+                    self.work_queued -= 1;
+                    Instant::now()
+                },
+            };
+            match work.task {
+                SimTaskType::CalFitness => {
+                    let member = work.member_idx
+                        .and_then(|idx| self.member(idx))
+                        .map(|cont| cont.member.read().unwrap().clone());
+                    if let Some(mut member) = member {
+                        let fitness = self.data.solve(&mut member);
+                        if let Some(cont) = work.member_idx.and_then(|idx| self.member_mut(idx)) {
+                            cont.set_fitness(fitness);
+                        }
                     }
-                    // check if generation has finished.
-                    self.finished_work();
                 },
-                WorkStatus::Finished(_) => {
-                    // work has already finished.  Ignore old results.
+                SimTaskType::TrainBest => {
+                    if let Some(mut solution) = self.solution.take() {
+                        self.data.train(&self.train, &mut solution);
+                        self.solution = Some(solution);
+                    }
                 },
             }
+            self.work[id].status = WorkStatus::Finished(start.elapsed());
+            self.server_scored += 1;
+        }
+        // every running task (expired or not) is finished now.
+        self.work_expired = 0;
+        self.end_if_finished();
+    }
+
+    /// The member the current generation's work units are sent as deltas from, the first member
+    /// of the generation as it was when its first work unit went out.  None once it's training.
+    pub fn get_base(&mut self) -> Option<Arc<Value>> {
+        if self.status != Status::Evolving {
+            return None;
+        }
+        match &self.base {
+            Some((gen, base)) if *gen == self.curr_gen => Some(base.clone()),
+            _ => {
+                let member = self.member(0).map(|cont| cont.member.read().unwrap().clone())?;
+                let base = Arc::new(compress::to_value(&member).ok()?);
+                self.base = Some((self.curr_gen, base.clone()));
+                Some(base)
+            },
         }
     }
 
@@ -426,12 +753,19 @@ impl Simulation {
             task: work.task,
             member_idx: work.member_idx,
             member: None,
+            delta: None,
             train: None,
         };
         match job.task {
             SimTaskType::CalFitness => {
-                if let Some(idx) = work.member_idx {
-                    job.member = self.member(idx).map(|cont| cont.member.read().unwrap().clone());
+                let member = work.member_idx.and_then(|idx| self.member(idx)).map(|cont| cont.member.read().unwrap().clone());
+                // the members of a generation are mostly the same network, only send how this one differs.
+                match (member, self.get_base()) {
+                    (Some(member), Some(base)) => match compress::to_value(&member) {
+                        Ok(value) => job.delta = Some(compress::diff(&base, &value)),
+                        Err(_) => job.member = Some(member),
+                    },
+                    (member, _) => job.member = member,
                 }
             },
             SimTaskType::TrainBest => {
@@ -443,15 +777,66 @@ impl Simulation {
         Some(job)
     }
 
-    /// Get a work unit for this simulation if there is queued or expired work.
-    pub fn get_work(&mut self) -> Option<WorkUnit> {
+    /// record what a worker says about itself each time it asks for work or sends results.
+    pub fn check_in(&mut self, worker: WorkerInfo) {
+        if let Some(id) = worker.id {
+            let record = self.workers.entry(id).or_default();
+            record.last_seen = Some(Instant::now());
+            if let Some(throughput) = worker.throughput.filter(|throughput| throughput.is_finite() && *throughput > 0.0) {
+                record.throughput = Some(throughput);
+            }
+        }
+    }
+
+    /// how fast a worker says it is, 0 for one which hasn't said.
+    fn throughput(&self, worker: Option<Uuid>) -> f32 {
+        worker.and_then(|worker| self.workers.get(&worker))
+            .and_then(|record| record.throughput)
+            .unwrap_or(0.0)
+    }
+
+    /// the throughput of every worker seen recently put together.
+    fn total_throughput(&self) -> f32 {
+        let recent = self.work_expire_timeout;
+        self.workers.values()
+            .filter(|record| record.last_seen.map_or(false, |seen| seen.elapsed() < recent))
+            .filter_map(|record| record.throughput)
+            .sum::<f32>()
+    }
+
+    /// How many of the tasks left to give a worker at once, see `plan_batch`.  One without batching.
+    fn batch_size(&self, worker: Option<Uuid>, left: usize) -> usize {
+        match &self.batch {
+            Some(batch) => plan_batch(batch, self.throughput(worker), self.total_throughput(), left),
+            None => 1,
+        }
+    }
+
+    /// Get a batch of work units for this simulation if there is queued or expired work.
+    /// Tasks waiting on a second opinion aren't given back to the worker which gave the first.
+    /// A quarantined worker gets nothing, and once nobody else is left the server does the work itself.
+    pub fn get_work(&mut self, worker: WorkerInfo) -> Vec<WorkUnit> {
+        let mut batch = Vec::new();
+        if self.is_quarantined(worker.id) {
+            self.score_on_server();
+            return batch;
+        }
+        self.check_in(worker);
         if self.has_work() {
-            let work = self.get_queued_work().or_else(|| {
-                // get next expired work.
-                self.get_expired_work()
-            });
-            if let Some((id, work)) = work {
-                return self.work_to_job(id, work);
+            let size = self.batch_size(worker.id, self.work_queued + self.work_expired);
+            while batch.len() < size {
+                let work = self.get_queued_work(worker.id).or_else(|| {
+                    // get next expired work.
+                    self.get_expired_work(worker.id)
+                });
+                match work {
+                    Some((id, work)) => batch.extend(self.work_to_job(id, work)),
+                    None => break,
+                }
+            }
+            if batch.is_empty() {
+                // the only work left might be waiting on a second opinion from another worker.
+                self.check_stalled();
             }
         } else {
             // check for expired work.
@@ -459,15 +844,45 @@ impl Simulation {
                 self.find_expired_work();
             }
         }
-        None
+        if batch.is_empty() {
+            batch = self.steal_work(worker.id);
+        }
+        batch
+    }
+
+    /// Once nothing is queued a worker faster than the ones still running tasks takes over the last
+    /// tasks of their batches (see `plan_steal`).  Both run them and whichever result comes back
+    /// first is used, the other is ignored like any late result.  A task is only stolen once.
+    fn steal_work(&mut self, thief: Option<Uuid>) -> Vec<WorkUnit> {
+        let batch = match (&self.batch, thief) {
+            (Some(batch), Some(_)) if batch.steal && self.status == Status::Evolving => batch,
+            _ => return Vec::new(),
+        };
+        let running = self.work.iter()
+            .enumerate()
+            .filter(|(_, work)| matches!(work.status, WorkStatus::Running(_)) && work.task == SimTaskType::CalFitness)
+            .filter(|(_, work)| !work.stolen && work.holder != thief && !work.waits_on_other(thief))
+            .map(|(id, work)| (id, self.throughput(work.holder)))
+            .collect::<Vec<_>>();
+        let stolen = plan_steal(batch, self.throughput(thief), self.total_throughput(), &running);
+        let mut batch = Vec::with_capacity(stolen.len());
+        for id in stolen {
+            self.work[id].stolen = true;
+            let work = self.work[id];
+            batch.extend(self.work_to_job(id, work));
+        }
+        if let (Some(thief), false) = (thief, batch.is_empty()) {
+            self.workers.entry(thief).or_default().stolen += batch.len();
+        }
+        batch
     }
 
     /// Find queued work.
-    fn get_queued_work(&mut self) -> Option<(usize, SimTask)> {
+    fn get_queued_work(&mut self, worker: Option<Uuid>) -> Option<(usize, SimTask)> {
         // TODO: track index of next task to avoid looping.
         for (id, work) in self.work.iter_mut().enumerate() {
-            if work.status == WorkStatus::Queued {
-                work.status = WorkStatus::Running(Instant::now());
+            if work.status == WorkStatus::Queued && !work.waits_on_other(worker) {
+                work.start(worker);
                 self.work_queued -= 1;
                 self.work_running += 1;
                 return Some((id, *work));
@@ -495,13 +910,13 @@ impl Simulation {
     }
 
     /// Find an expired job.
-    fn get_expired_work(&mut self) -> Option<(usize, SimTask)> {
+    fn get_expired_work(&mut self, worker: Option<Uuid>) -> Option<(usize, SimTask)> {
         for (id, work) in self.work.iter_mut().enumerate() {
             match work.status {
                 WorkStatus::Running(start) => {
-                    if start.elapsed() > self.work_expire_timeout {
+                    if start.elapsed() > self.work_expire_timeout && !work.waits_on_other(worker) {
                         self.work_expired -= 1;
-                        work.status = WorkStatus::Running(Instant::now());
+                        work.start(worker);
                         return Some((id, *work));
                     }
                 },
@@ -562,3 +977,160 @@ impl Simulation {
         self.curr_epoch = self.train.epochs as usize;
     }
 }
+
+/// How many of the `left` tasks to give a worker scoring `throughput` members a second at once.  Enough
+/// to keep it busy for the batch's seconds, but no more than its share of them by throughput among the
+/// workers seen recently (`total`, so the fast workers don't take everything and leave the slow ones
+/// idle) or the max size.  A worker which hasn't said how fast it is gets one at a time.
+pub fn plan_batch(batch: &BatchDto, throughput: f32, total: f32, left: usize) -> usize {
+    if throughput <= 0.0 {
+        return 1;
+    }
+    let share = (left as f32 * throughput / total.max(throughput)).ceil() as usize;
+    let size = (throughput * batch.seconds).round() as usize;
+    size.min(share).min(batch.max_size).max(1)
+}
+
+/// Which of the `running` tasks (by id, with the throughput of the worker holding each) a worker scoring
+/// `speed` members a second takes over.  Only tasks held by slower workers, slowest worker first and
+/// the last tasks of its batch before the rest since tasks are handed out in order, so the last ones
+/// of a batch are the furthest from done.  No more than a batch for the thief, see `plan_batch`.
+pub fn plan_steal(batch: &BatchDto, speed: f32, total: f32, running: &[(usize, f32)]) -> Vec<usize> {
+    let mut stragglers = running.iter()
+        .filter(|(_, holder)| *holder < speed)
+        .copied()
+        .collect::<Vec<_>>();
+    stragglers.sort_by(|(one, one_speed), (two, two_speed)| one_speed.total_cmp(two_speed).then(two.cmp(one)));
+    stragglers.truncate(plan_batch(batch, speed, total, stragglers.len()));
+    stragglers.into_iter().map(|(id, _)| id).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn batch() -> BatchDto {
+        BatchDto { seconds: 2.0, max_size: 50, steal: true }
+    }
+
+    /// an xor simulation of 4 members verifying every result, quarantining a worker at its first mismatch.
+    fn verified() -> Simulation {
+        let data = TrainingSet::new();
+        let net = Neat::new()
+            .input_size(2)
+            .dense(3, Activation::Relu)
+            .dense_pool(1, Activation::Sigmoid);
+        let population = NeatPopulationBuilder::new()
+            .num_evolve(5)
+            .size(4)
+            .config(Config { inbreed_rate: 0.001, crossover_rate: 0.75, distance: 0.5, species_target: 5 })
+            .verify(1.0, 1e-4, Some(1));
+        Simulation::new_from(RadiateDto::new()
+            .env(radiate::default_neat_env().set_input_size(2).set_output_size(1))
+            .train(1, 0.1)
+            .training_set(data.inputs, data.answers)
+            .neat(net)
+            .population(population)).unwrap()
+    }
+
+    fn worker(id: Uuid) -> WorkerInfo {
+        WorkerInfo { id: Some(id), throughput: None }
+    }
+
+    fn result(unit: &WorkUnit, worker: Uuid, fitness: f32) -> GetWorkResult {
+        GetWorkResult { id: unit.id, curr_gen: unit.curr_gen, task: unit.task, member: None, fitness: Some(fitness), worker: Some(worker) }
+    }
+
+    /// two workers which disagree with each other and the server about the first task.
+    fn quarantine_both(sim: &mut Simulation, one: Uuid, two: Uuid) {
+        let first = sim.get_work(worker(one)).pop().unwrap();
+        sim.work_results(result(&first, one, -100.0));
+        let second = sim.get_work(worker(two)).pop().unwrap();
+        assert_eq!(second.id, first.id);
+        sim.work_results(result(&second, two, -50.0));
+    }
+
+    #[test]
+    fn mismatches_are_in_the_status() {
+        let mut sim = verified();
+        let (one, two) = (Uuid::new_v4(), Uuid::new_v4());
+        quarantine_both(&mut sim, one, two);
+        let status = sim.get_status();
+        assert_eq!(status.mismatches.len(), 2);
+        for (mismatch, (id, fitness)) in status.mismatches.iter().zip(vec![(one, -100.0), (two, -50.0)]) {
+            assert_eq!((mismatch.worker, mismatch.fitness, mismatch.curr_gen), (id, fitness, 0));
+            assert_eq!(mismatch.member_idx, Some(0));
+            assert!(mismatch.quarantined);
+            assert!(mismatch.expected >= 0.0 && mismatch.expected <= 4.0);
+            assert!(status.workers[&id].quarantined);
+        }
+        assert!(sim.get_work(worker(one)).is_empty());
+    }
+
+    #[test]
+    fn server_scores_once_every_worker_is_quarantined() {
+        let mut sim = verified();
+        let (one, two) = (Uuid::new_v4(), Uuid::new_v4());
+        quarantine_both(&mut sim, one, two);
+        assert!(!sim.has_trusted_worker());
+        // the quarantined workers' results are ignored, so the server does the three tasks left.
+        assert!(sim.get_work(worker(one)).is_empty());
+        assert_eq!(sim.curr_gen(), 1);
+        assert_eq!(sim.get_status().server_scored, 3);
+        assert!((0..4).all(|idx| sim.member(idx).is_some()));
+        // one generation each time a quarantined worker asks.
+        assert!(sim.get_work(worker(two)).is_empty());
+        assert_eq!(sim.curr_gen(), 2);
+        assert_eq!(sim.get_status().server_scored, 7);
+    }
+
+    #[test]
+    fn server_leaves_work_to_trusted_workers() {
+        let mut sim = verified();
+        let (one, two, three) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        quarantine_both(&mut sim, one, two);
+        let unit = sim.get_work(worker(three)).pop().unwrap();
+        assert!(sim.has_trusted_worker());
+        assert!(sim.get_work(worker(one)).is_empty());
+        assert_eq!((sim.curr_gen(), sim.get_status().server_scored), (0, 0));
+        // its result waits on a second opinion like any other.
+        sim.work_results(result(&unit, three, 2.0));
+        assert_eq!(sim.get_status().server_scored, 0);
+    }
+
+    #[test]
+    fn batches_follow_throughput() {
+        // three workers scoring 10, 30 and 60 members a second split 100 tasks by their share.
+        assert_eq!(plan_batch(&batch(), 10.0, 100.0, 100), 10);
+        assert_eq!(plan_batch(&batch(), 30.0, 100.0, 100), 30);
+        // the fastest is capped by the max size before its share of 60.
+        assert_eq!(plan_batch(&batch(), 60.0, 100.0, 100), 50);
+        // with plenty left a worker only takes what it can do in the batch's seconds.
+        assert_eq!(plan_batch(&batch(), 10.0, 100.0, 1000), 20);
+        // near the end of a generation the shares round up so nothing is left over.
+        assert_eq!(plan_batch(&batch(), 60.0, 100.0, 3), 2);
+        assert_eq!(plan_batch(&batch(), 10.0, 100.0, 3), 1);
+        // a worker missing from the recent total is counted as all of it.
+        assert_eq!(plan_batch(&batch(), 30.0, 0.0, 100), 50);
+        // one which hasn't said how fast it is gets a task at a time.
+        assert_eq!(plan_batch(&batch(), 0.0, 100.0, 100), 1);
+    }
+
+    #[test]
+    fn stealing_takes_from_stragglers() {
+        // tasks 0 and 1 are the end of a slow worker's batch, 2 is held by a mid worker, 3 by the
+        // thief's equal and 4 by a straggler which never said how fast it is.
+        let running = [(0, 10.0), (1, 10.0), (2, 30.0), (3, 60.0), (4, 0.0)];
+        // 4 candidates, the thief's share of them by throughput is 3.
+        assert_eq!(plan_steal(&batch(), 60.0, 100.0, &running), vec![4, 1, 0]);
+        // a mid speed thief only takes from the slower workers, up to its share.
+        assert_eq!(plan_steal(&batch(), 30.0, 100.0, &running), vec![4]);
+        assert_eq!(plan_steal(&batch(), 30.0, 30.0, &running), vec![4, 1, 0]);
+        // nobody is slower than the slowest worker, and a worker of unknown speed takes nothing.
+        assert!(plan_steal(&batch(), 10.0, 100.0, &running[..4]).is_empty());
+        assert!(plan_steal(&batch(), 0.0, 100.0, &running).is_empty());
+        // the batch's max size still applies.
+        let small = BatchDto { seconds: 2.0, max_size: 2, steal: true };
+        assert_eq!(plan_steal(&small, 60.0, 60.0, &running), vec![4, 1]);
+    }
+}
@@ -114,8 +114,8 @@ impl Problem<Neat> for XOR {
         let mut total = 0.0;
         for (ins, outs) in self.inputs.iter().zip(self.answers.iter()) {
             match model.forward(&ins) {
-                Some(guess) => total += (guess[0] - outs[0]).powf(2.0),
-                None => panic!("Error in training NEAT")
+                Ok(guess) => total += (guess[0] - outs[0]).powf(2.0),
+                Err(e) => panic!("Error in training NEAT: {}", e)
             }
         }
         4.0 - total
@@ -0,0 +1,113 @@
+extern crate toml;
+
+use std::fs;
+use std::fmt;
+use std::error::Error;
+
+use serde::de::DeserializeOwned;
+use toml::value::Table;
+
+
+
+/// Error raised when reading a config file, wherever possible the error
+/// names the key in the file which caused it so a bad value in a config
+/// sweep can be tracked down without reading through the whole file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Io(String),
+    Parse(String),
+    UnknownKey(String),
+    MissingKey(String),
+    InvalidValue { key: String, reason: String }
+}
+
+
+
+impl ConfigError {
+
+    pub fn invalid(key: &str, reason: &str) -> Self {
+        ConfigError::InvalidValue {
+            key: key.to_string(),
+            reason: reason.to_string()
+        }
+    }
+
+
+    /// the key in the config file the error is about, if known
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            ConfigError::UnknownKey(key) | ConfigError::MissingKey(key) => Some(key),
+            ConfigError::InvalidValue { key, .. } => Some(key),
+            _ => None
+        }
+    }
+}
+
+
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "unable to read config: {}", err),
+            ConfigError::Parse(err) => write!(f, "unable to parse config: {}", err),
+            ConfigError::UnknownKey(key) => write!(f, "unknown key `{}`", key),
+            ConfigError::MissingKey(key) => write!(f, "missing required key `{}`", key),
+            ConfigError::InvalidValue { key, reason } => write!(f, "invalid value for `{}`: {}", key, reason)
+        }
+    }
+}
+
+
+
+impl Error for ConfigError {}
+
+
+
+/// read a config file to a string, naming the file if it can't be read
+pub(crate) fn read(path: &str) -> Result<String, ConfigError> {
+    fs::read_to_string(path).map_err(|e| ConfigError::Io(format!("{}: {}", path, e)))
+}
+
+
+
+/// parse the file into a table so the keys can be checked before 
+/// the file is turned into the config struct itself
+pub(crate) fn parse_table(contents: &str) -> Result<Table, ConfigError> {
+    toml::from_str::<Table>(contents).map_err(|e| ConfigError::Parse(e.to_string()))
+}
+
+
+
+/// make sure every key in the table is one the config knows about, catching typos 
+/// which would otherwise silently fall back to a default. Keys are reported with the 
+/// prefix of the table they came from, ie: config.distance
+pub(crate) fn check_keys(table: &Table, prefix: &str, known: &[&str], required: &[&str]) -> Result<(), ConfigError> {
+    for key in table.keys() {
+        if !known.contains(&key.as_str()) {
+            return Err(ConfigError::UnknownKey(format!("{}{}", prefix, key)));
+        }
+    }
+    for key in required.iter() {
+        if !table.contains_key(*key) {
+            return Err(ConfigError::MissingKey(format!("{}{}", prefix, key)));
+        }
+    }
+    Ok(())
+}
+
+
+
+/// parse the file into the config struct, toml's error names the key on a bad value
+pub(crate) fn parse<T: DeserializeOwned>(contents: &str) -> Result<T, ConfigError> {
+    toml::from_str::<T>(contents).map_err(|e| ConfigError::Parse(e.to_string()))
+}
+
+
+
+/// probabilities in a config need to fall between 0 and 1
+pub(crate) fn check_rate(key: &str, value: f32) -> Result<(), ConfigError> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(ConfigError::invalid(key, &format!("expected a probability between 0 and 1, got {}", value)));
+    }
+    Ok(())
+}
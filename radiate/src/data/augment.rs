@@ -0,0 +1,98 @@
+extern crate rand;
+
+use std::fmt::Debug;
+use rand::Rng;
+
+use crate::rng;
+
+
+
+
+/// Augment is applied to a single input sample before it is fed through a network. 
+/// Augmentations are meant to be random, each call should return a slightly different
+/// version of the sample so the network sees more variation than the raw dataset holds.
+/// The sample is treated as a plain feature vector, or as a sequence (one value per 
+/// time step) for augmentations like time warping.
+pub trait Augment: Debug + Send + Sync {
+    fn augment(&self, sample: &[f32]) -> Vec<f32>;
+}
+
+
+
+/// Built in augmentations
+///
+/// Jitter - add gaussian noise with the given standard deviation to each value
+/// Scale - multiply the whole sample by a random factor between (low, high), by low
+///         if the range is empty so Scale(1.0, 1.0) leaves the sample as it is
+/// TimeWarp - treat the sample as a sequence and randomly stretch or squeeze it by 
+///            up to the given fraction, the result is resampled back to the original length.
+///            A fraction of 0 or less leaves it as it is
+/// FeatureDropout - set each value to zero with the given probability
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+pub enum Augmentation {
+    Jitter(f32),
+    Scale(f32, f32),
+    TimeWarp(f32),
+    FeatureDropout(f32)
+}
+
+
+
+impl Augment for Augmentation {
+    fn augment(&self, sample: &[f32]) -> Vec<f32> {
+        let mut r = rng::thread();
+        match self {
+            Self::Jitter(std) => {
+                sample.iter()
+                    .map(|x| x + rng::standard_normal(&mut r) * std)
+                    .collect()
+            },
+            Self::Scale(low, high) => {
+                // gen_range panics on an empty range
+                let factor = if high > low { r.gen_range(*low, *high) } else { *low };
+                sample.iter()
+                    .map(|x| x * factor)
+                    .collect()
+            },
+            Self::TimeWarp(amount) => {
+                let stretch = if *amount > 0.0 { 1.0 + r.gen_range(-*amount, *amount) } else { 1.0 };
+                time_warp(sample, stretch)
+            },
+            Self::FeatureDropout(rate) => {
+                sample.iter()
+                    .map(|x| if r.gen::<f32>() < *rate { 0.0 } else { *x })
+                    .collect()
+            }
+        }
+    }
+}
+
+
+
+/// a list of augmentations is applied one after another 
+impl<A: Augment> Augment for Vec<A> {
+    fn augment(&self, sample: &[f32]) -> Vec<f32> {
+        self.iter()
+            .fold(sample.to_vec(), |result, augment| augment.augment(&result))
+    }
+}
+
+
+
+/// Resample the sequence as if time moved stretch times as fast. Values are linearly interpolated
+/// between the original time steps and the last value is held once the sequence runs out
+fn time_warp(sample: &[f32], stretch: f32) -> Vec<f32> {
+    if sample.len() < 2 {
+        return sample.to_vec();
+    }
+    let last = (sample.len() - 1) as f32;
+    (0..sample.len())
+        .map(|i| {
+            let position = (i as f32 * stretch).min(last);
+            let lower = position.floor() as usize;
+            let upper = (lower + 1).min(sample.len() - 1);
+            let frac = position - lower as f32;
+            sample[lower] * (1.0 - frac) + sample[upper] * frac
+        })
+        .collect()
+}
@@ -0,0 +1,106 @@
+extern crate rand;
+
+use rand::seq::SliceRandom;
+
+use super::dataset::{DataSet, group_by_class};
+use crate::rng;
+
+
+
+/// The order samples are fed through the network during an epoch.
+///
+/// None - the default, keep the order the data was given in. This is what sequence data
+///        fed through recurrent layers needs
+/// Random - shuffle the samples at the start of each epoch
+/// Balanced - every class gets an equal share of each batch. Samples are drawn from each class
+///            in turn, classes that run out of samples are reshuffled and drawn from again
+///            so minority classes are oversampled and majority classes are undersampled while
+///            the epoch keeps the same length as the dataset
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy, Default)]
+pub enum Shuffle {
+    #[default]
+    None,
+    Random,
+    Balanced
+}
+
+
+
+impl Shuffle {
+
+    /// get the order of the sample indexes for one epoch over the given targets
+    pub fn order(&self, targets: &[Vec<f32>]) -> Vec<usize> {
+        let mut r = rng::thread();
+        match self {
+            Self::None => (0..targets.len()).collect(),
+            Self::Random => {
+                let mut order = (0..targets.len()).collect::<Vec<_>>();
+                order.shuffle(&mut r);
+                order
+            },
+            Self::Balanced => {
+                let mut classes = group_by_class(targets)
+                    .into_values()
+                    .map(|mut indexes| {
+                        indexes.shuffle(&mut r);
+                        (indexes, 0)
+                    })
+                    .collect::<Vec<_>>();
+                let num_classes = classes.len();
+                let mut order = Vec::with_capacity(targets.len());
+                while order.len() < targets.len() {
+                    let (indexes, position) = &mut classes[order.len() % num_classes];
+                    if *position == indexes.len() {
+                        indexes.shuffle(&mut r);
+                        *position = 0;
+                    }
+                    order.push(indexes[*position]);
+                    *position += 1;
+                }
+                order
+            }
+        }
+    }
+}
+
+
+
+/// Iterate over a dataset in batches, each batch is returned as its own
+/// (smaller) dataset so it can be handed straight to the trainer
+pub struct BatchIter<'a> {
+    data: &'a DataSet,
+    order: Vec<usize>,
+    batch_size: usize,
+    position: usize
+}
+
+
+
+impl<'a> BatchIter<'a> {
+
+    pub fn new(data: &'a DataSet, batch_size: usize, shuffle: Shuffle) -> Self {
+        assert!(batch_size > 0, "Batch size must be greater than 0");
+        BatchIter {
+            data,
+            order: shuffle.order(&data.targets),
+            batch_size,
+            position: 0
+        }
+    }
+}
+
+
+
+impl<'a> Iterator for BatchIter<'a> {
+    type Item = DataSet;
+
+    fn next(&mut self) -> Option<DataSet> {
+        if self.position >= self.order.len() {
+            return None;
+        }
+        let end = (self.position + self.batch_size).min(self.order.len());
+        let batch = self.data.subset(&self.order[self.position..end]);
+        self.position = end;
+        Some(batch)
+    }
+}
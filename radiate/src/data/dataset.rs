@@ -0,0 +1,119 @@
+extern crate rand;
+
+use std::collections::BTreeMap;
+use rand::seq::SliceRandom;
+
+use super::batch::{BatchIter, Shuffle};
+use crate::rng;
+
+
+
+/// A dataset is a simple pairing of input vectors with their target vectors. It is
+/// what the supervised side of the crate (backprop training, supervised problems)
+/// works off of, and it gives a home to the utilities for splitting and batching data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataSet {
+    pub inputs: Vec<Vec<f32>>,
+    pub targets: Vec<Vec<f32>>
+}
+
+
+
+impl DataSet {
+
+
+    pub fn new(inputs: Vec<Vec<f32>>, targets: Vec<Vec<f32>>) -> Self {
+        assert!(inputs.len() == targets.len(), "Input and target data are different sizes");
+        DataSet { inputs, targets }
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+
+    /// the class label of the sample at the given index, see `class_label`
+    pub fn label(&self, index: usize) -> usize {
+        class_label(&self.targets[index])
+    }
+
+
+    /// group the sample indexes of the dataset by their class label. A BTreeMap is used
+    /// so the classes are always visited in the same order
+    pub fn classes(&self) -> BTreeMap<usize, Vec<usize>> {
+        group_by_class(&self.targets)
+    }
+
+
+    /// create a new dataset out of the samples at the given indexes
+    pub fn subset(&self, indexes: &[usize]) -> DataSet {
+        DataSet {
+            inputs: indexes.iter().map(|i| self.inputs[*i].clone()).collect(),
+            targets: indexes.iter().map(|i| self.targets[*i].clone()).collect()
+        }
+    }
+
+
+    /// Split the dataset into a (train, test) pair where each class is represented in the
+    /// test set with the same proportion it has in the whole dataset. test_ratio is the fraction
+    /// of each class to hold out, the samples are picked randomly and both sets are shuffled
+    pub fn stratified_split(&self, test_ratio: f32) -> (DataSet, DataSet) {
+        assert!((0.0..=1.0).contains(&test_ratio), "Test ratio must be between 0 and 1");
+        let mut r = rng::thread();
+        let (mut train, mut test) = (Vec::with_capacity(self.len()), Vec::new());
+        for (_, mut indexes) in self.classes() {
+            indexes.shuffle(&mut r);
+            let num_test = (indexes.len() as f32 * test_ratio).round() as usize;
+            test.extend_from_slice(&indexes[..num_test]);
+            train.extend_from_slice(&indexes[num_test..]);
+        }
+        train.shuffle(&mut r);
+        test.shuffle(&mut r);
+        (self.subset(&train), self.subset(&test))
+    }
+
+
+    /// iterate over the dataset in batches of batch_size, the order the samples are
+    /// visited in is decided by the shuffle option
+    pub fn batches(&self, batch_size: usize, shuffle: Shuffle) -> BatchIter<'_> {
+        BatchIter::new(self, batch_size, shuffle)
+    }
+
+}
+
+
+
+/// The class a target vector represents. One-hot (or any multi-output) targets are labeled
+/// by the index of their largest value, single output targets are labeled by their rounded
+/// value so a 0.0/1.0 binary target maps to classes 0 and 1.
+pub fn class_label(target: &[f32]) -> usize {
+    if target.len() == 1 {
+        return target[0].round().max(0.0) as usize;
+    }
+    let mut best = 0;
+    for (index, value) in target.iter().enumerate() {
+        if *value > target[best] {
+            best = index;
+        }
+    }
+    best
+}
+
+
+
+/// group indexes of the targets by their class label
+pub fn group_by_class(targets: &[Vec<f32>]) -> BTreeMap<usize, Vec<usize>> {
+    let mut classes = BTreeMap::new();
+    for (index, target) in targets.iter().enumerate() {
+        classes.entry(class_label(target))
+            .or_insert_with(Vec::new)
+            .push(index);
+    }
+    classes
+}
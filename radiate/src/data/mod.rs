@@ -0,0 +1,6 @@
+pub mod dataset;
+pub mod batch;
+pub mod augment;
+pub mod synthetic;
+pub mod scaler;
+
@@ -0,0 +1,95 @@
+/// Scaler normalizes each feature (column) of a set of vectors. Scalers are fit once
+/// to a dataset and then hold on to the statistics they were fit with, so the same 
+/// transform can be applied to new data and undone on network outputs.
+///
+/// Standard - shift each feature to zero mean and unit variance
+/// MinMax - shift each feature into the range (0, 1)
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub enum Scaler {
+    Standard { mean: Vec<f32>, std: Vec<f32> },
+    MinMax { min: Vec<f32>, max: Vec<f32> }
+}
+
+
+
+impl Scaler {
+
+    /// fit a standard scaler to the data
+    pub fn standard(data: &[Vec<f32>]) -> Self {
+        let width = data.first().map(|x| x.len()).unwrap_or(0);
+        let count = data.len().max(1) as f32;
+        let mut mean = vec![0.0; width];
+        for row in data.iter() {
+            for (m, x) in mean.iter_mut().zip(row.iter()) {
+                *m += x / count;
+            }
+        }
+        let mut std = vec![0.0; width];
+        for row in data.iter() {
+            for ((s, m), x) in std.iter_mut().zip(mean.iter()).zip(row.iter()) {
+                *s += (x - m).powi(2) / count;
+            }
+        }
+        let std = std.into_iter()
+            .map(|s| if s > 0.0 { s.sqrt() } else { 1.0 })
+            .collect();
+        Scaler::Standard { mean, std }
+    }
+
+
+    /// fit a min max scaler to the data
+    pub fn min_max(data: &[Vec<f32>]) -> Self {
+        let width = data.first().map(|x| x.len()).unwrap_or(0);
+        let mut min = vec![f32::MAX; width];
+        let mut max = vec![f32::MIN; width];
+        for row in data.iter() {
+            for (i, x) in row.iter().enumerate() {
+                min[i] = min[i].min(*x);
+                max[i] = max[i].max(*x);
+            }
+        }
+        Scaler::MinMax { min, max }
+    }
+
+
+    /// scale a single vector
+    pub fn transform(&self, row: &[f32]) -> Vec<f32> {
+        match self {
+            Self::Standard { mean, std } => {
+                row.iter().zip(mean.iter().zip(std.iter()))
+                    .map(|(x, (m, s))| (x - m) / s)
+                    .collect()
+            },
+            Self::MinMax { min, max } => {
+                row.iter().zip(min.iter().zip(max.iter()))
+                    .map(|(x, (lo, hi))| if hi > lo { (x - lo) / (hi - lo) } else { 0.0 })
+                    .collect()
+            }
+        }
+    }
+
+
+    /// undo the scaling of a single vector
+    pub fn inverse_transform(&self, row: &[f32]) -> Vec<f32> {
+        match self {
+            Self::Standard { mean, std } => {
+                row.iter().zip(mean.iter().zip(std.iter()))
+                    .map(|(x, (m, s))| x * s + m)
+                    .collect()
+            },
+            Self::MinMax { min, max } => {
+                row.iter().zip(min.iter().zip(max.iter()))
+                    .map(|(x, (lo, hi))| x * (hi - lo) + lo)
+                    .collect()
+            }
+        }
+    }
+
+
+    /// scale every vector in the data
+    pub fn transform_all(&self, data: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        data.iter()
+            .map(|row| self.transform(row))
+            .collect()
+    }
+}
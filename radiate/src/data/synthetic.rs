@@ -0,0 +1,105 @@
+//! Generators for common synthetic problems. Everything random is drawn from
+//! a generator seeded by the seed argument so the same seed always gives back the
+//! same dataset, which makes them usable for tests and repeatable benchmarks.
+
+extern crate rand;
+
+use std::f32::consts::PI;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use super::dataset::DataSet;
+use crate::rng;
+
+
+
+/// The classic two spirals classification problem. Two interleaved spirals of points_per_spiral
+/// points each, inputs are (x, y) scaled to (-1, 1) and the target is the spiral the point is on (0 or 1).
+/// noise is the standard deviation of gaussian noise added to each coordinate
+pub fn two_spirals(points_per_spiral: usize, noise: f32, seed: u64) -> DataSet {
+    let mut r = StdRng::seed_from_u64(seed);
+    let (mut inputs, mut targets) = (Vec::new(), Vec::new());
+    for i in 0..points_per_spiral {
+        let t = i as f32 / points_per_spiral as f32;
+        let angle = t * 3.0 * PI;
+        let radius = 0.1 + 0.9 * t;
+        for (class, sign) in [(0.0, 1.0), (1.0, -1.0)].iter() {
+            let x = sign * radius * angle.cos() + rng::standard_normal(&mut r) * noise;
+            let y = sign * radius * angle.sin() + rng::standard_normal(&mut r) * noise;
+            inputs.push(vec![x, y]);
+            targets.push(vec![*class]);
+        }
+    }
+    DataSet::new(inputs, targets)
+}
+
+
+
+/// One step ahead forecasting of a sine wave. The wave has a random phase and a frequency
+/// between 1 and 3 cycles over the series, it is scaled to (0, 1). Each input is the 
+/// previous window values and the target is the next value
+pub fn sine_forecast(length: usize, window: usize, seed: u64) -> DataSet {
+    let mut r = StdRng::seed_from_u64(seed);
+    let phase = r.gen::<f32>() * 2.0 * PI;
+    let cycles = r.gen_range(1.0, 3.0);
+    let series = (0..length + window)
+        .map(|i| {
+            let t = i as f32 / (length + window) as f32;
+            0.5 + 0.5 * (2.0 * PI * cycles * t + phase).sin()
+        })
+        .collect::<Vec<_>>();
+    windowed(&series, window)
+}
+
+
+
+/// One step ahead forecasting of the chaotic Mackey-Glass series
+/// dx/dt = 0.2 * x(t - 17) / (1 + x(t - 17)^10) - 0.1 * x(t)
+/// integrated with a unit step from a randomly perturbed constant history. The first
+/// few hundred steps are thrown away so the series is on the attractor, then it is scaled to (0, 1)
+pub fn mackey_glass(length: usize, window: usize, seed: u64) -> DataSet {
+    const TAU: usize = 17;
+    const TRANSIENT: usize = 300;
+    let mut r = StdRng::seed_from_u64(seed);
+    let mut series = (0..=TAU)
+        .map(|_| 1.2 + r.gen_range(-0.1, 0.1))
+        .collect::<Vec<f32>>();
+    while series.len() < TRANSIENT + length + window + TAU + 1 {
+        let x = series[series.len() - 1];
+        let delayed = series[series.len() - 1 - TAU];
+        series.push(x + 0.2 * delayed / (1.0 + delayed.powi(10)) - 0.1 * x);
+    }
+    let series = &series[series.len() - (length + window)..];
+    let min = series.iter().cloned().fold(f32::MAX, f32::min);
+    let max = series.iter().cloned().fold(f32::MIN, f32::max);
+    let scaled = series.iter()
+        .map(|x| (x - min) / (max - min))
+        .collect::<Vec<_>>();
+    windowed(&scaled, window)
+}
+
+
+
+/// The N bit parity problem, every combination of bits as inputs (0 or 1) with a target
+/// of 1 if an odd number of bits are set. Two bit parity is xor.
+pub fn parity(bits: usize) -> DataSet {
+    let (mut inputs, mut targets) = (Vec::new(), Vec::new());
+    for combination in 0..(1_usize << bits) {
+        inputs.push((0..bits).map(|b| ((combination >> b) & 1) as f32).collect());
+        targets.push(vec![(combination.count_ones() % 2) as f32]);
+    }
+    DataSet::new(inputs, targets)
+}
+
+
+
+/// turn a series into a forecasting dataset where each sample is window values
+/// and the target is the value that comes right after them
+fn windowed(series: &[f32], window: usize) -> DataSet {
+    let (mut inputs, mut targets) = (Vec::new(), Vec::new());
+    for start in 0..series.len() - window {
+        inputs.push(series[start..start + window].to_vec());
+        targets.push(vec![series[start + window]]);
+    }
+    DataSet::new(inputs, targets)
+}
@@ -0,0 +1,125 @@
+//! Scoring members with approximate activations. Most of the time spent evolving is spent scoring,
+//! and most members are nowhere near the line between surviving and not, where a small error in
+//! their score doesn't change anything. Members are scored cheaply first and only the ones close
+//! enough to a cutoff for the error to matter are scored again as they are.
+//!
+//! Scores and weights stay f32 throughout, what is cheaper is how a member computes,
+//! ie: a network's activations swapped for the Fast approximations of them (see Precision).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::learning::Restore;
+
+
+
+/// what switches a member's approximations on and gives its Restore, see ApproximateScoring::new
+type Lower<T> = Arc<dyn Fn(&mut T) -> Restore<T> + Send + Sync>;
+
+
+/// How members are scored cheaply, see Population::approximate_scoring
+///
+/// band - how close a member has to be to a cutoff to be scored again, as a fraction of the
+///     spread of the generation's cheap scores. The cutoffs are the best score and the line
+///     between the survivors and the rest, by rank or within each species (see near_species)
+#[derive(Clone)]
+pub struct ApproximateScoring<T> {
+    pub band: f32,
+    lower: Lower<T>
+}
+
+
+
+impl<T> ApproximateScoring<T> {
+
+    /// lower is given each member before it is scored cheaply and returns what puts it back the
+    /// way it was, ie: switching a network to fast activations and back (see Neat::approximate_scoring)
+    pub fn new<F>(band: f32, lower: F) -> Self
+        where F: Fn(&mut T) -> Restore<T> + Send + Sync + 'static
+    {
+        ApproximateScoring {
+            band,
+            lower: Arc::new(lower)
+        }
+    }
+
+
+    /// Score the member with its approximations switched on
+    pub fn score<F, R>(&self, member: &mut T, score: F) -> R
+        where F: FnOnce(&mut T) -> R
+    {
+        let restore = (self.lower)(member);
+        let result = score(member);
+        restore(member);
+        result
+    }
+
+
+    /// The index of each score within band of a cutoff. survivors is how many members the survival
+    /// criteria keeps by rank, if it keeps them by rank at all
+    pub fn near(&self, scores: &[f32], survivors: Option<usize>) -> Vec<usize> {
+        let mut sorted = scores.to_vec();
+        sorted.sort_by(|a, b| b.total_cmp(a));
+        let mut cutoffs = sorted.first().copied().into_iter().collect::<Vec<_>>();
+        if let Some(kept) = survivors.filter(|kept| *kept > 0 && *kept < sorted.len()) {
+            cutoffs.push((sorted[kept - 1] + sorted[kept]) / 2.0);
+        }
+        let band = self.width(scores);
+        scores.iter()
+            .enumerate()
+            .filter(|(_, score)| cutoffs.iter().any(|cutoff| (*score - cutoff).abs() <= band))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+
+    /// The index of each score within band of a cutoff when the survival criteria keeps the best of
+    /// each species (SurvivalCriteria::Fittest). species is the species each member is going to be
+    /// in, and the line between the best of a species and its runner-up is a cutoff for its members
+    /// on top of the best score overall. A member alone in its species survives whatever its score
+    pub fn near_species(&self, scores: &[f32], species: &[usize]) -> Vec<usize> {
+        let best = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let mut top = HashMap::<usize, (f32, f32)>::new();
+        for (score, species) in scores.iter().zip(species) {
+            let (first, second) = top.entry(*species).or_insert((f32::NEG_INFINITY, f32::NEG_INFINITY));
+            if *score > *first {
+                *second = *first;
+                *first = *score;
+            } else if *score > *second {
+                *second = *score;
+            }
+        }
+        let band = self.width(scores);
+        scores.iter()
+            .zip(species)
+            .enumerate()
+            .filter(|(_, (score, species))| {
+                let (first, second) = top[*species];
+                (*score - best).abs() <= band || (second.is_finite() && (*score - (first + second) / 2.0).abs() <= band)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+
+    /// how far from a cutoff band reaches for these scores
+    fn width(&self, scores: &[f32]) -> f32 {
+        let high = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let low = scores.iter().copied().fold(f32::INFINITY, f32::min);
+        match scores.is_empty() {
+            true => 0.0,
+            false => self.band.max(0.0) * (high - low)
+        }
+    }
+}
+
+
+
+impl<T> fmt::Debug for ApproximateScoring<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApproximateScoring")
+            .field("band", &self.band)
+            .finish()
+    }
+}
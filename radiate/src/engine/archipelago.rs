@@ -0,0 +1,141 @@
+extern crate rayon;
+
+use std::fmt::Debug;
+use rayon::prelude::*;
+use super::{
+    population::Population,
+    genome::Genome,
+    problem::Problem,
+    environment::Envionment
+};
+
+
+
+/// Which islands an island's champion migrates to
+///
+/// Ring - each island sends its champion to the next one, the last sending to the first. Good
+///        genes spread slowly so the islands stay different from each other for longer
+/// FullyConnected - every island sends its champion to every other island
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MigrationTopology {
+    Ring,
+    FullyConnected
+}
+
+
+
+/// An island model - several populations evolving on their own in parallel, each trading its
+/// champion with its neighbors every so many generations. Each island is a whole Population
+/// with its own settings, problem and species, so they explore different parts of the search
+/// space while migration keeps the best of what they find flowing between them. The islands
+/// are trained on separate threads, which scales better on many cores than one large population.
+pub struct Archipelago<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync,
+        E: Envionment + Sized + Send + Sync,
+        P: Problem<T>
+{
+    islands: Vec<Population<T, E, P>>,
+    every: usize,
+    topology: MigrationTopology,
+    generation: usize
+}
+
+
+
+impl<T, E, P> Archipelago<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync + Clone + Debug + PartialEq,
+        E: Envionment + Sized + Send + Sync + Default,
+        P: Problem<T> + Send + Sync
+{
+
+    /// the islands should already be populated, by default champions migrate around a ring every 10 generations
+    pub fn new(islands: Vec<Population<T, E, P>>) -> Self {
+        Archipelago {
+            islands,
+            every: 10,
+            topology: MigrationTopology::Ring,
+            generation: 0
+        }
+    }
+
+    /// set how many generations the islands evolve on their own between migrations
+    pub fn migrate_every(mut self, every: usize) -> Self {
+        self.every = every.max(1);
+        self
+    }
+
+    /// set which islands each champion migrates to
+    pub fn topology(mut self, topology: MigrationTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn islands(&self) -> &[Population<T, E, P>] {
+        &self.islands
+    }
+
+    pub fn islands_mut(&mut self) -> &mut [Population<T, E, P>] {
+        &mut self.islands
+    }
+
+    /// Get the number of generations each island has been through
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Evolve every island in parallel until the next migration then migrate, returning
+    /// the best champion of any island from the last generation before migrating
+    pub fn train(&mut self) -> Option<(f32, T)> {
+        let every = self.every;
+        let champions = self.islands
+            .par_iter_mut()
+            .map(|island| {
+                let mut champion = None;
+                for _ in 0..every {
+                    champion = Some(island.train()?);
+                }
+                champion
+            })
+            .collect::<Option<Vec<_>>>()?;
+        self.generation += every;
+        self.migrate(&champions);
+        champions.into_iter().fold(None, |best: Option<(f32, T)>, champion| match best {
+            Some(best) if best.0 >= champion.0 => Some(best),
+            _ => Some(champion)
+        })
+    }
+
+    /// send each island's champion to its neighbors in the topology
+    fn migrate(&mut self, champions: &[(f32, T)]) {
+        let count = self.islands.len();
+        if count < 2 {
+            return;
+        }
+        for (index, island) in self.islands.iter_mut().enumerate() {
+            let migrants = match self.topology {
+                MigrationTopology::Ring => vec![champions[(index + count - 1) % count].1.clone()],
+                MigrationTopology::FullyConnected => champions.iter()
+                    .enumerate()
+                    .filter(|(source, _)| *source != index)
+                    .map(|(_, (_, champion))| champion.clone())
+                    .collect()
+            };
+            island.immigrate(migrants);
+        }
+    }
+
+    /// Run the islands until the runner returns true, the inputs of which are the best champion
+    /// of the islands, its fitness, and the number of generations each island has been through
+    pub fn run<F>(&mut self, runner: F) -> Result<(f32, T), &'static str>
+        where F: Fn(&T, f32, usize) -> bool
+    {
+        loop {
+            let (fitness, champion) = self.train().ok_or("Error Training")?;
+            if runner(&champion, fitness, self.generation) {
+                return Ok((fitness, champion));
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+extern crate serde_json;
+
+use std::fmt;
+use std::error::Error;
+use std::fmt::Debug;
+use serde::Serialize;
+use serde_json::Value;
+use super::{
+    population::Population,
+    genome::Genome,
+    problem::Problem,
+    environment::Envionment
+};
+
+
+
+/// What two runs disagreed on
+///
+/// Size - the generations had a different number of members
+/// Genome - a member was different before it was evaluated, so whatever created or bred it
+///          wasn't deterministic. path is where in the serialized genome they first differ
+/// Fitness - the same member scored differently, so evaluation wasn't deterministic
+/// Objectives - the same member scored differently on its objectives
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diverged {
+    Size(usize, usize),
+    Genome { path: String },
+    Fitness(f32, f32),
+    Objectives(Option<Vec<f32>>, Option<Vec<f32>>)
+}
+
+
+
+/// The first place two runs of the same seeded population went different ways, see determinism.
+/// member is the index of the member in its generation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub generation: usize,
+    pub member: Option<usize>,
+    pub diverged: Diverged
+}
+
+
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "runs diverged in generation {}", self.generation)?;
+        if let Some(member) = self.member {
+            write!(f, " at member {}", member)?;
+        }
+        match &self.diverged {
+            Diverged::Size(one, two) => write!(f, ": {} members against {}", one, two),
+            Diverged::Genome { path } => write!(f, ": genomes differ at {}", path),
+            Diverged::Fitness(one, two) => write!(f, ": fitness {} against {}", one, two),
+            Diverged::Objectives(one, two) => write!(f, ": objectives {:?} against {:?}", one, two)
+        }
+    }
+}
+
+
+
+impl Error for Divergence {}
+
+
+
+/// Run the same seeded population twice side by side for the given number of generations and
+/// find the first place they differ, to track down nondeterminism from threads or from iterating a
+/// HashMap. Before each generation is evaluated every member is compared, then after it is evaluated
+/// their fitness and objectives, so a divergence says whether breeding or evaluation went wrong.
+/// build is called with 0 and then 1 to create each run, so the runs can differ in something which
+/// shouldn't change the outcome (ie: parallel_eval), and both should be seeded (see Population::seed).
+/// Genomes are compared by their serialized form. Returns the number of generations which matched
+pub fn determinism<T, E, P, F>(build: F, generations: usize) -> Result<usize, Divergence>
+    where
+        T: Genome<T, E> + Send + Sync + Clone + Debug + PartialEq + Serialize,
+        E: Envionment + Sized + Send + Sync + Default,
+        P: Problem<T> + Send + Sync,
+        F: Fn(usize) -> Population<T, E, P>
+{
+    let mut one = build(0);
+    let mut two = build(1);
+    for generation in 0..generations {
+        let diverged = |member, diverged| Divergence { generation, member, diverged };
+        let genomes = |population: &mut Population<T, E, P>| population.members_mut()
+            .iter()
+            .map(|cont| serde_json::to_value(&*cont.member.read().unwrap()).unwrap_or(Value::Null))
+            .collect::<Vec<_>>();
+        let (genomes_one, genomes_two) = (genomes(&mut one), genomes(&mut two));
+        if genomes_one.len() != genomes_two.len() {
+            return Err(diverged(None, Diverged::Size(genomes_one.len(), genomes_two.len())));
+        }
+        for (index, (a, b)) in genomes_one.iter().zip(genomes_two.iter()).enumerate() {
+            if let Some(path) = difference(a, b, String::new()) {
+                return Err(diverged(Some(index), Diverged::Genome { path }));
+            }
+        }
+        one.evaluate();
+        two.evaluate();
+        // a time budget can take members out while evaluating
+        let (size_one, size_two) = (one.members_mut().len(), two.members_mut().len());
+        if size_one != size_two {
+            return Err(diverged(None, Diverged::Size(size_one, size_two)));
+        }
+        for (index, (a, b)) in one.members_mut().iter().zip(two.members_mut().iter()).enumerate() {
+            if a.fitness_score.to_bits() != b.fitness_score.to_bits() {
+                return Err(diverged(Some(index), Diverged::Fitness(a.fitness_score, b.fitness_score)));
+            }
+            if a.objectives != b.objectives {
+                return Err(diverged(Some(index), Diverged::Objectives(a.objectives.clone(), b.objectives.clone())));
+            }
+        }
+        if one.end_generation().is_none() || two.end_generation().is_none() {
+            return Ok(generation + 1);
+        }
+    }
+    Ok(generations)
+}
+
+
+
+/// the path to the first place two values differ, None if they're the same
+fn difference(one: &Value, two: &Value, path: String) -> Option<String> {
+    match (one, two) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a.iter() {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match b.get(key) {
+                    Some(other) => if let Some(found) = difference(value, other, path) {
+                        return Some(found);
+                    },
+                    None => return Some(path)
+                }
+            }
+            b.keys()
+                .find(|key| !a.contains_key(*key))
+                .map(|key| if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) })
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            for (index, (value, other)) in a.iter().zip(b.iter()).enumerate() {
+                if let Some(found) = difference(value, other, format!("{}[{}]", path, index)) {
+                    return Some(found);
+                }
+            }
+            if a.len() != b.len() {
+                return Some(format!("{}[{}]", path, a.len().min(b.len())));
+            }
+            None
+        },
+        _ if one != two => Some(if path.is_empty() { String::from("(root)") } else { path }),
+        _ => None
+    }
+}
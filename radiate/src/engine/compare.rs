@@ -0,0 +1,247 @@
+//! Comparing configurations across repeated runs. A single run of evolution says little about the
+//! configuration it was run with, two runs of the same one can land far apart on luck alone. Give a
+//! RunComparison the histories of several runs of each configuration and it summarizes each by the
+//! median and interquartile range of a metric, and tests each pair with a Mann-Whitney U test which
+//! doesn't assume the results are normally distributed (they rarely are).
+
+use std::fmt;
+use super::history::RunHistory;
+
+
+
+/// What a run is measured by to be compared
+///
+/// BestFitness - the best fitness the run ever reached
+/// FinalBest - the best fitness of the run's last generation
+/// FinalMean - the mean fitness of the run's last generation
+/// AreaUnderCurve - the mean of the best fitness of each generation, which rewards getting there
+///     quickly as well as how far the run got
+/// GenerationsTo(target) - how many generations the run took for its best fitness to reach the
+///     target, lower is better. A run which never reached it counts as one generation past its end
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RunMetric {
+    BestFitness,
+    FinalBest,
+    FinalMean,
+    AreaUnderCurve,
+    GenerationsTo(f32)
+}
+
+
+/// The runs of each configuration by name, measured by the metric
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunComparison {
+    pub metric: RunMetric,
+    groups: Vec<(String, Vec<f32>)>
+}
+
+
+/// The spread of the metric over the runs of one configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupSummary {
+    pub name: String,
+    pub runs: usize,
+    pub median: f32,
+    pub q1: f32,
+    pub q3: f32
+}
+
+
+/// The result of a two sided Mann-Whitney U test between two samples. p is from the normal
+/// approximation of U with a correction for ties, which holds up from around 8 runs a side.
+/// a12 is the Vargha-Delaney effect size, the chance a run of the first sample scores higher than
+/// one of the second (ties count half). 0.5 is no effect, past 0.71 or under 0.29 is usually taken
+/// as a large one
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MannWhitney {
+    pub u: f32,
+    pub z: f32,
+    pub p: f32,
+    pub a12: f32
+}
+
+
+/// The summary of every group and the test between every pair of them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComparisonReport {
+    pub metric: RunMetric,
+    pub groups: Vec<GroupSummary>,
+    pub pairs: Vec<(String, String, MannWhitney)>
+}
+
+
+
+impl RunMetric {
+
+    /// the metric of one run, None for a run without any generations
+    pub fn measure(&self, history: &RunHistory) -> Option<f32> {
+        let last = history.generations.last()?;
+        Some(match self {
+            RunMetric::BestFitness => history.generations.iter().map(|record| record.best_fitness).fold(f32::MIN, f32::max),
+            RunMetric::FinalBest => last.best_fitness,
+            RunMetric::FinalMean => last.mean_fitness,
+            RunMetric::AreaUnderCurve => history.best_fitness().iter().sum::<f32>() / history.len() as f32,
+            RunMetric::GenerationsTo(target) => history.generations.iter()
+                .position(|record| record.best_fitness >= *target)
+                .map_or(history.len() + 1, |index| index + 1) as f32
+        })
+    }
+}
+
+
+
+impl RunComparison {
+
+    pub fn new(metric: RunMetric) -> Self {
+        RunComparison {
+            metric,
+            groups: Vec::new()
+        }
+    }
+
+
+    /// add the runs of a configuration, runs without any generations are left out
+    pub fn group(mut self, name: &str, runs: &[RunHistory]) -> Self {
+        let values = runs.iter().filter_map(|run| self.metric.measure(run)).collect();
+        self.groups.push((name.to_string(), values));
+        self
+    }
+
+
+    /// the metric of each run of the group
+    pub fn values(&self, name: &str) -> Option<&[f32]> {
+        self.groups.iter()
+            .find(|(group, _)| group == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+
+    /// Summarize every group and test every pair of groups in the order they were added,
+    /// groups without any runs are left out
+    pub fn report(&self) -> ComparisonReport {
+        let groups = self.groups.iter()
+            .filter_map(|(name, values)| {
+                let (q1, median, q3) = quartiles(values)?;
+                Some(GroupSummary { name: name.clone(), runs: values.len(), median, q1, q3 })
+            })
+            .collect();
+        let mut pairs = Vec::new();
+        for (index, (first, one)) in self.groups.iter().enumerate() {
+            for (second, two) in self.groups.iter().skip(index + 1) {
+                if let Some(test) = mann_whitney(one, two) {
+                    pairs.push((first.clone(), second.clone(), test));
+                }
+            }
+        }
+        ComparisonReport { metric: self.metric, groups, pairs }
+    }
+}
+
+
+
+impl GroupSummary {
+
+    pub fn iqr(&self) -> f32 {
+        self.q3 - self.q1
+    }
+}
+
+
+
+impl ComparisonReport {
+
+    /// the pairs which differ at the significance level alpha (ie: 0.05)
+    pub fn significant(&self, alpha: f32) -> Vec<&(String, String, MannWhitney)> {
+        self.pairs.iter().filter(|(_, _, test)| test.p < alpha).collect()
+    }
+}
+
+
+
+/// the first quartile, median and third quartile of the values, interpolated between the values
+/// either side where they fall between two. None without any values
+pub fn quartiles(values: &[f32]) -> Option<(f32, f32, f32)> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let quantile = |q: f32| {
+        let position = q * (sorted.len() - 1) as f32;
+        let (below, above) = (position.floor() as usize, position.ceil() as usize);
+        sorted[below] + (sorted[above] - sorted[below]) * (position - below as f32)
+    };
+    Some((quantile(0.25), quantile(0.5), quantile(0.75)))
+}
+
+
+/// Test whether one sample tends to score higher or lower than two, see MannWhitney. U is of the
+/// first sample. None if either sample is empty
+pub fn mann_whitney(one: &[f32], two: &[f32]) -> Option<MannWhitney> {
+    if one.is_empty() || two.is_empty() {
+        return None;
+    }
+    let (n1, n2) = (one.len() as f64, two.len() as f64);
+    let mut pooled = one.iter().map(|value| (*value, true))
+        .chain(two.iter().map(|value| (*value, false)))
+        .collect::<Vec<_>>();
+    pooled.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // tied values share the mean of the ranks they span
+    let (mut rank_sum, mut ties, mut start) = (0.0, 0.0, 0);
+    while start < pooled.len() {
+        let end = start + pooled[start..].iter().take_while(|(value, _)| *value == pooled[start].0).count();
+        let rank = (start + end + 1) as f64 / 2.0;
+        rank_sum += rank * pooled[start..end].iter().filter(|(_, first)| *first).count() as f64;
+        let tied = (end - start) as f64;
+        ties += tied.powi(3) - tied;
+        start = end;
+    }
+
+    let u = rank_sum - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let mean = n1 * n2 / 2.0;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    let (z, p) = if variance > 0.0 {
+        // continuity correction, U only takes whole values (or halves with ties) but z is continuous
+        let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt() * (u - mean).signum();
+        (z, erfc(z.abs() / 2f64.sqrt()).min(1.0))
+    } else {
+        (0.0, 1.0)
+    };
+    Some(MannWhitney {
+        u: u as f32,
+        z: z as f32,
+        p: p as f32,
+        a12: (u / (n1 * n2)) as f32
+    })
+}
+
+
+/// the complementary error function for x >= 0, to within 1.2e-7 (Numerical Recipes' erfcc)
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x);
+    let poly = -x * x - 1.26551223 + t * (1.00002368 + t * (0.37409196 + t * (0.09678418 + t * (-0.18628806
+        + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    t * poly.exp()
+}
+
+
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:?}", self.metric)?;
+        writeln!(f, "{:<16} {:>5} {:>12} {:>12} {:>12} {:>12}", "group", "runs", "median", "q1", "q3", "iqr")?;
+        for group in self.groups.iter() {
+            writeln!(f, "{:<16} {:>5} {:>12.6} {:>12.6} {:>12.6} {:>12.6}", group.name, group.runs, group.median, group.q1, group.q3, group.iqr())?;
+        }
+        if !self.pairs.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "{:<16} {:<16} {:>9} {:>8} {:>10} {:>6}", "group", "against", "U", "z", "p", "a12")?;
+            for (first, second, test) in self.pairs.iter() {
+                writeln!(f, "{:<16} {:<16} {:>9.1} {:>8.3} {:>10.6} {:>6.3}", first, second, test.u, test.z, test.p, test.a12)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+//! Making generations of genomes smaller to store and send. The members of a generation are mostly
+//! copies of each other with a few weights nudged and a few genes added, so written out one after
+//! another nearly all of a checkpoint is the same structure over and over. A DeltaSet writes one base
+//! member in full and only what the rest change from it. With the zstd feature a checkpoint saved to
+//! a path ending in .zst is compressed as well (see Population::save_checkpoint), resume recognizes
+//! a compressed checkpoint whatever its path.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, DeserializeOwned};
+use serde_json::Value;
+
+
+/// the first bytes of every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+
+
+/// How a value differs from the base it was taken against. Objects list only the fields which
+/// changed (None for a field the value doesn't have), arrays only the items which changed by their
+/// index along with the array's length so items can be added or dropped off the end. Anything else
+/// which changed is given in full
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Delta {
+    Same,
+    Value(Value),
+    Fields(BTreeMap<String, Option<Delta>>),
+    Items { len: usize, items: Vec<(usize, Delta)> }
+}
+
+
+/// A set of members written as one base member and the delta of each member from it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaSet {
+    pub base: Value,
+    pub deltas: Vec<Delta>
+}
+
+
+
+impl DeltaSet {
+
+    /// Encode the members against the base, which is usually one of them. Members go through their
+    /// json text on the way to a Value (see to_value) so their floats are written as short as they
+    /// would be without the encoding, and read back through the same f64 as they would be
+    pub fn encode<T: Serialize>(base: &T, members: &[T]) -> Result<Self, serde_json::Error> {
+        let base = to_value(base)?;
+        let deltas = members.iter()
+            .map(|member| Ok(diff(&base, &to_value(member)?)))
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        Ok(DeltaSet { base, deltas })
+    }
+
+
+    /// the members the set was encoded from
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<Vec<T>, serde_json::Error> {
+        self.deltas.iter()
+            .map(|delta| T::deserialize(patch(&self.base, delta)))
+            .collect()
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+}
+
+
+
+/// The value to take deltas of, read back from the json text rather than serde_json::to_value
+/// which would keep an f32 as the f64 it widens to and write out all 17 digits of it
+pub fn to_value<T: Serialize>(value: &T) -> Result<Value, serde_json::Error> {
+    serde_json::from_str(&serde_json::to_string(value)?)
+}
+
+
+/// how value differs from base
+pub fn diff(base: &Value, value: &Value) -> Delta {
+    if base == value {
+        return Delta::Same;
+    }
+    match (base, value) {
+        (Value::Object(base), Value::Object(value)) => {
+            let mut fields = value.iter()
+                .filter(|(key, field)| base.get(*key) != Some(field))
+                .map(|(key, field)| {
+                    let delta = match base.get(key) {
+                        Some(original) => diff(original, field),
+                        None => Delta::Value(field.clone())
+                    };
+                    (key.clone(), Some(delta))
+                })
+                .collect::<BTreeMap<_, _>>();
+            fields.extend(base.keys().filter(|key| !value.contains_key(*key)).map(|key| (key.clone(), None)));
+            Delta::Fields(fields)
+        },
+        (Value::Array(base), Value::Array(value)) => {
+            let items = value.iter()
+                .enumerate()
+                .filter(|(index, item)| base.get(*index) != Some(item))
+                .map(|(index, item)| match base.get(index) {
+                    Some(original) => (index, diff(original, item)),
+                    None => (index, Delta::Value(item.clone()))
+                })
+                .collect();
+            Delta::Items { len: value.len(), items }
+        },
+        _ => Delta::Value(value.clone())
+    }
+}
+
+
+/// the value the delta was taken from, given the base it was taken against
+pub fn patch(base: &Value, delta: &Delta) -> Value {
+    match delta {
+        Delta::Same => base.clone(),
+        Delta::Value(value) => value.clone(),
+        Delta::Fields(fields) => {
+            let mut object = base.as_object().cloned().unwrap_or_default();
+            for (key, field) in fields.iter() {
+                match field {
+                    Some(field) => {
+                        let patched = patch(object.get(key).unwrap_or(&Value::Null), field);
+                        object.insert(key.clone(), patched);
+                    },
+                    None => { object.remove(key); }
+                }
+            }
+            Value::Object(object)
+        },
+        Delta::Items { len, items } => {
+            let mut array = base.as_array().cloned().unwrap_or_default();
+            array.resize(*len, Value::Null);
+            for (index, item) in items.iter().filter(|(index, _)| index < len) {
+                array[*index] = patch(&array[*index], item);
+            }
+            Value::Array(array)
+        }
+    }
+}
+
+
+/// whether the bytes are a zstd frame
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+
+/// compress the bytes into a zstd frame, level 0 is zstd's default
+#[cfg(feature = "zstd")]
+pub fn compress(bytes: &[u8], level: i32) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(zstd::encode_all(bytes, level)?)
+}
+
+
+#[cfg(feature = "zstd")]
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(zstd::decode_all(bytes)?)
+}
+
+
+/// without the zstd feature there is nothing to compress with, so asking for it is an error
+#[cfg(not(feature = "zstd"))]
+pub fn compress(_bytes: &[u8], _level: i32) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("compressing needs radiate's zstd feature".into())
+}
+
+
+#[cfg(not(feature = "zstd"))]
+pub fn decompress(_bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("decompressing needs radiate's zstd feature".into())
+}
+
+
+
+
+/// Serialize the members of a checkpoint as a DeltaSet against the first of them, reading
+/// either that or the plain list of members checkpoints were written as before
+pub(crate) mod members {
+
+    use super::*;
+
+    pub fn serialize<T: Serialize, S: Serializer>(members: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+        match members.first() {
+            Some(base) => DeltaSet::encode(base, members)
+                .map_err(serde::ser::Error::custom)?
+                .serialize(serializer),
+            None => members.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<T>, D::Error> {
+        let members = match Value::deserialize(deserializer)? {
+            Value::Array(members) => members,
+            value => {
+                let set = DeltaSet::deserialize(value).map_err(de::Error::custom)?;
+                set.deltas.iter().map(|delta| patch(&set.base, delta)).collect()
+            }
+        };
+        members.into_iter()
+            .map(|member| T::deserialize(member).map_err(de::Error::custom))
+            .collect()
+    }
+}
@@ -0,0 +1,145 @@
+//! Neutral drift around the best fitness of a run. Once a run reaches a plateau every member at the
+//! top scores the same, so selection can't tell a network which is one mutation from the next step
+//! up from the one it started with. The archive keeps structurally different members which score
+//! the same as the best, and carries them back into the population in turn so the search wanders
+//! along the plateau instead of breeding from the same few members until it stagnates.
+
+use std::sync::{Arc, RwLock};
+use super::generation::Container;
+use super::genome::Genome;
+
+
+
+/// Up to capacity members which all score within tolerance of the best fitness seen so far (the level).
+/// Members closer than distance to one already archived (see Genome::within_distance) are taken as
+/// the same variant and left out. When the run climbs off the plateau the archive starts over at the
+/// new level, when it falls below it the archive is kept. Once full the oldest variant makes room.
+///
+/// carry - how many variants are put into each generation, in the order they were archived and
+/// starting again from the first once each has had a turn. They go in after the elites and are
+/// counted as elites themselves, see Elitism
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeutralDrift<T> {
+    pub capacity: usize,
+    pub tolerance: f32,
+    pub distance: f32,
+    pub carry: usize,
+    level: Option<f32>,
+    variants: Vec<T>,
+    cursor: usize
+}
+
+
+
+impl<T> NeutralDrift<T> {
+
+    pub fn new(capacity: usize) -> Self {
+        NeutralDrift {
+            capacity,
+            tolerance: 1e-6,
+            distance: 0.1,
+            carry: 1,
+            level: None,
+            variants: Vec::new(),
+            cursor: 0
+        }
+    }
+
+
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+
+    pub fn distance(mut self, distance: f32) -> Self {
+        self.distance = distance;
+        self
+    }
+
+
+    pub fn carry(mut self, count: usize) -> Self {
+        self.carry = count;
+        self
+    }
+
+
+    /// the fitness the variants score, None before anything has been recorded
+    pub fn level(&self) -> Option<f32> {
+        self.level
+    }
+
+
+    /// the variants oldest first
+    pub fn variants(&self) -> &[T] {
+        &self.variants
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+
+    /// Archive the scored members of a generation which are on the level and aren't already
+    /// a variant, starting over at a new level if one of them beat it. Returns how many got in
+    pub fn record<E>(&mut self, members: &[Container<T, E>], env: &Arc<RwLock<E>>) -> usize
+        where
+            T: Genome<T, E> + Clone + Send + Sync,
+            E: Send + Sync
+    {
+        let best = members.iter()
+            .map(|cont| cont.fitness_score)
+            .filter(|fitness| !fitness.is_nan())
+            .fold(None, |best: Option<f32>, fitness| Some(best.map_or(fitness, |best| best.max(fitness))));
+        let level = match (self.level, best) {
+            (Some(level), Some(best)) if best <= level + self.tolerance => level,
+            (_, Some(best)) => {
+                self.variants.clear();
+                self.cursor = 0;
+                best
+            },
+            (_, None) => return 0
+        };
+        self.level = Some(level);
+        let (mut archived, tolerance) = (0, self.tolerance);
+        for cont in members.iter().filter(|cont| (cont.fitness_score - level).abs() <= tolerance) {
+            if self.capacity == 0 {
+                break;
+            }
+            let member = cont.member.read().unwrap();
+            if self.variants.iter().any(|variant| T::within_distance(&member, variant, Arc::clone(env), self.distance)) {
+                continue;
+            }
+            if self.variants.len() == self.capacity {
+                self.variants.remove(0);
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            self.variants.push((*member).clone());
+            archived += 1;
+        }
+        archived
+    }
+
+
+    /// the next carry variants (no more than there are) to put into a generation, each variant
+    /// is only given again once every other one has been
+    pub fn next_variants(&mut self) -> Vec<T>
+        where T: Clone
+    {
+        if self.variants.is_empty() {
+            return Vec::new();
+        }
+        let count = self.carry.min(self.variants.len());
+        let start = self.cursor % self.variants.len();
+        self.cursor = (start + count) % self.variants.len();
+        (start..start + count)
+            .map(|index| self.variants[index % self.variants.len()].clone())
+            .collect()
+    }
+}
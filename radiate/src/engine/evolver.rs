@@ -0,0 +1,206 @@
+extern crate arc_swap;
+
+use std::fmt::Debug;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use arc_swap::ArcSwapOption;
+use super::{
+    population::Population,
+    genome::Genome,
+    problem::Problem,
+    environment::Envionment
+};
+
+
+
+/// The member an Evolver has swapped in for whatever is using it, along with its fitness
+/// and the generation it came from
+#[derive(Debug, Clone)]
+pub struct Champion<T> {
+    pub generation: usize,
+    pub fitness: f32,
+    pub member: T
+}
+
+
+
+/// A cheap to clone reader of the current champion of an Evolver. Loading it never locks or
+/// waits on the evolution thread, so it can be read every frame of a game loop. A loaded champion
+/// stays valid for as long as it is held even after a newer one has been swapped in
+pub struct ChampionHandle<T> {
+    current: Arc<ArcSwapOption<Champion<T>>>
+}
+
+
+
+impl<T> ChampionHandle<T> {
+
+    /// the current champion, None until the first generation has been evaluated
+    pub fn load(&self) -> Option<Arc<Champion<T>>> {
+        self.current.load_full()
+    }
+
+    /// the generation of the current champion
+    pub fn generation(&self) -> Option<usize> {
+        self.current.load().as_ref().map(|champion| champion.generation)
+    }
+}
+
+
+
+impl<T> Clone for ChampionHandle<T> {
+    fn clone(&self) -> Self {
+        ChampionHandle {
+            current: Arc::clone(&self.current)
+        }
+    }
+}
+
+
+
+/// Runs evolution on a background thread so something else (ie: a game adapting to its players)
+/// keeps going while the population evolves against the problem, which is whatever simulates the
+/// game. After each generation the top member is swapped in as the champion if it is at least as
+/// fit as the one before it, or always if always_swap is set for a problem that changes over time.
+/// The game thread reads the champion through a ChampionHandle without ever waiting on evolution
+pub struct Evolver<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync,
+        E: Envionment + Sized + Send + Sync,
+        P: Problem<T>
+{
+    population: Population<T, E, P>,
+    always_swap: bool,
+    pause: Option<Duration>
+}
+
+
+
+impl<T, E, P> Evolver<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync + Clone + Debug + PartialEq + 'static,
+        E: Envionment + Sized + Send + Sync + Default + 'static,
+        P: Problem<T> + Send + Sync + 'static
+{
+
+    /// the population should already be populated and have its problem imposed
+    pub fn new(population: Population<T, E, P>) -> Self {
+        Evolver {
+            population,
+            always_swap: false,
+            pause: None
+        }
+    }
+
+    /// swap in the top member of every generation even if it is less fit than the current champion
+    pub fn always_swap(mut self, opt: bool) -> Self {
+        self.always_swap = opt;
+        self
+    }
+
+    /// sleep between generations so evolution doesn't take cores the game needs
+    pub fn pause(mut self, pause: Duration) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// start evolving on a new thread, which runs until the handle is stopped or dropped
+    /// or a generation can't be created
+    pub fn spawn(self) -> EvolverHandle<T, E, P> {
+        let current = Arc::new(ArcSwapOption::<Champion<T>>::empty());
+        let stop = Arc::new(AtomicBool::new(false));
+        let champion = ChampionHandle { current: Arc::clone(&current) };
+        let running = Arc::clone(&stop);
+        let Evolver { mut population, always_swap, pause } = self;
+        let thread = thread::spawn(move || {
+            while !running.load(Ordering::Relaxed) && !population.is_stopped() {
+                let stats = match population.step() {
+                    Some(stats) => stats,
+                    None => break
+                };
+                let fitter = current.load().as_ref().is_none_or(|champion| stats.fitness >= champion.fitness);
+                if always_swap || fitter {
+                    current.store(Some(Arc::new(Champion {
+                        generation: stats.generation,
+                        fitness: stats.fitness,
+                        member: stats.top
+                    })));
+                }
+                if let Some(pause) = pause {
+                    thread::sleep(pause);
+                }
+            }
+            population
+        });
+        EvolverHandle {
+            champion,
+            stop,
+            thread: Some(thread)
+        }
+    }
+}
+
+
+
+/// The running evolution of an Evolver. Stopping it gives back the population so it can be
+/// checkpointed or evolved some more, dropping it stops the thread and throws the population away
+pub struct EvolverHandle<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync,
+        E: Envionment + Sized + Send + Sync,
+        P: Problem<T>
+{
+    champion: ChampionHandle<T>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Population<T, E, P>>>
+}
+
+
+
+impl<T, E, P> EvolverHandle<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync,
+        E: Envionment + Sized + Send + Sync,
+        P: Problem<T>
+{
+
+    /// a reader of the champion to hand to whatever is using it
+    pub fn champion(&self) -> ChampionHandle<T> {
+        self.champion.clone()
+    }
+
+    /// the current champion, see ChampionHandle::load
+    pub fn load(&self) -> Option<Arc<Champion<T>>> {
+        self.champion.load()
+    }
+
+    /// whether the evolution thread is still going
+    pub fn is_running(&self) -> bool {
+        self.thread.as_ref().is_some_and(|thread| !thread.is_finished())
+    }
+
+    /// stop after the generation being evolved and wait for it, returning the population
+    /// or None if the evolution thread panicked
+    pub fn stop(mut self) -> Option<Population<T, E, P>> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.take()?.join().ok()
+    }
+}
+
+
+
+impl<T, E, P> Drop for EvolverHandle<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync,
+        E: Envionment + Sized + Send + Sync,
+        P: Problem<T>
+{
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
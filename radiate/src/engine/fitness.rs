@@ -0,0 +1,102 @@
+use std::fmt;
+use std::sync::Arc;
+use super::problem::Problem;
+
+
+
+/// scores one part of a member's fitness
+pub type Term<T> = Arc<dyn Fn(&mut T) -> f32 + Send + Sync>;
+
+
+
+/// A fitness built out of named terms, each scaled by a weight and added together. Terms are
+/// small reusable scores (see models::terms for the ones for networks) composed into a Problem -
+///
+/// let fitness = terms::accuracy(data).minus(terms::complexity(0.01)).plus(Fitness::term("bonus", |net| ...));
+///
+/// instead of one closure doing everything, and breakdown shows what each term contributed.
+#[derive(Clone)]
+pub struct Fitness<T> {
+    terms: Vec<(String, f32, Term<T>)>
+}
+
+
+
+impl<T> Fitness<T> {
+
+    /// a fitness of a single term
+    pub fn term<F>(name: &str, score: F) -> Self
+        where F: Fn(&mut T) -> f32 + Send + Sync + 'static
+    {
+        Fitness { terms: vec![(name.to_string(), 1.0, Arc::new(score))] }
+    }
+
+
+    /// the same score for every member
+    pub fn constant(value: f32) -> Self
+        where T: 'static
+    {
+        Fitness::term("constant", move |_| value)
+    }
+
+
+    /// add the terms of other to this fitness
+    pub fn plus(mut self, other: Fitness<T>) -> Self {
+        self.terms.extend(other.terms);
+        self
+    }
+
+
+    /// subtract the terms of other from this fitness
+    pub fn minus(self, other: Fitness<T>) -> Self {
+        self.plus(other.scale(-1.0))
+    }
+
+
+    /// multiply every term by weight
+    pub fn scale(mut self, weight: f32) -> Self {
+        for term in self.terms.iter_mut() {
+            term.1 *= weight;
+        }
+        self
+    }
+
+
+    /// the names of the terms in the order they are scored
+    pub fn names(&self) -> Vec<&str> {
+        self.terms.iter().map(|(name, _, _)| name.as_str()).collect()
+    }
+
+
+    /// what each term added to the member's fitness, already scaled by its weight
+    pub fn breakdown(&self, member: &mut T) -> Vec<(String, f32)> {
+        self.terms.iter()
+            .map(|(name, weight, score)| (name.clone(), weight * score(member)))
+            .collect()
+    }
+}
+
+
+
+impl<T> Problem<T> for Fitness<T> {
+
+    /// no terms, every member scores 0
+    fn empty() -> Self {
+        Fitness { terms: Vec::new() }
+    }
+
+    fn solve(&self, member: &mut T) -> f32 {
+        self.terms.iter().map(|(_, weight, score)| weight * score(member)).sum()
+    }
+}
+
+
+
+impl<T> fmt::Debug for Fitness<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let terms = self.terms.iter()
+            .map(|(name, weight, _)| format!("{} * {}", weight, name))
+            .collect::<Vec<_>>();
+        write!(f, "Fitness({})", terms.join(" + "))
+    }
+}
@@ -1,6 +1,9 @@
 extern crate rand;
 
+use std::ops::Add;
+use std::iter::Sum;
 use std::sync::{Arc, Weak, RwLock};
+use std::time::{Duration, Instant};
 use rayon::prelude::*;
 use super::niche::{Niche, NicheMember};
 use super::{
@@ -8,8 +11,14 @@ use super::{
     problem::Problem,
     environment::Envionment,
     population::Config,
-    survival::{SurvivalCriteria, ParentalCriteria}
+    pareto,
+    survival::{SurvivalCriteria, ParentalCriteria, Elitism},
+    selection::{Selection, RouletteWheel, BreederPool},
+    stagnation::SpeciesBoost,
+    learning::{Learning, Inheritance},
+    approximate::ApproximateScoring
 };
+use crate::rng;
 
 
 
@@ -36,7 +45,8 @@ pub type FamilyWeak<T, E> = Weak<RwLock<Niche<T, E>>>;
 
 /// A container is a simple container to encapsulate a member (Type T)
 /// its fitness score for the current generation, and a weak reference 
-/// counting cell to the species it belongs to
+/// counting cell to the species it belongs to. Members of a multi-objective problem
+/// also keep their score on each objective, their fitness comes from their pareto rank
 #[derive(Debug)]
 pub struct Container<T, E>
     where 
@@ -45,6 +55,7 @@ pub struct Container<T, E>
 {
     pub member: Member<T>,
     pub fitness_score: f32,
+    pub objectives: Option<Vec<f32>>,
     pub species: Option<FamilyWeak<T, E>>
 }
 
@@ -71,7 +82,9 @@ impl<T, E> Container<T, E>
 
 /// A generation is meant to facilitate the speciation, crossover, and 
 /// reproduction of species and their types over the course of a single
-/// generation
+/// generation. elites is how many members at the front of the generation
+/// were carried over unchanged by elitism, mutation_boosts the species whose
+/// offspring are bred with boosted mutation rates (see MutationBoost)
 #[derive(Debug)]
 pub struct Generation<T, E> 
     where
@@ -81,7 +94,98 @@ pub struct Generation<T, E>
     pub members: Vec<Container<T, E>>,
     pub species: Vec<Family<T, E>>,
     pub survival_criteria: SurvivalCriteria,
-    pub parental_criteria: ParentalCriteria
+    pub parental_criteria: ParentalCriteria,
+    pub selection: Box<dyn Selection>,
+    pub breeders: BreederPool,
+    pub elitism: Elitism,
+    pub elites: usize,
+    pub mutation_boosts: Vec<SpeciesBoost>,
+    pub crossover_stats: CrossoverStats
+}
+
+
+
+/// How the genes of two parents lined up when they were mated. Matched genes are in both parents,
+/// the rest are in only one of them - disjoint if the other parent has genes from after it and
+/// excess if it comes after everything the parents share. Both parents' unmatched genes are
+/// counted, so the alignment is the same whichever of the two is the fitter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Alignment {
+    pub matched: usize,
+    pub disjoint: usize,
+    pub excess: usize
+}
+
+
+
+impl Alignment {
+
+    /// the fraction of both parents' genes which are matched, 1.0 for parents with no genes
+    pub fn matched_ratio(&self) -> f32 {
+        let total = 2 * self.matched + self.disjoint + self.excess;
+        if total == 0 {
+            return 1.0;
+        }
+        (2 * self.matched) as f32 / total as f32
+    }
+}
+
+
+
+impl Add for Alignment {
+    type Output = Alignment;
+
+    fn add(self, other: Alignment) -> Alignment {
+        Alignment {
+            matched: self.matched + other.matched,
+            disjoint: self.disjoint + other.disjoint,
+            excess: self.excess + other.excess
+        }
+    }
+}
+
+
+
+impl Sum for Alignment {
+    fn sum<I: Iterator<Item = Alignment>>(iter: I) -> Alignment {
+        iter.fold(Alignment::default(), Add::add)
+    }
+}
+
+
+
+/// The alignment of every mating which bred a generation, in the order the children were bred.
+/// Crossover can only mix matched genes, so when the distance threshold lets parents with few
+/// matched genes into the same species their children mostly just lose the other parent's
+/// structure. Empty if the genome doesn't implement alignment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrossoverStats {
+    pub matings: Vec<Alignment>
+}
+
+
+
+impl CrossoverStats {
+
+    /// the alignments of every mating added together
+    pub fn total(&self) -> Alignment {
+        self.matings.iter().copied().sum()
+    }
+
+
+    /// the mean matched ratio of the matings, None if there weren't any
+    pub fn mean_matched_ratio(&self) -> Option<f32> {
+        if self.matings.is_empty() {
+            return None;
+        }
+        Some(self.matings.iter().map(Alignment::matched_ratio).sum::<f32>() / self.matings.len() as f32)
+    }
+
+
+    /// the number of matings whose parents matched on less than min_ratio of their genes
+    pub fn poorly_aligned(&self, min_ratio: f32) -> usize {
+        self.matings.iter().filter(|alignment| alignment.matched_ratio() < min_ratio).count()
+    }
 }
 
 
@@ -101,13 +205,20 @@ impl<T, E> Generation<T, E>
             members: Vec::new(),
             species: Vec::new(),
             survival_criteria: SurvivalCriteria::Fittest,
-            parental_criteria: ParentalCriteria::BiasedRandom
+            parental_criteria: ParentalCriteria::BiasedRandom,
+            selection: Box::new(RouletteWheel),
+            breeders: BreederPool::default(),
+            elitism: Elitism::default(),
+            elites: 0,
+            mutation_boosts: Vec::new(),
+            crossover_stats: CrossoverStats::default()
         }
     }
 
     /// pass down the previous generation's members and species to a new generation
     #[inline]
     pub fn pass_down(&self, new_members: Vec<Member<T>>) -> Option<Self> {
+        let keys = rng::keys(self.species.len());
         Some(Generation {
             members: new_members
                 .into_par_iter()
@@ -115,19 +226,27 @@ impl<T, E> Generation<T, E>
                     Container {
                         member: Arc::clone(&x),
                         fitness_score: 0.0,
+                        objectives: None,
                         species: None
                     }
                 })
                 .collect(),
             species: self.species
                 .par_iter()
-                .map(|spec| {
-                    spec.write().unwrap().reset();
+                .zip(keys)
+                .map(|(spec, key)| {
+                    rng::scoped(key, || spec.write().unwrap().reset());
                     Arc::clone(spec)
                 })
                 .collect(),
             survival_criteria: self.survival_criteria.clone(),
-            parental_criteria: self.parental_criteria.clone()
+            parental_criteria: self.parental_criteria.clone(),
+            selection: self.selection.clone(),
+            breeders: self.breeders,
+            elitism: self.elitism,
+            elites: 0,
+            mutation_boosts: Vec::new(),
+            crossover_stats: CrossoverStats::default()
         })
     }
 
@@ -146,81 +265,308 @@ impl<T, E> Generation<T, E>
         self.members.get(idx)
     }
 
-    /// The optimization function
+    /// The optimization function, members of a multi-objective problem are given
+    /// their pareto fitness once the whole generation has been scored
     #[inline]
     pub fn optimize<P>(&mut self, prob: Arc<RwLock<P>>)
         where P: Problem<T> + Send + Sync
     {
-        // concurrently iterate the members and optimize them
-        self.members
-            .par_iter_mut()
-            .for_each_with(prob, |problem, cont| {
-                (*cont).fitness_score = problem.read().unwrap().solve(&mut *cont.member.write().unwrap());
+        self.optimize_with(prob, true)
+    }
+
+    /// optimize, scoring the members concurrently across rayon's thread pool if parallel
+    /// or one after the other on this thread if not
+    pub fn optimize_with<P>(&mut self, prob: Arc<RwLock<P>>, parallel: bool)
+        where P: Problem<T> + Send + Sync
+    {
+        self.optimize_within(prob, parallel, None);
+    }
+
+    /// optimize, but stop starting on new members once the budget has run out. The members which
+    /// weren't scored in time are taken out of the generation and returned, the first member is
+    /// always scored so there is something to select from no matter how small the budget is
+    pub fn optimize_within<P>(&mut self, prob: Arc<RwLock<P>>, parallel: bool, budget: Option<Duration>) -> Vec<Container<T, E>>
+        where P: Problem<T> + Send + Sync
+    {
+        self.optimize_learning(prob, parallel, budget, None)
+    }
+
+    /// optimize_within, with each member learning before it is scored if learning is given (see Learning)
+    pub fn optimize_learning<P>(&mut self, prob: Arc<RwLock<P>>, parallel: bool, budget: Option<Duration>, learning: Option<&Learning<T>>) -> Vec<Container<T, E>>
+        where P: Problem<T> + Send + Sync
+    {
+        self.optimize_approximate(prob, parallel, budget, learning, None)
+    }
+
+    /// optimize_speciated without knowing how the members will be speciated, so with Fittest survival
+    /// approximate only takes the best score as a cutoff
+    pub fn optimize_approximate<P>(&mut self, prob: Arc<RwLock<P>>, parallel: bool, budget: Option<Duration>, learning: Option<&Learning<T>>, approximate: Option<&ApproximateScoring<T>>) -> Vec<Container<T, E>>
+        where P: Problem<T> + Send + Sync
+    {
+        self.optimize_speciated(prob, parallel, budget, learning, approximate, None)
+    }
+
+    /// optimize_learning, with every member scored cheaply first if approximate is given and the ones near
+    /// a cutoff scored again as they are (see ApproximateScoring). A Lamarckian member already carries
+    /// what it learned so it doesn't learn again to be scored again. Members of a multi-objective
+    /// problem are all scored again, there is no single score for them to be near a cutoff by.
+    /// speciation is the distance and environment the generation is going to be speciated with,
+    /// which with Fittest survival gives the cutoffs within each species (see ApproximateScoring::near_species)
+    pub fn optimize_speciated<P>(&mut self, prob: Arc<RwLock<P>>, parallel: bool, budget: Option<Duration>, learning: Option<&Learning<T>>, approximate: Option<&ApproximateScoring<T>>, speciation: Option<(f32, Arc<RwLock<E>>)>) -> Vec<Container<T, E>>
+        where P: Problem<T> + Send + Sync
+    {
+        let deadline = budget.map(|budget| Instant::now() + budget);
+        let (references, preferences) = {
+            let problem = prob.read().unwrap();
+            (problem.reference_points(), problem.preferences())
+        };
+        // each member is scored under its own key so the order they're scored in doesn't matter
+        let score_member = |problem: &Arc<RwLock<P>>, cont: &mut Container<T, E>, key: u64, approximate: Option<&ApproximateScoring<T>>, learning: Option<&Learning<T>>| {
+            rng::scoped(key, || {
+                let problem = problem.read().unwrap();
+                let mut member = cont.member.write().unwrap();
+                let solve = |member: &mut T| match problem.objectives(member) {
+                    Some(objectives) => (Some(objectives), None),
+                    None => (None, Some(problem.solve(member)))
+                };
+                let score = |member: &mut T| match approximate {
+                    Some(approximate) => approximate.score(member, solve),
+                    None => solve(member)
+                };
+                let (objectives, fitness) = match learning {
+                    Some(learning) => learning.score(&mut member, score),
+                    None => score(&mut member)
+                };
+                cont.objectives = objectives;
+                if let Some(fitness) = fitness {
+                    cont.fitness_score = fitness;
+                }
             });
+        };
+        let evaluate = |problem: &Arc<RwLock<P>>, (index, (cont, key)): (usize, (&mut Container<T, E>, u64))| {
+            if index > 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            score_member(problem, cont, key, approximate, learning);
+            true
+        };
+        let keys = rng::keys(self.members.len());
+        let scored = phase!("evaluate", {
+            if parallel {
+                self.members
+                    .par_iter_mut()
+                    .zip(keys.clone())
+                    .enumerate()
+                    .map_with(Arc::clone(&prob), |problem, pair| evaluate(problem, pair))
+                    .collect::<Vec<_>>()
+            } else {
+                self.members
+                    .iter_mut()
+                    .zip(keys.clone())
+                    .enumerate()
+                    .map(|pair| evaluate(&prob, pair))
+                    .collect::<Vec<_>>()
+            }
+        });
+        let (members, unscored) = self.members
+            .drain(..)
+            .zip(scored.iter().zip(keys))
+            .partition::<Vec<_>, _>(|(_, (scored, _))| **scored);
+        let keys = members.iter().map(|(_, (_, key))| *key).collect::<Vec<_>>();
+        self.members = members.into_iter().map(|(cont, _)| cont).collect();
+        if let Some(approximate) = approximate {
+            phase!("rescore", {
+                let near = match self.members.iter().any(|cont| cont.objectives.is_some()) {
+                    true => (0..self.members.len()).collect::<Vec<_>>(),
+                    false => {
+                        let scores = self.members.iter().map(|cont| cont.fitness_score).collect::<Vec<_>>();
+                        match (self.survival_criteria.survivors(scores.len()), speciation) {
+                            (None, Some((distance, settings))) => approximate.near_species(&scores, &self.place(distance, &settings)),
+                            (survivors, _) => approximate.near(&scores, survivors)
+                        }
+                    }
+                };
+                let learning = learning.filter(|learning| learning.inheritance == Inheritance::Baldwinian);
+                let mut chosen = self.members.iter_mut()
+                    .zip(keys)
+                    .enumerate()
+                    .filter(|(index, _)| near.binary_search(index).is_ok())
+                    .map(|(_, pair)| pair)
+                    .collect::<Vec<_>>();
+                if parallel {
+                    chosen.par_iter_mut().for_each_with(Arc::clone(&prob), |problem, (cont, key)| score_member(problem, cont, *key, None, learning));
+                } else {
+                    chosen.iter_mut().for_each(|(cont, key)| score_member(&prob, cont, *key, None, learning));
+                }
+            })
+        }
+        if self.members.iter().all(|cont| cont.objectives.is_some()) && !self.members.is_empty() {
+            phase!("pareto", {
+                let scores = self.members.iter().filter_map(|cont| cont.objectives.clone()).collect::<Vec<_>>();
+                let fitness = match (preferences, references) {
+                    (Some(preferences), references) => pareto::preferred_fitness(&scores, &preferences, references.as_deref()),
+                    (None, Some(references)) => pareto::reference_fitness(&scores, &references),
+                    (None, None) => pareto::pareto_fitness(&scores)
+                };
+                for (cont, fitness) in self.members.iter_mut().zip(fitness) {
+                    cont.fitness_score = fitness;
+                }
+            })
+        }
+        unscored.into_iter().map(|(cont, _)| cont).collect()
+    }
+
+    /// the members of the first pareto front of a multi-objective problem along with their score
+    /// on each objective, empty if the generation hasn't been scored on multiple objectives. With
+    /// constraints the front is only taken from the feasible members (or the members closest to
+    /// feasible if there are none), see pareto::Preferences
+    pub fn pareto_front(&self, preferences: Option<&pareto::Preferences>) -> Vec<(Vec<f32>, T)> {
+        let violation = |scores: &Vec<f32>| preferences.map_or(0.0, |preferences| preferences.violation(scores));
+        let least = self.members.iter()
+            .filter_map(|cont| cont.objectives.as_ref())
+            .fold(f32::INFINITY, |least, scores| least.min(violation(scores)));
+        let scored = self.members.iter()
+            .filter_map(|cont| Some((cont.objectives.clone()?, cont)))
+            .filter(|(scores, _)| violation(scores) <= least)
+            .collect::<Vec<_>>();
+        let scores = scored.iter().map(|(scores, _)| scores.clone()).collect::<Vec<_>>();
+        match pareto::non_dominated_sort(&scores).first() {
+            Some(front) => front.iter()
+                .map(|i| (scored[*i].0.clone(), (*scored[*i].1.member.read().unwrap()).clone()))
+                .collect(),
+            None => Vec::new()
+        }
     }
 
     /// Speciation is the process of going through the members in the generation
     /// and assigning them species in which they belong to determined by a specific 
     /// distance between the member and the species mascot.
+    /// 
+    /// The existing species don't change while the members are being placed, so every member
+    /// is compared against them in parallel first. Only the members that don't fit any of them
+    /// are then walked serially to found (or join) the new species of this generation, which
+    /// gives the same species as comparing every member one after the other.
     #[inline]
     pub fn speciate(&mut self, distance: f32, settings: Arc<RwLock<E>>) {
-        // Loop over the members mutably to find a species which this member belongs to
-        for cont in self.members.iter_mut() {
-            // see if this member belongs to a given species 
-            let mem_spec = self.species
-                .iter()
-                .find(|s| {
-                    <T as Genome<T, E>>::distance(&*cont.member.read().unwrap(), &*s.read().unwrap().mascot.read().unwrap(), Arc::clone(&settings)) < distance
-                });
-            // if the member does belong to an existing species, add the two to each other 
-            // otherwise create a new species and add that to the species and the member 
-            match mem_spec {
-                Some(spec) => {
-                    let mut lock_spec = spec.write().unwrap();
-                    lock_spec.members.push(NicheMember(cont.fitness_score, Arc::downgrade(&cont.member)));
-                    cont.species = Some(Arc::downgrade(spec));
-                },
-                None => {
-                    let new_family = Arc::new(RwLock::new(Niche::new(&cont.member, cont.fitness_score)));
-                    cont.species = Some(Arc::downgrade(&new_family));
-                    self.species.push(new_family);
+        phase!("speciate", {
+            let places = self.place(distance, &settings);
+            // Loop over the members mutably to add each to the species it was placed in
+            for (cont, index) in self.members.iter_mut().zip(places) {
+                // if the member does belong to an existing species, add the two to each other 
+                // otherwise create a new species and add that to the species and the member 
+                match self.species.get(index) {
+                    Some(spec) => {
+                        let mut lock_spec = spec.write().unwrap();
+                        lock_spec.members.push(NicheMember(cont.fitness_score, Arc::downgrade(&cont.member)));
+                        cont.species = Some(Arc::downgrade(spec));
+                    },
+                    None => {
+                        let new_family = Arc::new(RwLock::new(Niche::new(&cont.member, cont.fitness_score)));
+                        cont.species = Some(Arc::downgrade(&new_family));
+                        self.species.push(new_family);
+                    }
                 }
             }
-        }
-        // first filter out all species with have died out.
-        // go through and set the total adjusted fitness for each species
-        self.species.retain(|x| Arc::weak_count(&x) > 0);
-        for i in self.species.iter() {
-            i.write().unwrap().calculate_total_adjusted_fitness();
-        }
+            // first filter out all species with have died out.
+            // go through and set the total adjusted fitness for each species
+            self.species.retain(|x| Arc::weak_count(&x) > 0);
+            let aging = settings.read().unwrap().species_aging();
+            for i in self.species.iter() {
+                i.write().unwrap().share_fitness(aging.as_ref());
+            }
+        })
+    }
+
+    /// the index of the species each member belongs in without changing any of them, past the
+    /// existing species are the new species founded (in order) by the members which fit none of them
+    fn place(&self, distance: f32, settings: &Arc<RwLock<E>>) -> Vec<usize> {
+        let mascots = self.species
+            .iter()
+            .map(|s| Arc::clone(&s.read().unwrap().mascot))
+            .collect::<Vec<_>>();
+        let existing = self.members
+            .par_iter()
+            .map(|cont| {
+                let member = cont.member.read().unwrap();
+                mascots.iter().position(|mascot| {
+                    <T as Genome<T, E>>::within_distance(&*member, &*mascot.read().unwrap(), Arc::clone(settings), distance)
+                })
+            })
+            .collect::<Vec<_>>();
+        // members without an existing species can still belong to one founded earlier in this loop
+        let mut founders: Vec<&Container<T, E>> = Vec::new();
+        self.members.iter()
+            .zip(existing)
+            .map(|(cont, index)| index.unwrap_or_else(|| {
+                let found = founders.iter().position(|founder| {
+                    <T as Genome<T, E>>::within_distance(&*cont.member.read().unwrap(), &*founder.member.read().unwrap(), Arc::clone(settings), distance)
+                });
+                mascots.len() + found.unwrap_or_else(|| {
+                    founders.push(cont);
+                    founders.len() - 1
+                })
+            }))
+            .collect()
     }
 
     /// Create the next generation and return a new generation struct with 
     /// new members, and reset species. This is how the generation moves from
     /// one to the next. This function also is the one which runs the crossover
     /// fn from the genome trait, the more efficient that function is, the faster
-    /// this function will be.
+    /// this function will be. The alignment of each pair of parents is kept in the
+    /// new generation's crossover stats. The elites come first in the new generation,
+    /// followed by the survivors which aren't elites and then the children.
     #[inline]
     pub fn create_next_generation(&mut self, pop_size: i32, config: Config, env: Arc<RwLock<E>>) -> Option<Self> {   
         // generating new members in a biased way using rayon to parallelize it
         // then crossover to fill the rest of the generation 
-        let mut new_members = self.survival_criteria.pick_survivors(&mut self.members, &self.species)?;
-        let children = (new_members.len() as i32..pop_size)
-            .into_par_iter()
-            .map(|_|{
-                // select two random species to crossover, with a chance of inbreeding then cross them over
-                let (one, two) = self.parental_criteria.pick_parents(config.inbreed_rate, &self.species).unwrap();
-                let child = if one.0 > two.0 {
-                    <T as Genome<T, E>>::crossover(&*one.1.read().unwrap(), &*two.1.read().unwrap(), Arc::clone(&env), config.crossover_rate).unwrap()
-                } else {
-                    <T as Genome<T, E>>::crossover(&*two.1.read().unwrap(), &*one.1.read().unwrap(), Arc::clone(&env), config.crossover_rate).unwrap()
-                };
-                Arc::new(RwLock::new(child))
+        let (elites, mut new_members) = phase!("survival", {
+            let elites = self.elitism.pick_elites(&self.members, &self.species, pop_size.max(0) as usize);
+            let survivors = self.survival_criteria.pick_survivors(&mut self.members, &self.species)?
+                .into_iter()
+                .filter(|member| !elites.iter().any(|elite| Arc::ptr_eq(elite, member)))
+                .collect::<Vec<_>>();
+            (elites.len(), elites.into_iter().chain(survivors).collect::<Vec<_>>())
+        });
+        // the members of each boosted species along with the environment their offspring are bred with
+        let boosted = self.mutation_boosts.iter()
+            .filter_map(|boost| {
+                let family = self.species.iter().find(|family| family.read().unwrap().niche_id == boost.niche_id)?;
+                let members = family.read().unwrap().members.iter().map(|member| Weak::clone(&member.1)).collect::<Vec<_>>();
+                let boosted_env = env.read().unwrap().boost_mutation(boost.factor)?;
+                Some((members, Arc::new(RwLock::new(boosted_env))))
             })
             .collect::<Vec<_>>();
+        let keys = rng::keys((pop_size - new_members.len() as i32).max(0) as usize);
+        let (children, alignments): (Vec<_>, Vec<_>) = phase!("breed", {
+            keys.into_par_iter()
+                .map(|key| rng::scoped(key, || {
+                    // select two random species to crossover, with a chance of inbreeding then cross them over
+                    let (one, two) = self.parental_criteria.pick_parents(config.inbreed_rate, &self.species, &*self.selection, &self.breeders).unwrap();
+                    let fitter = one.0 > two.0;
+                    let parent = if fitter { &one.1 } else { &two.1 };
+                    let env = boosted.iter()
+                        .find(|(members, _)| members.iter().any(|member| std::ptr::eq(member.as_ptr(), Arc::as_ptr(parent))))
+                        .map_or_else(|| Arc::clone(&env), |(_, boosted_env)| Arc::clone(boosted_env));
+                    let (one, two) = (one.1.read().unwrap(), two.1.read().unwrap());
+                    let alignment = <T as Genome<T, E>>::alignment(&*one, &*two);
+                    let child = if fitter {
+                        <T as Genome<T, E>>::crossover(&*one, &*two, Arc::clone(&env), config.crossover_rate)
+                    } else {
+                        <T as Genome<T, E>>::crossover(&*two, &*one, Arc::clone(&env), config.crossover_rate)
+                    }.unwrap_or_else(|e| panic!("Crossover failed: {}", e));
+                    (Arc::new(RwLock::new(child)), alignment)
+                }))
+                .unzip()
+        });
         // reset the species and pass down the new members to a new generation
         new_members.extend(children);
-        self.pass_down(new_members)
+        let mut next = self.pass_down(new_members)?;
+        next.elites = elites;
+        next.crossover_stats = CrossoverStats {
+            matings: alignments.into_iter().flatten().collect()
+        };
+        Some(next)
     }
 
     /// get the top member of the generations
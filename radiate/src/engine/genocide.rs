@@ -10,6 +10,7 @@ use rayon::prelude::*;
 use rand::Rng;
 use super::generation::{Generation};
 use super::genome::{Genome};
+use crate::rng;
 use super::niche::{NicheMember};
 
 
@@ -75,9 +76,12 @@ impl Genocide {
             T: Genome<T, E> + Send + Sync,
             E: Send + Sync
     {
+        let keys = rng::keys(generation.species.len());
         generation.species
             .par_iter_mut()
-            .map_init(|| rand::thread_rng(), |r, spec| {
+            .zip(keys)
+            .map(|(spec, key)| rng::scoped(key, || {
+                let mut r = rng::thread();
                 let mut new_members = Vec::new();
                 for mem in spec.read().unwrap().members.iter() {
                     if r.gen::<f32>() > perc {
@@ -89,7 +93,7 @@ impl Genocide {
                 if new_members.len() > 0 {
                     spec.write().unwrap().members = new_members;
                 }
-            })
+            }))
             .collect::<Vec<_>>();
     }
 
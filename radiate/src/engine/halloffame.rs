@@ -0,0 +1,117 @@
+//! The best members a run has ever seen. A generation only keeps its best members for as long as
+//! they survive selection, and stagnation purges (see Genocide) can wipe out a good solution with the
+//! rest of its species, the hall of fame keeps them regardless so they can be handed back at the end
+//! or put back into the population after a purge
+
+use std::sync::{Arc, RwLock};
+use super::generation::Container;
+use super::genome::Genome;
+
+
+
+/// The top capacity members seen across every generation, best first. Members closer than distance
+/// to one already in the hall (see Genome::within_distance) are taken as the same solution, only the
+/// fitter of the two is kept so the hall doesn't fill up with copies of one network. The distance is
+/// on the same scale as the speciation distance of the population's Config.
+///
+/// reinject - after stagnation purges the population this many of the best inductees take the place
+/// of the last offspring of the next generation, none by default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallOfFame<T> {
+    pub capacity: usize,
+    pub distance: f32,
+    pub reinject: usize,
+    inductees: Vec<Inductee<T>>
+}
+
+
+/// A member of the hall of fame, the score it got and the generation it got it in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inductee<T> {
+    pub fitness: f32,
+    pub generation: usize,
+    pub member: T
+}
+
+
+
+impl<T> HallOfFame<T> {
+
+    pub fn new(capacity: usize) -> Self {
+        HallOfFame {
+            capacity,
+            distance: 0.1,
+            reinject: 0,
+            inductees: Vec::new()
+        }
+    }
+
+
+    pub fn distance(mut self, distance: f32) -> Self {
+        self.distance = distance;
+        self
+    }
+
+
+    pub fn reinject(mut self, count: usize) -> Self {
+        self.reinject = count;
+        self
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.inductees.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.inductees.is_empty()
+    }
+
+
+    /// the inductees best first
+    pub fn inductees(&self) -> &[Inductee<T>] {
+        &self.inductees
+    }
+
+
+    /// the best member ever seen
+    pub fn best(&self) -> Option<&Inductee<T>> {
+        self.inductees.first()
+    }
+
+
+    /// Consider the scored members of a generation for the hall. Only the members which
+    /// would make it in are measured against the inductees, fittest first, so a full hall
+    /// costs little more than a sort once the run stops improving. Returns how many got in.
+    pub fn induct<E>(&mut self, generation: usize, members: &[Container<T, E>], env: &Arc<RwLock<E>>) -> usize
+        where
+            T: Genome<T, E> + Clone + Send + Sync,
+            E: Send + Sync
+    {
+        let mut candidates = members.iter()
+            .filter(|cont| !cont.fitness_score.is_nan())
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| b.fitness_score.total_cmp(&a.fitness_score));
+        let mut inducted = 0;
+        for cont in candidates {
+            if self.capacity == 0 || (self.inductees.len() == self.capacity && !self.inductees.last().is_some_and(|worst| cont.fitness_score > worst.fitness)) {
+                break;
+            }
+            let member = cont.member.read().unwrap();
+            let similar = self.inductees.iter()
+                .position(|inductee| T::within_distance(&member, &inductee.member, Arc::clone(env), self.distance));
+            match similar {
+                Some(index) if self.inductees[index].fitness >= cont.fitness_score => continue,
+                Some(index) => { self.inductees.remove(index); },
+                None => {}
+            }
+            let inductee = Inductee { fitness: cont.fitness_score, generation, member: (*member).clone() };
+            let index = self.inductees.partition_point(|other| other.fitness >= inductee.fitness);
+            self.inductees.insert(index, inductee);
+            self.inductees.truncate(self.capacity);
+            inducted += 1;
+        }
+        inducted
+    }
+}
@@ -0,0 +1,258 @@
+//! What a run came to generation by generation. The population keeps a record of every step it
+//! takes (see Population::history) so how a run converged can be plotted or compared with another
+//! run once it's over, without a hook having to collect it as it goes
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+use super::generation::Container;
+use super::genome::Genome;
+
+
+
+/// the header of the csv a history is written to, see RunHistory::to_csv
+const CSV_HEADER: &str = "generation,best_fitness,mean_fitness,std_fitness,species,species_sizes,\
+    mean_nodes,mean_edges,top_nodes,top_edges,evaluation_ms,elapsed_ms";
+
+
+
+/// What one generation came to. The fitness is over the members which were scored, species_sizes
+/// is how many members each species had once they were speciated. The complexity is (nodes, edges)
+/// as the genome reports it (see Genome::complexity), None if it doesn't. evaluation is how long
+/// scoring the members took and elapsed how long the whole generation did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationRecord {
+    pub generation: usize,
+    pub best_fitness: f32,
+    pub mean_fitness: f32,
+    pub std_fitness: f32,
+    pub species: usize,
+    pub species_sizes: Vec<usize>,
+    pub mean_complexity: Option<(f32, f32)>,
+    pub top_complexity: Option<(usize, usize)>,
+    pub evaluation: Duration,
+    pub elapsed: Duration
+}
+
+
+/// The record of every generation of a run in the order they were run, carried over in checkpoints
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunHistory {
+    pub generations: Vec<GenerationRecord>
+}
+
+
+
+impl GenerationRecord {
+
+    /// the fitness and complexity of the scored members of a generation, the rest
+    /// is filled in by the population once the generation is finished
+    pub(crate) fn measure<T, E>(generation: usize, members: &[Container<T, E>], evaluation: Duration) -> Self
+        where
+            T: Genome<T, E> + Send + Sync,
+            E: Send + Sync
+    {
+        let count = members.len().max(1) as f32;
+        let mean_fitness = members.iter().map(|cont| cont.fitness_score).sum::<f32>() / count;
+        let variance = members.iter().map(|cont| (cont.fitness_score - mean_fitness).powi(2)).sum::<f32>() / count;
+        let complexity = members.iter()
+            .map(|cont| T::complexity(&cont.member.read().unwrap()))
+            .collect::<Option<Vec<_>>>()
+            .filter(|sizes| !sizes.is_empty());
+        GenerationRecord {
+            generation,
+            best_fitness: members.iter().map(|cont| cont.fitness_score).fold(f32::MIN, f32::max),
+            mean_fitness,
+            std_fitness: variance.sqrt(),
+            species: 0,
+            species_sizes: Vec::new(),
+            mean_complexity: complexity.map(|sizes| {
+                let nodes = sizes.iter().map(|(nodes, _)| *nodes).sum::<usize>();
+                let edges = sizes.iter().map(|(_, edges)| *edges).sum::<usize>();
+                (nodes as f32 / count, edges as f32 / count)
+            }),
+            top_complexity: None,
+            evaluation,
+            elapsed: Duration::default()
+        }
+    }
+
+
+    /// the record as a row of the csv written by RunHistory::to_csv, without its line ending
+    pub fn to_csv_row(&self) -> String {
+        let sizes = self.species_sizes.iter().map(|size| size.to_string()).collect::<Vec<_>>().join(";");
+        let (mean_nodes, mean_edges) = self.mean_complexity
+            .map_or((String::new(), String::new()), |(nodes, edges)| (nodes.to_string(), edges.to_string()));
+        let (top_nodes, top_edges) = self.top_complexity
+            .map_or((String::new(), String::new()), |(nodes, edges)| (nodes.to_string(), edges.to_string()));
+        format!("{},{},{},{},{},{},{},{},{},{},{:.3},{:.3}",
+            self.generation,
+            self.best_fitness,
+            self.mean_fitness,
+            self.std_fitness,
+            self.species,
+            sizes,
+            mean_nodes,
+            mean_edges,
+            top_nodes,
+            top_edges,
+            self.evaluation.as_secs_f64() * 1000.0,
+            self.elapsed.as_secs_f64() * 1000.0
+        )
+    }
+
+
+    /// Add the record to the end of a csv written by RunHistory::save_csv, writing the header
+    /// first if the file is new or empty, so a run can keep its history on disk as it goes
+    pub fn append_csv(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let fresh = !Path::new(path).exists() || fs::metadata(path)?.len() == 0;
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if fresh {
+            writeln!(file, "{}", CSV_HEADER)?;
+        }
+        writeln!(file, "{}", self.to_csv_row())?;
+        Ok(())
+    }
+}
+
+
+
+impl RunHistory {
+
+    pub fn new() -> Self {
+        RunHistory::default()
+    }
+
+
+    pub fn push(&mut self, record: GenerationRecord) {
+        self.generations.push(record);
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.generations.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.generations.is_empty()
+    }
+
+
+    /// the best fitness of each generation, the run's convergence curve
+    pub fn best_fitness(&self) -> Vec<f32> {
+        self.generations.iter().map(|record| record.best_fitness).collect()
+    }
+
+
+    /// the best fitness of each generation paired with its generation number, which
+    /// doesn't start at 0 for a run resumed from a history that wasn't carried over
+    pub fn best_fitness_curve(&self) -> Vec<(usize, f32)> {
+        self.generations.iter().map(|record| (record.generation, record.best_fitness)).collect()
+    }
+
+
+    /// the mean fitness of each generation paired with its generation number
+    pub fn mean_fitness_curve(&self) -> Vec<(usize, f32)> {
+        self.generations.iter().map(|record| (record.generation, record.mean_fitness)).collect()
+    }
+
+
+    /// the record of a generation by its number, None if it isn't in the history
+    pub fn record(&self, generation: usize) -> Option<&GenerationRecord> {
+        self.generations.iter().find(|record| record.generation == generation)
+    }
+
+
+    /// the record of the most recent generation
+    pub fn last(&self) -> Option<&GenerationRecord> {
+        self.generations.last()
+    }
+
+
+    /// the record of the generation with the best fitness, the earliest of them on a tie
+    pub fn best_generation(&self) -> Option<&GenerationRecord> {
+        self.generations.iter().fold(None, |best: Option<&GenerationRecord>, record| match best {
+            Some(best) if best.best_fitness >= record.best_fitness => Some(best),
+            _ => Some(record)
+        })
+    }
+
+
+    /// the first generation whose best member scored at least fitness, ie: how long a run took
+    /// to solve a problem. None if no generation reached it
+    pub fn first_generation_reaching(&self, fitness: f32) -> Option<usize> {
+        self.generations.iter()
+            .find(|record| record.best_fitness >= fitness)
+            .map(|record| record.generation)
+    }
+
+
+    /// the records of the generations from start up to but not including end
+    pub fn between(&self, start: usize, end: usize) -> impl Iterator<Item = &GenerationRecord> {
+        self.generations.iter().filter(move |record| record.generation >= start && record.generation < end)
+    }
+
+
+    /// One row per generation with a header. The species sizes are separated by ';' so
+    /// they stay in one column, a complexity the genome doesn't report is left empty and
+    /// the times are in milliseconds
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for record in self.generations.iter() {
+            csv.push_str(&record.to_csv_row());
+            csv.push('\n');
+        }
+        csv
+    }
+
+
+    pub fn save_csv(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, self.to_csv())?;
+        Ok(())
+    }
+
+
+    /// Read back a history written by to_csv, ie: to compare runs saved by separate processes
+    /// (see RunComparison). The times only come back to the microsecond they were written to
+    pub fn from_csv(csv: &str) -> Result<Self, Box<dyn Error>> {
+        let mut history = RunHistory::new();
+        for line in csv.lines().skip(1).filter(|line| !line.trim().is_empty()) {
+            let fields = line.trim().split(',').collect::<Vec<_>>();
+            if fields.len() != 12 {
+                return Err(format!("expected 12 columns, found {} in '{}'", fields.len(), line).into());
+            }
+            let complexity = |nodes: &str, edges: &str| -> Result<Option<(f32, f32)>, Box<dyn Error>> {
+                if nodes.is_empty() || edges.is_empty() {
+                    return Ok(None);
+                }
+                Ok(Some((nodes.parse()?, edges.parse()?)))
+            };
+            let species_sizes = fields[5].split(';')
+                .filter(|size| !size.is_empty())
+                .map(|size| size.parse())
+                .collect::<Result<Vec<usize>, _>>()?;
+            history.push(GenerationRecord {
+                generation: fields[0].parse()?,
+                best_fitness: fields[1].parse()?,
+                mean_fitness: fields[2].parse()?,
+                std_fitness: fields[3].parse()?,
+                species: fields[4].parse()?,
+                species_sizes,
+                mean_complexity: complexity(fields[6], fields[7])?,
+                top_complexity: complexity(fields[8], fields[9])?
+                    .map(|(nodes, edges)| (nodes as usize, edges as usize)),
+                evaluation: Duration::from_secs_f64(fields[10].parse::<f64>()? / 1000.0),
+                elapsed: Duration::from_secs_f64(fields[11].parse::<f64>()? / 1000.0)
+            });
+        }
+        Ok(history)
+    }
+
+
+    pub fn load_csv(path: &str) -> Result<Self, Box<dyn Error>> {
+        RunHistory::from_csv(&fs::read_to_string(path)?)
+    }
+}
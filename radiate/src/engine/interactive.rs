@@ -0,0 +1,204 @@
+//! Interactive evolution, where a person is the fitness function. Each generation's members are
+//! handed to a callback which shows them (or whatever they render to, ie: the image a CPPN draws)
+//! to someone and returns their judgement, which is turned into the members' fitness. People can
+//! only look at so many candidates, so populations for this are small, see Population::interactive.
+//! To ask even less of them, a Surrogate learns from their past judgements to score most members
+//! itself and only shows them the members it is least sure about, see Population::train_assisted.
+
+use std::cmp::Ordering;
+
+use super::problem::Problem;
+
+
+
+/// What a person thought of the members of a generation, by their index in the generation
+///
+/// Ranked - the members they picked out, best first. It doesn't have to be all of them, the
+///          members left out are behind the last one ranked
+/// Scored - a score for each member they rated, higher is better. Members left out get
+///          the lowest score given
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Judgement {
+    Ranked(Vec<usize>),
+    Scored(Vec<(usize, f32)>)
+}
+
+
+
+impl Judgement {
+
+    /// the fitness of each of the given number of members, every fitness is positive so the
+    /// biased random picking of parents still works when nothing was ranked
+    pub fn fitness(&self, members: usize) -> Vec<f32> {
+        match self {
+            Judgement::Ranked(order) => {
+                let mut fitness = vec![1.0; members];
+                for (position, index) in order.iter().enumerate() {
+                    if let Some(fit) = fitness.get_mut(*index) {
+                        *fit = (1 + order.len() - position) as f32;
+                    }
+                }
+                fitness
+            },
+            Judgement::Scored(scores) => {
+                // shift the scores so the lowest, and every member left out, is 1
+                let lowest = scores.iter().map(|(_, score)| *score).fold(f32::INFINITY, f32::min);
+                let lowest = if lowest.is_finite() { lowest } else { 0.0 };
+                let mut fitness = vec![1.0; members];
+                for (index, score) in scores.iter() {
+                    if let Some(fit) = fitness.get_mut(*index) {
+                        *fit = score - lowest + 1.0;
+                    }
+                }
+                fitness
+            }
+        }
+    }
+}
+
+
+
+/// The problem of an interactive population, which doesn't solve anything since the fitness
+/// comes from a person's judgement, see Population::train_interactive
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Judged;
+
+
+
+impl<T> Problem<T> for Judged {
+
+    fn empty() -> Self {
+        Judged
+    }
+
+    fn solve(&self, _: &mut T) -> f32 {
+        0.0
+    }
+}
+
+
+
+/// A buffer of every judgement a person has made, kept as the features of each judged member and the
+/// fitness it was given, which doubles as a model to predict what they would think of new members.
+/// Predictions are a kernel weighted mean of the fitness of the nearest judged members, so members
+/// which look like ones people liked are predicted to be liked too. Judgements are relative to the
+/// generation they were made in, so this learns what people favor rather than any absolute score.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Surrogate {
+    pub neighbors: usize,
+    pub bandwidth: f32,
+    pub capacity: Option<usize>,
+    samples: Vec<(Vec<f32>, f32)>
+}
+
+
+
+impl Surrogate {
+
+    /// a surrogate predicting from the 5 nearest judged members with a bandwidth of 1
+    pub fn new() -> Self {
+        Surrogate {
+            neighbors: 5,
+            bandwidth: 1.0,
+            capacity: None,
+            samples: Vec::new()
+        }
+    }
+
+
+    /// how many of the nearest judged members a prediction is made from
+    pub fn neighbors(mut self, neighbors: usize) -> Self {
+        self.neighbors = neighbors.max(1);
+        self
+    }
+
+
+    /// how far apart features can be and still be considered alike
+    pub fn bandwidth(mut self, bandwidth: f32) -> Self {
+        self.bandwidth = bandwidth;
+        self
+    }
+
+
+    /// only keep the most recent judgements, so the surrogate follows a person's taste as it changes
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+
+    /// the number of judgements in the buffer
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+
+    /// add a judged member's features and the fitness it was given to the buffer
+    pub fn record(&mut self, features: Vec<f32>, fitness: f32) {
+        self.samples.push((features, fitness));
+        if let Some(capacity) = self.capacity {
+            if self.samples.len() > capacity {
+                self.samples.drain(..self.samples.len() - capacity);
+            }
+        }
+    }
+
+
+    /// The predicted fitness of a member with these features and how uncertain that prediction is,
+    /// none if nothing has been judged yet. The uncertainty grows the further the member is from
+    /// anything judged and the more the judgements of its nearest neighbors disagree
+    pub fn predict(&self, features: &[f32]) -> Option<(f32, f32)> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut nearest = self.samples.iter()
+            .map(|(sample, fitness)| {
+                let distance = sample.iter().zip(features.iter()).map(|(a, b)| (a - b).powi(2)).sum::<f32>();
+                (distance, *fitness)
+            })
+            .collect::<Vec<_>>();
+        nearest.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        nearest.truncate(self.neighbors);
+        let weights = nearest.iter()
+            .map(|(distance, _)| (-distance / (2.0 * self.bandwidth * self.bandwidth)).exp())
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f32>();
+        // far from everything every weight vanishes, fall back to the plain mean of the neighbors
+        let (weights, total) = match total > f32::EPSILON {
+            true => (weights, total),
+            false => (vec![1.0; nearest.len()], nearest.len() as f32)
+        };
+        let mean = nearest.iter().zip(weights.iter()).map(|((_, fitness), weight)| fitness * weight).sum::<f32>() / total;
+        let variance = nearest.iter().zip(weights.iter()).map(|((_, fitness), weight)| weight * (fitness - mean).powi(2)).sum::<f32>() / total;
+        let coverage = nearest.iter().map(|(distance, _)| (-distance / (2.0 * self.bandwidth * self.bandwidth)).exp()).sum::<f32>();
+        Some((mean, variance.sqrt() + 1.0 / (1.0 + coverage)))
+    }
+
+
+    /// the indexes of the count members with the most uncertain predictions (uncertainty sampling),
+    /// which are the ones a person's judgement teaches the surrogate the most about. With nothing
+    /// judged yet these are just the first count members
+    pub fn most_uncertain(&self, features: &[Vec<f32>], count: usize) -> Vec<usize> {
+        let mut order = (0..features.len()).collect::<Vec<_>>();
+        let uncertainty = features.iter()
+            .map(|features| self.predict(features).map_or(0.0, |(_, uncertainty)| uncertainty))
+            .collect::<Vec<_>>();
+        order.sort_by(|a, b| uncertainty[*b].partial_cmp(&uncertainty[*a]).unwrap_or(Ordering::Equal));
+        order.truncate(count);
+        order.sort_unstable();
+        order
+    }
+}
+
+
+
+impl Default for Surrogate {
+    fn default() -> Self {
+        Surrogate::new()
+    }
+}
@@ -0,0 +1,119 @@
+//! Learning during a member's lifetime. Evolution searches the structure and gradient descent is
+//! far better at tuning the weights of it, a member which learns before it is scored brings the two
+//! together in one loop. Whether what it learned is passed down is the difference between Lamarck's
+//! and Baldwin's take on evolution, see Inheritance.
+
+use std::fmt;
+use std::sync::Arc;
+
+
+
+/// What becomes of what a member learned once it has been scored.
+///
+/// Lamarckian - the learned member takes the place of the original, so its children inherit the
+/// learning directly. Converges fast but can crowd out the structural search.
+/// Baldwinian - the member is scored on what it could learn, then put back as it was (see
+/// Learning::undo) so only the untrained genes are passed down. Selection favors members which
+/// are good at learning without writing the learning into the genes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Inheritance {
+    Lamarckian,
+    Baldwinian
+}
+
+
+/// puts a member back the way it was before it learned
+pub type Restore<T> = Box<dyn FnOnce(&mut T) + Send>;
+
+/// what gives a member's Restore before it learns, see Learning::undo
+type Undo<T> = Arc<dyn Fn(&T) -> Option<Restore<T>> + Send + Sync>;
+
+
+/// How members learn before they're scored, see Population::learning
+#[derive(Clone)]
+pub struct Learning<T> {
+    pub inheritance: Inheritance,
+    learn: Arc<dyn Fn(&mut T) + Send + Sync>,
+    undo: Option<Undo<T>>
+}
+
+
+
+impl<T> Learning<T> {
+
+    pub fn new<F>(inheritance: Inheritance, learn: F) -> Self
+        where F: Fn(&mut T) + Send + Sync + 'static
+    {
+        Learning {
+            inheritance,
+            learn: Arc::new(learn),
+            undo: None
+        }
+    }
+
+
+    /// Take what a Baldwinian member needs to be put back after it learns instead of copying
+    /// the whole member, ie: only the weights of a network. undo is given the member before it
+    /// learns and returns what restores it, or None to fall back to learning on a copy
+    pub fn undo<U>(mut self, undo: U) -> Self
+        where U: Fn(&T) -> Option<Restore<T>> + Send + Sync + 'static
+    {
+        self.undo = Some(Arc::new(undo));
+        self
+    }
+
+
+    pub fn lamarckian<F>(learn: F) -> Self
+        where F: Fn(&mut T) + Send + Sync + 'static
+    {
+        Learning::new(Inheritance::Lamarckian, learn)
+    }
+
+
+    pub fn baldwinian<F>(learn: F) -> Self
+        where F: Fn(&mut T) + Send + Sync + 'static
+    {
+        Learning::new(Inheritance::Baldwinian, learn)
+    }
+
+
+    /// Score the member with what it learns. A Lamarckian member learns in place, a Baldwinian
+    /// one is restored once it's scored if there is an undo, otherwise it learns on a copy which
+    /// is scored and thrown away
+    pub fn score<F, R>(&self, member: &mut T, score: F) -> R
+        where
+            T: Clone,
+            F: FnOnce(&mut T) -> R
+    {
+        match self.inheritance {
+            Inheritance::Lamarckian => {
+                (self.learn)(member);
+                score(member)
+            },
+            Inheritance::Baldwinian => match self.undo.as_ref().and_then(|undo| undo(member)) {
+                Some(restore) => {
+                    (self.learn)(member);
+                    let result = score(member);
+                    restore(member);
+                    result
+                },
+                None => {
+                    let mut learned = member.clone();
+                    (self.learn)(&mut learned);
+                    score(&mut learned)
+                }
+            }
+        }
+    }
+}
+
+
+
+impl<T> fmt::Debug for Learning<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Learning")
+            .field("inheritance", &self.inheritance)
+            .field("undo", &self.undo.is_some())
+            .finish()
+    }
+}
@@ -1,8 +1,29 @@
+#[macro_use]
+mod trace;
+
 pub mod population;
 pub mod generation;
 pub mod niche;
 pub mod genocide;
+pub mod stagnation;
 pub mod survival;
+pub mod selection;
+pub mod stopping;
+pub mod history;
+pub mod speciation;
+pub mod compare;
+pub mod halloffame;
+pub mod drift;
+pub mod compress;
+pub mod learning;
+pub mod approximate;
+pub mod novelty;
+pub mod pareto;
+pub mod archipelago;
+pub mod fitness;
+pub mod interactive;
+pub mod evolver;
+pub mod audit;
 
 
 
@@ -11,8 +32,10 @@ pub mod survival;
 pub mod genome {
     
     use super::environment::Envionment;
+    use super::generation::Alignment;
     use std::marker::Sized;
     use std::sync::{Arc, RwLock};
+    use std::error::Error;
 
     pub trait Genome<T, E>
         where
@@ -24,7 +47,7 @@ pub mod genome {
         /// mutation using the config type, or through crossover 
         /// where parts of one type are given to parts of the other and that resulting
         /// type is returned
-        fn crossover(one: &T, two: &T, env: Arc<RwLock<E>>, crossover_rate: f32) -> Option<T> 
+        fn crossover(one: &T, two: &T, env: Arc<RwLock<E>>, crossover_rate: f32) -> Result<T, Box<dyn Error>> 
             where 
                 T: Sized,
                 E: Envionment + Sized;
@@ -34,6 +57,25 @@ pub mod genome {
         /// a measure of how far away two types are from each other in a genetic 
         /// sense. Think of something like how similar humans are to dolphins, this is a way to quantify that.
         fn distance(one: &T, two: &T, env: Arc<RwLock<E>>) -> f32;
+
+        /// Check if two types are closer than the threshold distance, which is all speciation 
+        /// needs to know. Implementations can override this to stop measuring as soon as the
+        /// distance is known to be over the threshold instead of computing the full distance.
+        fn within_distance(one: &T, two: &T, env: Arc<RwLock<E>>, threshold: f32) -> bool {
+            Self::distance(one, two, env) < threshold
+        }
+
+        /// How the genes of two types line up for crossover, see Alignment. This is only
+        /// used for the crossover stats of a generation so it isn't needed, by default there is none.
+        fn alignment(_one: &T, _two: &T) -> Option<Alignment> {
+            None
+        }
+
+        /// The size of a type's structure as (nodes, edges), for a network its neurons and connections.
+        /// This is only used for the run history of a population so it isn't needed, by default there is none.
+        fn complexity(_one: &T) -> Option<(usize, usize)> {
+            None
+        }
         
         /// Genome needs to have a base implementation in order for one of the population options to be satisfied
         /// 
@@ -56,11 +98,32 @@ pub mod genome {
 /// this will slow down the optimization process as it will have to be locked during the writing thus
 /// having the variables in the implementation of this trait be readonly is preferred but isn't that big of a deal
 pub mod environment {
+
+    use super::niche::SpeciesAging;
+
     pub trait Envionment {
         
         /// Reset can be used to reset the environment after a certain event occurs,
         /// if not this is an empty default implementation
         fn reset(&mut self) { }
+
+        /// How species are boosted while young and penalized once they stop improving when their
+        /// fitness is shared, see SpeciesAging. By default there is no aging
+        fn species_aging(&self) -> Option<SpeciesAging> {
+            None
+        }
+
+        /// Called before each generation is bred with how many generations the population has
+        /// been through, so settings which follow a schedule over a run can be brought up to date.
+        /// By default nothing changes
+        fn advance(&mut self, _generation: usize) { }
+
+        /// A copy of the environment with its mutation rates scaled up by factor, which the offspring
+        /// of a species whose mutation is boosted for stagnating are bred with (see MutationBoost).
+        /// By default there is no boost
+        fn boost_mutation(&self, _factor: f32) -> Option<Self> where Self: Sized {
+            None
+        }
     
     }
 }
@@ -78,6 +141,8 @@ pub mod environment {
 /// is explicitly readonly 
 pub mod problem {
 
+    use super::pareto::Preferences;
+
     pub trait Problem<T> {
 
         /// empty can be a new for Self, or some sort of default value,
@@ -88,5 +153,76 @@ pub mod problem {
         /// use the data in the type implementing the problem to solve the problem and return
         /// the member's score. The result of this function is the member's fitness score 
         fn solve(&self, member: &mut T) -> f32;
+
+        /// The scores of a member on each objective of a multi-objective problem, see MultiObjective.
+        /// Single objective problems don't have any
+        fn objectives(&self, _member: &mut T) -> Option<Vec<f32>> {
+            None
+        }
+
+        /// The reference points a many-objective problem's members are niched around, see MultiObjective
+        fn reference_points(&self) -> Option<Vec<Vec<f32>>> {
+            None
+        }
+
+        /// The goals, constraints and priorities on a multi-objective problem's objectives, see MultiObjective
+        fn preferences(&self) -> Option<Preferences> {
+            None
+        }
+    }
+
+    /// A problem with more than one thing to optimize at once (ie: accuracy and network size) which
+    /// can't be boiled down to one score. Every member is scored on each objective and the population
+    /// ranks them by pareto front and crowding distance instead of a single fitness score, see the
+    /// pareto module. Every objective is maximized. Wrap it in a Pareto to give it to a population.
+    pub trait MultiObjective<T> {
+
+        /// empty can be a new for Self, or some sort of default value,
+        /// just needed to create a population with base parameters 
+        fn empty() -> Self;
+
+        /// the member's score on each objective, the same length for every member
+        fn objectives(&self, member: &mut T) -> Vec<f32>;
+
+        /// For many objectives (four or more) give reference points to rank members the way NSGA-III does
+        /// instead of by crowding distance, which stops spreading them out along the front once nearly
+        /// every member is non-dominated. See pareto::reference_points and pareto::reference_fitness
+        fn reference_points(&self) -> Option<Vec<Vec<f32>>> {
+            None
+        }
+
+        /// Steer the population towards the members that matter in practice (ie: accurate enough and
+        /// as small as possible) rather than the whole front, see pareto::Preferences
+        fn preferences(&self) -> Option<Preferences> {
+            None
+        }
+    }
+
+    /// A MultiObjective problem as the Problem a population solves
+    pub struct Pareto<M>(pub M);
+
+    impl<T, M> Problem<T> for Pareto<M>
+        where M: MultiObjective<T>
+    {
+        fn empty() -> Self {
+            Pareto(M::empty())
+        }
+
+        /// a multi-objective problem scored as a single objective is the sum of its objectives
+        fn solve(&self, member: &mut T) -> f32 {
+            self.0.objectives(member).iter().sum()
+        }
+
+        fn objectives(&self, member: &mut T) -> Option<Vec<f32>> {
+            Some(self.0.objectives(member))
+        }
+
+        fn reference_points(&self) -> Option<Vec<Vec<f32>>> {
+            self.0.reference_points()
+        }
+
+        fn preferences(&self) -> Option<Preferences> {
+            self.0.preferences()
+        }
     }
 }
\ No newline at end of file
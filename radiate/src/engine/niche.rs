@@ -6,10 +6,10 @@ use std::sync::{Arc, RwLock};
 use std::marker::PhantomData;
 use uuid::Uuid;
 use rand::prelude::SliceRandom;
-use rayon::prelude::*;
 
 use super::generation::{Member, MemberWeak};
 use super::genome::{Genome};
+use crate::rng;
 
 
 
@@ -23,18 +23,106 @@ pub struct NicheMember<T>(pub f32, pub MemberWeak<T>);
 /// A species is meant to keep track of fitness scores of each of it's members,
 /// and a mascot. The mascot is the representation of the species by a Type 
 /// member in the population. It also holds the number of age it's been
-/// alive
+/// alive, the best fitness any of its members has scored and the age it was
+/// scored at, and what its fitness was multiplied by for its age (see SpeciesAging)
 #[derive(Debug, Clone)]
 pub struct Niche<T, E> {
     pub mascot: Member<T>,
     pub members: Vec<NicheMember<T>>,
     pub age: i32,
+    pub best_fitness: Option<f32>,
+    pub last_improvement: i32,
+    pub age_multiplier: f32,
     pub total_adjusted_fitness: Option<f32>,
     pub niche_id: Uuid,
     phantom: PhantomData<E>
 }
 
 
+/// How a species' fitness is scaled by its age when it is shared among its members, as in the
+/// original NEAT. A new species is rarely a match for ones which have had generations to optimize
+/// so it is protected with a boost until it has had the chance, while a species which has stopped
+/// improving is penalized so its place goes to the offspring of species which haven't. Like the
+/// rest of fitness sharing this expects fitness to be positive.
+///
+/// young_age - species younger than this many generations have their fitness multiplied by young_boost
+/// dropoff_age - species whose top fitness hasn't improved for more than this many generations
+///     have their fitness multiplied by dropoff_penalty
+///
+/// The defaults are the usual values from NEAT, young species are boosted by 20% for their first
+/// 10 generations and species which go 15 without improving drop to 1% of their fitness
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeciesAging {
+    pub young_age: i32,
+    pub young_boost: f32,
+    pub dropoff_age: i32,
+    pub dropoff_penalty: f32
+}
+
+
+/// A snapshot of a species once its generation has been speciated, see Niche::stats. Each member's
+/// fitness is shared with the rest of the species by then, so mean_fitness is the total adjusted
+/// fitness and top_fitness is the fittest member's score before it was shared
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NicheStats {
+    pub niche_id: Uuid,
+    pub age: i32,
+    pub members: usize,
+    pub mean_fitness: f32,
+    pub top_fitness: f32
+}
+
+
+
+
+impl SpeciesAging {
+
+    pub fn new() -> Self {
+        SpeciesAging {
+            young_age: 10,
+            young_boost: 1.2,
+            dropoff_age: 15,
+            dropoff_penalty: 0.01
+        }
+    }
+
+
+    pub fn young(mut self, age: i32, boost: f32) -> Self {
+        self.young_age = age;
+        self.young_boost = boost;
+        self
+    }
+
+
+    pub fn dropoff(mut self, age: i32, penalty: f32) -> Self {
+        self.dropoff_age = age;
+        self.dropoff_penalty = penalty;
+        self
+    }
+
+
+    /// what the fitness of a species of the age which last improved stagnant generations ago is multiplied by
+    pub fn multiplier(&self, age: i32, stagnant: i32) -> f32 {
+        let mut multiplier = 1.0;
+        if age < self.young_age {
+            multiplier *= self.young_boost;
+        }
+        if stagnant > self.dropoff_age {
+            multiplier *= self.dropoff_penalty;
+        }
+        multiplier
+    }
+}
+
+
+
+impl Default for SpeciesAging {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 
 
 /// Implement the species
@@ -50,8 +138,11 @@ impl<T, E> Niche<T, E>
             mascot: Arc::clone(mascot),
             members: vec![NicheMember(mascot_fitness, Arc::downgrade(mascot))],
             age: 0,
+            best_fitness: None,
+            last_improvement: 0,
+            age_multiplier: 1.0,
             total_adjusted_fitness: None,
-            niche_id: Uuid::new_v4(),
+            niche_id: rng::uuid(&mut rng::thread()),
             phantom: PhantomData
         }
     }
@@ -82,7 +173,7 @@ impl<T, E> Niche<T, E>
     /// and clearing the members vec. Basically starting from scratch again but 
     /// need to increment a few small things to keep track of the species
     pub fn reset(&mut self) {
-        let new_mascot = self.members.choose(&mut rand::thread_rng());
+        let new_mascot = self.members.choose(&mut rng::thread());
         match new_mascot {
             Some(member) => {
                 self.age += 1;
@@ -98,15 +189,30 @@ impl<T, E> Niche<T, E>
 
     // for species sizes which are large and populations holding multiple species,
     // it makes sense to just calculate this once then retrieve the the value
-    // instead of calculate it every time it's needed. Its a quick and simple operation
+    // instead of calculate it every time it's needed. Its a quick and simple operation,
+    // summed serially because a parallel sum adds in a different order every run
     pub fn calculate_total_adjusted_fitness(&mut self) {
-        let length = self.members.len() as f32;
+        self.share_fitness(None);
+    }
+
+
+
+    /// Note whether the species' top fitness improved then share its fitness among its members
+    /// like calculate_total_adjusted_fitness, first scaling it for the age of the species if aging is given
+    pub fn share_fitness(&mut self, aging: Option<&SpeciesAging>) {
+        let top = self.members.iter().map(|member| member.0).fold(f32::MIN, f32::max);
+        if !self.members.is_empty() && self.best_fitness.is_none_or(|best| top > best) {
+            self.best_fitness = Some(top);
+            self.last_improvement = self.age;
+        }
+        self.age_multiplier = aging.map_or(1.0, |aging| aging.multiplier(self.age, self.age - self.last_improvement));
+        let (length, multiplier) = (self.members.len() as f32, self.age_multiplier);
         self.total_adjusted_fitness = Some(
             self.members
-                .par_iter_mut()
+                .iter_mut()
                 .map(|x| {
                     if x.0 != 0.0 {
-                        x.0 = x.0 / length;
+                        x.0 = x.0 * multiplier / length;
                     }
                     x.0
                 })
@@ -127,6 +233,21 @@ impl<T, E> Niche<T, E>
 
 
 
+    /// what the species came to, only meaningful once its total adjusted fitness has been calculated
+    pub fn stats(&self) -> NicheStats {
+        let members = self.members.len();
+        let top = self.members.iter().map(|member| member.0).fold(f32::MIN, f32::max);
+        NicheStats {
+            niche_id: self.niche_id,
+            age: self.age,
+            members,
+            mean_fitness: self.total_adjusted_fitness.unwrap_or(0.0),
+            top_fitness: if members == 0 { 0.0 } else { top * members as f32 / self.age_multiplier }
+        }
+    }
+
+
+
     pub fn display_info(&self) {
         println!("Species: {} gens( {} ) members( {} ) adj fit( {:.3} )",
             self.niche_id,
@@ -0,0 +1,269 @@
+extern crate serde_json;
+
+use std::fs::File;
+use std::error::Error;
+
+
+
+/// The archive of behaviors used for novelty search. A member's novelty is the mean distance
+/// between its behavior descriptor and the k nearest behaviors in the archive, a member whose
+/// novelty is over the threshold gets archived itself. Looking up the nearest behaviors by
+/// scanning the whole archive makes scoring a generation quadratic once the archive grows into
+/// the thousands, so the archive is indexed by a kd-tree. The tree is rebuilt once the behaviors
+/// added since the last build outnumber the ones in it, the few recent behaviors which aren't
+/// in the tree yet are just scanned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoveltyArchive {
+    pub k: usize,
+    pub threshold: f32,
+    behaviors: Vec<Vec<f32>>,
+    #[serde(skip)]
+    index: KdTree
+}
+
+
+
+impl NoveltyArchive {
+
+    pub fn new(k: usize, threshold: f32) -> Self {
+        NoveltyArchive {
+            k,
+            threshold,
+            behaviors: Vec::new(),
+            index: KdTree::default()
+        }
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.behaviors.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.behaviors.is_empty()
+    }
+
+
+    pub fn behaviors(&self) -> &[Vec<f32>] {
+        &self.behaviors
+    }
+
+
+    /// length of the behavior descriptors in the archive, None while it is empty
+    pub fn dimensions(&self) -> Option<usize> {
+        self.behaviors.first().map(|behavior| behavior.len())
+    }
+
+
+    /// add a behavior to the archive regardless of how novel it is
+    pub fn insert(&mut self, behavior: Vec<f32>) {
+        if let Some(first) = self.behaviors.first() {
+            assert_eq!(first.len(), behavior.len(), "Behavior descriptors must all be the same length");
+        }
+        self.behaviors.push(behavior);
+        if self.behaviors.len() - self.index.len() > self.index.len().max(16) {
+            self.index = KdTree::build(&self.behaviors);
+        }
+    }
+
+
+    /// the mean distance to the k nearest behaviors in the archive, an empty archive
+    /// has nothing to compare to so everything is infinitely novel. Panics if the
+    /// behavior isn't the length of the ones in the archive, see nearest
+    pub fn novelty(&self, behavior: &[f32]) -> f32 {
+        let nearest = self.nearest(behavior, self.k);
+        if nearest.is_empty() {
+            return f32::INFINITY;
+        }
+        nearest.iter().map(|(_, distance)| distance).sum::<f32>() / nearest.len() as f32
+    }
+
+
+    /// score the behavior and archive it if it is novel enough, returns the novelty
+    pub fn evaluate(&mut self, behavior: Vec<f32>) -> f32 {
+        let novelty = self.novelty(&behavior);
+        if novelty > self.threshold {
+            self.insert(behavior);
+        }
+        novelty
+    }
+
+
+    /// fold another archive, usually from a different run, into this one. Each of its behaviors
+    /// is scored against this archive with this archive's k and threshold and only the novel ones
+    /// are kept, so behaviors both runs found aren't archived twice. Returns how many were added.
+    pub fn merge(&mut self, other: &NoveltyArchive) -> Result<usize, Box<dyn Error>> {
+        if let (Some(mine), Some(theirs)) = (self.dimensions(), other.dimensions()) {
+            if mine != theirs {
+                return Err(format!("Cannot merge archives of {} and {} dimensional behaviors", mine, theirs).into());
+            }
+        }
+        let before = self.len();
+        for behavior in other.behaviors.iter() {
+            self.evaluate(behavior.clone());
+        }
+        Ok(self.len() - before)
+    }
+
+
+    /// save the archive to a json file so it can seed or be merged into later runs
+    pub fn save(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer(&File::create(file_path)?, self)?;
+        Ok(())
+    }
+
+
+    /// load an archive saved with save, the index isn't saved so it is rebuilt here
+    pub fn load(file_path: &str) -> Result<NoveltyArchive, Box<dyn Error>> {
+        let mut archive: NoveltyArchive = serde_json::from_reader(File::open(file_path)?)?;
+        if let Some(dimensions) = archive.dimensions() {
+            if archive.behaviors.iter().any(|behavior| behavior.len() != dimensions) {
+                return Err("Behavior descriptors in the archive are not all the same length".into());
+            }
+        }
+        archive.index = KdTree::build(&archive.behaviors);
+        Ok(archive)
+    }
+
+
+    /// the indexes into the archive and euclidean distances of the k behaviors
+    /// nearest to the given one, closest first. Panics if the behavior isn't the length
+    /// of the ones in the archive, like insert, the distance to it would mean nothing
+    pub fn nearest(&self, behavior: &[f32], k: usize) -> Vec<(usize, f32)> {
+        if let Some(dimensions) = self.dimensions() {
+            assert_eq!(dimensions, behavior.len(), "Behavior descriptors must all be the same length");
+        }
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut nearest = Nearest::new(k);
+        self.index.search(&self.behaviors, behavior, &mut nearest);
+        for index in self.index.len()..self.behaviors.len() {
+            nearest.offer(index, squared_distance(&self.behaviors[index], behavior));
+        }
+        nearest.found
+            .into_iter()
+            .map(|(index, distance)| (index, distance.sqrt()))
+            .collect()
+    }
+}
+
+
+
+/// A kd-tree over the first len behaviors of the archive, stored implicitly - the point
+/// splitting each range of order is the one in the middle of it, the lower half of the range
+/// is left of the split and the upper half right of it. The axis cycles with the depth
+/// through the dims of the points the tree was built from.
+#[derive(Debug, Clone, Default)]
+struct KdTree {
+    order: Vec<usize>,
+    dims: usize
+}
+
+
+
+impl KdTree {
+
+    fn build(points: &[Vec<f32>]) -> Self {
+        let mut order = (0..points.len()).collect::<Vec<_>>();
+        let dims = points.first().map_or(0, |point| point.len());
+        if dims > 0 {
+            KdTree::split(points, &mut order, 0, dims);
+        }
+        KdTree { order, dims }
+    }
+
+
+    fn split(points: &[Vec<f32>], order: &mut [usize], depth: usize, dims: usize) {
+        if order.len() < 2 {
+            return;
+        }
+        let axis = depth % dims;
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by(mid, |a, b| {
+            points[*a][axis].partial_cmp(&points[*b][axis]).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let (lower, upper) = order.split_at_mut(mid);
+        KdTree::split(points, lower, depth + 1, dims);
+        KdTree::split(points, &mut upper[1..], depth + 1, dims);
+    }
+
+
+    fn len(&self) -> usize {
+        self.order.len()
+    }
+
+
+    fn search(&self, points: &[Vec<f32>], target: &[f32], nearest: &mut Nearest) {
+        KdTree::search_range(&self.order, points, target, 0, self.dims, nearest);
+    }
+
+
+    fn search_range(order: &[usize], points: &[Vec<f32>], target: &[f32], depth: usize, dims: usize, nearest: &mut Nearest) {
+        if order.is_empty() {
+            return;
+        }
+        let mid = order.len() / 2;
+        let point = &points[order[mid]];
+        nearest.offer(order[mid], squared_distance(point, target));
+
+        // search the side the target is on first, the other side can only hold
+        // something closer if the splitting plane is closer than the worst found
+        let axis = depth % dims;
+        let diff = target[axis] - point[axis];
+        let (near, far) = if diff < 0.0 {
+            (&order[..mid], &order[mid + 1..])
+        } else {
+            (&order[mid + 1..], &order[..mid])
+        };
+        KdTree::search_range(near, points, target, depth + 1, dims, nearest);
+        if diff * diff < nearest.worst() {
+            KdTree::search_range(far, points, target, depth + 1, dims, nearest);
+        }
+    }
+}
+
+
+
+/// the k closest points found so far, sorted by squared distance
+struct Nearest {
+    k: usize,
+    found: Vec<(usize, f32)>
+}
+
+
+
+impl Nearest {
+
+    fn new(k: usize) -> Self {
+        Nearest { k, found: Vec::with_capacity(k + 1) }
+    }
+
+
+    fn worst(&self) -> f32 {
+        if self.found.len() < self.k {
+            f32::INFINITY
+        } else {
+            self.found.last().map_or(f32::INFINITY, |(_, distance)| *distance)
+        }
+    }
+
+
+    fn offer(&mut self, index: usize, distance: f32) {
+        if distance < self.worst() {
+            let position = self.found.iter().position(|(_, other)| distance < *other).unwrap_or(self.found.len());
+            self.found.insert(position, (index, distance));
+            self.found.truncate(self.k);
+        }
+    }
+}
+
+
+
+fn squared_distance(one: &[f32], two: &[f32]) -> f32 {
+    one.iter()
+        .zip(two.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum()
+}
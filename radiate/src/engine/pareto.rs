@@ -0,0 +1,515 @@
+//! Pareto ranking for multi-objective problems, the parts of NSGA-II which turn each member's
+//! objective scores into a single fitness so the rest of the engine (survival, picking parents,
+//! species) works on multi-objective problems unchanged. Every objective is maximized, negate
+//! any that should be minimized (ie: the size of a network). With more than three or so objectives
+//! nearly every member is non-dominated, so NSGA-III's reference points take over from the crowding
+//! distance to keep the front spread out, see reference_fitness. Goals, constraints and priorities on
+//! the objectives narrow the search down to the part of the front that matters, see Preferences. The indicators at the end (hypervolume
+//! and IGD) measure how good a whole front is, to track a run converging from one generation to the next.
+
+extern crate serde_json;
+
+use std::fmt;
+use std::fs::File;
+use std::error::Error;
+use std::cmp::Ordering;
+
+
+
+/// true if a is at least as good as b on every objective and better on at least one
+pub fn dominates(a: &[f32], b: &[f32]) -> bool {
+    a.iter().zip(b.iter()).all(|(x, y)| x >= y) && a.iter().zip(b.iter()).any(|(x, y)| x > y)
+}
+
+
+
+/// split the scores into fronts of indexes, the first front is dominated by nothing, the
+/// second only by members of the first, and so on
+pub fn non_dominated_sort(scores: &[Vec<f32>]) -> Vec<Vec<usize>> {
+    let mut dominated_by = vec![0; scores.len()];
+    let mut dominating = vec![Vec::new(); scores.len()];
+    for i in 0..scores.len() {
+        for j in (i + 1)..scores.len() {
+            if dominates(&scores[i], &scores[j]) {
+                dominating[i].push(j);
+                dominated_by[j] += 1;
+            } else if dominates(&scores[j], &scores[i]) {
+                dominating[j].push(i);
+                dominated_by[i] += 1;
+            }
+        }
+    }
+    let mut fronts = Vec::new();
+    let mut front = (0..scores.len()).filter(|i| dominated_by[*i] == 0).collect::<Vec<_>>();
+    while !front.is_empty() {
+        let mut next = Vec::new();
+        for i in front.iter() {
+            for j in dominating[*i].iter() {
+                dominated_by[*j] -= 1;
+                if dominated_by[*j] == 0 {
+                    next.push(*j);
+                }
+            }
+        }
+        fronts.push(front);
+        front = next;
+    }
+    fronts
+}
+
+
+
+/// the crowding distance of each member of a front, in the order of the front. This is the sum
+/// over each objective of the gap between a member's neighbors on that objective, relative to the
+/// range of the front. The members at the ends of any objective are infinitely far from the rest.
+pub fn crowding_distance(scores: &[Vec<f32>], front: &[usize]) -> Vec<f32> {
+    let mut distances = vec![0.0; front.len()];
+    let first = match front.first() {
+        Some(first) => &scores[*first],
+        None => return distances
+    };
+    for (objective, _) in first.iter().enumerate() {
+        let mut order = (0..front.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| scores[front[*a]][objective].partial_cmp(&scores[front[*b]][objective]).unwrap_or(Ordering::Equal));
+        let (first, last) = (order[0], order[order.len() - 1]);
+        distances[first] = f32::INFINITY;
+        distances[last] = f32::INFINITY;
+        let range = scores[front[last]][objective] - scores[front[first]][objective];
+        if range <= 0.0 {
+            continue;
+        }
+        for window in order.windows(3) {
+            let gap = scores[front[window[2]]][objective] - scores[front[window[0]]][objective];
+            distances[window[1]] += gap / range;
+        }
+    }
+    distances
+}
+
+
+
+/// A fitness for each member which orders them the way NSGA-II's crowded comparison does - a
+/// member in an earlier front is always fitter, and within a front the less crowded member is
+/// fitter. Members of the first of n fronts score in [n, n + 0.5], the last front in [1, 1.5].
+pub fn pareto_fitness(scores: &[Vec<f32>]) -> Vec<f32> {
+    let fronts = non_dominated_sort(scores);
+    let mut fitness = vec![0.0; scores.len()];
+    for (rank, front) in fronts.iter().enumerate() {
+        let base = (fronts.len() - rank) as f32;
+        for (i, distance) in front.iter().zip(crowding_distance(scores, front)) {
+            let crowding = if distance.is_infinite() { 1.0 } else { distance / (1.0 + distance) };
+            fitness[*i] = base + 0.5 * crowding;
+        }
+    }
+    fitness
+}
+
+
+
+/// Das and Dennis's evenly spaced reference points on the unit simplex - every point whose
+/// coordinates are multiples of 1 / divisions and sum to 1. There are (objectives + divisions - 1)
+/// choose divisions of them, so keep divisions small as the number of objectives grows.
+pub fn reference_points(objectives: usize, divisions: usize) -> Vec<Vec<f32>> {
+    let mut points = Vec::<Vec<usize>>::new();
+    if objectives == 0 {
+        return Vec::new();
+    }
+    let mut point = vec![0; objectives];
+    lattice(&mut point, 0, divisions, &mut points);
+    points.into_iter()
+        .map(|point| point.iter().map(|steps| *steps as f32 / divisions.max(1) as f32).collect())
+        .collect()
+}
+
+
+
+/// every way to split the left over steps between the objectives from index on
+fn lattice(point: &mut Vec<usize>, index: usize, left: usize, points: &mut Vec<Vec<usize>>) {
+    if index == point.len() - 1 {
+        point[index] = left;
+        points.push(point.clone());
+        return;
+    }
+    for steps in 0..=left {
+        point[index] = steps;
+        lattice(point, index + 1, left - steps, points);
+    }
+}
+
+
+
+/// Two layers of reference points for many objectives, where one layer would need so many divisions to
+/// put points inside the simplex that there would be far more points than members. The inner layer is
+/// shrunk halfway towards the center of the simplex.
+pub fn layered_reference_points(objectives: usize, outer: usize, inner: usize) -> Vec<Vec<f32>> {
+    let center = 1.0 / objectives.max(1) as f32;
+    let mut points = reference_points(objectives, outer);
+    points.extend(reference_points(objectives, inner)
+        .into_iter()
+        .map(|point| point.iter().map(|value| (value + center) / 2.0).collect::<Vec<_>>()));
+    points
+}
+
+
+
+/// Translate and scale the scores so the best value on each objective is 0 and the worst is 1, which
+/// puts every objective on the same footing and makes them minimized like the reference points expect
+pub fn normalize(scores: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    let objectives = scores.first().map_or(0, |first| first.len());
+    let bounds = (0..objectives)
+        .map(|objective| scores.iter().fold((f32::MIN, f32::MAX), |(best, worst), score| (best.max(score[objective]), worst.min(score[objective]))))
+        .collect::<Vec<_>>();
+    scores.iter()
+        .map(|score| score.iter().zip(bounds.iter())
+            .map(|(value, (best, worst))| if best > worst { (best - value) / (best - worst) } else { 0.0 })
+            .collect())
+        .collect()
+}
+
+
+
+/// The closest reference line (from the origin through a reference point) to each normalized score
+/// and how far the score is from it
+pub fn associate(normalized: &[Vec<f32>], references: &[Vec<f32>]) -> Vec<(usize, f32)> {
+    normalized.iter()
+        .map(|point| {
+            references.iter()
+                .enumerate()
+                .map(|(index, reference)| (index, perpendicular_distance(point, reference)))
+                .fold((0, f32::INFINITY), |closest, next| if next.1 < closest.1 { next } else { closest })
+        })
+        .collect()
+}
+
+
+
+/// the distance from a point to the line from the origin through direction
+fn perpendicular_distance(point: &[f32], direction: &[f32]) -> f32 {
+    let length = direction.iter().map(|d| d * d).sum::<f32>();
+    if length == 0.0 {
+        return point.iter().map(|p| p * p).sum::<f32>().sqrt();
+    }
+    let along = point.iter().zip(direction.iter()).map(|(p, d)| p * d).sum::<f32>() / length;
+    point.iter().zip(direction.iter()).map(|(p, d)| (p - along * d).powi(2)).sum::<f32>().sqrt()
+}
+
+
+
+/// A fitness for each member which orders them the way NSGA-III's niching does - a member in an
+/// earlier front is always fitter, and within a front members are taken one at a time from the
+/// reference line with the fewest members already taken (the closest member to that line first),
+/// so the fitness follows that order. Crowding distance stops telling members apart once there
+/// are more than three or so objectives since nearly everything is non-dominated, the reference
+/// lines keep the members spread out over the whole front instead. Members of the first of n
+/// fronts score in [n, n + 0.5], the last front in [1, 1.5].
+pub fn reference_fitness(scores: &[Vec<f32>], references: &[Vec<f32>]) -> Vec<f32> {
+    if references.is_empty() {
+        return pareto_fitness(scores);
+    }
+    let fronts = non_dominated_sort(scores);
+    let associations = associate(&normalize(scores), references);
+    let mut niches = vec![0; references.len()];
+    let mut fitness = vec![0.0; scores.len()];
+    for (rank, front) in fronts.iter().enumerate() {
+        let base = (fronts.len() - rank) as f32;
+        let mut left = front.clone();
+        let mut taken = 0;
+        while !left.is_empty() {
+            // the emptiest niche which still has members of this front, then its closest member
+            let niche = left.iter()
+                .map(|i| associations[*i].0)
+                .min_by_key(|niche| (niches[*niche], *niche))
+                .unwrap_or(0);
+            let position = left.iter()
+                .enumerate()
+                .filter(|(_, i)| associations[**i].0 == niche)
+                .min_by(|(_, a), (_, b)| associations[**a].1.partial_cmp(&associations[**b].1).unwrap_or(Ordering::Equal))
+                .map_or(0, |(position, _)| position);
+            let member = left.swap_remove(position);
+            fitness[member] = base + 0.5 * (1.0 - taken as f32 / front.len() as f32);
+            niches[niche] += 1;
+            taken += 1;
+        }
+    }
+    fitness
+}
+
+
+/// Preferences on the objectives of a problem, so the population is steered towards the part of the
+/// front that matters in practice instead of spreading out over the whole thing.
+///
+/// * goals - a score which is good enough on an objective, scores past it are all as good as the goal
+///   so members stop trading other objectives away to improve it further
+/// * constraints - a score an objective has to reach for a member to be feasible, every feasible
+///   member is fitter than every infeasible one, and infeasible members are fitter the less they miss by
+/// * priority - objectives in order of importance, members are ordered by the first of them, then
+///   the second where they tie, and so on (lexicographic ordering). Scores within tolerance of each other
+///   (on the same multiple of the tolerance) tie, then the pareto ranking breaks the remaining ties
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Preferences {
+    pub goals: Vec<(usize, f32)>,
+    pub constraints: Vec<(usize, f32)>,
+    pub priority: Vec<usize>,
+    pub tolerance: f32
+}
+
+
+
+impl Preferences {
+
+    pub fn new() -> Self {
+        Preferences::default()
+    }
+
+
+    /// scores past value on the objective are no better than value
+    pub fn goal(mut self, objective: usize, value: f32) -> Self {
+        self.goals.push((objective, value));
+        self
+    }
+
+
+    /// the objective must score at least bound for a member to be feasible
+    pub fn constraint(mut self, objective: usize, bound: f32) -> Self {
+        self.constraints.push((objective, bound));
+        self
+    }
+
+
+    /// order members by these objectives first, most important first
+    pub fn priority(mut self, objectives: Vec<usize>) -> Self {
+        self.priority = objectives;
+        self
+    }
+
+
+    /// how close two scores on a prioritized objective are to count as a tie
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+
+    /// how far the scores miss the constraints by in total, 0 for a feasible member
+    pub fn violation(&self, scores: &[f32]) -> f32 {
+        self.constraints.iter()
+            .map(|(objective, bound)| (bound - scores[*objective]).max(0.0))
+            .sum()
+    }
+
+
+    /// true if the scores meet every constraint
+    pub fn feasible(&self, scores: &[f32]) -> bool {
+        self.violation(scores) == 0.0
+    }
+
+
+    /// the scores with every objective that has a goal capped at it
+    pub fn attained(&self, scores: &[f32]) -> Vec<f32> {
+        let mut attained = scores.to_vec();
+        for (objective, goal) in self.goals.iter() {
+            attained[*objective] = attained[*objective].min(*goal);
+        }
+        attained
+    }
+
+
+    /// the scores on the prioritized objectives, snapped down to a multiple of the tolerance
+    fn tiers(&self, scores: &[f32]) -> Vec<f32> {
+        self.priority.iter()
+            .map(|objective| match self.tolerance > 0.0 {
+                true => (scores[*objective] / self.tolerance).floor(),
+                false => scores[*objective]
+            })
+            .collect()
+    }
+}
+
+
+
+/// A fitness for each member which follows the preferences first and the pareto ranking (see
+/// pareto_fitness and reference_fitness) of the goal capped scores second. Members are put into groups
+/// by how much they violate the constraints and then by their tier on each prioritized objective, every
+/// member of a better group is fitter than every member of a worse one.
+pub fn preferred_fitness(scores: &[Vec<f32>], preferences: &Preferences, references: Option<&[Vec<f32>]>) -> Vec<f32> {
+    let attained = scores.iter().map(|score| preferences.attained(score)).collect::<Vec<_>>();
+    let ranked = match references {
+        Some(references) => reference_fitness(&attained, references),
+        None => pareto_fitness(&attained)
+    };
+    // smaller violations first, then higher tiers, each distinct key is a group
+    let keys = attained.iter()
+        .map(|score| (preferences.violation(score), preferences.tiers(score)))
+        .collect::<Vec<_>>();
+    let better = |a: &(f32, Vec<f32>), b: &(f32, Vec<f32>)| {
+        a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal).then_with(|| {
+            b.1.iter().zip(a.1.iter())
+                .map(|(x, y)| x.partial_cmp(y).unwrap_or(Ordering::Equal))
+                .find(|order| *order != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        })
+    };
+    let mut groups = keys.clone();
+    groups.sort_by(better);
+    groups.dedup_by(|a, b| better(a, b) == Ordering::Equal);
+    let span = ranked.iter().fold(0.0, |max: f32, fitness| max.max(*fitness)) + 1.0;
+    keys.iter()
+        .zip(ranked)
+        .map(|(key, fitness)| {
+            let group = groups.iter().position(|group| better(group, key) == Ordering::Equal).unwrap_or(0);
+            (groups.len() - 1 - group) as f32 * span + fitness
+        })
+        .collect()
+}
+
+
+
+/// The volume of objective space dominated by the front and bounded by the reference point, which
+/// should be worse than every member on every objective. A front closer to the true pareto front
+/// and more spread out along it dominates more, so this grows as a run converges. Members which
+/// are no better than the reference on some objective add nothing.
+pub fn hypervolume(front: &[Vec<f32>], reference: &[f32]) -> f32 {
+    let points = front.iter()
+        .filter(|point| point.iter().zip(reference.iter()).all(|(x, r)| x > r))
+        .cloned()
+        .collect::<Vec<_>>();
+    slice_volume(points, reference)
+}
+
+
+
+/// hypervolume by slicing - sweep the last objective from best to worst, each slab between two
+/// members is as thick as the gap between them and as big as the volume of the members above it
+/// in the remaining objectives
+fn slice_volume(mut points: Vec<Vec<f32>>, reference: &[f32]) -> f32 {
+    let last = match reference.len() {
+        0 => return 0.0,
+        len => len - 1
+    };
+    if last == 0 {
+        return points.iter().fold(0.0, |best: f32, point| best.max(point[0] - reference[0]));
+    }
+    points.sort_by(|a, b| b[last].partial_cmp(&a[last]).unwrap_or(Ordering::Equal));
+    let mut volume = 0.0;
+    for index in 0..points.len() {
+        let floor = points.get(index + 1).map_or(reference[last], |next| next[last]);
+        let height = points[index][last] - floor;
+        if height > 0.0 {
+            let above = points[..=index].iter().map(|point| point[..last].to_vec()).collect();
+            volume += height * slice_volume(above, &reference[..last]);
+        }
+    }
+    volume
+}
+
+
+
+/// Inverted generational distance, the mean distance from each point of a reference front (ie:
+/// samples of the true pareto front) to the closest member of the front. Zero when the front covers
+/// the reference front, infinite when the front is empty.
+pub fn igd(front: &[Vec<f32>], reference: &[Vec<f32>]) -> f32 {
+    if front.is_empty() {
+        return f32::INFINITY;
+    }
+    let total = reference.iter()
+        .map(|target| {
+            front.iter()
+                .map(|point| point.iter().zip(target.iter()).map(|(x, t)| (x - t).powi(2)).sum::<f32>().sqrt())
+                .fold(f32::INFINITY, f32::min)
+        })
+        .sum::<f32>();
+    total / reference.len().max(1) as f32
+}
+
+
+
+/// The indicators of the first pareto front of one generation. The hypervolume and IGD are only
+/// found if the population was given the reference they need.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParetoStats {
+    pub generation: usize,
+    pub size: usize,
+    pub hypervolume: Option<f32>,
+    pub igd: Option<f32>
+}
+
+
+
+impl ParetoStats {
+
+    /// the indicators of a front against the references which are given
+    pub fn new(generation: usize, front: &[Vec<f32>], reference: Option<&[f32]>, reference_front: Option<&[Vec<f32>]>) -> Self {
+        ParetoStats {
+            generation,
+            size: front.len(),
+            hypervolume: reference.map(|reference| hypervolume(front, reference)),
+            igd: reference_front.map(|reference_front| igd(front, reference_front))
+        }
+    }
+}
+
+
+
+impl fmt::Display for ParetoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: Option<f32>| value.map_or("-".to_string(), |value| format!("{:.6}", value));
+        write!(f, "generation={}, front={}, hypervolume={}, igd={}",
+            self.generation, self.size, show(self.hypervolume), show(self.igd))
+    }
+}
+
+
+
+/// A member of an exported pareto front and its score on each objective
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParetoMember<T> {
+    pub objectives: Vec<f32>,
+    pub genome: T
+}
+
+
+
+/// The first pareto front of a generation exported to json so it can be analyzed (or
+/// picked from) outside of the run that found it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParetoFront<T> {
+    pub generation: usize,
+    pub members: Vec<ParetoMember<T>>
+}
+
+
+
+impl<T> ParetoFront<T> {
+
+    pub fn new(generation: usize, front: &[(Vec<f32>, T)]) -> Self
+        where T: Clone
+    {
+        ParetoFront {
+            generation,
+            members: front.iter()
+                .map(|(objectives, genome)| ParetoMember { objectives: objectives.clone(), genome: genome.clone() })
+                .collect()
+        }
+    }
+
+
+    /// the score of every member on each objective
+    pub fn objectives(&self) -> Vec<Vec<f32>> {
+        self.members.iter().map(|member| member.objectives.clone()).collect()
+    }
+
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>>
+        where T: serde::Serialize
+    {
+        serde_json::to_writer_pretty(&File::create(path)?, self)?;
+        Ok(())
+    }
+
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>>
+        where T: serde::de::DeserializeOwned
+    {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+}
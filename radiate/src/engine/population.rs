@@ -1,19 +1,41 @@
 
 extern crate rayon;
+extern crate serde_json;
 
+use std::fs;
+use std::error::Error;
 use std::sync::{Arc, RwLock};
 use std::marker::Sync;
 use std::fmt::Debug;
 use std::cmp::PartialEq;
+use std::time::{Duration, Instant};
 use rayon::prelude::*;
+use uuid::Uuid;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use super::{
-    generation::{Generation, Container},
+    generation::{Generation, Container, CrossoverStats},
+    niche::{Niche, NicheStats},
+    pareto::{ParetoStats, ParetoFront},
+    interactive::{Judgement, Surrogate},
     genome::Genome,
     problem::Problem,
     environment::Envionment,
     genocide::Genocide,
-    survival::{SurvivalCriteria, ParentalCriteria}
+    stagnation::{StagnationPolicy, RemovedSpecies, MutationBoost, SpeciesBoost},
+    survival::{SurvivalCriteria, ParentalCriteria, Elitism},
+    selection::{Selection, RouletteWheel, BreederPool},
+    stopping::{StoppingCriteria, StopReason, Progress},
+    history::{RunHistory, GenerationRecord},
+    speciation::SpeciationHistory,
+    halloffame::HallOfFame,
+    drift::NeutralDrift,
+    learning::Learning,
+    approximate::ApproximateScoring,
+    compress
 };
+use crate::config::{self, ConfigError};
+use crate::rng;
 
 
 
@@ -42,6 +64,240 @@ pub struct Config {
 }
 
 
+/// Everything about a population that isn't the genome, environment, or problem,
+/// bundled so it can be kept in a config file instead of compiled code. The speciation
+/// config is required, the rest falls back to the same defaults as Population::new
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PopulationConfig {
+    #[serde(default = "PopulationConfig::default_size")]
+    pub size: i32,
+    #[serde(default)]
+    pub dynamic_distance: bool,
+    #[serde(default = "PopulationConfig::default_distance_step")]
+    pub distance_step: f32,
+    pub config: Config,
+    #[serde(default)]
+    pub stagnation: usize,
+    #[serde(default)]
+    pub genocide: Vec<Genocide>,
+    #[serde(default)]
+    pub species_stagnation: Option<StagnationPolicy>,
+    #[serde(default)]
+    pub mutation_boost: Option<MutationBoost>,
+    #[serde(default = "PopulationConfig::default_survivor_criteria")]
+    pub survivor_criteria: SurvivalCriteria,
+    #[serde(default = "PopulationConfig::default_parental_criteria")]
+    pub parental_criteria: ParentalCriteria,
+    #[serde(default = "PopulationConfig::default_selection")]
+    pub selection: Box<dyn Selection>,
+    #[serde(default)]
+    pub breeders: BreederPool,
+    #[serde(default)]
+    pub elitism: Elitism,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default = "PopulationConfig::default_parallel_eval")]
+    pub parallel_eval: bool,
+    #[serde(default)]
+    pub stopping: StoppingCriteria
+}
+
+
+/// Everything needed to pick a run back up between two generations - the members about
+/// to be evaluated, the species they will be sorted into, and the population's own
+/// bookkeeping. The problem isn't included, it is given to the population being resumed.
+/// If the population was suspended after its members were evaluated (see Population::evaluate)
+/// scores holds what each member scored, so the resumed run goes straight on to selection,
+/// and over_budget the members its time budget left unscored (see Population::time_budget).
+/// A seeded population saves its seed and how far along its random stream it is, so a resumed
+/// seeded run breeds the exact same children the run would have if it had never stopped.
+/// The stopping criteria are saved with how far the run is toward them, so patience and
+/// the time limit carry on counting from where they were rather than starting over.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "T: Serialize, E: Serialize", deserialize = "T: DeserializeOwned, E: DeserializeOwned"))]
+pub struct Checkpoint<T, E> {
+    pub generation: usize,
+    pub size: i32,
+    pub dynamic_distance: bool,
+    #[serde(default = "PopulationConfig::default_distance_step")]
+    pub distance_step: f32,
+    pub config: Config,
+    pub environment: E,
+    #[serde(with = "super::compress::members")]
+    pub members: Vec<T>,
+    pub species: Vec<SpeciesCheckpoint<T>>,
+    pub survivor_criteria: SurvivalCriteria,
+    pub parental_criteria: ParentalCriteria,
+    #[serde(default = "PopulationConfig::default_selection")]
+    pub selection: Box<dyn Selection>,
+    #[serde(default)]
+    pub breeders: BreederPool,
+    #[serde(default)]
+    pub elitism: Elitism,
+    #[serde(default)]
+    pub species_stagnation: Option<StagnationPolicy>,
+    #[serde(default)]
+    pub mutation_boost: Option<MutationBoost>,
+    stagnation: Stagnant,
+    #[serde(default)]
+    pub scores: Option<Vec<MemberScore>>,
+    #[serde(default)]
+    pub history: RunHistory,
+    #[serde(default)]
+    pub pareto_history: Vec<ParetoStats>,
+    #[serde(default)]
+    pub speciation: SpeciationHistory,
+    #[serde(default)]
+    pub hall_of_fame: Option<HallOfFame<T>>,
+    #[serde(default)]
+    pub neutral_drift: Option<NeutralDrift<T>>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub draws: u64,
+    #[serde(default)]
+    pub stopping: StoppingCriteria,
+    #[serde(default)]
+    progress: Progress,
+    #[serde(default)]
+    pub over_budget: Vec<T>
+}
+
+
+/// What a member scored when its generation was evaluated, see Checkpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberScore {
+    pub fitness: f32,
+    pub objectives: Option<Vec<f32>>
+}
+
+
+/// What one generation of a population came to, see Population::step. generation is the
+/// number of the generation which was evaluated, top its fittest member and fitness that
+/// member's score. species and distance are what its members were speciated into and by,
+/// niches what each of those species came to, stagnation how many generations in a row
+/// the top score hasn't moved, removed_species the species the population's StagnationPolicy
+/// took out of the generation before it was bred from, boosted_species the species whose
+/// offspring were bred with boosted mutation rates by the population's MutationBoost and
+/// write_failures whatever the population couldn't save while running it
+#[derive(Debug, Clone)]
+pub struct GenerationStats<T> {
+    pub generation: usize,
+    pub fitness: f32,
+    pub top: T,
+    pub mean_fitness: f32,
+    pub species: usize,
+    pub niches: Vec<NicheStats>,
+    pub distance: f32,
+    pub stagnation: usize,
+    pub removed_species: Vec<RemovedSpecies>,
+    pub boosted_species: Vec<SpeciesBoost>,
+    pub over_budget: usize,
+    pub write_failures: Vec<WriteFailure>,
+    pub elapsed: Duration
+}
+
+
+/// A file the population couldn't write while running, a checkpoint (see Population::checkpoint)
+/// or a generation's row of the history file (see Population::history_file).
+/// generation is the one being saved and error what went wrong. A failure doesn't end the run
+/// unless the population was told to stop on one, see Population::stop_on_write_failure
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteFailure {
+    pub generation: usize,
+    pub path: String,
+    pub error: String
+}
+
+
+/// What a run came to once it ended, see Population::run_result. top is the fittest member of
+/// the last generation and fitness its score, environment what it ended with, generations how
+/// many the population has run in all, stop_reason why the run ended if it wasn't the runner
+/// and history the record of every generation run
+#[derive(Debug, Clone)]
+pub struct RunResult<T, E> {
+    pub top: T,
+    pub fitness: f32,
+    pub environment: E,
+    pub generations: usize,
+    pub stop_reason: Option<StopReason>,
+    pub history: RunHistory
+}
+
+
+/// What happens to the members of a generation which couldn't be scored within the population's
+/// time budget (see Population::time_budget). Drop throws them away, Defer carries them into the
+/// next generation ahead of its offspring so they're the first to be scored
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Overrun {
+    Drop,
+    Defer
+}
+
+
+/// A species between generations only needs its mascot, identity and how long it
+/// has gone without improving, it has no members until the next generation is speciated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeciesCheckpoint<T> {
+    pub niche_id: Uuid,
+    pub age: i32,
+    #[serde(default)]
+    pub best_fitness: Option<f32>,
+    #[serde(default)]
+    pub last_improvement: i32,
+    pub mascot: T
+}
+
+
+/// What a hook added with Population::on_generation is given after each step. stats is what
+/// the generation came to and history every generation run so far including it, config and
+/// environment are what the next generation will be bred and speciated with so changing them
+/// tunes the rest of the run, and stop ends it
+pub struct GenerationContext<'a, T, E> {
+    pub stats: &'a GenerationStats<T>,
+    pub history: &'a RunHistory,
+    pub config: &'a mut Config,
+    pub environment: &'a mut E,
+    stop: bool
+}
+
+
+impl<'a, T, E> GenerationContext<'a, T, E> {
+    /// end the run after this generation, see Population::is_stopped
+    pub fn stop(&mut self) {
+        self.stop = true;
+    }
+
+    /// whether the population's stopping criteria, this or an earlier hook for the
+    /// same generation has stopped the run
+    pub fn is_stopping(&self) -> bool {
+        self.stop
+    }
+}
+
+
+/// called after every step, see Population::on_generation
+type Hook<T, E> = Box<dyn FnMut(&mut GenerationContext<T, E>) + Send + Sync>;
+
+
+/// saves a population to a path, see Population::save_checkpoint
+type SaveFn<T, E, P> = fn(&Population<T, E, P>, &str) -> Result<(), Box<dyn Error>>;
+
+
+/// where and how often the population saves itself, save is the population's
+/// own save_checkpoint which needs the genome and environment to be serializable
+struct Checkpointer<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync,
+        E: Envionment + Sized + Send + Sync,
+        P: Problem<T>
+{
+    path: String,
+    every: usize,
+    save: SaveFn<T, E, P>
+}
+
+
 /// Population is what facilitates the evolution from a 5000 ft view
 /// keeping track of what the generation is doing, marking statistics
 /// down from each one, and holding resource sensitive things like
@@ -55,14 +311,48 @@ pub struct Population<T, E, P>
 {
     size: i32,
     dynamic_distance: bool,
+    distance_step: f32,
     debug_progress: bool,
+    parallel_eval: bool,
+    evaluated: bool,
+    time_budget: Option<(Duration, Overrun)>,
+    over_budget: Vec<Container<T, E>>,
     config: Config,
     curr_gen: Generation<T, E>,
     stagnation: Stagnant,
     solve: Arc<RwLock<P>>,
     environment: Arc<RwLock<E>>,
     survivor_criteria: SurvivalCriteria,
-    parental_criteria: ParentalCriteria
+    parental_criteria: ParentalCriteria,
+    selection: Box<dyn Selection>,
+    species_stagnation: Option<StagnationPolicy>,
+    removed_species: Vec<RemovedSpecies>,
+    mutation_boost: Option<MutationBoost>,
+    boosted_species: Vec<SpeciesBoost>,
+    breeders: BreederPool,
+    elitism: Elitism,
+    generation: usize,
+    checkpointer: Option<Checkpointer<T, E, P>>,
+    write_failures: Vec<WriteFailure>,
+    stop_on_write_failure: bool,
+    hooks: Vec<Hook<T, E>>,
+    stopping: StoppingCriteria,
+    progress: Progress,
+    stopped: Option<StopReason>,
+    niches: Vec<NicheStats>,
+    history: RunHistory,
+    history_file: Option<String>,
+    speciation: SpeciationHistory,
+    hall_of_fame: Option<HallOfFame<T>>,
+    neutral_drift: Option<NeutralDrift<T>>,
+    learning: Option<Learning<T>>,
+    approximate_scoring: Option<ApproximateScoring<T>>,
+    seed: Option<u64>,
+    draws: u64,
+    pareto_front: Vec<(Vec<f32>, T)>,
+    hypervolume_reference: Option<Vec<f32>>,
+    igd_reference: Option<Vec<Vec<f32>>>,
+    pareto_history: Vec<ParetoStats>
 }
 
 
@@ -83,9 +373,19 @@ impl<T, E, P> Population<T, E, P>
             size: 100,
             // determin if the species should be aiming for a specific number of species by adjusting the distance threshold
             dynamic_distance: false,
+            // how far the distance moves each generation the number of species is off the target
+            distance_step: 0.1,
             // debug_progress is only used to print out some information from each generation
             // to the console during training to get a glimpse into what is going on
             debug_progress: false,
+            // score the members of each generation across rayon's thread pool
+            parallel_eval: true,
+            // whether the current generation has been scored but not yet selected from
+            evaluated: false,
+            // how long each generation has to score its members and what to do with the rest, unlimited if not set
+            time_budget: None,
+            // the members of the current generation which weren't scored in time
+            over_budget: Vec::new(),
             // create a new config to help the speciation of the population
             config: Config::new(),
             // create a new empty generation to be passed down through the population 
@@ -101,7 +401,133 @@ impl<T, E, P> Population<T, E, P>
             // determine which genomes will live on and pass down to the next generation
             survivor_criteria: SurvivalCriteria::Fittest,
             // determine how to pick parents to reproduce
-            parental_criteria: ParentalCriteria::BiasedRandom
+            parental_criteria: ParentalCriteria::BiasedRandom,
+            // determine which members of a species become parents once it's picked to breed
+            selection: Box::new(RouletteWheel),
+            // when species which have stopped improving are removed and the ones removed from the last generation
+            species_stagnation: None,
+            removed_species: Vec::new(),
+            mutation_boost: None,
+            boosted_species: Vec::new(),
+            // which members of a species can be picked as parents, all of them by default
+            breeders: BreederPool::default(),
+            // which members are carried over unchanged on top of the survivors, none by default
+            elitism: Elitism::default(),
+            // number of generations which have been run, carried over when resuming
+            generation: 0,
+            // periodically save the population if set
+            checkpointer: None,
+            // what couldn't be saved so far, and whether that should end the run
+            write_failures: Vec::new(),
+            stop_on_write_failure: false,
+            // called with what each generation came to
+            hooks: Vec::new(),
+            // when the run should end on its own, what it has come to so far, and why it ended if it has
+            stopping: StoppingCriteria::new(),
+            progress: Progress::default(),
+            stopped: None,
+            // what the species of the last generation came to
+            niches: Vec::new(),
+            // what every generation run so far came to
+            history: RunHistory::new(),
+            // the csv each generation's record is added to as it's run, if any
+            history_file: None,
+            // how the species of every generation run so far came and went
+            speciation: SpeciationHistory::new(),
+            // the best members ever seen if they're being kept
+            hall_of_fame: None,
+            // structurally different members which score the same as the best, carried back in turn if set
+            neutral_drift: None,
+            // how members learn before they're scored, if they do
+            learning: None,
+            // how members are scored cheaply before the ones near a cutoff are scored again, if they are
+            approximate_scoring: None,
+            // the population's own seed if it was seeded, and how many keys have been drawn from it
+            seed: None,
+            draws: 0,
+            // the best members of the last generation of a multi-objective problem
+            pareto_front: Vec::new(),
+            // the points the pareto front of each generation is measured against, if any
+            hypervolume_reference: None,
+            igd_reference: None,
+            // the indicators of the pareto front of each generation
+            pareto_history: Vec::new()
+        }
+    }
+
+    /// Get the number of generations the population has been through
+    pub fn get_generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Get the distance threshold the last generation was speciated with, when the distance is
+    /// dynamic this is where it has been adjusted to
+    pub fn get_distance(&self) -> f32 {
+        self.config.distance
+    }
+
+    /// Get the number of species the last generation was split into
+    pub fn get_species_count(&self) -> usize {
+        self.curr_gen.species.len()
+    }
+
+    /// Get how well the parents lined up in each mating which bred the current generation
+    pub fn crossover_stats(&self) -> &CrossoverStats {
+        &self.curr_gen.crossover_stats
+    }
+
+    /// Get the members of the first pareto front of the last generation trained on a multi-objective
+    /// problem along with their score on each objective. There is no single best member of these,
+    /// each is better than the rest on at least one objective. Empty for single objective problems
+    pub fn pareto_front(&self) -> &[(Vec<f32>, T)] {
+        &self.pareto_front
+    }
+
+    /// Get the best members ever seen if the population is keeping a hall of fame
+    pub fn get_hall_of_fame(&self) -> Option<&HallOfFame<T>> {
+        self.hall_of_fame.as_ref()
+    }
+
+    /// the neutral variants of the best fitness archived so far, None if the population doesn't drift
+    pub fn get_neutral_drift(&self) -> Option<&NeutralDrift<T>> {
+        self.neutral_drift.as_ref()
+    }
+
+    /// Get what every generation run so far came to, see RunHistory
+    pub fn history(&self) -> &RunHistory {
+        &self.history
+    }
+
+    /// Get how the species of every generation run so far came and went, see SpeciationHistory
+    pub fn speciation_history(&self) -> &SpeciationHistory {
+        &self.speciation
+    }
+
+    /// Get the indicators of the pareto front of every generation trained on a multi-objective problem,
+    /// the hypervolume and IGD are only found if their references were given to the population
+    pub fn pareto_history(&self) -> &[ParetoStats] {
+        &self.pareto_history
+    }
+
+    /// write the members of the last pareto front and their objective scores to a json file
+    pub fn save_pareto_front(&self, path: &str) -> Result<(), Box<dyn Error>>
+        where T: Serialize
+    {
+        let generation = self.pareto_history.last().map_or(self.generation, |stats| stats.generation);
+        ParetoFront::new(generation, &self.pareto_front).save(path)
+    }
+
+    /// Replace the last members of the current generation, which are children that haven't been
+    /// evaluated yet, with members from somewhere else (ie: another island of an Archipelago)
+    pub fn immigrate(&mut self, migrants: Vec<T>) {
+        let start = self.curr_gen.members.len().saturating_sub(migrants.len());
+        for (cont, migrant) in self.curr_gen.members[start..].iter_mut().zip(migrants) {
+            *cont = Container {
+                member: Arc::new(RwLock::new(migrant)),
+                fitness_score: 0.0,
+                objectives: None,
+                species: None
+            };
         }
     }
 
@@ -130,9 +556,209 @@ impl<T, E, P> Population<T, E, P>
             T: Genome<T, E> + Clone + Send + Sync + Debug + PartialEq,
             P: Send + Sync
     {
-        // optimize the population 
-        self.curr_gen.optimize(self.solve.clone());
-        self.end_generation()
+        self.step().map(|stats| (stats.fitness, stats.top))
+    }
+
+    /// Advance exactly one generation, the same as train, and return what it came to. Nothing
+    /// about the population blocks between steps, so the evolution can be driven one generation
+    /// at a time by whatever is scheduling it (a game loop, an actor, an async task) instead of run
+    pub fn step(&mut self) -> Option<GenerationStats<T>>
+        where 
+            T: Genome<T, E> + Clone + Send + Sync + Debug + PartialEq,
+            P: Send + Sync
+    {
+        let start = Instant::now();
+        let generation = self.generation;
+        let failed = self.write_failures.len();
+        self.progress.start();
+        let (mut record, over_budget, top) = phase!("generation", {
+            let evaluating = Instant::now();
+            self.evaluate();
+            let record = GenerationRecord::measure(generation, &self.curr_gen.members, evaluating.elapsed());
+            (record, self.over_budget.len(), self.end_generation())
+        });
+        let (fitness, top) = top?;
+        record.species = self.niches.len();
+        record.species_sizes = self.niches.iter().map(|niche| niche.members).collect();
+        record.top_complexity = T::complexity(&top);
+        record.elapsed = start.elapsed();
+        let mean_fitness = record.mean_fitness;
+        // like a checkpoint, a history which can't be written to is kept rather than ending the run
+        let failure = self.history_file.as_ref().and_then(|path| record.append_csv(path).err().map(|e| WriteFailure {
+            generation,
+            path: path.clone(),
+            error: e.to_string()
+        }));
+        if let Some(failure) = failure {
+            self.write_failed(failure);
+        }
+        self.history.push(record);
+        let stats = GenerationStats {
+            generation,
+            fitness,
+            top,
+            mean_fitness,
+            species: self.curr_gen.species.len(),
+            niches: self.niches.clone(),
+            distance: self.config.distance,
+            stagnation: self.stagnation.current_stagnation,
+            removed_species: self.removed_species.clone(),
+            boosted_species: self.boosted_species.clone(),
+            over_budget,
+            write_failures: self.write_failures[failed..].to_vec(),
+            elapsed: start.elapsed()
+        };
+        if self.stopped.is_none() {
+            self.stopped = self.stopping.check(&mut self.progress, stats.fitness);
+        }
+        self.call_hooks(&stats);
+        Some(stats)
+    }
+
+    /// give each hook what the generation came to and the chance to tune or stop the run
+    fn call_hooks(&mut self, stats: &GenerationStats<T>) {
+        if self.hooks.is_empty() {
+            return;
+        }
+        let mut environment = self.environment.write().unwrap();
+        let mut context = GenerationContext {
+            stats,
+            history: &self.history,
+            config: &mut self.config,
+            environment: &mut *environment,
+            stop: self.stopped.is_some()
+        };
+        for hook in self.hooks.iter_mut() {
+            hook(&mut context);
+        }
+        if context.stop && self.stopped.is_none() {
+            self.stopped = Some(StopReason::Hook);
+        }
+    }
+
+    /// whether the stopping criteria (see stop_when) or a hook (see GenerationContext::stop) has
+    /// ended the run, run returns once it has and anything else driving the population by step
+    /// or train should check it
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.is_some()
+    }
+
+    /// why the run ended, if it has
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.stopped
+    }
+
+    /// every file the population has failed to write so far, oldest first, see WriteFailure
+    pub fn write_failures(&self) -> &[WriteFailure] {
+        &self.write_failures
+    }
+
+    fn write_failed(&mut self, failure: WriteFailure) {
+        if self.stop_on_write_failure && self.stopped.is_none() {
+            self.stopped = Some(StopReason::WriteFailed);
+        }
+        self.write_failures.push(failure);
+    }
+
+    /// Score the members of the current generation against the problem, the first half of a step.
+    /// Stopping here leaves the population suspended between evaluation and selection, where
+    /// it can be checkpointed with its scores (see to_checkpoint) before end_generation selects
+    /// from them and breeds the next generation. Does nothing if the generation is already scored.
+    /// With a time budget the members which didn't fit in it are taken out of the generation
+    pub fn evaluate(&mut self)
+        where P: Send + Sync
+    {
+        if !self.evaluated {
+            let budget = self.time_budget.map(|(budget, _)| budget);
+            let unscored = self.seeded(|pop| {
+                let speciation = Some((pop.config.distance, Arc::clone(&pop.environment)));
+                pop.curr_gen.optimize_speciated(pop.solve.clone(), pop.parallel_eval, budget, pop.learning.as_ref(), pop.approximate_scoring.as_ref(), speciation)
+            });
+            self.over_budget = unscored;
+            self.evaluated = true;
+        }
+    }
+
+    /// whether the current generation has been scored but not yet selected from, see evaluate
+    pub fn is_evaluated(&self) -> bool {
+        self.evaluated
+    }
+
+    /// Run one generation of interactive evolution, judge is given the members of the generation
+    /// and the generation number and returns what a person thought of them, which is taken as their
+    /// fitness instead of solving the problem (see Judged). Partial rankings are fine, see Judgement.
+    /// Returns the favorite member and its fitness
+    pub fn train_interactive<F>(&mut self, judge: F) -> Option<(f32, T)>
+        where
+            F: FnOnce(&[T], usize) -> Judgement,
+            T: Genome<T, E> + Clone + Send + Sync + Debug + PartialEq,
+            P: Send + Sync
+    {
+        phase!("generation", {
+            let members = self.curr_gen.members
+                .iter()
+                .map(|cont| (*cont.member.read().unwrap()).clone())
+                .collect::<Vec<_>>();
+            let fitness = judge(&members, self.generation).fitness(members.len());
+            for (cont, fitness) in self.curr_gen.members.iter_mut().zip(fitness) {
+                cont.fitness_score = fitness;
+                cont.objectives = None;
+            }
+            self.end_generation()
+        })
+    }
+
+    /// Run one generation of interactive evolution with a surrogate filling in for the person. Only
+    /// the shown members the surrogate is least sure about (see Surrogate::most_uncertain) are given to
+    /// judge, whose judgement refers to them by their index in what it is given. Those judgements are
+    /// their fitness and are added to the surrogate, every other member's fitness is the surrogate's
+    /// prediction. features turns a member into what the surrogate compares them by (ie: the pixels
+    /// of the image a CPPN draws). Returns the favorite member and its fitness
+    pub fn train_assisted<F, J>(&mut self, surrogate: &mut Surrogate, features: F, shown: usize, judge: J) -> Option<(f32, T)>
+        where
+            F: Fn(&T) -> Vec<f32>,
+            J: FnOnce(&[T], usize) -> Judgement,
+            T: Genome<T, E> + Clone + Send + Sync + Debug + PartialEq,
+            P: Send + Sync
+    {
+        phase!("generation", {
+            let members = self.curr_gen.members
+                .iter()
+                .map(|cont| (*cont.member.read().unwrap()).clone())
+                .collect::<Vec<_>>();
+            let features = members.iter().map(features).collect::<Vec<_>>();
+            let picked = surrogate.most_uncertain(&features, shown);
+            let candidates = picked.iter().map(|index| members[*index].clone()).collect::<Vec<_>>();
+            let judged = judge(&candidates, self.generation).fitness(candidates.len());
+
+            // predict before recording so the judged members don't skew the rest of this generation
+            let mut fitness = features.iter()
+                .map(|features| surrogate.predict(features).map_or(1.0, |(fitness, _)| fitness))
+                .collect::<Vec<_>>();
+            for (index, judged) in picked.iter().zip(judged) {
+                fitness[*index] = judged;
+                surrogate.record(features[*index].clone(), judged);
+            }
+            for (cont, fitness) in self.curr_gen.members.iter_mut().zip(fitness) {
+                cont.fitness_score = fitness;
+                cont.objectives = None;
+            }
+            self.end_generation()
+        })
+    }
+
+    /// run f with the thread's stream keyed from the population's seed and the number of keys drawn
+    /// from it so far if the population was seeded, so nothing else seeding or drawing from the thread
+    /// streams changes the run, and a checkpoint only has to save the count to pick the stream back up
+    fn seeded<R, F: FnOnce(&mut Self) -> R>(&mut self, f: F) -> R {
+        match self.seed {
+            Some(seed) => {
+                let key = rng::mix(seed, self.draws);
+                self.draws += 1;
+                rng::scoped(key, || f(self))
+            },
+            None => f(self)
+        }
     }
 
     /// Handle end of generation calculations and create a new generation.
@@ -141,29 +767,118 @@ impl<T, E, P> Population<T, E, P>
         where 
             T: Genome<T, E> + Clone + Send + Sync + Debug + PartialEq,
             P: Send + Sync
+    {
+        self.seeded(|pop| pop.next_generation())
+    }
+
+    fn next_generation(&mut self) -> Option<(f32, T)>
+        where 
+            T: Genome<T, E> + Clone + Send + Sync + Debug + PartialEq,
+            P: Send + Sync
     {
         let top_member = self.curr_gen.best_member()?;
+        if let Some(hall) = self.hall_of_fame.as_mut() {
+            hall.induct(self.generation, &self.curr_gen.members, &self.environment);
+        }
+        if let Some(drift) = self.neutral_drift.as_mut() {
+            drift.record(&self.curr_gen.members, &self.environment);
+        }
+        let preferences = self.solve.read().unwrap().preferences();
+        self.pareto_front = self.curr_gen.pareto_front(preferences.as_ref());
+        if !self.pareto_front.is_empty() {
+            let scores = self.pareto_front.iter().map(|(scores, _)| scores.clone()).collect::<Vec<_>>();
+            let stats = ParetoStats::new(self.generation, &scores, self.hypervolume_reference.as_deref(), self.igd_reference.as_deref());
+            self.pareto_history.push(stats);
+        }
         // adjust the distance of the population if needed
         if self.dynamic_distance { self.adjust_distance(); }
         // speciate the generation into niches then see if the population is stagnant
         // if the population is stagnant, clean the population 
+        let before = self.curr_gen.species.iter().map(Arc::clone).collect::<Vec<_>>();
         self.curr_gen.speciate(self.config.distance, Arc::clone(&self.environment));
-        self.manage_stagnation(top_member.0);
+        self.niches = self.curr_gen.species.iter().map(|spec| spec.read().unwrap().stats()).collect();
+        self.speciation.record(self.generation, &before, &self.curr_gen.species, &self.environment);
+        self.removed_species = match self.species_stagnation {
+            Some(policy) => policy.apply(&mut self.curr_gen.species),
+            None => Vec::new()
+        };
+        self.boosted_species = match self.mutation_boost {
+            Some(boost) => boost.apply(&self.curr_gen.species),
+            None => Vec::new()
+        };
+        let purged = self.manage_stagnation(top_member.0);
         // If debug is set to true, this is the place to show it before the new generation is 
         if self.debug_progress { self.show_progress(); }
-        // create a new generation and return it
+        // create a new generation with how the population picks survivors and parents and return it
+        self.curr_gen.survival_criteria = self.survivor_criteria.clone();
+        self.curr_gen.parental_criteria = self.parental_criteria.clone();
+        self.curr_gen.selection = self.selection.clone();
+        self.curr_gen.breeders = self.breeders;
+        self.curr_gen.elitism = self.elitism;
+        self.curr_gen.mutation_boosts = self.boosted_species.clone();
+        self.environment.write().unwrap().advance(self.generation);
         self.curr_gen = self.curr_gen.create_next_generation(self.size, self.config.clone(), Arc::clone(&self.environment))?;
+        // the next neutral variants take the place of the last offspring and are kept as elites
+        if let Some(drift) = self.neutral_drift.as_mut() {
+            let (size, elites) = (self.curr_gen.members.len(), self.curr_gen.elites);
+            let variants = drift.next_variants().into_iter().take(size - elites).collect::<Vec<_>>();
+            self.curr_gen.elites += variants.len();
+            self.curr_gen.members.splice(elites..elites, variants.into_iter().map(|variant| Container {
+                member: Arc::new(RwLock::new(variant)),
+                fitness_score: 0.0,
+                objectives: None,
+                species: None
+            }));
+            self.curr_gen.members.truncate(size);
+        }
+        // deferred members take the place of the last offspring but are scored first after the elites
+        let over_budget = std::mem::take(&mut self.over_budget);
+        let elites = self.curr_gen.elites;
+        if let Some((_, Overrun::Defer)) = self.time_budget {
+            let size = self.curr_gen.members.len();
+            let over_budget = over_budget.into_iter().take(size - elites);
+            self.curr_gen.members.splice(elites..elites, over_budget.into_iter().map(|cont| Container { species: None, ..cont }));
+            self.curr_gen.members.truncate(size);
+        }
+        // the best members ever seen take the place of the last offspring after a purge
+        if let Some(hall) = self.hall_of_fame.as_ref().filter(|_| purged) {
+            let size = self.curr_gen.members.len();
+            let returning = hall.inductees().iter().take(hall.reinject.min(size - elites)).collect::<Vec<_>>();
+            self.curr_gen.members.truncate(size - returning.len());
+            self.curr_gen.members.extend(returning.into_iter().map(|inductee| Container {
+                member: Arc::new(RwLock::new(inductee.member.clone())),
+                fitness_score: 0.0,
+                objectives: None,
+                species: None
+            }));
+        }
+        self.generation += 1;
+        self.evaluated = false;
+        // a failed checkpoint is kept rather than ending the run, the next one might succeed
+        let failure = self.checkpointer.as_ref()
+            .filter(|checkpointer| self.generation.is_multiple_of(checkpointer.every))
+            .and_then(|checkpointer| (checkpointer.save)(self, &checkpointer.path).err().map(|e| WriteFailure {
+                generation: self.generation,
+                path: checkpointer.path.clone(),
+                error: e.to_string()
+            }));
+        if let Some(failure) = failure {
+            self.write_failed(failure);
+        }
         // return the top member score and the member
         Some((top_member.0, (*top_member.1).clone()))
     }
 
     /// Check to see if the population is stagnant or not, if it is,
-    /// then go ahead and clean the population 
-    fn manage_stagnation(&mut self, curr_top_score: f32) {
-        if self.stagnation.target_stagnation == self.stagnation.current_stagnation {
-            for cleaner in self.stagnation.cleaners.iter() {
-                cleaner.kill(&mut self.curr_gen);
-            }
+    /// then go ahead and clean the population, returns whether it was cleaned
+    fn manage_stagnation(&mut self, curr_top_score: f32) -> bool {
+        let stagnant = self.stagnation.target_stagnation == self.stagnation.current_stagnation;
+        if stagnant {
+            phase!("genocide", {
+                for cleaner in self.stagnation.cleaners.iter() {
+                    cleaner.kill(&mut self.curr_gen);
+                }
+            });
             self.stagnation.current_stagnation = 0;
         } else if curr_top_score == self.stagnation.previous_top_score {
             self.stagnation.current_stagnation += 1;
@@ -171,14 +886,16 @@ impl<T, E, P> Population<T, E, P>
             self.stagnation.current_stagnation = 0;
         }
         self.stagnation.previous_top_score = curr_top_score;
+        stagnant && !self.stagnation.cleaners.is_empty()
     }
 
-    /// dynamically adjust the distance of a population
+    /// dynamically adjust the distance of a population, too many species means members 
+    /// need to be further apart to be split up and too few means they need to be closer
     fn adjust_distance(&mut self) {
         if self.curr_gen.species.len() < self.config.species_target {
-            self.config.distance -= 0.1;
+            self.config.distance -= self.distance_step;
         } else if self.curr_gen.species.len() > self.config.species_target {
-            self.config.distance += 0.1;
+            self.config.distance += self.distance_step;
         }
         if self.config.distance < 0.2 {
             self.config.distance = 0.1;
@@ -189,6 +906,7 @@ impl<T, E, P> Population<T, E, P>
     /// are a borrowed member which is the top member of the current generation, 
     /// the fitness of that member, and the current number of generations.
     /// This function will continue until this function returns a true value 
+    /// or a hook stops the run (see on_generation)
     pub fn run<F>(&mut self, runner: F) -> Result<(T, E), &'static str>
         where 
             F: Fn(&T, f32, i32) -> bool + Sized,
@@ -196,15 +914,32 @@ impl<T, E, P> Population<T, E, P>
             P: Send + Sync,
             E: Clone
     {
-        let mut index = 0;
+        self.run_result(runner).map(|result| (result.top, result.environment))
+    }
+
+    /// Run the population the same as run, returning everything the run came to
+    /// along with its top member, see RunResult
+    pub fn run_result<F>(&mut self, runner: F) -> Result<RunResult<T, E>, &'static str>
+        where 
+            F: Fn(&T, f32, i32) -> bool + Sized,
+            T: Genome<T, E> + Clone + Send + Sync + Debug + PartialEq,
+            P: Send + Sync,
+            E: Clone
+    {
+        let mut index = self.generation as i32;
         loop {
             match self.train() {
                 Some(result) => {
                     let (fit, top) = result;
-                    if runner(&top, fit, index) {
-                        let solution = top.clone();
-                        let env = (*self.environment.read().unwrap()).clone();
-                        return Ok((solution, env));
+                    if runner(&top, fit, index) || self.stopped.is_some() {
+                        return Ok(RunResult {
+                            top,
+                            fitness: fit,
+                            environment: (*self.environment.read().unwrap()).clone(),
+                            generations: self.generation,
+                            stop_reason: self.stopped,
+                            history: self.history.clone()
+                        });
                     }
                     index += 1;
                 },
@@ -217,9 +952,17 @@ impl<T, E, P> Population<T, E, P>
     /// the training to the screen during optimization.
     fn show_progress(&self) {
         println!("\n");
+        println!("Species: {} target( {} ) distance( {:.3} )", self.curr_gen.species.len(), self.config.species_target, self.config.distance);
         for i in self.curr_gen.species.iter() {
             i.read().unwrap().display_info();
         }
+        if let Some(ratio) = self.curr_gen.crossover_stats.mean_matched_ratio() {
+            let total = self.curr_gen.crossover_stats.total();
+            println!("Crossover: matched( {} ) disjoint( {} ) excess( {} ) mean matched ratio( {:.3} )", total.matched, total.disjoint, total.excess, ratio);
+        }
+        if let Some(stats) = self.pareto_history.last().filter(|stats| stats.generation == self.generation) {
+            println!("Pareto: {}", stats);
+        }
     }
     
     /////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -250,22 +993,31 @@ impl<T, E, P> Population<T, E, P>
     pub fn populate_base(mut self) -> Self 
         where P: Send + Sync
     {
-        self.curr_gen = Generation {
-            members: (0..self.size)
-                .into_par_iter()
-                .map(|_| {
-                    let mut lock_set = self.environment.write().unwrap();
-                    Container {
-                        member: Arc::new(RwLock::new(T::base(&mut lock_set))),
-                        fitness_score: 0.0,
-                        species: None
-                    }    
-                })
-                .collect(),
-            species: Vec::new(),
-            survival_criteria: SurvivalCriteria::Fittest,
-            parental_criteria: ParentalCriteria::BiasedRandom
-        };
+        self.seeded(|pop| {
+            pop.curr_gen = Generation {
+                members: rng::keys(pop.size.max(0) as usize)
+                    .into_par_iter()
+                    .map(|key| {
+                        let mut lock_set = pop.environment.write().unwrap();
+                        Container {
+                            member: Arc::new(RwLock::new(rng::scoped(key, || T::base(&mut lock_set)))),
+                            fitness_score: 0.0,
+                            objectives: None,
+                            species: None
+                        }    
+                    })
+                    .collect(),
+                species: Vec::new(),
+                survival_criteria: SurvivalCriteria::Fittest,
+                parental_criteria: ParentalCriteria::BiasedRandom,
+                selection: Box::new(RouletteWheel),
+                breeders: BreederPool::default(),
+                elitism: Elitism::default(),
+                elites: 0,
+                mutation_boosts: Vec::new(),
+                crossover_stats: CrossoverStats::default()
+            };
+        });
         self
     }
     
@@ -277,13 +1029,20 @@ impl<T, E, P> Population<T, E, P>
                     Container {
                         member: Arc::new(RwLock::new(x)),
                         fitness_score: 0.0,
+                        objectives: None,
                         species: None
                     }
                 })
                 .collect(),
             species: Vec::new(),
             survival_criteria: SurvivalCriteria::Fittest,
-            parental_criteria: ParentalCriteria::BiasedRandom
+            parental_criteria: ParentalCriteria::BiasedRandom,
+            selection: Box::new(RouletteWheel),
+            breeders: BreederPool::default(),
+            elitism: Elitism::default(),
+            elites: 0,
+            mutation_boosts: Vec::new(),
+            crossover_stats: CrossoverStats::default()
         };
         self
     }
@@ -299,13 +1058,20 @@ impl<T, E, P> Population<T, E, P>
                     Container {
                         member: Arc::new(RwLock::new(original.clone())),
                         fitness_score: 0.0,
+                        objectives: None,
                         species: None
                     }
                 })
                 .collect(),
             species: Vec::new(),
             survival_criteria: SurvivalCriteria::Fittest,
-            parental_criteria: ParentalCriteria::BiasedRandom
+            parental_criteria: ParentalCriteria::BiasedRandom,
+            selection: Box::new(RouletteWheel),
+            breeders: BreederPool::default(),
+            elitism: Elitism::default(),
+            elites: 0,
+            mutation_boosts: Vec::new(),
+            crossover_stats: CrossoverStats::default()
         };
         self
     }
@@ -329,12 +1095,74 @@ impl<T, E, P> Population<T, E, P>
         self.size
     }
 
+    /// seed the population's random stream, everything the population draws at random while
+    /// populating and evolving comes from it so the same seed, settings and problem give the
+    /// same run every time. Set it before populating so the first generation is seeded too
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self.draws = 0;
+        self
+    }
+
+    /// Score the members of each generation across rayon's thread pool, which is the default. Turn
+    /// it off to score them one at a time on the calling thread, for problems which hold onto
+    /// something that isn't cheap to share between threads or to step through a fitness function.
+    /// Each member draws from its own random stream either way, so a seeded run scores the same
+    pub fn parallel_eval(mut self, opt: bool) -> Self {
+        self.parallel_eval = opt;
+        self
+    }
+
+    /// Give each generation a wall-clock budget to score its members in, members which haven't
+    /// started being scored by the time it runs out are dropped or deferred to the next generation
+    /// (see Overrun). Keeps evolution running alongside something with a frame or tick budget
+    pub fn time_budget(mut self, budget: Duration, overrun: Overrun) -> Self {
+        self.time_budget = Some((budget, overrun));
+        self
+    }
+
     /// set the dynamic distance bool
     pub fn dynamic_distance(mut self, opt: bool) -> Self {
         self.dynamic_distance = opt;
         self
     }
 
+    /// set how much the distance is moved each generation when the distance is dynamic, 
+    /// defaults to 0.1. Smaller steps settle closer to the species target but take longer to get there
+    pub fn distance_step(mut self, step: f32) -> Self {
+        self.distance_step = step;
+        self
+    }
+
+    /// measure the hypervolume of the pareto front of each generation of a multi-objective problem
+    /// against this point, which should be worse than any member on every objective
+    pub fn hypervolume_reference(mut self, reference: Vec<f32>) -> Self {
+        self.hypervolume_reference = Some(reference);
+        self
+    }
+
+    /// measure the IGD of the pareto front of each generation of a multi-objective problem against
+    /// these points, which should be samples of the true pareto front of the problem
+    pub fn igd_reference(mut self, reference: Vec<Vec<f32>>) -> Self {
+        self.igd_reference = Some(reference);
+        self
+    }
+
+    /// small population defaults for interactive evolution, where a person has to look at every member
+    /// of each generation: 12 members, a few species and a dynamic distance to keep them there. Set
+    /// this before populating since it sets the size, see train_interactive
+    pub fn interactive(mut self) -> Self {
+        self.size = 12;
+        self.dynamic_distance = true;
+        self.config = Config {
+            inbreed_rate: 0.001,
+            crossover_rate: 0.5,
+            distance: 0.5,
+            species_target: 3
+        };
+        self
+    }
+
     /// set the stagnation number of the population
     pub fn stagnation(mut self, stag: usize, cleaner: Vec<Genocide>) -> Self {
         self.stagnation = Stagnant::new(stag, cleaner);
@@ -365,6 +1193,72 @@ impl<T, E, P> Population<T, E, P>
         self
     }
 
+    /// End the run on its own once any of the criteria are met, see StoppingCriteria. The
+    /// generation which meets them is still finished, so the stats of step say what it came to
+    pub fn stop_when(mut self, criteria: StoppingCriteria) -> Self {
+        self.stopping = criteria;
+        self
+    }
+
+    /// Have every member learn before it is scored, ie: a few epochs of backprop for a network (see
+    /// Supervised::fine_tune). With Lamarckian inheritance what a member learned is kept and bred from,
+    /// with Baldwinian it only counts towards the member's fitness, see Learning
+    pub fn learning(mut self, learning: Learning<T>) -> Self {
+        self.learning = Some(learning);
+        self
+    }
+
+    /// Score every member with approximate activations and score the ones near enough to a selection
+    /// cutoff to be misplaced by it again as they are, ie: Fast activations for a network (see
+    /// Neat::approximate_scoring). With Fittest survival the cutoffs are found within the species the
+    /// members are about to be speciated into. Members scored again keep the score they get as they are
+    pub fn approximate_scoring(mut self, approximate: ApproximateScoring<T>) -> Self {
+        self.approximate_scoring = Some(approximate);
+        self
+    }
+
+    /// Keep the best members seen across every generation, see HallOfFame. The hall is
+    /// filled from each generation once it's scored and can put its best back into the
+    /// population after a stagnation purge
+    pub fn hall_of_fame(mut self, hall: HallOfFame<T>) -> Self {
+        self.hall_of_fame = Some(hall);
+        self
+    }
+
+    /// Archive structurally different members which score the same as the best and carry them
+    /// back into the population in turn, so a run on a plateau drifts across it rather than
+    /// breeding from the same few members, see NeutralDrift
+    pub fn neutral_drift(mut self, drift: NeutralDrift<T>) -> Self {
+        self.neutral_drift = Some(drift);
+        self
+    }
+
+    /// Call hook after every generation with what it came to (see GenerationContext), hooks are
+    /// called in the order they were added. This is the place to log or plot a run, adjust the
+    /// config or environment as it goes, or stop it early
+    pub fn on_generation<F>(mut self, hook: F) -> Self
+        where F: FnMut(&mut GenerationContext<T, E>) + Send + Sync + 'static
+    {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// End the run the first time a file can't be written instead of carrying on without it,
+    /// for runs which can't afford to lose their checkpoints. Either way the failure is kept,
+    /// see write_failures
+    pub fn stop_on_write_failure(mut self, stop: bool) -> Self {
+        self.stop_on_write_failure = stop;
+        self
+    }
+
+    /// Add the record of each generation to the csv at path as it's run, so a run's history
+    /// survives it being killed. Rows are appended so a run resumed from a checkpoint carries
+    /// on the same file, see RunHistory::load_csv to read it back
+    pub fn history_file(mut self, path: &str) -> Self {
+        self.history_file = Some(path.to_string());
+        self
+    }
+
     /// give the population a survival criteria, if none is supplied then it
     /// defaults to the fittest genome from each species
     pub fn survivor_criteria(mut self, survive: SurvivalCriteria) -> Self {
@@ -378,6 +1272,79 @@ impl<T, E, P> Population<T, E, P>
         self.parental_criteria =parents;
         self
     }
+
+    /// give the population a way to pick parents from within a species (see the selection
+    /// module), if none is supplied then default to a roulette wheel over their fitness
+    pub fn selection<S: Selection + 'static>(mut self, selection: S) -> Self {
+        self.selection = Box::new(selection);
+        self
+    }
+
+    /// remove species which have gone too long without improving, see StagnationPolicy. The species
+    /// removed from each generation are in what it came to (see GenerationStats::removed_species)
+    pub fn species_stagnation(mut self, policy: StagnationPolicy) -> Self {
+        self.species_stagnation = Some(policy);
+        self
+    }
+
+    /// boost the mutation rates of species which have gone too long without improving, see MutationBoost.
+    /// The species boosted each generation are in what it came to (see GenerationStats::boosted_species)
+    pub fn mutation_boost(mut self, boost: MutationBoost) -> Self {
+        self.mutation_boost = Some(boost);
+        self
+    }
+
+    /// set which members of each species can be picked as parents and whether a child's two parents
+    /// can be the same member (see BreederPool), by default any member can be picked with replacement
+    pub fn breeders(mut self, breeders: BreederPool) -> Self {
+        self.breeders = breeders;
+        self
+    }
+
+    /// which members of each species can be picked as parents, see Population::breeders
+    pub fn get_breeders(&self) -> BreederPool {
+        self.breeders
+    }
+
+    /// carry the top members of each species and of the whole generation over into the next
+    /// generation unchanged on top of the survivors (see Elitism), none are by default
+    pub fn elitism(mut self, elitism: Elitism) -> Self {
+        self.elitism = elitism;
+        self
+    }
+
+    /// what is carried over unchanged each generation, see Population::elitism
+    pub fn get_elitism(&self) -> Elitism {
+        self.elitism
+    }
+
+    /// the way parents are picked from within a species, see Population::selection
+    pub fn get_selection(&self) -> &dyn Selection {
+        &*self.selection
+    }
+
+    /// set every parameter held in a population config at once, this should be
+    /// called before the population is populated so the size is respected
+    pub fn population_config(self, settings: PopulationConfig) -> Self {
+        let mut population = self.size(settings.size)
+            .dynamic_distance(settings.dynamic_distance)
+            .distance_step(settings.distance_step)
+            .configure(settings.config)
+            .stagnation(settings.stagnation, settings.genocide)
+            .survivor_criteria(settings.survivor_criteria)
+            .parental_criteria(settings.parental_criteria)
+            .breeders(settings.breeders)
+            .elitism(settings.elitism)
+            .parallel_eval(settings.parallel_eval)
+            .stop_when(settings.stopping);
+        population.selection = settings.selection;
+        population.species_stagnation = settings.species_stagnation;
+        population.mutation_boost = settings.mutation_boost;
+        match settings.seed {
+            Some(seed) => population.seed(seed),
+            None => population
+        }
+    }
 }
 
 
@@ -387,6 +1354,194 @@ impl<T, E, P> Population<T, E, P>
 /// needs to be set for the population to evolve 
 /// with speciation. These numbers need to be 
 /// set for the evolution to work correctly
+impl<T, E, P> Population<T, E, P>
+    where
+        T: Genome<T, E> + Send + Sync + Clone + Serialize + DeserializeOwned,
+        E: Envionment + Sized + Send + Sync + Default + Clone + Serialize + DeserializeOwned,
+        P: Problem<T>
+{
+
+    /// save the population every so many generations, the file is overwritten each time
+    pub fn checkpoint(mut self, path: &str, every: usize) -> Self {
+        self.checkpointer = Some(Checkpointer {
+            path: path.to_string(),
+            every: every.max(1),
+            save: Self::save_checkpoint
+        });
+        self
+    }
+
+    /// snapshot the state of the population between generations
+    pub fn to_checkpoint(&self) -> Checkpoint<T, E> {
+        Checkpoint {
+            generation: self.generation,
+            size: self.size,
+            dynamic_distance: self.dynamic_distance,
+            distance_step: self.distance_step,
+            config: self.config.clone(),
+            environment: (*self.environment.read().unwrap()).clone(),
+            members: self.curr_gen.members
+                .iter()
+                .map(|cont| (*cont.member.read().unwrap()).clone())
+                .collect(),
+            species: self.curr_gen.species
+                .iter()
+                .map(|spec| {
+                    let spec = spec.read().unwrap();
+                    let mascot = (*spec.mascot.read().unwrap()).clone();
+                    SpeciesCheckpoint {
+                        niche_id: spec.niche_id,
+                        age: spec.age,
+                        best_fitness: spec.best_fitness,
+                        last_improvement: spec.last_improvement,
+                        mascot
+                    }
+                })
+                .collect(),
+            survivor_criteria: self.survivor_criteria.clone(),
+            parental_criteria: self.parental_criteria.clone(),
+            selection: self.selection.clone(),
+            breeders: self.breeders,
+            elitism: self.elitism,
+            species_stagnation: self.species_stagnation,
+            mutation_boost: self.mutation_boost,
+            stagnation: self.stagnation.clone(),
+            scores: match self.evaluated {
+                true => Some(self.curr_gen.members
+                    .iter()
+                    .map(|cont| MemberScore { fitness: cont.fitness_score, objectives: cont.objectives.clone() })
+                    .collect()),
+                false => None
+            },
+            history: self.history.clone(),
+            pareto_history: self.pareto_history.clone(),
+            speciation: self.speciation.clone(),
+            hall_of_fame: self.hall_of_fame.clone(),
+            neutral_drift: self.neutral_drift.clone(),
+            seed: self.seed,
+            draws: self.draws,
+            stopping: self.stopping.clone(),
+            progress: self.progress.saved(),
+            over_budget: self.over_budget
+                .iter()
+                .map(|cont| (*cont.member.read().unwrap()).clone())
+                .collect()
+        }
+    }
+
+    /// write the checkpoint to a temporary file first then move it over the old one, so
+    /// a crash in the middle of writing doesn't lose the previous checkpoint as well. The members
+    /// are written as deltas from the first of them (see compress::DeltaSet), a path ending in .zst
+    /// is compressed with zstd on top of that which needs the zstd feature
+    pub fn save_checkpoint(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let temp = format!("{}.tmp", path);
+        let mut bytes = serde_json::to_vec(&self.to_checkpoint())?;
+        if path.ends_with(".zst") {
+            bytes = compress::compress(&bytes, 0)?;
+        }
+        fs::write(&temp, bytes)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// replace the state of the population with a checkpoint, the problem and 
+    /// any checkpoint schedule of this population are kept
+    pub fn from_checkpoint(mut self, checkpoint: Checkpoint<T, E>) -> Self {
+        self.generation = checkpoint.generation;
+        self.size = checkpoint.size;
+        self.dynamic_distance = checkpoint.dynamic_distance;
+        self.distance_step = checkpoint.distance_step;
+        self.config = checkpoint.config;
+        self.stagnation = checkpoint.stagnation;
+        self.history = checkpoint.history;
+        self.pareto_history = checkpoint.pareto_history;
+        self.speciation = checkpoint.speciation;
+        self.survivor_criteria = checkpoint.survivor_criteria.clone();
+        self.parental_criteria = checkpoint.parental_criteria.clone();
+        self.selection = checkpoint.selection.clone();
+        self.breeders = checkpoint.breeders;
+        self.elitism = checkpoint.elitism;
+        self.species_stagnation = checkpoint.species_stagnation;
+        self.mutation_boost = checkpoint.mutation_boost;
+        // a checkpoint without a hall keeps the one the population was built with
+        self.hall_of_fame = checkpoint.hall_of_fame.or(self.hall_of_fame.take());
+        self.neutral_drift = checkpoint.neutral_drift.or(self.neutral_drift.take());
+        // a checkpoint of an unseeded run keeps the seed the population was built with
+        if let Some(seed) = checkpoint.seed {
+            self.seed = Some(seed);
+            self.draws = checkpoint.draws;
+        }
+        // a checkpoint without criteria keeps the ones the population was built with
+        if !checkpoint.stopping.is_empty() {
+            self.stopping = checkpoint.stopping;
+        }
+        self.progress = checkpoint.progress;
+        self.over_budget = checkpoint.over_budget
+            .into_iter()
+            .map(|member| Container {
+                member: Arc::new(RwLock::new(member)),
+                fitness_score: 0.0,
+                objectives: None,
+                species: None
+            })
+            .collect();
+        self.environment = Arc::new(RwLock::new(checkpoint.environment));
+        // scores which don't line up with the members can't be trusted, so they're evaluated again
+        let size = checkpoint.members.len();
+        let scores = checkpoint.scores.filter(|scores| scores.len() == size);
+        self.evaluated = scores.is_some();
+        let mut scores = scores.into_iter().flatten();
+        self.curr_gen = Generation {
+            members: checkpoint.members
+                .into_iter()
+                .map(|member| {
+                    let score = scores.next().unwrap_or(MemberScore { fitness: 0.0, objectives: None });
+                    Container {
+                        member: Arc::new(RwLock::new(member)),
+                        fitness_score: score.fitness,
+                        objectives: score.objectives,
+                        species: None
+                    }
+                })
+                .collect(),
+            species: checkpoint.species
+                .into_iter()
+                .map(|spec| {
+                    let mut niche = Niche::new(&Arc::new(RwLock::new(spec.mascot)), 0.0);
+                    niche.niche_id = spec.niche_id;
+                    niche.age = spec.age;
+                    niche.best_fitness = spec.best_fitness;
+                    niche.last_improvement = spec.last_improvement;
+                    niche.members = Vec::new();
+                    Arc::new(RwLock::new(niche))
+                })
+                .collect(),
+            survival_criteria: checkpoint.survivor_criteria,
+            parental_criteria: checkpoint.parental_criteria,
+            selection: checkpoint.selection,
+            breeders: checkpoint.breeders,
+            elitism: checkpoint.elitism,
+            elites: 0,
+            mutation_boosts: Vec::new(),
+            crossover_stats: CrossoverStats::default()
+        };
+        self
+    }
+
+    /// load a checkpoint saved by save_checkpoint and resume from it
+    pub fn resume(self, path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut bytes = fs::read(path)?;
+        if compress::is_compressed(&bytes) {
+            bytes = compress::decompress(&bytes)?;
+        }
+        let checkpoint = serde_json::from_slice::<Checkpoint<T, E>>(&bytes)?;
+        Ok(self.from_checkpoint(checkpoint))
+    }
+}
+
+
+
+
 impl Config {
     pub fn new() -> Self {
         Config {
@@ -400,6 +1555,124 @@ impl Config {
 
 
 
+impl PopulationConfig {
+
+    /// read the population settings from a toml file, the speciation settings 
+    /// are expected under a [config] table
+    pub fn from_toml(path: &str) -> Result<Self, ConfigError> {
+        Self::from_toml_str(&config::read(path)?)
+    }
+
+
+    pub fn from_toml_str(contents: &str) -> Result<Self, ConfigError> {
+        let table = config::parse_table(contents)?;
+        config::check_keys(&table, "", &[
+            "size", "dynamic_distance", "distance_step", "config", "stagnation", "genocide", "species_stagnation", "mutation_boost", "survivor_criteria", "parental_criteria", "selection", "breeders", "elitism", "seed", "parallel_eval", "stopping"
+        ], &["config"])?;
+        match table.get("config") {
+            Some(toml::Value::Table(inner)) => {
+                let keys = ["inbreed_rate", "crossover_rate", "distance", "species_target"];
+                config::check_keys(inner, "config.", &keys, &keys)?
+            },
+            _ => return Err(ConfigError::invalid("config", "expected a table"))
+        }
+        match table.get("species_stagnation") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "species_stagnation.", &["max_stagnation", "protect"], &["max_stagnation"])?,
+            Some(_) => return Err(ConfigError::invalid("species_stagnation", "expected a table")),
+            None => {}
+        }
+        match table.get("mutation_boost") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "mutation_boost.", &["after", "multiplier", "cap"], &["after"])?,
+            Some(_) => return Err(ConfigError::invalid("mutation_boost", "expected a table")),
+            None => {}
+        }
+        match table.get("breeders") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "breeders.", &["fraction", "replacement"], &[])?,
+            Some(_) => return Err(ConfigError::invalid("breeders", "expected a table")),
+            None => {}
+        }
+        match table.get("elitism") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "elitism.", &["per_species", "global"], &[])?,
+            Some(_) => return Err(ConfigError::invalid("elitism", "expected a table")),
+            None => {}
+        }
+        match table.get("stopping") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "stopping.", &["patience", "target", "time_limit"], &[])?,
+            Some(_) => return Err(ConfigError::invalid("stopping", "expected a table")),
+            None => {}
+        }
+        let settings = config::parse::<PopulationConfig>(contents)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+
+    /// check each of the settings is in a usable range
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.size <= 0 {
+            return Err(ConfigError::invalid("size", &format!("expected a positive number, got {}", self.size)));
+        }
+        if self.distance_step <= 0.0 {
+            return Err(ConfigError::invalid("distance_step", &format!("expected a positive number, got {}", self.distance_step)));
+        }
+        config::check_rate("config.inbreed_rate", self.config.inbreed_rate)?;
+        config::check_rate("config.crossover_rate", self.config.crossover_rate)?;
+        if self.config.distance <= 0.0 {
+            return Err(ConfigError::invalid("config.distance", &format!("expected a positive number, got {}", self.config.distance)));
+        }
+        if self.species_stagnation.is_some_and(|policy| policy.max_stagnation < 0) {
+            return Err(ConfigError::invalid("species_stagnation.max_stagnation", "expected a number of generations, got a negative number"));
+        }
+        if let Some(boost) = self.mutation_boost {
+            if boost.after < 0 {
+                return Err(ConfigError::invalid("mutation_boost.after", "expected a number of generations, got a negative number"));
+            }
+            if boost.multiplier < 1.0 || boost.cap < 1.0 {
+                return Err(ConfigError::invalid("mutation_boost", "the multiplier and cap can't be less than 1"));
+            }
+        }
+        if !(self.breeders.fraction > 0.0 && self.breeders.fraction <= 1.0) {
+            return Err(ConfigError::invalid("breeders.fraction", &format!("expected a fraction in (0, 1], got {}", self.breeders.fraction)));
+        }
+        if self.stopping.patience == Some(0) {
+            return Err(ConfigError::invalid("stopping.patience", "expected a positive number, got 0"));
+        }
+        Ok(())
+    }
+
+
+    fn default_size() -> i32 {
+        100
+    }
+
+
+    fn default_distance_step() -> f32 {
+        0.1
+    }
+
+
+    fn default_survivor_criteria() -> SurvivalCriteria {
+        SurvivalCriteria::Fittest
+    }
+
+
+    fn default_parental_criteria() -> ParentalCriteria {
+        ParentalCriteria::BiasedRandom
+    }
+
+
+    fn default_selection() -> Box<dyn Selection> {
+        Box::new(RouletteWheel)
+    }
+
+
+    fn default_parallel_eval() -> bool {
+        true
+    }
+}
+
+
+
 impl Stagnant {
     pub fn new(target_stagnation: usize, cleaners: Vec<Genocide>) -> Self {
         Stagnant {
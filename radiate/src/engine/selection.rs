@@ -0,0 +1,214 @@
+//! How parents are picked from within a species. Once a species has been picked to breed from
+//! (see ParentalCriteria) a Selection picks which of its members become parents, which is where
+//! most of the selection pressure of a run comes from. A roulette wheel is gentle while the
+//! fitness of the members is close, a tournament or truncation keeps the pressure up however
+//! close the members are.
+
+use std::fmt::Debug;
+use rand::Rng;
+use crate::rng;
+
+
+
+/// Pick a member of a species to be a parent. Implementations are serialized with the population's
+/// config and checkpoints through typetag, the same way layers are, so they need `#[typetag::serde]`
+#[typetag::serde(tag = "type")]
+pub trait Selection: SelectionClone + Debug + Send + Sync {
+
+    /// the index of the member to breed, given the adjusted fitness of each member of the
+    /// species in the order they were speciated. There is always at least one member
+    fn select(&self, r: &mut rng::Stream, fitness: &[f32]) -> usize;
+}
+
+
+
+/// Cloning a Box<dyn Selection>, see LayerClone
+pub trait SelectionClone {
+    fn clone_box(&self) -> Box<dyn Selection>;
+}
+
+
+impl<S> SelectionClone for S
+    where S: 'static + Selection + Clone
+{
+    fn clone_box(&self) -> Box<dyn Selection> {
+        Box::new(self.clone())
+    }
+}
+
+
+impl Clone for Box<dyn Selection> {
+    fn clone(&self) -> Box<dyn Selection> {
+        self.clone_box()
+    }
+}
+
+
+
+/// Which members of a species can be picked to breed at all, the rest only get through as survivors.
+///
+/// fraction - the fittest fraction (0, 1] of each species which is eligible, at least one member.
+///     1.0 by default so every member is
+/// replacement - whether the two parents of a child picked from the same species can be the same
+///     member, true by default. Without it the second parent is picked from the eligible members
+///     other than the first, unless the first is the only one
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreederPool {
+    pub fraction: f32,
+    pub replacement: bool
+}
+
+
+/// The default, members are picked with a chance in proportion to their fitness. If the fitness
+/// of the species is all negative the first member is picked, so the selection is essentially a
+/// random search until fitness is above 0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouletteWheel;
+
+
+/// The fittest of size members picked at random (the same member can be picked more than once),
+/// the larger the tournament the higher the pressure. A size of 1 is a uniform random pick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tournament {
+    pub size: usize
+}
+
+
+/// Members are picked with a chance in proportion to their rank, the worst member has a weight of 1
+/// and the best the size of the species. The same pressure however close or far apart the fitness
+/// of the members is, and fitness can be negative
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rank;
+
+
+/// A uniform random pick of the fittest fraction (0, 1] of the species, at least one member
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Truncation {
+    pub fraction: f32
+}
+
+
+
+#[typetag::serde]
+impl Selection for RouletteWheel {
+    fn select(&self, r: &mut rng::Stream, fitness: &[f32]) -> usize {
+        let total = fitness.iter().sum::<f32>();
+        let index = r.gen::<f32>() * total;
+        let mut curr = 0.0;
+        for (i, score) in fitness.iter().enumerate() {
+            curr += score;
+            if curr >= index {
+                return i;
+            }
+        }
+        0
+    }
+}
+
+
+
+#[typetag::serde]
+impl Selection for Tournament {
+    fn select(&self, r: &mut rng::Stream, fitness: &[f32]) -> usize {
+        (0..self.size.max(1))
+            .map(|_| r.gen_range(0, fitness.len()))
+            .fold(None, |best: Option<usize>, index| match best {
+                Some(best) if fitness[best] >= fitness[index] => Some(best),
+                _ => Some(index)
+            })
+            .unwrap_or(0)
+    }
+}
+
+
+
+#[typetag::serde]
+impl Selection for Rank {
+    fn select(&self, r: &mut rng::Stream, fitness: &[f32]) -> usize {
+        let order = by_fitness(fitness);
+        let n = order.len();
+        // the member ranked i from the worst (0) has a weight of i + 1 out of n(n + 1) / 2
+        let mut pick = r.gen_range(0, n * (n + 1) / 2);
+        for (rank, index) in order.iter().rev().enumerate() {
+            if pick <= rank {
+                return *index;
+            }
+            pick -= rank + 1;
+        }
+        order[0]
+    }
+}
+
+
+
+#[typetag::serde]
+impl Selection for Truncation {
+    fn select(&self, r: &mut rng::Stream, fitness: &[f32]) -> usize {
+        let order = by_fitness(fitness);
+        let keep = ((order.len() as f32 * self.fraction).ceil() as usize).clamp(1, order.len());
+        order[r.gen_range(0, keep)]
+    }
+}
+
+
+
+impl BreederPool {
+
+    pub fn new() -> Self {
+        BreederPool {
+            fraction: 1.0,
+            replacement: true
+        }
+    }
+
+
+    pub fn fraction(mut self, fraction: f32) -> Self {
+        self.fraction = fraction;
+        self
+    }
+
+
+    pub fn replacement(mut self, replacement: bool) -> Self {
+        self.replacement = replacement;
+        self
+    }
+
+
+    /// The indexes of the members eligible to breed given the fitness of each member of the species,
+    /// in the order the members came so a selection sees them the same way it would the whole species.
+    /// exclude is a parent already picked from the species, left out without replacement
+    pub fn eligible(&self, fitness: &[f32], exclude: Option<usize>) -> Vec<usize> {
+        let keep = ((fitness.len() as f32 * self.fraction).ceil() as usize).clamp(1, fitness.len().max(1));
+        let mut pool = match keep < fitness.len() {
+            true => {
+                let mut top = by_fitness(fitness);
+                top.truncate(keep);
+                top.sort_unstable();
+                top
+            },
+            false => (0..fitness.len()).collect()
+        };
+        if let Some(exclude) = exclude.filter(|_| !self.replacement && pool.len() > 1) {
+            pool.retain(|index| *index != exclude);
+        }
+        pool
+    }
+}
+
+
+
+impl Default for BreederPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+
+/// the indexes of the members fittest first, ties kept in the order they came
+fn by_fitness(fitness: &[f32]) -> Vec<usize> {
+    let mut order = (0..fitness.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| fitness[*b].total_cmp(&fitness[*a]));
+    order
+}
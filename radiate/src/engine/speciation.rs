@@ -0,0 +1,168 @@
+//! How the species of a run came and went. Each generation the population records how many members
+//! every species had once they were speciated, and where each new species came from and where each
+//! one which died out went, which is what a speciation stack plot (the size of every species stacked
+//! up over the generations, each band branching off its parent) is drawn from. A genome doesn't know
+//! its parents' species, so a new species is taken to have split from the existing species whose
+//! mascot is the closest to its own, and a species which died out to have merged into the closest
+//! surviving species.
+
+use std::fs::File;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+use super::generation::Family;
+use super::genome::Genome;
+
+
+
+/// One species in one generation. split_from is only set in the generation the species appeared in,
+/// and is None for the species of the first generation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeciesRecord {
+    pub niche_id: Uuid,
+    pub age: i32,
+    pub members: usize,
+    pub top_fitness: f32,
+    pub split_from: Option<Uuid>
+}
+
+
+/// A species which was around the generation before but has no members in this one, merged_into
+/// is None if no species survived
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Extinction {
+    pub niche_id: Uuid,
+    pub merged_into: Option<Uuid>
+}
+
+
+/// The species of one generation in the order they were speciated, and the ones which died out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeciationRecord {
+    pub generation: usize,
+    pub species: Vec<SpeciesRecord>,
+    pub extinct: Vec<Extinction>
+}
+
+
+/// The speciation of every generation of a run in the order they were run, carried over in checkpoints
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpeciationHistory {
+    pub generations: Vec<SpeciationRecord>
+}
+
+
+
+impl SpeciationHistory {
+
+    pub fn new() -> Self {
+        SpeciationHistory::default()
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.generations.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.generations.is_empty()
+    }
+
+
+    /// Record the species of a generation once it has been speciated. before is the species the
+    /// generation was speciated into (those of the generation before it), after what was left of
+    /// them along with the species founded by this generation
+    pub fn record<T, E>(&mut self, generation: usize, before: &[Family<T, E>], after: &[Family<T, E>], env: &Arc<RwLock<E>>)
+        where
+            T: Genome<T, E> + Send + Sync + Clone,
+            E: Send + Sync
+    {
+        let closest = |family: &Family<T, E>, among: &[&Family<T, E>]| -> Option<Uuid> {
+            let mascot = Arc::clone(&family.read().unwrap().mascot);
+            let mascot = mascot.read().unwrap();
+            among.iter()
+                .map(|other| {
+                    let other = other.read().unwrap();
+                    let distance = T::distance(&mascot, &other.mascot.read().unwrap(), Arc::clone(env));
+                    (distance, other.niche_id)
+                })
+                .fold(None, |best: Option<(f32, Uuid)>, (distance, id)| match best {
+                    Some(best) if best.0 <= distance => Some(best),
+                    _ => Some((distance, id))
+                })
+                .map(|(_, id)| id)
+        };
+        let existing = before.iter().collect::<Vec<_>>();
+        let surviving = after.iter().filter(|family| before.iter().any(|old| Arc::ptr_eq(old, family))).collect::<Vec<_>>();
+        let species = after.iter()
+            .map(|family| {
+                let split_from = match surviving.iter().any(|old| Arc::ptr_eq(old, family)) {
+                    true => None,
+                    false => closest(family, &existing)
+                };
+                let stats = family.read().unwrap().stats();
+                SpeciesRecord {
+                    niche_id: stats.niche_id,
+                    age: stats.age,
+                    members: stats.members,
+                    top_fitness: stats.top_fitness,
+                    split_from
+                }
+            })
+            .collect();
+        let extinct = before.iter()
+            .filter(|family| !after.iter().any(|new| Arc::ptr_eq(new, family)))
+            .map(|family| Extinction {
+                niche_id: family.read().unwrap().niche_id,
+                merged_into: closest(family, &surviving)
+            })
+            .collect();
+        self.generations.push(SpeciationRecord { generation, species, extinct });
+    }
+
+
+    /// Every species of the run in the order they first appeared along with its size in each
+    /// generation (0 where it had no members), the bands of a speciation stack plot
+    pub fn stack(&self) -> Vec<(Uuid, Vec<usize>)> {
+        let mut bands: Vec<(Uuid, Vec<usize>)> = Vec::new();
+        for (index, record) in self.generations.iter().enumerate() {
+            for species in record.species.iter() {
+                match bands.iter_mut().find(|(id, _)| *id == species.niche_id) {
+                    Some((_, sizes)) => sizes[index] = species.members,
+                    None => {
+                        let mut sizes = vec![0; self.generations.len()];
+                        sizes[index] = species.members;
+                        bands.push((species.niche_id, sizes));
+                    }
+                }
+            }
+        }
+        bands
+    }
+
+
+    /// every (parent, child) species split recorded over the run, in the order they happened
+    pub fn splits(&self) -> Vec<(Uuid, Uuid)> {
+        self.generations.iter()
+            .flat_map(|record| record.species.iter())
+            .filter_map(|species| Some((species.split_from?, species.niche_id)))
+            .collect()
+    }
+
+
+    pub fn to_json(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&File::create(path)?, self)?;
+        Ok(())
+    }
+
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+}
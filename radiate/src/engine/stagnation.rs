@@ -0,0 +1,179 @@
+//! Retiring species which have stopped improving. Fitness sharing keeps a species from taking over
+//! the population but not from holding on to its share of it long after it has stopped going
+//! anywhere, so once a species goes too long without its best fitness improving it is removed
+//! and its place goes to the offspring of species which are still improving. How long a species
+//! has gone without improving is tracked by the species itself, see Niche::best_fitness.
+//! Before it comes to that a stuck species can be given a chance to escape by mutating its
+//! offspring harder the longer it goes without improving, see MutationBoost.
+
+use uuid::Uuid;
+use super::generation::Family;
+use super::genome::Genome;
+
+
+
+/// Remove species whose best fitness hasn't improved for more than max_stagnation generations.
+/// The protect species with the fittest members this generation are never removed, so the species
+/// holding the best member of the generation always survives (protect is at least 1)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StagnationPolicy {
+    pub max_stagnation: i32,
+    #[serde(default = "StagnationPolicy::default_protect")]
+    pub protect: usize
+}
+
+
+/// Scale up the mutation rates a species' offspring are bred with once it has gone more than after
+/// generations without improving. The rates are multiplied by multiplier for each generation past
+/// after, up to cap times what they are, so a species stuck for long enough mutates its way out
+/// or is left to a StagnationPolicy with a longer max_stagnation to remove. A child's rates are
+/// boosted if its fitter parent's species is, how the rates are scaled is up to the environment
+/// (see Envionment::boost_mutation)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MutationBoost {
+    pub after: i32,
+    #[serde(default = "MutationBoost::default_multiplier")]
+    pub multiplier: f32,
+    #[serde(default = "MutationBoost::default_cap")]
+    pub cap: f32
+}
+
+
+/// A species whose mutation rates were boosted for stagnating, and what they were multiplied by
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeciesBoost {
+    pub niche_id: Uuid,
+    pub stagnation: i32,
+    pub factor: f32
+}
+
+
+/// A species removed for stagnating, what it came to in the generation it was removed in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemovedSpecies {
+    pub niche_id: Uuid,
+    pub age: i32,
+    pub members: usize,
+    pub best_fitness: f32,
+    pub stagnation: i32
+}
+
+
+
+impl StagnationPolicy {
+
+    pub fn new(max_stagnation: i32) -> Self {
+        StagnationPolicy {
+            max_stagnation,
+            protect: 1
+        }
+    }
+
+
+    pub fn protect(mut self, count: usize) -> Self {
+        self.protect = count;
+        self
+    }
+
+
+    /// Take the stagnant species out of a speciated generation's species, returning them in the order they were in
+    pub fn apply<T, E>(&self, species: &mut Vec<Family<T, E>>) -> Vec<RemovedSpecies>
+        where
+            T: Genome<T, E> + Send + Sync + Clone,
+            E: Send + Sync
+    {
+        let tops = species.iter()
+            .map(|family| family.read().unwrap().stats().top_fitness)
+            .collect::<Vec<_>>();
+        let mut order = (0..species.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| tops[*b].total_cmp(&tops[*a]));
+        let protected = order.into_iter().take(self.protect.max(1)).collect::<Vec<_>>();
+
+        let mut removed = Vec::new();
+        let mut index = 0;
+        species.retain(|family| {
+            let niche = family.read().unwrap();
+            let stagnation = niche.age - niche.last_improvement;
+            let keep = protected.contains(&index) || stagnation <= self.max_stagnation;
+            if !keep {
+                removed.push(RemovedSpecies {
+                    niche_id: niche.niche_id,
+                    age: niche.age,
+                    members: niche.members.len(),
+                    best_fitness: niche.best_fitness.unwrap_or(f32::MIN),
+                    stagnation
+                });
+            }
+            index += 1;
+            keep
+        });
+        removed
+    }
+
+
+    fn default_protect() -> usize {
+        1
+    }
+}
+
+
+
+
+impl MutationBoost {
+
+    pub fn new(after: i32) -> Self {
+        MutationBoost {
+            after,
+            multiplier: MutationBoost::default_multiplier(),
+            cap: MutationBoost::default_cap()
+        }
+    }
+
+
+    pub fn multiplier(mut self, multiplier: f32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+
+    pub fn cap(mut self, cap: f32) -> Self {
+        self.cap = cap;
+        self
+    }
+
+
+    /// what the rates of a species which has gone stagnation generations without improving are multiplied by
+    pub fn factor(&self, stagnation: i32) -> f32 {
+        match stagnation - self.after {
+            over if over > 0 => self.multiplier.powi(over).min(self.cap),
+            _ => 1.0
+        }
+    }
+
+
+    /// the species of a speciated generation which are boosted, in the order they are in
+    pub fn apply<T, E>(&self, species: &[Family<T, E>]) -> Vec<SpeciesBoost>
+        where
+            T: Genome<T, E> + Send + Sync + Clone,
+            E: Send + Sync
+    {
+        species.iter()
+            .filter_map(|family| {
+                let niche = family.read().unwrap();
+                let stagnation = niche.age - niche.last_improvement;
+                let factor = self.factor(stagnation);
+                (factor != 1.0).then_some(SpeciesBoost { niche_id: niche.niche_id, stagnation, factor })
+            })
+            .collect()
+    }
+
+
+    fn default_multiplier() -> f32 {
+        1.5
+    }
+
+
+    fn default_cap() -> f32 {
+        4.0
+    }
+}
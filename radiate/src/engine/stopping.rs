@@ -0,0 +1,139 @@
+//! When a run should end on its own. Population::run otherwise only ends when its runner says so,
+//! which leaves every caller writing the same checks of the fitness and time into it
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+
+
+/// Conditions under which a population stops itself, any one of them being met ends the run
+/// after the generation which met it (see Population::stop_when and Population::is_stopped).
+///
+/// patience - stop after this many generations in a row without the top fitness improving
+/// target - stop once the top fitness reaches this
+/// time_limit - stop once the run has been going this long, counted from its first step
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StoppingCriteria {
+    pub patience: Option<usize>,
+    pub target: Option<f32>,
+    pub time_limit: Option<Duration>
+}
+
+
+/// Why a run stopped, Hook means one of the population's generation hooks stopped it and
+/// WriteFailed that a file couldn't be written, see Population::stop_on_write_failure
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StopReason {
+    Patience,
+    Target,
+    TimeLimit,
+    Hook,
+    WriteFailed
+}
+
+
+/// What the criteria need to remember between generations, kept by the population and saved
+/// with its checkpoints. An Instant means nothing to another process, so how long the run went
+/// before it was last resumed is kept as elapsed and the clock is started again on top of it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Progress {
+    best: Option<f32>,
+    since_improvement: usize,
+    elapsed: Duration,
+    #[serde(skip)]
+    started: Option<Instant>
+}
+
+
+
+impl StoppingCriteria {
+
+    pub fn new() -> Self {
+        StoppingCriteria::default()
+    }
+
+
+    pub fn patience(mut self, generations: usize) -> Self {
+        self.patience = Some(generations);
+        self
+    }
+
+
+    pub fn target(mut self, fitness: f32) -> Self {
+        self.target = Some(fitness);
+        self
+    }
+
+
+    pub fn time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+
+    /// whether any criteria is set at all
+    pub fn is_empty(&self) -> bool {
+        *self == StoppingCriteria::default()
+    }
+
+
+    /// Record the top fitness of a generation and see if the run should stop because of it.
+    /// The target is checked first, so a run which reaches it as its time runs out is still
+    /// said to have reached it
+    pub(crate) fn check(&self, progress: &mut Progress, fitness: f32) -> Option<StopReason> {
+        match progress.best {
+            Some(best) if fitness <= best => progress.since_improvement += 1,
+            _ => {
+                progress.best = Some(fitness);
+                progress.since_improvement = 0;
+            }
+        }
+        if self.target.is_some_and(|target| fitness >= target) {
+            Some(StopReason::Target)
+        } else if self.patience.is_some_and(|patience| progress.since_improvement >= patience) {
+            Some(StopReason::Patience)
+        } else if self.time_limit.is_some_and(|limit| progress.started.is_some() && progress.elapsed() >= limit) {
+            Some(StopReason::TimeLimit)
+        } else {
+            None
+        }
+    }
+}
+
+
+
+impl Progress {
+
+    /// start the clock of the time limit if the run hasn't started already
+    pub(crate) fn start(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+
+    /// how long the run has been going in all, across every time it was resumed
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.elapsed + self.started.map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+
+    /// the progress as it is saved, with the time run so far folded into elapsed
+    pub(crate) fn saved(&self) -> Progress {
+        Progress { elapsed: self.elapsed(), started: None, ..self.clone() }
+    }
+}
+
+
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::Patience => write!(f, "the top fitness stopped improving"),
+            StopReason::Target => write!(f, "the target fitness was reached"),
+            StopReason::TimeLimit => write!(f, "the time limit ran out"),
+            StopReason::Hook => write!(f, "a hook stopped the run"),
+            StopReason::WriteFailed => write!(f, "a file couldn't be written")
+        }
+    }
+}
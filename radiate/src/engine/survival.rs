@@ -4,11 +4,13 @@ extern crate rand;
 
 use std::sync::{Arc, RwLock};
 use rand::Rng;
-use rand::rngs::ThreadRng;
 use rand::seq::SliceRandom;
 use rayon::prelude::*;
 use super::generation::{Container, Family, Member};
+use super::niche::NicheMember;
 use super::genome::Genome;
+use super::selection::{Selection, RouletteWheel, BreederPool};
+use crate::rng;
 
 
 
@@ -37,15 +39,31 @@ pub enum SurvivalCriteria {
 }
 
 
+/// Members carried over into the next generation unchanged on top of the survivors, so the best
+/// solutions found so far can't be lost to the survival criteria or a stagnation purge.
+///
+/// per_species - the top number of members of each species
+/// global - the top number of members of the whole generation, regardless of species
+///
+/// A member is only carried over once however many of these it is in (or if the survival
+/// criteria already keeps it). Both are 0 by default, which leaves it to the survival criteria
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Elitism {
+    pub per_species: usize,
+    pub global: usize
+}
+
+
 /// Implement a way to pick parents of children, in other words
 /// how is the rest of the population generation after those who 
 /// don't survive die out.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ParentalCriteria {
-    /// The default option, statistically pick more fit parents
-    /// however allow for less fit parents to be picked as well. This is
-    /// kinda like putting the members in a species on a curve and randomly
-    /// picking from that distribution
+    /// The default option, statistically pick more fit species
+    /// however allow for less fit species to be picked as well, then pick
+    /// the parents from within them with the population's Selection, which
+    /// is a roulette wheel over their fitness by default (see the selection module)
     BiasedRandom,
     /// Only the best in each species are allowed to reproduce
     BestInSpecies,
@@ -61,6 +79,16 @@ pub enum ParentalCriteria {
 impl SurvivalCriteria {
 
 
+    /// how many of the given number of members survive if the criteria keeps them by rank
+    pub fn survivors(&self, members: usize) -> Option<usize> {
+        match self {
+            Self::Fittest => None,
+            Self::TopNumber(num) => Some(*num),
+            Self::TopPercent(perc) => Some((members as f32 * perc) as usize)
+        }
+    }
+
+
     /// Based on the survival criteria, given a vec of containers and families, pick who survives
     #[inline]
     pub fn pick_survivors<T, E>(&self, members: &mut [Container<T, E>], families: &[Family<T, E>]) -> Option<Vec<Arc<RwLock<T>>>>
@@ -70,8 +98,18 @@ impl SurvivalCriteria {
     {
         match self {
             Self::Fittest => {
+                // the member itself rather than a copy, so elitism can tell it is already kept
                 Some(families.par_iter()
-                    .map(|x| x.read().unwrap().fittest().1)
+                    .filter_map(|x| {
+                        let spec = x.read().unwrap();
+                        let mut top: Option<&NicheMember<T>> = None;
+                        for i in spec.members.iter() {
+                            if top.is_none() || i.0 > top?.0 {
+                                top = Some(i);
+                            }
+                        }
+                        top?.1.upgrade()
+                    })
                     .collect::<Vec<_>>())
             },
             Self::TopNumber(num) => {
@@ -120,23 +158,78 @@ impl SurvivalCriteria {
 
 
 
+impl Elitism {
+
+    pub fn new() -> Self {
+        Elitism::default()
+    }
+
+
+    pub fn per_species(mut self, num: usize) -> Self {
+        self.per_species = num;
+        self
+    }
+
+
+    pub fn global(mut self, num: usize) -> Self {
+        self.global = num;
+        self
+    }
+
+
+    /// The members to carry over, fittest first and no more than limit of them. The members are
+    /// the same ones as in the generation, not copies
+    pub fn pick_elites<T, E>(&self, members: &[Container<T, E>], families: &[Family<T, E>], limit: usize) -> Vec<Member<T>>
+        where
+            T: Genome<T, E> + Send + Sync + Clone,
+            E: Send + Sync
+    {
+        let mut ranked = members.iter()
+            .map(|cont| (cont.fitness_score, Arc::clone(&cont.member)))
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut elites = ranked.into_iter().take(self.global).collect::<Vec<_>>();
+        if self.per_species > 0 {
+            for family in families.iter() {
+                let mut best = family.read().unwrap().members
+                    .iter()
+                    .filter_map(|member| Some((member.0, member.1.upgrade()?)))
+                    .collect::<Vec<_>>();
+                best.sort_by(|a, b| b.0.total_cmp(&a.0));
+                for (fitness, member) in best.into_iter().take(self.per_species) {
+                    if !elites.iter().any(|(_, elite)| Arc::ptr_eq(elite, &member)) {
+                        elites.push((fitness, member));
+                    }
+                }
+            }
+        }
+        elites.sort_by(|a, b| b.0.total_cmp(&a.0));
+        elites.truncate(limit);
+        elites.into_iter().map(|(_, member)| member).collect()
+    }
+}
+
+
+
+
 /// implement picking parents
 impl ParentalCriteria {
 
 
-    /// Find two parents to crossover and produce a child
+    /// Find two parents to crossover and produce a child, selection picks them from within their species
+    /// out of the members the breeder pool makes eligible
     #[inline]
-    pub fn pick_parents<T, E>(&self, inbreed_rate: f32, families: &[Family<T, E>]) -> Option<((f32, Member<T>), (f32, Member<T>))>
+    pub fn pick_parents<T, E>(&self, inbreed_rate: f32, families: &[Family<T, E>], selection: &dyn Selection, breeders: &BreederPool) -> Option<((f32, Member<T>), (f32, Member<T>))>
         where
             T: Genome<T, E> + Send + Sync + Clone,
             E: Send + Sync 
     {
         match self {
             Self::BiasedRandom => {
-                return Some(self.create_match(inbreed_rate, families))
+                return Some(self.create_match(inbreed_rate, families, selection, breeders))
             },
             Self::BestInSpecies => {
-                let mut r = rand::thread_rng();
+                let mut r = rng::thread();
                 let child_one = families.choose(&mut r)?.read().unwrap().fittest();
                 let child_two = families.choose(&mut r)?.read().unwrap().fittest();
                 return Some((child_one, child_two))
@@ -150,12 +243,12 @@ impl ParentalCriteria {
     /// parents and returns a tuple of tuples where the f32 is the parent's fitness,
     /// and the type is the parent itself
     #[inline]
-    fn create_match<T, E>(&self, inbreed_rate: f32, families: &[Family<T, E>]) -> ((f32, Member<T>), (f32, Member<T>))
+    fn create_match<T, E>(&self, inbreed_rate: f32, families: &[Family<T, E>], selection: &dyn Selection, breeders: &BreederPool) -> ((f32, Member<T>), (f32, Member<T>))
         where
             T: Genome<T, E> + Send + Sync + Clone,
             E: Send + Sync
     {
-        let mut r = rand::thread_rng();
+        let mut r = rng::thread();
         let (species_one, species_two);
         // get two species to pick from taking into account an inbreeding rate - an inbreed can happen without this 
         if r.gen::<f32>() < inbreed_rate {
@@ -166,9 +259,10 @@ impl ParentalCriteria {
             species_one = self.get_biased_random_species(&mut r, families).unwrap();
             species_two = self.get_biased_random_species(&mut r, families).unwrap();
         }
-        // get two parents from the species, again the parent may be the same 
-        let parent_one = self.get_biased_random_member(&mut r, &species_one);
-        let parent_two = self.get_biased_random_member(&mut r, &species_two);
+        // get two parents from the species, again the parent may be the same unless the breeders are picked without replacement
+        let (index, parent_one) = self.select_member(&mut r, &species_one, selection, breeders, None);
+        let exclude = Arc::ptr_eq(&species_one, &species_two).then_some(index);
+        let (_, parent_two) = self.select_member(&mut r, &species_two, selection, breeders, exclude);
         // return the parent tuples
         (parent_one, parent_two)
     }
@@ -182,7 +276,7 @@ impl ParentalCriteria {
     /// Statistically this allows for species with larger adjusted fitnesses to
     /// have a greater change of being picked for breeding
     #[inline]
-    fn get_biased_random_species<T, E>(&self, r: &mut ThreadRng, families: &[Family<T, E>]) -> Option<Family<T, E>>
+    fn get_biased_random_species<T, E>(&self, r: &mut rng::Stream, families: &[Family<T, E>]) -> Option<Family<T, E>>
         where 
             T: Genome<T, E> + Send + Sync + Clone,
             E: Send + Sync
@@ -215,37 +309,31 @@ impl ParentalCriteria {
     /// Get a biased random member from the species. By summing the fitness scores of the 
     /// members, members with larger fitness scores are statistically more likely to be picked
     #[inline]
-    pub fn get_biased_random_member<T, E>(&self, r: &mut ThreadRng, family: &Family<T, E>) -> (f32, Member<T>)
+    pub fn get_biased_random_member<T, E>(&self, r: &mut rng::Stream, family: &Family<T, E>) -> (f32, Member<T>)
+        where
+            T: Genome<T, E> + Send + Sync + Clone,
+            E: Send + Sync
+    {
+        self.select_member(r, family, &RouletteWheel, &BreederPool::default(), None).1
+    }
+
+
+
+    /// pick a member from the eligible breeders of the species with the selection, returning
+    /// its index in the species along with its adjusted fitness and the member
+    #[inline]
+    fn select_member<T, E>(&self, r: &mut rng::Stream, family: &Family<T, E>, selection: &dyn Selection, breeders: &BreederPool, exclude: Option<usize>) -> (usize, (f32, Member<T>))
         where
             T: Genome<T, E> + Send + Sync + Clone,
             E: Send + Sync
     {
-        // declare a result which will panic! at the end of the function if there 
-        // is no member found, then get the species total fitness score
         let species_lock = family.read().unwrap();
-        let total = species_lock.get_total_adjusted_fitness();
-        let index = r.gen::<f32>() * total;
-        let (mut result, mut curr) = (None, 0.0);
-        // go through each member and see if it's adjusted fitness has pushed it over the edge
-        for member in species_lock.members.iter() {
-            curr += member.0;
-            if curr >= index {
-                result = Some(member);
-                break
-            }
-        };
-        // either unwrap the result, or if the adjusted fitness of the species was all
-        // negative, just take the first member. If the fitness of the species is negative,
-        // the algorithm essentially preforms a random search for these biased functions 
-        // once the fitness is above 0, it will 'catch on' and start producing biased results
-        result.or_else(|| Some(&species_lock.members[0]))
-            .and_then(|val| {
-                Some((val.0, val.1.clone()
-                    .upgrade()
-                    .unwrap_or_else(|| panic!("Failed to get random species member."))
-                ))
-            })
-            .unwrap()
+        let fitness = species_lock.members.iter().map(|member| member.0).collect::<Vec<_>>();
+        let pool = breeders.eligible(&fitness, exclude);
+        let eligible = pool.iter().map(|index| fitness[*index]).collect::<Vec<_>>();
+        let index = pool[selection.select(r, &eligible).min(pool.len() - 1)];
+        let member = &species_lock.members[index];
+        (index, (member.0, member.1.upgrade().unwrap_or_else(|| panic!("Failed to get random species member."))))
     }
 
 }
@@ -0,0 +1,18 @@
+/// Run a block of the engine inside a `tracing` span named after the phase of evolution 
+/// it belongs to, so tools like tracing-flame can attribute time to each phase. Without
+/// the `tracing` feature this is just the block.
+#[cfg(feature = "tracing")]
+macro_rules! phase {
+    ($name:expr, $body:block) => {{
+        let _span = tracing::info_span!($name).entered();
+        $body
+    }};
+}
+
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! phase {
+    ($name:expr, $body:block) => {
+        $body
+    };
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+use std::error::Error;
+
+
+
+/// Everything that can go wrong feeding data through a network, training it, or crossing it over.
+/// These used to come back as a bare None, which left no way to tell a shape mismatch from
+/// a network which couldn't be fed forward at all.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NeatError {
+    /// the data given to a layer or network isn't the length it takes
+    InputSize { expected: usize, actual: usize },
+    /// an input was picked out by an index the network doesn't have
+    InvalidInput(usize),
+    /// the errors given to a layer's backward pass aren't the length of its output
+    ErrorSize { expected: usize, actual: usize },
+    /// the connections of a dense layer loop back on themselves so it can't be fed forward
+    Cycle,
+    /// backward was called on a layer without the forward pass it has to step back through,
+    /// the str is what was missing
+    MissingState(&'static str),
+    /// a connection or neuron of a layer points at something which isn't in the layer
+    InvalidGraph,
+    /// the network carries state from one input to the next, so it can't be analysed from its inputs alone
+    Recurrent,
+    /// a setting needed for crossover isn't set in the environment
+    MissingSetting(&'static str),
+    /// a lock or thread holding part of the network panicked
+    Poisoned,
+    /// a weight snapshot was restored to a layer or network other than the one it was taken of,
+    /// or one which has since gained or lost edges or neurons
+    SnapshotMismatch,
+    /// a placement doesn't have a position for each input and output of the dense layer it was
+    /// used on, there isn't one for each dense layer of the network, or its positions don't all
+    /// have the same number of coordinates
+    PlacementMismatch,
+    /// the network has something the operation can't handle, the str says what
+    Unsupported(&'static str)
+}
+
+
+
+impl fmt::Display for NeatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NeatError::InputSize { expected, actual } => write!(f, "input length {} does not match expected {}", actual, expected),
+            NeatError::InvalidInput(index) => write!(f, "input {} is out of range", index),
+            NeatError::ErrorSize { expected, actual } => write!(f, "error length {} does not match expected {}", actual, expected),
+            NeatError::Cycle => write!(f, "layer has a cycle of forward connections and can't be fed forward"),
+            NeatError::MissingState(state) => write!(f, "nothing to backpropagate, missing {}", state),
+            NeatError::Recurrent => write!(f, "network has recurrent state"),
+            NeatError::InvalidGraph => write!(f, "layer has a connection to a neuron which doesn't exist"),
+            NeatError::MissingSetting(key) => write!(f, "environment setting {} is not set", key),
+            NeatError::Poisoned => write!(f, "a lock or thread holding part of the network panicked"),
+            NeatError::SnapshotMismatch => write!(f, "weight snapshot was taken of a different layer"),
+            NeatError::PlacementMismatch => write!(f, "placement doesn't match the dense layers it was used on"),
+            NeatError::Unsupported(what) => write!(f, "unsupported: {}", what)
+        }
+    }
+}
+
+
+
+impl Error for NeatError {}
+
+
+
+impl NeatError {
+
+    /// check the length of data given to something which takes expected values
+    pub fn check_input(expected: usize, actual: usize) -> Result<(), NeatError> {
+        if expected != actual {
+            return Err(NeatError::InputSize { expected, actual });
+        }
+        Ok(())
+    }
+
+
+    /// check the length of errors given to something which outputs expected values
+    pub fn check_errors(expected: usize, actual: usize) -> Result<(), NeatError> {
+        if expected != actual {
+            return Err(NeatError::ErrorSize { expected, actual });
+        }
+        Ok(())
+    }
+}
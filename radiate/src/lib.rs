@@ -1,9 +1,14 @@
 
 // Bring everything in the lib into scope
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
 pub mod prelude;
 pub mod models;
 pub mod engine;
+pub mod data;
+pub mod config;
+pub mod rng;
+pub mod error;
 
 #[macro_use]
 extern crate serde_derive;
@@ -15,29 +20,96 @@ pub use models::{
             layertype::LayerType,
             dense::Dense,
             lstm::LSTM,
-            gru::GRU
+            gru::GRU,
+            sparse::SparseDense,
+            conv1d::Conv1D,
+            conv2d::Conv2D,
+            pool::MaxPool2D,
+            flatten::Flatten,
+            dropout::Dropout,
+            norm::{Norm, NormType}
         },
         neurontype::NeuronType,
         loss::Loss,
         edge::Edge,
         neuron::Neuron,
         neat::Neat,
-        neatenv::NeatEnvironment,
-        activation::Activation,
-    }
+        neatenv::{NeatEnvironment, Extension},
+        activation::{Activation, Precision},
+        benchmark::{Benchmark, BenchmarkProblem},
+        session::{TrainingSession, LearningRate},
+        golden::{Golden, GoldenMismatch},
+        migrate::{MODEL_VERSION, MigrationError},
+        profile::{Profiler, ProfileReport, LayerTiming},
+        compiled::{CompiledNeat, CompiledLayer},
+        preset,
+        topology::Topology,
+        bias::Bias,
+        perturb::WeightMutation,
+        innovation::{InnovationCounter, NodeKey, Structure},
+        optimizer::Optimizer,
+        weights::{WeightStats, WeightReport, WeightSnapshot, NetworkSnapshot},
+        prune::{DeadNeuron, DeadNeuronReport, PruneReport},
+        mutation::{Mutation, Change, MutationPreview},
+        analysis::{self, Sweep, ResponseCurve, Interval},
+        landscape::{LandscapeProbe, LandscapeReport},
+        card::ModelCard,
+        dot::DotOptions,
+        hyperneat::{Substrate, SubstrateNetwork},
+        adapt::{RateSchedule, MutationSchedule, SelfAdaptation, MutationRates},
+        wiring::Placement,
+        graph::{Graph, GraphNode, GraphEdge, GraphStats, LayerGraph, GraphReport},
+    },
+    supervised::{Network, Supervised, SupervisedReport, TargetMetrics},
+    robust::{Robustness, Aggregate, RobustnessReport},
+    adversarial::{AdversarialSearch, Search, Target, Adversary, AdversarialReport},
+    terms
 };
+#[cfg(feature = "onnx")]
+pub use models::neat::onnx;
+
+
+pub use data::{
+    dataset::DataSet,
+    batch::{BatchIter, Shuffle},
+    augment::{Augment, Augmentation},
+    scaler::Scaler
+};
+
+
+pub use config::ConfigError;
+pub use error::NeatError;
 
 
 pub use engine::{
     population::*,
     genome::Genome,
-    problem::Problem,
-    niche::Niche,
+    problem::{Problem, MultiObjective, Pareto},
+    pareto::{self, ParetoStats, ParetoFront, ParetoMember, Preferences},
+    archipelago::{Archipelago, MigrationTopology},
+    fitness::Fitness,
+    interactive::{Judgement, Judged, Surrogate},
+    evolver::{Evolver, EvolverHandle, Champion, ChampionHandle},
+    audit::{self, Divergence, Diverged},
+    niche::{Niche, NicheStats, SpeciesAging},
     generation::*,
     genocide::Genocide,
+    stagnation::{StagnationPolicy, RemovedSpecies, MutationBoost, SpeciesBoost},
     environment::Envionment,
     survival::SurvivalCriteria,
-    survival::ParentalCriteria
+    survival::ParentalCriteria,
+    survival::Elitism,
+    selection::{Selection, RouletteWheel, Tournament, Rank, Truncation, BreederPool},
+    stopping::{StoppingCriteria, StopReason},
+    history::{RunHistory, GenerationRecord},
+    speciation::{SpeciationHistory, SpeciationRecord, SpeciesRecord, Extinction},
+    compare::{self, RunComparison, RunMetric, GroupSummary, MannWhitney, ComparisonReport},
+    halloffame::{HallOfFame, Inductee},
+    drift::NeutralDrift,
+    compress::{self, Delta, DeltaSet},
+    learning::{Learning, Inheritance, Restore},
+    approximate::ApproximateScoring,
+    novelty::NoveltyArchive
 };
 
 
@@ -0,0 +1,295 @@
+//! Adversarial input search, looking for the smallest change to an input which flips the class a
+//! network gives it or moves its outputs further than a controller can tolerate. An evolved network
+//! can score well and still hinge on a knife edge somewhere in its input space, searching around
+//! the inputs it will see in deployment is a check to run on a champion before trusting it.
+
+use std::fmt;
+
+use super::supervised::Network;
+use crate::data::dataset::class_label;
+use crate::error::NeatError;
+use crate::rng;
+
+
+
+/// How perturbations are searched for
+///
+/// Random - try samples perturbations drawn uniformly from the box of the search's epsilon
+/// Gradient - take up to steps steps of step_size along the sign of the gradient of how close the
+///     input is to being flipped, staying inside the box. Backward trains the weights and scales the
+///     errors it passes down for training, so the gradient is estimated by central differences of
+///     forward passes instead, which also works through every layer and compiled networks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Search {
+    Random { samples: usize },
+    Gradient { steps: usize, step_size: f32 }
+}
+
+
+
+/// What counts as a successful perturbation
+///
+/// Classification - the class of the outputs changes, see dataset::class_label
+/// Deviation - some output moves by more than the tolerance
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Target {
+    Classification,
+    Deviation(f32)
+}
+
+
+
+/// A search for perturbations of at most epsilon in any input value (the L∞ norm). Each one found
+/// is shrunk toward the input it perturbs over refine halvings to get as close to the smallest
+/// which still succeeds as it can. Random draws come from a stream keyed by seed, see rng::scoped
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdversarialSearch {
+    pub epsilon: f32,
+    pub search: Search,
+    pub target: Target,
+    pub refine: usize,
+    pub seed: u64
+}
+
+
+
+/// The smallest perturbation found for one of the inputs searched, size is its L∞ norm
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Adversary {
+    pub sample: usize,
+    pub perturbation: Vec<f32>,
+    pub size: f32,
+    pub before: Vec<f32>,
+    pub after: Vec<f32>
+}
+
+
+
+/// What a search found over a set of inputs, one adversary for each input a perturbation was found for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdversarialReport {
+    pub samples: usize,
+    pub epsilon: f32,
+    pub found: Vec<Adversary>
+}
+
+
+
+impl AdversarialSearch {
+
+    /// a random search of 100 samples for perturbations flipping the class of the inputs
+    pub fn new(epsilon: f32) -> Self {
+        AdversarialSearch {
+            epsilon,
+            search: Search::Random { samples: 100 },
+            target: Target::Classification,
+            refine: 12,
+            seed: 0
+        }
+    }
+
+
+    pub fn random(mut self, samples: usize) -> Self {
+        self.search = Search::Random { samples };
+        self
+    }
+
+
+    pub fn gradient(mut self, steps: usize, step_size: f32) -> Self {
+        self.search = Search::Gradient { steps, step_size };
+        self
+    }
+
+
+    pub fn target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+
+    pub fn refine(mut self, refine: usize) -> Self {
+        self.refine = refine;
+        self
+    }
+
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+
+    /// Search around each of the inputs. Networks are reset before every pass so recurrent
+    /// state from one doesn't leak into the next
+    pub fn run<N: Network>(&self, model: &mut N, inputs: &[Vec<f32>]) -> Result<AdversarialReport, NeatError> {
+        let found = rng::scoped(self.seed, || {
+            inputs.iter()
+                .enumerate()
+                .map(|(sample, input)| self.search_around(model, sample, input))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        Ok(AdversarialReport {
+            samples: inputs.len(),
+            epsilon: self.epsilon,
+            found: found.into_iter().flatten().collect()
+        })
+    }
+
+
+    fn search_around<N: Network>(&self, model: &mut N, sample: usize, input: &[f32]) -> Result<Option<Adversary>, NeatError> {
+        let before = predict(model, input)?;
+        let mut best: Option<Vec<f32>> = None;
+        match self.search {
+            Search::Random { samples } => {
+                let mut r = rng::thread();
+                for _ in 0..samples {
+                    let perturbation = input.iter().map(|_| rng::uniform(&mut r, -self.epsilon, self.epsilon)).collect::<Vec<_>>();
+                    if self.succeeds(model, input, &perturbation, &before)? {
+                        let refined = self.shrink(model, input, perturbation, &before)?;
+                        if best.as_ref().is_none_or(|best| norm(&refined) < norm(best)) {
+                            best = Some(refined);
+                        }
+                    }
+                }
+            },
+            Search::Gradient { steps, step_size } => {
+                let mut perturbation = vec![0.0; input.len()];
+                for _ in 0..steps {
+                    let gradient = self.gradient_at(model, input, &perturbation, &before)?;
+                    for (delta, slope) in perturbation.iter_mut().zip(gradient.iter()) {
+                        *delta = (*delta + step_size * slope.signum()).clamp(-self.epsilon, self.epsilon);
+                    }
+                    if self.succeeds(model, input, &perturbation, &before)? {
+                        best = Some(self.shrink(model, input, perturbation, &before)?);
+                        break;
+                    }
+                }
+            }
+        }
+        match best {
+            Some(perturbation) => {
+                let after = predict(model, &apply(input, &perturbation))?;
+                Ok(Some(Adversary { sample, size: norm(&perturbation), perturbation, before, after }))
+            },
+            None => Ok(None)
+        }
+    }
+
+
+    /// how far the outputs of the perturbed input are toward succeeding, which is above 0 once they have
+    fn pressure(&self, before: &[f32], after: &[f32]) -> f32 {
+        match self.target {
+            Target::Classification => -margin(after, class_label(before)),
+            Target::Deviation(tolerance) => {
+                before.iter().zip(after.iter()).map(|(one, two)| (one - two).abs()).fold(0.0, f32::max) - tolerance
+            }
+        }
+    }
+
+
+    fn succeeds<N: Network>(&self, model: &mut N, input: &[f32], perturbation: &[f32], before: &[f32]) -> Result<bool, NeatError> {
+        let after = predict(model, &apply(input, perturbation))?;
+        Ok(match self.target {
+            Target::Classification => class_label(&after) != class_label(before),
+            Target::Deviation(_) => self.pressure(before, &after) > 0.0
+        })
+    }
+
+
+    /// the gradient of the pressure with respect to each input value by central differences
+    fn gradient_at<N: Network>(&self, model: &mut N, input: &[f32], perturbation: &[f32], before: &[f32]) -> Result<Vec<f32>, NeatError> {
+        const H: f32 = 1e-3;
+        let mut gradient = Vec::with_capacity(input.len());
+        let mut shifted = perturbation.to_vec();
+        for index in 0..input.len() {
+            shifted[index] = perturbation[index] + H;
+            let up = self.pressure(before, &predict(model, &apply(input, &shifted))?);
+            shifted[index] = perturbation[index] - H;
+            let down = self.pressure(before, &predict(model, &apply(input, &shifted))?);
+            shifted[index] = perturbation[index];
+            gradient.push((up - down) / (2.0 * H));
+        }
+        Ok(gradient)
+    }
+
+
+    /// bisect the scale of a successful perturbation between 0 and 1 for the smallest that still succeeds
+    fn shrink<N: Network>(&self, model: &mut N, input: &[f32], perturbation: Vec<f32>, before: &[f32]) -> Result<Vec<f32>, NeatError> {
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..self.refine {
+            let middle = (low + high) / 2.0;
+            let scaled = perturbation.iter().map(|delta| delta * middle).collect::<Vec<_>>();
+            if self.succeeds(model, input, &scaled, before)? {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        Ok(perturbation.iter().map(|delta| delta * high).collect())
+    }
+}
+
+
+
+/// how much more the outputs favor the class than the next best, for a single output how far it is from 0.5
+fn margin(outputs: &[f32], class: usize) -> f32 {
+    if outputs.len() == 1 {
+        return if class == 0 { 0.5 - outputs[0] } else { outputs[0] - 0.5 };
+    }
+    let others = outputs.iter()
+        .enumerate()
+        .filter(|(index, _)| *index != class)
+        .map(|(_, value)| *value)
+        .fold(f32::NEG_INFINITY, f32::max);
+    outputs.get(class).copied().unwrap_or(f32::NEG_INFINITY) - others
+}
+
+
+fn predict<N: Network>(model: &mut N, input: &[f32]) -> Result<Vec<f32>, NeatError> {
+    model.reset();
+    let outputs = model.predict(input);
+    model.reset();
+    outputs
+}
+
+
+fn apply(input: &[f32], perturbation: &[f32]) -> Vec<f32> {
+    input.iter().zip(perturbation.iter()).map(|(value, delta)| value + delta).collect()
+}
+
+
+fn norm(perturbation: &[f32]) -> f32 {
+    perturbation.iter().fold(0.0, |max, delta| f32::max(max, delta.abs()))
+}
+
+
+
+impl AdversarialReport {
+
+    /// the fraction of inputs no perturbation was found for
+    pub fn robust_fraction(&self) -> f32 {
+        if self.samples == 0 {
+            return 1.0;
+        }
+        1.0 - self.found.len() as f32 / self.samples as f32
+    }
+
+
+    /// the smallest perturbation found for any of the inputs
+    pub fn smallest(&self) -> Option<&Adversary> {
+        self.found.iter().min_by(|one, two| one.size.total_cmp(&two.size))
+    }
+}
+
+
+
+impl fmt::Display for AdversarialReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "perturbations within {} found for {} of {} inputs", self.epsilon, self.found.len(), self.samples)?;
+        writeln!(f, "{:<7} {:>10}  {:<24} {:<24}", "sample", "size", "before", "after")?;
+        for adversary in self.found.iter() {
+            writeln!(f, "{:<7} {:>10.6}  {:<24} {:<24}", adversary.sample, adversary.size, format!("{:.3?}", adversary.before), format!("{:.3?}", adversary.after))?;
+        }
+        Ok(())
+    }
+}
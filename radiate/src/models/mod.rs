@@ -1 +1,5 @@
-pub mod neat;
\ No newline at end of file
+pub mod neat;
+pub mod supervised;
+pub mod terms;
+pub mod robust;
+pub mod adversarial;
\ No newline at end of file
@@ -0,0 +1,251 @@
+//! Mutation rates which change over a run. Fixed rates force a compromise between the big
+//! structural changes that help early on and the small weight changes that fine tune a network
+//! once it is close, so the rates of a NeatEnvironment can either follow a schedule over the
+//! generations of a run or be carried by each network and evolve along with it.
+
+use super::neatenv::NeatEnvironment;
+use crate::rng;
+
+
+
+/// The value of a rate for a given generation of a run
+///
+/// Constant - the same rate every generation
+/// Linear - move evenly from start to end over the first generations, then stay at end
+/// Exponential - multiply the rate by decay every generation, never going below floor
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+pub enum RateSchedule {
+    Constant(f32),
+    Linear { start: f32, end: f32, generations: usize },
+    Exponential { initial: f32, decay: f32, floor: f32 }
+}
+
+
+/// A schedule for each of the mutation rates of a NeatEnvironment, a rate without one is left
+/// at whatever it is set to. The rates are set from their schedules before each generation is
+/// bred, so a schedule overrides any change made to the same rate from a generation hook
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy, Default)]
+#[serde(default)]
+pub struct MutationSchedule {
+    pub weight_mutate_rate: Option<RateSchedule>,
+    pub weight_perturb: Option<RateSchedule>,
+    pub new_node_rate: Option<RateSchedule>,
+    pub new_edge_rate: Option<RateSchedule>,
+    pub edit_weights: Option<RateSchedule>,
+    pub reactivate: Option<RateSchedule>
+}
+
+
+/// Self-adaptive mutation rates. Each network carries its own rates (see MutationRates) and is
+/// mutated with them instead of the environment's. A child starts from the rates of its fitter
+/// parent, or the environment's if that parent has none (ie: the first generation), and each
+/// is multiplied by exp(learning_rate * N(0, 1)) so the rates are themselves mutated and the ones
+/// which breed fitter networks are the ones that get passed on. The probabilities are kept
+/// between min_rate and max_rate, weight_perturb only above min_rate
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(default)]
+pub struct SelfAdaptation {
+    pub learning_rate: f32,
+    pub min_rate: f32,
+    pub max_rate: f32
+}
+
+
+/// The mutation rates a network was mutated with when it was bred, see SelfAdaptation
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+pub struct MutationRates {
+    pub weight_mutate_rate: f32,
+    pub weight_perturb: f32,
+    pub new_node_rate: f32,
+    pub new_edge_rate: f32,
+    pub edit_weights: f32,
+    pub reactivate: f32
+}
+
+
+
+impl RateSchedule {
+
+    pub fn rate(&self, generation: usize) -> f32 {
+        match self {
+            Self::Constant(rate) => *rate,
+            Self::Linear { start, end, generations } => {
+                let progress = (generation as f32 / (*generations).max(1) as f32).min(1.0);
+                start * (1.0 - progress) + end * progress
+            },
+            Self::Exponential { initial, decay, floor } => {
+                (initial * decay.powi(generation.min(i32::MAX as usize) as i32)).max(*floor)
+            }
+        }
+    }
+
+
+    /// every value the schedule can take, for checking them against the range of the rate
+    fn bounds(&self) -> [f32; 2] {
+        match self {
+            Self::Constant(rate) => [*rate, *rate],
+            Self::Linear { start, end, .. } => [*start, *end],
+            Self::Exponential { initial, floor, .. } => [*initial, *floor]
+        }
+    }
+}
+
+
+
+impl MutationSchedule {
+
+    pub fn new() -> Self {
+        MutationSchedule::default()
+    }
+
+
+    pub fn weight_mutate_rate(mut self, schedule: RateSchedule) -> Self {
+        self.weight_mutate_rate = Some(schedule);
+        self
+    }
+
+
+    pub fn weight_perturb(mut self, schedule: RateSchedule) -> Self {
+        self.weight_perturb = Some(schedule);
+        self
+    }
+
+
+    pub fn new_node_rate(mut self, schedule: RateSchedule) -> Self {
+        self.new_node_rate = Some(schedule);
+        self
+    }
+
+
+    pub fn new_edge_rate(mut self, schedule: RateSchedule) -> Self {
+        self.new_edge_rate = Some(schedule);
+        self
+    }
+
+
+    pub fn edit_weights(mut self, schedule: RateSchedule) -> Self {
+        self.edit_weights = Some(schedule);
+        self
+    }
+
+
+    pub fn reactivate(mut self, schedule: RateSchedule) -> Self {
+        self.reactivate = Some(schedule);
+        self
+    }
+
+
+    /// set each scheduled rate of the environment to its value for the generation
+    pub fn apply(&self, env: &mut NeatEnvironment, generation: usize) {
+        let rates = [
+            (self.weight_mutate_rate, &mut env.weight_mutate_rate),
+            (self.weight_perturb, &mut env.weight_perturb),
+            (self.new_node_rate, &mut env.new_node_rate),
+            (self.new_edge_rate, &mut env.new_edge_rate),
+            (self.edit_weights, &mut env.edit_weights),
+            (self.reactivate, &mut env.reactivate)
+        ];
+        for (schedule, rate) in rates {
+            if let Some(schedule) = schedule {
+                *rate = Some(schedule.rate(generation));
+            }
+        }
+    }
+
+
+    /// each scheduled rate with every value its schedule can take, weight_perturb is left out
+    /// as it isn't a probability
+    pub(crate) fn probabilities(&self) -> Vec<(&'static str, [f32; 2])> {
+        [
+            ("weight_mutate_rate", self.weight_mutate_rate),
+            ("new_node_rate", self.new_node_rate),
+            ("new_edge_rate", self.new_edge_rate),
+            ("edit_weights", self.edit_weights),
+            ("reactivate", self.reactivate)
+        ]
+        .iter()
+        .filter_map(|(key, schedule)| Some((*key, schedule.as_ref()?.bounds())))
+        .collect()
+    }
+
+
+    /// every value the weight_perturb schedule can take, if there is one
+    pub(crate) fn perturb_bounds(&self) -> Option<[f32; 2]> {
+        self.weight_perturb.as_ref().map(RateSchedule::bounds)
+    }
+}
+
+
+
+impl SelfAdaptation {
+
+    pub fn new(learning_rate: f32) -> Self {
+        SelfAdaptation {
+            learning_rate,
+            ..SelfAdaptation::default()
+        }
+    }
+
+
+    pub fn bounds(mut self, min_rate: f32, max_rate: f32) -> Self {
+        self.min_rate = min_rate;
+        self.max_rate = max_rate;
+        self
+    }
+
+
+    /// the rates of a child bred from a parent carrying the given rates, see SelfAdaptation
+    pub fn inherit(&self, parent: Option<&MutationRates>, env: &NeatEnvironment) -> MutationRates {
+        let base = parent.copied().unwrap_or_else(|| MutationRates::from_env(env));
+        let mut r = rng::thread();
+        let mut mutate = |rate: f32| rate * (self.learning_rate * rng::standard_normal(&mut r)).exp();
+        let probability = |rate: f32| rate.clamp(self.min_rate, self.max_rate);
+        MutationRates {
+            weight_mutate_rate: probability(mutate(base.weight_mutate_rate)),
+            weight_perturb: mutate(base.weight_perturb).max(self.min_rate),
+            new_node_rate: probability(mutate(base.new_node_rate)),
+            new_edge_rate: probability(mutate(base.new_edge_rate)),
+            edit_weights: probability(mutate(base.edit_weights)),
+            reactivate: probability(mutate(base.reactivate))
+        }
+    }
+}
+
+
+impl Default for SelfAdaptation {
+    fn default() -> Self {
+        SelfAdaptation {
+            learning_rate: 0.2,
+            min_rate: 0.001,
+            max_rate: 1.0
+        }
+    }
+}
+
+
+
+impl MutationRates {
+
+    /// the environment's rates, a rate it doesn't have is taken as 0
+    pub fn from_env(env: &NeatEnvironment) -> Self {
+        MutationRates {
+            weight_mutate_rate: env.weight_mutate_rate.unwrap_or(0.0),
+            weight_perturb: env.weight_perturb.unwrap_or(0.0),
+            new_node_rate: env.new_node_rate.unwrap_or(0.0),
+            new_edge_rate: env.new_edge_rate.unwrap_or(0.0),
+            edit_weights: env.edit_weights.unwrap_or(0.0),
+            reactivate: env.reactivate.unwrap_or(0.0)
+        }
+    }
+
+
+    /// set the rates of the environment to these
+    pub fn apply(&self, env: &mut NeatEnvironment) {
+        env.weight_mutate_rate = Some(self.weight_mutate_rate);
+        env.weight_perturb = Some(self.weight_perturb);
+        env.new_node_rate = Some(self.new_node_rate);
+        env.new_edge_rate = Some(self.new_edge_rate);
+        env.edit_weights = Some(self.edit_weights);
+        env.reactivate = Some(self.reactivate);
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt;
+use std::f32::consts::{TAU, FRAC_PI_2};
+
+use super::{
+    compiled::{CompiledNeat, CompiledLayer},
+    layers::sparse::SparseDense,
+    activation::{Activation, Precision}
+};
+use crate::error::NeatError;
+
+
+
+/// Sweep one input of a network from low to high in steps evenly spaced values (including both
+/// ends) while the rest of the inputs are held at the values of each baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sweep {
+    pub input: usize,
+    pub low: f32,
+    pub high: f32,
+    pub steps: usize
+}
+
+
+
+/// How a network's outputs respond to one input, see response_curve. For each value of the swept
+/// input there is the mean of each output over the baselines along with the least and most it was,
+/// so with a single baseline all three are the same.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseCurve {
+    pub input: usize,
+    pub values: Vec<f32>,
+    pub mean: Vec<Vec<f32>>,
+    pub min: Vec<Vec<f32>>,
+    pub max: Vec<Vec<f32>>
+}
+
+
+
+/// A closed range of values, low <= high
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Interval {
+    pub low: f32,
+    pub high: f32
+}
+
+
+
+impl Sweep {
+
+    pub fn new(input: usize, low: f32, high: f32, steps: usize) -> Self {
+        Sweep { input, low, high, steps }
+    }
+
+
+    /// the values the input is set to
+    pub fn values(&self) -> Vec<f32> {
+        match self.steps {
+            0 => Vec::new(),
+            1 => vec![self.low],
+            steps => (0..steps)
+                .map(|i| self.low + (self.high - self.low) * i as f32 / (steps - 1) as f32)
+                .collect()
+        }
+    }
+}
+
+
+
+/// Feed the compiled network every value of the sweep with the rest of the inputs taken from each
+/// baseline, to see how a controller responds to one input before trusting it. The baselines can
+/// be a single fixed operating point or rows sampled from real data to see how much the response
+/// depends on the other inputs. Recurrent layers are reset before every pass so each is independent.
+pub fn response_curve(model: &mut CompiledNeat, sweep: &Sweep, baselines: &[Vec<f32>]) -> Result<ResponseCurve, NeatError> {
+    if baselines.is_empty() {
+        return Err(NeatError::MissingState("baseline inputs"));
+    }
+    let values = sweep.values();
+    let (mut mean, mut min, mut max) = (Vec::with_capacity(values.len()), Vec::with_capacity(values.len()), Vec::with_capacity(values.len()));
+    for value in values.iter() {
+        let mut sum: Vec<f32> = Vec::new();
+        let mut least: Vec<f32> = Vec::new();
+        let mut most: Vec<f32> = Vec::new();
+        for baseline in baselines.iter() {
+            let mut inputs = baseline.clone();
+            *inputs.get_mut(sweep.input).ok_or(NeatError::InvalidInput(sweep.input))? = *value;
+            model.reset();
+            let outputs = model.forward(&inputs)?;
+            if sum.is_empty() {
+                sum = vec![0.0; outputs.len()];
+                least = vec![f32::INFINITY; outputs.len()];
+                most = vec![f32::NEG_INFINITY; outputs.len()];
+            }
+            for (i, output) in outputs.iter().enumerate() {
+                sum[i] += output;
+                least[i] = least[i].min(*output);
+                most[i] = most[i].max(*output);
+            }
+        }
+        mean.push(sum.iter().map(|total| total / baselines.len() as f32).collect());
+        min.push(least);
+        max.push(most);
+    }
+    model.reset();
+    Ok(ResponseCurve { input: sweep.input, values, mean, min, max })
+}
+
+
+
+impl Interval {
+
+    pub fn new(low: f32, high: f32) -> Self {
+        Interval { low: low.min(high), high: low.max(high) }
+    }
+
+
+    /// the interval holding only value
+    pub fn point(value: f32) -> Self {
+        Interval { low: value, high: value }
+    }
+
+
+    pub fn width(&self) -> f32 {
+        self.high - self.low
+    }
+
+
+    pub fn contains(&self, value: f32) -> bool {
+        self.low <= value && value <= self.high
+    }
+
+
+    /// true if all of self is inside of other
+    pub fn within(&self, other: &Interval) -> bool {
+        other.low <= self.low && self.high <= other.high
+    }
+
+
+    /// the ends are rounded outward, low down and high up, so the exact product is
+    /// always inside even when the f32 one was rounded toward the middle
+    fn scale(&self, weight: f32) -> Self {
+        let (one, two) = (self.low * weight, self.high * weight);
+        Interval { low: one.min(two).next_down(), high: one.max(two).next_up() }
+    }
+
+
+    /// rounded outward like scale
+    fn add(&self, other: &Interval) -> Self {
+        Interval { low: (self.low + other.low).next_down(), high: (self.high + other.high).next_up() }
+    }
+
+
+    /// every activation function but sine and swish is monotonic on either side of zero, so the ends
+    /// of its range are at the ends of the interval or at zero. Sine also reaches +/-1 at any peak
+    /// inside the interval, and swish turns at its minimum rather than at zero. The fast approximations are bounded with the exact function widened by their max error,
+    /// the strict functions are within an ulp of it, which is well under 1e-6 for anything they can return.
+    fn activate(&self, activation: Activation, precision: Precision) -> Self {
+        if activation == Activation::Sine {
+            let peak = |offset: f32| ((self.low - offset) / TAU).ceil() * TAU + offset <= self.high;
+            let bounds = Interval::new(self.low.sin(), self.high.sin());
+            return Interval {
+                low: if peak(-FRAC_PI_2) { -1.0 } else { bounds.low },
+                high: if peak(FRAC_PI_2) { 1.0 } else { bounds.high }
+            };
+        }
+        let mut bounds = Interval::new(activation.activate(self.low), activation.activate(self.high));
+        if activation == Activation::Swish {
+            // swish is smallest at the root of 1 + e^x + x = 0, decreasing before and increasing after
+            let turn = -1.278_464_5;
+            if self.contains(turn) {
+                bounds = Interval::new(bounds.low.min(activation.activate(turn)), bounds.high);
+            }
+        } else if self.contains(0.0) {
+            let zero = activation.activate(0.0);
+            bounds = Interval::new(bounds.low.min(zero), bounds.high.max(zero));
+        }
+        match (precision, activation) {
+            (Precision::Fast, Activation::Sigmoid) => Interval::new((bounds.low - 5e-5).max(0.0), (bounds.high + 5e-5).min(1.0)),
+            (Precision::Fast, Activation::Tanh) => Interval::new((bounds.low - 1e-4).max(-1.0), (bounds.high + 1e-4).min(1.0)),
+            (Precision::Strict, Activation::Sigmoid) => Interval::new((bounds.low - 1e-6).max(0.0), (bounds.high + 1e-6).min(1.0)),
+            (Precision::Strict, Activation::Tanh) => Interval::new((bounds.low - 1e-6).max(-1.0), (bounds.high + 1e-6).min(1.0)),
+            (Precision::Strict, Activation::ExpRelu(_)) | (Precision::Strict, Activation::Gaussian) => Interval::new(bounds.low - 1e-6, bounds.high + 1e-6),
+            // the error of strict swish grows with x, it is x times the error of the sigmoid
+            (Precision::Strict, Activation::Swish) => {
+                Interval::new(bounds.low - 1e-6 * bounds.low.abs().max(1.0), bounds.high + 1e-6 * bounds.high.abs().max(1.0))
+            },
+            _ => bounds
+        }
+    }
+}
+
+
+
+/// Conservative bounds of every output of a compiled feedforward network given the range
+/// each input can take, found by pushing intervals through each layer. Any input inside the
+/// ranges gives outputs inside the bounds (up to float rounding), though the bounds can be looser
+/// than what the network can actually reach when the same input takes more than one path to an
+/// output. Recurrent neurons or layers carry state from one input to the next so they can't be
+/// bounded by the inputs alone.
+pub fn output_bounds(model: &CompiledNeat, inputs: &[Interval]) -> Result<Vec<Interval>, NeatError> {
+    let mut transfer = inputs.to_vec();
+    for layer in model.layers.iter() {
+        transfer = match layer {
+            CompiledLayer::Sparse(sparse) => sparse_bounds(sparse, &transfer)?,
+            CompiledLayer::Layer(_) => return Err(NeatError::Recurrent)
+        };
+    }
+    Ok(transfer)
+}
+
+
+
+fn sparse_bounds(sparse: &SparseDense, inputs: &[Interval]) -> Result<Vec<Interval>, NeatError> {
+    NeatError::check_input(sparse.inputs.len(), inputs.len())?;
+    if !sparse.feedback.is_empty() {
+        return Err(NeatError::Recurrent);
+    }
+    let mut states = vec![Interval::point(0.0); sparse.bias.len()];
+    let mut values = vec![Interval::point(0.0); sparse.bias.len()];
+    for (index, value) in sparse.inputs.iter().zip(inputs.iter()) {
+        values[*index] = *value;
+    }
+    for (i, index) in sparse.order.iter().enumerate() {
+        let index = *index;
+        let (start, end) = (sparse.offsets[i], sparse.offsets[i + 1]);
+        let state = sparse.sources[start..end].iter()
+            .zip(sparse.weights[start..end].iter())
+            .fold(Interval::point(sparse.bias[index]), |sum, (src, weight)| sum.add(&values[*src].scale(*weight)));
+        states[index] = state;
+        values[index] = match sparse.activations[index] {
+            Activation::Softmax => Interval::point(0.0),
+            _ if sparse.recurrent[index] => return Err(NeatError::Recurrent),
+            activation => state.activate(activation, sparse.precision)
+        };
+    }
+    if sparse.activation == Activation::Softmax {
+        let states = sparse.outputs.iter().map(|index| states[*index]).collect::<Vec<_>>();
+        return Ok(softmax_bounds(&states));
+    }
+    Ok(sparse.outputs.iter().map(|index| values[*index]).collect())
+}
+
+
+
+/// each output of a softmax is smallest when its own input is lowest and all the others are
+/// highest, and largest the other way around
+fn softmax_bounds(states: &[Interval]) -> Vec<Interval> {
+    let shift = states.iter().fold(f32::NEG_INFINITY, |max, state| max.max(state.high));
+    let lows = states.iter().map(|state| (state.low - shift).exp()).collect::<Vec<_>>();
+    let highs = states.iter().map(|state| (state.high - shift).exp()).collect::<Vec<_>>();
+    let (low_total, high_total) = (lows.iter().sum::<f32>(), highs.iter().sum::<f32>());
+    (0..states.len())
+        .map(|i| Interval::new(
+            lows[i] / (lows[i] + high_total - highs[i]),
+            highs[i] / (highs[i] + low_total - lows[i])
+        ))
+        .collect()
+}
+
+
+
+impl fmt::Display for ResponseCurve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>10}  output: mean [min, max]", format!("input {}", self.input))?;
+        for (i, value) in self.values.iter().enumerate() {
+            let outputs = (0..self.mean[i].len())
+                .map(|o| format!("{:.4} [{:.4}, {:.4}]", self.mean[i][o], self.min[i][o], self.max[i][o]))
+                .collect::<Vec<_>>();
+            writeln!(f, "{:>10.4}  {}", value, outputs.join("  "))?;
+        }
+        Ok(())
+    }
+}
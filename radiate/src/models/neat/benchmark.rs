@@ -0,0 +1,97 @@
+use crate::engine::problem::Problem;
+use crate::data::{
+    dataset::DataSet,
+    synthetic
+};
+use super::neat::Neat;
+
+
+
+/// The built in benchmark problems, each one describes a synthetic dataset
+/// the network is scored against. Forecasting problems feed a window of past
+/// values through the network so they can be solved without recurrent layers.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub enum Benchmark {
+    Xor,
+    Parity(usize),
+    TwoSpirals(usize),
+    SineForecast { length: usize, window: usize },
+    MackeyGlass { length: usize, window: usize }
+}
+
+
+
+impl Benchmark {
+
+    /// generate the dataset for this benchmark, seed is ignored by the deterministic problems
+    pub fn data(&self, seed: u64) -> DataSet {
+        match self {
+            Self::Xor => synthetic::parity(2),
+            Self::Parity(bits) => synthetic::parity(*bits),
+            Self::TwoSpirals(points) => synthetic::two_spirals(*points, 0.0, seed),
+            Self::SineForecast { length, window } => synthetic::sine_forecast(*length, *window, seed),
+            Self::MackeyGlass { length, window } => synthetic::mackey_glass(*length, *window, seed)
+        }
+    }
+
+
+    /// the (input size, output size) a network needs to solve this benchmark
+    pub fn shape(&self) -> (u32, u32) {
+        match self {
+            Self::Xor => (2, 1),
+            Self::Parity(bits) => (*bits as u32, 1),
+            Self::TwoSpirals(_) => (2, 1),
+            Self::SineForecast { window, .. } | Self::MackeyGlass { window, .. } => (*window as u32, 1)
+        }
+    }
+}
+
+
+
+/// A benchmark problem scores a network by how closely it reproduces the targets of its dataset.
+/// The fitness is 1 - mean squared error so a perfect network scores 1.0
+#[derive(Debug, Clone)]
+pub struct BenchmarkProblem {
+    pub benchmark: Benchmark,
+    pub data: DataSet
+}
+
+
+
+impl BenchmarkProblem {
+
+    pub fn new(benchmark: Benchmark, seed: u64) -> Self {
+        BenchmarkProblem {
+            data: benchmark.data(seed),
+            benchmark
+        }
+    }
+}
+
+
+
+impl Problem<Neat> for BenchmarkProblem {
+
+    fn empty() -> Self { 
+        BenchmarkProblem::new(Benchmark::Xor, 0)
+    }
+
+    fn solve(&self, model: &mut Neat) -> f32 {
+        let mut total = 0.0;
+        let mut count = 0;
+        model.reset();
+        for (inputs, targets) in self.data.inputs.iter().zip(self.data.targets.iter()) {
+            match model.forward(inputs) {
+                Ok(guess) => {
+                    for (g, t) in guess.iter().zip(targets.iter()) {
+                        total += (g - t).powi(2);
+                        count += 1;
+                    }
+                },
+                Err(_) => return f32::MIN
+            }
+        }
+        model.reset();
+        1.0 - total / count.max(1) as f32
+    }
+}
@@ -0,0 +1,12 @@
+/// Where the constant offset each neuron of a dense layer adds to what it sums comes from.
+/// A bias weight on a neuron is the same as a connection to it from an input which is always
+/// 1.0, so inputs never need a constant appended to them by hand.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Bias {
+    /// every hidden and output neuron has its own bias weight, which is trained by backprop,
+    /// edited by weight mutations, and on the outputs taken from either parent in crossover
+    #[default]
+    Neuron,
+    /// the biases are held at 0 so each neuron only sees what its connections bring it
+    Off
+}
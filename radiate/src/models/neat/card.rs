@@ -0,0 +1,117 @@
+extern crate serde_json;
+
+use std::fs::File;
+use std::error::Error;
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+
+
+/// The key a model card is stored under in a saved model
+pub const CARD_KEY: &str = "card";
+
+
+
+/// A description of a trained model saved alongside it so a deployed champion says where it came
+/// from - what it was trained on, how to reproduce it, and how well it did. Everything is optional,
+/// anything without a field of its own can go in extra. The card isn't part of the network, it is
+/// only read back by Neat::load_with_card or ModelCard::read and ignored by Neat::load.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelCard {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub data: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Value>
+}
+
+
+
+impl ModelCard {
+
+    pub fn new() -> Self {
+        ModelCard::default()
+    }
+
+
+    /// what the model is for
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+
+    /// what the model was trained on
+    pub fn data(mut self, data: &str) -> Self {
+        self.data = Some(data.to_string());
+        self
+    }
+
+
+    pub fn author(mut self, author: &str) -> Self {
+        self.author = Some(author.to_string());
+        self
+    }
+
+
+    /// the seed the population or training was run with
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+
+    /// the commit of the code which trained the model
+    pub fn commit(mut self, commit: &str) -> Self {
+        self.commit = Some(commit.to_string());
+        self
+    }
+
+
+    /// record how the model scored on something, setting a metric again replaces it
+    pub fn metric(mut self, name: &str, value: f32) -> Self {
+        self.metrics.insert(name.to_string(), value);
+        self
+    }
+
+
+    /// anything else worth keeping with the model
+    pub fn extra<V: Into<Value>>(mut self, key: &str, value: V) -> Self {
+        self.extra.insert(key.to_string(), value.into());
+        self
+    }
+
+
+    /// add the card to a serialized model
+    pub fn attach(&self, model: &mut Value) -> Result<(), Box<dyn Error>> {
+        let table = model.as_object_mut().ok_or("expected the model to be an object")?;
+        table.insert(CARD_KEY.to_string(), serde_json::to_value(self)?);
+        Ok(())
+    }
+
+
+    /// the card of a serialized model, None if it was saved without one
+    pub fn from_model(model: &Value) -> Result<Option<Self>, Box<dyn Error>> {
+        match model.get(CARD_KEY) {
+            Some(card) => Ok(Some(serde_json::from_value(card.clone())?)),
+            None => Ok(None)
+        }
+    }
+
+
+    /// read just the card of a saved model without loading the network
+    pub fn read(file_path: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        let model: Value = serde_json::from_reader(File::open(file_path)?)?;
+        ModelCard::from_model(&model)
+    }
+}
@@ -0,0 +1,74 @@
+use super::{
+    neat::Neat,
+    layers::{
+        layer::Layer,
+        dense::Dense,
+        layertype::LayerType,
+        sparse::SparseDense
+    }
+};
+use crate::error::NeatError;
+
+
+
+/// A layer of a compiled network, dense layers are compiled to their sparse form
+/// while the recurrent layers, which are made of small fixed size gates, are kept as is
+#[derive(Debug, Clone)]
+pub enum CompiledLayer {
+    Sparse(Box<SparseDense>),
+    Layer(Box<dyn Layer>)
+}
+
+
+
+/// An inference only copy of a Neat network with each dense layer compiled to a SparseDense.
+/// This is what to evaluate when the same network is fed a lot of data (ie: scoring a large
+/// dataset during evolution or serving a champion), the original network is left untouched
+/// so it can keep being mutated and crossed over.
+#[derive(Debug, Clone)]
+pub struct CompiledNeat {
+    pub layers: Vec<CompiledLayer>
+}
+
+
+
+impl CompiledNeat {
+
+    /// compile the network, returns None if one of the dense layers can't be ordered
+    pub fn compile(model: &Neat) -> Option<Self> {
+        let layers = model.layers.iter()
+            .map(|wrap| match wrap.layer_type {
+                LayerType::Dense | LayerType::DensePool => {
+                    Some(CompiledLayer::Sparse(Box::new(SparseDense::compile(wrap.as_ref::<Dense>())?)))
+                },
+                _ => Some(CompiledLayer::Layer(wrap.layer.clone()))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(CompiledNeat { layers })
+    }
+
+
+    /// feed forward a vec of data through the compiled network
+    #[inline]
+    pub fn forward(&mut self, data: &[f32]) -> Result<Vec<f32>, NeatError> {
+        let mut transfer = data.to_vec();
+        for layer in self.layers.iter_mut() {
+            transfer = match layer {
+                CompiledLayer::Sparse(sparse) => sparse.forward(&transfer)?,
+                CompiledLayer::Layer(layer) => layer.forward(&transfer)?
+            };
+        }
+        Ok(transfer)
+    }
+
+
+    /// reset the state of the recurrent layers
+    pub fn reset(&mut self) {
+        for layer in self.layers.iter_mut() {
+            match layer {
+                CompiledLayer::Sparse(sparse) => sparse.reset(),
+                CompiledLayer::Layer(layer) => layer.reset()
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt::Write;
+
+use super::{
+    neat::Neat,
+    neurontype::NeuronType,
+    direction::NeuronDirection,
+    id::NeuronId,
+    layers::{
+        dense::Dense,
+        layertype::LayerType
+    }
+};
+
+
+
+/// What goes into the Graphviz description of a network, see Neat::to_dot and Dense::to_dot.
+/// color_types fills input, hidden, and output neurons with their own color, weights labels
+/// the edges with their weights and the neurons with their biases to the given number of decimal
+/// places, and disabled_edges draws the edges which were split by adding a neuron as dashed lines.
+/// Recurrent edges are always drawn in blue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DotOptions {
+    pub color_types: bool,
+    pub weights: bool,
+    pub disabled_edges: bool,
+    pub precision: usize
+}
+
+
+
+impl DotOptions {
+
+    pub fn new() -> Self {
+        DotOptions {
+            color_types: true,
+            weights: true,
+            disabled_edges: false,
+            precision: 3
+        }
+    }
+
+
+    /// fill neurons with a color for their type
+    pub fn color_types(mut self, opt: bool) -> Self {
+        self.color_types = opt;
+        self
+    }
+
+
+    /// label edges with their weights and neurons with their biases
+    pub fn weights(mut self, opt: bool) -> Self {
+        self.weights = opt;
+        self
+    }
+
+
+    /// draw disabled edges as dashed lines instead of leaving them out
+    pub fn disabled_edges(mut self, opt: bool) -> Self {
+        self.disabled_edges = opt;
+        self
+    }
+
+
+    /// decimal places of the weights and biases
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+}
+
+
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        DotOptions::new()
+    }
+}
+
+
+
+/// a whole network, each layer is a cluster and the outputs of one layer lead into the inputs
+/// of the next. Layers which aren't dense are drawn as a single box with their shape
+pub fn network(neat: &Neat, options: &DotOptions) -> String {
+    let mut dot = String::from("digraph neat {\n    rankdir=LR;\n    node [fontsize=10];\n");
+    // the neurons leading out of and into each layer, a layer drawn as a box is both
+    let mut ends: Vec<(Vec<String>, Vec<String>)> = Vec::with_capacity(neat.layers.len());
+    for (index, wrap) in neat.layers.iter().enumerate() {
+        let prefix = format!("l{}_", index);
+        let (inputs, outputs) = wrap.layer.shape();
+        writeln!(dot, "    subgraph cluster_{} {{", index).unwrap();
+        writeln!(dot, "        label=\"{}: {:?} {} -> {}\";", index, wrap.layer_type, inputs, outputs).unwrap();
+        match wrap.layer_type {
+            LayerType::Dense | LayerType::DensePool => {
+                let dense = wrap.as_ref::<Dense>();
+                layer(&mut dot, dense, &prefix, "        ", options);
+                let name = |id: &NeuronId| format!("{}n{}", prefix, id.index());
+                ends.push((dense.inputs.iter().map(name).collect(), dense.outputs.iter().map(name).collect()));
+            },
+            _ => {
+                writeln!(dot, "        {} [shape=box, label=\"{:?}\\n{} -> {}\"];", prefix, wrap.layer_type, inputs, outputs).unwrap();
+                ends.push((vec![prefix.clone()], vec![prefix]));
+            }
+        }
+        dot.push_str("    }\n");
+    }
+    for pair in ends.windows(2) {
+        let (outputs, inputs) = (&pair[0].1, &pair[1].0);
+        if outputs.len() == inputs.len() {
+            for (src, dst) in outputs.iter().zip(inputs.iter()) {
+                writeln!(dot, "    {} -> {} [style=dotted, arrowhead=none];", src, dst).unwrap();
+            }
+        } else {
+            for src in outputs.iter() {
+                for dst in inputs.iter() {
+                    writeln!(dot, "    {} -> {} [style=dotted];", src, dst).unwrap();
+                }
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+
+
+/// a single dense layer on its own
+pub fn dense(dense: &Dense, options: &DotOptions) -> String {
+    let mut dot = String::from("digraph dense {\n    rankdir=LR;\n    node [fontsize=10];\n");
+    layer(&mut dot, dense, "", "    ", options);
+    dot.push_str("}\n");
+    dot
+}
+
+
+
+/// the neurons and edges of a dense layer, named by their index after the prefix
+fn layer(dot: &mut String, dense: &Dense, prefix: &str, indent: &str, options: &DotOptions) {
+    for node in dense.nodes.iter() {
+        let (name, color) = match node.neuron_type {
+            NeuronType::Input => ("in", "lightblue"),
+            NeuronType::Hidden => ("hidden", "lightgrey"),
+            NeuronType::Output => ("out", "palegreen")
+        };
+        let mut label = format!("{} {}", name, node.id.index());
+        if node.neuron_type != NeuronType::Input {
+            write!(label, "\\n{:?}", node.activation()).unwrap();
+            if options.weights {
+                write!(label, "\\nb={:.*}", options.precision, node.bias).unwrap();
+            }
+        }
+        let shape = match node.direction() {
+            NeuronDirection::Forward => "circle",
+            NeuronDirection::Recurrent => "doublecircle"
+        };
+        write!(dot, "{}{}n{} [label=\"{}\", shape={}", indent, prefix, node.id.index(), label, shape).unwrap();
+        if options.color_types {
+            write!(dot, ", style=filled, fillcolor={}", color).unwrap();
+        }
+        dot.push_str("];\n");
+    }
+    // keep the inputs and the outputs lined up on either side of the layer
+    for ids in [&dense.inputs, &dense.outputs].iter() {
+        let names = ids.iter().map(|id| format!("{}n{}", prefix, id.index())).collect::<Vec<_>>();
+        writeln!(dot, "{}{{ rank=same; {}; }}", indent, names.join("; ")).unwrap();
+    }
+    for edge in dense.edges.iter() {
+        if !edge.active && !options.disabled_edges {
+            continue;
+        }
+        let mut attributes = Vec::new();
+        if options.weights {
+            attributes.push(format!("label=\"{:.*}\"", options.precision, edge.weight));
+        }
+        if !edge.active {
+            attributes.push(String::from("style=dashed"));
+        }
+        if edge.recurrent {
+            attributes.push(String::from("color=blue, constraint=false"));
+        }
+        write!(dot, "{}{}n{} -> {}n{}", indent, prefix, edge.src.index(), prefix, edge.dst.index()).unwrap();
+        if !attributes.is_empty() {
+            write!(dot, " [{}]", attributes.join(", ")).unwrap();
+        }
+        dot.push_str(";\n");
+    }
+}
@@ -2,6 +2,7 @@
 use uuid::Uuid;
 use super::id::*;
 use super::neuron::*;
+use crate::rng;
 
 /// Edge is a connection between two nodes in the graph
 /// 
@@ -11,6 +12,8 @@ use super::neuron::*;
 /// weight is the weight of the connection
 /// active keeps track of if this edge is active or not, meaning it will be used 
 /// while feeding data through the network
+/// recurrent edges close a cycle in the graph, they feed forward the value their src
+/// neuron had on the pass before instead of waiting on it
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Edge {
     pub id: EdgeId,
@@ -18,7 +21,9 @@ pub struct Edge {
     pub src: NeuronId,
     pub dst: NeuronId,
     pub weight: f32,
-    pub active: bool
+    pub active: bool,
+    #[serde(default)]
+    pub recurrent: bool
 }
 
 impl Edge {
@@ -27,9 +32,10 @@ impl Edge {
             id,
             src,
             dst,
-            innov: Uuid::new_v4(),
+            innov: rng::uuid(&mut rng::thread()),
             weight,
-            active
+            active,
+            recurrent: false
         }
     }
 
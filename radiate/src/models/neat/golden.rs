@@ -0,0 +1,120 @@
+extern crate serde_json;
+
+use std::fmt;
+use std::fs::File;
+use std::error::Error;
+
+use super::{
+    neat::Neat,
+    migrate
+};
+
+
+
+/// A champion stored alongside a set of probe inputs and the outputs it gave for them
+/// when it was recorded. Checking a golden reloads the champion with the current code,
+/// runs the probes again, and fails if any output has moved further than the tolerance,
+/// so refactors which change the numerical behavior of saved models get caught in tests.
+///
+/// Probes are run in order from a reset network, so recurrent models are checked
+/// on the sequence as a whole rather than on each probe independently.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Golden {
+    #[serde(with = "migrate::versioned")]
+    pub model: Neat,
+    pub probes: Vec<Vec<f32>>,
+    pub expected: Vec<Vec<f32>>
+}
+
+
+
+/// The first output which didn't match the golden
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenMismatch {
+    pub probe: usize,
+    pub output: usize,
+    pub expected: f32,
+    pub actual: Option<f32>
+}
+
+
+
+impl Golden {
+
+    /// record the model's current outputs for the probes
+    pub fn record(model: &Neat, probes: Vec<Vec<f32>>) -> Option<Self> {
+        let mut model = model.clone();
+        let expected = Self::outputs(&mut model, &probes)?;
+        Some(Golden { model, probes, expected })
+    }
+
+
+    /// check the stored model still produces the recorded outputs
+    pub fn check(&self, tolerance: f32) -> Result<(), GoldenMismatch> {
+        self.check_model(&mut self.model.clone(), tolerance)
+    }
+
+
+    /// check another model, for instance a deployed copy of the champion
+    /// or one loaded from an older save, against the recorded outputs
+    pub fn check_model(&self, model: &mut Neat, tolerance: f32) -> Result<(), GoldenMismatch> {
+        model.reset();
+        for (probe, (inputs, expected)) in self.probes.iter().zip(self.expected.iter()).enumerate() {
+            let actual = model.forward(inputs).ok();
+            for (output, expected) in expected.iter().enumerate() {
+                let val = actual.as_ref().and_then(|outs| outs.get(output)).copied();
+                let matches = val.is_some_and(|val| (val - expected).abs() <= tolerance || val == *expected);
+                if !matches {
+                    model.reset();
+                    return Err(GoldenMismatch { probe, output, expected: *expected, actual: val });
+                }
+            }
+        }
+        model.reset();
+        Ok(())
+    }
+
+
+    /// check the golden and panic with the mismatch if it fails, for use in tests
+    pub fn assert(&self, tolerance: f32) {
+        if let Err(mismatch) = self.check(tolerance) {
+            panic!("golden check failed with tolerance {}: {}", tolerance, mismatch);
+        }
+    }
+
+
+    pub fn save(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&File::create(file_path)?, &self)?;
+        Ok(())
+    }
+
+
+    pub fn load(file_path: &str) -> Result<Golden, Box<dyn Error>> {
+        Ok(serde_json::from_reader(File::open(file_path)?)?)
+    }
+
+
+    fn outputs(model: &mut Neat, probes: &[Vec<f32>]) -> Option<Vec<Vec<f32>>> {
+        model.reset();
+        let outputs = probes.iter()
+            .map(|probe| model.forward(probe).ok())
+            .collect::<Option<Vec<_>>>();
+        model.reset();
+        outputs
+    }
+}
+
+
+
+impl fmt::Display for GoldenMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.actual {
+            Some(actual) => write!(f, "probe {} output {} expected {} got {}", self.probe, self.output, self.expected, actual),
+            None => write!(f, "probe {} output {} expected {} got no output", self.probe, self.output, self.expected)
+        }
+    }
+}
+
+
+
+impl Error for GoldenMismatch {}
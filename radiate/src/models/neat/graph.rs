@@ -0,0 +1,279 @@
+use std::fmt;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use super::{
+    neat::Neat,
+    neurontype::NeuronType,
+    activation::Activation,
+    layers::{
+        dense::Dense,
+        layertype::LayerType
+    }
+};
+
+
+
+/// The topology of a dense layer pulled out of it as a plain graph to study or export, only the
+/// active edges are kept. Nodes are in the same order as the layer's neurons and edges refer to
+/// them by index, see Dense::graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub neuron_type: NeuronType,
+    pub activation: Activation,
+    pub bias: f32
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub src: usize,
+    pub dst: usize,
+    pub weight: f32
+}
+
+
+
+/// Structural properties of a graph, with the direction and weights of its edges ignored.
+///
+/// communities - the community of each node, numbered from 0 in the order they are first seen
+/// modularity - how much more the edges fall inside the communities than they would at random,
+///     from -0.5 to 1 and 0 without edges. Above about 0.3 the graph is clearly modular
+/// average_path_length - the mean number of edges on the shortest path between every pair of
+///     nodes with a path between them, 0 if there are none
+/// degrees - the number of neighbours of each node
+/// hubs - the nodes with at least twice the mean degree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphStats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub communities: Vec<usize>,
+    pub community_count: usize,
+    pub modularity: f32,
+    pub average_path_length: f32,
+    pub degrees: Vec<usize>,
+    pub mean_degree: f32,
+    pub max_degree: usize,
+    pub hubs: Vec<usize>
+}
+
+
+
+/// The graph of a dense layer of a network along with its stats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerGraph {
+    pub layer: usize,
+    pub graph: Graph,
+    pub stats: GraphStats
+}
+
+
+
+/// The graph and stats of every dense layer of a network, see Neat::graph_report. It serializes
+/// with everything in it to be analysed elsewhere
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphReport {
+    pub layers: Vec<LayerGraph>
+}
+
+
+
+impl Graph {
+
+    pub fn from_dense(dense: &Dense) -> Self {
+        Graph {
+            nodes: dense.nodes.iter()
+                .map(|node| GraphNode {
+                    neuron_type: node.neuron_type,
+                    activation: node.activation(),
+                    bias: node.bias
+                })
+                .collect(),
+            edges: dense.edges.iter()
+                .filter(|edge| edge.active)
+                .map(|edge| GraphEdge {
+                    src: edge.src.index(),
+                    dst: edge.dst.index(),
+                    weight: edge.weight
+                })
+                .collect()
+        }
+    }
+
+
+    /// the neighbours of each node with the edges taken both ways, self loops left out
+    pub fn neighbours(&self) -> Vec<BTreeSet<usize>> {
+        let mut neighbours = vec![BTreeSet::new(); self.nodes.len()];
+        for edge in self.edges.iter().filter(|edge| edge.src != edge.dst) {
+            neighbours[edge.src].insert(edge.dst);
+            neighbours[edge.dst].insert(edge.src);
+        }
+        neighbours
+    }
+
+
+    pub fn stats(&self) -> GraphStats {
+        let neighbours = self.neighbours();
+        let degrees = neighbours.iter().map(|set| set.len()).collect::<Vec<_>>();
+        let mean_degree = degrees.iter().sum::<usize>() as f32 / degrees.len().max(1) as f32;
+        let communities = communities(&neighbours);
+        GraphStats {
+            nodes: self.nodes.len(),
+            edges: self.edges.len(),
+            community_count: communities.iter().max().map_or(0, |last| last + 1),
+            modularity: modularity(&neighbours, &communities),
+            average_path_length: average_path_length(&neighbours),
+            max_degree: degrees.iter().copied().max().unwrap_or(0),
+            hubs: degrees.iter()
+                .enumerate()
+                .filter(|(_, degree)| **degree > 0 && **degree as f32 >= 2.0 * mean_degree)
+                .map(|(node, _)| node)
+                .collect(),
+            mean_degree,
+            degrees,
+            communities
+        }
+    }
+}
+
+
+
+/// Split the nodes into communities by moving each node into the community of its neighbours
+/// which raises the modularity the most, pass after pass until no move raises it (the first
+/// phase of the Louvain method). Nodes are visited in order so the split is always the same
+fn communities(neighbours: &[BTreeSet<usize>]) -> Vec<usize> {
+    let mut community = (0..neighbours.len()).collect::<Vec<_>>();
+    let two_m = neighbours.iter().map(|set| set.len()).sum::<usize>() as f32;
+    if two_m > 0.0 {
+        let mut totals = neighbours.iter().map(|set| set.len() as f32).collect::<Vec<_>>();
+        for _ in 0..neighbours.len() {
+            let mut moved = false;
+            for (node, adjacent) in neighbours.iter().enumerate() {
+                let degree = adjacent.len() as f32;
+                let current = community[node];
+                totals[current] -= degree;
+                let mut links = BTreeMap::new();
+                for neighbour in adjacent.iter() {
+                    *links.entry(community[*neighbour]).or_insert(0.0) += 1.0;
+                }
+                let gain = |target: usize, links: f32| links - totals[target] * degree / two_m;
+                let mut best = (current, gain(current, links.get(&current).copied().unwrap_or(0.0)));
+                for (target, count) in links.iter() {
+                    let value = gain(*target, *count);
+                    if value > best.1 + 1e-6 {
+                        best = (*target, value);
+                    }
+                }
+                community[node] = best.0;
+                totals[best.0] += degree;
+                moved |= best.0 != current;
+            }
+            if !moved {
+                break;
+            }
+        }
+    }
+    let mut numbers = BTreeMap::new();
+    community.iter()
+        .map(|label| {
+            let next = numbers.len();
+            *numbers.entry(*label).or_insert(next)
+        })
+        .collect()
+}
+
+
+
+/// the fraction of edges inside communities less the fraction expected if the edges were placed at random
+fn modularity(neighbours: &[BTreeSet<usize>], communities: &[usize]) -> f32 {
+    let two_m = neighbours.iter().map(|set| set.len()).sum::<usize>() as f32;
+    if two_m == 0.0 {
+        return 0.0;
+    }
+    let count = communities.iter().max().map_or(0, |last| last + 1);
+    let mut inside = vec![0.0; count];
+    let mut degrees = vec![0.0; count];
+    for (node, adjacent) in neighbours.iter().enumerate() {
+        degrees[communities[node]] += adjacent.len() as f32;
+        inside[communities[node]] += adjacent.iter().filter(|neighbour| communities[**neighbour] == communities[node]).count() as f32;
+    }
+    inside.iter()
+        .zip(degrees.iter())
+        .map(|(inside, degree)| inside / two_m - (degree / two_m).powi(2))
+        .sum()
+}
+
+
+
+/// a breadth first search from every node, summing the distances to the nodes it reaches
+fn average_path_length(neighbours: &[BTreeSet<usize>]) -> f32 {
+    let (mut total, mut pairs) = (0, 0);
+    for start in 0..neighbours.len() {
+        let mut distance = vec![None; neighbours.len()];
+        distance[start] = Some(0);
+        let mut queue = VecDeque::from(vec![start]);
+        while let Some(node) = queue.pop_front() {
+            let next = distance[node].unwrap_or(0) + 1;
+            for neighbour in neighbours[node].iter() {
+                if distance[*neighbour].is_none() {
+                    distance[*neighbour] = Some(next);
+                    total += next;
+                    pairs += 1;
+                    queue.push_back(*neighbour);
+                }
+            }
+        }
+    }
+    match pairs {
+        0 => 0.0,
+        _ => total as f32 / pairs as f32
+    }
+}
+
+
+
+impl GraphReport {
+
+    pub fn new(neat: &Neat) -> Self {
+        GraphReport {
+            layers: neat.layers.iter()
+                .enumerate()
+                .filter(|(_, wrap)| wrap.layer_type == LayerType::Dense || wrap.layer_type == LayerType::DensePool)
+                .map(|(layer, wrap)| {
+                    let graph = wrap.as_ref::<Dense>().graph();
+                    LayerGraph { layer, stats: graph.stats(), graph }
+                })
+                .collect()
+        }
+    }
+}
+
+
+
+impl fmt::Display for GraphReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<6} {:>6} {:>6} {:>11} {:>10} {:>9} {:>9} {:>5}", "layer", "nodes", "edges", "communities", "modularity", "path", "degree", "hubs")?;
+        for layer in self.layers.iter() {
+            let stats = &layer.stats;
+            writeln!(f, "{:<6} {:>6} {:>6} {:>11} {:>10.4} {:>9.4} {:>9.4} {:>5}",
+                layer.layer,
+                stats.nodes,
+                stats.edges,
+                stats.community_count,
+                stats.modularity,
+                stats.average_path_length,
+                stats.mean_degree,
+                stats.hubs.len()
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+use super::{
+    neat::Neat,
+    activation::Activation
+};
+use crate::models::supervised::Network;
+use crate::error::NeatError;
+
+
+
+/// The layout of a HyperNEAT phenotype. Instead of evolving every connection of a large network,
+/// a small CPPN (a Neat network, usually with the sine, gaussian, and abs activations of
+/// preset::cppn) is evolved and queried with the coordinates of each pair of neurons to get the
+/// weight between them. Neurons close to each other in the substrate get related weights, so
+/// the geometry of the problem (ie: the pixels of an image) is baked into the network and the
+/// CPPN stays small however many neurons the substrate has.
+///
+/// The layers are fully connected one after the other, the first layer is the inputs and the
+/// last is the outputs. Each neuron is placed at a coordinate, all of the same length. The CPPN
+/// takes the source's coordinate followed by the target's and its first output is the weight. If
+/// it has a second output that is the bias of the target, queried with the source at the origin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Substrate {
+    pub layers: Vec<Vec<Vec<f32>>>,
+    /// CPPN outputs (clamped to +/-1) closer to zero than this are no connection at all
+    pub threshold: f32,
+    pub max_weight: f32,
+    pub activation: Activation,
+    pub output_activation: Activation
+}
+
+
+
+/// The fixed topology network a Substrate builds from a CPPN. weights[l][j][i] is
+/// the weight from neuron i of layer l to neuron j of layer l + 1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubstrateNetwork {
+    pub weights: Vec<Vec<Vec<f32>>>,
+    pub biases: Vec<Vec<f32>>,
+    pub activations: Vec<Activation>
+}
+
+
+
+impl Substrate {
+
+    pub fn new(layers: Vec<Vec<Vec<f32>>>) -> Self {
+        Substrate {
+            layers,
+            threshold: 0.2,
+            max_weight: 3.0,
+            activation: Activation::Sigmoid,
+            output_activation: Activation::Sigmoid
+        }
+    }
+
+
+    /// coordinates for a layer laid out on a width by height grid spanning -1 to 1 on each side,
+    /// row by row. A single row or column sits at 0, so grid(n, 1) is a line of n neurons
+    pub fn grid(width: usize, height: usize) -> Vec<Vec<f32>> {
+        let spread = |i: usize, n: usize| if n < 2 { 0.0 } else { -1.0 + 2.0 * i as f32 / (n - 1) as f32 };
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| vec![spread(x, width), spread(y, height)]))
+            .collect()
+    }
+
+
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+
+    pub fn max_weight(mut self, max_weight: f32) -> Self {
+        self.max_weight = max_weight;
+        self
+    }
+
+
+    /// set the activation of the hidden layers
+    pub fn activation(mut self, activation: Activation) -> Self {
+        self.activation = activation;
+        self
+    }
+
+
+    pub fn output_activation(mut self, activation: Activation) -> Self {
+        self.output_activation = activation;
+        self
+    }
+
+
+    /// the length of each coordinate
+    pub fn dimensions(&self) -> usize {
+        self.layers.first().and_then(|layer| layer.first()).map(|coordinate| coordinate.len()).unwrap_or(0)
+    }
+
+
+    /// a minimal CPPN for this substrate with a weight and a bias output, evolve it with preset::cppn
+    pub fn cppn(&self) -> Neat {
+        Neat::new()
+            .input_size(2 * self.dimensions() as u32)
+            .dense_pool(2, Activation::Tanh)
+    }
+
+
+    /// Query the CPPN for every connection and bias of the substrate. Anything which feeds forward
+    /// can be the CPPN, a compiled Neat builds large substrates a good deal faster than the Neat itself.
+    pub fn build<N: Network>(&self, cppn: &mut N) -> Result<SubstrateNetwork, NeatError> {
+        let origin = vec![0.0; self.dimensions()];
+        let mut weights = Vec::with_capacity(self.layers.len().saturating_sub(1));
+        let mut biases = Vec::with_capacity(self.layers.len().saturating_sub(1));
+        for pair in self.layers.windows(2) {
+            let (sources, targets) = (&pair[0], &pair[1]);
+            let mut layer_weights = Vec::with_capacity(targets.len());
+            let mut layer_biases = Vec::with_capacity(targets.len());
+            for target in targets.iter() {
+                layer_weights.push(sources.iter()
+                    .map(|source| Ok(self.express(self.query(cppn, source, target)?[0])))
+                    .collect::<Result<Vec<_>, NeatError>>()?);
+                let outputs = self.query(cppn, &origin, target)?;
+                layer_biases.push(outputs.get(1).map(|bias| bias.clamp(-1.0, 1.0) * self.max_weight).unwrap_or(0.0));
+            }
+            weights.push(layer_weights);
+            biases.push(layer_biases);
+        }
+        let activations = (0..weights.len())
+            .map(|i| if i + 1 == weights.len() { self.output_activation } else { self.activation })
+            .collect();
+        Ok(SubstrateNetwork { weights, biases, activations })
+    }
+
+
+    fn query<N: Network>(&self, cppn: &mut N, source: &[f32], target: &[f32]) -> Result<Vec<f32>, NeatError> {
+        let inputs = source.iter().chain(target.iter()).copied().collect::<Vec<_>>();
+        cppn.reset();
+        let outputs = cppn.predict(&inputs)?;
+        if outputs.is_empty() {
+            return Err(NeatError::ErrorSize { expected: 1, actual: 0 });
+        }
+        Ok(outputs)
+    }
+
+
+    /// outputs under the threshold are no connection, the rest are scaled from the threshold up to the max weight
+    fn express(&self, output: f32) -> f32 {
+        let output = output.clamp(-1.0, 1.0);
+        if output.abs() <= self.threshold {
+            return 0.0;
+        }
+        output.signum() * (output.abs() - self.threshold) / (1.0 - self.threshold) * self.max_weight
+    }
+}
+
+
+
+impl SubstrateNetwork {
+
+    /// feed the inputs through each layer
+    pub fn forward(&self, inputs: &[f32]) -> Result<Vec<f32>, NeatError> {
+        let input_size = self.weights.first().and_then(|layer| layer.first()).map(|weights| weights.len()).unwrap_or(0);
+        NeatError::check_input(input_size, inputs.len())?;
+        let mut transfer = inputs.to_vec();
+        for ((weights, biases), activation) in self.weights.iter().zip(self.biases.iter()).zip(self.activations.iter()) {
+            let states = weights.iter()
+                .zip(biases.iter())
+                .map(|(weights, bias)| weights.iter().zip(transfer.iter()).fold(*bias, |sum, (w, x)| sum + w * x))
+                .collect::<Vec<_>>();
+            transfer = match activation {
+                Activation::Softmax => super::layers::vectorops::softmax(&states),
+                activation => states.iter().map(|state| activation.activate(*state)).collect()
+            };
+        }
+        Ok(transfer)
+    }
+
+
+    /// the number of connections the CPPN expressed
+    pub fn connections(&self) -> usize {
+        self.weights.iter().flatten().flatten().filter(|weight| **weight != 0.0).count()
+    }
+}
+
+
+
+impl Network for SubstrateNetwork {
+
+    fn predict(&mut self, inputs: &[f32]) -> Result<Vec<f32>, NeatError> {
+        self.forward(inputs)
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use uuid::{Uuid, Builder, Variant, Version};
+
+use crate::rng;
+
+
+
+/// What a neuron of a dense layer is across networks, rather than where it happens to sit in one
+/// of them (positions differ between networks which grew differently and change when a layer is
+/// compacted). The inputs, outputs and hidden neurons a layer was built with are named by their
+/// place among the neurons of their type, which every network bred from the same base shares
+///
+/// Split - a neuron added by splitting an edge, named by the innovation number of the edge made
+///     into it, which a counter marks the same for the same split in the same generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum NodeKey {
+    Input(usize),
+    Output(usize),
+    Hidden(usize),
+    Split(Uuid)
+}
+
+
+
+/// A structural mutation of a dense pool layer, what its innovation numbers are marked by
+///
+/// Connect - a new edge from src to dst
+/// SplitIn - the edge from the source of the edge a new neuron split to the new neuron
+/// SplitOut - the edge from the new neuron to the destination of the edge it split
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Structure {
+    Connect { src: NodeKey, dst: NodeKey, recurrent: bool },
+    SplitIn { edge: Uuid },
+    SplitOut { edge: Uuid }
+}
+
+
+
+/// Historical markings for the edges added by mutation, shared by every layer of every network
+/// bred from an environment (see NeatEnvironment::set_innovation_counter). The same structure added
+/// by different networks in the same generation is given the same innovation number, so crossover
+/// and speciation line those edges up as the same gene instead of as disjoint ones. Each number is
+/// drawn from a stream keyed by the salt, the generation and the structure rather than by the order
+/// they're asked for in, so networks mutated in parallel get the same numbers for the same salt.
+/// Structures are only remembered for a generation, see next_generation. A counter is saved with
+/// its salt, generation and markings, so a resumed run numbers its edges the same as it would have
+#[derive(Debug)]
+pub struct InnovationCounter {
+    salt: u64,
+    markings: Mutex<Markings>
+}
+
+
+#[derive(Debug, Default)]
+struct Markings {
+    generation: usize,
+    issued: u64,
+    seen: HashMap<Structure, Uuid>
+}
+
+
+/// what is saved of a counter, the markings in order so the same counter is always saved the same
+#[derive(Serialize, Deserialize)]
+struct SavedCounter {
+    salt: u64,
+    generation: usize,
+    issued: u64,
+    markings: Vec<(Structure, Uuid)>
+}
+
+
+
+impl InnovationCounter {
+
+    /// salt keeps the numbers of separate runs apart, a seeded run should take it from its seed
+    pub fn new(salt: u64) -> Self {
+        InnovationCounter {
+            salt,
+            markings: Mutex::new(Markings::default())
+        }
+    }
+
+
+    /// the innovation number of the structure this generation, a new one the first time it is seen
+    pub fn innovation(&self, structure: Structure) -> Uuid {
+        let mut markings = self.markings.lock().unwrap();
+        if let Some(innov) = markings.seen.get(&structure) {
+            return *innov;
+        }
+        let innov = self.draw(markings.generation, &structure);
+        markings.issued += 1;
+        markings.seen.insert(structure, innov);
+        innov
+    }
+
+
+    /// the innovation number the structure was given this generation, if it has been added yet
+    pub fn lookup(&self, structure: &Structure) -> Option<Uuid> {
+        self.markings.lock().unwrap().seen.get(structure).copied()
+    }
+
+
+    /// every structure added this generation and the innovation number it was given
+    pub fn markings(&self) -> Vec<(Structure, Uuid)> {
+        let mut markings = self.markings.lock().unwrap().seen.iter()
+            .map(|(structure, innov)| (*structure, *innov))
+            .collect::<Vec<_>>();
+        markings.sort();
+        markings
+    }
+
+
+    /// how many innovation numbers have been given out since the counter was made or reset
+    pub fn issued(&self) -> u64 {
+        self.markings.lock().unwrap().issued
+    }
+
+
+    /// the generation innovations are being marked for
+    pub fn generation(&self) -> usize {
+        self.markings.lock().unwrap().generation
+    }
+
+
+    /// forget the structures of the last generation, the same structure added again
+    /// from here on is a new innovation. Called by the environment as each generation starts
+    pub fn next_generation(&self, generation: usize) {
+        let mut markings = self.markings.lock().unwrap();
+        markings.generation = generation;
+        markings.seen.clear();
+    }
+
+
+    /// forget everything and count from 0 again
+    pub fn reset(&self) {
+        *self.markings.lock().unwrap() = Markings::default();
+    }
+
+
+    /// a (version 4) uuid mixed word by word out of a fixed encoding of the structure, see
+    /// rng::mix, so it comes out the same on every platform and toolchain
+    fn draw(&self, generation: usize, structure: &Structure) -> Uuid {
+        let key = structure.words().iter().fold(rng::mix(self.salt, generation as u64), |key, word| rng::mix(key, *word));
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&rng::mix(key, 1).to_le_bytes());
+        bytes[8..].copy_from_slice(&rng::mix(key, 2).to_le_bytes());
+        Builder::from_bytes(bytes)
+            .set_variant(Variant::RFC4122)
+            .set_version(Version::Random)
+            .build()
+    }
+}
+
+
+
+impl NodeKey {
+    fn words(&self) -> [u64; 3] {
+        match self {
+            NodeKey::Input(index) => [1, *index as u64, 0],
+            NodeKey::Output(index) => [2, *index as u64, 0],
+            NodeKey::Hidden(index) => [3, *index as u64, 0],
+            NodeKey::Split(innov) => uuid_words(4, innov)
+        }
+    }
+}
+
+
+
+impl Structure {
+    fn words(&self) -> Vec<u64> {
+        match self {
+            Structure::Connect { src, dst, recurrent } => {
+                let mut words = vec![1];
+                words.extend(src.words());
+                words.extend(dst.words());
+                words.push(*recurrent as u64);
+                words
+            },
+            Structure::SplitIn { edge } => uuid_words(2, edge).to_vec(),
+            Structure::SplitOut { edge } => uuid_words(3, edge).to_vec()
+        }
+    }
+}
+
+
+
+impl Serialize for InnovationCounter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let saved = SavedCounter {
+            salt: self.salt,
+            generation: self.generation(),
+            issued: self.issued(),
+            markings: self.markings()
+        };
+        saved.serialize(serializer)
+    }
+}
+
+
+
+impl<'de> Deserialize<'de> for InnovationCounter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedCounter::deserialize(deserializer)?;
+        Ok(InnovationCounter {
+            salt: saved.salt,
+            markings: Mutex::new(Markings {
+                generation: saved.generation,
+                issued: saved.issued,
+                seen: saved.markings.into_iter().collect()
+            })
+        })
+    }
+}
+
+
+
+fn uuid_words(tag: u64, innov: &Uuid) -> [u64; 3] {
+    let value = innov.as_u128();
+    [tag, (value >> 64) as u64, value as u64]
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use std::error::Error;
+
+use super::{
+    neat::Neat,
+    mutation::Mutation
+};
+use crate::engine::problem::Problem;
+use crate::rng;
+
+
+
+/// How to probe the fitness landscape around a network, see Neat::probe_landscape
+///
+/// samples - how many mutated copies of the network to score, each mutated once from the original
+/// walk - how many mutations in a row to take from the network for the random walk, 0 for none
+/// tolerance - how small a change in fitness is counted as no change at all (neutral)
+/// seed - the mutations are drawn from streams keyed by the seed, so a probe can be repeated
+///        with a different mutation magnitude and the only difference is the magnitude
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LandscapeProbe {
+    pub samples: usize,
+    pub walk: usize,
+    pub tolerance: f32,
+    pub seed: u64
+}
+
+
+/// What the fitness landscape looks like one mutation away from a network.
+///
+/// deltas - the fitness of each mutated copy less the fitness of the original
+/// neutral - the fraction of mutations which changed the fitness by no more than the tolerance,
+///        a landscape which is mostly neutral gives selection nothing to go on
+/// improving - the fraction which raised the fitness by more than the tolerance
+/// unchanged - the fraction which didn't change the network at all (ie: an edge which couldn't
+///        be added), counted as neutral as well
+/// autocorrelation - the correlation of the fitness of each step of the random walk with the next.
+///        Near 1 the landscape is smooth and small steps lead somewhere, near 0 (or below) every
+///        step lands somewhere unrelated. None without a walk or if the walk's fitness never changed
+/// ruggedness - 1 - autocorrelation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LandscapeReport {
+    pub fitness: f32,
+    pub deltas: Vec<f32>,
+    pub neutral: f32,
+    pub improving: f32,
+    pub unchanged: f32,
+    pub mean_delta: f32,
+    pub std_delta: f32,
+    pub best_delta: f32,
+    pub walk: Vec<f32>,
+    pub autocorrelation: Option<f32>
+}
+
+
+
+impl LandscapeProbe {
+
+    pub fn new(samples: usize) -> Self {
+        LandscapeProbe {
+            samples,
+            walk: samples,
+            tolerance: 1e-6,
+            seed: 0
+        }
+    }
+
+
+    pub fn walk(mut self, steps: usize) -> Self {
+        self.walk = steps;
+        self
+    }
+
+
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+
+
+impl LandscapeReport {
+
+    pub fn ruggedness(&self) -> Option<f32> {
+        self.autocorrelation.map(|correlation| 1.0 - correlation)
+    }
+}
+
+
+
+/// Score the network, each of the probe's samples of the mutation applied to it once and each
+/// step of a random walk of the mutation away from it on the problem, see LandscapeReport
+pub fn probe<P>(network: &Neat, problem: &P, mutation: &Mutation, probe: &LandscapeProbe) -> Result<LandscapeReport, Box<dyn Error>>
+    where P: Problem<Neat>
+{
+    let keys = rng::scoped(probe.seed, || rng::keys(probe.samples + probe.walk));
+    let fitness = problem.solve(&mut network.clone());
+
+    let mut deltas = Vec::with_capacity(probe.samples);
+    let mut unchanged = 0;
+    for key in keys.iter().take(probe.samples) {
+        let preview = network.preview_mutation(mutation, *key)?;
+        if preview.is_empty() {
+            unchanged += 1;
+        }
+        deltas.push(problem.solve(&mut preview.commit()) - fitness);
+    }
+
+    let mut walk = vec![fitness];
+    let mut current = network.clone();
+    for key in keys.iter().skip(probe.samples) {
+        current = current.preview_mutation(mutation, *key)?.commit();
+        walk.push(problem.solve(&mut current.clone()));
+    }
+
+    let count = deltas.len().max(1) as f32;
+    let mean_delta = deltas.iter().sum::<f32>() / count;
+    let variance = deltas.iter().map(|delta| (delta - mean_delta).powi(2)).sum::<f32>() / count;
+    Ok(LandscapeReport {
+        fitness,
+        neutral: deltas.iter().filter(|delta| delta.abs() <= probe.tolerance).count() as f32 / count,
+        improving: deltas.iter().filter(|delta| **delta > probe.tolerance).count() as f32 / count,
+        unchanged: unchanged as f32 / count,
+        mean_delta,
+        std_delta: variance.sqrt(),
+        best_delta: deltas.iter().copied().fold(f32::MIN, f32::max),
+        autocorrelation: autocorrelation(&walk),
+        deltas,
+        walk
+    })
+}
+
+
+
+/// the correlation of each value of the series with the next, None if there
+/// aren't two pairs to correlate or the series never changes
+fn autocorrelation(series: &[f32]) -> Option<f32> {
+    // checked directly because the mean of a constant series can be off by an ulp
+    if series.len() < 3 || series.iter().all(|value| *value == series[0]) {
+        return None;
+    }
+    let mean = series.iter().sum::<f32>() / series.len() as f32;
+    let variance = series.iter().map(|value| (value - mean).powi(2)).sum::<f32>();
+    let covariance = series.windows(2).map(|pair| (pair[0] - mean) * (pair[1] - mean)).sum::<f32>();
+    Some(covariance / variance)
+}
+
+
+
+impl fmt::Display for LandscapeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "fitness {:.6} over {} samples", self.fitness, self.deltas.len())?;
+        writeln!(f, "neutral {:.1}% (unchanged {:.1}%), improving {:.1}%", self.neutral * 100.0, self.unchanged * 100.0, self.improving * 100.0)?;
+        writeln!(f, "delta mean {:.6} std {:.6} best {:.6}", self.mean_delta, self.std_delta, self.best_delta)?;
+        match self.ruggedness() {
+            Some(ruggedness) => writeln!(f, "walk of {} steps, ruggedness {:.4}", self.walk.len() - 1, ruggedness),
+            None => writeln!(f, "walk of {} steps, ruggedness unknown", self.walk.len() - 1)
+        }
+    }
+}
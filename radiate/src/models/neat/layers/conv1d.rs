@@ -0,0 +1,408 @@
+
+extern crate rand;
+
+use std::fmt;
+use std::any::Any;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use uuid::Uuid;
+
+use super::{layer::Layer, vectorops};
+use super::super::{
+    neatenv::NeatEnvironment,
+    optimizer::{Optimizer, OptimizerState},
+    perturb::WeightEdit,
+    weights::WeightStats,
+    activation::{Activation, Precision}
+};
+
+use crate::Genome;
+use crate::engine::generation::Alignment;
+use crate::error::NeatError;
+use crate::rng;
+
+
+
+/// A one dimensional convolution over a sequence or signal. The input is read as length time steps
+/// of channels values each (step major, so the channels of one step sit next to each other) and the
+/// output is laid out the same way, one value per filter for each position the kernel is slid to.
+/// Every filter has kernel * channels weights shared across the whole sequence, so unlike a dense
+/// layer the topology never changes - only the weights are evolved or trained.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conv1D {
+    pub length: usize,
+    pub channels: usize,
+    pub filters: usize,
+    pub kernel: usize,
+    pub stride: usize,
+    pub padding: usize,
+    pub activation: Activation,
+    /// the weights of each filter, indexed by kernel offset then channel
+    pub weights: Vec<Vec<f32>>,
+    pub biases: Vec<f32>,
+    #[serde(default)]
+    pub precision: Precision,
+    #[serde(default)]
+    pub optimizer: Optimizer,
+    #[serde(skip)]
+    trace: Option<Vec<Pass>>,
+    #[serde(skip)]
+    last: Option<Pass>,
+    #[serde(skip)]
+    gradients: Option<Gradients>,
+    #[serde(default)]
+    optimizer_state: OptimizerState
+}
+
+
+
+/// the inputs and the states (before activation) of the outputs of one forward pass
+#[derive(Debug, Clone)]
+pub(super) struct Pass {
+    pub inputs: Vec<f32>,
+    pub states: Vec<f32>
+}
+
+
+
+/// the weight and bias gradients summed over a batch and the learning rate they were found with
+#[derive(Debug, Clone, Default)]
+pub(super) struct Gradients {
+    pub weights: Vec<Vec<f32>>,
+    pub biases: Vec<f32>,
+    pub rate: f32
+}
+
+
+
+impl Gradients {
+
+    /// add the gradients of one backward pass to the batch
+    pub fn add(&mut self, weights: &[Vec<f32>], biases: &[f32], rate: f32) {
+        self.weights.resize(weights.len(), vec![0.0; weights.first().map_or(0, |filter| filter.len())]);
+        self.biases.resize(biases.len(), 0.0);
+        self.rate = rate;
+        for (sum, gradients) in self.weights.iter_mut().zip(weights.iter()) {
+            vectorops::element_add(sum, gradients);
+        }
+        vectorops::element_add(&mut self.biases, biases);
+    }
+}
+
+
+
+/// take each weight and bias of the filters of a convolution from the other parent with an equal chance
+pub(super) fn cross_filters(filters: &mut [Vec<f32>], biases: &mut [f32], parent_filters: &[Vec<f32>], parent_biases: &[f32]) {
+    let mut r = rng::thread();
+    let weights = filters.iter_mut().flatten().zip(parent_filters.iter().flatten());
+    for (weight, parent_weight) in weights.chain(biases.iter_mut().zip(parent_biases.iter())) {
+        if rng::chance(&mut r, 0.5) {
+            *weight = *parent_weight;
+        }
+    }
+}
+
+
+
+/// Edit the weights of every filter randomly by either perturbing them, or giving
+/// them an entire new weight all together, the same as a dense layer does
+pub(super) fn edit_filters(filters: &mut [Vec<f32>], biases: &mut [f32], edit: &WeightEdit) {
+    let mut r = rng::thread();
+    for weight in filters.iter_mut().flatten().chain(biases.iter_mut()) {
+        *weight = edit.apply(&mut r, *weight);
+    }
+}
+
+
+
+/// step the weights and biases of every filter of a convolution by their gradients
+pub(super) fn step_filters(filters: &mut [Vec<f32>], biases: &mut [f32], state: &mut OptimizerState, optimizer: &Optimizer, gradients: (&[Vec<f32>], &[f32]), scale: f32, rate: f32) {
+    let size = filters.first().map_or(0, |filter| filter.len());
+    for (filter, (weights, weight_gradients)) in filters.iter_mut().zip(gradients.0.iter()).enumerate() {
+        for (index, (weight, gradient)) in weights.iter_mut().zip(weight_gradients.iter()).enumerate() {
+            *weight += state.step_weight(optimizer, filter * size + index, gradient * scale, rate);
+        }
+    }
+    for (index, (bias, gradient)) in biases.iter_mut().zip(gradients.1.iter()).enumerate() {
+        *bias += state.step_bias(optimizer, index, gradient * scale, rate);
+    }
+}
+
+
+
+impl Conv1D {
+
+    /// create a new convolution over a sequence of length steps with the given number of channels,
+    /// each filter gets randomly generated weights. Padding adds that many zero steps on both ends
+    /// of the sequence before the kernel is slid over it stride steps at a time.
+    pub fn new(length: usize, channels: usize, filters: usize, kernel: usize, stride: usize, padding: usize, activation: Activation) -> Self {
+        assert!(channels > 0 && filters > 0 && kernel > 0 && stride > 0, "Conv1D channels, filters, kernel and stride must be positive");
+        assert!(length + 2 * padding >= kernel, "Conv1D kernel is longer than the padded sequence");
+        assert!(activation != Activation::Softmax, "Conv1D doesn't support softmax, add a dense layer after it");
+        let mut r = rng::thread();
+        let weights = (0..filters)
+            .map(|_| (0..kernel * channels).map(|_| rng::weight(&mut r)).collect())
+            .collect();
+        let biases = (0..filters).map(|_| rng::weight(&mut r)).collect();
+        Conv1D {
+            length,
+            channels,
+            filters,
+            kernel,
+            stride,
+            padding,
+            activation,
+            weights,
+            biases,
+            precision: Precision::default(),
+            optimizer: Optimizer::default(),
+            trace: None,
+            last: None,
+            gradients: None,
+            optimizer_state: OptimizerState::default()
+        }
+    }
+
+
+    /// the number of positions the kernel is slid to along the padded sequence
+    pub fn output_length(&self) -> usize {
+        (self.length + 2 * self.padding - self.kernel) / self.stride + 1
+    }
+
+
+    /// the step of the input sequence the kernel offset of the given output position reads,
+    /// none if it falls in the padding
+    #[inline]
+    fn source(&self, position: usize, offset: usize) -> Option<usize> {
+        (position * self.stride + offset)
+            .checked_sub(self.padding)
+            .filter(|step| *step < self.length)
+    }
+
+
+    /// every weight followed by every bias
+    pub(super) fn all_weights(&self) -> Vec<f32> {
+        self.weights.iter().flatten().chain(self.biases.iter()).copied().collect()
+    }
+
+
+
+
+    /// the gradient of every weight and bias and the error of every input for one backward pass
+    fn gradients_of(&self, pass: &Pass, errors: &[f32]) -> (Vec<Vec<f32>>, Vec<f32>, Vec<f32>) {
+        let mut weights = vec![vec![0.0; self.kernel * self.channels]; self.filters];
+        let mut biases = vec![0.0; self.filters];
+        let mut input_errors = vec![0.0; self.length * self.channels];
+        for position in 0..self.output_length() {
+            for filter in 0..self.filters {
+                let index = position * self.filters + filter;
+                let step = errors[index] * self.activation.deactivate_with(pass.states[index], self.precision);
+                biases[filter] += step;
+                for offset in 0..self.kernel {
+                    if let Some(source) = self.source(position, offset) {
+                        for channel in 0..self.channels {
+                            let weight = offset * self.channels + channel;
+                            let input = source * self.channels + channel;
+                            weights[filter][weight] += step * pass.inputs[input];
+                            input_errors[input] += step * self.weights[filter][weight];
+                        }
+                    }
+                }
+            }
+        }
+        (weights, biases, input_errors)
+    }
+}
+
+
+
+#[typetag::serde]
+impl Layer for Conv1D {
+
+    /// slide every filter over the padded sequence, each output is the activated sum of the
+    /// filter's weights times the inputs under the kernel plus the filter's bias
+    fn forward(&mut self, inputs: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.length * self.channels, inputs.len())?;
+        let mut states = Vec::with_capacity(self.output_length() * self.filters);
+        for position in 0..self.output_length() {
+            for filter in 0..self.filters {
+                let mut state = self.biases[filter];
+                for offset in 0..self.kernel {
+                    if let Some(source) = self.source(position, offset) {
+                        let window = &inputs[source * self.channels..(source + 1) * self.channels];
+                        let weights = &self.weights[filter][offset * self.channels..(offset + 1) * self.channels];
+                        state += window.iter().zip(weights.iter()).map(|(input, weight)| input * weight).sum::<f32>();
+                    }
+                }
+                states.push(state);
+            }
+        }
+        let outputs = states.iter()
+            .map(|state| self.activation.activate_with(*state, self.precision))
+            .collect();
+        let pass = Pass { inputs: inputs.clone(), states };
+        match self.trace.as_mut() {
+            Some(trace) => trace.push(pass),
+            None => self.last = Some(pass)
+        }
+        Ok(outputs)
+    }
+
+
+    /// backpropagate through the most recent forward pass not yet stepped back through when traced,
+    /// otherwise through the last forward pass. The filters' weights are shared so their gradients are
+    /// summed over every position of the kernel before being stepped
+    fn backward(&mut self, errors: &Vec<f32>, learning_rate: f32) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_errors(self.output_length() * self.filters, errors.len())?;
+        let pass = match self.trace.as_mut() {
+            Some(trace) => trace.pop(),
+            None => self.last.clone()
+        };
+        let pass = pass.ok_or(NeatError::MissingState("traced forward pass"))?;
+        let (weights, biases, input_errors) = self.gradients_of(&pass, errors);
+        match self.gradients.as_mut() {
+            Some(gradients) => gradients.add(&weights, &biases, learning_rate),
+            None => {
+                self.optimizer_state.steps += 1;
+                step_filters(&mut self.weights, &mut self.biases, &mut self.optimizer_state, &self.optimizer, (&weights, &biases), 1.0, learning_rate);
+            }
+        }
+        Ok(input_errors)
+    }
+
+
+    fn reset(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.clear();
+        }
+        self.last = None;
+    }
+
+
+    fn add_tracer(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+
+    fn remove_tracer(&mut self) {
+        self.trace = None;
+    }
+
+
+    fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+
+    fn set_optimizer(&mut self, optimizer: Optimizer) {
+        if self.optimizer != optimizer {
+            self.optimizer_state = OptimizerState::default();
+        }
+        self.optimizer = optimizer;
+    }
+
+
+    fn accumulate_gradients(&mut self, accumulate: bool) {
+        self.gradients = if accumulate { Some(Gradients::default()) } else { None };
+    }
+
+
+    fn apply_gradients(&mut self, scale: f32) {
+        if let Some(gradients) = self.gradients.take() {
+            self.optimizer_state.steps += 1;
+            step_filters(&mut self.weights, &mut self.biases, &mut self.optimizer_state, &self.optimizer, (&gradients.weights, &gradients.biases), scale, gradients.rate);
+            self.gradients = Some(Gradients::default());
+        }
+    }
+
+
+    /// the filters' weights have no innovation numbers, so each is identified by its position
+    /// among the flattened weights of every filter
+    fn visit_weights_mut(&mut self, visit: &mut dyn FnMut(Uuid, &mut f32)) {
+        for (index, weight) in self.weights.iter_mut().flatten().enumerate() {
+            visit(Uuid::from_u128(index as u128), weight);
+        }
+        self.reset();
+    }
+
+
+    fn weight_stats(&self) -> WeightStats {
+        WeightStats::new(&self.all_weights())
+    }
+
+
+    fn as_ref_any(&self) -> &dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn as_mut_any(&mut self) -> &mut dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn shape(&self) -> (usize, usize) {
+        (self.length * self.channels, self.output_length() * self.filters)
+    }
+}
+
+
+
+impl Genome<Conv1D, NeatEnvironment> for Conv1D
+    where Conv1D: Layer
+{
+
+    /// the child takes each filter weight from either parent with an equal chance when crossing
+    /// over, otherwise the weights of the fitter parent may be mutated
+    fn crossover(child: &Conv1D, parent_two: &Conv1D, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Result<Conv1D, Box<dyn Error>> {
+        let mut new_child = child.clone();
+        new_child.trace = child.trace.as_ref().map(|_| Vec::new());
+        new_child.last = None;
+        let set = (*env).read().map_err(|_| NeatError::Poisoned)?;
+        let mut r = rng::thread();
+        if rng::chance(&mut r, crossover_rate) {
+            if new_child.shape() == parent_two.shape() && new_child.kernel == parent_two.kernel {
+                cross_filters(&mut new_child.weights, &mut new_child.biases, &parent_two.weights, &parent_two.biases);
+            }
+        } else if rng::chance(&mut r, set.weight_mutate_rate.ok_or(NeatError::MissingSetting("weight_mutate_rate"))?) {
+            edit_filters(&mut new_child.weights, &mut new_child.biases, &WeightEdit::from_env(&set)?);
+        }
+        Ok(new_child)
+    }
+
+
+    /// the mean absolute difference between the weights and biases of two convolutions of
+    /// the same shape, which keeps it in about the same range as the distance of a dense layer.
+    /// Convolutions of different shapes are as far apart as two dense layers sharing no edges
+    fn distance(one: &Conv1D, two: &Conv1D, _: Arc<RwLock<NeatEnvironment>>) -> f32 {
+        if one.shape() != two.shape() || one.kernel != two.kernel {
+            return 2.0;
+        }
+        let (one, two) = (one.all_weights(), two.all_weights());
+        one.iter().zip(two.iter()).map(|(a, b)| (a - b).abs()).sum::<f32>() / one.len() as f32
+    }
+
+
+    /// every weight of two convolutions of the same shape is matched, otherwise none are
+    fn alignment(one: &Conv1D, two: &Conv1D) -> Option<Alignment> {
+        let (one_size, two_size) = (one.all_weights().len(), two.all_weights().len());
+        if one.shape() == two.shape() && one.kernel == two.kernel {
+            return Some(Alignment { matched: one_size, disjoint: 0, excess: 0 });
+        }
+        Some(Alignment { matched: 0, disjoint: one_size + two_size, excess: 0 })
+    }
+}
+
+
+
+impl fmt::Display for Conv1D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Conv1D=[length={}, channels={}, filters={}, kernel={}, stride={}, padding={}]",
+            self.length, self.channels, self.filters, self.kernel, self.stride, self.padding)
+    }
+}
@@ -0,0 +1,357 @@
+
+extern crate rand;
+
+use std::fmt;
+use std::any::Any;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use uuid::Uuid;
+
+use super::{
+    layer::Layer,
+    conv1d::{Pass, Gradients, cross_filters, edit_filters, step_filters}
+};
+use super::super::{
+    neatenv::NeatEnvironment,
+    optimizer::{Optimizer, OptimizerState},
+    perturb::WeightEdit,
+    weights::WeightStats,
+    activation::{Activation, Precision}
+};
+
+use crate::Genome;
+use crate::engine::generation::Alignment;
+use crate::error::NeatError;
+use crate::rng;
+
+
+
+/// A two dimensional convolution over an image. The input is read as height rows of width pixels,
+/// each pixel being channels values next to each other (see Flatten), and the output is laid out
+/// the same way with one channel per filter. Every filter is a square kernel of weights for each
+/// channel shared across the whole image, so only the weights are evolved or trained.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conv2D {
+    pub height: usize,
+    pub width: usize,
+    pub channels: usize,
+    pub filters: usize,
+    pub kernel: usize,
+    pub stride: usize,
+    pub padding: usize,
+    pub activation: Activation,
+    /// the weights of each filter, indexed by kernel row, then kernel column, then channel
+    pub weights: Vec<Vec<f32>>,
+    pub biases: Vec<f32>,
+    #[serde(default)]
+    pub precision: Precision,
+    #[serde(default)]
+    pub optimizer: Optimizer,
+    #[serde(skip)]
+    trace: Option<Vec<Pass>>,
+    #[serde(skip)]
+    last: Option<Pass>,
+    #[serde(skip)]
+    gradients: Option<Gradients>,
+    #[serde(default)]
+    optimizer_state: OptimizerState
+}
+
+
+
+impl Conv2D {
+
+    /// create a new convolution over images of the given (height, width, channels) shape, each filter
+    /// gets randomly generated weights. Padding adds that many rows and columns of zeros around the
+    /// image before the kernel is slid over it stride pixels at a time.
+    pub fn new(shape: (usize, usize, usize), filters: usize, kernel: usize, stride: usize, padding: usize, activation: Activation) -> Self {
+        let (height, width, channels) = shape;
+        assert!(channels > 0 && filters > 0 && kernel > 0 && stride > 0, "Conv2D channels, filters, kernel and stride must be positive");
+        assert!(height + 2 * padding >= kernel && width + 2 * padding >= kernel, "Conv2D kernel is bigger than the padded image");
+        assert!(activation != Activation::Softmax, "Conv2D doesn't support softmax, add a dense layer after it");
+        let mut r = rng::thread();
+        let weights = (0..filters)
+            .map(|_| (0..kernel * kernel * channels).map(|_| rng::weight(&mut r)).collect())
+            .collect();
+        let biases = (0..filters).map(|_| rng::weight(&mut r)).collect();
+        Conv2D {
+            height,
+            width,
+            channels,
+            filters,
+            kernel,
+            stride,
+            padding,
+            activation,
+            weights,
+            biases,
+            precision: Precision::default(),
+            optimizer: Optimizer::default(),
+            trace: None,
+            last: None,
+            gradients: None,
+            optimizer_state: OptimizerState::default()
+        }
+    }
+
+
+    /// the (height, width, channels) of the image this convolution outputs
+    pub fn output_shape(&self) -> (usize, usize, usize) {
+        let slide = |size: usize| (size + 2 * self.padding - self.kernel) / self.stride + 1;
+        (slide(self.height), slide(self.width), self.filters)
+    }
+
+
+    /// the row or column of the input image the kernel offset of an output row or column
+    /// reads, none if it falls in the padding
+    #[inline]
+    fn source(&self, position: usize, offset: usize, size: usize) -> Option<usize> {
+        (position * self.stride + offset)
+            .checked_sub(self.padding)
+            .filter(|source| *source < size)
+    }
+
+
+    /// the index of the first channel of every input pixel under the kernel at an output pixel
+    /// along with the index of the first of its weights in each filter
+    fn window(&self, row: usize, column: usize) -> Vec<(usize, usize)> {
+        let mut window = Vec::with_capacity(self.kernel * self.kernel);
+        for kernel_row in 0..self.kernel {
+            if let Some(source_row) = self.source(row, kernel_row, self.height) {
+                for kernel_column in 0..self.kernel {
+                    if let Some(source_column) = self.source(column, kernel_column, self.width) {
+                        let input = (source_row * self.width + source_column) * self.channels;
+                        let weight = (kernel_row * self.kernel + kernel_column) * self.channels;
+                        window.push((input, weight));
+                    }
+                }
+            }
+        }
+        window
+    }
+
+
+    /// every weight followed by every bias
+    fn all_weights(&self) -> Vec<f32> {
+        self.weights.iter().flatten().chain(self.biases.iter()).copied().collect()
+    }
+
+
+    /// the gradient of every weight and bias and the error of every input for one backward pass
+    fn gradients_of(&self, pass: &Pass, errors: &[f32]) -> (Vec<Vec<f32>>, Vec<f32>, Vec<f32>) {
+        let mut weights = vec![vec![0.0; self.kernel * self.kernel * self.channels]; self.filters];
+        let mut biases = vec![0.0; self.filters];
+        let mut input_errors = vec![0.0; self.height * self.width * self.channels];
+        let (rows, columns, _) = self.output_shape();
+        for row in 0..rows {
+            for column in 0..columns {
+                let window = self.window(row, column);
+                for filter in 0..self.filters {
+                    let index = (row * columns + column) * self.filters + filter;
+                    let step = errors[index] * self.activation.deactivate_with(pass.states[index], self.precision);
+                    biases[filter] += step;
+                    for (input, weight) in window.iter() {
+                        for channel in 0..self.channels {
+                            weights[filter][weight + channel] += step * pass.inputs[input + channel];
+                            input_errors[input + channel] += step * self.weights[filter][weight + channel];
+                        }
+                    }
+                }
+            }
+        }
+        (weights, biases, input_errors)
+    }
+}
+
+
+
+#[typetag::serde]
+impl Layer for Conv2D {
+
+    /// slide every filter over the padded image, each output is the activated sum of the
+    /// filter's weights times the pixels under the kernel plus the filter's bias
+    fn forward(&mut self, inputs: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.height * self.width * self.channels, inputs.len())?;
+        let (rows, columns, _) = self.output_shape();
+        let mut states = Vec::with_capacity(rows * columns * self.filters);
+        for row in 0..rows {
+            for column in 0..columns {
+                let window = self.window(row, column);
+                for filter in 0..self.filters {
+                    let mut state = self.biases[filter];
+                    for (input, weight) in window.iter() {
+                        let pixel = &inputs[*input..input + self.channels];
+                        let weights = &self.weights[filter][*weight..weight + self.channels];
+                        state += pixel.iter().zip(weights.iter()).map(|(input, weight)| input * weight).sum::<f32>();
+                    }
+                    states.push(state);
+                }
+            }
+        }
+        let outputs = states.iter()
+            .map(|state| self.activation.activate_with(*state, self.precision))
+            .collect();
+        let pass = Pass { inputs: inputs.clone(), states };
+        match self.trace.as_mut() {
+            Some(trace) => trace.push(pass),
+            None => self.last = Some(pass)
+        }
+        Ok(outputs)
+    }
+
+
+    /// backpropagate through the most recent forward pass not yet stepped back through when traced,
+    /// otherwise through the last forward pass, the same as Conv1D
+    fn backward(&mut self, errors: &Vec<f32>, learning_rate: f32) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_errors(self.shape().1, errors.len())?;
+        let pass = match self.trace.as_mut() {
+            Some(trace) => trace.pop(),
+            None => self.last.clone()
+        };
+        let pass = pass.ok_or(NeatError::MissingState("traced forward pass"))?;
+        let (weights, biases, input_errors) = self.gradients_of(&pass, errors);
+        match self.gradients.as_mut() {
+            Some(gradients) => gradients.add(&weights, &biases, learning_rate),
+            None => {
+                self.optimizer_state.steps += 1;
+                step_filters(&mut self.weights, &mut self.biases, &mut self.optimizer_state, &self.optimizer, (&weights, &biases), 1.0, learning_rate);
+            }
+        }
+        Ok(input_errors)
+    }
+
+
+    fn reset(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.clear();
+        }
+        self.last = None;
+    }
+
+
+    fn add_tracer(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+
+    fn remove_tracer(&mut self) {
+        self.trace = None;
+    }
+
+
+    fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+
+    fn set_optimizer(&mut self, optimizer: Optimizer) {
+        if self.optimizer != optimizer {
+            self.optimizer_state = OptimizerState::default();
+        }
+        self.optimizer = optimizer;
+    }
+
+
+    fn accumulate_gradients(&mut self, accumulate: bool) {
+        self.gradients = if accumulate { Some(Gradients::default()) } else { None };
+    }
+
+
+    fn apply_gradients(&mut self, scale: f32) {
+        if let Some(gradients) = self.gradients.take() {
+            self.optimizer_state.steps += 1;
+            step_filters(&mut self.weights, &mut self.biases, &mut self.optimizer_state, &self.optimizer, (&gradients.weights, &gradients.biases), scale, gradients.rate);
+            self.gradients = Some(Gradients::default());
+        }
+    }
+
+
+    /// like Conv1D, each weight is identified by its position among the flattened weights of every filter
+    fn visit_weights_mut(&mut self, visit: &mut dyn FnMut(Uuid, &mut f32)) {
+        for (index, weight) in self.weights.iter_mut().flatten().enumerate() {
+            visit(Uuid::from_u128(index as u128), weight);
+        }
+        self.reset();
+    }
+
+
+    fn weight_stats(&self) -> WeightStats {
+        WeightStats::new(&self.all_weights())
+    }
+
+
+    fn as_ref_any(&self) -> &dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn as_mut_any(&mut self) -> &mut dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn shape(&self) -> (usize, usize) {
+        let (rows, columns, filters) = self.output_shape();
+        (self.height * self.width * self.channels, rows * columns * filters)
+    }
+}
+
+
+
+impl Genome<Conv2D, NeatEnvironment> for Conv2D
+    where Conv2D: Layer
+{
+
+    /// the child takes each kernel weight from either parent with an equal chance when crossing
+    /// over, otherwise the weights of the fitter parent may be mutated
+    fn crossover(child: &Conv2D, parent_two: &Conv2D, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Result<Conv2D, Box<dyn Error>> {
+        let mut new_child = child.clone();
+        new_child.trace = child.trace.as_ref().map(|_| Vec::new());
+        new_child.last = None;
+        let set = (*env).read().map_err(|_| NeatError::Poisoned)?;
+        let mut r = rng::thread();
+        if rng::chance(&mut r, crossover_rate) {
+            if new_child.shape() == parent_two.shape() && new_child.kernel == parent_two.kernel {
+                cross_filters(&mut new_child.weights, &mut new_child.biases, &parent_two.weights, &parent_two.biases);
+            }
+        } else if rng::chance(&mut r, set.weight_mutate_rate.ok_or(NeatError::MissingSetting("weight_mutate_rate"))?) {
+            edit_filters(&mut new_child.weights, &mut new_child.biases, &WeightEdit::from_env(&set)?);
+        }
+        Ok(new_child)
+    }
+
+
+    /// the mean absolute difference between the kernels and biases of two convolutions of the
+    /// same shape, or 2.0 if their shapes differ, the same as Conv1D
+    fn distance(one: &Conv2D, two: &Conv2D, _: Arc<RwLock<NeatEnvironment>>) -> f32 {
+        if one.shape() != two.shape() || one.kernel != two.kernel {
+            return 2.0;
+        }
+        let (one, two) = (one.all_weights(), two.all_weights());
+        one.iter().zip(two.iter()).map(|(a, b)| (a - b).abs()).sum::<f32>() / one.len() as f32
+    }
+
+
+    /// every weight of two convolutions of the same shape is matched, otherwise none are
+    fn alignment(one: &Conv2D, two: &Conv2D) -> Option<Alignment> {
+        let (one_size, two_size) = (one.all_weights().len(), two.all_weights().len());
+        if one.shape() == two.shape() && one.kernel == two.kernel {
+            return Some(Alignment { matched: one_size, disjoint: 0, excess: 0 });
+        }
+        Some(Alignment { matched: 0, disjoint: one_size + two_size, excess: 0 })
+    }
+}
+
+
+
+impl fmt::Display for Conv2D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Conv2D=[height={}, width={}, channels={}, filters={}, kernel={}, stride={}, padding={}]",
+            self.height, self.width, self.channels, self.filters, self.kernel, self.stride, self.padding)
+    }
+}
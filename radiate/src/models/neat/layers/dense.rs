@@ -2,12 +2,15 @@ extern crate rand;
 
 use std::fmt;
 use std::any::Any;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::error::Error;
 use std::sync::{Arc, RwLock};
 use rand::Rng;
 use rand::seq::SliceRandom;
 
 use uuid::Uuid;
+#[cfg(feature = "ndarray")]
+use ndarray::{Array1, Array2};
 
 use super::{
     layertype::LayerType,
@@ -21,40 +24,111 @@ use super::super::{
     tracer::Tracer,
     neatenv::NeatEnvironment,
     neurontype::NeuronType,
-    activation::Activation,
-    direction::NeuronDirection
+    topology::Topology,
+    optimizer::{Optimizer, OptimizerState},
+    bias::Bias,
+    perturb::WeightEdit,
+    innovation::{InnovationCounter, NodeKey, Structure},
+    weights::{WeightStats, WeightSnapshot},
+    activation::{Activation, Precision},
+    direction::NeuronDirection,
+    dot::{self, DotOptions},
+    wiring::Placement,
+    prune::PruneReport,
+    graph::Graph
 };
 
 use crate::Genome;
+use crate::engine::generation::Alignment;
+use crate::error::NeatError;
+use crate::rng;
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dense {
     pub inputs: Vec<NeuronId>,
     pub outputs: Vec<NeuronId>,
-    pub nodes: Vec<Neuron>,
-    pub edges: Vec<Edge>,
-    pub edge_innov_map: HashMap<Uuid, EdgeId>,
+    /// the neurons, which hold a copy of the weights coming into them, the edges and the innovation
+    /// map are shared between a layer and its clones until one of them changes them, so offspring
+    /// don't copy the whole graph just to be bred
+    pub nodes: Arc<Vec<Neuron>>,
+    pub edges: Arc<Vec<Edge>>,
+    /// ordered by innovation so serializing a layer, or walking its edges by innovation,
+    /// comes out the same on every run
+    pub edge_innov_map: Arc<BTreeMap<Uuid, EdgeId>>,
+    /// what each neuron holds from pass to pass, indexed the same as the nodes. It is the layer's
+    /// own so running the layer leaves the nodes shared, see NeuronState
+    #[serde(skip)]
+    pub states: Vec<NeuronState>,
     pub trace_states: Option<Tracer>,
     pub layer_type: LayerType,
     pub activation: Activation,
+    #[serde(default)]
+    pub precision: Precision,
+    #[serde(default)]
+    pub optimizer: Optimizer,
+    #[serde(default)]
+    pub bias: Bias,
     fast_mode: bool,
+    #[serde(skip)]
+    gradients: Option<Gradients>,
+    #[serde(default)]
+    optimizer_state: OptimizerState,
+    #[cfg(feature = "ndarray")]
+    #[serde(skip)]
+    matrix: Option<Arc<WeightMatrix>>
+}
+
+
+
+/// the weight and bias gradients summed over a batch, indexed the same as the edges and nodes,
+/// and the learning rate they were found with so the optimizer can step them once the batch is done
+#[derive(Debug, Clone, Default)]
+struct Gradients {
+    edges: Vec<f32>,
+    biases: Vec<f32>,
+    rate: f32
+}
+
+
+/// the weights of a layer as an (outputs, inputs) matrix and the biases of its outputs, kept
+/// along with the edges they were read from. Changing a weight takes the layer's own copy of its
+/// edges (see Arc::make_mut) so the layer no longer holds those edges and the matrix is read again
+#[cfg(feature = "ndarray")]
+#[derive(Debug)]
+struct WeightMatrix {
+    edges: Arc<Vec<Edge>>,
+    weights: Array2<f32>,
+    biases: Array1<f32>
 }
 
 impl Dense {
     /// create a new fully connected dense layer.
     /// Each input is connected to each output with a randomly generated weight attached to the connection
     pub fn new(num_in: u32, num_out: u32, layer_type: LayerType, activation: Activation) -> Self {
+        Dense::with_topology(num_in, num_out, layer_type, activation, &Topology::FullyConnected)
+    }
+
+    /// create a new dense layer with its starting connections laid out by the topology, see Topology
+    pub fn with_topology(num_in: u32, num_out: u32, layer_type: LayerType, activation: Activation, topology: &Topology) -> Self {
         let mut layer = Dense {
             inputs: vec![],
             outputs: vec![],
-            nodes: vec![],
-            edges: vec![],
-            edge_innov_map: HashMap::new(),
+            nodes: Arc::new(vec![]),
+            edges: Arc::new(vec![]),
+            edge_innov_map: Arc::new(BTreeMap::new()),
+            states: vec![],
             trace_states: None, 
             layer_type,
             activation,
+            precision: Precision::Exact,
+            optimizer: Optimizer::Sgd,
+            bias: Bias::Neuron,
             fast_mode: true,
+            gradients: None,
+            optimizer_state: OptimizerState::default(),
+            #[cfg(feature = "ndarray")]
+            matrix: None
         };
 
         let mut inputs = Vec::with_capacity(num_in as usize);
@@ -68,77 +142,255 @@ impl Dense {
             outputs.push(node_id);
         }
 
-        let mut r = rand::thread_rng();
-        for node_in in inputs.iter() {
-            for node_out in outputs.iter() {
-                let weight = r.gen::<f32>() * 2.0 - 1.0;
-                layer.make_edge(*node_in, *node_out, weight);
+        let mut r = rng::thread();
+        match topology {
+            Topology::FullyConnected => layer.connect(&inputs, &outputs),
+            Topology::Sparse(probability) => {
+                // fast mode feeds every input to every output so it can't skip connections
+                layer.fast_mode = false;
+                for node_out in outputs.iter() {
+                    let mut connected = false;
+                    for node_in in inputs.iter() {
+                        if rng::chance(&mut r, *probability) {
+                            let weight = rng::weight(&mut r);
+                            layer.make_edge(*node_in, *node_out, weight);
+                            connected = true;
+                        }
+                    }
+                    if let (false, Some(node_in)) = (connected, inputs.choose(&mut r)) {
+                        let weight = rng::weight(&mut r);
+                        layer.make_edge(*node_in, *node_out, weight);
+                    }
+                }
+            },
+            Topology::Layered { hidden, activation } => {
+                let mut previous = inputs.clone();
+                for size in hidden.iter().filter(|size| **size > 0) {
+                    layer.fast_mode = false;
+                    let current = (0..*size)
+                        .map(|_| layer.make_node(NeuronType::Hidden, *activation, NeuronDirection::Forward))
+                        .collect::<Vec<_>>();
+                    layer.connect(&previous, &current);
+                    previous = current;
+                }
+                layer.connect(&previous, &outputs);
             }
         }
         layer.inputs = inputs;
         layer.outputs = outputs;
+        // name each neuron by its place among the neurons of its type, see NodeKey
+        let nodes = Arc::make_mut(&mut layer.nodes);
+        for (index, id) in layer.inputs.iter().enumerate() {
+            nodes[id.index()].key = Some(NodeKey::Input(index));
+        }
+        for (index, id) in layer.outputs.iter().enumerate() {
+            nodes[id.index()].key = Some(NodeKey::Output(index));
+        }
+        let hidden = nodes.iter_mut().filter(|node| node.neuron_type == NeuronType::Hidden);
+        for (index, node) in hidden.enumerate() {
+            node.key = Some(NodeKey::Hidden(index));
+        }
 
         layer
     }
 
+    /// connect every one of the sending nodes to every one of the receiving nodes with a random weight
+    fn connect(&mut self, sending: &[NeuronId], receiving: &[NeuronId]) {
+        let mut r = rng::thread();
+        for node_in in sending.iter() {
+            for node_out in receiving.iter() {
+                let weight = rng::weight(&mut r);
+                self.make_edge(*node_in, *node_out, weight);
+            }
+        }
+    }
+
     /// Make a new node
     fn make_node(&mut self, neuron_type: NeuronType, activation: Activation, direction: NeuronDirection) -> NeuronId {
         let node_id = NeuronId::new(self.nodes.len());
-        let node = Neuron::new(node_id, neuron_type, activation, direction);
+        let mut node = Neuron::new(node_id, neuron_type, activation, direction);
+        if self.bias == Bias::Off {
+            node.bias = 0.0;
+        }
         // Create a new node and add it to the node list.
-        self.nodes.push(node);
+        Arc::make_mut(&mut self.nodes).push(node);
+        self.sync_states();
 
         node_id
     }
 
     /// Make a new edge
     fn make_edge(&mut self, src: NeuronId, dst: NeuronId, weight: f32) -> EdgeId {
+        self.make_edge_with(src, dst, weight, false)
+    }
+
+    /// Make a new edge, recurrent or not
+    fn make_edge_with(&mut self, src: NeuronId, dst: NeuronId, weight: f32, recurrent: bool) -> EdgeId {
         let edge_id = EdgeId::new(self.edges.len());
         // Create a new edge and add it to the edge list.
-        let edge = Edge::new(edge_id, src, dst, weight, true);
-        edge.link_nodes(&mut self.nodes);
+        let mut edge = Edge::new(edge_id, src, dst, weight, true);
+        edge.recurrent = recurrent;
+        edge.link_nodes(Arc::make_mut(&mut self.nodes).as_mut_slice());
 
-        self.edge_innov_map.insert(edge.innov, edge_id);
-        self.edges.push(edge);
+        Arc::make_mut(&mut self.edge_innov_map).insert(edge.innov, edge_id);
+        Arc::make_mut(&mut self.edges).push(edge);
 
         edge_id
     }
 
+    /// what the neuron held after the last pass, a neuron the layer hasn't been run with holds nothing
+    pub fn state(&self, id: NeuronId) -> NeuronState {
+        self.states.get(id.index()).copied().unwrap_or_default()
+    }
+
+    /// give every neuron a state, the states aren't saved with the layer so one read back starts them from nothing
+    fn sync_states(&mut self) {
+        self.states.resize(self.nodes.len(), NeuronState::default());
+    }
+
+    /// What a neuron is across networks, see NodeKey. Neurons of a layer saved before
+    /// keys were kept are named by their place in the layer instead
+    pub fn node_key(&self, id: NeuronId) -> NodeKey {
+        let node = &self.nodes[id.index()];
+        node.key.unwrap_or_else(|| match node.neuron_type {
+            NeuronType::Input => NodeKey::Input(self.inputs.iter().position(|input| *input == id).unwrap_or(id.index())),
+            NeuronType::Output => NodeKey::Output(self.outputs.iter().position(|output| *output == id).unwrap_or(id.index())),
+            NeuronType::Hidden => NodeKey::Hidden(id.index())
+        })
+    }
+
+    /// Give an edge the innovation number the counter marks its structure with, unless another
+    /// edge of the layer already has it, ie: a neuron split again from a re-enabled edge
+    fn mark_edge(&mut self, edge_id: EdgeId, structure: Structure, innovations: Option<&InnovationCounter>) {
+        let innov = match innovations {
+            Some(counter) => counter.innovation(structure),
+            None => return
+        };
+        if self.contains_edge(&innov) {
+            return;
+        }
+        let edges = Arc::make_mut(&mut self.edges);
+        let map = Arc::make_mut(&mut self.edge_innov_map);
+        if let Some(edge) = edges.get_mut(edge_id.index()) {
+            map.remove(&edge.innov);
+            map.insert(innov, edge_id);
+            edge.innov = innov;
+        }
+    }
+
     /// Disable an edge.
     fn disable_edge(&mut self, edge_id: EdgeId) {
-        let edges = &mut self.edges;
+        let edges = Arc::make_mut(&mut self.edges);
         // disable edge
         if let Some(edge) = edges.get_mut(edge_id.index()) {
-          edge.disable(&mut self.nodes)
+          edge.disable(Arc::make_mut(&mut self.nodes).as_mut_slice())
         }
     }
 
+    /// Check if this layer still shares its edges with another, which is 
+    /// the case for a clone until either of them mutates its edges
+    pub fn shares_edges(&self, other: &Dense) -> bool {
+        Arc::ptr_eq(&self.edges, &other.edges)
+    }
+
     /// Get edge by innov
     pub fn get_edge_by_innov(&self, innov: &Uuid) -> Option<&Edge> {
         self.edge_innov_map.get(innov).and_then(|edge_id| self.edges.get(edge_id.index()))
     }
 
+    /// take a hidden neuron whose activation is always value out of the layer without changing what
+    /// the layer outputs. What the neuron sends along each of its connections is folded into the bias
+    /// of the neuron receiving it, then every connection to and from the neuron is disabled. The
+    /// neuron itself stays so ids don't change. Returns the number of connections disabled. With
+    /// the biases off a neuron which sends anything but 0 along a connection can't be pruned
+    pub fn prune_neuron(&mut self, neuron: NeuronId, value: f32) -> Result<usize, NeatError> {
+        let node = self.nodes.get(neuron.index()).ok_or(NeatError::InvalidGraph)?;
+        if node.neuron_type != NeuronType::Hidden {
+            return Err(NeatError::InvalidGraph);
+        }
+        let connected = self.edges.iter()
+            .filter(|edge| edge.active && (edge.src == neuron || edge.dst == neuron))
+            .map(|edge| edge.id)
+            .collect::<Vec<_>>();
+        if connected.is_empty() {
+            return Ok(0);
+        }
+        if self.bias == Bias::Off && self.edges.iter().any(|edge| edge.active && edge.src == neuron && value * edge.weight != 0.0) {
+            return Err(NeatError::Unsupported("pruning a neuron into biases which are off"));
+        }
+        let edges = Arc::make_mut(&mut self.edges);
+        for edge_id in connected.iter() {
+            let edge = &mut edges[edge_id.index()];
+            if edge.src == neuron {
+                let receiver = Arc::make_mut(&mut self.nodes).get_mut(edge.dst.index()).ok_or(NeatError::InvalidGraph)?;
+                receiver.bias += value * edge.weight;
+            }
+            edge.disable(Arc::make_mut(&mut self.nodes).as_mut_slice());
+        }
+        Ok(connected.len())
+    }
+
+    /// the weights of the connections which are in use
+    pub fn active_weights(&self) -> Vec<f32> {
+        self.edges.iter()
+            .filter(|edge| edge.active)
+            .map(|edge| edge.weight)
+            .collect()
+    }
+
+    /// the summed length of the connections which are in use with the layer laid out by the placement
+    pub fn wiring_cost(&self, placement: &Placement) -> Result<f32, NeatError> {
+        placement.wiring_cost(self)
+    }
+
     /// Check if this layer contains an edge.
     pub fn contains_edge(&self, innov: &Uuid) -> bool {
         self.get_edge_by_innov(innov).is_some()
     }
 
+    /// the errors of softmax outputs through the softmax jacobian, 
+    /// output_i * (error_i - Σ(error_j * output_j))
+    fn softmax_errors(&self, errors: &[f32]) -> Result<Vec<f32>, NeatError> {
+        let outputs = self.outputs.iter()
+            .map(|id| match &self.trace_states {
+                Some(tracer) => Ok(tracer.neuron_activation(*id)),
+                None => self.states.get(id.index()).map(|state| state.activated_value).ok_or(NeatError::InvalidGraph)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let weighted = errors.iter().zip(outputs.iter()).map(|(error, output)| error * output).sum::<f32>();
+        Ok(errors.iter()
+            .zip(outputs.iter())
+            .map(|(error, output)| output * (error - weighted))
+            .collect())
+    }
+
     /// reset all the neurons in the network so they can be fed forward again
     fn reset_neurons(&mut self) {
-        for val in self.nodes.iter_mut() {
-            val.reset_neuron();
+        for state in self.states.iter_mut() {
+            state.reset();
         }
     }
 
+    /// A Graphviz description of the layer's neurons and edges, see Neat::to_dot
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        dot::dense(self, options)
+    }
+
+    /// The layer's active topology as a plain graph, see Graph
+    pub fn graph(&self) -> Graph {
+        Graph::from_dense(self)
+    }
+
     /// get the outputs from the layer in a vec form
-    pub fn get_outputs(&self) -> Option<Vec<f32>> {
-        let result = self.outputs
+    pub fn get_outputs(&self) -> Result<Vec<f32>, NeatError> {
+        self.outputs
             .iter()
             .map(|x| {
-                self.nodes.get(x.index()).unwrap().activated_value
+                self.states.get(x.index())
+                    .map(|state| state.activated_value)
+                    .ok_or(NeatError::InvalidGraph)
             })
-            .collect::<Vec<_>>();
-        Some(result)
+            .collect()
     }
 
     /// Add a node to the network by getting a random edge 
@@ -147,10 +399,15 @@ impl Dense {
     /// while the new weight is randomly chosen and put between the 
     /// old source node and the new node
     pub fn add_node(&mut self, activation: Activation, direction: NeuronDirection) {
+        self.add_marked_node(activation, direction, None);
+    }
+
+    /// add a node like add_node, marking the two new edges with the counter's innovation numbers
+    pub(crate) fn add_marked_node(&mut self, activation: Activation, direction: NeuronDirection, innovations: Option<&InnovationCounter>) {
         assert!(self.layer_type == LayerType::DensePool);
 
-        // Restrict layer size to the maximum supported neurons.
-        if self.nodes.len() == NeuronId::MAX {
+        // Restrict layer size to the maximum supported neurons, and there has to be an edge to split.
+        if self.nodes.len() == NeuronId::MAX || self.edges.is_empty() {
             return;
         }
 
@@ -164,9 +421,13 @@ impl Dense {
         let curr_edge = self.random_edge().clone();
 
         // create two new edges that connect the src and the new node and the 
-        // new node and dst, then disable the current edge 
-        self.make_edge(curr_edge.src, new_node_id, 1.0);
-        self.make_edge(new_node_id, curr_edge.dst, curr_edge.weight);
+        // new node and dst, then disable the current edge. Splitting a recurrent
+        // edge keeps the delay on the edge going into dst, which closes the cycle
+        let incoming = self.make_edge(curr_edge.src, new_node_id, 1.0);
+        let outgoing = self.make_edge_with(new_node_id, curr_edge.dst, curr_edge.weight, curr_edge.recurrent);
+        self.mark_edge(incoming, Structure::SplitIn { edge: curr_edge.innov }, innovations);
+        self.mark_edge(outgoing, Structure::SplitOut { edge: curr_edge.innov }, innovations);
+        Arc::make_mut(&mut self.nodes)[new_node_id.index()].key = Some(NodeKey::Split(self.edges[incoming.index()].innov));
 
         // disable current edge
         self.disable_edge(curr_edge.id);
@@ -177,6 +438,18 @@ impl Dense {
     /// that the desired connection can be made. If it can be, make the connection
     /// with a weight of .5 in order to minimally impact the network 
     pub fn add_edge(&mut self) {
+        self.grow_edge(false, None);
+    }
+
+    /// add a connection to the network like add_edge, but one which would close a cycle (including
+    /// a neuron connected back to itself) is made as a recurrent edge instead of being given up on
+    pub fn add_recurrent_edge(&mut self) {
+        self.grow_edge(true, None);
+    }
+
+    /// add a connection like add_edge, or add_recurrent_edge if recurrent, marking it
+    /// with the counter's innovation number if there is one
+    pub(crate) fn grow_edge(&mut self, recurrent: bool, innovations: Option<&InnovationCounter>) {
         assert!(self.layer_type == LayerType::DensePool);
 
         // Restrict layer size to the maximum supported edges.
@@ -195,8 +468,216 @@ impl Dense {
         // determine if the connection to be made is valid 
         if self.valid_connection(sending, receiving) {
             // if the connection is valid, make it and wire the nodes to each
-            let mut r = rand::thread_rng();
-            self.make_edge(sending, receiving, r.gen::<f32>());
+            let weight = rng::weight(&mut rng::thread());
+            let edge = self.make_edge(sending, receiving, weight);
+            self.mark_edge(edge, Structure::Connect { src: self.node_key(sending), dst: self.node_key(receiving), recurrent: false }, innovations);
+        } else if recurrent && !self.exists(sending, receiving) {
+            let weight = rng::weight(&mut rng::thread());
+            let edge = self.make_edge_with(sending, receiving, weight, true);
+            self.mark_edge(edge, Structure::Connect { src: self.node_key(sending), dst: self.node_key(receiving), recurrent: true }, innovations);
+        }
+    }
+
+    /// take a random active connection out of the layer altogether, rather than just disabling it,
+    /// along with any hidden neuron it leaves without an active connection going out
+    pub fn remove_edge(&mut self) {
+        assert!(self.layer_type == LayerType::DensePool);
+
+        let active = self.edges.iter()
+            .filter(|edge| edge.active)
+            .map(|edge| edge.id)
+            .collect::<Vec<_>>();
+        if let Some(edge) = active.choose(&mut rng::thread()) {
+            self.remove(&[*edge], &[]);
+        }
+    }
+
+    /// take a random hidden neuron out of the layer along with all of its connections, and any
+    /// other hidden neuron that leaves without an active connection going out
+    pub fn remove_node(&mut self) {
+        assert!(self.layer_type == LayerType::DensePool);
+
+        let hidden = self.nodes.iter()
+            .filter(|node| node.neuron_type == NeuronType::Hidden)
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        if let Some(node) = hidden.choose(&mut rng::thread()) {
+            self.remove(&[], &[*node]);
+        }
+    }
+
+    /// give a random hidden neuron a different activation function from the ones given, nothing
+    /// changes if there isn't a hidden neuron or another function for it. Hidden neurons can't
+    /// use softmax since it needs every output at once, so it is never picked
+    pub fn mutate_activation(&mut self, activations: &[Activation]) {
+        assert!(self.layer_type == LayerType::DensePool);
+
+        let mut r = rng::thread();
+        let hidden = self.nodes.iter()
+            .filter(|node| node.neuron_type == NeuronType::Hidden)
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        if let Some(node) = hidden.choose(&mut r) {
+            let neuron = &mut Arc::make_mut(&mut self.nodes)[node.index()];
+            let others = activations.iter()
+                .filter(|activation| **activation != neuron.activation() && **activation != Activation::Softmax)
+                .collect::<Vec<_>>();
+            if let Some(activation) = others.choose(&mut r) {
+                neuron.set_activation(**activation);
+            }
+        }
+    }
+
+    /// Remove the edges and neurons from the layer, see Dense::removal. Nothing is removed if it
+    /// would take every edge, a layer needs an edge to grow from again
+    fn remove(&mut self, edges: &[EdgeId], neurons: &[NeuronId]) {
+        let (removed_nodes, removed_edges) = self.removal(edges, neurons);
+        if removed_edges.iter().all(|removed| *removed) {
+            return;
+        }
+        self.compact(&removed_nodes, &removed_edges);
+    }
+
+    /// Simplify the layer for deployment. Disabled edges are dropped, as are connections which feed
+    /// no more than threshold either way into the neuron they go to, and then every hidden neuron
+    /// with no path left to an output (a hidden neuron with no path to it from an input is kept, it
+    /// still feeds its bias forward). A disabled edge whose weight has been edited since it was
+    /// disabled still feeds that weight forward, so it is enabled instead of dropped. With a threshold
+    /// of 0 the layer outputs exactly what it did before, otherwise it only moves by the connections cut.
+    /// What is left is renumbered so the layer can't be trained further from its old state
+    pub fn prune(&mut self, threshold: f32) -> PruneReport {
+        let fed = self.edges.iter()
+            .map(|edge| self.nodes[edge.dst.index()].incoming_edges().iter().find(|link| link.id == edge.id).map_or(0.0, |link| link.weight))
+            .collect::<Vec<_>>();
+        let live = self.edges.iter()
+            .filter(|edge| !edge.active && fed[edge.id.index()] != 0.0)
+            .map(|edge| edge.id)
+            .collect::<Vec<_>>();
+        if !live.is_empty() {
+            let edges = Arc::make_mut(&mut self.edges);
+            for id in live {
+                let weight = fed[id.index()];
+                edges[id.index()].enable(Arc::make_mut(&mut self.nodes).as_mut_slice());
+                edges[id.index()].update_weight(weight, Arc::make_mut(&mut self.nodes).as_mut_slice());
+            }
+        }
+        let pruned = self.edges.iter()
+            .filter(|edge| fed[edge.id.index()].abs() <= threshold.max(0.0))
+            .map(|edge| edge.id)
+            .collect::<Vec<_>>();
+        // walk back from the outputs along the connections which are kept
+        let mut reaches_output = vec![false; self.nodes.len()];
+        let mut stack = self.outputs.clone();
+        while let Some(neuron) = stack.pop() {
+            if !std::mem::replace(&mut reaches_output[neuron.index()], true) {
+                stack.extend(self.edges.iter()
+                    .filter(|edge| edge.dst == neuron && edge.active && fed[edge.id.index()].abs() > threshold.max(0.0))
+                    .map(|edge| edge.src));
+            }
+        }
+        let unreachable = self.nodes.iter()
+            .filter(|node| node.neuron_type == NeuronType::Hidden && !reaches_output[node.id.index()])
+            .map(|node| node.id)
+            .collect::<Vec<_>>();
+        let (removed_nodes, removed_edges) = self.removal(&pruned, &unreachable);
+        let report = PruneReport {
+            nodes: removed_nodes.iter().filter(|removed| **removed).count(),
+            edges: removed_edges.iter().filter(|removed| **removed).count()
+        };
+        if report.nodes + report.edges > 0 {
+            self.compact(&removed_nodes, &removed_edges);
+        }
+        report
+    }
+
+    /// Work out which neurons and edges go when the given ones are removed. A hidden neuron with no
+    /// active connection going out doesn't change what the layer outputs, so once the edges are gone
+    /// so are the hidden neurons they leave dangling, and the edges into those in turn.
+    /// Inputs and outputs are never removed
+    fn removal(&self, edges: &[EdgeId], neurons: &[NeuronId]) -> (Vec<bool>, Vec<bool>) {
+        let mut removed_edges = vec![false; self.edges.len()];
+        let mut removed_nodes = vec![false; self.nodes.len()];
+        // the neurons which lost a connection going out and might be left dangling
+        let mut touched = Vec::new();
+        for edge_id in edges.iter() {
+            removed_edges[edge_id.index()] = true;
+            touched.push(self.edges[edge_id.index()].src);
+        }
+        let mut dangling = neurons.to_vec();
+        loop {
+            for neuron in dangling.drain(..) {
+                removed_nodes[neuron.index()] = true;
+                for edge in self.edges.iter().filter(|edge| edge.src == neuron || edge.dst == neuron) {
+                    removed_edges[edge.id.index()] = true;
+                    if edge.dst == neuron {
+                        touched.push(edge.src);
+                    }
+                }
+            }
+            dangling = touched.drain(..)
+                .filter(|neuron| !removed_nodes[neuron.index()] && self.nodes[neuron.index()].neuron_type == NeuronType::Hidden)
+                .filter(|neuron| !self.edges.iter().any(|edge| edge.active && edge.src == *neuron && !removed_edges[edge.id.index()]))
+                .collect();
+            if dangling.is_empty() {
+                break;
+            }
+        }
+        (removed_nodes, removed_edges)
+    }
+
+    /// drop the removed neurons and edges, renumbering the rest and linking them back up
+    fn compact(&mut self, removed_nodes: &[bool], removed_edges: &[bool]) {
+        // the weight each edge feeds into the neuron it goes to, which is zero for a disabled
+        // edge unless its weight has been edited since
+        let link_weights = self.edges.iter()
+            .map(|edge| self.nodes[edge.dst.index()].incoming_edges().iter().find(|link| link.id == edge.id).map(|link| link.weight))
+            .collect::<Vec<_>>();
+        let mut ids = vec![None; self.nodes.len()];
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        for (index, mut node) in std::mem::take(Arc::make_mut(&mut self.nodes)).into_iter().enumerate() {
+            if !removed_nodes[index] {
+                let id = NeuronId::new(nodes.len());
+                ids[index] = Some(id);
+                node.relabel(id);
+                nodes.push(node);
+            }
+        }
+        let relabel = |id: NeuronId| ids[id.index()].expect("a kept edge connects a removed neuron");
+        let edges = self.edges.iter()
+            .filter(|edge| !removed_edges[edge.id.index()])
+            .enumerate()
+            .map(|(index, edge)| {
+                let link_weight = link_weights[edge.id.index()];
+                let mut edge = edge.clone();
+                edge.id = EdgeId::new(index);
+                edge.src = relabel(edge.src);
+                edge.dst = relabel(edge.dst);
+                (edge, link_weight)
+            })
+            .collect::<Vec<_>>();
+        // a disabled edge is still linked to the neuron it goes into, but not from the one it leaves
+        for (edge, link_weight) in edges.iter() {
+            edge.link_nodes(&mut nodes);
+            if !edge.active {
+                nodes[edge.src.index()].remove_outgoing(edge.id);
+            }
+            if let Some(weight) = link_weight {
+                nodes[edge.dst.index()].update_incoming(edge, *weight);
+            }
+        }
+        let edges = edges.into_iter().map(|(edge, _)| edge).collect::<Vec<_>>();
+        self.inputs = self.inputs.iter().map(|id| relabel(*id)).collect();
+        self.outputs = self.outputs.iter().map(|id| relabel(*id)).collect();
+        self.edge_innov_map = Arc::new(edges.iter().map(|edge| (edge.innov, edge.id)).collect());
+        self.edges = Arc::new(edges);
+        self.states = vec![NeuronState::default(); nodes.len()];
+        self.nodes = Arc::new(nodes);
+        // the training state is kept by index, which has just changed
+        self.fast_mode = false;
+        self.gradients = None;
+        self.optimizer_state = OptimizerState::default();
+        if self.trace_states.is_some() {
+            self.trace_states = Some(Tracer::new());
         }
     }
 
@@ -217,13 +698,16 @@ impl Dense {
     }
 
     /// check to see if the connection to be made would create a cycle in the graph
-    /// and therefore make it network invalid and unable to feed forward
+    /// and therefore make it network invalid and unable to feed forward. Recurrent
+    /// edges don't wait on their src so they are left out of the search
     fn cyclical(&self, sending: NeuronId, receiving: NeuronId) -> bool {
         let recv_node = self.nodes.get(receiving.index()).unwrap();
         // dfs stack which gets the receiving Neuron<dyn neurons> outgoing connections
         let mut stack = recv_node.outgoing_edges()
             .iter()
-            .map(|x| self.edges.get(x.index()).unwrap().dst.index())
+            .map(|x| self.edges.get(x.index()).unwrap())
+            .filter(|edge| !edge.recurrent)
+            .map(|edge| edge.dst.index())
             .collect::<Vec<_>>();
 
         // while the stack still has nodes, continue
@@ -236,7 +720,9 @@ impl Dense {
             }
             for i in curr.outgoing_edges().iter() {
                 let edge = self.edges.get(i.index()).unwrap();
-                stack.push(edge.dst.index());
+                if !edge.recurrent {
+                    stack.push(edge.dst.index());
+                }
             }
         }
         false
@@ -255,7 +741,7 @@ impl Dense {
 
     /// get a random node from the network
     fn random_node(&self) -> &Neuron {
-        let index = rand::thread_rng().gen_range(0, self.nodes.len());
+        let index = rng::thread().gen_range(0, self.nodes.len());
         let node = self.nodes.get(index)
             .expect("Failed to get random node");
         return node;
@@ -274,30 +760,25 @@ impl Dense {
 
     /// get a random connection from the network
     fn random_edge(&self) -> &Edge {
-        let index = rand::thread_rng().gen_range(0, self.edges.len());
+        let index = rng::thread().gen_range(0, self.edges.len());
         self.edges.get(index)
             .expect("Failed to get random edge")
     }
 
 
-    /// Edit the weights in the network randomly by either uniformly perturbing
-    /// them, or giving them an entire new weight all together
-    fn edit_weights(&mut self, editable: f32, size: f32) {
-        let mut r = rand::thread_rng();
-        for edge in self.edges.iter_mut() {
-            let weight = if r.gen::<f32>() < editable {
-                r.gen::<f32>()
-            } else {
-                edge.weight * r.gen_range(-size, size)
-            };
-            edge.update_weight(weight, &mut self.nodes);
+    /// Edit the weights and biases in the network randomly by either perturbing
+    /// them, or giving them an entire new weight all together, see WeightEdit
+    pub(crate) fn edit_weights(&mut self, edit: &WeightEdit) {
+        let mut r = rng::thread();
+        for edge in Arc::make_mut(&mut self.edges).iter_mut() {
+            let weight = edit.apply(&mut r, edge.weight);
+            edge.update_weight(weight, Arc::make_mut(&mut self.nodes).as_mut_slice());
         }
-        for node in self.nodes.iter_mut() {
-            if r.gen::<f32>() < editable {
-                node.bias = r.gen::<f32>();
-            } else {
-                node.bias *= r.gen_range(-size, size);
-            }
+        if self.bias == Bias::Off {
+            return;
+        }
+        for node in Arc::make_mut(&mut self.nodes).iter_mut() {
+            node.bias = edit.apply(&mut r, node.bias);
         }
     }
 
@@ -307,34 +788,33 @@ impl Dense {
     pub fn get_output_states(&self) -> Vec<f32> {
         self.outputs
             .iter()
-            .map(|x| {
-                let output_neuron = self.nodes.get(x.index()).unwrap();
-                output_neuron.current_state
-            })
+            .map(|x| self.state(*x).current_state)
             .collect::<Vec<_>>()
     }
 
 
     /// Because the output neurons might need to be seen togehter, this must be called to 
-    /// set their values before finishing the feed forward function
+    /// set their values before finishing the feed forward function. The derivative of a softmax
+    /// output depends on every output so it can't be kept per neuron, it is left at 1 and
+    /// backward applies the whole softmax jacobian to the errors instead
     pub fn set_output_values(&mut self) {
         let vals = self.get_output_states();
         let (act, d_act) = match self.activation {
             Activation::Softmax => {
-                let act = vectorops::softmax(&vals);
-                let d_act = vectorops::d_softmax(&act);
+                let act = vectorops::softmax_with(&vals, self.precision);
+                let d_act = vec![1.0; act.len()];
                 (act, d_act)
             },
             _ => {
-                let act = vectorops::element_activate(&vals, self.activation);
-                let d_act = vectorops::element_deactivate(&vals, self.activation);
+                let act = vectorops::element_activate_with(&vals, self.activation, self.precision);
+                let d_act = vectorops::element_deactivate_with(&vals, self.activation, self.precision);
                 (act, d_act)
             }
         };
         for (i, neuron_id) in self.outputs.iter().enumerate() {
-            let curr_neuron = self.nodes.get_mut(neuron_id.index()).unwrap();
-            curr_neuron.activated_value = act[i];
-            curr_neuron.deactivated_value = d_act[i];
+            let state = &mut self.states[neuron_id.index()];
+            state.activated_value = act[i];
+            state.deactivated_value = d_act[i];
         }
     }
 
@@ -343,26 +823,231 @@ impl Dense {
     /// take a snapshot of the neuron's values at this time step if trace is enabled
     pub fn update_traces(&mut self) {
         if let Some(tracer) = &mut self.trace_states {
-            for node in self.nodes.iter() {
-                tracer.update_neuron_activation(&node.id, node.activated_value);
-                tracer.update_neuron_derivative(&node.id, node.deactivated_value);
+            for (node, state) in self.nodes.iter().zip(self.states.iter()) {
+                tracer.update_neuron_activation(&node.id, state.activated_value);
+                tracer.update_neuron_derivative(&node.id, state.deactivated_value);
             }
             tracer.index += 1;
         }
     }
 
 
-    fn fast_forward(&mut self, data: &[f32]) -> Option<Vec<f32>> {
+    /// Feed the inputs through the graph node by node until every node is activated, with updates
+    /// as the buffer for the state of each node so it can be reused from pass to pass.
+    /// Errors if the connections loop back on themselves so the outputs can't be reached
+    fn graph_forward(&mut self, data: &[f32], updates: &mut Vec<NodeUpdate>) -> Result<Vec<f32>, NeatError> {
+        // keep track of outputs as they are calculated.
+        let mut outputs = Vec::with_capacity(self.outputs.len());
+
+        updates.clear();
+        self.sync_states();
+        let mut pending_cnt = 0;
+        let mut lowest_pending_idx = self.nodes.len();
+
+        // recurrent edges feed forward what their neuron output on the pass before
+        let previous = match self.edges.iter().any(|edge| edge.recurrent) {
+            true => self.states.iter_mut()
+                .map(|state| {
+                    state.previous_value = state.activated_value;
+                    state.previous_value
+                })
+                .collect(),
+            false => Vec::new()
+        };
+
+        // First phase:
+        // 1. reset all neurons
+        // 2. set inputs
+        // 3. try activating Output/Hidden nodes.
+        //
+        // If their are no Hidden nodes, then all node should be activated
+        // during this first pass.
+        let mut inputs = data.iter();
+        for (node, state) in self.nodes.iter().zip(self.states.iter_mut()) {
+            // reset neuron
+            state.reset();
+
+            // set inputs
+            let update = match node.neuron_type {
+                NeuronType::Input => {
+                    let value = *inputs.next().unwrap();
+                    state.activated_value = value;
+                    // active input node from input data.
+                    NodeUpdate::Activated{
+                        value,
+                        output: None,
+                    }
+                },
+                NeuronType::Output => {
+                    // try activating Output nodes.
+                    let update = NodeUpdate::process(updates, &previous, node, state, Some(outputs.len()), self.precision);
+                    if let Some((value, _)) = update.is_activated() {
+                        // activated, push value.
+                        outputs.push(value);
+                    } else {
+                        // still pending, push place-holder value.
+                        outputs.push(0.0);
+                    }
+                    update
+                },
+                NeuronType::Hidden => {
+                    // try activating Output nodes.
+                    NodeUpdate::process(updates, &previous, node, state, None, self.precision)
+                },
+            };
+            // count pending updates
+            if update.is_pending() {
+                let idx = updates.len();
+                if idx < lowest_pending_idx {
+                    lowest_pending_idx = idx;
+                }
+                pending_cnt += 1;
+            }
+            updates.push(update);
+        }
+
+        // Second phase:
+        // Loop until all nodes have been activated.
+        // This phase should only loop a few times (0 to node depth).
+        // If no progress (Pending -> Activated changes) is made for `max_tries`
+        // then the network has a cycle.
+        let mut max_tries = 10;
+        while pending_cnt > 0 {
+            let mut changes = 0;
+
+            // start from the first pending node (lowest pending idx)
+            let start_idx = lowest_pending_idx;
+            let end_idx = self.nodes.len();
+            lowest_pending_idx = end_idx;
+
+            for idx in start_idx..end_idx {
+                let old_update = updates[idx];
+                if old_update.is_pending() {
+                    let output_idx = old_update.output();
+                    // try activating node
+                    let update = NodeUpdate::process(updates, &previous, &self.nodes[idx], &mut self.states[idx], output_idx, self.precision);
+                    match update {
+                        NodeUpdate::Pending{..} => {
+                            // keep track of lowest pending idx.
+                            if idx < lowest_pending_idx {
+                                lowest_pending_idx = idx;
+                            }
+                        },
+                        NodeUpdate::Activated{value, output} => {
+                            // check for activated output
+                            if let Some(out_idx) = output {
+                                // update activated output.
+                                outputs[out_idx] = value;
+                            }
+                            // node changed from Pending->Activated
+                            pending_cnt -= 1;
+                            changes += 1;
+                        },
+                    }
+                    updates[idx] = update;
+                }
+            }
+            // This is to avoid infinite looping on a bad network (cyclical links)
+            if changes == 0 {
+                max_tries -= 1;
+                if max_tries == 0 {
+                    // Abort, the network can't be fed forward.
+                    return Err(NeatError::Cycle);
+                }
+            }
+        }
+
+        // once we've made it through the network, the outputs should all
+        // have calculated their values. Gather the values and return the vec
+        if self.activation == Activation::Softmax {
+            // Only need to re-process output neurons for Softmax activation.
+            self.set_output_values();
+
+            self.update_traces();
+            self.get_outputs()
+        } else {
+            self.update_traces();
+            Ok(outputs)
+        }
+    }
+
+
+    /// a layer without hidden or recurrent neurons that isn't being traced holds no state
+    /// between passes, so its outputs are just activate(W · x + b). ndarray (or the BLAS behind it)
+    /// is free to sum the products in any order, so strict layers stay on the neuron by neuron pass
+    #[cfg(feature = "ndarray")]
+    fn is_matrix(&self) -> bool {
+        let in_size = self.inputs.len();
+        self.fast_mode
+            && self.precision != Precision::Strict
+            && self.trace_states.is_none()
+            && self.nodes[in_size..].iter().all(|node| node.direction() == NeuronDirection::Forward)
+    }
+
+
+    /// the weights of the layer as a matrix, only read out of the neurons again once the
+    /// weights or biases have changed since it was last read, see WeightMatrix
+    #[cfg(feature = "ndarray")]
+    fn weight_matrix(&mut self) -> Arc<WeightMatrix> {
+        let in_size = self.inputs.len();
+        if let Some(matrix) = self.matrix.as_ref() {
+            let same_biases = matrix.biases.iter().eq(self.nodes[in_size..].iter().map(|node| &node.bias));
+            if Arc::ptr_eq(&matrix.edges, &self.edges) && same_biases {
+                return Arc::clone(matrix);
+            }
+        }
+        let mut weights = Array2::zeros((self.outputs.len(), in_size));
+        for (row, node) in self.nodes[in_size..].iter().enumerate() {
+            for link in node.incoming_edges() {
+                weights[[row, link.src.index()]] += link.weight;
+            }
+        }
+        let biases = self.nodes[in_size..].iter().map(|node| node.bias).collect();
+        let matrix = Arc::new(WeightMatrix { edges: Arc::clone(&self.edges), weights, biases });
+        self.matrix = Some(Arc::clone(&matrix));
+        matrix
+    }
+
+
+    /// Feed a whole batch through the layer as one matrix product instead of walking the
+    /// edges of every output for each input. The last input is replayed through the graph
+    /// so the neurons are left holding its values for a following backward pass, which
+    /// still walks the graph one sample at a time.
+    #[cfg(feature = "ndarray")]
+    fn matrix_forward(&mut self, inputs: &[Vec<f32>]) -> Result<Vec<Vec<f32>>, NeatError> {
         let in_size = self.inputs.len();
+        let matrix = self.weight_matrix();
+        let batch = Array2::from_shape_fn((inputs.len() - 1, in_size), |(row, col)| inputs[row][col]);
+        let states = batch.dot(&matrix.weights.t()) + &matrix.biases;
+
+        let mut outputs = Vec::with_capacity(inputs.len());
+        for row in states.outer_iter() {
+            let row = row.to_vec();
+            outputs.push(match self.activation {
+                Activation::Softmax => vectorops::softmax_with(&row, self.precision),
+                _ => row.iter()
+                    .zip(self.nodes[in_size..].iter())
+                    .map(|(state, node)| node.activation().activate_with(*state, self.precision))
+                    .collect()
+            });
+        }
+        outputs.push(self.fast_forward(&inputs[inputs.len() - 1])?);
+        Ok(outputs)
+    }
+
+
+    fn fast_forward(&mut self, data: &[f32]) -> Result<Vec<f32>, NeatError> {
+        let in_size = self.inputs.len();
+        self.sync_states();
 
         // First phase: update input neurons
-        for (node, value) in self.nodes[0..in_size].iter_mut().zip(data.iter()) {
+        for ((node, state), value) in self.nodes[0..in_size].iter().zip(self.states[0..in_size].iter_mut()).zip(data.iter()) {
             assert!(node.neuron_type == NeuronType::Input);
             // reset neuron
-            node.reset_neuron();
+            state.reset();
 
             // set inputs
-            node.activated_value = *value;
+            state.activated_value = *value;
         }
 
         // keep track of outputs as they are calculated.
@@ -370,20 +1055,20 @@ impl Dense {
 
         // Second phase: update output neurons
         let end_idx = self.nodes.len();
-        for node in self.nodes[in_size..end_idx].iter_mut() {
+        for (node, state) in self.nodes[in_size..end_idx].iter().zip(self.states[in_size..end_idx].iter_mut()) {
             assert!(node.neuron_type == NeuronType::Output);
             // reset neuron
-            node.reset_neuron();
+            state.reset();
 
             // (inputs[] * weights[]) + bias
-            node.current_state = node.incoming_edges().iter().zip(data.iter())
+            state.current_state = node.incoming_edges().iter().zip(data.iter())
               .fold(node.bias, |sum, (edge, value)| {
                   sum + (value * edge.weight)
               });
             // set inputs
-            node.activate();
+            node.activate_with(state, self.precision);
 
-            outputs.push(node.activated_value);
+            outputs.push(state.activated_value);
         }
 
         // once we've made it through the network, the outputs should all
@@ -396,7 +1081,7 @@ impl Dense {
             self.get_outputs()
         } else {
             self.update_traces();
-            Some(outputs)
+            Ok(outputs)
         }
     }
 }
@@ -408,11 +1093,15 @@ enum NodeUpdate {
 }
 
 impl NodeUpdate {
-    pub fn process(updates: &[NodeUpdate], node: &mut Neuron, output: Option<usize>) -> Self {
+    pub fn process(updates: &[NodeUpdate], previous: &[f32], node: &Neuron, state: &mut NeuronState, output: Option<usize>, precision: Precision) -> Self {
         let mut sum = node.bias;
         let mut pending_inputs = 0;
 
         for edge in node.incoming_edges().iter() {
+            if edge.recurrent {
+                sum += previous.get(edge.src.index()).copied().unwrap_or(0.0) * edge.weight;
+                continue;
+            }
             match updates.get(edge.src.index()) {
                 Some(NodeUpdate::Activated{value, ..}) => {
                     // calculate weighted value for this edge.
@@ -428,12 +1117,12 @@ impl NodeUpdate {
         // if no pending inputs, then active the node.
         if pending_inputs == 0 {
             // pass sum into node and active it.
-            node.current_state = sum;
-            node.activate();
+            state.current_state = sum;
+            node.activate_with(state, precision);
 
             // mark this node as activated.
             NodeUpdate::Activated{
-                value: node.activated_value,
+                value: state.activated_value,
                 output,
             }
         } else {
@@ -470,150 +1159,67 @@ impl NodeUpdate {
 
 #[typetag::serde]
 impl Layer for Dense {
-    /// Feed a vec of inputs through the network, will panic! if 
-    /// the shapes of the values do not match or if something goes 
-    /// wrong within the feed forward process.
-    fn forward(&mut self, data: &Vec<f32>) -> Option<Vec<f32>> {
-        assert!(data.len() == self.inputs.len());
+    /// Feed a vec of inputs through the network, errors if the
+    /// shapes of the values do not match or if the connections
+    /// loop back on themselves so the outputs can't be reached.
+    fn forward(&mut self, data: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.inputs.len(), data.len())?;
         if self.fast_mode {
             return self.fast_forward(data);
         }
 
-        // keep track of outputs as they are calculated.
-        let mut outputs = Vec::with_capacity(self.outputs.len());
-
         let mut updates = Vec::with_capacity(self.nodes.len());
-        let mut pending_cnt = 0;
-        let mut lowest_pending_idx = self.nodes.len();
+        self.graph_forward(data, &mut updates)
+    }
 
-        // First phase:
-        // 1. reset all neurons
-        // 2. set inputs
-        // 3. try activating Output/Hidden nodes.
-        //
-        // If their are no Hidden nodes, then all node should be activated
-        // during this first pass.
-        let mut inputs = data.into_iter();
-        for node in self.nodes.iter_mut() {
-            // reset neuron
-            node.reset_neuron();
 
-            // set inputs
-            let update = match node.neuron_type {
-                NeuronType::Input => {
-                    let value = *inputs.next().unwrap();
-                    node.activated_value = value;
-                    // active input node from input data.
-                    NodeUpdate::Activated{
-                        value,
-                        output: None,
-                    }
-                },
-                NeuronType::Output => {
-                    // try activating Output nodes.
-                    let update = NodeUpdate::process(&updates, node, Some(outputs.len()));
-                    if let Some((value, _)) = update.is_activated() {
-                        // activated, push value.
-                        outputs.push(value);
-                    } else {
-                        // still pending, push place-holder value.
-                        outputs.push(0.0);
-                    }
-                    update
-                },
-                NeuronType::Hidden => {
-                    // try activating Output nodes.
-                    NodeUpdate::process(&updates, node, None)
-                },
-            };
-            // count pending updates
-            if update.is_pending() {
-                let idx = updates.len();
-                if idx < lowest_pending_idx {
-                    lowest_pending_idx = idx;
-                }
-                pending_cnt += 1;
-            }
-            updates.push(update);
+    /// Feed each vec of inputs through the network, sharing one buffer of node
+    /// updates across the whole batch instead of allocating one for every pass
+    fn forward_batch(&mut self, inputs: &[Vec<f32>]) -> Result<Vec<Vec<f32>>, NeatError> {
+        for data in inputs.iter() {
+            NeatError::check_input(self.inputs.len(), data.len())?;
         }
-
-        // Second phase:
-        // Loop until all nodes have been activated.
-        // This phase should only loop a few times (0 to node depth).
-        // If no progress (Pending -> Activated changes) is made for `max_tries`
-        // then return no data.
-        let mut max_tries = 10;
-        while pending_cnt > 0 {
-            let mut changes = 0;
-
-            // start from the first pending node (lowest pending idx)
-            let start_idx = lowest_pending_idx;
-            let end_idx = self.nodes.len();
-            lowest_pending_idx = end_idx;
-
-            for idx in start_idx..end_idx {
-                let node = self.nodes.get_mut(idx).unwrap();
-                let old_update = updates[idx];
-                if old_update.is_pending() {
-                    let output_idx = old_update.output();
-                    // try activating node
-                    let update = NodeUpdate::process(&updates, node, output_idx);
-                    match update {
-                        NodeUpdate::Pending{..} => {
-                            // keep track of lowest pending idx.
-                            if idx < lowest_pending_idx {
-                                lowest_pending_idx = idx;
-                            }
-                        },
-                        NodeUpdate::Activated{value, output} => {
-                            // check for activated output
-                            if let Some(out_idx) = output {
-                                // update activated output.
-                                outputs[out_idx] = value;
-                            }
-                            // node changed from Pending->Activated
-                            pending_cnt -= 1;
-                            changes += 1;
-                        },
-                    }
-                    updates[idx] = update;
-                }
-            }
-            // This is to avoid infinite looping on a bad network (cyclical links)
-            if changes == 0 {
-                max_tries -= 1;
-                if max_tries == 0 {
-                    // Abort and return NO DATA.
-                    return None;
-                }
+        #[cfg(feature = "ndarray")]
+        {
+            if self.is_matrix() && inputs.len() > 1 {
+                return self.matrix_forward(inputs);
             }
         }
-
-        // once we've made it through the network, the outputs should all
-        // have calculated their values. Gather the values and return the vec
-        if self.activation == Activation::Softmax {
-            // Only need to re-process output neurons for Softmax activation.
-            self.set_output_values();
-
-            self.update_traces();
-            self.get_outputs()
-        } else {
-            self.update_traces();
-            Some(outputs)
+        if self.fast_mode {
+            return inputs.iter().map(|data| self.fast_forward(data)).collect();
         }
+        let mut updates = Vec::with_capacity(self.nodes.len());
+        inputs.iter().map(|data| self.graph_forward(data, &mut updates)).collect()
     }
 
 
     /// Backpropagation algorithm, transfer the error through the network and change the weights of the
     /// edges accordingly, this is pretty straightforward due to the design of the neat graph
-    fn backward(&mut self, error: &Vec<f32>, learning_rate: f32) -> Option<Vec<f32>> {
+    fn backward(&mut self, error: &Vec<f32>, learning_rate: f32) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_errors(self.outputs.len(), error.len())?;
+        if self.trace_states.as_ref().is_some_and(|tracer| tracer.index == 0) {
+            return Err(NeatError::MissingState("traced forward pass"));
+        }
+        self.sync_states();
+        match self.gradients.as_mut() {
+            Some(gradients) => {
+                gradients.edges.resize(self.edges.len(), 0.0);
+                gradients.biases.resize(self.nodes.len(), 0.0);
+                gradients.rate = learning_rate;
+            },
+            None => self.optimizer_state.steps += 1
+        }
         // feed forward the input data to get the output in order to compute the error of the network
         // create a dfs stack to step backwards through the network and compute the error of each neuron
         // then insert that error in a hashmap to keep track of innov of the neuron and it's error 
+        let error = match self.activation {
+            Activation::Softmax => self.softmax_errors(error)?,
+            _ => error.clone()
+        };
         let mut path = Vec::with_capacity(self.inputs.len());
         for (index, id) in self.outputs.iter().enumerate() {
-            let node = self.nodes.get_mut(id.index()).unwrap();
-            node.error = error[index];
+            let state = self.states.get_mut(id.index()).ok_or(NeatError::InvalidGraph)?;
+            state.error = error[index];
             path.push(*id);
         }
 
@@ -623,18 +1229,13 @@ impl Layer for Dense {
         // step through the network backwards and adjust the weights
         while let Some(node_id) = path.pop() {
             // get the current node and it's error 
-            let curr_node = self.nodes.get_mut(node_id.index())?;
-            let curr_error = curr_node.error;
+            let curr_node = self.nodes.get(node_id.index()).ok_or(NeatError::InvalidGraph)?;
+            let curr_state = self.states.get_mut(node_id.index()).ok_or(NeatError::InvalidGraph)?;
+            let curr_error = curr_state.error;
             let step = match &self.trace_states {
                 Some(tracer) => curr_error * tracer.neuron_derivative(curr_node.id),
-                None => curr_error * curr_node.deactivated_value
-            } * learning_rate;
-
-            // reset the nodes error if it isn't an input node
-            if curr_node.neuron_type != NeuronType::Input {
-                curr_node.bias += learning_rate * curr_error;
-                curr_node.error = 0.0;
-            }
+                None => curr_error * curr_state.deactivated_value
+            };
 
             // iterate through each of the incoming edges to this neuron and adjust its weight
             // and add its error to the errors map
@@ -642,26 +1243,51 @@ impl Layer for Dense {
                 edge_updates.push(edge.id);
             }
 
+            // reset the nodes error if it isn't an input node, the bias is added before the
+            // activation so like the weights its gradient is the step rather than the raw error
+            if curr_node.neuron_type != NeuronType::Input {
+                curr_state.error = 0.0;
+                match (self.bias, self.gradients.as_mut()) {
+                    (Bias::Off, _) => {},
+                    (Bias::Neuron, Some(gradients)) => gradients.biases[node_id.index()] += step,
+                    (Bias::Neuron, None) => {
+                        let delta = self.optimizer_state.step_bias(&self.optimizer, node_id.index(), step, learning_rate);
+                        Arc::make_mut(&mut self.nodes)[node_id.index()].bias += delta;
+                    }
+                }
+            }
+
             // apply pending edge updates.
             for incoming_edge_id in edge_updates.iter() {
-                let curr_edge = self.edges.get_mut(incoming_edge_id.index())?;
+                let curr_edge = self.edges.get(incoming_edge_id.index()).ok_or(NeatError::InvalidGraph)?;
 
-                // if the current edge is active, then it is contributing to the error and we need to adjust it
+                // if the current edge is active, then it is contributing to the error and we need to adjust it.
+                // A recurrent edge carried the value of the pass before, the error isn't sent back through it
                 if curr_edge.active {
-                    path.push(curr_edge.src);
-
-                    let src_neuron = self.nodes.get_mut(curr_edge.src.index())?;
-                    src_neuron.error += curr_edge.weight * curr_error;
+                    let src_state = self.states.get_mut(curr_edge.src.index()).ok_or(NeatError::InvalidGraph)?;
+                    if !curr_edge.recurrent {
+                        path.push(curr_edge.src);
+                        src_state.error += curr_edge.weight * curr_error;
+                    }
 
-                    // add the weight step (gradient) * the current value to the weight to adjust the weight
-                    // then update the connection so it knows if it should update the weight, or store the delta
-                    let delta = match &self.trace_states {
-                        Some(tracer) => step * tracer.neuron_activation(src_neuron.id),
-                        None => step * src_neuron.activated_value
+                    // the weight's gradient is the step * the current value, the optimizer turns it into the
+                    // delta to add to the weight then the connection knows if it should update the weight, or store the delta
+                    let gradient = match (&self.trace_states, curr_edge.recurrent) {
+                        (Some(tracer), false) => step * tracer.neuron_activation(curr_edge.src),
+                        (Some(tracer), true) => step * tracer.previous_activation(curr_edge.src),
+                        (None, false) => step * src_state.activated_value,
+                        (None, true) => step * src_state.previous_value
                     };
 
-                    // Update edge
-                    curr_edge.update(delta, &mut self.nodes);
+                    // Update edge, or hold the gradient until the batch is done
+                    match self.gradients.as_mut() {
+                        Some(gradients) => gradients.edges[incoming_edge_id.index()] += gradient,
+                        None => {
+                            let delta = self.optimizer_state.step_weight(&self.optimizer, incoming_edge_id.index(), gradient, learning_rate);
+                            let curr_edge = &mut Arc::make_mut(&mut self.edges)[incoming_edge_id.index()];
+                            curr_edge.update(delta, Arc::make_mut(&mut self.nodes).as_mut_slice());
+                        }
+                    }
                 }
             }
             // clear pending updates.
@@ -671,19 +1297,19 @@ impl Layer for Dense {
         // gather and return the output of the backwards pass
         let mut output = Vec::with_capacity(self.inputs.len());
         for x in self.inputs.iter() {
-            let neuron = self.nodes.get_mut(x.index()).unwrap();
+            let state = self.states.get_mut(x.index()).ok_or(NeatError::InvalidGraph)?;
             let error = match &self.trace_states {
-                Some(tracer) => neuron.error * tracer.neuron_activation(neuron.id),
-                None => neuron.error * neuron.activated_value
+                Some(tracer) => state.error * tracer.neuron_activation(*x),
+                None => state.error * state.activated_value
             };
-            neuron.error = 0.0;
+            state.error = 0.0;
             output.push(error);
         }
         // deduct the backprop index 
         if let Some(tracer) = &mut self.trace_states {
             tracer.index -= 1;
         }
-        Some(output)
+        Ok(output)
     }
 
 
@@ -707,6 +1333,137 @@ impl Layer for Dense {
 
 
 
+    fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+
+
+    fn accumulate_gradients(&mut self, accumulate: bool) {
+        self.gradients = if accumulate { Some(Gradients::default()) } else { None };
+    }
+
+
+
+    fn set_optimizer(&mut self, optimizer: Optimizer) {
+        if self.optimizer != optimizer {
+            self.optimizer_state = OptimizerState::default();
+        }
+        self.optimizer = optimizer;
+    }
+
+
+
+    /// turning the biases off zeroes them, turning them back on starts them from 0
+    fn set_bias(&mut self, bias: Bias) {
+        self.bias = bias;
+        if bias == Bias::Off {
+            for node in Arc::make_mut(&mut self.nodes).iter_mut() {
+                node.bias = 0.0;
+            }
+        }
+    }
+
+
+
+    fn apply_gradients(&mut self, scale: f32) {
+        if let Some(gradients) = self.gradients.as_mut() {
+            let state = &mut self.optimizer_state;
+            state.steps += 1;
+            if gradients.edges.iter().any(|gradient| *gradient != 0.0) {
+                let edges = Arc::make_mut(&mut self.edges);
+                // only active edges get gradients, updating a disabled one would relink its weight
+                for (index, (edge, gradient)) in edges.iter_mut().zip(gradients.edges.iter_mut()).enumerate() {
+                    if *gradient != 0.0 {
+                        let delta = state.step_weight(&self.optimizer, index, *gradient * scale, gradients.rate);
+                        edge.update(delta, Arc::make_mut(&mut self.nodes).as_mut_slice());
+                        *gradient = 0.0;
+                    }
+                }
+            }
+            for (index, (node, gradient)) in Arc::make_mut(&mut self.nodes).iter_mut().zip(gradients.biases.iter_mut()).enumerate() {
+                if *gradient != 0.0 {
+                    node.bias += state.step_bias(&self.optimizer, index, *gradient * scale, gradients.rate);
+                    *gradient = 0.0;
+                }
+            }
+        }
+    }
+
+
+
+    /// weights are only written back (and the edges only copied if shared with a clone) if visit
+    /// changes them. Disabled edges keep a zero weight on their neuron until they are enabled again,
+    /// and any traced forward passes are dropped since they were taken with the old weights
+    fn visit_weights_mut(&mut self, visit: &mut dyn FnMut(Uuid, &mut f32)) {
+        let changes = self.edges.iter()
+            .filter_map(|edge| {
+                let mut weight = edge.weight;
+                visit(edge.innov, &mut weight);
+                (weight != edge.weight).then_some((edge.id, weight))
+            })
+            .collect::<Vec<_>>();
+        if changes.is_empty() {
+            return;
+        }
+        let edges = Arc::make_mut(&mut self.edges);
+        for (edge_id, weight) in changes {
+            let edge = &mut edges[edge_id.index()];
+            if edge.active {
+                edge.update_weight(weight, Arc::make_mut(&mut self.nodes).as_mut_slice());
+            } else {
+                edge.weight = weight;
+            }
+        }
+        if let Some(tracer) = &mut self.trace_states {
+            tracer.reset();
+        }
+    }
+
+
+
+    fn weight_stats(&self) -> WeightStats {
+        WeightStats::new(&self.active_weights())
+    }
+
+
+
+    /// the edges and neurons are shared with the snapshot until the layer changes them,
+    /// so the snapshot only copies the optimizer's running state
+    fn snapshot(&self) -> Option<WeightSnapshot> {
+        Some(WeightSnapshot::Dense {
+            edges: Arc::clone(&self.edges),
+            nodes: Arc::clone(&self.nodes),
+            optimizer_state: self.optimizer_state.clone()
+        })
+    }
+
+
+
+    /// put back the edges and neurons, which carry the weights of their incoming edges and
+    /// their biases. The snapshot has to have the same edges in the same state and the same neurons
+    fn restore(&mut self, snapshot: &WeightSnapshot) -> Result<(), NeatError> {
+        let (edges, nodes, optimizer_state) = match snapshot {
+            WeightSnapshot::Dense { edges, nodes, optimizer_state } => (edges, nodes, optimizer_state),
+            _ => return Err(NeatError::SnapshotMismatch)
+        };
+        let same_edges = edges.len() == self.edges.len() && edges.iter()
+            .zip(self.edges.iter())
+            .all(|(taken, edge)| taken.innov == edge.innov && taken.active == edge.active);
+        if !same_edges || nodes.len() != self.nodes.len() {
+            return Err(NeatError::SnapshotMismatch);
+        }
+        self.edges = Arc::clone(edges);
+        self.nodes = Arc::clone(nodes);
+        self.optimizer_state = optimizer_state.clone();
+        if let Some(tracer) = &mut self.trace_states {
+            tracer.reset();
+        }
+        Ok(())
+    }
+
+
+
     fn as_ref_any(&self) -> &dyn Any
         where Self: Sized + 'static
     {
@@ -730,24 +1487,45 @@ impl Layer for Dense {
 impl Genome<Dense, NeatEnvironment> for Dense
     where Dense: Layer
 {
-    fn crossover(child: &Dense, parent_two: &Dense, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Option<Dense> {
+    fn crossover(child: &Dense, parent_two: &Dense, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Result<Dense, Box<dyn Error>> {
         let mut new_child = child.clone();
-        let set = (*env).read().ok()?;
-        let mut r = rand::thread_rng();
-        if r.gen::<f32>() < crossover_rate {
-            for edge in new_child.edges.iter_mut() {
+        let set = (*env).read().map_err(|_| NeatError::Poisoned)?;
+        if let Some(bias) = set.bias {
+            new_child.set_bias(bias);
+        }
+        let mut r = rng::thread();
+        if rng::chance(&mut r, crossover_rate) {
+            // the output neurons are the only ones which are the same neurons in any two parents,
+            // so their biases are taken from either one the same as the weights of shared edges
+            if new_child.bias == Bias::Neuron {
+                for id in new_child.outputs.iter() {
+                    if let Some(parent_node) = parent_two.nodes.get(id.index()) {
+                        if rng::chance(&mut r, 0.5) {
+                            Arc::make_mut(&mut new_child.nodes)[id.index()].bias = parent_node.bias;
+                        }
+                    }
+                }
+            }
+            for index in 0..new_child.edges.len() {
                 // if the edge is in both networks, then randomly assign the weight to the edge
                 // because we are already looping over the most fit parent, we only need to change the 
                 // weight to the second parent if necessary.
-                if let Some(parent_edge) = parent_two.get_edge_by_innov(&edge.innov) {
-                    if r.gen::<f32>() < 0.5 {
-                        edge.update_weight(parent_edge.weight, &mut new_child.nodes);
-                    }
+                if let Some(parent_edge) = parent_two.get_edge_by_innov(&new_child.edges[index].innov) {
+                    let take_weight = rng::chance(&mut r, 0.5) && parent_edge.weight != new_child.edges[index].weight;
 
                     // if the edge is deactivated in either network and a random number is less than the 
                     // reactivate parameter, then reactivate the edge and insert it back into the network
-                    if (!edge.active || !parent_edge.active) && r.gen::<f32>() < set.reactivate? {
-                        edge.enable(&mut new_child.nodes);
+                    let reactivate = (!new_child.edges[index].active || !parent_edge.active) && rng::chance(&mut r, set.reactivate.ok_or(NeatError::MissingSetting("reactivate"))?);
+
+                    // only take a private copy of the edges once one actually changes
+                    if take_weight || reactivate {
+                        let edge = &mut Arc::make_mut(&mut new_child.edges)[index];
+                        if take_weight {
+                            edge.update_weight(parent_edge.weight, Arc::make_mut(&mut new_child.nodes).as_mut_slice());
+                        }
+                        if reactivate {
+                            edge.enable(Arc::make_mut(&mut new_child.nodes).as_mut_slice());
+                        }
                     }
                 }
             }
@@ -755,26 +1533,37 @@ impl Genome<Dense, NeatEnvironment> for Dense
             // if a random number is less than the edit_weights parameter, then edit the weights of the network edges
             // add a possible new node to the network randomly 
             // attempt to add a new edge to the network, there is a chance this operation will add no edge
-            if r.gen::<f32>() < set.weight_mutate_rate? {
-                new_child.edit_weights(set.edit_weights?, set.weight_perturb?);
+            if rng::chance(&mut r, set.weight_mutate_rate.ok_or(NeatError::MissingSetting("weight_mutate_rate"))?) {
+                new_child.edit_weights(&WeightEdit::from_env(&set)?);
             }
 
             // if the layer is a dense pool then it can add nodes and connections to the layer as well
             if new_child.layer_type == LayerType::DensePool {
-                if r.gen::<f32>() < set.new_node_rate? {
-                    let act_func = *set.activation_functions.choose(&mut r)?;
-                    if r.gen::<f32>() < set.recurrent_neuron_rate? {
-                        new_child.add_node(act_func, NeuronDirection::Recurrent);
+                let innovations = set.innovations.as_deref();
+                if rng::chance(&mut r, set.new_node_rate.ok_or(NeatError::MissingSetting("new_node_rate"))?) {
+                    let act_func = *set.activation_functions.choose(&mut r).ok_or(NeatError::MissingSetting("activation_functions"))?;
+                    if rng::chance(&mut r, set.recurrent_neuron_rate.ok_or(NeatError::MissingSetting("recurrent_neuron_rate"))?) {
+                        new_child.add_marked_node(act_func, NeuronDirection::Recurrent, innovations);
                     } else {
-                        new_child.add_node(act_func, NeuronDirection::Forward);
+                        new_child.add_marked_node(act_func, NeuronDirection::Forward, innovations);
                     }
                 }
-                if r.gen::<f32>() < set.new_edge_rate? {
-                    new_child.add_edge();
+                if rng::chance(&mut r, set.new_edge_rate.ok_or(NeatError::MissingSetting("new_edge_rate"))?) {
+                    new_child.grow_edge(set.recurrent_edges, innovations);
+                }
+                // shrinking the layer is optional, so it doesn't draw from the stream unless it is set
+                if set.remove_node_rate.is_some_and(|rate| rng::chance(&mut r, rate)) {
+                    new_child.remove_node();
+                }
+                if set.remove_edge_rate.is_some_and(|rate| rng::chance(&mut r, rate)) {
+                    new_child.remove_edge();
+                }
+                if set.activation_mutate_rate.is_some_and(|rate| rng::chance(&mut r, rate)) {
+                    new_child.mutate_activation(&set.activation_functions);
                 }
             }
         }
-        Some(new_child)
+        Ok(new_child)
     }
 
 
@@ -790,6 +1579,47 @@ impl Genome<Dense, NeatEnvironment> for Dense
         let two_score = similar / two.edges.len() as f32;
         2.0 - (one_score + two_score)
     }
+
+
+
+    /// the share of one's edges which two doesn't have is part of the distance on its
+    /// own, so stop walking the edges as soon as that alone reaches the threshold
+    fn within_distance(one: &Dense, two: &Dense, _: Arc<RwLock<NeatEnvironment>>, threshold: f32) -> bool {
+        let mut similar = 0.0;
+        let mut unmatched = 0.0;
+        for innov in one.edge_innov_map.keys() {
+            if two.contains_edge(innov) {
+                similar += 1.0;
+            } else {
+                unmatched += 1.0;
+                if unmatched / one.edges.len() as f32 >= threshold {
+                    return false;
+                }
+            }
+        }
+        let one_score = similar / one.edges.len() as f32;
+        let two_score = similar / two.edges.len() as f32;
+        2.0 - (one_score + two_score) < threshold
+    }
+
+
+    /// edges are appended as they are made and clones keep their order, so an edge only
+    /// one layer has is excess if it comes after the last edge it shares with the other
+    fn alignment(one: &Dense, two: &Dense) -> Option<Alignment> {
+        let unmatched = |one: &Dense, two: &Dense| {
+            let shared = one.edges.iter().map(|edge| two.contains_edge(&edge.innov)).collect::<Vec<_>>();
+            let last = shared.iter().rposition(|shared| *shared).map_or(0, |index| index + 1);
+            let disjoint = shared[..last].iter().filter(|shared| !**shared).count();
+            (last - disjoint, disjoint, shared.len() - last)
+        };
+        let (matched, one_disjoint, one_excess) = unmatched(one, two);
+        let (_, two_disjoint, two_excess) = unmatched(two, one);
+        Some(Alignment {
+            matched,
+            disjoint: one_disjoint + two_disjoint,
+            excess: one_excess + two_excess
+        })
+    }
 }
 
 
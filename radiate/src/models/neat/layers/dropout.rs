@@ -0,0 +1,168 @@
+
+use std::fmt;
+use std::any::Any;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use super::layer::Layer;
+use super::super::neatenv::NeatEnvironment;
+
+use crate::Genome;
+use crate::engine::generation::Alignment;
+use crate::error::NeatError;
+use crate::rng;
+
+
+
+/// Dropout zeroes each of its inputs with the given probability while the network is training and
+/// scales the rest up to make up for them, so the next layer can't lean on any one of its inputs and
+/// backprop overfits less. Outside of training (evolution, inference) it passes its inputs through
+/// untouched. There are no weights, so there is nothing to evolve.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dropout {
+    pub size: usize,
+    pub probability: f32,
+    #[serde(skip)]
+    training: bool,
+    #[serde(skip)]
+    trace: Option<Vec<Vec<f32>>>,
+    #[serde(skip)]
+    last: Option<Vec<f32>>
+}
+
+
+
+impl Dropout {
+
+    /// create a new dropout layer over size inputs, dropping each with the given probability
+    pub fn new(size: usize, probability: f32) -> Self {
+        assert!((0.0..1.0).contains(&probability), "Dropout probability must be in [0, 1)");
+        Dropout {
+            size,
+            probability,
+            training: false,
+            trace: None,
+            last: None
+        }
+    }
+
+
+    /// true if the layer is dropping inputs, see Layer::set_training
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+}
+
+
+
+#[typetag::serde]
+impl Layer for Dropout {
+
+    /// while training each input is either dropped or scaled by 1 / (1 - probability)
+    /// so the expected output is the same as the input
+    fn forward(&mut self, inputs: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.size, inputs.len())?;
+        if !self.training {
+            return Ok(inputs.clone());
+        }
+        let mut r = rng::thread();
+        let keep = 1.0 / (1.0 - self.probability);
+        let mask = (0..self.size)
+            .map(|_| if rng::chance(&mut r, self.probability) { 0.0 } else { keep })
+            .collect::<Vec<_>>();
+        let outputs = inputs.iter().zip(mask.iter()).map(|(input, scale)| input * scale).collect();
+        match self.trace.as_mut() {
+            Some(trace) => trace.push(mask),
+            None => self.last = Some(mask)
+        }
+        Ok(outputs)
+    }
+
+
+    /// errors only flow back through the inputs which weren't dropped in the matching forward pass
+    fn backward(&mut self, errors: &Vec<f32>, _: f32) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_errors(self.size, errors.len())?;
+        if !self.training {
+            return Ok(errors.clone());
+        }
+        let mask = match self.trace.as_mut() {
+            Some(trace) => trace.pop(),
+            None => self.last.clone()
+        };
+        let mask = mask.ok_or(NeatError::MissingState("traced forward pass"))?;
+        Ok(errors.iter().zip(mask.iter()).map(|(error, scale)| error * scale).collect())
+    }
+
+
+    fn reset(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.clear();
+        }
+        self.last = None;
+    }
+
+
+    fn add_tracer(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+
+    fn remove_tracer(&mut self) {
+        self.trace = None;
+    }
+
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+        self.reset();
+    }
+
+
+    fn as_ref_any(&self) -> &dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn as_mut_any(&mut self) -> &mut dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn shape(&self) -> (usize, usize) {
+        (self.size, self.size)
+    }
+}
+
+
+
+/// dropout layers have nothing to cross over, offspring always start out of training
+impl Genome<Dropout, NeatEnvironment> for Dropout
+    where Dropout: Layer
+{
+
+    fn crossover(child: &Dropout, _: &Dropout, _: Arc<RwLock<NeatEnvironment>>, _: f32) -> Result<Dropout, Box<dyn Error>> {
+        Ok(Dropout::new(child.size, child.probability))
+    }
+
+
+    fn distance(one: &Dropout, two: &Dropout, _: Arc<RwLock<NeatEnvironment>>) -> f32 {
+        if one.size == two.size { 0.0 } else { 2.0 }
+    }
+
+
+    fn alignment(_: &Dropout, _: &Dropout) -> Option<Alignment> {
+        Some(Alignment::default())
+    }
+}
+
+
+
+impl fmt::Display for Dropout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Dropout=[size={}, probability={}]", self.size, self.probability)
+    }
+}
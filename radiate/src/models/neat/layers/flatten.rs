@@ -0,0 +1,75 @@
+
+use crate::error::NeatError;
+
+
+
+/// The shape of an image and how it is flattened into the inputs of a network. Conv2D and
+/// MaxPool2D read their inputs as rows of pixels from the top left, with the channels of each
+/// pixel next to each other, this turns images given as rows of pixels of channels into that.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Flatten {
+    pub height: usize,
+    pub width: usize,
+    pub channels: usize
+}
+
+
+
+impl Flatten {
+
+    pub fn new(height: usize, width: usize, channels: usize) -> Self {
+        Flatten { height, width, channels }
+    }
+
+
+    /// the (height, width, channels) shape the spatial layers are built with
+    pub fn shape(&self) -> (usize, usize, usize) {
+        (self.height, self.width, self.channels)
+    }
+
+
+    /// the number of inputs a flattened image takes
+    pub fn size(&self) -> usize {
+        self.height * self.width * self.channels
+    }
+
+
+    /// the index of a channel of a pixel in the flattened image
+    pub fn index(&self, row: usize, column: usize, channel: usize) -> usize {
+        (row * self.width + column) * self.channels + channel
+    }
+
+
+    /// flatten one image, an error if it isn't this shape
+    pub fn image(&self, image: &[Vec<Vec<f32>>]) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.height, image.len())?;
+        let mut flat = Vec::with_capacity(self.size());
+        for row in image.iter() {
+            NeatError::check_input(self.width, row.len())?;
+            for pixel in row.iter() {
+                NeatError::check_input(self.channels, pixel.len())?;
+                flat.extend_from_slice(pixel);
+            }
+        }
+        Ok(flat)
+    }
+
+
+    /// flatten a single channel image given as rows of values
+    pub fn grayscale(&self, image: &[Vec<f32>]) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(1, self.channels)?;
+        NeatError::check_input(self.height, image.len())?;
+        let mut flat = Vec::with_capacity(self.size());
+        for row in image.iter() {
+            NeatError::check_input(self.width, row.len())?;
+            flat.extend_from_slice(row);
+        }
+        Ok(flat)
+    }
+
+
+    /// flatten every image of a dataset
+    pub fn images(&self, images: &[Vec<Vec<Vec<f32>>>]) -> Result<Vec<Vec<f32>>, NeatError> {
+        images.iter().map(|image| self.image(image)).collect()
+    }
+}
@@ -4,6 +4,8 @@ extern crate rand;
 use std::fmt;
 use std::any::Any;
 use std::sync::{Arc, RwLock};
+use std::error::Error;
+use uuid::Uuid;
 use super::{
     layertype::LayerType,
     layer::Layer,
@@ -11,11 +13,61 @@ use super::{
     vectorops
 };    
 use super::super::{
-    activation::Activation,
+    activation::{Activation, Precision},
+    optimizer::Optimizer,
+    weights::WeightStats,
     neatenv::NeatEnvironment,
 };    
 
 use crate::Genome;
+use crate::engine::generation::Alignment;
+use crate::error::NeatError;
+
+
+
+
+/// GRU State is the 'snapshot' of the gate outputs and the memory going into each time
+/// step, the rest of the time-step values are held in the tracers of the gates
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GRUState {
+    pub f_gate_output: Vec<Vec<f32>>,
+    pub e_gate_output: Vec<Vec<f32>>,
+    pub memory_states: Vec<Vec<f32>>,
+    pub d_prev_memory: Option<Vec<f32>>,
+    pub d_prev_output: Option<Vec<f32>>
+}
+
+
+
+impl GRUState {
+
+
+    pub fn new() -> Self {
+        GRUState {
+            f_gate_output: Vec::new(),
+            e_gate_output: Vec::new(),
+            memory_states: Vec::new(),
+            d_prev_memory: None,
+            d_prev_output: None
+        }
+    }
+
+
+    /// add the gate outputs and the previous memory to the state for this time step
+    pub fn update_forward(&mut self, fg: Vec<f32>, eg: Vec<f32>, mem_state: Vec<f32>) {
+        self.f_gate_output.push(fg);
+        self.e_gate_output.push(eg);
+        self.memory_states.push(mem_state);
+    }
+}
+
+
+
+impl Default for GRUState {
+    fn default() -> Self {
+        GRUState::new()
+    }
+}
 
 
 
@@ -27,6 +79,8 @@ pub struct GRU {
     pub output_size: u32,
     pub current_memory: Vec<f32>,
     pub current_output: Vec<f32>,
+    #[serde(default = "GRUState::new")]
+    pub states: GRUState,
     pub f_gate: Dense,
     pub e_gate: Dense,
     pub o_gate: Dense,
@@ -46,6 +100,7 @@ impl GRU {
             output_size,
             current_memory: vec![0.0; memory_size as usize],
             current_output: vec![0.0; output_size as usize],
+            states: GRUState::new(),
             f_gate: Dense::new(network_in_size, memory_size, LayerType::DensePool, Activation::Sigmoid),
             e_gate: Dense::new(network_in_size, memory_size, LayerType::DensePool, Activation::Tanh),
             o_gate: Dense::new(network_in_size, output_size, LayerType::DensePool, act),
@@ -53,6 +108,64 @@ impl GRU {
     }
 
 
+
+    /// Preform one step backwards through time. The output gate sees the same output and input as the
+    /// memory gates but the updated memory, so its error is split back into the three, the memory error 
+    /// flows back through the update m = f * m_old + (1 - f) * e into the forget and memory gates.
+    /// The error on the previous output and memory is kept for the step before this one
+    #[inline]
+    pub fn step_back(&mut self, errors: &[f32], l_rate: f32) -> Result<Vec<f32>, NeatError> {
+        let (output_size, input_size) = (self.output_size as usize, self.input_size as usize);
+        let split = output_size + input_size;
+
+        // get the error on the output and memory from the step after this one 
+        let do_next = self.states.d_prev_output.clone().ok_or(NeatError::MissingState("previous output gradient"))?;
+        let dm_next = self.states.d_prev_memory.clone().ok_or(NeatError::MissingState("previous memory gradient"))?;
+
+        // unpack the gate outputs and the memory going into this step
+        let m_old = self.states.memory_states.pop().ok_or(NeatError::MissingState("memory state"))?;
+        let e_curr = self.states.e_gate_output.pop().ok_or(NeatError::MissingState("e gate output"))?;
+        let f_curr = self.states.f_gate_output.pop().ok_or(NeatError::MissingState("f gate output"))?;
+
+        // do = error + do_next
+        // dm = do @ Wo[memory] + dm_next
+        let mut d_output = errors.to_vec();
+        vectorops::element_add(&mut d_output, &do_next);
+        let do_input = self.o_gate.backward(&d_output, l_rate)?;
+        let mut dm = do_input[split..].to_vec();
+        vectorops::element_add(&mut dm, &dm_next);
+
+        // Gradient for f and e in m = f * m_old + (1 - f) * e
+        // df = (m_old - e) * dm
+        // de = (1 - f) * dm
+        let df = vectorops::product(&vectorops::subtract(&m_old, &e_curr), &dm);
+        let mut f_inverse = f_curr.clone();
+        vectorops::element_invert(&mut f_inverse);
+        let de = vectorops::product(&f_inverse, &dm);
+        let df_input = self.f_gate.backward(&df, l_rate)?;
+        let de_input = self.e_gate.backward(&de, l_rate)?;
+
+        // the previous output, input, and previous memory were used in all three gates 
+        // so the gradient must be accumulated here
+        // dX = dXo + dXf + dXe
+        let mut dx = do_input[..split].to_vec();
+        vectorops::element_add(&mut dx, &df_input[..split]);
+        vectorops::element_add(&mut dx, &de_input[..split]);
+
+        // Gradient for m_old, directly through the update and through both gates
+        // dm_next = f * dm + dXf[memory] + dXe[memory]
+        let mut dm_prev = vectorops::product(&f_curr, &dm);
+        vectorops::element_add(&mut dm_prev, &df_input[split..]);
+        vectorops::element_add(&mut dm_prev, &de_input[split..]);
+
+        self.states.d_prev_output = Some(dx[..output_size].to_vec());
+        self.states.d_prev_memory = Some(dm_prev);
+
+        // return the error of the input given to the layer
+        Ok(dx[output_size..].to_vec())
+    }
+
+
 }
 
 
@@ -65,7 +178,8 @@ impl Layer for GRU {
 
     /// implement the propagation function for the GRU layer 
     #[inline]
-    fn forward(&mut self, inputs: &Vec<f32>) -> Option<Vec<f32>> {
+    fn forward(&mut self, inputs: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.input_size as usize, inputs.len())?;
         let mut concat_input_output = self.current_output.clone();
         concat_input_output.extend(inputs);
 
@@ -76,6 +190,13 @@ impl Layer for GRU {
         let mut forget = self.f_gate.forward(&network_input)?;
         let mut memory = self.e_gate.forward(&network_input)?;
 
+        // keep the gate outputs and previous memory for bptt, every step when tracing 
+        // and only the latest when not so a single step can still be backpropagated
+        if self.f_gate.trace_states.is_none() {
+            self.states = GRUState::new();
+        }
+        self.states.update_forward(forget.clone(), memory.clone(), self.current_memory.clone());
+
         // figure out what to forget from the current memory
         vectorops::element_multiply(&mut self.current_memory, &forget);
         vectorops::element_invert(&mut forget);
@@ -87,34 +208,46 @@ impl Layer for GRU {
 
         // calculate the current output of the layer
         self.current_output = self.o_gate.forward(&concat_input_output)?;
-        Some(self.current_output.clone())
+        Ok(self.current_output.clone())
+    }
+
+
+    /// apply backpropagation through time, starting from no error on the future output and memory
+    #[inline]
+    fn backward(&mut self, errors: &Vec<f32>, learning_rate: f32) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_errors(self.output_size as usize, errors.len())?;
+        if self.states.d_prev_output.is_none() && self.states.d_prev_memory.is_none() {
+            self.states.d_prev_output = Some(vec![0.0; self.output_size as usize]);
+            self.states.d_prev_memory = Some(vec![0.0; self.memory_size as usize]);
+        }
+        self.step_back(errors, learning_rate)
     }
 
 
-    fn backward(&mut self, _errors: &Vec<f32>, _learning_rate: f32) -> Option<Vec<f32>> {
-        panic!("Backprop for GRU is not implemented yet");
-        // let output_error = self.o_gate.backward(&errors, learning_rate)?;
-        // // let delta_mem = self.current_memory
-        // //     .iter()
-        // //     .zip(output_error.iter())
-        // //     .map(|(a, b)| {
-        // //         a * b
-        // //     })
-        // //     .collect::<Vec<_>>();
+    /// reset the gru by clearing the gates, the states, and the memory and output
+    fn reset(&mut self) {
+        self.f_gate.reset();
+        self.e_gate.reset();
+        self.o_gate.reset();
+        self.states = GRUState::new();
+        self.current_memory = vec![0.0; self.memory_size as usize];
+        self.current_output = vec![0.0; self.output_size as usize];
+    }
+
+
+    /// add tracers to all the gates in the layer
+    fn add_tracer(&mut self) {
+        self.f_gate.add_tracer();
+        self.e_gate.add_tracer();
+        self.o_gate.add_tracer();
+    }
 
-        // // let delta_out = errors
-        // //     .iter()
-        // //     .zip(output_error.iter())
-        // //     .map(|(a, b)| {
-        // //         a * b
-        // //     })
-        // //     .collect::<Vec<_>>();
-        
-        // self.f_gate.backward(&output_error, learning_rate)?;
-        // self.e_gate.backward(&output_error, learning_rate)?;
 
-        
-        // Some(errors.clone())
+    /// remove the tracers from all the gates in the layer
+    fn remove_tracer(&mut self) {
+        self.f_gate.remove_tracer();
+        self.e_gate.remove_tracer();
+        self.o_gate.remove_tracer();
     }
 
 
@@ -135,6 +268,51 @@ impl Layer for GRU {
         (self.input_size as usize, self.output_size as usize)
     }
 
+
+    fn set_precision(&mut self, precision: Precision) {
+        self.f_gate.set_precision(precision);
+        self.e_gate.set_precision(precision);
+        self.o_gate.set_precision(precision);
+    }
+
+
+    fn weight_stats(&self) -> WeightStats {
+        let weights = [&self.f_gate, &self.e_gate, &self.o_gate]
+            .iter()
+            .flat_map(|gate| gate.active_weights())
+            .collect::<Vec<_>>();
+        WeightStats::new(&weights)
+    }
+
+
+    fn visit_weights_mut(&mut self, visit: &mut dyn FnMut(Uuid, &mut f32)) {
+        self.f_gate.visit_weights_mut(visit);
+        self.e_gate.visit_weights_mut(visit);
+        self.o_gate.visit_weights_mut(visit);
+    }
+
+
+    fn set_optimizer(&mut self, optimizer: Optimizer) {
+        self.f_gate.set_optimizer(optimizer);
+        self.e_gate.set_optimizer(optimizer);
+        self.o_gate.set_optimizer(optimizer);
+    }
+
+
+    /// accumulate the gradients of every gate across a batch
+    fn accumulate_gradients(&mut self, accumulate: bool) {
+        self.f_gate.accumulate_gradients(accumulate);
+        self.e_gate.accumulate_gradients(accumulate);
+        self.o_gate.accumulate_gradients(accumulate);
+    }
+
+
+    fn apply_gradients(&mut self, scale: f32) {
+        self.f_gate.apply_gradients(scale);
+        self.e_gate.apply_gradients(scale);
+        self.o_gate.apply_gradients(scale);
+    }
+
 }
 
 
@@ -150,6 +328,7 @@ impl Clone for GRU {
             output_size: self.output_size,
             current_memory: vec![0.0; self.memory_size as usize],
             current_output: vec![0.0; self.output_size as usize],
+            states: GRUState::new(),
             f_gate: self.f_gate.clone(),
             o_gate: self.o_gate.clone(),
             e_gate: self.e_gate.clone(),
@@ -168,18 +347,19 @@ impl Genome<GRU, NeatEnvironment> for GRU
 
     /// implement how to crossover two GRU layers 
     #[inline]
-    fn crossover(child: &GRU, parent_two: &GRU, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Option<GRU> {
+    fn crossover(child: &GRU, parent_two: &GRU, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Result<GRU, Box<dyn Error>> {
         let child = GRU {
             input_size: child.input_size,
             memory_size: child.memory_size,
             output_size: child.output_size,
             current_memory: vec![0.0; child.memory_size as usize],
             current_output: vec![0.0; child.output_size as usize],
+            states: GRUState::new(),
             f_gate: Dense::crossover(&child.f_gate, &parent_two.f_gate, Arc::clone(&env), crossover_rate)?,
             o_gate: Dense::crossover(&child.o_gate, &parent_two.o_gate, Arc::clone(&env), crossover_rate)?,
             e_gate: Dense::crossover(&child.e_gate, &parent_two.e_gate, Arc::clone(&env), crossover_rate)?,
         };
-        Some(child)
+        Ok(child)
     }
 
 
@@ -192,6 +372,16 @@ impl Genome<GRU, NeatEnvironment> for GRU
         result += Dense::distance(&one.e_gate, &two.e_gate, Arc::clone(&env));
         result
     }
+
+
+    /// the alignment of every gate added together
+    #[inline]
+    fn alignment(one: &GRU, two: &GRU) -> Option<Alignment> {
+        [(&one.f_gate, &two.f_gate), (&one.o_gate, &two.o_gate), (&one.e_gate, &two.e_gate)]
+            .iter()
+            .map(|(gate_one, gate_two)| Dense::alignment(gate_one, gate_two))
+            .sum()
+    }
 }
 
 /// implement display for the GRU layer of the network
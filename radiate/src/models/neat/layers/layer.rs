@@ -1,6 +1,13 @@
 
 use std::any::Any;
 use std::fmt::Debug;
+use uuid::Uuid;
+
+use super::super::activation::Precision;
+use super::super::optimizer::Optimizer;
+use super::super::bias::Bias;
+use super::super::weights::{WeightStats, WeightSnapshot};
+use crate::error::NeatError;
 
 
 /// Layer is a layer in the neural network. In order for 
@@ -15,12 +22,19 @@ pub trait Layer: LayerClone + Any + Debug + Send + Sync {
     /// if the layer is just being evolved, it needs to not keep track of the 
     /// meta data within because there is no need for the network  to backprop after
     /// Return the output as a vec 
-    fn forward(&mut self, inputs: &Vec<f32>) -> Option<Vec<f32>>;
+    fn forward(&mut self, inputs: &Vec<f32>) -> Result<Vec<f32>, NeatError>;
 
     /// Take the errors of the feed forward and backpropagate them through the network
     /// to adjust the weights of the connections between the neurons. Return the error 
     /// of the input neurons from this layer - needed to transfer error from layer to layer
-    fn backward(&mut self, errors: &Vec<f32>, learning_rate: f32) -> Option<Vec<f32>>;
+    fn backward(&mut self, errors: &Vec<f32>, learning_rate: f32) -> Result<Vec<f32>, NeatError>;
+
+    /// propagate each input vec through this layer in order, the same as calling forward on each
+    /// of them one after another (so recurrent layers take them as time steps). Layers can override
+    /// this to reuse their buffers across the batch instead of setting up every pass from scratch
+    fn forward_batch(&mut self, inputs: &[Vec<f32>]) -> Result<Vec<Vec<f32>>, NeatError> {
+        inputs.iter().map(|input| self.forward(input)).collect()
+    }
 
     /// Get a reference to the underlying type without generics in order to downcast to a concrete type
     fn as_ref_any(&self) -> &dyn Any;
@@ -41,6 +55,52 @@ pub trait Layer: LayerClone + Any + Debug + Send + Sync {
     /// remove the tracer from a layer so that it can be evolved without keeping track of data
     fn remove_tracer(&mut self) { }
 
+    /// switch the layer between training (backprop) and inference, which is the default. Only layers
+    /// which act differently while training (ie: Dropout) need to implement it
+    fn set_training(&mut self, _training: bool) { }
+
+    /// set how exactly the activation functions in the layer are computed, not a necessary implementation
+    fn set_precision(&mut self, _precision: Precision) { }
+
+    /// set how the layer turns its gradients into weight changes, not a necessary implementation
+    fn set_optimizer(&mut self, _optimizer: Optimizer) { }
+
+    /// set where the biases of the neurons in the layer come from, not a necessary implementation
+    fn set_bias(&mut self, _bias: Bias) { }
+
+    /// while accumulating, backward sums the weight and bias steps instead of taking them
+    /// so a whole batch is stepped at once by apply_gradients. Turning it off drops anything
+    /// not yet applied. Not a necessary implementation, layers without it step every backward
+    fn accumulate_gradients(&mut self, _accumulate: bool) { }
+
+    /// take the accumulated steps scaled by scale (ie: 1 / batch size to step by the mean)
+    /// and clear them for the next batch
+    fn apply_gradients(&mut self, _scale: f32) { }
+
+    /// give visit the innovation number and weight of every connection in the layer to read or
+    /// change, the layer keeps itself consistent with whatever weights visit leaves behind.
+    /// Not a necessary implementation, layers without it have no weights to visit
+    fn visit_weights_mut(&mut self, _visit: &mut dyn FnMut(Uuid, &mut f32)) { }
+
+    /// summarize the weights of the layer's active connections, see WeightStats.
+    /// Not a necessary implementation, layers without it report no weights
+    fn weight_stats(&self) -> WeightStats {
+        WeightStats::default()
+    }
+
+    /// take the weights, biases and optimizer state of the layer so restore can put them back,
+    /// ie: to score what the layer can learn without keeping what it learned. Not a necessary
+    /// implementation, layers without it return None
+    fn snapshot(&self) -> Option<WeightSnapshot> {
+        None
+    }
+
+    /// put back a snapshot taken of this layer, errors if it was taken of a different layer
+    /// or the layer has gained edges or neurons since
+    fn restore(&mut self, _snapshot: &WeightSnapshot) -> Result<(), NeatError> {
+        Err(NeatError::Unsupported("restoring the weights of this layer"))
+    }
+
 }
 
 
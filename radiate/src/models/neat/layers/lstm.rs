@@ -5,6 +5,8 @@ use std::fmt;
 use std::any::Any;
 use std::sync::{Arc, RwLock};
 use std::thread;
+use std::error::Error;
+use uuid::Uuid;
 use super::{
     layertype::LayerType,
     layer::Layer,
@@ -12,11 +14,15 @@ use super::{
     vectorops
 };    
 use super::super::{
-    activation::Activation,
+    activation::{Activation, Precision},
+    optimizer::Optimizer,
+    weights::{WeightStats, WeightSnapshot},
     neatenv::NeatEnvironment,
 };    
 
 use crate::Genome;
+use crate::engine::generation::Alignment;
+use crate::error::NeatError;
 
 
 
@@ -60,6 +66,15 @@ impl LSTMState {
         self.o_gate_output.push(og);
         self.memory_states.push(mem_state);
     }
+
+
+    /// drop all but the last n time steps
+    pub fn keep_last(&mut self, n: usize) {
+        for states in [&mut self.f_gate_output, &mut self.i_gate_output, &mut self.s_gate_output, &mut self.o_gate_output, &mut self.memory_states] {
+            let dropped = states.len().saturating_sub(n);
+            states.drain(..dropped);
+        }
+    }
 }
 
 
@@ -73,9 +88,14 @@ pub struct LSTM {
     pub memory_size: u32,
     pub output_size: u32,
     pub activation: Activation,
+    #[serde(default)]
+    pub precision: Precision,
     pub memory: Vec<f32>,
     pub hidden: Vec<f32>,
     pub states: LSTMState,
+    /// the most time steps backprop goes back through, None for every step since the last reset
+    #[serde(default)]
+    pub bptt_window: Option<usize>,
     pub g_gate: Arc<RwLock<Dense>>,
     pub i_gate: Arc<RwLock<Dense>>,
     pub f_gate: Arc<RwLock<Dense>>,
@@ -95,9 +115,11 @@ impl LSTM {
             memory_size,
             output_size,
             activation,
+            precision: Precision::Exact,
             memory: vec![0.0; memory_size as usize],
             hidden: vec![0.0; memory_size as usize],
             states: LSTMState::new(),
+            bptt_window: None,
             g_gate: Arc::new(RwLock::new(Dense::new(cell_input, memory_size, LayerType::DensePool, Activation::Tanh))),
             i_gate: Arc::new(RwLock::new(Dense::new(cell_input, memory_size, LayerType::DensePool, Activation::Sigmoid))),
             f_gate: Arc::new(RwLock::new(Dense::new(cell_input, memory_size, LayerType::DensePool, Activation::Sigmoid))),
@@ -108,11 +130,35 @@ impl LSTM {
 
 
 
+    /// Truncate backpropagation through time to the last window time steps. While training, only
+    /// the gate outputs and traces of the last window steps are kept so a long sequence doesn't keep
+    /// growing them. Each backward steps back through one of them, newest first, with the error of
+    /// that step's output and the hidden and memory gradients carried back from the steps after it,
+    /// so every output's error reaches back to the oldest step in the window. The outputs of steps
+    /// older than the window can't be stepped back through and give no error. A window of 0 is taken as 1
+    pub fn set_bptt_window(&mut self, window: usize) {
+        self.bptt_window = Some(window.max(1));
+    }
+
+
+
+    /// drop the states and gate traces older than the window
+    fn truncate(&mut self, window: usize) {
+        self.states.keep_last(window);
+        for gate in [&self.g_gate, &self.i_gate, &self.f_gate, &self.o_gate, &self.v_gate] {
+            if let Some(tracer) = gate.write().unwrap().trace_states.as_mut() {
+                tracer.keep_last(window);
+            }
+        }
+    }
+
+
+
     /// Feed forward with each forward propagation being executed in a separate thread to speed up
     /// the forward pass if the network is NOT being evolved. If it is, there are already so many threads
     /// working to optimize the entire population that extra threading is unnecessary and might actually slow it down
     #[inline]
-    pub fn step_forward_async(&mut self, inputs: &[f32]) -> Option<Vec<f32>> {
+    pub fn step_forward_async(&mut self, inputs: &[f32]) -> Result<Vec<f32>, NeatError> {
         // get the previous state and output and create the input to the layer
         let mut hidden_input = self.hidden.clone();
         hidden_input.extend(inputs);
@@ -131,16 +177,16 @@ impl LSTM {
         let i_input = Arc::clone(&hidden_async);
 
         // spawn the threads 
-        let g_output = thread::spawn(move || { return g_gate_clone.write().unwrap().forward(&*g_input).unwrap(); });
-        let o_output = thread::spawn(move || { return o_gate_clone.write().unwrap().forward(&*o_input).unwrap(); });
-        let f_output = thread::spawn(move || { return f_gate_clone.write().unwrap().forward(&*f_input).unwrap(); });
-        let i_output = thread::spawn(move || { return i_gate_clone.write().unwrap().forward(&*i_input).unwrap(); });
+        let g_output = thread::spawn(move || { return g_gate_clone.write().unwrap().forward(&*g_input); });
+        let o_output = thread::spawn(move || { return o_gate_clone.write().unwrap().forward(&*o_input); });
+        let f_output = thread::spawn(move || { return f_gate_clone.write().unwrap().forward(&*f_input); });
+        let i_output = thread::spawn(move || { return i_gate_clone.write().unwrap().forward(&*i_input); });
 
         // current memory and output need to be mutable but we also want to save that data for bptt
-        let mut curr_state = g_output.join().ok()?;
-        let mut curr_output = o_output.join().ok()?;
-        let f_curr = f_output.join().ok()?;
-        let i_curr = i_output.join().ok()?;
+        let mut curr_state = g_output.join().map_err(|_| NeatError::Poisoned)??;
+        let mut curr_output = o_output.join().map_err(|_| NeatError::Poisoned)??;
+        let f_curr = f_output.join().map_err(|_| NeatError::Poisoned)??;
+        let i_curr = i_output.join().map_err(|_| NeatError::Poisoned)??;
 
         let g_out = curr_state.clone();
         let o_out = curr_output.clone();
@@ -149,7 +195,7 @@ impl LSTM {
         vectorops::element_multiply(&mut self.memory, &f_curr);
         vectorops::element_multiply(&mut curr_state, &i_curr);
         vectorops::element_add(&mut self.memory, &curr_state);
-        vectorops::element_multiply(&mut curr_output, &vectorops::element_activate(&self.memory, Activation::Tanh));
+        vectorops::element_multiply(&mut curr_output, &vectorops::element_activate_with(&self.memory, Activation::Tanh, self.precision));
 
         // update the state parameters only if the gates are traceable and the data needs to be collected
         self.states.update_forward(f_curr, i_curr, g_out, o_out, self.memory.clone());   
@@ -157,14 +203,18 @@ impl LSTM {
         // return the output of the layer
         // keep track of the memory and the current output and the current state
         self.hidden = curr_output;
-        self.v_gate.write().unwrap().forward(&self.hidden)
+        let output = self.v_gate.write().unwrap().forward(&self.hidden)?;
+        if let Some(window) = self.bptt_window {
+            self.truncate(window);
+        }
+        Ok(output)
     }
 
 
 
     /// step forward synchronously
     #[inline]
-    pub fn step_forward(&mut self, inputs: &[f32]) -> Option<Vec<f32>> {
+    pub fn step_forward(&mut self, inputs: &[f32]) -> Result<Vec<f32>, NeatError> {
         // get the previous state and output and create the input to the layer
         // let mut previous_state = &mut self.memory;
         let mut hidden_input = self.hidden.clone();
@@ -184,7 +234,7 @@ impl LSTM {
         vectorops::element_multiply(&mut self.memory, &f_output);
         vectorops::element_multiply(&mut current_state, &i_output);
         vectorops::element_add(&mut self.memory, &current_state);
-        vectorops::element_multiply(&mut current_output, &vectorops::element_activate(&self.memory, Activation::Tanh));
+        vectorops::element_multiply(&mut current_output, &vectorops::element_activate_with(&self.memory, Activation::Tanh, self.precision));
 
         // return the output of the layer
         // keep track of the memory and the current output and the current state
@@ -198,17 +248,17 @@ impl LSTM {
     /// index, and use that data to compute the gradient steps for each weight in each gated network.
     /// If update is true, the gates will take the accumulated gradient steps, and add them to their respective weight values
     #[inline]
-    pub fn step_back(&mut self, errors: &Vec<f32>, l_rate: f32) -> Option<Vec<f32>> {
+    pub fn step_back(&mut self, errors: &Vec<f32>, l_rate: f32) -> Result<Vec<f32>, NeatError> {
         // get the derivative of the cell and hidden state from the previous step as well as the previous memory state
-        let dh_next = self.states.d_prev_hidden.clone()?;
-        let dc_next = self.states.d_prev_memory.clone()?;
+        let dh_next = self.states.d_prev_hidden.clone().ok_or(NeatError::MissingState("previous hidden gradient"))?;
+        let dc_next = self.states.d_prev_memory.clone().ok_or(NeatError::MissingState("previous memory gradient"))?;
 
         // unpack the current gate outputs 
-        let c_old = self.states.memory_states.pop()?;
-        let g_curr = self.states.s_gate_output.pop()?;
-        let i_curr = self.states.i_gate_output.pop()?;
-        let f_curr = self.states.f_gate_output.pop()?;
-        let o_curr = self.states.o_gate_output.pop()?;
+        let c_old = self.states.memory_states.pop().ok_or(NeatError::MissingState("memory state"))?;
+        let g_curr = self.states.s_gate_output.pop().ok_or(NeatError::MissingState("g gate output"))?;
+        let i_curr = self.states.i_gate_output.pop().ok_or(NeatError::MissingState("i gate output"))?;
+        let f_curr = self.states.f_gate_output.pop().ok_or(NeatError::MissingState("f gate output"))?;
+        let o_curr = self.states.o_gate_output.pop().ok_or(NeatError::MissingState("o gate output"))?;
 
         
         // compute the hidden to output gradient
@@ -224,7 +274,7 @@ impl LSTM {
         vectorops::element_multiply(&mut dho, &vectorops::element_deactivate(&o_curr, self.o_gate.read().unwrap().activation));
         let o_gate_clone = Arc::clone(&self.o_gate);
         let o_handle = thread::spawn(move || { 
-            return o_gate_clone.write().unwrap().backward(&dho, l_rate).unwrap(); 
+            return o_gate_clone.write().unwrap().backward(&dho, l_rate); 
         });
         
         // Gradient for c in h = ho * tanh(c), note we're adding dc_next here     
@@ -241,7 +291,7 @@ impl LSTM {
         vectorops::element_multiply(&mut dhf, &vectorops::element_deactivate(&f_curr, self.f_gate.read().unwrap().activation));
         let f_gate_clone = Arc::clone(&self.f_gate);
         let f_handle = thread::spawn(move || { 
-            return f_gate_clone.write().unwrap().backward(&dhf, l_rate).unwrap(); 
+            return f_gate_clone.write().unwrap().backward(&dhf, l_rate); 
         });
 
         // Gradient for hi in c = hf * c_old + hi * hc     
@@ -251,7 +301,7 @@ impl LSTM {
         vectorops::element_multiply(&mut dhi, &vectorops::element_deactivate(&i_curr, self.i_gate.read().unwrap().activation));
         let i_gate_clone = Arc::clone(&self.i_gate);
         let i_handle = thread::spawn(move || { 
-            return i_gate_clone.write().unwrap().backward(&dhi, l_rate).unwrap(); 
+            return i_gate_clone.write().unwrap().backward(&dhi, l_rate); 
         });
 
         // Gradient for hc in c = hf * c_old + hi * hc     
@@ -261,16 +311,16 @@ impl LSTM {
         vectorops::element_multiply(&mut dhc, &vectorops::element_deactivate(&g_curr, self.g_gate.read().unwrap().activation));
         let g_gate_clone = Arc::clone(&self.g_gate);
         let g_handle = thread::spawn(move || { 
-            return g_gate_clone.write().unwrap().backward(&dhc, l_rate).unwrap(); 
+            return g_gate_clone.write().unwrap().backward(&dhc, l_rate); 
         });
 
         // As X was used in multiple gates, the gradient must be accumulated here     
         // dX = dXo + dXc + dXi + dXf
         let mut dx = vec![0.0; (self.input_size + self.memory_size) as usize];
-        vectorops::element_add(&mut dx, &o_handle.join().ok()?);
-        vectorops::element_add(&mut dx, &f_handle.join().ok()?);
-        vectorops::element_add(&mut dx, &i_handle.join().ok()?);
-        vectorops::element_add(&mut dx, &g_handle.join().ok()?);
+        vectorops::element_add(&mut dx, &o_handle.join().map_err(|_| NeatError::Poisoned)??);
+        vectorops::element_add(&mut dx, &f_handle.join().map_err(|_| NeatError::Poisoned)??);
+        vectorops::element_add(&mut dx, &i_handle.join().map_err(|_| NeatError::Poisoned)??);
+        vectorops::element_add(&mut dx, &g_handle.join().map_err(|_| NeatError::Poisoned)??);
         
         // Split the concatenated X, so that we get our gradient of h_old     
         // dh_next = dx[:, :H]
@@ -283,9 +333,11 @@ impl LSTM {
         self.states.d_prev_memory = Some(dc_next);
 
         // return the error of the input given to the layer
-        Some(dx[..self.input_size as usize].to_vec())
+        Ok(dx[..self.input_size as usize].to_vec())
     }
 
+
+
 }
 
 
@@ -299,8 +351,9 @@ impl Layer for LSTM {
     /// traditionally, step forward asynchronously by spawning a thread for each individual gate
     /// which results in speeds about double as a synchronous thread.
     #[inline]
-    fn forward(&mut self, inputs: &Vec<f32>) -> Option<Vec<f32>> {
-        if self.f_gate.read().map(|x| x.trace_states.is_some()).ok()? {
+    fn forward(&mut self, inputs: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.input_size as usize, inputs.len())?;
+        if self.f_gate.read().map(|x| x.trace_states.is_some()).map_err(|_| NeatError::Poisoned)? {
             return self.step_forward_async(inputs);
         }
         self.step_forward(inputs)
@@ -308,9 +361,56 @@ impl Layer for LSTM {
 
 
 
+    /// step forward through each input as a time step, holding onto the gates and the buffer the
+    /// hidden state and input are joined in for the whole sequence instead of taking them every step.
+    /// While training the gates need to trace each step so this falls back to stepping one at a time
+    fn forward_batch(&mut self, inputs: &[Vec<f32>]) -> Result<Vec<Vec<f32>>, NeatError> {
+        for input in inputs.iter() {
+            NeatError::check_input(self.input_size as usize, input.len())?;
+        }
+        if self.f_gate.read().map(|x| x.trace_states.is_some()).map_err(|_| NeatError::Poisoned)? {
+            return inputs.iter().map(|input| self.step_forward_async(input)).collect();
+        }
+        let mut f_gate = self.f_gate.write().map_err(|_| NeatError::Poisoned)?;
+        let mut i_gate = self.i_gate.write().map_err(|_| NeatError::Poisoned)?;
+        let mut o_gate = self.o_gate.write().map_err(|_| NeatError::Poisoned)?;
+        let mut g_gate = self.g_gate.write().map_err(|_| NeatError::Poisoned)?;
+        let mut v_gate = self.v_gate.write().map_err(|_| NeatError::Poisoned)?;
+
+        let mut hidden_input = Vec::with_capacity((self.memory_size + self.input_size) as usize);
+        let mut outputs = Vec::with_capacity(inputs.len());
+        for input in inputs.iter() {
+            hidden_input.clear();
+            hidden_input.extend_from_slice(&self.hidden);
+            hidden_input.extend_from_slice(input);
+
+            let f_output = f_gate.forward(&hidden_input)?;
+            let i_output = i_gate.forward(&hidden_input)?;
+            let mut current_output = o_gate.forward(&hidden_input)?;
+            let mut current_state = g_gate.forward(&hidden_input)?;
+
+            // update the current state the same as step_forward
+            vectorops::element_multiply(&mut self.memory, &f_output);
+            vectorops::element_multiply(&mut current_state, &i_output);
+            vectorops::element_add(&mut self.memory, &current_state);
+            vectorops::element_multiply(&mut current_output, &vectorops::element_activate_with(&self.memory, Activation::Tanh, self.precision));
+
+            outputs.push(v_gate.forward(&current_output)?);
+            self.hidden = current_output;
+        }
+        Ok(outputs)
+    }
+
+
+
     /// apply backpropagation through time asynchronously because this is not done during evolution
     #[inline]
-    fn backward(&mut self, errors: &Vec<f32>, learning_rate: f32) -> Option<Vec<f32>> {
+    fn backward(&mut self, errors: &Vec<f32>, learning_rate: f32) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_errors(self.output_size as usize, errors.len())?;
+        // with a window the steps older than it were dropped while feeding forward
+        if self.bptt_window.is_some() && self.states.memory_states.is_empty() {
+            return Ok(vec![0.0; self.input_size as usize]);
+        }
         if self.states.d_prev_hidden.is_none() && self.states.d_prev_memory.is_none() {
             self.states.d_prev_memory = Some(vec![0.0; self.memory_size as usize]);      
             self.states.d_prev_hidden = Some(vec![0.0; self.memory_size as usize]);          
@@ -357,6 +457,100 @@ impl Layer for LSTM {
 
 
 
+    /// set the precision of the layer's own activations and those of each gate
+    fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+        self.g_gate.write().unwrap().set_precision(precision);
+        self.i_gate.write().unwrap().set_precision(precision);
+        self.f_gate.write().unwrap().set_precision(precision);
+        self.o_gate.write().unwrap().set_precision(precision);
+        self.v_gate.write().unwrap().set_precision(precision);
+    }
+
+
+
+    /// the stats of the weights of every gate together
+    fn weight_stats(&self) -> WeightStats {
+        let weights = [&self.g_gate, &self.i_gate, &self.f_gate, &self.o_gate, &self.v_gate]
+            .iter()
+            .flat_map(|gate| gate.read().unwrap().active_weights())
+            .collect::<Vec<_>>();
+        WeightStats::new(&weights)
+    }
+
+
+
+    /// visit the weights of each gate
+    fn visit_weights_mut(&mut self, visit: &mut dyn FnMut(Uuid, &mut f32)) {
+        self.g_gate.write().unwrap().visit_weights_mut(visit);
+        self.i_gate.write().unwrap().visit_weights_mut(visit);
+        self.f_gate.write().unwrap().visit_weights_mut(visit);
+        self.o_gate.write().unwrap().visit_weights_mut(visit);
+        self.v_gate.write().unwrap().visit_weights_mut(visit);
+    }
+
+
+
+    /// the snapshot of each gate
+    fn snapshot(&self) -> Option<WeightSnapshot> {
+        [&self.g_gate, &self.i_gate, &self.f_gate, &self.o_gate, &self.v_gate]
+            .iter()
+            .map(|gate| gate.read().unwrap().snapshot())
+            .collect::<Option<Vec<_>>>()
+            .map(WeightSnapshot::Gates)
+    }
+
+
+
+    /// restore each gate from its snapshot
+    fn restore(&mut self, snapshot: &WeightSnapshot) -> Result<(), NeatError> {
+        match snapshot {
+            WeightSnapshot::Gates(gates) if gates.len() == 5 => {
+                [&self.g_gate, &self.i_gate, &self.f_gate, &self.o_gate, &self.v_gate]
+                    .iter()
+                    .zip(gates.iter())
+                    .try_for_each(|(gate, taken)| gate.write().map_err(|_| NeatError::Poisoned)?.restore(taken))?;
+                self.states = LSTMState::new();
+                Ok(())
+            },
+            _ => Err(NeatError::SnapshotMismatch)
+        }
+    }
+
+
+
+    /// set the optimizer of each gate
+    fn set_optimizer(&mut self, optimizer: Optimizer) {
+        self.g_gate.write().unwrap().set_optimizer(optimizer);
+        self.i_gate.write().unwrap().set_optimizer(optimizer);
+        self.f_gate.write().unwrap().set_optimizer(optimizer);
+        self.o_gate.write().unwrap().set_optimizer(optimizer);
+        self.v_gate.write().unwrap().set_optimizer(optimizer);
+    }
+
+
+
+    /// accumulate the gradients of every gate across a batch
+    fn accumulate_gradients(&mut self, accumulate: bool) {
+        self.g_gate.write().unwrap().accumulate_gradients(accumulate);
+        self.i_gate.write().unwrap().accumulate_gradients(accumulate);
+        self.f_gate.write().unwrap().accumulate_gradients(accumulate);
+        self.o_gate.write().unwrap().accumulate_gradients(accumulate);
+        self.v_gate.write().unwrap().accumulate_gradients(accumulate);
+    }
+
+
+
+    fn apply_gradients(&mut self, scale: f32) {
+        self.g_gate.write().unwrap().apply_gradients(scale);
+        self.i_gate.write().unwrap().apply_gradients(scale);
+        self.f_gate.write().unwrap().apply_gradients(scale);
+        self.o_gate.write().unwrap().apply_gradients(scale);
+        self.v_gate.write().unwrap().apply_gradients(scale);
+    }
+
+
+
     fn as_ref_any(&self) -> &dyn Any
         where Self: Sized + 'static
     {
@@ -390,9 +584,11 @@ impl Clone for LSTM {
             memory_size: self.memory_size,
             output_size: self.output_size,
             activation: self.activation.clone(),
+            precision: self.precision,
             memory: vec![0.0; self.memory_size as usize],
             hidden: vec![0.0; self.memory_size as usize],
             states: LSTMState::new(),
+            bptt_window: self.bptt_window,
             g_gate: Arc::new(RwLock::new((*self.g_gate.read().unwrap()).clone())), 
             i_gate: Arc::new(RwLock::new((*self.i_gate.read().unwrap()).clone())), 
             f_gate: Arc::new(RwLock::new((*self.f_gate.read().unwrap()).clone())), 
@@ -413,22 +609,24 @@ impl Genome<LSTM, NeatEnvironment> for LSTM
 
     /// implement how to crossover two LSTM layers 
     #[inline]
-    fn crossover(child: &LSTM, parent_two: &LSTM, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Option<LSTM> {
+    fn crossover(child: &LSTM, parent_two: &LSTM, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Result<LSTM, Box<dyn Error>> {
         let child = LSTM {
             input_size: child.input_size,
             memory_size: child.memory_size,
             output_size: child.output_size,
             activation: child.activation,
+            precision: child.precision,
             memory: vec![0.0; child.memory_size as usize],
             hidden: vec![0.0; child.memory_size as usize],
             states: LSTMState::new(),
+            bptt_window: child.bptt_window,
             g_gate: Arc::new(RwLock::new(Dense::crossover(&child.g_gate.read().unwrap(), &parent_two.g_gate.read().unwrap(), Arc::clone(&env), crossover_rate)?)),
             i_gate: Arc::new(RwLock::new(Dense::crossover(&child.i_gate.read().unwrap(), &parent_two.i_gate.read().unwrap(), Arc::clone(&env), crossover_rate)?)),
             f_gate: Arc::new(RwLock::new(Dense::crossover(&child.f_gate.read().unwrap(), &parent_two.f_gate.read().unwrap(), Arc::clone(&env), crossover_rate)?)),
             o_gate: Arc::new(RwLock::new(Dense::crossover(&child.o_gate.read().unwrap(), &parent_two.o_gate.read().unwrap(), Arc::clone(&env), crossover_rate)?)),
             v_gate: Arc::new(RwLock::new(Dense::crossover(&child.v_gate.read().unwrap(), &parent_two.v_gate.read().unwrap(), Arc::clone(&env), crossover_rate)?)),
         };
-        Some(child)
+        Ok(child)
     }
 
 
@@ -443,6 +641,16 @@ impl Genome<LSTM, NeatEnvironment> for LSTM
         result += Dense::distance(&one.v_gate.read().unwrap(), &two.v_gate.read().unwrap(), Arc::clone(&env));
         result
     }
+
+
+    /// the alignment of every gate added together
+    #[inline]
+    fn alignment(one: &LSTM, two: &LSTM) -> Option<Alignment> {
+        [(&one.g_gate, &two.g_gate), (&one.i_gate, &two.i_gate), (&one.f_gate, &two.f_gate), (&one.o_gate, &two.o_gate), (&one.v_gate, &two.v_gate)]
+            .iter()
+            .map(|(gate_one, gate_two)| Dense::alignment(&gate_one.read().unwrap(), &gate_two.read().unwrap()))
+            .sum()
+    }
 }
 
 /// implement display for the LSTM layer of the network
@@ -2,6 +2,13 @@ pub mod layer;
 pub mod dense;
 pub mod lstm;
 pub mod gru;
+pub mod sparse;
+pub mod conv1d;
+pub mod conv2d;
+pub mod pool;
+pub mod flatten;
+pub mod dropout;
+pub mod norm;
 pub mod vectorops;
 
 
@@ -13,7 +20,12 @@ pub mod layertype {
         DensePool,
         Dense,
         LSTM,
-        GRU
+        GRU,
+        Conv1D,
+        Conv2D,
+        MaxPool2D,
+        Dropout,
+        Norm
     }
 
 }
@@ -0,0 +1,353 @@
+
+use std::fmt;
+use std::any::Any;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use uuid::Uuid;
+
+use super::layer::Layer;
+use super::super::{
+    neatenv::NeatEnvironment,
+    optimizer::{Optimizer, OptimizerState},
+    weights::WeightStats
+};
+
+use crate::Genome;
+use crate::engine::generation::Alignment;
+use crate::error::NeatError;
+use crate::rng;
+
+
+
+/// What the inputs of a Norm layer are normalized against
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+pub enum NormType {
+    /// each input against its running mean and variance. Samples are fed through the network one at a
+    /// time, so rather than the statistics of a batch these are a moving average over the samples seen
+    /// while training, updated with each one, and frozen outside of training
+    Batch,
+    /// each sample's inputs against the mean and variance of that sample's inputs
+    Layer
+}
+
+
+
+/// A normalization layer, every input is normalized to zero mean and unit variance then scaled by
+/// its gamma and shifted by its beta, both of which are learned. Keeps the activations of deep
+/// stacks from exploding or vanishing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Norm {
+    pub size: usize,
+    pub norm_type: NormType,
+    pub gamma: Vec<f32>,
+    pub beta: Vec<f32>,
+    pub running_mean: Vec<f32>,
+    pub running_variance: Vec<f32>,
+    /// how much each training sample moves the running statistics
+    pub momentum: f32,
+    pub epsilon: f32,
+    #[serde(default)]
+    pub optimizer: Optimizer,
+    #[serde(skip)]
+    training: bool,
+    #[serde(skip)]
+    trace: Option<Vec<NormPass>>,
+    #[serde(skip)]
+    last: Option<NormPass>,
+    #[serde(skip)]
+    gradients: Option<NormGradients>,
+    #[serde(default)]
+    optimizer_state: OptimizerState
+}
+
+
+
+/// the normalized inputs of one forward pass and the inverse of the standard deviation they were
+/// normalized with, one per input for batch norm and a single one for layer norm
+#[derive(Debug, Clone)]
+struct NormPass {
+    normalized: Vec<f32>,
+    inverse_deviation: Vec<f32>
+}
+
+
+
+/// the gamma and beta gradients summed over a batch and the learning rate they were found with
+#[derive(Debug, Clone, Default)]
+struct NormGradients {
+    gamma: Vec<f32>,
+    beta: Vec<f32>,
+    rate: f32
+}
+
+
+
+impl Norm {
+
+    /// create a new normalization layer over size inputs which starts out as the identity,
+    /// with a gamma of 1 and beta of 0
+    pub fn new(size: usize, norm_type: NormType) -> Self {
+        Norm {
+            size,
+            norm_type,
+            gamma: vec![1.0; size],
+            beta: vec![0.0; size],
+            running_mean: vec![0.0; size],
+            running_variance: vec![1.0; size],
+            momentum: 0.1,
+            epsilon: 1e-5,
+            optimizer: Optimizer::default(),
+            training: false,
+            trace: None,
+            last: None,
+            gradients: None,
+            optimizer_state: OptimizerState::default()
+        }
+    }
+
+
+    /// set how much each training sample moves the running statistics of batch norm
+    pub fn momentum(mut self, momentum: f32) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+
+    /// the normalized inputs and the inverse standard deviations they were normalized with
+    fn normalize(&mut self, inputs: &[f32]) -> NormPass {
+        match self.norm_type {
+            NormType::Batch => {
+                if self.training {
+                    for ((mean, variance), input) in self.running_mean.iter_mut().zip(self.running_variance.iter_mut()).zip(inputs.iter()) {
+                        *mean += self.momentum * (input - *mean);
+                        *variance += self.momentum * ((input - *mean).powi(2) - *variance);
+                    }
+                }
+                let inverse_deviation = self.running_variance.iter()
+                    .map(|variance| 1.0 / (variance + self.epsilon).sqrt())
+                    .collect::<Vec<_>>();
+                let normalized = inputs.iter()
+                    .zip(self.running_mean.iter().zip(inverse_deviation.iter()))
+                    .map(|(input, (mean, inverse))| (input - mean) * inverse)
+                    .collect();
+                NormPass { normalized, inverse_deviation }
+            },
+            NormType::Layer => {
+                let mean = inputs.iter().sum::<f32>() / self.size as f32;
+                let variance = inputs.iter().map(|input| (input - mean).powi(2)).sum::<f32>() / self.size as f32;
+                let inverse = 1.0 / (variance + self.epsilon).sqrt();
+                let normalized = inputs.iter().map(|input| (input - mean) * inverse).collect();
+                NormPass { normalized, inverse_deviation: vec![inverse] }
+            }
+        }
+    }
+
+
+    /// every gamma followed by every beta
+    fn parameters(&self) -> Vec<f32> {
+        self.gamma.iter().chain(self.beta.iter()).copied().collect()
+    }
+
+
+    /// step gamma and beta by their gradients
+    fn step(&mut self, gamma: &[f32], beta: &[f32], scale: f32, rate: f32) {
+        for (index, (value, gradient)) in self.gamma.iter_mut().zip(gamma.iter()).enumerate() {
+            *value += self.optimizer_state.step_weight(&self.optimizer, index, gradient * scale, rate);
+        }
+        for (index, (value, gradient)) in self.beta.iter_mut().zip(beta.iter()).enumerate() {
+            *value += self.optimizer_state.step_bias(&self.optimizer, index, gradient * scale, rate);
+        }
+    }
+}
+
+
+
+#[typetag::serde]
+impl Layer for Norm {
+
+    fn forward(&mut self, inputs: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.size, inputs.len())?;
+        let pass = self.normalize(inputs);
+        let outputs = pass.normalized.iter()
+            .zip(self.gamma.iter().zip(self.beta.iter()))
+            .map(|(normalized, (gamma, beta))| normalized * gamma + beta)
+            .collect();
+        match self.trace.as_mut() {
+            Some(trace) => trace.push(pass),
+            None => self.last = Some(pass)
+        }
+        Ok(outputs)
+    }
+
+
+    /// Batch norm's running statistics are treated as constants, so each input's error is just scaled.
+    /// Layer norm's statistics come from the sample itself, so the error of each input also flows
+    /// through the mean and variance to every other input
+    fn backward(&mut self, errors: &Vec<f32>, learning_rate: f32) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_errors(self.size, errors.len())?;
+        let pass = match self.trace.as_mut() {
+            Some(trace) => trace.pop(),
+            None => self.last.clone()
+        };
+        let pass = pass.ok_or(NeatError::MissingState("traced forward pass"))?;
+        let gamma = errors.iter().zip(pass.normalized.iter()).map(|(error, normalized)| error * normalized).collect::<Vec<_>>();
+        let scaled = errors.iter().zip(self.gamma.iter()).map(|(error, gamma)| error * gamma).collect::<Vec<_>>();
+        let input_errors = match self.norm_type {
+            NormType::Batch => scaled.iter()
+                .zip(pass.inverse_deviation.iter())
+                .map(|(error, inverse)| error * inverse)
+                .collect(),
+            NormType::Layer => {
+                let count = self.size as f32;
+                let mean_error = scaled.iter().sum::<f32>() / count;
+                let mean_projection = scaled.iter().zip(pass.normalized.iter()).map(|(error, normalized)| error * normalized).sum::<f32>() / count;
+                scaled.iter()
+                    .zip(pass.normalized.iter())
+                    .map(|(error, normalized)| pass.inverse_deviation[0] * (error - mean_error - normalized * mean_projection))
+                    .collect()
+            }
+        };
+        match self.gradients.as_mut() {
+            Some(gradients) => {
+                gradients.gamma.resize(self.size, 0.0);
+                gradients.beta.resize(self.size, 0.0);
+                gradients.rate = learning_rate;
+                gradients.gamma.iter_mut().zip(gamma.iter()).for_each(|(sum, gradient)| *sum += gradient);
+                gradients.beta.iter_mut().zip(errors.iter()).for_each(|(sum, gradient)| *sum += gradient);
+            },
+            None => {
+                self.optimizer_state.steps += 1;
+                self.step(&gamma, errors, 1.0, learning_rate);
+            }
+        }
+        Ok(input_errors)
+    }
+
+
+    fn reset(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.clear();
+        }
+        self.last = None;
+    }
+
+
+    fn add_tracer(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+
+    fn remove_tracer(&mut self) {
+        self.trace = None;
+    }
+
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+
+    fn set_optimizer(&mut self, optimizer: Optimizer) {
+        if self.optimizer != optimizer {
+            self.optimizer_state = OptimizerState::default();
+        }
+        self.optimizer = optimizer;
+    }
+
+
+    fn accumulate_gradients(&mut self, accumulate: bool) {
+        self.gradients = if accumulate { Some(NormGradients::default()) } else { None };
+    }
+
+
+    fn apply_gradients(&mut self, scale: f32) {
+        if let Some(gradients) = self.gradients.take() {
+            self.optimizer_state.steps += 1;
+            self.step(&gradients.gamma, &gradients.beta, scale, gradients.rate);
+            self.gradients = Some(NormGradients::default());
+        }
+    }
+
+
+    /// gamma and beta are identified by their position, every gamma then every beta
+    fn visit_weights_mut(&mut self, visit: &mut dyn FnMut(Uuid, &mut f32)) {
+        for (index, value) in self.gamma.iter_mut().chain(self.beta.iter_mut()).enumerate() {
+            visit(Uuid::from_u128(index as u128), value);
+        }
+        self.reset();
+    }
+
+
+    fn weight_stats(&self) -> WeightStats {
+        WeightStats::new(&self.parameters())
+    }
+
+
+    fn as_ref_any(&self) -> &dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn as_mut_any(&mut self) -> &mut dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn shape(&self) -> (usize, usize) {
+        (self.size, self.size)
+    }
+}
+
+
+
+impl Genome<Norm, NeatEnvironment> for Norm
+    where Norm: Layer
+{
+
+    /// when crossing over, the child gets the mean of both parents' gamma, beta and running statistics,
+    /// otherwise it takes them from the fitter parent
+    fn crossover(child: &Norm, parent_two: &Norm, _: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Result<Norm, Box<dyn Error>> {
+        let mut new_child = child.clone();
+        new_child.trace = child.trace.as_ref().map(|_| Vec::new());
+        new_child.last = None;
+        if rng::chance(&mut rng::thread(), crossover_rate) && child.size == parent_two.size {
+            let average = |one: &mut Vec<f32>, two: &[f32]| one.iter_mut().zip(two.iter()).for_each(|(a, b)| *a = (*a + b) / 2.0);
+            average(&mut new_child.gamma, &parent_two.gamma);
+            average(&mut new_child.beta, &parent_two.beta);
+            average(&mut new_child.running_mean, &parent_two.running_mean);
+            average(&mut new_child.running_variance, &parent_two.running_variance);
+        }
+        Ok(new_child)
+    }
+
+
+    /// the mean absolute difference between gamma and beta, or 2.0 if the layers are different sizes
+    fn distance(one: &Norm, two: &Norm, _: Arc<RwLock<NeatEnvironment>>) -> f32 {
+        if one.size != two.size || one.norm_type != two.norm_type {
+            return 2.0;
+        }
+        let (one, two) = (one.parameters(), two.parameters());
+        one.iter().zip(two.iter()).map(|(a, b)| (a - b).abs()).sum::<f32>() / one.len().max(1) as f32
+    }
+
+
+    fn alignment(one: &Norm, two: &Norm) -> Option<Alignment> {
+        if one.size == two.size {
+            return Some(Alignment { matched: 2 * one.size, disjoint: 0, excess: 0 });
+        }
+        Some(Alignment { matched: 0, disjoint: 2 * (one.size + two.size), excess: 0 })
+    }
+}
+
+
+
+impl fmt::Display for Norm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Norm=[size={}, type={:?}]", self.size, self.norm_type)
+    }
+}
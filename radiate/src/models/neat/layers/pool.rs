@@ -0,0 +1,182 @@
+
+use std::fmt;
+use std::any::Any;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+use super::layer::Layer;
+use super::super::neatenv::NeatEnvironment;
+
+use crate::Genome;
+use crate::engine::generation::Alignment;
+use crate::error::NeatError;
+
+
+
+/// Max pooling over an image laid out the same way as for Conv2D, each channel of the output
+/// is the biggest value of that channel in a size by size window of the input, slid stride
+/// pixels at a time. There are no weights, so there is nothing to evolve or train, but errors
+/// are passed back to whichever input was the biggest of its window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaxPool2D {
+    pub height: usize,
+    pub width: usize,
+    pub channels: usize,
+    pub size: usize,
+    pub stride: usize,
+    #[serde(skip)]
+    trace: Option<Vec<Vec<usize>>>,
+    #[serde(skip)]
+    last: Option<Vec<usize>>
+}
+
+
+
+impl MaxPool2D {
+
+    /// create a new pooling layer over images of the given (height, width, channels) shape
+    pub fn new(shape: (usize, usize, usize), size: usize, stride: usize) -> Self {
+        let (height, width, channels) = shape;
+        assert!(channels > 0 && size > 0 && stride > 0, "MaxPool2D channels, size and stride must be positive");
+        assert!(height >= size && width >= size, "MaxPool2D window is bigger than the image");
+        MaxPool2D {
+            height,
+            width,
+            channels,
+            size,
+            stride,
+            trace: None,
+            last: None
+        }
+    }
+
+
+    /// the (height, width, channels) of the image this layer outputs
+    pub fn output_shape(&self) -> (usize, usize, usize) {
+        let slide = |size: usize| (size - self.size) / self.stride + 1;
+        (slide(self.height), slide(self.width), self.channels)
+    }
+}
+
+
+
+#[typetag::serde]
+impl Layer for MaxPool2D {
+
+    /// take the biggest value of each channel in every window, remembering where it came from
+    fn forward(&mut self, inputs: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.height * self.width * self.channels, inputs.len())?;
+        let (rows, columns, _) = self.output_shape();
+        let mut sources = Vec::with_capacity(rows * columns * self.channels);
+        for row in 0..rows {
+            for column in 0..columns {
+                for channel in 0..self.channels {
+                    let mut best = None;
+                    for window_row in row * self.stride..row * self.stride + self.size {
+                        for window_column in column * self.stride..column * self.stride + self.size {
+                            let index = (window_row * self.width + window_column) * self.channels + channel;
+                            if best.is_none_or(|best: usize| inputs[index] > inputs[best]) {
+                                best = Some(index);
+                            }
+                        }
+                    }
+                    sources.push(best.ok_or(NeatError::InvalidGraph)?);
+                }
+            }
+        }
+        let outputs = sources.iter().map(|source| inputs[*source]).collect();
+        match self.trace.as_mut() {
+            Some(trace) => trace.push(sources),
+            None => self.last = Some(sources)
+        }
+        Ok(outputs)
+    }
+
+
+    /// each output's error goes to the input it was taken from, the rest get none
+    fn backward(&mut self, errors: &Vec<f32>, _: f32) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_errors(self.shape().1, errors.len())?;
+        let sources = match self.trace.as_mut() {
+            Some(trace) => trace.pop(),
+            None => self.last.clone()
+        };
+        let sources = sources.ok_or(NeatError::MissingState("traced forward pass"))?;
+        let mut input_errors = vec![0.0; self.height * self.width * self.channels];
+        for (source, error) in sources.iter().zip(errors.iter()) {
+            input_errors[*source] += error;
+        }
+        Ok(input_errors)
+    }
+
+
+    fn reset(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.clear();
+        }
+        self.last = None;
+    }
+
+
+    fn add_tracer(&mut self) {
+        self.trace = Some(Vec::new());
+    }
+
+
+    fn remove_tracer(&mut self) {
+        self.trace = None;
+    }
+
+
+    fn as_ref_any(&self) -> &dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn as_mut_any(&mut self) -> &mut dyn Any
+        where Self: Sized + 'static
+    {
+        self
+    }
+
+
+    fn shape(&self) -> (usize, usize) {
+        let (rows, columns, channels) = self.output_shape();
+        (self.height * self.width * self.channels, rows * columns * channels)
+    }
+}
+
+
+
+/// pooling layers have nothing to cross over, they only need to be the same shape
+impl Genome<MaxPool2D, NeatEnvironment> for MaxPool2D
+    where MaxPool2D: Layer
+{
+
+    fn crossover(child: &MaxPool2D, _: &MaxPool2D, _: Arc<RwLock<NeatEnvironment>>, _: f32) -> Result<MaxPool2D, Box<dyn Error>> {
+        let mut new_child = child.clone();
+        new_child.trace = child.trace.as_ref().map(|_| Vec::new());
+        new_child.last = None;
+        Ok(new_child)
+    }
+
+
+    fn distance(one: &MaxPool2D, two: &MaxPool2D, _: Arc<RwLock<NeatEnvironment>>) -> f32 {
+        if one.shape() == two.shape() && one.size == two.size { 0.0 } else { 2.0 }
+    }
+
+
+    fn alignment(_: &MaxPool2D, _: &MaxPool2D) -> Option<Alignment> {
+        Some(Alignment::default())
+    }
+}
+
+
+
+impl fmt::Display for MaxPool2D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MaxPool2D=[height={}, width={}, channels={}, size={}, stride={}]",
+            self.height, self.width, self.channels, self.size, self.stride)
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+
+use super::{
+    dense::Dense,
+    vectorops
+};
+use super::super::{
+    neurontype::NeuronType,
+    activation::{Activation, Precision},
+    direction::NeuronDirection
+};
+use crate::error::NeatError;
+
+
+
+/// A read only, compiled form of a Dense layer for fast forward passes through large evolved graphs.
+/// The graph is sorted once so every neuron can be evaluated in a single pass, and the connections
+/// are laid out CSR style - each neuron's incoming sources and weights sit next to each other in 
+/// one contiguous array instead of being scattered across each neuron's own Vec. Disabled 
+/// connections are dropped entirely. Recurrent connections are kept apart in feedback, they carry
+/// the values of the pass before so they are summed before the pass starts. Mutation still happens
+/// on the Dense layer, compile it again to pick up the changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SparseDense {
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+    /// non input neurons in the order they are evaluated
+    pub order: Vec<usize>,
+    /// order[i]'s connections are sources[offsets[i]..offsets[i + 1]]
+    pub offsets: Vec<usize>,
+    pub sources: Vec<usize>,
+    pub weights: Vec<f32>,
+    /// the active recurrent connections as (src, dst, weight)
+    pub feedback: Vec<(usize, usize, f32)>,
+    pub bias: Vec<f32>,
+    pub activations: Vec<Activation>,
+    pub recurrent: Vec<bool>,
+    pub activation: Activation,
+    pub precision: Precision,
+    states: Vec<f32>,
+    values: Vec<f32>,
+    previous: Vec<f32>,
+    carried: Vec<f32>
+}
+
+
+
+impl SparseDense {
+
+    /// compile a dense layer, returns None if the layer's graph can't be 
+    /// ordered (the same networks Dense::forward gives up on)
+    pub fn compile(dense: &Dense) -> Option<Self> {
+        let size = dense.nodes.len();
+        // recurrent connections don't wait on their src, so they are left out of the ordering
+        let is_active = |link_id: usize| dense.edges.get(link_id).is_some_and(|edge| edge.active && !edge.recurrent);
+
+        // count the active incoming connections of each node, then walk the graph from the inputs
+        let mut pending = dense.nodes.iter()
+            .map(|node| node.incoming_edges().iter().filter(|link| is_active(link.id.index())).count())
+            .collect::<Vec<_>>();
+        let mut queue = dense.nodes.iter()
+            .filter(|node| node.neuron_type == NeuronType::Input)
+            .map(|node| node.id.index())
+            .collect::<VecDeque<_>>();
+        queue.extend(dense.nodes.iter()
+            .filter(|node| node.neuron_type != NeuronType::Input && pending[node.id.index()] == 0)
+            .map(|node| node.id.index()));
+
+        let mut order = Vec::with_capacity(size);
+        let mut visited = 0;
+        while let Some(index) = queue.pop_front() {
+            visited += 1;
+            if dense.nodes[index].neuron_type != NeuronType::Input {
+                order.push(index);
+            }
+            for edge_id in dense.nodes[index].outgoing_edges() {
+                let edge = dense.edges.get(edge_id.index())?;
+                if edge.active && !edge.recurrent {
+                    let dst = edge.dst.index();
+                    pending[dst] -= 1;
+                    if pending[dst] == 0 {
+                        queue.push_back(dst);
+                    }
+                }
+            }
+        }
+        if visited != size {
+            return None;
+        }
+
+        // lay the connections out in evaluation order
+        let mut offsets = Vec::with_capacity(order.len() + 1);
+        let mut sources = Vec::new();
+        let mut weights = Vec::new();
+        offsets.push(0);
+        for index in order.iter() {
+            for link in dense.nodes[*index].incoming_edges() {
+                if is_active(link.id.index()) {
+                    sources.push(link.src.index());
+                    weights.push(link.weight);
+                }
+            }
+            offsets.push(sources.len());
+        }
+
+        Some(SparseDense {
+            inputs: dense.inputs.iter().map(|id| id.index()).collect(),
+            outputs: dense.outputs.iter().map(|id| id.index()).collect(),
+            order,
+            offsets,
+            sources,
+            weights,
+            feedback: dense.edges.iter()
+                .filter(|edge| edge.active && edge.recurrent)
+                .map(|edge| (edge.src.index(), edge.dst.index(), edge.weight))
+                .collect(),
+            bias: dense.nodes.iter().map(|node| node.bias).collect(),
+            activations: dense.nodes.iter().map(|node| node.activation()).collect(),
+            recurrent: dense.nodes.iter().map(|node| node.direction() == NeuronDirection::Recurrent).collect(),
+            activation: dense.activation,
+            precision: dense.precision,
+            states: vec![0.0; size],
+            values: vec![0.0; size],
+            previous: dense.nodes.iter().map(|node| dense.state(node.id).previous_state).collect(),
+            carried: vec![0.0; size]
+        })
+    }
+
+
+    /// feed the inputs through the layer, giving the same outputs as the Dense layer it was compiled from
+    #[inline]
+    pub fn forward(&mut self, data: &[f32]) -> Result<Vec<f32>, NeatError> {
+        NeatError::check_input(self.inputs.len(), data.len())?;
+        if !self.feedback.is_empty() {
+            self.carried.iter_mut().for_each(|carried| *carried = 0.0);
+            for (src, dst, weight) in self.feedback.iter() {
+                self.carried[*dst] += self.values[*src] * weight;
+            }
+        }
+        for (index, value) in self.inputs.iter().zip(data.iter()) {
+            self.values[*index] = *value;
+        }
+        for (i, index) in self.order.iter().enumerate() {
+            let index = *index;
+            let (start, end) = (self.offsets[i], self.offsets[i + 1]);
+            let state = self.sources[start..end].iter()
+                .zip(self.weights[start..end].iter())
+                .fold(self.bias[index] + self.carried[index], |sum, (src, weight)| sum + self.values[*src] * weight);
+            self.states[index] = state;
+            self.values[index] = match self.activations[index] {
+                Activation::Softmax => 0.0,
+                activation => {
+                    let input = if self.recurrent[index] { state + self.previous[index] } else { state };
+                    self.previous[index] = state;
+                    activation.activate_with(input, self.precision)
+                }
+            };
+        }
+        if self.activation == Activation::Softmax {
+            let states = self.outputs.iter().map(|index| self.states[*index]).collect::<Vec<_>>();
+            return Ok(vectorops::softmax_with(&states, self.precision));
+        }
+        Ok(self.outputs.iter().map(|index| self.values[*index]).collect())
+    }
+
+
+    /// number of active connections in the compiled layer
+    pub fn connections(&self) -> usize {
+        self.sources.len() + self.feedback.len()
+    }
+
+
+    /// forget the values the recurrent connections carry into the next pass
+    pub fn reset(&mut self) {
+        self.values.iter_mut().for_each(|value| *value = 0.0);
+    }
+}
@@ -1,19 +1,240 @@
+//! Element-wise operations on the vectors passed between and within layers, which is where most
+//! of the time of a recurrent layer's forward and backward passes goes. The vectors are worked
+//! through LANES values at a time, with the last partial chunk padded out, so the compiler can keep
+//! each chunk in one vector register. With the simd feature the chunks are std::simd vectors
+//! (needs a nightly compiler), otherwise they are plain arrays which the compiler vectorizes itself.
+
+use std::ops::{Add, Sub, Mul, Div};
 
 use super::super::{
-    activation::Activation,
-    loss::Loss
+    activation::{Activation, Precision},
+    loss::Loss,
+    strict
 };
 
 
+/// how many values are worked on at once, 8 f32 fill a 256 bit (AVX) register
+const LANES: usize = 8;
+
+
+#[cfg(feature = "simd")]
+type Lanes = std::simd::Simd<f32, LANES>;
+
+
+#[cfg(not(feature = "simd"))]
+#[derive(Debug, Clone, Copy)]
+struct Lanes([f32; LANES]);
+
+
+
+/// the operations on a chunk both kinds of Lanes need for the functions below, along with
+/// splat (every lane the same value) which std::simd already has
+trait Chunk: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    /// load exactly LANES values
+    fn load_exact(values: &[f32]) -> Self;
+    /// store every lane into exactly LANES values
+    fn store_exact(self, values: &mut [f32]);
+    /// load up to LANES values, padding a short slice with zeros
+    fn load(values: &[f32]) -> Self;
+    /// store the first values.len() lanes
+    fn store(self, values: &mut [f32]);
+    fn clamp(self, low: f32, high: f32) -> Self;
+}
+
+
+
+#[cfg(feature = "simd")]
+impl Chunk for Lanes {
+    #[inline]
+    fn load_exact(values: &[f32]) -> Self {
+        Lanes::from_slice(values)
+    }
+
+    #[inline]
+    fn store_exact(self, values: &mut [f32]) {
+        self.copy_to_slice(values);
+    }
+
+    #[inline]
+    fn load(values: &[f32]) -> Self {
+        Lanes::load_or_default(values)
+    }
+
+    #[inline]
+    fn store(self, values: &mut [f32]) {
+        let len = values.len();
+        values.copy_from_slice(&self.as_array()[..len]);
+    }
+
+    #[inline]
+    fn clamp(self, low: f32, high: f32) -> Self {
+        use std::simd::num::SimdFloat;
+        self.simd_clamp(Lanes::splat(low), Lanes::splat(high))
+    }
+}
+
+
+
+#[cfg(not(feature = "simd"))]
+impl Chunk for Lanes {
+    #[inline]
+    fn load_exact(values: &[f32]) -> Self {
+        let mut lanes = [0.0; LANES];
+        lanes.copy_from_slice(&values[..LANES]);
+        Lanes(lanes)
+    }
+
+    #[inline]
+    fn store_exact(self, values: &mut [f32]) {
+        values[..LANES].copy_from_slice(&self.0);
+    }
+
+    #[inline]
+    fn load(values: &[f32]) -> Self {
+        let mut lanes = [0.0; LANES];
+        lanes[..values.len()].copy_from_slice(values);
+        Lanes(lanes)
+    }
+
+    #[inline]
+    fn store(self, values: &mut [f32]) {
+        let len = values.len();
+        values.copy_from_slice(&self.0[..len]);
+    }
+
+    #[inline]
+    fn clamp(self, low: f32, high: f32) -> Self {
+        self.map(|a| a.clamp(low, high))
+    }
+}
+
+
+
+#[cfg(not(feature = "simd"))]
+impl Lanes {
+    #[inline]
+    fn splat(value: f32) -> Self {
+        Lanes([value; LANES])
+    }
+
+    #[inline(always)]
+    fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        let mut lanes = self.0;
+        lanes.iter_mut().for_each(|a| *a = f(*a));
+        Lanes(lanes)
+    }
+
+    #[inline(always)]
+    fn zip(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        let mut lanes = self.0;
+        lanes.iter_mut().zip(other.0.iter()).for_each(|(a, b)| *a = f(*a, *b));
+        Lanes(lanes)
+    }
+}
+
+
+
+#[cfg(not(feature = "simd"))]
+macro_rules! lanes_op {
+    ($trait:ident, $fn:ident, $op:tt) => {
+        impl $trait for Lanes {
+            type Output = Lanes;
+
+            #[inline(always)]
+            fn $fn(self, other: Lanes) -> Lanes {
+                self.zip(other, |a, b| a $op b)
+            }
+        }
+    };
+}
+
+
+#[cfg(not(feature = "simd"))]
+lanes_op!(Add, add, +);
+#[cfg(not(feature = "simd"))]
+lanes_op!(Sub, sub, -);
+#[cfg(not(feature = "simd"))]
+lanes_op!(Mul, mul, *);
+#[cfg(not(feature = "simd"))]
+lanes_op!(Div, div, /);
+
+
+
+/// replace each value of one with f of it and the value at the same index of two, a chunk at a time
+#[inline(always)]
+fn zip_in_place(one: &mut [f32], two: &[f32], f: impl Fn(Lanes, Lanes) -> Lanes) {
+    let mut ones = one.chunks_exact_mut(LANES);
+    let mut twos = two.chunks_exact(LANES);
+    for (a, b) in (&mut ones).zip(&mut twos) {
+        f(Lanes::load_exact(a), Lanes::load_exact(b)).store_exact(a);
+    }
+    let tail = ones.into_remainder();
+    if !tail.is_empty() {
+        f(Lanes::load(tail), Lanes::load(twos.remainder())).store(tail);
+    }
+}
+
+
+
+/// f of each value of one, a chunk at a time
+#[inline(always)]
+fn map(one: &[f32], f: impl Fn(Lanes) -> Lanes) -> Vec<f32> {
+    let mut out = vec![0.0; one.len()];
+    let mut outs = out.chunks_exact_mut(LANES);
+    let mut ones = one.chunks_exact(LANES);
+    for (o, a) in (&mut outs).zip(&mut ones) {
+        f(Lanes::load_exact(a)).store_exact(o);
+    }
+    let tail = outs.into_remainder();
+    if !tail.is_empty() {
+        f(Lanes::load(ones.remainder())).store(tail);
+    }
+    out
+}
+
+
+
+/// replace each value of one with f of it, a chunk at a time
+#[inline(always)]
+fn map_in_place(one: &mut [f32], f: impl Fn(Lanes) -> Lanes) {
+    let mut ones = one.chunks_exact_mut(LANES);
+    for a in &mut ones {
+        f(Lanes::load_exact(a)).store_exact(a);
+    }
+    let tail = ones.into_remainder();
+    if !tail.is_empty() {
+        f(Lanes::load(tail)).store(tail);
+    }
+}
+
+
+
+/// the fast rational approximation of tanh from the activation module, a chunk at a time
+#[inline]
+fn fast_tanh(x: Lanes) -> Lanes {
+    let c = Lanes::splat;
+    let x2 = x * x;
+    let num = x * (c(135135.0) + x2 * (c(17325.0) + x2 * (c(378.0) + x2)));
+    let den = c(135135.0) + x2 * (c(62370.0) + x2 * (c(3150.0) + c(28.0) * x2));
+    (num / den).clamp(-1.0, 1.0)
+}
+
+
+
+/// the fast sigmoid from the activation module, a chunk at a time
+#[inline]
+fn fast_sigmoid(x: Lanes) -> Lanes {
+    Lanes::splat(0.5) + Lanes::splat(0.5) * fast_tanh(x * Lanes::splat(2.45))
+}
+
+
+
+
 /// multiply two vectors element-wise
 #[inline]
 pub fn element_multiply(one: &mut [f32], two: &[f32]) {
     assert!(one.len() == two.len(), "Element multiply vector shapes don't match");
-    one.iter_mut()
-        .zip(two.iter())
-        .for_each(|(a, b)| {
-            *a *= b
-        });
+    zip_in_place(one, two, |a, b| a * b);
 }
 
 
@@ -21,8 +242,7 @@ pub fn element_multiply(one: &mut [f32], two: &[f32]) {
 /// invert a vector that is already holding values between 0 and 1
 #[inline]
 pub fn element_invert(one: &mut [f32]) {
-    one.iter_mut()
-        .for_each(|a| *a = 1.0 - *a);
+    map_in_place(one, |a| Lanes::splat(1.0) - a);
 }
 
 
@@ -31,11 +251,7 @@ pub fn element_invert(one: &mut [f32]) {
 #[inline]
 pub fn element_add(one: &mut [f32], two: &[f32]) {
     assert!(one.len() == two.len(), "Element add vector shapes don't match");
-    one.iter_mut()
-        .zip(two.iter())
-        .for_each(|(a, b)| {
-            *a += b
-        });
+    zip_in_place(one, two, |a, b| a + b);
 }
 
 
@@ -59,31 +275,66 @@ pub fn element_deactivate(one: &[f32], func: Activation) -> Vec<f32> {
 }
 
 
+/// activate with the given precision, the fast sigmoid and tanh are worked out a chunk
+/// at a time while everything else is computed exactly the same as Activation::activate_with
+#[inline]
+pub fn element_activate_with(one: &[f32], func: Activation, precision: Precision) -> Vec<f32> {
+    match (precision, func) {
+        (Precision::Fast, Activation::Sigmoid) => map(one, fast_sigmoid),
+        (Precision::Fast, Activation::Tanh) => map(one, fast_tanh),
+        _ => one.iter().map(|x| func.activate_with(*x, precision)).collect()
+    }
+}
+
+
+/// deactivate with the given precision, see element_activate_with
+#[inline]
+pub fn element_deactivate_with(one: &[f32], func: Activation, precision: Precision) -> Vec<f32> {
+    match (precision, func) {
+        (Precision::Fast, Activation::Sigmoid) => map(one, |x| {
+            let act = fast_sigmoid(x);
+            act * (Lanes::splat(1.0) - act)
+        }),
+        (Precision::Fast, Activation::Tanh) => map(one, |x| {
+            let act = fast_tanh(x);
+            Lanes::splat(1.0) - act * act
+        }),
+        _ => one.iter().map(|x| func.deactivate_with(*x, precision)).collect()
+    }
+}
+
+
 #[inline]
 pub fn product(one: &[f32], two: &[f32]) -> Vec<f32> {
     assert!(one.len() == two.len(), "Product dimensions do not match");
-    one.iter()
-        .zip(two.iter())
-        .map(|(o, t)| o * t)
-        .collect::<Vec<_>>()
+    let mut out = one.to_vec();
+    zip_in_place(&mut out, two, |a, b| a * b);
+    out
 }
 
 
 #[inline]
 pub fn subtract(one: &[f32], two: &[f32]) -> Vec<f32> {
     assert!(one.len() == two.len(), "Subtract lengths do not match");
-    one.iter()
-        .zip(two.iter())
-        .map(|(tar, pre)| (tar - pre))
-        .collect::<Vec<_>>()
+    let mut out = one.to_vec();
+    zip_in_place(&mut out, two, |a, b| a - b);
+    out
 }
 
 
 #[inline]
 pub fn softmax(one: &[f32]) -> Vec<f32> {
+    softmax_with(one, Precision::Exact)
+}
+
+
+/// softmax with the given precision, only Strict changes how it's computed (see strict::exp)
+#[inline]
+pub fn softmax_with(one: &[f32], precision: Precision) -> Vec<f32> {
+    let exp = if precision == Precision::Strict { strict::exp } else { f32::exp };
     let ex = one   
         .iter()
-        .map(|x| x.exp())
+        .map(|x| exp(*x))
         .collect::<Vec<_>>();
     let sum = ex.iter().sum::<f32>();
     ex.iter()
@@ -92,36 +343,18 @@ pub fn softmax(one: &[f32]) -> Vec<f32> {
 }
 
 
+/// the diagonal of the softmax jacobian given the softmax output
 #[inline]
 pub fn d_softmax(one: &[f32]) -> Vec<f32> {
     one.iter()
-        .map(|x| x - 1.0)
+        .map(|x| x * (1.0 - x))
         .collect()
 }
 
 
 
+/// the loss of the targets (one) against the outputs (two) and the error of each output, see Loss
 #[inline]
 pub fn loss(one: &[f32], two: &[f32], loss_fn: &Loss) -> (f32, Vec<f32>) {
-    assert!(one.len() == two.len(), "Loss vector shape don't match");
-    match loss_fn {
-        Loss::Diff => {
-            let difference = subtract(one, two);
-            let total = difference.iter().sum::<f32>();
-            return (total, difference);
-        },
-        Loss::MSE => {
-            let mut squared_error = 0.0;
-            let errs = one.iter()
-                .zip(two.iter())
-                .map(|(i, j)| {
-                    let e = (i - j).powf(2.0);
-                    squared_error += e;
-                    e
-                })
-                .collect::<Vec<_>>();
-            return ((1.0 / one.len() as f32) * squared_error, errs);
-
-        }
-    }
+    loss_fn.loss(one, two)
 }
\ No newline at end of file
@@ -0,0 +1,172 @@
+extern crate serde_json;
+
+use std::fmt;
+use std::fs::{self, File};
+use std::error::Error;
+
+use serde_json::Value;
+
+use super::neat::Neat;
+
+
+
+/// The version of the serialized model format written by this version of the crate.
+/// Bump this and add a migration whenever a change to the model's structure would
+/// stop older saves from deserializing (or would deserialize them incorrectly).
+pub const MODEL_VERSION: u32 = 1;
+
+
+/// The key the version is stored under in a saved model, saves made before
+/// versioning was added don't have it and are treated as version 0
+pub const VERSION_KEY: &str = "format_version";
+
+
+
+/// A single step upgrading a serialized model from one version to the next
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut Value) -> Result<(), MigrationError>
+}
+
+
+
+/// Errors raised while upgrading a saved model
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationError {
+    /// the model was saved by a newer version of the crate than this one
+    NewerVersion(u32),
+    /// there is no migration registered to get from this version to the next
+    MissingMigration(u32),
+    /// the saved model isn't shaped the way its version says it should be
+    Malformed(String)
+}
+
+
+
+/// Every registered migration, in order. Each one takes a model from `from` to `from + 1`.
+static MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "unversioned saves, training options added after the first release are filled with their defaults",
+        apply: unversioned
+    }
+];
+
+
+
+pub fn migrations() -> &'static [Migration] {
+    MIGRATIONS
+}
+
+
+
+/// the version a serialized model was saved with
+pub fn version_of(model: &Value) -> u32 {
+    model.get(VERSION_KEY)
+        .and_then(|version| version.as_u64())
+        .unwrap_or(0) as u32
+}
+
+
+
+/// mark a serialized model with the current version
+pub fn stamp(model: &mut Value) {
+    if let Some(table) = model.as_object_mut() {
+        table.insert(VERSION_KEY.to_string(), Value::from(MODEL_VERSION));
+    }
+}
+
+
+
+/// upgrade a serialized model from whatever version it was saved with to the current one
+pub fn migrate(mut model: Value) -> Result<Value, MigrationError> {
+    let mut version = version_of(&model);
+    if version > MODEL_VERSION {
+        return Err(MigrationError::NewerVersion(version));
+    }
+    while version < MODEL_VERSION {
+        let migration = MIGRATIONS.iter()
+            .find(|migration| migration.from == version)
+            .ok_or(MigrationError::MissingMigration(version))?;
+        (migration.apply)(&mut model)?;
+        version += 1;
+    }
+    stamp(&mut model);
+    Ok(model)
+}
+
+
+
+/// rewrite a saved model file in the current format, returning the version it was saved with.
+/// Files holding a model under a `model` key (sessions, goldens) are upgraded in place as well.
+pub fn upgrade_file(file_path: &str) -> Result<u32, Box<dyn Error>> {
+    let mut saved: Value = serde_json::from_reader(File::open(file_path)?)?;
+    let version = match saved.get_mut("model") {
+        Some(model) => {
+            let version = version_of(model);
+            *model = migrate(model.take())?;
+            version
+        },
+        None => {
+            let version = version_of(&saved);
+            saved = migrate(saved)?;
+            version
+        }
+    };
+    fs::write(file_path, serde_json::to_string_pretty(&saved)?)?;
+    Ok(version)
+}
+
+
+
+/// Serde helpers for a model held inside another saved struct so the nested model is
+/// versioned and migrated the same as a model saved on its own, 
+/// ie: #[serde(with = "migrate::versioned")]
+pub mod versioned {
+
+    use serde::{Serialize, Deserialize, Serializer, Deserializer};
+    use serde::ser::Error as SerError;
+    use serde::de::Error as DeError;
+    use serde_json::Value;
+
+    use super::Neat;
+
+    pub fn serialize<S: Serializer>(model: &Neat, serializer: S) -> Result<S::Ok, S::Error> {
+        model.to_versioned()
+            .map_err(|e| S::Error::custom(e.to_string()))?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Neat, D::Error> {
+        Neat::from_versioned(Value::deserialize(deserializer)?)
+            .map_err(|e| D::Error::custom(e.to_string()))
+    }
+}
+
+
+
+fn unversioned(model: &mut Value) -> Result<(), MigrationError> {
+    let table = model.as_object_mut().ok_or_else(|| MigrationError::Malformed(String::from("expected the model to be an object")))?;
+    if !table.contains_key("layers") {
+        return Err(MigrationError::Malformed(String::from("missing layers")));
+    }
+    table.entry("shuffle").or_insert_with(|| Value::from("None"));
+    Ok(())
+}
+
+
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::NewerVersion(version) => write!(f, "model was saved with format version {} but only {} and older can be read", version, MODEL_VERSION),
+            MigrationError::MissingMigration(version) => write!(f, "no migration from model format version {}", version),
+            MigrationError::Malformed(reason) => write!(f, "malformed model: {}", reason)
+        }
+    }
+}
+
+
+
+impl Error for MigrationError {}
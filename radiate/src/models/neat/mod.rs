@@ -5,6 +5,32 @@ pub mod neuron;
 pub mod layers;
 pub mod tracer;
 pub mod id;
+pub mod benchmark;
+pub mod session;
+pub mod golden;
+pub mod migrate;
+pub mod profile;
+pub mod compiled;
+pub mod preset;
+pub mod topology;
+pub mod bias;
+pub mod perturb;
+pub mod innovation;
+pub mod optimizer;
+pub mod weights;
+pub mod prune;
+pub mod mutation;
+pub mod analysis;
+pub mod landscape;
+pub mod strict;
+pub mod card;
+pub mod dot;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+pub mod hyperneat;
+pub mod adapt;
+pub mod wiring;
+pub mod graph;
 
 
 
@@ -22,9 +48,91 @@ pub mod direction {
 
 pub mod loss {
 
+    use super::activation::Activation;
+
+    /// How the error of a network's output is measured during backprop. Each loss gives
+    /// the value of the loss for one sample along with the error of each output to backpropagate,
+    /// which is the negative gradient of the loss so the layers add it to their weights.
+    ///
+    /// Diff - target - output, kept as it is what networks were always trained on but the
+    ///        value it reports is the signed sum of the differences, which can cancel out
+    /// MSE - mean squared error
+    /// MAE - mean absolute error, the gradient is the same size however close the output is
+    /// CrossEntropy - binary cross-entropy of each output against a target in [0, 1], through a
+    ///        sigmoid the error is target - output. Outputs from a softmax layer (see Loss::loss_for)
+    ///        take the categorical cross-entropy instead, its targets are one-hot (or otherwise sum
+    ///        to 1) and once backprop takes the error through the softmax it is also target - output
+    /// Huber(delta) - squared error for differences under delta and absolute error past it
+    ///        so a few outliers don't swamp the gradient
+    #[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
     pub enum Loss {
         MSE,
-        Diff
+        Diff,
+        MAE,
+        CrossEntropy,
+        Huber(f32)
+    }
+
+
+    /// outputs are kept at least this far from 0 and 1 so cross-entropy doesn't take the log of zero
+    const EPSILON: f32 = 1e-7;
+
+
+    impl Loss {
+
+        /// the loss of the outputs against the targets and the error of each output
+        #[inline]
+        pub fn loss(&self, targets: &[f32], outputs: &[f32]) -> (f32, Vec<f32>) {
+            assert!(targets.len() == outputs.len(), "Loss vector shape don't match");
+            let n = targets.len() as f32;
+            let pairs = targets.iter().zip(outputs.iter());
+            match self {
+                Loss::Diff => {
+                    let errors = pairs.map(|(t, o)| t - o).collect::<Vec<_>>();
+                    (errors.iter().sum(), errors)
+                },
+                Loss::MSE => {
+                    let loss = pairs.clone().map(|(t, o)| (t - o).powi(2)).sum::<f32>() / n;
+                    (loss, pairs.map(|(t, o)| 2.0 * (t - o) / n).collect())
+                },
+                Loss::MAE => {
+                    let loss = pairs.clone().map(|(t, o)| (t - o).abs()).sum::<f32>() / n;
+                    (loss, pairs.map(|(t, o)| if t == o { 0.0 } else { (t - o).signum() / n }).collect())
+                },
+                Loss::CrossEntropy => {
+                    let clamp = |o: &f32| o.clamp(EPSILON, 1.0 - EPSILON);
+                    let loss = -pairs.clone()
+                        .map(|(t, o)| t * clamp(o).ln() + (1.0 - t) * (1.0 - clamp(o)).ln())
+                        .sum::<f32>();
+                    (loss, pairs.map(|(t, o)| t / clamp(o) - (1.0 - t) / (1.0 - clamp(o))).collect())
+                },
+                Loss::Huber(delta) => {
+                    let loss = pairs.clone()
+                        .map(|(t, o)| {
+                            let e = (t - o).abs();
+                            if e <= *delta { 0.5 * e * e } else { delta * (e - 0.5 * delta) }
+                        })
+                        .sum::<f32>() / n;
+                    (loss, pairs.map(|(t, o)| (t - o).clamp(-*delta, *delta) / n).collect())
+                }
+            }
+        }
+
+
+        /// the loss of outputs given by a layer with the activation, which only matters for cross-entropy
+        /// as a softmax layer's outputs are one distribution and take the categorical cross-entropy
+        #[inline]
+        pub fn loss_for(&self, targets: &[f32], outputs: &[f32], activation: Activation) -> (f32, Vec<f32>) {
+            match (self, activation) {
+                (Loss::CrossEntropy, Activation::Softmax) => {
+                    assert!(targets.len() == outputs.len(), "Loss vector shape don't match");
+                    let pairs = targets.iter().zip(outputs.iter());
+                    let loss = -pairs.clone().map(|(t, o)| t * o.max(EPSILON).ln()).sum::<f32>();
+                    (loss, pairs.map(|(t, o)| t / o.max(EPSILON)).collect())
+                },
+                _ => self.loss(targets, outputs)
+            }
+        }
     }
 }
 
@@ -33,8 +141,12 @@ pub mod loss {
 pub mod activation {
 
     use std::f32::consts::E as Eul;
+    use super::strict;
 
-    /// Various activation functions for a neuron, must be specified at creation
+    /// Various activation functions for a neuron, must be specified at creation. Sine, Gaussian
+    /// (e^-x^2) and Abs give the repetition and symmetry CPPNs build patterns out of, see hyperneat.
+    /// LeakyRelu takes the slope below zero and ExpRelu is ELU with the given alpha. Swish is
+    /// x * 1 / (1 + e^-x), smooth like sigmoid but unbounded above like relu
     #[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
     pub enum Activation {
         Sigmoid,
@@ -46,7 +158,33 @@ pub mod activation {
         Softmax,
         LeakyRelu(f32),
         ExpRelu(f32),
-        Linear(f32)   
+        Linear(f32),
+        Sine,
+        Gaussian,
+        Abs,
+        Swish
+    }
+
+
+    /// How exactly activation functions are computed
+    ///
+    /// Exact - the standard library's transcendental functions
+    /// Fast - rational approximations of sigmoid and tanh, which are where most of the time
+    ///        goes when evaluating large populations. The max absolute error is 5e-5 for sigmoid
+    ///        and its derivative, 1e-4 for tanh, and 2e-4 for tanh's derivative, which is usually
+    ///        far below anything evolution can notice.
+    ///        The other activation functions are computed exactly either way
+    /// Strict - exp, tanh, sin and cos computed in plain arithmetic (see the strict module) instead
+    ///          of by the platform's math library, so the same seed gives the same bits on every
+    ///          platform, ie: to check results evaluated on other machines. Layers also stay off any
+    ///          path (ie: the ndarray matrix forward pass) which could sum in a different order.
+    ///          Within an ulp of Exact, and a little slower
+    #[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy, Default)]
+    pub enum Precision {
+        #[default]
+        Exact,
+        Fast,
+        Strict
     }
 
 
@@ -86,6 +224,18 @@ pub mod activation {
                     }
                     alpha * (Eul.powf(x) - 1.0)
                 },
+                Self::Sine => {
+                    x.sin()
+                },
+                Self::Gaussian => {
+                    (-x * x).exp()
+                },
+                Self::Abs => {
+                    x.abs()
+                },
+                Self::Swish => {
+                    x / (1.0 + (-x).exp())
+                },
                 _ => panic!("Cannot activate single neuron")
 
             }
@@ -93,6 +243,56 @@ pub mod activation {
 
 
 
+        /// activate with the given precision, see Precision
+        #[inline]
+        pub fn activate_with(&self, x: f32, precision: Precision) -> f32 {
+            match (precision, self) {
+                (Precision::Fast, Self::Sigmoid) => fast_sigmoid(x),
+                (Precision::Fast, Self::Tanh) | (Precision::Fast, Self::Tahn) => fast_tanh(x),
+                (Precision::Strict, Self::Sigmoid) => strict::sigmoid(x, 4.9),
+                (Precision::Strict, Self::Tanh) | (Precision::Strict, Self::Tahn) => strict::tanh(x),
+                (Precision::Strict, Self::ExpRelu(alpha)) => if x >= 0.0 { x } else { alpha * strict::exp_m1(x) },
+                (Precision::Strict, Self::Sine) => strict::sin(x),
+                (Precision::Strict, Self::Gaussian) => strict::exp(-x * x),
+                (Precision::Strict, Self::Swish) => x * strict::sigmoid(x, 1.0),
+                _ => self.activate(x)
+            }
+        }
+
+
+
+        /// deactivate with the given precision, see Precision
+        #[inline]
+        pub fn deactivate_with(&self, x: f32, precision: Precision) -> f32 {
+            match (precision, self) {
+                (Precision::Fast, Self::Sigmoid) => {
+                    let act = fast_sigmoid(x);
+                    act * (1.0 - act)
+                },
+                (Precision::Fast, Self::Tanh) | (Precision::Fast, Self::Tahn) => {
+                    1.0 - fast_tanh(x).powf(2.0)
+                },
+                (Precision::Strict, Self::Sigmoid) => {
+                    let act = strict::sigmoid(x, 4.9);
+                    act * (1.0 - act)
+                },
+                (Precision::Strict, Self::Tanh) | (Precision::Strict, Self::Tahn) => {
+                    let act = strict::tanh(x);
+                    1.0 - act * act
+                },
+                (Precision::Strict, Self::ExpRelu(alpha)) => if x > 0.0 { 1.0 } else { alpha * strict::exp(x) },
+                (Precision::Strict, Self::Sine) => strict::cos(x),
+                (Precision::Strict, Self::Gaussian) => -2.0 * x * strict::exp(-x * x),
+                (Precision::Strict, Self::Swish) => {
+                    let act = strict::sigmoid(x, 1.0);
+                    act + x * act * (1.0 - act)
+                },
+                _ => self.deactivate(x)
+            }
+        }
+
+
+
         /// Deactivation functions for the activation neurons 
         #[inline]
         pub fn deactivate(&self, x: f32) -> f32 {
@@ -124,10 +324,46 @@ pub mod activation {
                     } 
                     *alpha 
                 },
+                Self::Sine => {
+                    x.cos()
+                },
+                Self::Gaussian => {
+                    -2.0 * x * (-x * x).exp()
+                },
+                Self::Abs => {
+                    if x == 0.0 {
+                        return 0.0;
+                    }
+                    x.signum()
+                },
+                Self::Swish => {
+                    let act = 1.0 / (1.0 + (-x).exp());
+                    act + x * act * (1.0 - act)
+                },
                 _ => panic!("Cannot deactivate single neuron")
             }
         }
     }
+
+
+
+    /// (7, 6) rational approximation of tanh from its continued fraction, clamped
+    /// to +/-1 where the approximation crosses it (|x| ~ 4.97)
+    #[inline]
+    fn fast_tanh(x: f32) -> f32 {
+        let x2 = x * x;
+        let num = x * (135135.0 + x2 * (17325.0 + x2 * (378.0 + x2)));
+        let den = 135135.0 + x2 * (62370.0 + x2 * (3150.0 + 28.0 * x2));
+        (num / den).clamp(-1.0, 1.0)
+    }
+
+
+
+    /// sigmoid(4.9x) written in terms of tanh so it can share the approximation
+    #[inline]
+    fn fast_sigmoid(x: f32) -> f32 {
+        0.5 + 0.5 * fast_tanh(x * 2.45)
+    }
 }
 
 
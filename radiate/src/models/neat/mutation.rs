@@ -0,0 +1,233 @@
+use std::fmt;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{
+    neat::{Neat, LayerWrap},
+    neatenv::NeatEnvironment,
+    id::NeuronId,
+    activation::Activation,
+    direction::NeuronDirection,
+    layers::{
+        dense::Dense,
+        layertype::LayerType
+    }
+};
+
+
+
+/// A mutation to preview on a copy of a network, see Neat::preview_mutation
+///
+/// Environment - everything crossover does to a network when it isn't crossed over with its
+///        partner, at the rates of the environment, which is how the population mutates members
+/// EditWeights - perturb or replace the weights and biases of the dense layer at layer, see
+///        NeatEnvironment::edit_weights and weight_perturb for what editable and perturb are
+/// AddNode - split a random edge of the dense pool layer at layer with a new neuron
+/// AddEdge - try to connect two random neurons of the dense pool layer at layer, which
+///        can end up changing nothing if the connection isn't valid
+/// AddRecurrentEdge - like AddEdge, but a connection closing a cycle is made recurrent, see Dense::add_recurrent_edge
+/// RemoveNode - remove a random hidden neuron and its edges from the dense pool layer at layer
+/// RemoveEdge - remove a random active edge from the dense pool layer at layer, see Dense::remove_edge
+/// MutateActivation - give a random hidden neuron of the dense pool layer at layer another of the
+///        activations, see Dense::mutate_activation
+#[derive(Debug, Clone)]
+pub enum Mutation {
+    Environment(Box<NeatEnvironment>),
+    EditWeights { layer: usize, editable: f32, perturb: f32 },
+    AddNode { layer: usize, activation: Activation, direction: NeuronDirection },
+    AddEdge { layer: usize },
+    AddRecurrentEdge { layer: usize },
+    RemoveNode { layer: usize },
+    RemoveEdge { layer: usize },
+    MutateActivation { layer: usize, activations: Vec<Activation> }
+}
+
+
+
+/// One thing a mutation changed in a network. Edges are named by their innovation number, so
+/// the same change can be found in the network the preview is committed to. Removing neurons
+/// renumbers the rest, a removed neuron is named by its id before and every other by its id after.
+/// Layers other than dense layers only report the weights which changed, by whatever their
+/// visit_weights_mut gives
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Change {
+    NodeAdded { layer: usize, neuron: NeuronId, activation: Activation, direction: NeuronDirection },
+    NodeRemoved { layer: usize, neuron: NeuronId },
+    ActivationChanged { layer: usize, neuron: NeuronId, before: Activation, after: Activation },
+    EdgeAdded { layer: usize, innov: Uuid, src: NeuronId, dst: NeuronId, weight: f32, recurrent: bool },
+    EdgeEnabled { layer: usize, innov: Uuid },
+    EdgeDisabled { layer: usize, innov: Uuid },
+    EdgeRemoved { layer: usize, innov: Uuid },
+    WeightChanged { layer: usize, innov: Uuid, before: f32, after: f32 },
+    BiasChanged { layer: usize, neuron: NeuronId, before: f32, after: f32 }
+}
+
+
+
+/// What a mutation would do to a network without it being done, the mutated copy along with
+/// every change between it and the original. Nothing is committed until the copy is taken
+pub struct MutationPreview {
+    pub child: Neat,
+    pub changes: Vec<Change>
+}
+
+
+
+impl Change {
+
+    /// the index of the layer the change is in
+    pub fn layer(&self) -> usize {
+        match self {
+            Change::NodeAdded { layer, .. } 
+                | Change::NodeRemoved { layer, .. } 
+                | Change::ActivationChanged { layer, .. } 
+                | Change::EdgeAdded { layer, .. } 
+                | Change::EdgeEnabled { layer, .. } 
+                | Change::EdgeDisabled { layer, .. } 
+                | Change::EdgeRemoved { layer, .. } 
+                | Change::WeightChanged { layer, .. } 
+                | Change::BiasChanged { layer, .. } => *layer
+        }
+    }
+
+
+    /// whether the change is to the shape of the network rather than the values in it
+    pub fn is_structural(&self) -> bool {
+        !matches!(self, Change::WeightChanged { .. } | Change::BiasChanged { .. })
+    }
+}
+
+
+
+impl MutationPreview {
+
+    /// find every change between the network before and after being mutated
+    pub fn new(before: &Neat, after: Neat) -> Self {
+        let mut changes = Vec::new();
+        for (index, (one, two)) in before.layers.iter().zip(after.layers.iter()).enumerate() {
+            match one.layer_type {
+                LayerType::Dense | LayerType::DensePool => dense_changes(index, one.as_ref(), two.as_ref(), &mut changes),
+                _ => weight_changes(index, one, two, &mut changes)
+            }
+        }
+        MutationPreview { child: after, changes }
+    }
+
+
+    /// true if the mutation didn't change anything
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+
+    /// whether any of the changes are to the shape of the network, see Change::is_structural
+    pub fn is_structural(&self) -> bool {
+        self.changes.iter().any(Change::is_structural)
+    }
+
+
+    /// take the mutated network, committing the mutation
+    pub fn commit(self) -> Neat {
+        self.child
+    }
+}
+
+
+
+/// the nodes, activations, biases, and edges which changed in a dense layer. Removing neurons renumbers the
+/// rest, so each neuron is matched to what it became by the inputs, outputs, and edges it is in
+fn dense_changes(layer: usize, before: &Dense, after: &Dense, changes: &mut Vec<Change>) {
+    let mut matched = vec![None; before.nodes.len()];
+    for (one, two) in before.inputs.iter().zip(after.inputs.iter()).chain(before.outputs.iter().zip(after.outputs.iter())) {
+        matched[one.index()] = Some(*two);
+    }
+    for edge in after.edges.iter() {
+        if let Some(old) = before.get_edge_by_innov(&edge.innov) {
+            matched[old.src.index()] = Some(edge.src);
+            matched[old.dst.index()] = Some(edge.dst);
+        }
+    }
+    for (node, matched) in before.nodes.iter().zip(matched.iter()) {
+        match matched.and_then(|id| after.nodes.get(id.index())) {
+            None => changes.push(Change::NodeRemoved { layer, neuron: node.id }),
+            Some(two) => {
+                if two.activation() != node.activation() {
+                    changes.push(Change::ActivationChanged { layer, neuron: two.id, before: node.activation(), after: two.activation() });
+                }
+                if two.bias != node.bias {
+                    changes.push(Change::BiasChanged { layer, neuron: two.id, before: node.bias, after: two.bias });
+                }
+            }
+        }
+    }
+    for node in after.nodes.iter().filter(|node| !matched.contains(&Some(node.id))) {
+        changes.push(Change::NodeAdded { layer, neuron: node.id, activation: node.activation(), direction: node.direction() });
+    }
+    for edge in before.edges.iter().filter(|edge| !after.contains_edge(&edge.innov)) {
+        changes.push(Change::EdgeRemoved { layer, innov: edge.innov });
+    }
+    for edge in after.edges.iter() {
+        match before.get_edge_by_innov(&edge.innov) {
+            None => changes.push(Change::EdgeAdded { layer, innov: edge.innov, src: edge.src, dst: edge.dst, weight: edge.weight, recurrent: edge.recurrent }),
+            Some(old) => {
+                if old.weight != edge.weight {
+                    changes.push(Change::WeightChanged { layer, innov: edge.innov, before: old.weight, after: edge.weight });
+                }
+                match (old.active, edge.active) {
+                    (false, true) => changes.push(Change::EdgeEnabled { layer, innov: edge.innov }),
+                    (true, false) => changes.push(Change::EdgeDisabled { layer, innov: edge.innov }),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+
+
+/// the weights which changed in any other layer, read through copies of the layers
+/// because visiting the weights takes them mutably
+fn weight_changes(layer: usize, before: &LayerWrap, after: &LayerWrap, changes: &mut Vec<Change>) {
+    let mut weights = HashMap::new();
+    before.layer.clone().visit_weights_mut(&mut |innov, weight| { weights.insert(innov, *weight); });
+    after.layer.clone().visit_weights_mut(&mut |innov, weight| {
+        if let Some(old) = weights.get(&innov) {
+            if old != weight {
+                changes.push(Change::WeightChanged { layer, innov, before: *old, after: *weight });
+            }
+        }
+    });
+}
+
+
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::NodeAdded { layer, neuron, activation, direction } => write!(f, "layer {}: added {:?} {:?} neuron {}", layer, direction, activation, neuron.index()),
+            Change::NodeRemoved { layer, neuron } => write!(f, "layer {}: removed neuron {}", layer, neuron.index()),
+            Change::ActivationChanged { layer, neuron, before, after } => write!(f, "layer {}: neuron {} activation {:?} -> {:?}", layer, neuron.index(), before, after),
+            Change::EdgeAdded { layer, innov, src, dst, weight, recurrent } => {
+                let kind = if *recurrent { "recurrent edge" } else { "edge" };
+                write!(f, "layer {}: added {} {} from {} to {} weighing {:.4}", layer, kind, innov, src.index(), dst.index(), weight)
+            },
+            Change::EdgeEnabled { layer, innov } => write!(f, "layer {}: enabled edge {}", layer, innov),
+            Change::EdgeDisabled { layer, innov } => write!(f, "layer {}: disabled edge {}", layer, innov),
+            Change::EdgeRemoved { layer, innov } => write!(f, "layer {}: removed edge {}", layer, innov),
+            Change::WeightChanged { layer, innov, before, after } => write!(f, "layer {}: edge {} weight {:.4} -> {:.4}", layer, innov, before, after),
+            Change::BiasChanged { layer, neuron, before, after } => write!(f, "layer {}: neuron {} bias {:.4} -> {:.4}", layer, neuron.index(), before, after)
+        }
+    }
+}
+
+
+
+impl fmt::Display for MutationPreview {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} changes", self.changes.len())?;
+        for change in self.changes.iter() {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
@@ -4,22 +4,55 @@ extern crate serde_json;
 use std::fs::File;
 use std::error::Error;
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use uuid::Uuid;
 
 use super::{
     neatenv::NeatEnvironment,
-    activation::Activation,
+    topology::Topology,
+    migrate,
+    card::ModelCard,
+    profile::{Profiler, ProfileReport},
+    weights::{WeightReport, NetworkSnapshot},
+    prune::{DeadNeuron, DeadNeuronReport, PruneReport},
+    graph::GraphReport,
+    dot::{self, DotOptions},
+    mutation::{Mutation, MutationPreview},
+    landscape::{self, LandscapeProbe, LandscapeReport},
+    neurontype::NeuronType,
+    compiled::CompiledNeat,
+    activation::{Activation, Precision},
+    optimizer::Optimizer,
+    bias::Bias,
+    perturb::WeightEdit,
+    adapt::MutationRates,
+    wiring::Placement,
     loss::Loss,
     layers::{
         layer::Layer,
         dense::Dense,
         lstm::LSTM,
         gru::GRU,
+        conv1d::Conv1D,
+        conv2d::Conv2D,
+        pool::MaxPool2D,
+        dropout::Dropout,
+        norm::{Norm, NormType},
         layertype::LayerType,
         vectorops
     }
 };
 
 use crate::engine::genome::Genome;
+use crate::engine::generation::Alignment;
+use crate::engine::problem::Problem;
+use crate::engine::approximate::ApproximateScoring;
+use crate::error::NeatError;
+use crate::rng;
+use crate::data::{
+    batch::Shuffle,
+    augment::Augment
+};
 
 
 
@@ -50,7 +83,21 @@ impl LayerWrap {
 pub struct Neat {
     pub layers: Vec<LayerWrap>,
     pub input_size: u32,
-    pub batch_size: usize
+    pub batch_size: usize,
+    #[serde(default)]
+    pub shuffle: Shuffle,
+    #[serde(skip)]
+    pub augment: Option<Arc<dyn Augment>>,
+    #[serde(skip)]
+    pub profiler: Option<Profiler>,
+    #[serde(default)]
+    pub precision: Precision,
+    #[serde(default)]
+    pub optimizer: Optimizer,
+    #[serde(default)]
+    pub bias: Bias,
+    #[serde(default)]
+    pub mutation_rates: Option<MutationRates>
 }
 
 
@@ -62,7 +109,14 @@ impl Neat {
         Neat { 
             layers: Vec::new(),
             input_size: 0,
-            batch_size: 1
+            batch_size: 1,
+            shuffle: Shuffle::None,
+            augment: None,
+            profiler: None,
+            precision: Precision::Exact,
+            optimizer: Optimizer::Sgd,
+            bias: Bias::Neuron,
+            mutation_rates: None
         }
     }
 
@@ -84,6 +138,435 @@ impl Neat {
 
 
 
+    /// set the order the training data is fed through the network each epoch
+    pub fn shuffle(mut self, shuffle: Shuffle) -> Self {
+        self.shuffle = shuffle;
+        self
+    }
+
+
+
+    /// set an augmentation to apply to each input sample during training (train and fit), forward
+    /// passes always see the data as it is. The augmentation isn't saved with the network. To score
+    /// networks on augmented inputs during evolution set it on the problem, see Supervised::robust
+    pub fn augment<A: Augment + 'static>(mut self, augment: A) -> Self {
+        self.augment = Some(Arc::new(augment));
+        self
+    }
+
+
+
+    /// set how exactly the activation functions of every layer (including ones added after this)
+    /// are computed during the forward pass, see Precision for the error of the fast approximations
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.set_precision(precision);
+        self
+    }
+
+
+
+    fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+        for wrap in self.layers.iter_mut() {
+            wrap.layer.set_precision(precision);
+        }
+    }
+
+
+
+    /// Score networks with every layer computing its activations at the given precision, usually
+    /// the Fast approximations (everything is still f32), and those near a selection cutoff again
+    /// at their own, see Population::approximate_scoring
+    pub fn approximate_scoring(precision: Precision, band: f32) -> ApproximateScoring<Neat> {
+        ApproximateScoring::new(band, move |model: &mut Neat| {
+            let own = model.precision;
+            model.set_precision(precision);
+            Box::new(move |model: &mut Neat| model.set_precision(own))
+        })
+    }
+
+
+
+    /// set how every layer (including ones added after this) turns the gradients found by
+    /// backpropagation into weight changes, see Optimizer. Each layer keeps its own optimizer state
+    pub fn optimizer(mut self, optimizer: Optimizer) -> Self {
+        self.optimizer = optimizer;
+        for wrap in self.layers.iter_mut() {
+            wrap.layer.set_optimizer(optimizer);
+        }
+        self
+    }
+
+
+
+    /// set where the biases of the neurons in every dense layer (including ones added after this)
+    /// come from, see Bias. Turning them off zeroes the biases the layers already have
+    pub fn bias(mut self, bias: Bias) -> Self {
+        self.bias = bias;
+        for wrap in self.layers.iter_mut() {
+            wrap.layer.set_bias(bias);
+        }
+        self
+    }
+
+
+
+    /// give visit the index of the layer, innovation number and weight of every connection in
+    /// the network so external optimizers or pruning can read and change the weights. Only weights
+    /// visit changes are written back, and each layer keeps its neurons and any traced state
+    /// consistent with the new weights. A network compiled before this has to be compiled again
+    pub fn visit_weights_mut<F>(&mut self, mut visit: F)
+        where F: FnMut(usize, Uuid, &mut f32)
+    {
+        for (index, wrap) in self.layers.iter_mut().enumerate() {
+            wrap.layer.visit_weights_mut(&mut |innov, weight| visit(index, innov, weight));
+        }
+    }
+
+
+
+    /// Take the weights, biases and optimizer state of every layer so restore_weights can undo
+    /// whatever training does to them, ie: to score a network by what it can learn while keeping
+    /// the weights it was born with (see Inheritance::Baldwinian). None if a layer has weights
+    /// which can't be snapshot, currently every layer with weights other than dense and lstm
+    pub fn snapshot_weights(&self) -> Option<NetworkSnapshot> {
+        self.layers.iter()
+            .map(|wrap| match wrap.layer.snapshot() {
+                Some(snapshot) => Some(Some(snapshot)),
+                None if wrap.layer.weight_stats().count == 0 => Some(None),
+                None => None
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|layers| NetworkSnapshot { layers })
+    }
+
+
+
+    /// put back the weights of a snapshot taken of this network, errors if the network has
+    /// gained or lost layers, neurons or edges since it was taken
+    pub fn restore_weights(&mut self, snapshot: &NetworkSnapshot) -> Result<(), NeatError> {
+        if snapshot.layers.len() != self.layers.len() {
+            return Err(NeatError::SnapshotMismatch);
+        }
+        for (wrap, taken) in self.layers.iter_mut().zip(snapshot.layers.iter()) {
+            if let Some(taken) = taken {
+                wrap.layer.restore(taken)?;
+            }
+        }
+        self.reset();
+        Ok(())
+    }
+
+
+
+    /// A Graphviz description of the network's topology to see what evolution actually built,
+    /// render it with `dot -Tsvg`. Each layer is a cluster, dense layers show their neurons and
+    /// edges and the rest are drawn as a box, see DotOptions for what is labeled
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        dot::network(self, options)
+    }
+
+
+
+    /// The graph of each dense layer along with its modularity, path lengths and hubs, to study
+    /// the structure evolution settled on. Serialize it to take it elsewhere, see GraphStats
+    pub fn graph_report(&self) -> GraphReport {
+        GraphReport::new(self)
+    }
+
+
+
+    /// The network as the bytes of an ONNX model to deploy with onnxruntime or import into another
+    /// framework, see onnx::export for how each layer is written and what isn't supported
+    #[cfg(feature = "onnx")]
+    pub fn to_onnx(&self) -> Result<Vec<u8>, NeatError> {
+        super::onnx::encode(self)
+    }
+
+
+
+    /// write the network to an .onnx file, see to_onnx
+    #[cfg(feature = "onnx")]
+    pub fn save_onnx(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        super::onnx::save(self, path)
+    }
+
+
+
+    /// feed the inputs through the network and find the hidden neurons of its dense layers whose
+    /// activation never moved by more than tolerance. The network is reset after so the run
+    /// doesn't leave any state behind
+    pub fn dead_neurons(&mut self, inputs: &[Vec<f32>], tolerance: f32) -> Result<DeadNeuronReport, NeatError> {
+        // (layer, neuron, min, max, sum) of each hidden neuron
+        let mut ranges = self.layers.iter()
+            .enumerate()
+            .filter(|(_, wrap)| wrap.layer_type == LayerType::Dense || wrap.layer_type == LayerType::DensePool)
+            .flat_map(|(index, wrap)| {
+                wrap.as_ref::<Dense>().nodes.iter()
+                    .filter(|node| node.neuron_type == NeuronType::Hidden)
+                    .map(move |node| (index, node.id, f32::INFINITY, f32::NEG_INFINITY, 0.0))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for input in inputs.iter() {
+            self.forward(input)?;
+            for (layer, neuron, min, max, sum) in ranges.iter_mut() {
+                let value = self.layers[*layer].as_ref::<Dense>().state(*neuron).activated_value;
+                *min = min.min(value);
+                *max = max.max(value);
+                *sum += value;
+            }
+        }
+        self.reset();
+        Ok(DeadNeuronReport {
+            samples: inputs.len(),
+            hidden: ranges.len(),
+            dead: ranges.into_iter()
+                .filter(|(_, _, min, max, _)| !inputs.is_empty() && max - min <= tolerance)
+                .map(|(layer, neuron, min, max, sum)| DeadNeuron {
+                    layer,
+                    neuron,
+                    value: sum / inputs.len() as f32,
+                    spread: max - min
+                })
+                .collect()
+        })
+    }
+
+
+
+    /// prune every neuron in the report from its layer, see Dense::prune_neuron. The outputs of the
+    /// network only change by however much the dead neurons moved. Returns the number of connections disabled
+    pub fn prune_dead(&mut self, report: &DeadNeuronReport) -> Result<usize, NeatError> {
+        let mut pruned = 0;
+        for dead in report.dead.iter() {
+            let wrap = self.layers.get_mut(dead.layer).ok_or(NeatError::InvalidGraph)?;
+            let dense = wrap.layer.as_mut_any().downcast_mut::<Dense>().ok_or(NeatError::InvalidGraph)?;
+            pruned += dense.prune_neuron(dead.neuron, dead.value)?;
+        }
+        Ok(pruned)
+    }
+
+
+
+    /// Simplify every dense layer of the network for deployment, see Dense::prune. Returns how
+    /// many neurons and edges were removed across the layers
+    pub fn prune(&mut self, threshold: f32) -> PruneReport {
+        let mut report = PruneReport::default();
+        for wrap in self.layers.iter_mut() {
+            if let Some(dense) = wrap.layer.as_mut_any().downcast_mut::<Dense>() {
+                let pruned = dense.prune(threshold);
+                report.nodes += pruned.nodes;
+                report.edges += pruned.edges;
+            }
+        }
+        report
+    }
+
+
+
+    /// Apply the mutation to a copy of the network and describe what it changed without touching
+    /// the network itself, see MutationPreview. The mutation draws its randomness from a stream
+    /// keyed by seed, so the same seed always previews (and commits) the same mutation. Errors if
+    /// the mutation points at a layer which isn't there or can't take it (only dense pool layers
+    /// can add or remove neurons and edges) or the environment is missing a setting
+    pub fn preview_mutation(&self, mutation: &Mutation, seed: u64) -> Result<MutationPreview, Box<dyn Error>> {
+        rng::scoped(seed, || {
+            let mut child = self.clone();
+            match mutation {
+                Mutation::Environment(env) => {
+                    child = Neat::crossover(self, self, Arc::new(RwLock::new((**env).clone())), 0.0)?;
+                },
+                Mutation::EditWeights { layer, editable, perturb } => {
+                    let wrap = child.layers.get_mut(*layer).ok_or(NeatError::InvalidGraph)?;
+                    let dense = wrap.layer.as_mut_any().downcast_mut::<Dense>().ok_or(NeatError::InvalidGraph)?;
+                    dense.edit_weights(&WeightEdit::scale(*editable, *perturb));
+                },
+                Mutation::AddNode { layer, activation, direction } => {
+                    child.dense_pool_at(*layer)?.add_node(*activation, *direction);
+                },
+                Mutation::AddEdge { layer } => {
+                    child.dense_pool_at(*layer)?.add_edge();
+                },
+                Mutation::AddRecurrentEdge { layer } => {
+                    child.dense_pool_at(*layer)?.add_recurrent_edge();
+                },
+                Mutation::RemoveNode { layer } => {
+                    child.dense_pool_at(*layer)?.remove_node();
+                },
+                Mutation::RemoveEdge { layer } => {
+                    child.dense_pool_at(*layer)?.remove_edge();
+                },
+                Mutation::MutateActivation { layer, activations } => {
+                    child.dense_pool_at(*layer)?.mutate_activation(activations);
+                }
+            }
+            Ok(MutationPreview::new(self, child))
+        })
+    }
+
+
+
+    /// Sample what the mutation does to the network's fitness on the problem, ie: to see whether
+    /// a mutation magnitude mostly does nothing (neutral) or throws the network somewhere unrelated
+    /// (rugged), see LandscapeReport. The network itself is left as it is
+    pub fn probe_landscape<P>(&self, problem: &P, mutation: &Mutation, probe: &LandscapeProbe) -> Result<LandscapeReport, Box<dyn Error>>
+        where P: Problem<Neat>
+    {
+        landscape::probe(self, problem, mutation, probe)
+    }
+
+
+
+    /// the dense pool layer at index, for mutations which change the shape of a layer
+    fn dense_pool_at(&mut self, index: usize) -> Result<&mut Dense, NeatError> {
+        let wrap = self.layers.get_mut(index).ok_or(NeatError::InvalidGraph)?;
+        if wrap.layer_type != LayerType::DensePool {
+            return Err(NeatError::InvalidGraph);
+        }
+        Ok(wrap.as_mut())
+    }
+
+
+
+    /// distance between two layers of the same type at the same depth
+    fn layer_distance(layer_one: &LayerWrap, layer_two: &LayerWrap, env: Arc<RwLock<NeatEnvironment>>) -> f32 {
+        match layer_one.layer_type {
+            LayerType::Dense | LayerType::DensePool => {
+                Dense::distance(layer_one.as_ref(), layer_two.as_ref(), env)
+            },
+            LayerType::LSTM => {
+                LSTM::distance(layer_one.as_ref(), layer_two.as_ref(), env)
+            },
+            LayerType::GRU => {
+                GRU::distance(layer_one.as_ref(), layer_two.as_ref(), env)
+            },
+            LayerType::Conv1D => {
+                Conv1D::distance(layer_one.as_ref(), layer_two.as_ref(), env)
+            },
+            LayerType::Conv2D => {
+                Conv2D::distance(layer_one.as_ref(), layer_two.as_ref(), env)
+            },
+            LayerType::MaxPool2D => {
+                MaxPool2D::distance(layer_one.as_ref(), layer_two.as_ref(), env)
+            },
+            LayerType::Dropout => {
+                Dropout::distance(layer_one.as_ref(), layer_two.as_ref(), env)
+            },
+            LayerType::Norm => {
+                Norm::distance(layer_one.as_ref(), layer_two.as_ref(), env)
+            }
+        }
+    }
+
+
+
+    /// alignment between two layers of the same type at the same depth
+    fn layer_alignment(layer_one: &LayerWrap, layer_two: &LayerWrap) -> Option<Alignment> {
+        match layer_one.layer_type {
+            LayerType::Dense | LayerType::DensePool => {
+                Dense::alignment(layer_one.as_ref(), layer_two.as_ref())
+            },
+            LayerType::LSTM => {
+                LSTM::alignment(layer_one.as_ref(), layer_two.as_ref())
+            },
+            LayerType::GRU => {
+                GRU::alignment(layer_one.as_ref(), layer_two.as_ref())
+            },
+            LayerType::Conv1D => {
+                Conv1D::alignment(layer_one.as_ref(), layer_two.as_ref())
+            },
+            LayerType::Conv2D => {
+                Conv2D::alignment(layer_one.as_ref(), layer_two.as_ref())
+            },
+            LayerType::MaxPool2D => {
+                MaxPool2D::alignment(layer_one.as_ref(), layer_two.as_ref())
+            },
+            LayerType::Dropout => {
+                Dropout::alignment(layer_one.as_ref(), layer_two.as_ref())
+            },
+            LayerType::Norm => {
+                Norm::alignment(layer_one.as_ref(), layer_two.as_ref())
+            }
+        }
+    }
+
+
+
+    /// compile an inference only copy of the network, see CompiledNeat
+    pub fn compile(&self) -> Option<CompiledNeat> {
+        CompiledNeat::compile(self)
+    }
+
+
+
+    /// turn timing of each layer's forward and backward calls on or off, see profile_report.
+    /// Clones and children of a profiled model are profiled as well, starting from zero
+    pub fn profile(mut self, enabled: bool) -> Self {
+        self.profiler = if enabled { Some(Profiler::new()) } else { None };
+        self
+    }
+
+
+
+    /// the time spent in each layer since profiling was turned on, or None if it isn't
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        let profiler = self.profiler.as_ref()?;
+        Some(ProfileReport {
+            layers: self.layers.iter()
+                .enumerate()
+                .map(|(i, wrap)| (wrap.layer_type, profiler.layers.get(i).copied().unwrap_or_default()))
+                .collect()
+        })
+    }
+
+
+
+    /// the stats of the weights of each layer, useful for finding dead or saturated layers
+    pub fn weight_report(&self) -> WeightReport {
+        WeightReport {
+            layers: self.layers.iter()
+                .map(|wrap| (wrap.layer_type, wrap.layer.weight_stats()))
+                .collect()
+        }
+    }
+
+
+
+    /// The summed wiring cost of the network's dense layers, each laid out by the placement at the
+    /// same index. There must be a placement for every dense layer, see Placement
+    pub fn wiring_cost(&self, placements: &[Placement]) -> Result<f32, NeatError> {
+        let dense = self.layers.iter()
+            .filter(|wrap| wrap.layer_type == LayerType::Dense || wrap.layer_type == LayerType::DensePool)
+            .collect::<Vec<_>>();
+        if dense.len() != placements.len() {
+            return Err(NeatError::PlacementMismatch);
+        }
+        dense.iter()
+            .zip(placements.iter())
+            .map(|(wrap, placement)| wrap.as_ref::<Dense>().wiring_cost(placement))
+            .sum()
+    }
+
+
+
+    /// The size of the network as (neurons, connections), not counting its inputs. Dense layers
+    /// count their hidden and output neurons and their active edges, the rest their outputs and weights
+    pub fn complexity(&self) -> (usize, usize) {
+        self.layers.iter().fold((0, 0), |(nodes, edges), wrap| {
+            match wrap.layer_type {
+                LayerType::Dense | LayerType::DensePool => {
+                    let dense = wrap.as_ref::<Dense>();
+                    let active = dense.edges.iter().filter(|edge| edge.active).count();
+                    (nodes + dense.nodes.len() - dense.inputs.len(), edges + active)
+                },
+                _ => (nodes + wrap.layer.shape().1, edges + wrap.layer.weight_stats().count)
+            }
+        })
+    }
+
+
+
     /// reset the layers on the network
     pub fn reset(&mut self) {
         for l in self.layers.iter_mut() {
@@ -93,7 +576,18 @@ impl Neat {
 
 
 
-    /// train the network
+    /// switch every layer between training and inference (the default). train and fit switch the
+    /// network to training while they run, this is only needed to call backward by hand
+    pub fn set_training(&mut self, training: bool) {
+        for l in self.layers.iter_mut() {
+            l.layer.set_training(training);
+        }
+    }
+
+
+
+    /// train the network, run is given the epoch and the loss of the epoch summed over its samples
+    /// and training stops once it returns true
     #[inline]
     pub fn train<F>(&mut self, inputs: &[Vec<f32>], targets: &[Vec<f32>], rate: f32, loss_fn: Loss, run: F) -> Result<(), Box<dyn Error>>
         where F: Fn(usize, f32) -> bool 
@@ -108,6 +602,7 @@ impl Neat {
         let (mut epoch, mut count, mut loss) = (0, 0, 0.0);
         
         // add tracers to the layers during training to keep track of meta data for backprop
+        self.set_training(true);
         if self.batch_size > 1 {
             self.layers
                 .iter_mut()
@@ -116,13 +611,18 @@ impl Neat {
         
         // iterate through the number of iterations and train the network
         loop {
-            for j in 0..inputs.len() {
+            let order = self.shuffle.order(targets);
+            for (n, j) in order.iter().enumerate() {
                 count += 1;
-                pass_out.push(self.forward(&inputs[j]).ok_or("Error in network feed forward")?);
-                pass_tar.push(targets[j].clone());
-                if count == self.batch_size || j == inputs.len() - 1 {
+                let output = match self.augment.clone() {
+                    Some(augment) => self.forward(&augment.augment(&inputs[*j])),
+                    None => self.forward(&inputs[*j])
+                };
+                pass_out.push(output?);
+                pass_tar.push(targets[*j].clone());
+                if count == self.batch_size || n == order.len() - 1 {
                     count = 0;
-                    loss += self.backward(&pass_out, &pass_tar, rate, &loss_fn);
+                    loss += self.backward(&pass_out, &pass_tar, rate, &loss_fn)?;
                     pass_out = Vec::with_capacity(self.batch_size);
                     pass_tar = Vec::with_capacity(self.batch_size);
                 }
@@ -138,56 +638,163 @@ impl Neat {
         self.layers
             .iter_mut()
             .for_each(|x| x.layer.remove_tracer());
+        self.set_training(false);
 
         Ok(())
     }
 
     
 
-    /// backpropagate the network, will move through time if needed
+    /// train the network on (input, target) pairs in mini-batches. Unlike train, where every
+    /// sample steps the weights as soon as it is backpropagated, the steps of each sample in a
+    /// batch are summed and the mean is taken once the whole batch is through, which is far
+    /// more stable when fine-tuning an evolved network. Recurrent layers see each batch as a
+    /// sequence, same as train. Returns the loss of each epoch, summed over its samples
+    pub fn fit(&mut self, data: &[(Vec<f32>, Vec<f32>)], batch_size: usize, epochs: usize, rate: f32, loss_fn: Loss) -> Result<Vec<f32>, NeatError> {
+        for wrap in self.layers.iter_mut() {
+            wrap.layer.add_tracer();
+            wrap.layer.accumulate_gradients(true);
+            wrap.layer.set_training(true);
+        }
+        let result = self.fit_epochs(data, batch_size.max(1), epochs, rate, &loss_fn);
+
+        // leave the network how it was found, even if a batch failed part way through
+        for wrap in self.layers.iter_mut() {
+            wrap.layer.accumulate_gradients(false);
+            wrap.layer.remove_tracer();
+            wrap.layer.set_training(false);
+        }
+        self.reset();
+        result
+    }
+
+
+
+    fn fit_epochs(&mut self, data: &[(Vec<f32>, Vec<f32>)], batch_size: usize, epochs: usize, rate: f32, loss_fn: &Loss) -> Result<Vec<f32>, NeatError> {
+        let targets = data.iter().map(|(_, target)| target.clone()).collect::<Vec<_>>();
+        let mut losses = Vec::with_capacity(epochs);
+        for _ in 0..epochs {
+            let mut loss = 0.0;
+            for batch in self.shuffle.order(&targets).chunks(batch_size) {
+                let mut pass_out = Vec::with_capacity(batch.len());
+                let mut pass_tar = Vec::with_capacity(batch.len());
+                for j in batch.iter() {
+                    let output = match self.augment.clone() {
+                        Some(augment) => self.forward(&augment.augment(&data[*j].0)),
+                        None => self.forward(&data[*j].0)
+                    };
+                    pass_out.push(output?);
+                    pass_tar.push(targets[*j].clone());
+                }
+                loss += self.backward(&pass_out, &pass_tar, rate, loss_fn)?;
+                for wrap in self.layers.iter_mut() {
+                    wrap.layer.apply_gradients(1.0 / batch.len() as f32);
+                }
+            }
+            losses.push(loss);
+        }
+        Ok(losses)
+    }
+
+
+
+    /// backpropagate the network, will move through time if needed. The error fed back
+    /// through the layers comes from the loss function, returns the loss summed over the samples
     #[inline]
-    pub fn backward(&mut self, net_outs: &[Vec<f32>], net_targets: &[Vec<f32>], rate: f32, loss_fn: &Loss) -> f32 {
+    pub fn backward(&mut self, net_outs: &[Vec<f32>], net_targets: &[Vec<f32>], rate: f32, loss_fn: &Loss) -> Result<f32, NeatError> {
         let mut total_loss = 0.0;
+        let activation = self.output_activation();
         for i in (0..net_outs.len()).rev() {
-            let errors = vectorops::loss(&net_targets[i], &net_outs[i], &loss_fn);
+            let errors = match activation {
+                Some(activation) => loss_fn.loss_for(&net_targets[i], &net_outs[i], activation),
+                None => vectorops::loss(&net_targets[i], &net_outs[i], &loss_fn)
+            };
             total_loss += errors.0;
+            let profiler = &mut self.profiler;
             self.layers
                 .iter_mut()
+                .enumerate()
                 .rev()
-                .fold(errors.1, |res, curr| {
-                    curr.layer.backward(&res, rate).unwrap()
-                });
+                .try_fold(errors.1, |res, (index, curr)| {
+                    match profiler {
+                        Some(profiler) => {
+                            let start = Instant::now();
+                            let errors = curr.layer.backward(&res, rate);
+                            profiler.record_backward(index, start.elapsed());
+                            errors
+                        },
+                        None => curr.layer.backward(&res, rate)
+                    }
+                })?;
         }
         self.reset();
-        total_loss
+        Ok(total_loss)
     }
 
 
 
     /// feed forward a vec of data through the neat network 
     #[inline]
-    pub fn forward(&mut self, data: &Vec<f32>) -> Option<Vec<f32>> {
+    pub fn forward(&mut self, data: &Vec<f32>) -> Result<Vec<f32>, NeatError> {
         // keep two vec in order to transfer the data from one layer to another layer in the network
         let mut temp;
         let mut data_transfer = data;
-        for wrapper in self.layers.iter_mut() {
-            temp = wrapper.layer.forward(data_transfer)?;
+        for (index, wrapper) in self.layers.iter_mut().enumerate() {
+            temp = match self.profiler.as_mut() {
+                Some(profiler) => {
+                    let start = Instant::now();
+                    let output = wrapper.layer.forward(data_transfer);
+                    profiler.record_forward(index, start.elapsed());
+                    output?
+                },
+                None => wrapper.layer.forward(data_transfer)?
+            };
             data_transfer = &temp;
         }
-        // gather the output and return it as an option
-        Some(data_transfer.to_owned())
-    }    
+        // gather the output and return it
+        Ok(data_transfer.to_owned())
+    }
 
 
 
+    /// feed a batch of data through the network one layer at a time, the same as calling forward on
+    /// each of them in order but letting each layer reuse its buffers across the whole batch
+    pub fn forward_batch(&mut self, data: &[Vec<f32>]) -> Result<Vec<Vec<f32>>, NeatError> {
+        let mut batch = data.to_vec();
+        for (index, wrapper) in self.layers.iter_mut().enumerate() {
+            batch = match self.profiler.as_mut() {
+                Some(profiler) => {
+                    let start = Instant::now();
+                    let output = wrapper.layer.forward_batch(&batch);
+                    profiler.record_forward(index, start.elapsed());
+                    output?
+                },
+                None => wrapper.layer.forward_batch(&batch)?
+            };
+        }
+        Ok(batch)
+    }
+
+
     /// create and append a new dense pool layer onto the neat network
     #[inline]
-    pub fn dense_pool(mut self, size: u32, activation: Activation) -> Self {
+    pub fn dense_pool(self, size: u32, activation: Activation) -> Self {
+        self.dense_pool_with(size, activation, Topology::FullyConnected)
+    }
+
+
+
+    /// create and append a new dense pool layer whose starting connections are laid out by the topology
+    #[inline]
+    pub fn dense_pool_with(mut self, size: u32, activation: Activation, topology: Topology) -> Self {
         let (input_size, output_size) = self.get_layer_sizes(size).unwrap();
-        let wrapper = LayerWrap {
+        let mut wrapper = LayerWrap {
             layer_type: LayerType::DensePool,
-            layer: Box::new(Dense::new(input_size, output_size, LayerType::DensePool, activation))
+            layer: Box::new(Dense::with_topology(input_size, output_size, LayerType::DensePool, activation, &topology))
         };
+        wrapper.layer.set_precision(self.precision);
+        wrapper.layer.set_optimizer(self.optimizer);
+        wrapper.layer.set_bias(self.bias);
         self.layers.push(wrapper);
         self
     }
@@ -198,10 +805,13 @@ impl Neat {
     #[inline]
     pub fn dense(mut self, size: u32, activation: Activation) -> Self {
         let (input_size, output_size) = self.get_layer_sizes(size).unwrap();
-        let wrapper = LayerWrap {
+        let mut wrapper = LayerWrap {
             layer_type: LayerType::Dense,
             layer: Box::new(Dense::new(input_size, output_size, LayerType::Dense, activation))
         };
+        wrapper.layer.set_precision(self.precision);
+        wrapper.layer.set_optimizer(self.optimizer);
+        wrapper.layer.set_bias(self.bias);
         self.layers.push(wrapper);
         self
     }
@@ -212,10 +822,12 @@ impl Neat {
     #[inline]
     pub fn lstm(mut self, size: u32, output_size: u32, act: Activation) -> Self {
         let (input_size, output_size) = self.get_layer_sizes(output_size).unwrap();
-        let wrapper = LayerWrap {
+        let mut wrapper = LayerWrap {
             layer_type: LayerType::LSTM,
             layer: Box::new(LSTM::new(input_size, size, output_size, act))
         };
+        wrapper.layer.set_precision(self.precision);
+        wrapper.layer.set_optimizer(self.optimizer);
         self.layers.push(wrapper);
         self
     }
@@ -225,16 +837,138 @@ impl Neat {
     #[inline]
     pub fn gru(mut self, size: u32, output_size: u32, act: Activation) -> Self {
         let (input_size, output_size) = self.get_layer_sizes(output_size).unwrap();
-        let wrapper = LayerWrap {
+        let mut wrapper = LayerWrap {
             layer_type: LayerType::GRU,
             layer: Box::new(GRU::new(input_size, size, output_size, act))
         };
+        wrapper.layer.set_precision(self.precision);
+        wrapper.layer.set_optimizer(self.optimizer);
         self.layers.push(wrapper);
         self
     }
 
     
 
+    /// create a new one dimensional convolution and add it to the network. The output of the previous
+    /// layer (or the network's input) is read as a sequence of steps with the given number of channels each
+    #[inline]
+    pub fn conv1d(mut self, channels: usize, filters: usize, kernel: usize, stride: usize, padding: usize, act: Activation) -> Self {
+        let (input_size, _) = self.get_layer_sizes(0).unwrap();
+        let length = input_size as usize / channels;
+        assert!(length * channels == input_size as usize, "Conv1D input size must be a multiple of its channels");
+        let mut wrapper = LayerWrap {
+            layer_type: LayerType::Conv1D,
+            layer: Box::new(Conv1D::new(length, channels, filters, kernel, stride, padding, act))
+        };
+        wrapper.layer.set_precision(self.precision);
+        wrapper.layer.set_optimizer(self.optimizer);
+        self.layers.push(wrapper);
+        self
+    }
+
+
+
+    /// create a new two dimensional convolution and add it to the network. The output of the previous
+    /// layer (or the network's input) is read as an image of the given (height, width, channels) shape,
+    /// see Flatten. Directly after another convolution or pooling layer the shape can be left out
+    #[inline]
+    pub fn conv2d(mut self, shape: Option<(usize, usize, usize)>, filters: usize, kernel: usize, stride: usize, padding: usize, act: Activation) -> Self {
+        let shape = self.image_shape(shape);
+        let mut wrapper = LayerWrap {
+            layer_type: LayerType::Conv2D,
+            layer: Box::new(Conv2D::new(shape, filters, kernel, stride, padding, act))
+        };
+        wrapper.layer.set_precision(self.precision);
+        wrapper.layer.set_optimizer(self.optimizer);
+        self.layers.push(wrapper);
+        self
+    }
+
+
+
+    /// create a new max pooling layer and add it to the network, it reads the output of the previous
+    /// convolution or pooling layer as an image, see conv2d
+    #[inline]
+    pub fn max_pool2d(mut self, size: usize, stride: usize) -> Self {
+        let shape = self.image_shape(None);
+        self.layers.push(LayerWrap {
+            layer_type: LayerType::MaxPool2D,
+            layer: Box::new(MaxPool2D::new(shape, size, stride))
+        });
+        self
+    }
+
+
+
+    /// add a dropout layer to the network which drops each output of the previous layer with the
+    /// given probability while the network is being trained, see Dropout
+    #[inline]
+    pub fn dropout(mut self, probability: f32) -> Self {
+        let (input_size, _) = self.get_layer_sizes(0).unwrap();
+        self.layers.push(LayerWrap {
+            layer_type: LayerType::Dropout,
+            layer: Box::new(Dropout::new(input_size as usize, probability))
+        });
+        self
+    }
+
+
+
+    /// add a batch normalization layer over the outputs of the previous layer, see Norm
+    #[inline]
+    pub fn batch_norm(self) -> Self {
+        self.norm(NormType::Batch)
+    }
+
+
+
+    /// add a layer normalization layer over the outputs of the previous layer, see Norm
+    #[inline]
+    pub fn layer_norm(self) -> Self {
+        self.norm(NormType::Layer)
+    }
+
+
+
+    fn norm(mut self, norm_type: NormType) -> Self {
+        let (input_size, _) = self.get_layer_sizes(0).unwrap();
+        let mut wrapper = LayerWrap {
+            layer_type: LayerType::Norm,
+            layer: Box::new(Norm::new(input_size as usize, norm_type))
+        };
+        wrapper.layer.set_optimizer(self.optimizer);
+        self.layers.push(wrapper);
+        self
+    }
+
+
+
+    /// the activation of the last layer if it is a dense layer, which decides the form of cross-entropy
+    pub fn output_activation(&self) -> Option<Activation> {
+        self.layers.last().and_then(|wrap| match wrap.layer_type {
+            LayerType::Dense | LayerType::DensePool => Some(wrap.as_ref::<Dense>().activation),
+            _ => None
+        })
+    }
+
+
+
+    /// the shape of the image the next layer reads, either the given shape which has to be the
+    /// size of the previous layer's output, or the shape of the previous layer's output image
+    fn image_shape(&self, shape: Option<(usize, usize, usize)>) -> (usize, usize, usize) {
+        let (input_size, _) = self.get_layer_sizes(0).unwrap();
+        let previous = self.layers.last().and_then(|wrap| match wrap.layer_type {
+            LayerType::Conv2D => Some(wrap.as_ref::<Conv2D>().output_shape()),
+            LayerType::MaxPool2D => Some(wrap.as_ref::<MaxPool2D>().output_shape()),
+            _ => None
+        });
+        let shape = shape.or(previous).expect("The image shape is needed unless the previous layer is a Conv2D or MaxPool2D");
+        assert!(shape.0 * shape.1 * shape.2 == input_size as usize, "The image shape doesn't match the size of the previous layer's output");
+        shape
+    }
+
+
+
     /// in order to more efficiently give inputs to the network, this function simple
     /// finds the shape of the layer that should be created based on the desired size
     #[inline]
@@ -247,17 +981,52 @@ impl Neat {
 
 
     
-    /// dumy model saver file to export the model to json
+    /// dumy model saver file to export the model to json, stamped with the model format version
     pub fn save(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
-        serde_json::to_writer_pretty(&File::create(file_path)?, &self)?;
+        serde_json::to_writer_pretty(&File::create(file_path)?, &self.to_versioned()?)?;
         Ok(())
     }
 
 
 
-    /// load in a saved neat model from a file path
+    /// load in a saved neat model from a file path, upgrading it from older formats if needed
     pub fn load(file_path: &str) -> Result<Neat, Box<dyn Error>> {
-        Ok(serde_json::from_reader(File::open(file_path).expect("file not found")).expect("error while reading json"))
+        Neat::from_versioned(serde_json::from_reader(File::open(file_path)?)?)
+    }
+
+
+
+    /// save the model with a card describing it, see ModelCard
+    pub fn save_with_card(&self, file_path: &str, card: &ModelCard) -> Result<(), Box<dyn Error>> {
+        let mut model = self.to_versioned()?;
+        card.attach(&mut model)?;
+        serde_json::to_writer_pretty(&File::create(file_path)?, &model)?;
+        Ok(())
+    }
+
+
+
+    /// load in a saved neat model along with its card, if it was saved with one
+    pub fn load_with_card(file_path: &str) -> Result<(Neat, Option<ModelCard>), Box<dyn Error>> {
+        let model: serde_json::Value = serde_json::from_reader(File::open(file_path)?)?;
+        let card = ModelCard::from_model(&model)?;
+        Ok((Neat::from_versioned(model)?, card))
+    }
+
+
+
+    /// serialize the model with the current format version
+    pub fn to_versioned(&self) -> Result<serde_json::Value, Box<dyn Error>> {
+        let mut model = serde_json::to_value(self)?;
+        migrate::stamp(&mut model);
+        Ok(model)
+    }
+
+
+
+    /// deserialize a model saved with this or any older format version
+    pub fn from_versioned(model: serde_json::Value) -> Result<Neat, Box<dyn Error>> {
+        Ok(serde_json::from_value(migrate::migrate(model)?)?)
     }
 
 
@@ -280,7 +1049,14 @@ impl Clone for Neat {
                 })
                 .collect(),
             input_size: self.input_size,
-            batch_size: self.batch_size
+            batch_size: self.batch_size,
+            shuffle: self.shuffle,
+            augment: self.augment.clone(),
+            profiler: self.profiler.as_ref().map(|_| Profiler::new()),
+            precision: self.precision,
+            optimizer: self.optimizer,
+            bias: self.bias,
+            mutation_rates: self.mutation_rates
         }
     }
 }
@@ -304,7 +1080,21 @@ impl PartialEq for Neat {
 impl Genome<Neat, NeatEnvironment> for Neat {
 
     #[inline]
-    fn crossover(one: &Neat, two: &Neat, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Option<Neat> {
+    fn crossover(one: &Neat, two: &Neat, env: Arc<RwLock<NeatEnvironment>>, crossover_rate: f32) -> Result<Neat, Box<dyn Error>> {
+        // with self-adaptive rates the child is mutated with the rates it inherits from the fitter parent
+        let (env, mutation_rates, bias) = {
+            let set = env.read().unwrap();
+            let bias = set.bias.unwrap_or(one.bias);
+            match set.self_adaptive {
+                Some(adaptation) => {
+                    let rates = adaptation.inherit(one.mutation_rates.as_ref(), &set);
+                    let mut child_env = set.clone();
+                    rates.apply(&mut child_env);
+                    (Arc::new(RwLock::new(child_env)), Some(rates), bias)
+                },
+                None => (Arc::clone(&env), None, bias)
+            }
+        };
         let mut result_layers = Vec::with_capacity(one.layers.len());
         // iterate through the layers of the network and cross them over with each other
         for (one_layer, two_layer) in one.layers.iter().zip(two.layers.iter()) {
@@ -317,6 +1107,21 @@ impl Genome<Neat, NeatEnvironment> for Neat {
                 },
                 LayerType::GRU => {
                     Box::new(GRU::crossover(one_layer.as_ref(), two_layer.as_ref(), Arc::clone(&env), crossover_rate)?)
+                },
+                LayerType::Conv1D => {
+                    Box::new(Conv1D::crossover(one_layer.as_ref(), two_layer.as_ref(), Arc::clone(&env), crossover_rate)?)
+                },
+                LayerType::Conv2D => {
+                    Box::new(Conv2D::crossover(one_layer.as_ref(), two_layer.as_ref(), Arc::clone(&env), crossover_rate)?)
+                },
+                LayerType::MaxPool2D => {
+                    Box::new(MaxPool2D::crossover(one_layer.as_ref(), two_layer.as_ref(), Arc::clone(&env), crossover_rate)?)
+                },
+                LayerType::Dropout => {
+                    Box::new(Dropout::crossover(one_layer.as_ref(), two_layer.as_ref(), Arc::clone(&env), crossover_rate)?)
+                },
+                LayerType::Norm => {
+                    Box::new(Norm::crossover(one_layer.as_ref(), two_layer.as_ref(), Arc::clone(&env), crossover_rate)?)
                 }
             };
 
@@ -326,37 +1131,63 @@ impl Genome<Neat, NeatEnvironment> for Neat {
             });
         }
         // return the new child network
-        Some(Neat { 
+        Ok(Neat { 
             layers: result_layers, 
             input_size: one.input_size, 
-            batch_size: one.batch_size
+            batch_size: one.batch_size,
+            shuffle: one.shuffle,
+            augment: one.augment.clone(),
+            profiler: one.profiler.as_ref().map(|_| Profiler::new()),
+            precision: one.precision,
+            optimizer: one.optimizer,
+            bias,
+            mutation_rates
         })
     }
 
 
 
     fn base(env: &mut NeatEnvironment) -> Neat {
-        Neat::new().input_size(env.input_size.unwrap()).dense_pool(env.output_size.unwrap(), Activation::Sigmoid)
+        Neat::new().input_size(env.input_size.unwrap()).bias(env.bias.unwrap_or_default()).dense_pool_with(env.output_size.unwrap(), Activation::Sigmoid, env.topology.clone())
     }
 
 
     #[inline]
     fn distance(one: &Neat, two: &Neat, env: Arc<RwLock<NeatEnvironment>>) -> f32 {
+        one.layers.iter()
+            .zip(two.layers.iter())
+            .map(|(layer_one, layer_two)| Neat::layer_distance(layer_one, layer_two, Arc::clone(&env)))
+            .sum()
+    }
+
+
+    /// layer distances are never negative so the sum can only grow, 
+    /// stop as soon as it reaches the threshold
+    #[inline]
+    fn within_distance(one: &Neat, two: &Neat, env: Arc<RwLock<NeatEnvironment>>, threshold: f32) -> bool {
         let mut total_distance = 0.0;
         for (layer_one, layer_two) in one.layers.iter().zip(two.layers.iter()) {
-            total_distance += match layer_one.layer_type {
-                LayerType::Dense | LayerType::DensePool => {
-                    Dense::distance(layer_one.as_ref(), layer_two.as_ref(), Arc::clone(&env))
-                },
-                LayerType::LSTM => {
-                    LSTM::distance(layer_one.as_ref(), layer_two.as_ref(), Arc::clone(&env))
-                },
-                LayerType::GRU => {
-                    GRU::distance(layer_one.as_ref(), layer_two.as_ref(), Arc::clone(&env))
-                }
-            };
+            total_distance += Neat::layer_distance(layer_one, layer_two, Arc::clone(&env));
+            if total_distance >= threshold {
+                return false;
+            }
         }
-        total_distance
+        total_distance < threshold
+    }
+
+
+    #[inline]
+    fn alignment(one: &Neat, two: &Neat) -> Option<Alignment> {
+        one.layers.iter()
+            .zip(two.layers.iter())
+            .map(|(layer_one, layer_two)| Neat::layer_alignment(layer_one, layer_two))
+            .sum()
+    }
+
+
+    #[inline]
+    fn complexity(one: &Neat) -> Option<(usize, usize)> {
+        Some(one.complexity())
     }
 
 }
@@ -1,7 +1,25 @@
 
-use super::activation::Activation;
+extern crate serde_json;
+
+use std::fmt;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::marker::PhantomData;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::{
+    activation::Activation,
+    topology::Topology,
+    bias::Bias,
+    perturb::WeightMutation,
+    innovation::InnovationCounter,
+    adapt::{MutationSchedule, SelfAdaptation}
+};
 
 use crate::engine::environment::Envionment;
+use crate::engine::niche::SpeciesAging;
+use crate::config::{self, ConfigError};
 
 
 /// Configuration settings for the NeatAlgorithm
@@ -10,8 +28,30 @@ use crate::engine::environment::Envionment;
 /// weight_perturb: the uniform range to perturb a weight by will go from negative num to pos (if you enter 5, it will pertub a weight randomly between -5 and 5)
 /// new_node_rate: the probability of adding a new node to the network
 /// new_edge_rate: the probability of adding a new edge to the network
-/// edit_weights: the probability of weights in the network being edited or just left alone
+/// remove_node_rate: the probability of removing a random hidden node and its connections from the network, never by default
+/// remove_edge_rate: the probability of removing a random connection from the network, never by default. Either removal
+///     takes any hidden node left without a connection going out along with it
+/// recurrent_edges: let a new connection in a dense pool close a cycle, feeding forward the value its neuron had on
+///     the pass before instead of being given up on. Off by default so the networks stay feed forward
+/// activation_mutate_rate: the probability of a random hidden neuron being given another of the activation_functions,
+///     never by default so neurons keep the function they were added with
+/// edit_weights: the probability of each weight picked by weight_mutate_rate being replaced with a new random
+///     value rather than scaled by weight_perturb
+/// weight_mutation: perturb weights with gaussian noise and replace them at rates and magnitudes of their own
+///     instead of by edit_weights and weight_perturb, which can be left out once it's set, see WeightMutation.
+///     A schedule or self_adaptive rates only move edit_weights and weight_perturb, not it
+/// weight_clamp: the [low, high] range every weight and bias is kept within when it's edited, unbounded by default
 /// reactivate: the probability of reactivating a connection between two neurons 
+/// topology: how the connections of the networks the population starts from are laid out, see Topology
+/// bias: where the biases of the neurons of every dense layer come from, see Bias. Unset leaves each network
+///     the way it was built, set it makes every layer follow it from its next crossover on
+/// species_aging: how species are boosted while young and penalized once they stop improving, see SpeciesAging
+/// schedule: how the mutation rates change over the generations of a run, see MutationSchedule
+/// self_adaptive: each network carries its own mutation rates which evolve along with it, see SelfAdaptation
+/// extensions: settings for user defined layers and operators, see Extension
+/// innovations: the historical markings edges added by mutation are numbered with, shared by every clone of the
+///     environment. Without one every new edge is a new innovation, see InnovationCounter. Saved with the environment
+///     so a resumed run keeps numbering from where it was, though it can't be set from a config file
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,12 +60,56 @@ pub struct NeatEnvironment {
     pub weight_perturb: Option<f32>,
     pub new_node_rate: Option<f32>,
     pub new_edge_rate: Option<f32>,
+    #[serde(default)]
+    pub remove_node_rate: Option<f32>,
+    #[serde(default)]
+    pub remove_edge_rate: Option<f32>,
     pub recurrent_neuron_rate: Option<f32>,
+    #[serde(default)]
+    pub recurrent_edges: bool,
+    #[serde(default)]
+    pub activation_mutate_rate: Option<f32>,
     pub edit_weights: Option<f32>,
+    #[serde(default)]
+    pub weight_mutation: Option<WeightMutation>,
+    #[serde(default)]
+    pub weight_clamp: Option<(f32, f32)>,
     pub reactivate: Option<f32>,
     pub input_size: Option<u32>,
     pub output_size: Option<u32>,
     pub activation_functions: Vec<Activation>,
+    #[serde(default)]
+    pub topology: Topology,
+    #[serde(default)]
+    pub bias: Option<Bias>,
+    #[serde(default)]
+    pub species_aging: Option<SpeciesAging>,
+    #[serde(default)]
+    pub schedule: Option<Box<MutationSchedule>>,
+    #[serde(default)]
+    pub self_adaptive: Option<SelfAdaptation>,
+    #[serde(default)]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub innovations: Option<Arc<InnovationCounter>>
+}
+
+
+
+/// A typed key into the extensions of a NeatEnvironment. Custom layers and mutation operators
+/// declare a key for each of their settings, usually as a const, and read them back out of the
+/// environment during crossover - 
+/// 
+/// const SPLIT_RATE: Extension<f32> = Extension::new("my_layer.split_rate");
+/// let env = NeatEnvironment::new().set_extension(&SPLIT_RATE, 0.05);
+/// let rate = env.extension(&SPLIT_RATE);
+/// 
+/// Values are stored serialized so the environment can still be cloned, saved with a 
+/// checkpoint, or given in the [extensions] table of a toml config. Getting one deserializes
+/// it, so read it once per crossover rather than once per node or edge.
+pub struct Extension<T> {
+    pub name: &'static str,
+    kind: PhantomData<fn() -> T>
 }
 
 
@@ -37,12 +121,25 @@ impl NeatEnvironment {
             weight_perturb: None,
             new_node_rate: None,
             new_edge_rate: None,
+            remove_node_rate: None,
+            remove_edge_rate: None,
             recurrent_neuron_rate: Some(0.0),
+            recurrent_edges: false,
+            activation_mutate_rate: None,
             edit_weights: None,
+            weight_mutation: None,
+            weight_clamp: None,
             reactivate: None,
             input_size: None,
             output_size: None,
             activation_functions: vec![Activation::Sigmoid],
+            topology: Topology::FullyConnected,
+            bias: None,
+            species_aging: None,
+            schedule: None,
+            self_adaptive: None,
+            extensions: BTreeMap::new(),
+            innovations: None
         }
     }
 
@@ -71,18 +168,60 @@ impl NeatEnvironment {
     }
 
 
+    pub fn set_recurrent_edges(mut self, allow: bool) -> Self {
+        self.recurrent_edges = allow;
+        self
+    }
+
+
+    pub fn set_activation_mutate_rate(mut self, num: f32) -> Self {
+        self.activation_mutate_rate = Some(num);
+        self
+    }
+
+
     pub fn set_new_edge_rate(mut self, num: f32) -> Self {
         self.new_edge_rate = Some(num);
         self
     }
 
 
+    pub fn set_remove_node_rate(mut self, num: f32) -> Self {
+        self.remove_node_rate = Some(num);
+        self
+    }
+
+
+    pub fn set_remove_edge_rate(mut self, num: f32) -> Self {
+        self.remove_edge_rate = Some(num);
+        self
+    }
+
+
     pub fn set_edit_weights(mut self, num: f32) -> Self {
         self.edit_weights = Some(num);
         self
     }
 
 
+    pub fn set_weight_mutation(mut self, mutation: WeightMutation) -> Self {
+        self.weight_mutation = Some(mutation);
+        self
+    }
+
+
+    pub fn set_weight_clamp(mut self, low: f32, high: f32) -> Self {
+        self.weight_clamp = Some((low, high));
+        self
+    }
+
+
+    pub fn set_innovation_counter(mut self, counter: Arc<InnovationCounter>) -> Self {
+        self.innovations = Some(counter);
+        self
+    }
+
+
     pub fn set_reactivate(mut self, num: f32) -> Self {
         self.reactivate = Some(num);
         self
@@ -107,6 +246,196 @@ impl NeatEnvironment {
     }
 
 
+    pub fn set_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+
+    pub fn set_bias(mut self, bias: Bias) -> Self {
+        self.bias = Some(bias);
+        self
+    }
+
+
+    pub fn set_species_aging(mut self, aging: SpeciesAging) -> Self {
+        self.species_aging = Some(aging);
+        self
+    }
+
+
+    pub fn set_schedule(mut self, schedule: MutationSchedule) -> Self {
+        self.schedule = Some(Box::new(schedule));
+        self
+    }
+
+
+    pub fn set_self_adaptive(mut self, adaptation: SelfAdaptation) -> Self {
+        self.self_adaptive = Some(adaptation);
+        self
+    }
+
+
+    /// set the value of an extension, replacing any value it already had
+    pub fn set_extension<T: Serialize>(mut self, key: &Extension<T>, value: T) -> Self {
+        let value = serde_json::to_value(value).expect("Extension values must be serializable to json");
+        self.extensions.insert(key.name.to_string(), value);
+        self
+    }
+
+
+    /// get the value of an extension, None if it was never set or the value set 
+    /// under its name (ie: from a config file) isn't the type of the key
+    pub fn extension<T: DeserializeOwned>(&self, key: &Extension<T>) -> Option<T> {
+        serde_json::from_value(self.extensions.get(key.name)?.clone()).ok()
+    }
+
+
+    /// get the value of an extension, or the default if it isn't set
+    pub fn extension_or<T: DeserializeOwned>(&self, key: &Extension<T>, default: T) -> T {
+        self.extension(key).unwrap_or(default)
+    }
+
+
+    /// read the environment from a toml file. Every rate besides recurrent_neuron_rate
+    /// must be given, the input and output sizes can be left out if the network is
+    /// built in code. Unknown keys are treated as errors so a typo can't silently
+    /// leave a setting at its default
+    pub fn from_toml(path: &str) -> Result<Self, ConfigError> {
+        Self::from_toml_str(&config::read(path)?)
+    }
+
+
+    pub fn from_toml_str(contents: &str) -> Result<Self, ConfigError> {
+        let table = config::parse_table(contents)?;
+        config::check_keys(&table, "", &[
+            "weight_mutate_rate", "weight_perturb", "new_node_rate", "new_edge_rate", "remove_node_rate", "remove_edge_rate", "recurrent_neuron_rate", "recurrent_edges", "activation_mutate_rate",
+            "edit_weights", "weight_mutation", "weight_clamp", "reactivate", "input_size", "output_size", "activation_functions", "topology", "bias", "species_aging", 
+            "schedule", "self_adaptive", "extensions"
+        ], &[
+            "weight_mutate_rate", "new_node_rate", "new_edge_rate", "reactivate", "activation_functions"
+        ])?;
+        match table.get("weight_mutation") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "weight_mutation.", &["perturb_rate", "perturb_std", "replace_rate", "replace_range"], &[
+                "perturb_rate", "perturb_std", "replace_rate"
+            ])?,
+            Some(_) => return Err(ConfigError::invalid("weight_mutation", "expected a table")),
+            None => {}
+        }
+        match table.get("species_aging") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "species_aging.", &["young_age", "young_boost", "dropoff_age", "dropoff_penalty"], &[])?,
+            Some(_) => return Err(ConfigError::invalid("species_aging", "expected a table")),
+            None => {}
+        }
+        match table.get("schedule") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "schedule.", &[
+                "weight_mutate_rate", "weight_perturb", "new_node_rate", "new_edge_rate", "edit_weights", "reactivate"
+            ], &[])?,
+            Some(_) => return Err(ConfigError::invalid("schedule", "expected a table")),
+            None => {}
+        }
+        match table.get("self_adaptive") {
+            Some(toml::Value::Table(inner)) => config::check_keys(inner, "self_adaptive.", &["learning_rate", "min_rate", "max_rate"], &[])?,
+            Some(_) => return Err(ConfigError::invalid("self_adaptive", "expected a table")),
+            None => {}
+        }
+        let mut env = config::parse::<NeatEnvironment>(contents)?;
+        if env.recurrent_neuron_rate.is_none() {
+            env.recurrent_neuron_rate = Some(0.0);
+        }
+        env.validate()?;
+        Ok(env)
+    }
+
+
+    /// check the environment has everything needed to evolve a network 
+    /// and that each setting is in a usable range
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let rates = [
+            ("weight_mutate_rate", self.weight_mutate_rate),
+            ("new_node_rate", self.new_node_rate),
+            ("new_edge_rate", self.new_edge_rate),
+            ("recurrent_neuron_rate", self.recurrent_neuron_rate),
+            ("reactivate", self.reactivate)
+        ];
+        for (key, rate) in rates.iter() {
+            config::check_rate(key, rate.ok_or_else(|| ConfigError::MissingKey(key.to_string()))?)?;
+        }
+        for (key, rate) in [("remove_node_rate", self.remove_node_rate), ("remove_edge_rate", self.remove_edge_rate), ("activation_mutate_rate", self.activation_mutate_rate)] {
+            if let Some(rate) = rate {
+                config::check_rate(key, rate)?;
+            }
+        }
+        // the single edit is only needed when there's no weight mutation to use instead
+        match (self.edit_weights, self.weight_mutation) {
+            (Some(rate), _) => config::check_rate("edit_weights", rate)?,
+            (None, None) => return Err(ConfigError::MissingKey(String::from("edit_weights"))),
+            (None, Some(_)) => {}
+        }
+        match (self.weight_perturb, self.weight_mutation) {
+            (Some(perturb), _) if perturb > 0.0 => {},
+            (Some(perturb), _) => return Err(ConfigError::invalid("weight_perturb", &format!("expected a positive number, got {}", perturb))),
+            (None, None) => return Err(ConfigError::MissingKey(String::from("weight_perturb"))),
+            (None, Some(_)) => {}
+        }
+        if let Some(mutation) = &self.weight_mutation {
+            config::check_rate("weight_mutation.perturb_rate", mutation.perturb_rate)?;
+            config::check_rate("weight_mutation.replace_rate", mutation.replace_rate)?;
+            if mutation.perturb_std <= 0.0 {
+                return Err(ConfigError::invalid("weight_mutation.perturb_std", "expected a positive number"));
+            }
+            if mutation.replace_range <= 0.0 {
+                return Err(ConfigError::invalid("weight_mutation.replace_range", "expected a positive number"));
+            }
+        }
+        if let Some((low, high)) = self.weight_clamp {
+            if low >= high {
+                return Err(ConfigError::invalid("weight_clamp", &format!("expected low < high, got [{}, {}]", low, high)));
+            }
+        }
+        if self.input_size == Some(0) {
+            return Err(ConfigError::invalid("input_size", "must be greater than 0"));
+        }
+        if self.output_size == Some(0) {
+            return Err(ConfigError::invalid("output_size", "must be greater than 0"));
+        }
+        match &self.topology {
+            Topology::Sparse(probability) => config::check_rate("topology", *probability)?,
+            Topology::Layered { activation: Activation::Softmax, .. } => {
+                return Err(ConfigError::invalid("topology", "hidden neurons can't use softmax"));
+            },
+            _ => {}
+        }
+        if self.activation_functions.is_empty() {
+            return Err(ConfigError::invalid("activation_functions", "at least one activation function is needed"));
+        }
+        if let Some(aging) = &self.species_aging {
+            if aging.young_boost <= 0.0 || aging.dropoff_penalty <= 0.0 {
+                return Err(ConfigError::invalid("species_aging", "the boost and penalty must be positive"));
+            }
+        }
+        if let Some(schedule) = &self.schedule {
+            for (key, bounds) in schedule.probabilities() {
+                for rate in bounds.iter() {
+                    config::check_rate(&format!("schedule.{}", key), *rate)?;
+                }
+            }
+            if schedule.perturb_bounds().is_some_and(|bounds| bounds.iter().any(|perturb| *perturb <= 0.0)) {
+                return Err(ConfigError::invalid("schedule.weight_perturb", "expected a positive number"));
+            }
+        }
+        if let Some(adaptive) = &self.self_adaptive {
+            if adaptive.learning_rate < 0.0 {
+                return Err(ConfigError::invalid("self_adaptive.learning_rate", "can't be negative"));
+            }
+            if adaptive.min_rate <= 0.0 || adaptive.min_rate > adaptive.max_rate || adaptive.max_rate > 1.0 {
+                return Err(ConfigError::invalid("self_adaptive", "expected 0 < min_rate <= max_rate <= 1"));
+            }
+        }
+        Ok(())
+    }
+
+
 }
 
 
@@ -118,4 +447,63 @@ impl Default for NeatEnvironment {
 
 
 
-impl Envionment for NeatEnvironment {}
+impl Envionment for NeatEnvironment {
+    fn species_aging(&self) -> Option<SpeciesAging> {
+        self.species_aging
+    }
+
+    fn advance(&mut self, generation: usize) {
+        if let Some(counter) = self.innovations.as_ref() {
+            counter.next_generation(generation);
+        }
+        if let Some(schedule) = self.schedule.as_deref().copied() {
+            schedule.apply(self, generation);
+        }
+    }
+
+    /// the probabilities are capped at 1. Self-adaptive networks are mutated with the rates they
+    /// carry rather than the environment's, so their species are only boosted through the rates
+    /// of networks which don't carry any yet
+    fn boost_mutation(&self, factor: f32) -> Option<Self> {
+        let probability = |rate: Option<f32>| rate.map(|rate| (rate * factor).min(1.0));
+        let mut boosted = self.clone();
+        boosted.weight_mutate_rate = probability(self.weight_mutate_rate);
+        boosted.weight_perturb = self.weight_perturb.map(|perturb| perturb * factor);
+        boosted.new_node_rate = probability(self.new_node_rate);
+        boosted.new_edge_rate = probability(self.new_edge_rate);
+        boosted.edit_weights = probability(self.edit_weights);
+        boosted.weight_mutation = self.weight_mutation.map(|mutation| WeightMutation {
+            perturb_rate: (mutation.perturb_rate * factor).min(1.0),
+            perturb_std: mutation.perturb_std * factor,
+            replace_rate: (mutation.replace_rate * factor).min(1.0),
+            replace_range: mutation.replace_range
+        });
+        boosted.reactivate = probability(self.reactivate);
+        Some(boosted)
+    }
+}
+
+
+
+impl<T> Extension<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Extension { name, kind: PhantomData }
+    }
+}
+
+
+impl<T> Clone for Extension<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+
+impl<T> Copy for Extension<T> {}
+
+
+impl<T> fmt::Debug for Extension<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Extension({})", self.name)
+    }
+}
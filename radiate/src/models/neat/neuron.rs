@@ -1,13 +1,11 @@
 
-extern crate rand;
-
-use rand::Rng;
-
 use super::id::*;
 use super::edge::*;
-use super::activation::Activation;
+use super::activation::{Activation, Precision};
 use super::neurontype::NeuronType;
 use super::direction::NeuronDirection;
+use super::innovation::NodeKey;
+use crate::rng;
 
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -15,6 +13,8 @@ pub struct NeuronLink {
     pub id: EdgeId,
     pub src: NeuronId,
     pub weight: f32,
+    #[serde(default)]
+    pub recurrent: bool
 }
 
 impl NeuronLink {
@@ -23,6 +23,7 @@ impl NeuronLink {
             id: edge.id,
             src: edge.src,
             weight: edge.weight,
+            recurrent: edge.recurrent
         }
     }
 }
@@ -31,7 +32,8 @@ impl NeuronLink {
 /// to the NEAT graph, while the neuron encapsulates the neural network logic for the specific node type,
 /// Some neurons like an LSTM require more variables and different internal activation logic,
 /// so encapsulating that within a normal node on the graph would be misplaced.
-#[derive(Deserialize, Serialize, Debug)]
+/// What the neuron holds from pass to pass is kept apart from it in a NeuronState
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Neuron {
     pub id: NeuronId,
     outgoing: Vec<EdgeId>,
@@ -39,12 +41,10 @@ pub struct Neuron {
     activation: Activation,
     direction: NeuronDirection,
     pub neuron_type: NeuronType,
-    pub activated_value: f32,
-    pub deactivated_value: f32,
-    pub current_state: f32,
-    pub previous_state: f32,
-    pub error: f32,
     pub bias: f32,
+    /// what the neuron is across networks, see NodeKey. None for neurons saved before it was kept
+    #[serde(default)]
+    pub key: Option<NodeKey>
 }
 
 
@@ -57,12 +57,8 @@ impl Neuron {
             activation,
             neuron_type,
             direction,
-            activated_value: 0.0,
-            deactivated_value: 0.0,
-            current_state: 0.0,
-            previous_state: 0.0,
-            error: 0.0,
-            bias: rand::thread_rng().gen::<f32>(),
+            bias: rng::weight(&mut rng::thread()),
+            key: None
         }
     }
 
@@ -93,6 +89,13 @@ impl Neuron {
         self.outgoing.retain(|x| x != &edge);
     }
 
+    /// Give the neuron a new id and drop its links, for when the layer it is in is compacted
+    pub(crate) fn relabel(&mut self, id: NeuronId) {
+        self.id = id;
+        self.incoming.clear();
+        self.outgoing.clear();
+    }
+
     /// Get incoming edge ids.
     pub fn incoming_edges(&self) -> &[NeuronLink] {
         &self.incoming
@@ -103,71 +106,73 @@ impl Neuron {
         &self.outgoing
     }
 
+    /// Get the activation function of the neuron.
+    pub fn activation(&self) -> Activation {
+        self.activation
+    }
+
+    /// Set the activation function of the neuron.
+    pub fn set_activation(&mut self, activation: Activation) {
+        self.activation = activation;
+    }
+
+    /// Get the direction of the neuron.
+    pub fn direction(&self) -> NeuronDirection {
+        self.direction
+    }
+
     /// 𝜎(Σ(w * i) + b)
     /// activate this node by calling the underlying neuron's logic for activation
     #[inline]
-    pub fn activate(&mut self) {
+    pub fn activate(&self, state: &mut NeuronState) {
+        self.activate_with(state, Precision::Exact);
+    }
+
+    /// activate this node computing the activation function with the given precision
+    #[inline]
+    pub fn activate_with(&self, state: &mut NeuronState, precision: Precision) {
         if self.activation != Activation::Softmax {
             match self.direction {
                 NeuronDirection::Forward => {
-                    self.activated_value = self.activation.activate(self.current_state);
-                    self.deactivated_value = self.activation.deactivate(self.current_state);
+                    state.activated_value = self.activation.activate_with(state.current_state, precision);
+                    state.deactivated_value = self.activation.deactivate_with(state.current_state, precision);
                 },
                 NeuronDirection::Recurrent => {
-                    self.activated_value = self.activation.activate(self.current_state + self.previous_state);
-                    self.deactivated_value = self.activation.deactivate(self.current_state + self.previous_state);
+                    state.activated_value = self.activation.activate_with(state.current_state + state.previous_state, precision);
+                    state.deactivated_value = self.activation.deactivate_with(state.current_state + state.previous_state, precision);
                 }
             }
-            self.previous_state = self.current_state;
+            state.previous_state = state.current_state;
         }
     }
+}
+
 
 
+/// What a neuron of a layer holds from one pass to the next. The layer keeps these apart
+/// from its neurons, indexed the same, so feeding it forward or backward leaves the neurons
+/// shared with its clones
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NeuronState {
+    pub activated_value: f32,
+    pub deactivated_value: f32,
+    pub current_state: f32,
+    pub previous_state: f32,
+    /// the activated value of the pass before, which recurrent edges out of the neuron feed forward
+    pub previous_value: f32,
+    pub error: f32
+}
+
+
+impl NeuronState {
+
     /// each Neuron has a base layer of reset which needs to happen 
     /// but on top of that each neuron might need to do more internally
     #[inline]
-    pub fn reset_neuron(&mut self) {
+    pub fn reset(&mut self) {
         self.error = 0.0;
         self.activated_value = 0.0;
         self.deactivated_value = 0.0;
         self.current_state = 0.0;
     }
-
-
-    #[inline]
-    pub fn clone_with_values(&self) -> Self {
-        Neuron {
-            id: self.id,
-            outgoing: self.outgoing.clone(),
-            incoming: self.incoming.clone(),
-            current_state: self.current_state.clone(),
-            previous_state: self.previous_state.clone(),
-            activated_value: self.activated_value.clone(),
-            deactivated_value: self.deactivated_value.clone(),
-            error: self.error.clone(),
-            bias: self.bias.clone(),
-            activation: self.activation.clone(),
-            neuron_type: self.neuron_type.clone(),
-            direction: self.direction.clone()
-        }
-    }
-}
-
-impl Clone for Neuron {
-    fn clone(&self) -> Self { 
-        Neuron {
-            id: self.id,
-            outgoing: self.outgoing.clone(),
-            incoming: self.incoming.clone(),
-            current_state: 0.0,
-            previous_state: 0.0,
-            activated_value: 0.0,
-            deactivated_value: 0.0,
-            error: 0.0,
-            bias: self.bias.clone(),
-            activation: self.activation.clone(),
-            neuron_type: self.neuron_type.clone(),
-            direction: self.direction.clone()
-        }
-    }
 }
@@ -0,0 +1,537 @@
+//! Export of a trained network to an ONNX graph for onnxruntime or any other framework
+//! which imports ONNX, see Neat::to_onnx. Enabled with the onnx feature.
+extern crate prost;
+
+use std::error::Error;
+use std::fs;
+
+use prost::Message;
+
+use super::{
+    neat::Neat,
+    activation::Activation,
+    layers::{
+        dense::Dense,
+        lstm::LSTM,
+        gru::GRU,
+        norm::{Norm, NormType},
+        layertype::LayerType,
+        sparse::SparseDense
+    }
+};
+use crate::error::NeatError;
+
+
+
+/// the opset the exported graphs are written against
+pub const OPSET: i64 = 13;
+/// the ONNX IR version which goes with the opset
+pub const IR_VERSION: i64 = 7;
+
+const FLOAT: i32 = 1;
+const INT64: i32 = 7;
+const ATTRIBUTE_FLOAT: i32 = 1;
+const ATTRIBUTE_INT: i32 = 2;
+const ATTRIBUTE_INTS: i32 = 7;
+
+
+
+/// The parts of the ONNX protobuf schema the export writes, enough to read an exported
+/// model back in. Field numbers match onnx.proto
+pub mod proto {
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ModelProto {
+        #[prost(int64, tag = "1")]
+        pub ir_version: i64,
+        #[prost(string, tag = "2")]
+        pub producer_name: String,
+        #[prost(string, tag = "3")]
+        pub producer_version: String,
+        #[prost(message, optional, tag = "7")]
+        pub graph: Option<GraphProto>,
+        #[prost(message, repeated, tag = "8")]
+        pub opset_import: Vec<OperatorSetIdProto>
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct OperatorSetIdProto {
+        #[prost(string, tag = "1")]
+        pub domain: String,
+        #[prost(int64, tag = "2")]
+        pub version: i64
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GraphProto {
+        #[prost(message, repeated, tag = "1")]
+        pub node: Vec<NodeProto>,
+        #[prost(string, tag = "2")]
+        pub name: String,
+        #[prost(message, repeated, tag = "5")]
+        pub initializer: Vec<TensorProto>,
+        #[prost(message, repeated, tag = "11")]
+        pub input: Vec<ValueInfoProto>,
+        #[prost(message, repeated, tag = "12")]
+        pub output: Vec<ValueInfoProto>
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NodeProto {
+        #[prost(string, repeated, tag = "1")]
+        pub input: Vec<String>,
+        #[prost(string, repeated, tag = "2")]
+        pub output: Vec<String>,
+        #[prost(string, tag = "3")]
+        pub name: String,
+        #[prost(string, tag = "4")]
+        pub op_type: String,
+        #[prost(message, repeated, tag = "5")]
+        pub attribute: Vec<AttributeProto>
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AttributeProto {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(float, tag = "2")]
+        pub f: f32,
+        #[prost(int64, tag = "3")]
+        pub i: i64,
+        #[prost(int64, repeated, tag = "8")]
+        pub ints: Vec<i64>,
+        #[prost(int32, tag = "20")]
+        pub r#type: i32
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorProto {
+        #[prost(int64, repeated, tag = "1")]
+        pub dims: Vec<i64>,
+        #[prost(int32, tag = "2")]
+        pub data_type: i32,
+        #[prost(float, repeated, tag = "4")]
+        pub float_data: Vec<f32>,
+        #[prost(int64, repeated, tag = "7")]
+        pub int64_data: Vec<i64>,
+        #[prost(string, tag = "8")]
+        pub name: String
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ValueInfoProto {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(message, optional, tag = "2")]
+        pub r#type: Option<TypeProto>
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TypeProto {
+        #[prost(message, optional, tag = "1")]
+        pub tensor_type: Option<TensorTypeProto>
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorTypeProto {
+        #[prost(int32, tag = "1")]
+        pub elem_type: i32,
+        #[prost(message, optional, tag = "2")]
+        pub shape: Option<TensorShapeProto>
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorShapeProto {
+        #[prost(message, repeated, tag = "1")]
+        pub dim: Vec<Dimension>
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Dimension {
+        #[prost(int64, optional, tag = "1")]
+        pub dim_value: Option<i64>,
+        #[prost(string, optional, tag = "2")]
+        pub dim_param: Option<String>
+    }
+}
+
+use proto::*;
+
+
+
+/// Convert a network to an ONNX model. The graph takes a batch of inputs named "input" shaped
+/// [N, input size] and gives "output" shaped [N, output size], activations are computed exactly.
+///
+/// Dense layers can have any evolved topology without recurrent neurons, their neurons are grouped
+/// by how deep in the layer they are and each group is one MatMul and Add. LSTM and GRU layers are
+/// exported as a single time step, their state going in as extra inputs (state_<layer>_hidden and
+/// state_<layer>_memory for an LSTM, state_<layer>_output and state_<layer>_memory for a GRU) and
+/// coming back out under the same names ending in _out, which are fed back in for the next step and
+/// start as zeros. Dropout is left out as it does nothing outside of training and batch norm uses
+/// its running statistics. Convolution and pooling layers aren't supported.
+pub fn export(neat: &Neat) -> Result<ModelProto, NeatError> {
+    let mut graph = Graph::default();
+    graph.input("input", neat.input_size as usize);
+    let mut current = String::from("input");
+    for (index, wrap) in neat.layers.iter().enumerate() {
+        current = match wrap.layer_type {
+            LayerType::Dense | LayerType::DensePool => graph.dense(wrap.as_ref::<Dense>(), &current)?,
+            LayerType::LSTM => graph.lstm(wrap.as_ref::<LSTM>(), &current, index)?,
+            LayerType::GRU => graph.gru(wrap.as_ref::<GRU>(), &current, index)?,
+            LayerType::Norm => graph.norm(wrap.as_ref::<Norm>(), &current),
+            LayerType::Dropout => current,
+            _ => return Err(NeatError::Unsupported("convolution and pooling layers can't be exported to onnx"))
+        };
+    }
+    let size = neat.layers.last().map_or(neat.input_size as usize, |wrap| wrap.layer.shape().1);
+    graph.node("Identity", &[&current], "output", Vec::new());
+    graph.output("output", size);
+    Ok(ModelProto {
+        ir_version: IR_VERSION,
+        producer_name: String::from("radiate"),
+        producer_version: String::from(env!("CARGO_PKG_VERSION")),
+        graph: Some(GraphProto {
+            node: graph.nodes,
+            name: String::from("neat"),
+            initializer: graph.initializers,
+            input: graph.inputs,
+            output: graph.outputs
+        }),
+        opset_import: vec![OperatorSetIdProto { domain: String::new(), version: OPSET }]
+    })
+}
+
+
+
+/// the exported model as the bytes of an .onnx file
+pub fn encode(neat: &Neat) -> Result<Vec<u8>, NeatError> {
+    Ok(export(neat)?.encode_to_vec())
+}
+
+
+
+/// write the exported model to an .onnx file
+pub fn save(neat: &Neat, path: &str) -> Result<(), Box<dyn Error>> {
+    fs::write(path, encode(neat)?)?;
+    Ok(())
+}
+
+
+
+/// the graph being built, each tensor made along the way gets a fresh name
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<NodeProto>,
+    initializers: Vec<TensorProto>,
+    inputs: Vec<ValueInfoProto>,
+    outputs: Vec<ValueInfoProto>,
+    count: usize
+}
+
+
+
+impl Graph {
+
+    fn name(&mut self, base: &str) -> String {
+        self.count += 1;
+        format!("{}_{}", base, self.count)
+    }
+
+
+    /// a [N, size] float tensor
+    fn value(name: &str, size: usize) -> ValueInfoProto {
+        let dim = vec![
+            Dimension { dim_value: None, dim_param: Some(String::from("N")) },
+            Dimension { dim_value: Some(size as i64), dim_param: None }
+        ];
+        ValueInfoProto {
+            name: name.to_string(),
+            r#type: Some(TypeProto {
+                tensor_type: Some(TensorTypeProto { elem_type: FLOAT, shape: Some(TensorShapeProto { dim }) })
+            })
+        }
+    }
+
+
+    fn input(&mut self, name: &str, size: usize) {
+        self.inputs.push(Graph::value(name, size));
+    }
+
+
+    fn output(&mut self, name: &str, size: usize) {
+        self.outputs.push(Graph::value(name, size));
+    }
+
+
+    fn node(&mut self, op_type: &str, inputs: &[&str], output: &str, attribute: Vec<AttributeProto>) {
+        self.nodes.push(NodeProto {
+            input: inputs.iter().map(|input| input.to_string()).collect(),
+            output: vec![output.to_string()],
+            name: output.to_string(),
+            op_type: op_type.to_string(),
+            attribute
+        });
+    }
+
+
+    /// add a node with a single output and give back the output's name
+    fn op(&mut self, op_type: &str, inputs: &[&str], attribute: Vec<AttributeProto>) -> String {
+        let output = self.name(&op_type.to_lowercase());
+        self.node(op_type, inputs, &output, attribute);
+        output
+    }
+
+
+    fn constant(&mut self, dims: &[usize], data: Vec<f32>) -> String {
+        let name = self.name("const");
+        self.initializers.push(TensorProto {
+            dims: dims.iter().map(|dim| *dim as i64).collect(),
+            data_type: FLOAT,
+            float_data: data,
+            int64_data: Vec::new(),
+            name: name.clone()
+        });
+        name
+    }
+
+
+    fn indices(&mut self, data: Vec<i64>) -> String {
+        let name = self.name("indices");
+        self.initializers.push(TensorProto {
+            dims: vec![data.len() as i64],
+            data_type: INT64,
+            float_data: Vec::new(),
+            int64_data: data,
+            name: name.clone()
+        });
+        name
+    }
+
+
+    fn scalar(&mut self, value: f32) -> String {
+        self.constant(&[], vec![value])
+    }
+
+
+    fn concat(&mut self, inputs: &[&str]) -> String {
+        self.op("Concat", inputs, vec![int("axis", 1)])
+    }
+
+
+    /// the activation functions as the ops which compute them exactly
+    fn activate(&mut self, input: &str, activation: Activation) -> String {
+        match activation {
+            Activation::Sigmoid => {
+                let scale = self.scalar(4.9);
+                let scaled = self.op("Mul", &[input, &scale], Vec::new());
+                self.op("Sigmoid", &[&scaled], Vec::new())
+            },
+            Activation::Relu => self.op("Relu", &[input], Vec::new()),
+            Activation::LeakyRelu(alpha) => {
+                let alpha = self.scalar(alpha);
+                let scaled = self.op("Mul", &[input, &alpha], Vec::new());
+                self.op("Max", &[input, &scaled], Vec::new())
+            },
+            Activation::ExpRelu(alpha) => self.op("Elu", &[input], vec![float("alpha", alpha)]),
+            Activation::Linear(alpha) => {
+                let alpha = self.scalar(alpha);
+                self.op("Mul", &[input, &alpha], Vec::new())
+            },
+            Activation::Sine => self.op("Sin", &[input], Vec::new()),
+            Activation::Gaussian => {
+                let squared = self.op("Mul", &[input, input], Vec::new());
+                let negated = self.op("Neg", &[&squared], Vec::new());
+                self.op("Exp", &[&negated], Vec::new())
+            },
+            Activation::Abs => self.op("Abs", &[input], Vec::new()),
+            Activation::Swish => {
+                let gate = self.op("Sigmoid", &[input], Vec::new());
+                self.op("Mul", &[input, &gate], Vec::new())
+            },
+            Activation::Softmax => input.to_string(),
+            // Tanh and its deprecated spelling
+            _ => self.op("Tanh", &[input], Vec::new())
+        }
+    }
+
+
+    /// A dense layer's neurons only depend on the neurons before them, so every neuron at the same
+    /// depth can be computed at once from what came before. The values computed so far are kept
+    /// side by side in one tensor starting with the inputs, each depth multiplies it by the weights
+    /// of its neurons (one group per activation function) and adds its results on the end
+    fn dense(&mut self, dense: &Dense, input: &str) -> Result<String, NeatError> {
+        let sparse = SparseDense::compile(dense).ok_or(NeatError::Cycle)?;
+        if sparse.recurrent.iter().any(|recurrent| *recurrent) || !sparse.feedback.is_empty() {
+            return Err(NeatError::Recurrent);
+        }
+        // softmax neurons hold their state for the layer's output but feed nothing forward,
+        // inputs feed their value forward whatever the layer's activation is
+        let feeds = |index: usize| sparse.activations[index] != Activation::Softmax || sparse.inputs.contains(&index);
+        let mut depth = vec![0; dense.nodes.len()];
+        for (i, index) in sparse.order.iter().enumerate() {
+            depth[*index] = sparse.sources[sparse.offsets[i]..sparse.offsets[i + 1]].iter()
+                .filter(|src| feeds(**src))
+                .map(|src| depth[*src] + 1)
+                .max()
+                .unwrap_or(1);
+        }
+
+        let mut column = vec![0; dense.nodes.len()];
+        for (position, index) in sparse.inputs.iter().enumerate() {
+            column[*index] = position;
+        }
+        let mut values = input.to_string();
+        let mut width = sparse.inputs.len();
+        let deepest = depth.iter().copied().max().unwrap_or(0);
+        for level in 1..=deepest {
+            // (activation, order positions) of the neurons at this depth
+            let mut groups: Vec<(Activation, Vec<usize>)> = Vec::new();
+            for (i, index) in sparse.order.iter().enumerate() {
+                if depth[*index] == level {
+                    let activation = sparse.activations[*index];
+                    match groups.iter_mut().find(|(group, _)| *group == activation) {
+                        Some((_, members)) => members.push(i),
+                        None => groups.push((activation, vec![i]))
+                    }
+                }
+            }
+            let mut results = Vec::with_capacity(groups.len());
+            let mut added = 0;
+            for (activation, members) in groups.iter() {
+                let mut weights = vec![0.0; width * members.len()];
+                for (j, i) in members.iter().enumerate() {
+                    let (start, end) = (sparse.offsets[*i], sparse.offsets[*i + 1]);
+                    for (src, weight) in sparse.sources[start..end].iter().zip(sparse.weights[start..end].iter()) {
+                        if feeds(*src) {
+                            weights[column[*src] * members.len() + j] += weight;
+                        }
+                    }
+                }
+                let bias = members.iter().map(|i| sparse.bias[sparse.order[*i]]).collect();
+                let weights = self.constant(&[width, members.len()], weights);
+                let bias = self.constant(&[members.len()], bias);
+                let product = self.op("MatMul", &[&values, &weights], Vec::new());
+                let state = self.op("Add", &[&product, &bias], Vec::new());
+                results.push(self.activate(&state, *activation));
+                for i in members.iter() {
+                    column[sparse.order[*i]] = width + added;
+                    added += 1;
+                }
+            }
+            let mut parts = vec![values.as_str()];
+            parts.extend(results.iter().map(|result| result.as_str()));
+            values = self.concat(&parts);
+            width += added;
+        }
+
+        let outputs = self.indices(sparse.outputs.iter().map(|index| column[*index] as i64).collect());
+        let gathered = self.op("Gather", &[&values, &outputs], vec![int("axis", 1)]);
+        Ok(match dense.activation {
+            Activation::Softmax => self.op("Softmax", &[&gathered], vec![int("axis", 1)]),
+            _ => gathered
+        })
+    }
+
+
+    /// one step of an LSTM, memory = memory * forget + state * input and hidden = output * tanh(memory)
+    fn lstm(&mut self, lstm: &LSTM, input: &str, layer: usize) -> Result<String, NeatError> {
+        let size = lstm.memory_size as usize;
+        let (hidden, memory) = (format!("state_{}_hidden", layer), format!("state_{}_memory", layer));
+        self.input(&hidden, size);
+        self.input(&memory, size);
+        let hidden_input = self.concat(&[&hidden, input]);
+        let forget = self.dense(&*lstm.f_gate.read().map_err(|_| NeatError::Poisoned)?, &hidden_input)?;
+        let update = self.dense(&*lstm.i_gate.read().map_err(|_| NeatError::Poisoned)?, &hidden_input)?;
+        let output = self.dense(&*lstm.o_gate.read().map_err(|_| NeatError::Poisoned)?, &hidden_input)?;
+        let state = self.dense(&*lstm.g_gate.read().map_err(|_| NeatError::Poisoned)?, &hidden_input)?;
+
+        let kept = self.op("Mul", &[&memory, &forget], Vec::new());
+        let added = self.op("Mul", &[&state, &update], Vec::new());
+        let (hidden_out, memory_out) = (format!("{}_out", hidden), format!("{}_out", memory));
+        self.node("Add", &[&kept, &added], &memory_out, Vec::new());
+        let squashed = self.op("Tanh", &[&memory_out], Vec::new());
+        self.node("Mul", &[&output, &squashed], &hidden_out, Vec::new());
+        self.output(&hidden_out, size);
+        self.output(&memory_out, size);
+        self.dense(&*lstm.v_gate.read().map_err(|_| NeatError::Poisoned)?, &hidden_out)
+    }
+
+
+    /// one step of a GRU, memory = memory * forget + extract * (1 - forget) and the
+    /// output comes from the last output, the input, and the new memory
+    fn gru(&mut self, gru: &GRU, input: &str, layer: usize) -> Result<String, NeatError> {
+        let (output, memory) = (format!("state_{}_output", layer), format!("state_{}_memory", layer));
+        self.input(&output, gru.output_size as usize);
+        self.input(&memory, gru.memory_size as usize);
+        let network_input = self.concat(&[&output, input, &memory]);
+        let forget = self.dense(&gru.f_gate, &network_input)?;
+        let extract = self.dense(&gru.e_gate, &network_input)?;
+
+        let one = self.scalar(1.0);
+        let inverted = self.op("Sub", &[&one, &forget], Vec::new());
+        let kept = self.op("Mul", &[&memory, &forget], Vec::new());
+        let added = self.op("Mul", &[&extract, &inverted], Vec::new());
+        let (output_out, memory_out) = (format!("{}_out", output), format!("{}_out", memory));
+        self.node("Add", &[&kept, &added], &memory_out, Vec::new());
+        let output_input = self.concat(&[&output, input, &memory_out]);
+        let result = self.dense(&gru.o_gate, &output_input)?;
+        self.node("Identity", &[&result], &output_out, Vec::new());
+        self.output(&output_out, gru.output_size as usize);
+        self.output(&memory_out, gru.memory_size as usize);
+        Ok(output_out)
+    }
+
+
+    /// batch norm's running statistics fold into one scale and shift, layer
+    /// norm's statistics are computed from each sample
+    fn norm(&mut self, norm: &Norm, input: &str) -> String {
+        let size = norm.size;
+        match norm.norm_type {
+            NormType::Batch => {
+                let scale = norm.running_variance.iter()
+                    .zip(norm.gamma.iter())
+                    .map(|(variance, gamma)| gamma / (variance + norm.epsilon).sqrt())
+                    .collect::<Vec<_>>();
+                let shift = norm.beta.iter()
+                    .zip(norm.running_mean.iter().zip(scale.iter()))
+                    .map(|(beta, (mean, scale))| beta - mean * scale)
+                    .collect();
+                let scale = self.constant(&[size], scale);
+                let shift = self.constant(&[size], shift);
+                let scaled = self.op("Mul", &[input, &scale], Vec::new());
+                self.op("Add", &[&scaled, &shift], Vec::new())
+            },
+            NormType::Layer => {
+                let reduce = || vec![ints("axes", vec![1]), int("keepdims", 1)];
+                let mean = self.op("ReduceMean", &[input], reduce());
+                let centered = self.op("Sub", &[input, &mean], Vec::new());
+                let squared = self.op("Mul", &[&centered, &centered], Vec::new());
+                let variance = self.op("ReduceMean", &[&squared], reduce());
+                let epsilon = self.scalar(norm.epsilon);
+                let shifted = self.op("Add", &[&variance, &epsilon], Vec::new());
+                let deviation = self.op("Sqrt", &[&shifted], Vec::new());
+                let normalized = self.op("Div", &[&centered, &deviation], Vec::new());
+                let gamma = self.constant(&[size], norm.gamma.clone());
+                let beta = self.constant(&[size], norm.beta.clone());
+                let scaled = self.op("Mul", &[&normalized, &gamma], Vec::new());
+                self.op("Add", &[&scaled, &beta], Vec::new())
+            }
+        }
+    }
+}
+
+
+
+fn float(name: &str, f: f32) -> AttributeProto {
+    AttributeProto { name: name.to_string(), f, r#type: ATTRIBUTE_FLOAT, ..Default::default() }
+}
+
+
+fn int(name: &str, i: i64) -> AttributeProto {
+    AttributeProto { name: name.to_string(), i, r#type: ATTRIBUTE_INT, ..Default::default() }
+}
+
+
+fn ints(name: &str, ints: Vec<i64>) -> AttributeProto {
+    AttributeProto { name: name.to_string(), ints, r#type: ATTRIBUTE_INTS, ..Default::default() }
+}
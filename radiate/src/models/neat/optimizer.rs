@@ -0,0 +1,115 @@
+/// How the gradient of a weight found by backpropagation is turned into a change to the weight.
+/// Sgd takes the gradient scaled by the learning rate as is, the others keep a little state for
+/// each weight (held by the layer the weight is in) to smooth or rescale the steps over time.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy, Default)]
+pub enum Optimizer {
+    #[default]
+    Sgd,
+    /// keep moving in the direction the weight has been moving, beta is how much of the
+    /// last step carries over to the next one
+    Momentum { beta: f32 },
+    /// divide each step by a running average of the size of the weight's recent gradients
+    /// so weights with large gradients don't take huge steps and small ones aren't stuck
+    RmsProp { decay: f32, epsilon: f32 },
+    /// momentum and rmsprop together, with both averages corrected for starting at zero
+    Adam { beta1: f32, beta2: f32, epsilon: f32 }
+}
+
+
+
+/// The optimizer's state for each weight and bias of a layer, and how many steps it has taken.
+/// It's saved with the layer so training picks back up with the same moments (see TrainingSession)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimizerState {
+    pub weights: Vec<f32>,
+    pub biases: Vec<f32>,
+    pub steps: i32
+}
+
+
+
+impl Optimizer {
+
+    /// momentum with the usual beta of 0.9
+    pub fn momentum() -> Self {
+        Optimizer::Momentum { beta: 0.9 }
+    }
+
+
+    /// rmsprop with the usual decay of 0.9
+    pub fn rms_prop() -> Self {
+        Optimizer::RmsProp { decay: 0.9, epsilon: 1e-8 }
+    }
+
+
+    /// adam with the betas from the paper, 0.9 and 0.999
+    pub fn adam() -> Self {
+        Optimizer::Adam { beta1: 0.9, beta2: 0.999, epsilon: 1e-8 }
+    }
+
+
+    /// the number of values kept for each weight
+    pub fn state_size(&self) -> usize {
+        match self {
+            Optimizer::Sgd => 0,
+            Optimizer::Momentum { .. } | Optimizer::RmsProp { .. } => 1,
+            Optimizer::Adam { .. } => 2
+        }
+    }
+
+
+    /// the change to make to a weight given its gradient, state is the weight's own values
+    /// and steps is how many steps the layer has taken including this one
+    #[inline]
+    pub fn step(&self, gradient: f32, rate: f32, state: &mut [f32], steps: i32) -> f32 {
+        match self {
+            Optimizer::Sgd => rate * gradient,
+            Optimizer::Momentum { beta } => {
+                state[0] = beta * state[0] + gradient;
+                rate * state[0]
+            },
+            Optimizer::RmsProp { decay, epsilon } => {
+                state[0] = decay * state[0] + (1.0 - decay) * gradient * gradient;
+                rate * gradient / (state[0].sqrt() + epsilon)
+            },
+            Optimizer::Adam { beta1, beta2, epsilon } => {
+                state[0] = beta1 * state[0] + (1.0 - beta1) * gradient;
+                state[1] = beta2 * state[1] + (1.0 - beta2) * gradient * gradient;
+                let mean = state[0] / (1.0 - beta1.powi(steps.max(1)));
+                let variance = state[1] / (1.0 - beta2.powi(steps.max(1)));
+                rate * mean / (variance.sqrt() + epsilon)
+            }
+        }
+    }
+}
+
+
+
+impl OptimizerState {
+
+    /// the change to make to the weight of the edge at index
+    #[inline]
+    pub fn step_weight(&mut self, optimizer: &Optimizer, index: usize, gradient: f32, rate: f32) -> f32 {
+        OptimizerState::step_in(&mut self.weights, optimizer, index, gradient, rate, self.steps)
+    }
+
+
+    /// the change to make to the bias of the neuron at index
+    #[inline]
+    pub fn step_bias(&mut self, optimizer: &Optimizer, index: usize, gradient: f32, rate: f32) -> f32 {
+        OptimizerState::step_in(&mut self.biases, optimizer, index, gradient, rate, self.steps)
+    }
+
+
+    /// the state grows as it's needed because layers gain edges and neurons as they evolve
+    #[inline]
+    fn step_in(values: &mut Vec<f32>, optimizer: &Optimizer, index: usize, gradient: f32, rate: f32, steps: i32) -> f32 {
+        let size = optimizer.state_size();
+        let (start, end) = (index * size, (index + 1) * size);
+        if values.len() < end {
+            values.resize(end, 0.0);
+        }
+        optimizer.step(gradient, rate, &mut values[start..end], steps)
+    }
+}
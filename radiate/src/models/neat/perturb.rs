@@ -0,0 +1,122 @@
+use rand::Rng;
+
+use super::neatenv::NeatEnvironment;
+use crate::error::NeatError;
+use crate::rng;
+
+
+
+/// How each weight and bias of a layer picked by weight_mutate_rate is changed, with the two
+/// kinds of change tuned apart. Perturbing nudges a weight which is close to right, replacing
+/// it throws away a weight which isn't, so a run which has converged wants a high perturb_rate
+/// with a small perturb_std and a low replace_rate.
+///
+/// replace_rate - the probability of a weight being given a new value uniform in
+///     [-replace_range, replace_range), 1 by default the same as a new connection
+/// perturb_rate - the probability of a weight which isn't replaced having gaussian noise with
+///     a standard deviation of perturb_std added to it. Weights neither replaced nor perturbed
+///     are left alone
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+pub struct WeightMutation {
+    pub perturb_rate: f32,
+    pub perturb_std: f32,
+    pub replace_rate: f32,
+    #[serde(default = "WeightMutation::default_replace_range")]
+    pub replace_range: f32
+}
+
+
+
+/// How one crossover edits weights, read out of the environment once rather than per weight.
+/// Scale is the single edit networks have always had (see NeatEnvironment::edit_weights and
+/// weight_perturb), Split a WeightMutation, and either is clamped to the environment's weight_clamp
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct WeightEdit {
+    mode: Mode,
+    clamp: Option<(f32, f32)>
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Scale { editable: f32, size: f32 },
+    Split(WeightMutation)
+}
+
+
+
+impl WeightMutation {
+
+    pub fn new(perturb_rate: f32, perturb_std: f32, replace_rate: f32) -> Self {
+        WeightMutation {
+            perturb_rate,
+            perturb_std,
+            replace_rate,
+            replace_range: Self::default_replace_range()
+        }
+    }
+
+
+    pub fn replace_range(mut self, range: f32) -> Self {
+        self.replace_range = range;
+        self
+    }
+
+
+    /// the weight after being replaced, perturbed, or left alone
+    pub fn mutate<R: Rng + ?Sized>(&self, r: &mut R, weight: f32) -> f32 {
+        if rng::chance(r, self.replace_rate) {
+            rng::uniform(r, -self.replace_range, self.replace_range)
+        } else if rng::chance(r, self.perturb_rate) {
+            weight + rng::normal(r, 0.0, self.perturb_std)
+        } else {
+            weight
+        }
+    }
+
+
+    fn default_replace_range() -> f32 {
+        1.0
+    }
+}
+
+
+
+impl WeightEdit {
+
+    /// the environment's weight_mutation if it has one, otherwise its edit_weights and weight_perturb
+    pub(crate) fn from_env(set: &NeatEnvironment) -> Result<Self, NeatError> {
+        let mode = match set.weight_mutation {
+            Some(mutation) => Mode::Split(mutation),
+            None => Mode::Scale {
+                editable: set.edit_weights.ok_or(NeatError::MissingSetting("edit_weights"))?,
+                size: set.weight_perturb.ok_or(NeatError::MissingSetting("weight_perturb"))?
+            }
+        };
+        Ok(WeightEdit { mode, clamp: set.weight_clamp })
+    }
+
+
+    /// the single edit, unclamped, see Mutation::EditWeights
+    pub(crate) fn scale(editable: f32, size: f32) -> Self {
+        WeightEdit { mode: Mode::Scale { editable, size }, clamp: None }
+    }
+
+
+    pub(crate) fn apply<R: Rng + ?Sized>(&self, r: &mut R, weight: f32) -> f32 {
+        let weight = match self.mode {
+            Mode::Scale { editable, size } => {
+                if rng::chance(r, editable) {
+                    rng::weight(r)
+                } else {
+                    weight * rng::uniform(r, -size, size)
+                }
+            },
+            Mode::Split(mutation) => mutation.mutate(r, weight)
+        };
+        match self.clamp {
+            Some((low, high)) => weight.clamp(low, high),
+            None => weight
+        }
+    }
+}
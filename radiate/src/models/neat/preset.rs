@@ -0,0 +1,121 @@
+//! Starting points for the common kinds of problems NEAT gets used for. Each preset is a
+//! NeatEnvironment with the rates and activations which have worked well for that kind of
+//! problem, and a population Config with matching speciation settings. The input and output
+//! sizes are left for the problem to fill in, and anything else can be overridden as usual -
+//!
+//! let env = preset::control().set_input_size(4).set_output_size(1).set_new_node_rate(0.05);
+//! let pop = Population::new().constrain(env).configure(preset::control_config());
+
+use super::{
+    neatenv::NeatEnvironment,
+    activation::Activation
+};
+use crate::engine::population::Config;
+
+
+
+/// Control problems (ie: balancing, steering) are usually solved by small networks with a 
+/// handful of inputs. Weights are perturbed often and structure is added slowly, a few 
+/// recurrent neurons let the network keep track of velocities it can't observe directly.
+pub fn control() -> NeatEnvironment {
+    NeatEnvironment::new()
+        .set_weight_mutate_rate(0.8)
+        .set_edit_weights(0.1)
+        .set_weight_perturb(1.5)
+        .set_new_node_rate(0.03)
+        .set_new_edge_rate(0.05)
+        .set_recurrent_neuron_rate(0.1)
+        .set_reactivate(0.25)
+        .set_activation_functions(vec![Activation::Sigmoid, Activation::Tanh])
+}
+
+
+pub fn control_config() -> Config {
+    Config {
+        inbreed_rate: 0.001,
+        crossover_rate: 0.75,
+        distance: 0.5,
+        species_target: 5
+    }
+}
+
+
+
+/// Classification networks are feed forward and can need a lot more hidden structure than
+/// a controller, so nodes are added a bit faster and hidden neurons can also be relus. 
+/// More species are kept around so different decision boundaries have time to develop.
+pub fn classification() -> NeatEnvironment {
+    NeatEnvironment::new()
+        .set_weight_mutate_rate(0.8)
+        .set_edit_weights(0.1)
+        .set_weight_perturb(1.0)
+        .set_new_node_rate(0.05)
+        .set_new_edge_rate(0.05)
+        .set_recurrent_neuron_rate(0.0)
+        .set_reactivate(0.2)
+        .set_activation_functions(vec![Activation::Sigmoid, Activation::Relu, Activation::Tanh])
+}
+
+
+pub fn classification_config() -> Config {
+    Config {
+        inbreed_rate: 0.001,
+        crossover_rate: 0.75,
+        distance: 0.5,
+        species_target: 8
+    }
+}
+
+
+
+/// Time series need memory, so recurrent neurons are added far more often than in the other
+/// presets and hidden neurons are tanh which keeps recurrent values from saturating. Weights
+/// are perturbed by less because small changes to a recurrent weight compound over each step.
+pub fn timeseries() -> NeatEnvironment {
+    NeatEnvironment::new()
+        .set_weight_mutate_rate(0.8)
+        .set_edit_weights(0.15)
+        .set_weight_perturb(0.5)
+        .set_new_node_rate(0.04)
+        .set_new_edge_rate(0.05)
+        .set_recurrent_neuron_rate(0.3)
+        .set_reactivate(0.2)
+        .set_activation_functions(vec![Activation::Tanh, Activation::Sigmoid])
+}
+
+
+pub fn timeseries_config() -> Config {
+    Config {
+        inbreed_rate: 0.001,
+        crossover_rate: 0.6,
+        distance: 0.6,
+        species_target: 6
+    }
+}
+
+
+
+/// CPPNs for HyperNEAT, see hyperneat::Substrate. The periodic and symmetric activations are what let
+/// a small CPPN draw regular patterns of weights across the substrate, and they start small so
+/// structure is added quickly and mostly through new activations rather than long chains of sigmoids.
+pub fn cppn() -> NeatEnvironment {
+    NeatEnvironment::new()
+        .set_weight_mutate_rate(0.8)
+        .set_edit_weights(0.1)
+        .set_weight_perturb(1.0)
+        .set_new_node_rate(0.06)
+        .set_new_edge_rate(0.06)
+        .set_recurrent_neuron_rate(0.0)
+        .set_reactivate(0.2)
+        .set_activation_functions(vec![Activation::Sine, Activation::Gaussian, Activation::Abs, Activation::Tanh, Activation::Sigmoid])
+}
+
+
+pub fn cppn_config() -> Config {
+    Config {
+        inbreed_rate: 0.001,
+        crossover_rate: 0.75,
+        distance: 0.5,
+        species_target: 6
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::time::Duration;
+
+use super::layers::layertype::LayerType;
+
+
+
+/// Time spent in and number of calls to a single layer
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LayerTiming {
+    pub forward_calls: u64,
+    pub forward_time: Duration,
+    pub backward_calls: u64,
+    pub backward_time: Duration
+}
+
+
+
+/// Opt in timing of each layer of a network. When enabled on a Neat model, every 
+/// forward and backward call through a layer is timed and counted by layer index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profiler {
+    pub layers: Vec<LayerTiming>
+}
+
+
+
+/// A snapshot of a profiler alongside the layer types it was recorded for
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileReport {
+    pub layers: Vec<(LayerType, LayerTiming)>
+}
+
+
+
+impl LayerTiming {
+
+    pub fn total_time(&self) -> Duration {
+        self.forward_time + self.backward_time
+    }
+}
+
+
+
+impl Profiler {
+
+    pub fn new() -> Self {
+        Profiler { layers: Vec::new() }
+    }
+
+
+    pub fn record_forward(&mut self, layer: usize, time: Duration) {
+        let timing = self.timing(layer);
+        timing.forward_calls += 1;
+        timing.forward_time += time;
+    }
+
+
+    pub fn record_backward(&mut self, layer: usize, time: Duration) {
+        let timing = self.timing(layer);
+        timing.backward_calls += 1;
+        timing.backward_time += time;
+    }
+
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+
+    fn timing(&mut self, layer: usize) -> &mut LayerTiming {
+        if self.layers.len() <= layer {
+            self.layers.resize(layer + 1, LayerTiming::default());
+        }
+        &mut self.layers[layer]
+    }
+}
+
+
+
+impl ProfileReport {
+
+    pub fn total_time(&self) -> Duration {
+        self.layers.iter().map(|(_, timing)| timing.total_time()).sum()
+    }
+
+
+    /// the fraction of the total time spent in each layer
+    pub fn shares(&self) -> Vec<f32> {
+        let total = self.total_time().as_secs_f32();
+        self.layers.iter()
+            .map(|(_, timing)| if total > 0.0 { timing.total_time().as_secs_f32() / total } else { 0.0 })
+            .collect()
+    }
+}
+
+
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<6} {:<10} {:>10} {:>14} {:>10} {:>14} {:>7}", "layer", "type", "fwd calls", "fwd time", "bwd calls", "bwd time", "share")?;
+        for (index, ((layer_type, timing), share)) in self.layers.iter().zip(self.shares()).enumerate() {
+            writeln!(f, "{:<6} {:<10} {:>10} {:>14} {:>10} {:>14} {:>6.1}%",
+                index,
+                format!("{:?}", layer_type),
+                timing.forward_calls,
+                format!("{:?}", timing.forward_time),
+                timing.backward_calls,
+                format!("{:?}", timing.backward_time),
+                share * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
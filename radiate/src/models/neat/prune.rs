@@ -0,0 +1,70 @@
+use std::fmt;
+
+use super::id::NeuronId;
+
+
+
+/// A hidden neuron whose activation barely moved over a dataset, like a relu which never
+/// fires or a sigmoid stuck at one end. value is its mean activation and spread the
+/// difference between the largest and smallest activation it had.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeadNeuron {
+    pub layer: usize,
+    pub neuron: NeuronId,
+    pub value: f32,
+    pub spread: f32
+}
+
+
+
+/// The dead hidden neurons found by running a dataset through a network, see Neat::dead_neurons.
+/// Only the hidden neurons of dense layers are checked, hidden is how many there were.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadNeuronReport {
+    pub samples: usize,
+    pub hidden: usize,
+    pub dead: Vec<DeadNeuron>
+}
+
+
+
+impl DeadNeuronReport {
+
+    /// the fraction of the hidden neurons checked which are dead
+    pub fn dead_fraction(&self) -> f32 {
+        if self.hidden == 0 {
+            return 0.0;
+        }
+        self.dead.len() as f32 / self.hidden as f32
+    }
+}
+
+
+
+impl fmt::Display for DeadNeuronReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} of {} hidden neurons dead over {} samples", self.dead.len(), self.hidden, self.samples)?;
+        writeln!(f, "{:<6} {:<8} {:>10} {:>10}", "layer", "neuron", "value", "spread")?;
+        for dead in self.dead.iter() {
+            writeln!(f, "{:<6} {:<8} {:>10.4} {:>10.6}", dead.layer, dead.neuron.index(), dead.value, dead.spread)?;
+        }
+        Ok(())
+    }
+}
+
+
+
+/// How many neurons and edges were taken out of a network by Neat::prune
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PruneReport {
+    pub nodes: usize,
+    pub edges: usize
+}
+
+
+
+impl fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pruned {} neurons and {} edges", self.nodes, self.edges)
+    }
+}
@@ -0,0 +1,162 @@
+extern crate serde_json;
+
+use std::fs::File;
+use std::cell::Cell;
+use std::error::Error;
+
+use super::{
+    neat::Neat,
+    loss::Loss,
+    migrate
+};
+use crate::data::{
+    dataset::DataSet,
+    scaler::Scaler
+};
+use crate::error::NeatError;
+
+
+
+/// The learning rate to use for a given epoch of training
+///
+/// Constant - the same rate every epoch
+/// StepDecay - multiply the rate by factor every `every` epochs
+/// Exponential - multiply the rate by decay every epoch
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+pub enum LearningRate {
+    Constant(f32),
+    StepDecay { initial: f32, factor: f32, every: usize },
+    Exponential { initial: f32, decay: f32 }
+}
+
+
+
+impl LearningRate {
+
+    pub fn rate(&self, epoch: usize) -> f32 {
+        match self {
+            Self::Constant(rate) => *rate,
+            Self::StepDecay { initial, factor, every } => {
+                initial * factor.powi((epoch / (*every).max(1)) as i32)
+            },
+            Self::Exponential { initial, decay } => initial * decay.powi(epoch as i32)
+        }
+    }
+}
+
+
+
+/// A training session holds everything needed to pick backprop training back up exactly where it
+/// left off - the model itself, where the learning rate schedule is, the scalers the data was
+/// normalized with and how many epochs have been run along with the loss of each one. The whole
+/// session is saved to and loaded from a single file, it is meant for long supervised training
+/// runs that might get interrupted, not for checkpointing an evolving population.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingSession {
+    #[serde(with = "migrate::versioned")]
+    pub model: Neat,
+    pub learning_rate: LearningRate,
+    pub loss: Loss,
+    pub input_scaler: Option<Scaler>,
+    pub target_scaler: Option<Scaler>,
+    pub epoch: usize,
+    pub history: Vec<f32>
+}
+
+
+
+impl TrainingSession {
+
+
+    pub fn new(model: Neat) -> Self {
+        TrainingSession {
+            model,
+            learning_rate: LearningRate::Constant(0.1),
+            loss: Loss::Diff,
+            input_scaler: None,
+            target_scaler: None,
+            epoch: 0,
+            history: Vec::new()
+        }
+    }
+
+
+    pub fn learning_rate(mut self, learning_rate: LearningRate) -> Self {
+        self.learning_rate = learning_rate;
+        self
+    }
+
+
+    pub fn loss(mut self, loss: Loss) -> Self {
+        self.loss = loss;
+        self
+    }
+
+
+    /// scale inputs with the given scaler before they are fed through the model
+    pub fn input_scaler(mut self, scaler: Scaler) -> Self {
+        self.input_scaler = Some(scaler);
+        self
+    }
+
+
+    /// train against scaled targets, predictions are scaled back before they are returned
+    pub fn target_scaler(mut self, scaler: Scaler) -> Self {
+        self.target_scaler = Some(scaler);
+        self
+    }
+
+
+    /// Run epochs more epochs of training over the data, continuing the learning rate 
+    /// schedule and epoch count from wherever the session is. Returns the loss of the last epoch
+    pub fn train(&mut self, data: &DataSet, epochs: usize) -> Result<f32, Box<dyn Error>> {
+        let inputs = match &self.input_scaler {
+            Some(scaler) => scaler.transform_all(&data.inputs),
+            None => data.inputs.clone()
+        };
+        let targets = match &self.target_scaler {
+            Some(scaler) => scaler.transform_all(&data.targets),
+            None => data.targets.clone()
+        };
+        let mut last_loss = 0.0;
+        for _ in 0..epochs {
+            let epoch_loss = Cell::new(0.0);
+            let rate = self.learning_rate.rate(self.epoch);
+            self.model.train(&inputs, &targets, rate, self.loss, |_, loss| {
+                epoch_loss.set(loss);
+                true
+            })?;
+            last_loss = epoch_loss.get();
+            self.history.push(last_loss);
+            self.epoch += 1;
+        }
+        Ok(last_loss)
+    }
+
+
+    /// feed an input through the model applying the session's scalers
+    pub fn predict(&mut self, input: &[f32]) -> Result<Vec<f32>, NeatError> {
+        let input = match &self.input_scaler {
+            Some(scaler) => scaler.transform(input),
+            None => input.to_vec()
+        };
+        let output = self.model.forward(&input)?;
+        match &self.target_scaler {
+            Some(scaler) => Ok(scaler.inverse_transform(&output)),
+            None => Ok(output)
+        }
+    }
+
+
+    /// save the whole session to a json file
+    pub fn save(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&File::create(file_path)?, &self)?;
+        Ok(())
+    }
+
+
+    /// load a session saved with save
+    pub fn load(file_path: &str) -> Result<TrainingSession, Box<dyn Error>> {
+        Ok(serde_json::from_reader(File::open(file_path)?)?)
+    }
+}
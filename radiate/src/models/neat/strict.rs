@@ -0,0 +1,135 @@
+//! The transcendental functions the activation functions need, computed with nothing but the basic
+//! arithmetic IEEE 754 requires to be correctly rounded. The standard library's exp, tanh and sin call
+//! into the platform's math library, which is free to round differently (and does, between glibc, musl,
+//! the msvc runtime and Apple's libm), so the same network can give different bits on x86 and ARM.
+//! These are worked out in f64 from range reductions and Taylor series and rounded once to f32, which
+//! keeps them within an ulp of the exact result while giving the same bits everywhere. Rust never fuses
+//! a multiply and an add unless asked to (mul_add), so nothing here can be contracted into an FMA either.
+//! See Precision::Strict
+
+use std::f64::consts::{LN_2, LOG2_E, FRAC_2_PI};
+
+
+/// pi / 2 split in two so k * pi / 2 can be taken off an argument without losing its low bits,
+/// the high part has few enough bits that k * PI_2_HI is exact for any k that matters
+const PI_2_HI: f64 = 1.570_796_326_734_125_6;
+const PI_2_LO: f64 = 6.077_100_506_506_192e-11;
+
+
+
+/// e^x
+pub fn exp(x: f32) -> f32 {
+    exp64(x as f64) as f32
+}
+
+
+/// e^x - 1
+pub fn exp_m1(x: f32) -> f32 {
+    exp_m1_64(x as f64) as f32
+}
+
+
+/// the hyperbolic tangent of x
+pub fn tanh(x: f32) -> f32 {
+    tanh64(x as f64) as f32
+}
+
+
+/// 1 / (1 + e^(-scale * x)), the sigmoid of the activation module is scaled by 4.9. The scaling
+/// is rounded to f32 the same as Exact so the two only differ by how e^x is worked out
+pub fn sigmoid(x: f32, scale: f32) -> f32 {
+    (1.0 / (1.0 + exp64((-x * scale) as f64))) as f32
+}
+
+
+/// the sine of x
+pub fn sin(x: f32) -> f32 {
+    let (quadrant, r) = reduce(x as f64);
+    let sin = match quadrant {
+        0 => sin_series(r),
+        1 => cos_series(r),
+        2 => -sin_series(r),
+        _ => -cos_series(r)
+    };
+    sin as f32
+}
+
+
+/// the cosine of x
+pub fn cos(x: f32) -> f32 {
+    let (quadrant, r) = reduce(x as f64);
+    let cos = match quadrant {
+        0 => cos_series(r),
+        1 => -sin_series(r),
+        2 => -cos_series(r),
+        _ => sin_series(r)
+    };
+    cos as f32
+}
+
+
+
+/// e^x = 2^k * e^r where k is x / ln 2 to the nearest integer, leaving |r| <= ln 2 / 2. Anything
+/// outside of [-110, 100] is 0 or infinite once it's rounded to f32 so it's clamped first
+fn exp64(x: f64) -> f64 {
+    let x = x.clamp(-110.0, 100.0);
+    let k = (x * LOG2_E).round();
+    let r = x - k * LN_2;
+    let mut sum = 1.0;
+    for n in (1..=13).rev() {
+        sum = 1.0 + r * sum / n as f64;
+    }
+    sum * f64::from_bits(((k as i64 + 1023) as u64) << 52)
+}
+
+
+/// e^x - 1 without the cancellation of taking 1 from e^x near zero
+fn exp_m1_64(x: f64) -> f64 {
+    if x.abs() > LN_2 / 2.0 {
+        return exp64(x) - 1.0;
+    }
+    let mut sum = 1.0;
+    for n in (2..=14).rev() {
+        sum = 1.0 + x * sum / n as f64;
+    }
+    x * sum
+}
+
+
+/// tanh(x) = (e^2x - 1) / (e^2x + 1), which is +/-1 in f32 well before |x| = 20
+fn tanh64(x: f64) -> f64 {
+    if x.abs() > 20.0 {
+        return x.signum();
+    }
+    let em1 = exp_m1_64(2.0 * x);
+    em1 / (em1 + 2.0)
+}
+
+
+/// take the nearest multiple k of pi / 2 off x, returning which quadrant (k mod 4) the
+/// angle is in and what's left, |r| <= pi / 4
+fn reduce(x: f64) -> (i64, f64) {
+    let k = (x * FRAC_2_PI).round();
+    let r = (x - k * PI_2_HI) - k * PI_2_LO;
+    ((k as i64).rem_euclid(4), r)
+}
+
+
+fn sin_series(r: f64) -> f64 {
+    let r2 = r * r;
+    let mut sum = 1.0;
+    for n in (1..=8).rev() {
+        sum = 1.0 - r2 * sum / ((2 * n) * (2 * n + 1)) as f64;
+    }
+    r * sum
+}
+
+
+fn cos_series(r: f64) -> f64 {
+    let r2 = r * r;
+    let mut sum = 1.0;
+    for n in (1..=8).rev() {
+        sum = 1.0 - r2 * sum / ((2 * n - 1) * (2 * n)) as f64;
+    }
+    sum
+}
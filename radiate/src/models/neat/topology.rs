@@ -0,0 +1,20 @@
+use super::activation::Activation;
+
+
+
+/// How the connections of a new dense pool are laid out before evolution starts changing them.
+/// The starting structure has a big say in what evolution can find - a sparse start leaves
+/// evolution to pick which inputs matter, a layered start gives it depth to work with from
+/// the first generation instead of waiting for nodes to be split in one at a time.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+pub enum Topology {
+    /// every input connected to every output, the classic minimal NEAT start
+    #[default]
+    FullyConnected,
+    /// each input connected to each output with the given probability, an output 
+    /// which doesn't get any connections is given one to a random input
+    Sparse(f32),
+    /// layers of hidden neurons of the given sizes between the inputs and outputs,
+    /// each fully connected to the next
+    Layered { hidden: Vec<u32>, activation: Activation }
+}
@@ -1,5 +1,5 @@
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use super::id::*;
 
@@ -9,8 +9,8 @@ use super::id::*;
 /// is available for batch processing and weight updates
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Tracer {
-    pub neuron_activation: HashMap<NeuronId, Vec<f32>>,
-    pub neuron_derivative: HashMap<NeuronId, Vec<f32>>,
+    pub neuron_activation: BTreeMap<NeuronId, Vec<f32>>,
+    pub neuron_derivative: BTreeMap<NeuronId, Vec<f32>>,
     pub max_neuron_index: usize,
     pub index: usize,
 }
@@ -21,8 +21,8 @@ impl Tracer {
 
     pub fn new() -> Self {
         Tracer {
-            neuron_activation: HashMap::new(),
-            neuron_derivative: HashMap::new(),        
+            neuron_activation: BTreeMap::new(),
+            neuron_derivative: BTreeMap::new(),        
             max_neuron_index: 0,
             index: 0,
         }
@@ -33,13 +33,27 @@ impl Tracer {
     /// reset the tracer. The backprop works off of indexed values so when the
     /// layer is reset, the tracer must be reset as well
     pub fn reset(&mut self) {
-        self.neuron_activation = HashMap::new();
-        self.neuron_derivative = HashMap::new();
+        self.neuron_activation = BTreeMap::new();
+        self.neuron_derivative = BTreeMap::new();
         self.index = 0;        
     }
 
 
 
+    /// drop all but the last n time steps, backprop can then step back through at most n
+    pub fn keep_last(&mut self, n: usize) {
+        if self.index <= n {
+            return;
+        }
+        let dropped = self.index - n;
+        for states in self.neuron_activation.values_mut().chain(self.neuron_derivative.values_mut()) {
+            states.drain(..dropped.min(states.len()));
+        }
+        self.index = n;
+    }
+
+
+
     /// update a neuron and add it's activated value 𝜎(Σ(w * i) + b)
     pub fn update_neuron_activation(&mut self, neuron_id: &NeuronId, neuron_value: f32) {
         if self.neuron_activation.contains_key(&neuron_id) {
@@ -84,6 +98,17 @@ impl Tracer {
 
 
 
+    /// return the activated value of a neuron at the index before the current one, or 0 if
+    /// the current index is the first
+    pub fn previous_activation(&self, neuron_id: NeuronId) -> f32 {
+        match self.index {
+            0 | 1 => 0.0,
+            index => self.neuron_activation.get(&neuron_id).and_then(|states| states.get(index - 2)).copied().unwrap_or(0.0)
+        }
+    }
+
+
+
     /// return the derivative of a neuron at the current index 
     pub fn neuron_derivative(&self, neuron_id: NeuronId) -> f32 {
         if !self.neuron_derivative.contains_key(&neuron_id) {
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::layers::layertype::LayerType;
+use super::edge::Edge;
+use super::neuron::Neuron;
+use super::optimizer::OptimizerState;
+
+
+
+/// weights with a magnitude under this are counted as near zero
+pub const NEAR_ZERO: f32 = 1e-3;
+
+/// the number of buckets in a weight histogram
+pub const BUCKETS: usize = 10;
+
+
+
+/// Summary of the weights of the active connections of a layer. A layer whose weights are mostly
+/// near zero is doing little, one whose weights are spread very wide is likely to saturate its
+/// neurons. The histogram splits [min, max] into evenly sized buckets, it is empty with no weights.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WeightStats {
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub std: f32,
+    pub near_zero: f32,
+    pub histogram: Vec<usize>
+}
+
+
+
+/// The weight stats of every layer of a network alongside the layer types
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightReport {
+    pub layers: Vec<(LayerType, WeightStats)>
+}
+
+
+
+/// The learnable state of a layer taken by Layer::snapshot, everything training and scoring change
+/// about it so Layer::restore can put the layer back exactly as it was. A dense layer's edges
+/// and neurons are shared with the layer until it changes them, so taking a snapshot copies next to nothing
+#[derive(Debug, Clone)]
+pub enum WeightSnapshot {
+    Dense {
+        edges: Arc<Vec<Edge>>,
+        nodes: Arc<Vec<Neuron>>,
+        optimizer_state: OptimizerState
+    },
+    Gates(Vec<WeightSnapshot>)
+}
+
+
+
+/// The snapshot of every layer of a network, None for layers without weights, see Neat::snapshot_weights
+#[derive(Debug, Clone)]
+pub struct NetworkSnapshot {
+    pub layers: Vec<Option<WeightSnapshot>>
+}
+
+
+
+impl WeightStats {
+
+    pub fn new(weights: &[f32]) -> Self {
+        if weights.is_empty() {
+            return WeightStats::default();
+        }
+        let count = weights.len() as f32;
+        let min = weights.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = weights.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let mean = weights.iter().sum::<f32>() / count;
+        let variance = weights.iter().map(|w| (w - mean).powi(2)).sum::<f32>() / count;
+        let near_zero = weights.iter().filter(|w| w.abs() < NEAR_ZERO).count() as f32 / count;
+
+        let mut histogram = vec![0; BUCKETS];
+        let width = (max - min) / BUCKETS as f32;
+        for weight in weights.iter() {
+            let bucket = if width > 0.0 { ((weight - min) / width) as usize } else { 0 };
+            histogram[bucket.min(BUCKETS - 1)] += 1;
+        }
+
+        WeightStats {
+            count: weights.len(),
+            min,
+            max,
+            mean,
+            std: variance.sqrt(),
+            near_zero,
+            histogram
+        }
+    }
+}
+
+
+
+impl fmt::Display for WeightReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<6} {:<10} {:>7} {:>9} {:>9} {:>9} {:>9} {:>6}  histogram", "layer", "type", "weights", "min", "max", "mean", "std", "~zero")?;
+        for (index, (layer_type, stats)) in self.layers.iter().enumerate() {
+            writeln!(f, "{:<6} {:<10} {:>7} {:>9.4} {:>9.4} {:>9.4} {:>9.4} {:>5.1}%  {:?}",
+                index,
+                format!("{:?}", layer_type),
+                stats.count,
+                stats.min,
+                stats.max,
+                stats.mean,
+                stats.std,
+                stats.near_zero * 100.0,
+                stats.histogram
+            )?;
+        }
+        Ok(())
+    }
+}
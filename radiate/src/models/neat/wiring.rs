@@ -0,0 +1,114 @@
+//! The wiring cost of a dense layer, how much connection it takes to lay the layer out in space.
+//! Networks evolved to minimize it along with their error come out modular, with neurons wired
+//! to the neurons near them instead of to everything (Clune, Mouret & Lipson 2013). The inputs
+//! and outputs are given fixed positions, the hidden neurons don't have any of their own so each
+//! is placed where its connections are the shortest they can be before the lengths are added up.
+//! The cost can be given to a population as an objective of its own (negated, every objective is
+//! maximized) or subtracted from the fitness as a penalty.
+
+use super::id::NeuronId;
+use super::neurontype::NeuronType;
+use super::layers::dense::Dense;
+use crate::error::NeatError;
+
+
+
+/// The positions of the inputs and outputs of a dense layer, each a point with the same number of
+/// coordinates. Hidden neurons are placed at the mean position of the neurons they are connected
+/// to, which is worked out over iterations passes as the neurons they connect to may be hidden too.
+/// This is where the sum of their squared connection lengths is the smallest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Placement {
+    pub inputs: Vec<Vec<f32>>,
+    pub outputs: Vec<Vec<f32>>,
+    pub iterations: usize
+}
+
+
+
+impl Placement {
+
+    pub fn new(inputs: Vec<Vec<f32>>, outputs: Vec<Vec<f32>>) -> Self {
+        Placement {
+            inputs,
+            outputs,
+            iterations: 20
+        }
+    }
+
+
+    /// the inputs spread evenly along the line y = 0 and the outputs along y = 1, both from x = -1 to 1
+    pub fn line(inputs: usize, outputs: usize) -> Self {
+        let spread = |count: usize, y: f32| (0..count)
+            .map(|index| match count {
+                1 => vec![0.0, y],
+                _ => vec![-1.0 + 2.0 * index as f32 / (count - 1) as f32, y]
+            })
+            .collect();
+        Placement::new(spread(inputs, 0.0), spread(outputs, 1.0))
+    }
+
+
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+
+    /// the position of every neuron of the layer, in the same order as its nodes
+    pub fn positions(&self, dense: &Dense) -> Result<Vec<Vec<f32>>, NeatError> {
+        let dimensions = self.inputs.first().or(self.outputs.first()).map_or(0, |point| point.len());
+        if self.inputs.len() != dense.inputs.len() || self.outputs.len() != dense.outputs.len()
+            || self.inputs.iter().chain(self.outputs.iter()).any(|point| point.len() != dimensions) {
+            return Err(NeatError::PlacementMismatch);
+        }
+        // hidden neurons start in the middle of the inputs and outputs
+        let fixed = self.inputs.len() + self.outputs.len();
+        let center = (0..dimensions)
+            .map(|axis| self.inputs.iter().chain(self.outputs.iter()).map(|point| point[axis]).sum::<f32>() / fixed.max(1) as f32)
+            .collect::<Vec<_>>();
+        let mut positions = vec![center; dense.nodes.len()];
+        for (id, point) in dense.inputs.iter().zip(self.inputs.iter()).chain(dense.outputs.iter().zip(self.outputs.iter())) {
+            *positions.get_mut(id.index()).ok_or(NeatError::InvalidGraph)? = point.clone();
+        }
+        let hidden = dense.nodes.iter()
+            .filter(|node| node.neuron_type == NeuronType::Hidden)
+            .map(|node| {
+                let neighbours = dense.edges.iter()
+                    .filter(|edge| edge.active && edge.src != edge.dst)
+                    .filter_map(|edge| match (edge.src == node.id, edge.dst == node.id) {
+                        (true, _) => Some(edge.dst),
+                        (_, true) => Some(edge.src),
+                        _ => None
+                    })
+                    .collect::<Vec<NeuronId>>();
+                (node.id.index(), neighbours)
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..self.iterations {
+            for (index, neighbours) in hidden.iter().filter(|(_, neighbours)| !neighbours.is_empty()) {
+                let mut mean = vec![0.0; dimensions];
+                for neighbour in neighbours.iter() {
+                    let point = positions.get(neighbour.index()).ok_or(NeatError::InvalidGraph)?;
+                    mean.iter_mut().zip(point.iter()).for_each(|(sum, value)| *sum += value / neighbours.len() as f32);
+                }
+                positions[*index] = mean;
+            }
+        }
+        Ok(positions)
+    }
+
+
+    /// the summed length of the layer's active connections with its neurons at their positions
+    pub fn wiring_cost(&self, dense: &Dense) -> Result<f32, NeatError> {
+        let positions = self.positions(dense)?;
+        dense.edges.iter()
+            .filter(|edge| edge.active)
+            .map(|edge| {
+                let src = positions.get(edge.src.index()).ok_or(NeatError::InvalidGraph)?;
+                let dst = positions.get(edge.dst.index()).ok_or(NeatError::InvalidGraph)?;
+                Ok(src.iter().zip(dst.iter()).map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt())
+            })
+            .sum()
+    }
+}
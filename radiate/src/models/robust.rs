@@ -0,0 +1,127 @@
+//! Test-time augmentation, scoring a network on augmented copies of its inputs as well as the
+//! inputs themselves. A network selected on clean data alone can lean on exact input values and
+//! fall apart under sensor noise or a change of scale, scoring it on the copies and selecting on
+//! its mean or worst case performance evolves networks which hold up instead, see Supervised::robust.
+
+use std::fmt;
+
+use crate::data::augment::{Augment, Augmentation};
+use crate::rng;
+
+
+
+/// How the losses on the inputs and their copies are boiled down to one
+///
+/// Mean - the mean loss over every input and every copy of it
+/// Worst - the loss of the worst of each input and its copies, averaged over the inputs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Aggregate {
+    Mean,
+    Worst
+}
+
+
+
+/// copies augmented copies of a dataset's inputs, each made by applying every augmentation to the
+/// input in order. The copies are drawn from a stream keyed by seed so every network is scored on
+/// the same ones, otherwise selection would reward the luck of the draw as much as robustness
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Robustness {
+    pub augmentations: Vec<Augmentation>,
+    pub copies: usize,
+    pub aggregate: Aggregate,
+    pub seed: u64
+}
+
+
+
+/// The loss of a network on a dataset's inputs alone (clean) and with their augmented copies
+/// aggregated both ways, see Aggregate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RobustnessReport {
+    pub samples: usize,
+    pub copies: usize,
+    pub clean: f32,
+    pub mean: f32,
+    pub worst: f32
+}
+
+
+
+impl Robustness {
+
+    pub fn new(copies: usize) -> Self {
+        Robustness {
+            augmentations: Vec::new(),
+            copies,
+            aggregate: Aggregate::Mean,
+            seed: 0
+        }
+    }
+
+
+    pub fn augment(mut self, augmentation: Augmentation) -> Self {
+        self.augmentations.push(augmentation);
+        self
+    }
+
+
+    pub fn aggregate(mut self, aggregate: Aggregate) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+
+    /// every copy of the inputs, copies[i][j] is the ith copy of inputs[j]. Each copy is kept
+    /// in the order of the inputs so recurrent networks can be run over it the same way
+    pub fn copies(&self, inputs: &[Vec<f32>]) -> Vec<Vec<Vec<f32>>> {
+        rng::scoped(self.seed, || {
+            (0..self.copies)
+                .map(|_| inputs.iter().map(|input| self.augmentations.augment(input)).collect())
+                .collect()
+        })
+    }
+}
+
+
+
+impl RobustnessReport {
+
+    /// the report of the losses of each input on its own (clean[j]) and of each copy of it (copies[i][j])
+    pub fn new(clean: &[f32], copies: &[Vec<f32>]) -> Self {
+        let samples = clean.len().max(1) as f32;
+        let passes = (copies.len() + 1) as f32;
+        RobustnessReport {
+            samples: clean.len(),
+            copies: copies.len(),
+            clean: clean.iter().sum::<f32>() / samples,
+            mean: (clean.iter().sum::<f32>() + copies.iter().flatten().sum::<f32>()) / (samples * passes),
+            worst: clean.iter()
+                .enumerate()
+                .map(|(index, loss)| copies.iter().map(|copy| copy[index]).fold(*loss, f32::max))
+                .sum::<f32>() / samples
+        }
+    }
+
+
+    pub fn loss(&self, aggregate: Aggregate) -> f32 {
+        match aggregate {
+            Aggregate::Mean => self.mean,
+            Aggregate::Worst => self.worst
+        }
+    }
+}
+
+
+
+impl fmt::Display for RobustnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} samples with {} copies each, loss clean {:.6} mean {:.6} worst {:.6}", self.samples, self.copies, self.clean, self.mean, self.worst)
+    }
+}
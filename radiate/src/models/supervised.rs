@@ -0,0 +1,249 @@
+use std::fmt;
+
+use super::neat::{
+    neat::Neat,
+    compiled::CompiledNeat,
+    loss::Loss
+};
+use super::robust::{Robustness, RobustnessReport};
+use crate::engine::problem::Problem;
+use crate::engine::learning::{Learning, Inheritance};
+use crate::data::dataset::DataSet;
+use crate::error::NeatError;
+
+
+
+/// Anything which can be fed a sample and give back an output for each target, which
+/// is all a Supervised problem needs of the networks it is scoring
+pub trait Network {
+
+    fn predict(&mut self, inputs: &[f32]) -> Result<Vec<f32>, NeatError>;
+
+    /// clear any state carried from one sample to the next, done before and after each pass over the data
+    fn reset(&mut self) { }
+}
+
+
+
+/// A supervised problem - networks are scored on how closely they reproduce the targets of a
+/// dataset under a loss, which is all most supervised users need to bridge their data into the
+/// engine. The fitness is 1 / (1 + mean loss) so it is always positive and a perfect network
+/// scores 1.0, a network which fails to feed forward scores f32::MIN. With robustness set the
+/// mean loss is the one aggregated over the inputs and their augmented copies, see Supervised::robust
+#[derive(Debug, Clone)]
+pub struct Supervised {
+    pub data: DataSet,
+    pub loss: Loss,
+    pub robustness: Option<Robustness>
+}
+
+
+
+/// How closely a network reproduces one target column of a dataset
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TargetMetrics {
+    pub mse: f32,
+    pub mae: f32,
+    pub max_error: f32,
+    /// the fraction of the target's variance the network explains, 1.0 is a perfect fit and
+    /// anything under 0.0 is worse than always guessing the mean. NaN for a constant target
+    pub r2: f32
+}
+
+
+
+/// The mean loss of a network over a dataset along with metrics for each target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupervisedReport {
+    pub samples: usize,
+    pub loss: f32,
+    pub targets: Vec<TargetMetrics>
+}
+
+
+
+impl Supervised {
+
+    pub fn new(data: DataSet, loss: Loss) -> Self {
+        Supervised { data, loss, robustness: None }
+    }
+
+
+    /// score networks on augmented copies of the inputs as well as the inputs themselves, so
+    /// the fitness selected on is the aggregated loss over the copies instead of the clean one
+    pub fn robust(mut self, robustness: Robustness) -> Self {
+        self.robustness = Some(robustness);
+        self
+    }
+
+
+    /// Learning for Population::learning which trains each network on the dataset with the
+    /// problem's loss for a few epochs of mini-batched backprop (see Neat::fit). A network
+    /// which can't be trained is scored as it is. Baldwinian networks keep a snapshot of their
+    /// weights to restore once they're scored (see Neat::snapshot_weights) rather than training a copy
+    pub fn fine_tune(&self, epochs: usize, batch_size: usize, rate: f32, inheritance: Inheritance) -> Learning<Neat> {
+        let data = self.data.inputs.iter().cloned().zip(self.data.targets.iter().cloned()).collect::<Vec<_>>();
+        let loss = self.loss;
+        Learning::new(inheritance, move |model: &mut Neat| {
+            model.fit(&data, batch_size, epochs, rate, loss).ok();
+        })
+        .undo(|model: &Neat| {
+            let snapshot = model.snapshot_weights()?;
+            // training only changes weights so the snapshot always fits the network it came from
+            Some(Box::new(move |model: &mut Neat| { model.restore_weights(&snapshot).ok(); }))
+        })
+    }
+
+
+    /// the outputs of the network for every sample of the dataset, in order
+    pub fn predict<N: Network>(&self, model: &mut N) -> Result<Vec<Vec<f32>>, NeatError> {
+        Self::run(model, &self.data.inputs)
+    }
+
+
+    /// The loss of the network on the inputs of the dataset and on each copy of them, every copy
+    /// is a pass over the whole dataset of its own. The robustness given is used rather than the
+    /// problem's own, so a network can be checked against perturbations it wasn't selected on
+    pub fn robustness_report<N: Network>(&self, model: &mut N, robustness: &Robustness) -> Result<RobustnessReport, NeatError> {
+        let clean = self.sample_losses(&self.predict(model)?);
+        let copies = robustness.copies(&self.data.inputs).iter()
+            .map(|inputs| Ok(self.sample_losses(&Self::run(model, inputs)?)))
+            .collect::<Result<Vec<_>, NeatError>>()?;
+        Ok(RobustnessReport::new(&clean, &copies))
+    }
+
+
+    /// the mean loss of the network over the dataset, aggregated over the copies of the
+    /// inputs as well if the problem has robustness set
+    pub fn loss<N: Network>(&self, model: &mut N) -> Result<f32, NeatError> {
+        match &self.robustness {
+            Some(robustness) => Ok(self.robustness_report(model, robustness)?.loss(robustness.aggregate)),
+            None => {
+                let outputs = self.predict(model)?;
+                Ok(self.mean_loss(&outputs))
+            }
+        }
+    }
+
+
+    /// score the network on every target of the dataset
+    pub fn report<N: Network>(&self, model: &mut N) -> Result<SupervisedReport, NeatError> {
+        let outputs = self.predict(model)?;
+        let columns = self.data.targets.first().map(|targets| targets.len()).unwrap_or(0);
+        let targets = (0..columns)
+            .map(|column| {
+                let pairs = self.data.targets.iter()
+                    .zip(outputs.iter())
+                    .map(|(targets, outputs)| (targets[column], outputs[column]))
+                    .collect::<Vec<_>>();
+                TargetMetrics::new(&pairs)
+            })
+            .collect();
+        Ok(SupervisedReport {
+            samples: self.data.len(),
+            loss: self.mean_loss(&outputs),
+            targets
+        })
+    }
+
+
+    fn run<N: Network>(model: &mut N, inputs: &[Vec<f32>]) -> Result<Vec<Vec<f32>>, NeatError> {
+        model.reset();
+        let outputs = inputs.iter()
+            .map(|inputs| model.predict(inputs))
+            .collect::<Result<Vec<_>, _>>();
+        model.reset();
+        outputs
+    }
+
+
+    fn mean_loss(&self, outputs: &[Vec<f32>]) -> f32 {
+        self.sample_losses(outputs).iter().sum::<f32>() / self.data.len().max(1) as f32
+    }
+
+
+    /// the loss of each sample, infinite for an output of the wrong size
+    fn sample_losses(&self, outputs: &[Vec<f32>]) -> Vec<f32> {
+        self.data.targets.iter()
+            .zip(outputs.iter())
+            .map(|(targets, outputs)| {
+                NeatError::check_input(targets.len(), outputs.len()).map(|_| self.loss.loss(targets, outputs).0)
+            })
+            .map(|loss| loss.unwrap_or(f32::INFINITY))
+            .collect()
+    }
+}
+
+
+
+impl<N: Network> Problem<N> for Supervised {
+
+    fn empty() -> Self {
+        Supervised::new(DataSet::new(Vec::new(), Vec::new()), Loss::MSE)
+    }
+
+    fn solve(&self, model: &mut N) -> f32 {
+        match self.loss(model) {
+            Ok(loss) => 1.0 / (1.0 + loss),
+            Err(_) => f32::MIN
+        }
+    }
+}
+
+
+
+impl TargetMetrics {
+
+    /// metrics from (target, output) pairs
+    pub fn new(pairs: &[(f32, f32)]) -> Self {
+        let n = pairs.len().max(1) as f32;
+        let mean = pairs.iter().map(|(target, _)| target).sum::<f32>() / n;
+        let squared = pairs.iter().map(|(target, output)| (target - output).powi(2)).sum::<f32>();
+        let variance = pairs.iter().map(|(target, _)| (target - mean).powi(2)).sum::<f32>();
+        TargetMetrics {
+            mse: squared / n,
+            mae: pairs.iter().map(|(target, output)| (target - output).abs()).sum::<f32>() / n,
+            max_error: pairs.iter().fold(0.0, |max, (target, output)| f32::max(max, (target - output).abs())),
+            r2: if variance > 0.0 { 1.0 - squared / variance } else { f32::NAN }
+        }
+    }
+}
+
+
+
+impl Network for Neat {
+
+    fn predict(&mut self, inputs: &[f32]) -> Result<Vec<f32>, NeatError> {
+        self.forward(&inputs.to_vec())
+    }
+
+    fn reset(&mut self) {
+        Neat::reset(self);
+    }
+}
+
+
+
+impl Network for CompiledNeat {
+
+    fn predict(&mut self, inputs: &[f32]) -> Result<Vec<f32>, NeatError> {
+        self.forward(inputs)
+    }
+
+    fn reset(&mut self) {
+        CompiledNeat::reset(self);
+    }
+}
+
+
+
+impl fmt::Display for SupervisedReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} samples, mean loss {:.6}", self.samples, self.loss)?;
+        writeln!(f, "{:<7} {:>10} {:>10} {:>10} {:>8}", "target", "mse", "mae", "max", "r2")?;
+        for (index, metrics) in self.targets.iter().enumerate() {
+            writeln!(f, "{:<7} {:>10.6} {:>10.6} {:>10.6} {:>8.4}", index, metrics.mse, metrics.mae, metrics.max_error, metrics.r2)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+//! Fitness terms for networks, each one is a Fitness with a single term to be composed
+//! with the rest, ie: terms::accuracy(data).minus(terms::complexity(0.001))
+
+use super::{
+    neat::{neat::Neat, loss::Loss},
+    supervised::{Network, Supervised},
+    robust::Robustness
+};
+use crate::engine::fitness::Fitness;
+use crate::engine::problem::Problem;
+use crate::data::dataset::{DataSet, class_label};
+
+
+
+/// the fraction of samples whose output is labeled the same class as the target, see
+/// dataset::class_label. A network which fails to feed forward scores 0
+pub fn accuracy<N: Network + 'static>(data: DataSet) -> Fitness<N> {
+    Fitness::term("accuracy", move |model: &mut N| {
+        model.reset();
+        let correct = data.inputs.iter()
+            .zip(data.targets.iter())
+            .map(|(inputs, targets)| model.predict(inputs).map(|outputs| class_label(&outputs) == class_label(targets)))
+            .collect::<Result<Vec<_>, _>>();
+        model.reset();
+        match correct {
+            Ok(correct) => correct.iter().filter(|hit| **hit).count() as f32 / data.len().max(1) as f32,
+            Err(_) => 0.0
+        }
+    })
+}
+
+
+
+/// 1 / (1 + mean loss) over the data, the fitness of a Supervised problem
+pub fn fit<N: Network + 'static>(data: DataSet, loss: Loss) -> Fitness<N> {
+    let problem = Supervised::new(data, loss);
+    Fitness::term("fit", move |model: &mut N| problem.solve(model))
+}
+
+
+
+/// 1 / (1 + loss) aggregated over the data and augmented copies of it, see Supervised::robust
+pub fn robust<N: Network + 'static>(data: DataSet, loss: Loss, robustness: Robustness) -> Fitness<N> {
+    let problem = Supervised::new(data, loss).robust(robustness);
+    Fitness::term("robust", move |model: &mut N| problem.solve(model))
+}
+
+
+
+/// weight for every active connection of the network, meant to be subtracted
+pub fn complexity(weight: f32) -> Fitness<Neat> {
+    Fitness::term("complexity", move |model: &mut Neat| {
+        weight * model.weight_report().layers.iter().map(|(_, stats)| stats.count).sum::<usize>() as f32
+    })
+}
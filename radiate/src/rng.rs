@@ -0,0 +1,237 @@
+//! Every random number radiate draws goes through this module. Each thread has its own
+//! stream which is seeded from entropy until seed is called, after which every thread's
+//! stream is reseeded from that seed and the thread's index in the rayon pool the next time
+//! it is used, so the same seed gives the same streams. The sampling functions are all f32 and
+//! take the rng as an argument so they work the same on a thread stream or a seeded StdRng.
+//! Rayon hands work out to its threads differently every run, so anything run in parallel
+//! which draws random numbers is wrapped in scoped with a key drawn up front on the calling
+//! thread, giving each piece of work the same stream no matter which thread picks it up.
+
+extern crate rand;
+extern crate uuid;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicU64, Ordering};
+use rand::{Rng, RngCore, SeedableRng};
+use rand::rngs::StdRng;
+use uuid::{Uuid, Builder, Variant, Version};
+
+
+
+/// the seed set by seed, and how many times it has been set so streams know when to reseed
+static SEED: AtomicU64 = AtomicU64::new(0);
+static EPOCH: AtomicU64 = AtomicU64::new(0);
+
+/// epoch of a stream swapped in by scoped, which must not be reseeded while it is in use
+const PINNED: u64 = u64::MAX;
+
+
+thread_local! {
+    static STREAM: Rc<RefCell<(u64, StdRng)>> = Rc::new(RefCell::new((0, StdRng::from_entropy())));
+}
+
+
+
+/// Seed every thread's stream. Streams only reseed when they are next used, and each
+/// rayon worker gets its own stream derived from the seed so they don't repeat each other
+pub fn seed(seed: u64) {
+    SEED.store(seed, Ordering::SeqCst);
+    EPOCH.fetch_add(1, Ordering::SeqCst);
+}
+
+
+
+/// get a handle to the current thread's stream
+pub fn thread() -> Stream {
+    Stream { inner: STREAM.with(Rc::clone) }
+}
+
+
+
+/// run f with the thread's stream replaced by one seeded from key, the thread's own stream
+/// is put back after. The scoped stream isn't reseeded by seed, so work keyed from a stream
+/// of its own gives the same numbers even if something else seeds the thread streams meanwhile.
+pub fn scoped<T, F: FnOnce() -> T>(key: u64, f: F) -> T {
+    let stream = STREAM.with(Rc::clone);
+    let previous = stream.replace((PINNED, StdRng::seed_from_u64(key)));
+    let _restore = Restore { stream, previous: Some(previous) };
+    f()
+}
+
+
+
+/// draw a key for each of n pieces of work to be run under scoped
+pub fn keys(n: usize) -> Vec<u64> {
+    let mut r = thread();
+    (0..n).map(|_| r.next_u64()).collect()
+}
+
+
+
+/// puts a thread's own stream back when scoped finishes, even if the work panicked
+struct Restore {
+    stream: Rc<RefCell<(u64, StdRng)>>,
+    previous: Option<(u64, StdRng)>
+}
+
+
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            self.stream.replace(previous);
+        }
+    }
+}
+
+
+
+/// a random (version 4) uuid drawn from the given rng rather than the os, so ids
+/// like innovation numbers come out the same in a seeded run
+#[inline]
+pub fn uuid<R: Rng + ?Sized>(r: &mut R) -> Uuid {
+    Builder::from_bytes(r.gen::<[u8; 16]>())
+        .set_variant(Variant::RFC4122)
+        .set_version(Version::Random)
+        .build()
+}
+
+
+
+/// A handle to a thread's random stream, like rand's ThreadRng it is cheap to get
+/// and can't be sent to another thread
+#[derive(Clone, Debug)]
+pub struct Stream {
+    inner: Rc<RefCell<(u64, StdRng)>>
+}
+
+
+
+impl Stream {
+
+    #[inline]
+    fn with<F: FnOnce(&mut StdRng) -> T, T>(&mut self, f: F) -> T {
+        let mut inner = self.inner.borrow_mut();
+        let epoch = EPOCH.load(Ordering::Relaxed);
+        if inner.0 != epoch && inner.0 != PINNED {
+            let index = rayon::current_thread_index().map_or(0, |i| i as u64 + 1);
+            *inner = (epoch, StdRng::seed_from_u64(mix(SEED.load(Ordering::Relaxed), index)));
+        }
+        f(&mut inner.1)
+    }
+}
+
+
+
+impl RngCore for Stream {
+
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.with(|r| r.next_u32())
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.with(|r| r.next_u64())
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.with(|r| r.fill_bytes(dest))
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.with(|r| r.try_fill_bytes(dest))
+    }
+}
+
+
+
+/// The distributions weights can be drawn from. Uniform is flat over [low, high), normal is
+/// a gaussian and cauchy has the same bell shape with much heavier tails, which every so often
+/// gives a big jump while mostly staying close to the location.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+pub enum Distribution {
+    Uniform { low: f32, high: f32 },
+    Normal { mean: f32, std: f32 },
+    Cauchy { location: f32, scale: f32 }
+}
+
+
+
+impl Distribution {
+
+    #[inline]
+    pub fn sample<R: Rng + ?Sized>(&self, r: &mut R) -> f32 {
+        match self {
+            Distribution::Uniform { low, high } => uniform(r, *low, *high),
+            Distribution::Normal { mean, std } => normal(r, *mean, *std),
+            Distribution::Cauchy { location, scale } => cauchy(r, *location, *scale)
+        }
+    }
+}
+
+
+
+/// uniform sample in [low, high)
+#[inline]
+pub fn uniform<R: Rng + ?Sized>(r: &mut R, low: f32, high: f32) -> f32 {
+    low + (high - low) * r.gen::<f32>()
+}
+
+
+
+/// a new weight or bias, uniform in [-1, 1) so new connections are as likely
+/// to inhibit as to excite
+#[inline]
+pub fn weight<R: Rng + ?Sized>(r: &mut R) -> f32 {
+    uniform(r, -1.0, 1.0)
+}
+
+
+
+/// true with the given probability
+#[inline]
+pub fn chance<R: Rng + ?Sized>(r: &mut R, probability: f32) -> bool {
+    r.gen::<f32>() < probability
+}
+
+
+
+/// standard normal sample through the box-muller transform
+#[inline]
+pub fn standard_normal<R: Rng + ?Sized>(r: &mut R) -> f32 {
+    let u = r.gen::<f32>().max(f32::MIN_POSITIVE);
+    let v = r.gen::<f32>();
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+
+
+#[inline]
+pub fn normal<R: Rng + ?Sized>(r: &mut R, mean: f32, std: f32) -> f32 {
+    mean + std * standard_normal(r)
+}
+
+
+
+/// cauchy sample through the inverse cdf, the uniform is kept off 0 and 1 where tan blows up
+#[inline]
+pub fn cauchy<R: Rng + ?Sized>(r: &mut R, location: f32, scale: f32) -> f32 {
+    let u = r.gen::<f32>().clamp(f32::EPSILON, 1.0 - f32::EPSILON);
+    location + scale * (PI * (u - 0.5)).tan()
+}
+
+
+
+/// splitmix64 of the seed and stream index, so neighbouring indexes don't give neighbouring
+/// seeds. It's fixed arithmetic, so unlike std's hashers it gives the same bits on every toolchain
+pub fn mix(seed: u64, index: u64) -> u64 {
+    let mut z = seed.wrapping_add(index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,46 @@
+extern crate radiate;
+extern crate serde_json;
+
+use radiate::prelude::*;
+
+mod common;
+
+
+
+fn island(seed: u64) -> Population<Neat, NeatEnvironment, BenchmarkProblem> {
+    common::xor_population(20, BenchmarkProblem::new(Benchmark::Xor, 0))
+        .seed(seed)
+        .populate_base()
+}
+
+
+fn holds(population: &Population<Neat, NeatEnvironment, BenchmarkProblem>, target: &serde_json::Value) -> bool {
+    (0..population.get_size() as usize)
+        .filter_map(|i| population.member(i))
+        .any(|cont| serde_json::to_value(&*cont.member.read().unwrap()).unwrap() == *target)
+}
+
+
+#[test]
+fn test_fully_connected_migration() {
+    let mut archipelago = Archipelago::new((0..3).map(island).collect())
+        .migrate_every(2)
+        .topology(MigrationTopology::FullyConnected);
+    let (_, champion) = archipelago.train().unwrap();
+    assert_eq!(archipelago.get_generation(), 2);
+    assert!(archipelago.islands().iter().all(|island| island.get_generation() == 2));
+
+    // the best champion is on its own island as a survivor and was sent to every other island
+    let champion = serde_json::to_value(&champion).unwrap();
+    let holding = archipelago.islands().iter().filter(|island| holds(island, &champion)).count();
+    assert!(holding >= 2);
+}
+
+
+#[test]
+fn test_archipelago_run() {
+    let mut archipelago = Archipelago::new((0..2).map(island).collect()).migrate_every(3);
+    let (fitness, _) = archipelago.run(|_, _, generation| generation >= 9).unwrap();
+    assert_eq!(archipelago.get_generation(), 9);
+    assert!(fitness > 0.0);
+}
@@ -0,0 +1,214 @@
+extern crate radiate;
+extern crate serde_json;
+
+use std::sync::Arc;
+use radiate::prelude::*;
+
+mod common;
+
+
+
+fn population() -> Population<Neat, NeatEnvironment, BenchmarkProblem> {
+    common::xor_population(20, BenchmarkProblem::new(Benchmark::Xor, 0))
+}
+
+
+#[test]
+fn test_checkpoint_resumes() {
+    let path = std::env::temp_dir().join("radiate_checkpoint_test.json");
+    let path = path.to_str().unwrap();
+    let mut pop = population()
+        .populate_clone(Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid))
+        .checkpoint(path, 2);
+    for _ in 0..4 {
+        pop.train().unwrap();
+    }
+    assert_eq!(pop.get_generation(), 4);
+
+    let mut resumed = population().resume(path).expect("failed to resume");
+    std::fs::remove_file(path).ok();
+    let saved = pop.to_checkpoint();
+    let loaded = resumed.to_checkpoint();
+    assert_eq!(resumed.get_generation(), 4);
+    assert_eq!(serde_json::to_value(&loaded.members).unwrap(), serde_json::to_value(&saved.members).unwrap());
+    assert_eq!(loaded.species.iter().map(|s| s.niche_id).collect::<Vec<_>>(), saved.species.iter().map(|s| s.niche_id).collect::<Vec<_>>());
+
+    resumed.train().unwrap();
+    assert_eq!(resumed.get_generation(), 5);
+}
+
+
+#[test]
+fn test_suspend_between_evaluation_and_selection() {
+    let mut pop = population()
+        .populate_clone(Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid))
+        .seed(9);
+    pop.train().unwrap();
+    assert!(pop.to_checkpoint().scores.is_none());
+
+    // suspend once the generation is scored, before anything is selected from it
+    pop.evaluate();
+    assert!(pop.is_evaluated());
+    let checkpoint = pop.to_checkpoint();
+    let scores = checkpoint.scores.clone().expect("evaluated generation has no scores");
+    assert_eq!(scores.len(), checkpoint.members.len());
+    assert!(scores.iter().any(|score| score.fitness != 0.0));
+
+    let saved = serde_json::to_string(&checkpoint).unwrap();
+    let mut resumed = population().from_checkpoint(serde_json::from_str(&saved).unwrap());
+    assert!(resumed.is_evaluated());
+    assert_eq!(resumed.to_checkpoint().scores, Some(scores.clone()));
+
+    // the resumed population goes straight on to selection with the scores it was suspended with
+    let best = scores.iter().map(|score| score.fitness).fold(f32::MIN, f32::max);
+    let stats = resumed.step().unwrap();
+    assert_eq!(stats.generation, 1);
+    assert_eq!(stats.fitness, best);
+    assert!(!resumed.is_evaluated());
+    assert_eq!(pop.step().unwrap().fitness, best);
+}
+
+
+#[test]
+fn test_seeded_resume_matches_uninterrupted_run() {
+    // the members are bred from one network so they share the genes speciation lines up
+    let seeded = || {
+        let base = radiate::rng::scoped(17, || Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid));
+        let env = radiate::default_neat_env()
+            .set_input_size(2)
+            .set_output_size(1)
+            .set_recurrent_neuron_rate(0.0)
+            .set_innovation_counter(Arc::new(InnovationCounter::new(17)));
+        population()
+            .constrain(env)
+            .seed(17)
+            .populate_clone(base)
+    };
+    // the top fitness stops improving after generation 8, so the patience runs out
+    // two generations after the checkpoint and only if the resumed run remembers the first
+    let criteria = StoppingCriteria::new().patience(3);
+    let mut uninterrupted = seeded().stop_when(criteria.clone());
+    let mut stops = Vec::new();
+    for _ in 0..12 {
+        uninterrupted.train().unwrap();
+        stops.push(uninterrupted.stop_reason());
+    }
+    assert!(stops[..11].iter().all(Option::is_none));
+    assert_eq!(stops[11], Some(StopReason::Patience));
+
+    let mut stopped = seeded().stop_when(criteria);
+    for _ in 0..10 {
+        stopped.train().unwrap();
+    }
+    let saved = serde_json::to_string(&stopped.to_checkpoint()).unwrap();
+    drop(stopped);
+    // the criteria come back with the checkpoint as well
+    let mut resumed = seeded().from_checkpoint(serde_json::from_str(&saved).unwrap());
+    let mut resumed_stops = Vec::new();
+    for _ in 0..2 {
+        resumed.train().unwrap();
+        resumed_stops.push(resumed.stop_reason());
+    }
+    assert_eq!(resumed_stops, stops[10..]);
+
+    let expected = uninterrupted.to_checkpoint();
+    let actual = resumed.to_checkpoint();
+    assert_eq!(actual.generation, 12);
+    assert_eq!(serde_json::to_value(&actual.members).unwrap(), serde_json::to_value(&expected.members).unwrap());
+    assert_eq!(serde_json::to_value(&actual.environment).unwrap(), serde_json::to_value(&expected.environment).unwrap());
+    assert_eq!(resumed.history().best_fitness_curve(), uninterrupted.history().best_fitness_curve());
+}
+
+
+
+#[test]
+fn test_failed_checkpoint_is_kept() {
+    let path = std::env::temp_dir().join("radiate_missing_dir").join("checkpoint.json");
+    let path = path.to_str().unwrap();
+    let mut pop = population()
+        .populate_clone(Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid))
+        .checkpoint(path, 2);
+    assert!(pop.step().unwrap().write_failures.is_empty());
+    let stats = pop.step().unwrap();
+    assert_eq!(stats.write_failures.len(), 1);
+    assert_eq!(pop.write_failures(), &stats.write_failures[..]);
+    assert_eq!((stats.write_failures[0].generation, stats.write_failures[0].path.as_str()), (2, path));
+    assert!(!stats.write_failures[0].error.is_empty());
+    assert!(!pop.is_stopped());
+
+    // told to, the population stops on the first failure instead
+    let mut strict = population()
+        .populate_clone(Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid))
+        .checkpoint(path, 1)
+        .stop_on_write_failure(true);
+    strict.step().unwrap();
+    assert_eq!(strict.stop_reason(), Some(StopReason::WriteFailed));
+    assert_eq!(strict.write_failures().len(), 1);
+}
+
+
+
+#[test]
+fn test_delta_encoded_members() {
+    let mut pop = population()
+        .populate_clone(Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid))
+        .seed(4);
+    for _ in 0..5 {
+        pop.train().unwrap();
+    }
+    let checkpoint = pop.to_checkpoint();
+    let plain = serde_json::to_string(&checkpoint.members).unwrap();
+    let set = DeltaSet::encode(&checkpoint.members[0], &checkpoint.members).unwrap();
+    let encoded = serde_json::to_string(&set).unwrap();
+    assert!(encoded.len() * 2 < plain.len(), "{} encoded against {} plain", encoded.len(), plain.len());
+    let decoded = set.decode::<Neat>().unwrap();
+    assert_eq!(serde_json::to_string(&decoded).unwrap(), plain);
+
+    // checkpoints written before members were encoded still load
+    let mut old = serde_json::to_value(&checkpoint).unwrap();
+    assert!(old["members"].is_object());
+    old["members"] = serde_json::from_str(&plain).unwrap();
+    let resumed = population().from_checkpoint(serde_json::from_value(old).unwrap());
+    assert_eq!(serde_json::to_string(&resumed.to_checkpoint().members).unwrap(), serde_json::to_string(&checkpoint.members).unwrap());
+
+    // fields and items can be added, changed and dropped
+    let base = serde_json::json!({ "a": 1, "b": [1, 2, 3], "c": { "d": true } });
+    let value = serde_json::json!({ "a": 2, "b": [1, 5], "c": { "d": true }, "e": "new" });
+    let delta = compress::diff(&base, &value);
+    assert_eq!(compress::patch(&base, &delta), value);
+    assert_eq!(compress::diff(&value, &value), Delta::Same);
+    assert_eq!(compress::patch(&value, &compress::diff(&value, &base)), base);
+}
+
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_compressed_checkpoint() {
+    let path = std::env::temp_dir().join("radiate_checkpoint_test.json.zst");
+    let path = path.to_str().unwrap();
+    let mut pop = population()
+        .populate_clone(Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid))
+        .seed(6);
+    for _ in 0..3 {
+        pop.train().unwrap();
+    }
+    pop.save_checkpoint(path).unwrap();
+    let bytes = std::fs::read(path).unwrap();
+    assert!(compress::is_compressed(&bytes));
+    assert!(bytes.len() < serde_json::to_vec(&pop.to_checkpoint()).unwrap().len());
+
+    let resumed = population().resume(path).expect("failed to resume");
+    std::fs::remove_file(path).ok();
+    assert_eq!(resumed.get_generation(), 3);
+    assert_eq!(serde_json::to_string(&resumed.to_checkpoint().members).unwrap(), serde_json::to_string(&pop.to_checkpoint().members).unwrap());
+}
+
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_compressed_checkpoint_needs_zstd() {
+    let path = std::env::temp_dir().join("radiate_checkpoint_needs_zstd.json.zst");
+    let pop = population().populate_clone(Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid));
+    assert!(pop.save_checkpoint(path.to_str().unwrap()).is_err());
+    assert!(!path.exists());
+}
@@ -0,0 +1,21 @@
+//! What the integration tests share, a test file picks it up with `mod common;`
+
+use radiate::prelude::*;
+
+
+
+/// A population of size networks from 2 inputs to 1 output (the shape of xor) evolving against
+/// problem with the config the tests use. Nothing else is set and it isn't populated, so a test
+/// adds whatever else it needs (ie: a seed) before populating it
+pub fn xor_population<P: Problem<Neat>>(size: i32, problem: P) -> Population<Neat, NeatEnvironment, P> {
+    Population::<Neat, NeatEnvironment, P>::new()
+        .size(size)
+        .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(1))
+        .impose(problem)
+        .configure(Config {
+            inbreed_rate: 0.001,
+            crossover_rate: 0.75,
+            distance: 0.5,
+            species_target: 5
+        })
+}
@@ -0,0 +1,208 @@
+use radiate::prelude::*;
+
+mod common;
+
+
+const ENVIRONMENT: &str = r#"
+weight_mutate_rate = 0.8
+weight_perturb = 1.5
+new_node_rate = 0.03
+new_edge_rate = 0.04
+edit_weights = 0.1
+reactivate = 0.2
+input_size = 2
+output_size = 1
+activation_functions = ["Sigmoid", { LeakyRelu = 0.02 }]
+"#;
+
+
+const POPULATION: &str = r#"
+size = 50
+stagnation = 10
+genocide = [{ KillWorst = 0.9 }]
+
+[config]
+inbreed_rate = 0.001
+crossover_rate = 0.75
+distance = 0.5
+species_target = 5
+"#;
+
+
+#[test]
+fn test_environment_from_toml() {
+    let env = NeatEnvironment::from_toml_str(ENVIRONMENT).unwrap();
+    assert_eq!(env.new_node_rate, Some(0.03));
+    assert_eq!(env.recurrent_neuron_rate, Some(0.0));
+    assert_eq!(env.activation_functions, vec![Activation::Sigmoid, Activation::LeakyRelu(0.02)]);
+
+    let typo = ENVIRONMENT.replace("new_edge_rate", "new_edge_rte");
+    assert_eq!(NeatEnvironment::from_toml_str(&typo).unwrap_err(), ConfigError::UnknownKey(String::from("new_edge_rte")));
+
+    let missing = ENVIRONMENT.replace("reactivate = 0.2", "");
+    assert_eq!(NeatEnvironment::from_toml_str(&missing).unwrap_err().key(), Some("reactivate"));
+
+    let invalid = ENVIRONMENT.replace("new_node_rate = 0.03", "new_node_rate = 3.0");
+    assert_eq!(NeatEnvironment::from_toml_str(&invalid).unwrap_err().key(), Some("new_node_rate"));
+
+    assert_eq!((env.remove_node_rate, env.remove_edge_rate), (None, None));
+    let shrinking = NeatEnvironment::from_toml_str(&format!("remove_edge_rate = 0.05\n{}", ENVIRONMENT)).unwrap();
+    assert_eq!(shrinking.remove_edge_rate, Some(0.05));
+    assert!(!env.recurrent_edges);
+    let recurrent = NeatEnvironment::from_toml_str(&format!("recurrent_edges = true\n{}", ENVIRONMENT)).unwrap();
+    assert!(recurrent.recurrent_edges);
+    assert_eq!(env.bias, None);
+    assert_eq!(env.activation_mutate_rate, None);
+    let swapping = NeatEnvironment::from_toml_str(&format!("activation_mutate_rate = 0.05\n{}", ENVIRONMENT)).unwrap();
+    assert_eq!(swapping.activation_mutate_rate, Some(0.05));
+    let invalid = NeatEnvironment::from_toml_str(&format!("activation_mutate_rate = 2.0\n{}", ENVIRONMENT));
+    assert_eq!(invalid.unwrap_err().key(), Some("activation_mutate_rate"));
+    let unbiased = NeatEnvironment::from_toml_str(&format!("bias = \"Off\"\n{}", ENVIRONMENT)).unwrap();
+    assert_eq!(unbiased.bias, Some(Bias::Off));
+    assert_eq!((env.weight_mutation, env.weight_clamp), (None, None));
+    let split = ENVIRONMENT.replace("weight_perturb = 1.5", "weight_clamp = [-4.0, 4.0]").replace("edit_weights = 0.1", "");
+    let split = NeatEnvironment::from_toml_str(&format!("{}\n[weight_mutation]\nperturb_rate = 0.9\nperturb_std = 0.1\nreplace_rate = 0.05\n", split)).unwrap();
+    assert_eq!(split.weight_mutation, Some(WeightMutation::new(0.9, 0.1, 0.05)));
+    assert_eq!(split.weight_clamp, Some((-4.0, 4.0)));
+    let missing = NeatEnvironment::from_toml_str(&ENVIRONMENT.replace("weight_perturb = 1.5", ""));
+    assert_eq!(missing.unwrap_err().key(), Some("weight_perturb"));
+    let invalid = NeatEnvironment::from_toml_str(&format!("{}\n[weight_mutation]\nperturb_rate = 0.9\nperturb_std = 0.0\nreplace_rate = 0.05\n", ENVIRONMENT));
+    assert_eq!(invalid.unwrap_err().key(), Some("weight_mutation.perturb_std"));
+    let invalid = NeatEnvironment::from_toml_str(&format!("weight_clamp = [1.0, -1.0]\n{}", ENVIRONMENT));
+    assert_eq!(invalid.unwrap_err().key(), Some("weight_clamp"));
+    let invalid = NeatEnvironment::from_toml_str(&format!("remove_node_rate = 1.5\n{}", ENVIRONMENT));
+    assert_eq!(invalid.unwrap_err().key(), Some("remove_node_rate"));
+
+    assert_eq!(env.species_aging, None);
+    let aging = NeatEnvironment::from_toml_str(&format!("{}\n[species_aging]\ndropoff_age = 20\n", ENVIRONMENT)).unwrap();
+    assert_eq!(aging.species_aging, Some(SpeciesAging::new().dropoff(20, 0.01)));
+    let typo = NeatEnvironment::from_toml_str(&format!("{}\n[species_aging]\nyoung_boots = 1.5\n", ENVIRONMENT));
+    assert_eq!(typo.unwrap_err().key(), Some("species_aging.young_boots"));
+    let penalty = NeatEnvironment::from_toml_str(&format!("{}\n[species_aging]\ndropoff_penalty = 0.0\n", ENVIRONMENT));
+    assert_eq!(penalty.unwrap_err().key(), Some("species_aging"));
+
+    assert_eq!(env.schedule, None);
+    let schedule = NeatEnvironment::from_toml_str(&format!("{}\n[schedule]\nnew_node_rate = {{ Linear = {{ start = 0.1, end = 0.01, generations = 50 }} }}\n", ENVIRONMENT)).unwrap();
+    assert_eq!(schedule.schedule.as_deref(), Some(&MutationSchedule::new().new_node_rate(RateSchedule::Linear { start: 0.1, end: 0.01, generations: 50 })));
+    let rate = NeatEnvironment::from_toml_str(&format!("{}\n[schedule]\nreactivate = {{ Constant = 1.5 }}\n", ENVIRONMENT));
+    assert_eq!(rate.unwrap_err().key(), Some("schedule.reactivate"));
+    let adaptive = NeatEnvironment::from_toml_str(&format!("{}\n[self_adaptive]\nlearning_rate = 0.1\n", ENVIRONMENT)).unwrap();
+    assert_eq!(adaptive.self_adaptive, Some(SelfAdaptation::new(0.1)));
+    let bounds = NeatEnvironment::from_toml_str(&format!("{}\n[self_adaptive]\nmin_rate = 0.5\nmax_rate = 0.2\n", ENVIRONMENT));
+    assert_eq!(bounds.unwrap_err().key(), Some("self_adaptive"));
+}
+
+
+#[test]
+fn test_population_config_from_toml() {
+    let settings = PopulationConfig::from_toml_str(POPULATION).unwrap();
+    assert_eq!(settings.size, 50);
+    assert_eq!(settings.stagnation, 10);
+    assert_eq!(settings.config.species_target, 5);
+    assert_eq!(settings.distance_step, 0.1);
+    assert!(settings.parallel_eval);
+    assert!(!PopulationConfig::from_toml_str(&format!("parallel_eval = false\n{}", POPULATION)).unwrap().parallel_eval);
+
+    let typo = POPULATION.replace("distance", "distnace");
+    assert_eq!(PopulationConfig::from_toml_str(&typo).unwrap_err().key(), Some("config.distnace"));
+
+    let invalid = POPULATION.replace("crossover_rate = 0.75", "crossover_rate = -0.75");
+    assert_eq!(PopulationConfig::from_toml_str(&invalid).unwrap_err().key(), Some("config.crossover_rate"));
+
+    let step = format!("distance_step = -0.2\n{}", POPULATION);
+    assert_eq!(PopulationConfig::from_toml_str(&step).unwrap_err().key(), Some("distance_step"));
+
+    assert!(settings.stopping.is_empty());
+    let stopping = PopulationConfig::from_toml_str(&format!("{}\n[stopping]\npatience = 20\ntarget = 3.9\n", POPULATION)).unwrap();
+    assert_eq!(stopping.stopping, StoppingCriteria::new().patience(20).target(3.9));
+    let impatient = format!("{}\n[stopping]\npatience = 0\n", POPULATION);
+    assert_eq!(PopulationConfig::from_toml_str(&impatient).unwrap_err().key(), Some("stopping.patience"));
+
+    assert_eq!(format!("{:?}", settings.selection), "RouletteWheel");
+    let tournament = PopulationConfig::from_toml_str(&format!("{}\n[selection]\ntype = \"Tournament\"\nsize = 3\n", POPULATION)).unwrap();
+    assert_eq!(format!("{:?}", tournament.selection), "Tournament { size: 3 }");
+
+    assert_eq!(settings.species_stagnation, None);
+    let policy = PopulationConfig::from_toml_str(&format!("{}\n[species_stagnation]\nmax_stagnation = 15\n", POPULATION)).unwrap();
+    assert_eq!(policy.species_stagnation, Some(StagnationPolicy::new(15)));
+    let missing = PopulationConfig::from_toml_str(&format!("{}\n[species_stagnation]\nprotect = 2\n", POPULATION));
+    assert_eq!(missing.unwrap_err().key(), Some("species_stagnation.max_stagnation"));
+
+    assert_eq!(settings.mutation_boost, None);
+    let boost = PopulationConfig::from_toml_str(&format!("{}\n[mutation_boost]\nafter = 5\ncap = 3.0\n", POPULATION)).unwrap();
+    assert_eq!(boost.mutation_boost, Some(MutationBoost::new(5).cap(3.0)));
+    let shrink = PopulationConfig::from_toml_str(&format!("{}\n[mutation_boost]\nafter = 5\nmultiplier = 0.5\n", POPULATION));
+    assert_eq!(shrink.unwrap_err().key(), Some("mutation_boost"));
+
+    assert_eq!(settings.breeders, BreederPool::new());
+    let breeders = PopulationConfig::from_toml_str(&format!("{}\n[breeders]\nfraction = 0.2\nreplacement = false\n", POPULATION)).unwrap();
+    assert_eq!(breeders.breeders, BreederPool::new().fraction(0.2).replacement(false));
+    let none = PopulationConfig::from_toml_str(&format!("{}\n[breeders]\nfraction = 0.0\n", POPULATION));
+    assert_eq!(none.unwrap_err().key(), Some("breeders.fraction"));
+
+    assert_eq!(settings.elitism, Elitism::default());
+    let elitism = PopulationConfig::from_toml_str(&format!("{}\n[elitism]\nglobal = 2\n", POPULATION)).unwrap();
+    assert_eq!(elitism.elitism, Elitism::new().global(2));
+    let typo = PopulationConfig::from_toml_str(&format!("{}\n[elitism]\nper_specie = 2\n", POPULATION));
+    assert_eq!(typo.unwrap_err().key(), Some("elitism.per_specie"));
+}
+
+
+#[test]
+fn test_dynamic_distance() {
+    let settings = PopulationConfig::from_toml_str(&format!("dynamic_distance = true\ndistance_step = 0.25\n{}", POPULATION)).unwrap();
+    let mut population = common::xor_population(50, BenchmarkProblem::new(Benchmark::Xor, 0))
+        .population_config(settings)
+        .seed(5)
+        .populate_base();
+
+    // the distance steps toward the species target each generation
+    for _ in 0..10 {
+        let (species, distance) = (population.get_species_count(), population.get_distance());
+        population.train().unwrap();
+        let expected = if species < 5 { distance - 0.25 } else if species > 5 { distance + 0.25 } else { distance };
+        assert!((population.get_distance() - expected.max(0.1)).abs() < 1e-5 || expected < 0.2);
+    }
+}
+
+
+#[test]
+fn test_environment_extensions() {
+    const SPLIT_RATE: Extension<f32> = Extension::new("split.rate");
+    const SPLIT_RANGE: Extension<(u32, u32)> = Extension::new("split.range");
+
+    let env = NeatEnvironment::new()
+        .set_extension(&SPLIT_RATE, 0.25)
+        .set_extension(&SPLIT_RANGE, (2, 8));
+    assert_eq!(env.extension(&SPLIT_RATE), Some(0.25));
+    assert_eq!(env.clone().extension(&SPLIT_RANGE), Some((2, 8)));
+    assert_eq!(env.extension_or(&Extension::<f32>::new("missing"), 1.0), 1.0);
+
+    let with_table = format!("{}\n[extensions]\n\"split.rate\" = 0.5\n\"split.range\" = \"wide\"\n", ENVIRONMENT);
+    let env = NeatEnvironment::from_toml_str(&with_table).unwrap();
+    assert_eq!(env.extension(&SPLIT_RATE), Some(0.5));
+    assert_eq!(env.extension(&SPLIT_RANGE), None);
+}
+
+
+#[test]
+fn test_presets_are_valid() {
+    let presets = [
+        (preset::control(), preset::control_config()),
+        (preset::classification(), preset::classification_config()),
+        (preset::timeseries(), preset::timeseries_config()),
+        (preset::cppn(), preset::cppn_config())
+    ];
+    for (env, config) in presets.iter() {
+        env.validate().unwrap();
+        PopulationConfig::from_toml_str(&format!(
+            "[config]\ninbreed_rate = {}\ncrossover_rate = {}\ndistance = {}\nspecies_target = {}\n",
+            config.inbreed_rate, config.crossover_rate, config.distance, config.species_target
+        )).unwrap();
+    }
+
+    let env = preset::timeseries().set_input_size(3).set_output_size(1).set_new_node_rate(0.1);
+    assert_eq!(env.new_node_rate, Some(0.1));
+    assert_eq!(env.recurrent_neuron_rate, preset::timeseries().recurrent_neuron_rate);
+    env.validate().unwrap();
+}
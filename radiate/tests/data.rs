@@ -0,0 +1,97 @@
+use radiate::prelude::*;
+
+
+fn imbalanced(majority: usize, minority: usize) -> DataSet {
+    let mut inputs = Vec::new();
+    let mut targets = Vec::new();
+    for i in 0..majority {
+        inputs.push(vec![i as f32]);
+        targets.push(vec![0.0]);
+    }
+    for i in 0..minority {
+        inputs.push(vec![i as f32]);
+        targets.push(vec![1.0]);
+    }
+    DataSet::new(inputs, targets)
+}
+
+
+#[test]
+fn stratified_split_keeps_class_ratios() {
+    let data = imbalanced(80, 20);
+    let (train, test) = data.stratified_split(0.25);
+
+    assert_eq!(train.len() + test.len(), data.len());
+    assert_eq!(test.classes()[&0].len(), 20);
+    assert_eq!(test.classes()[&1].len(), 5);
+    assert_eq!(train.classes()[&1].len(), 15);
+}
+
+
+#[test]
+fn balanced_batches_share_classes_equally() {
+    let data = imbalanced(90, 10);
+    let mut total = 0;
+    for batch in data.batches(10, Shuffle::Balanced) {
+        let classes = batch.classes();
+        assert_eq!(classes[&0].len(), 5);
+        assert_eq!(classes[&1].len(), 5);
+        total += batch.len();
+    }
+    assert_eq!(total, data.len());
+}
+
+
+#[test]
+fn augmentations_keep_sample_shape() {
+    let sample = (0..10).map(|x| x as f32).collect::<Vec<_>>();
+    let chain = vec![
+        Augmentation::Jitter(0.01),
+        Augmentation::Scale(0.9, 1.1),
+        Augmentation::TimeWarp(0.2),
+        Augmentation::FeatureDropout(0.0)
+    ];
+    assert_eq!(chain.augment(&sample).len(), sample.len());
+    assert_eq!(Augmentation::FeatureDropout(1.0).augment(&sample), vec![0.0; 10]);
+}
+
+
+#[test]
+fn degenerate_augmentations_leave_sample() {
+    let sample = (0..10).map(|x| x as f32).collect::<Vec<_>>();
+    assert_eq!(Augmentation::Scale(1.0, 1.0).augment(&sample), sample);
+    assert_eq!(Augmentation::Scale(2.0, 2.0).augment(&sample), sample.iter().map(|x| x * 2.0).collect::<Vec<_>>());
+    assert_eq!(Augmentation::TimeWarp(0.0).augment(&sample), sample);
+    assert_eq!(Augmentation::TimeWarp(-0.5).augment(&sample), sample);
+}
+
+
+#[test]
+fn augmentation_only_applies_while_training() {
+    let mut plain = Neat::new()
+        .input_size(3)
+        .dense(4, Activation::Sigmoid)
+        .dense(1, Activation::Sigmoid);
+    let mut augmented = plain.clone().augment(Augmentation::FeatureDropout(1.0));
+    let sample = vec![1.0, 2.0, 3.0];
+    assert_eq!(augmented.forward(&sample).unwrap(), plain.forward(&sample).unwrap());
+
+    // every input is dropped while fitting, so the loss is the same as fitting on zeros
+    let mut zeros = plain.clone();
+    let loss = augmented.fit(&[(sample.clone(), vec![1.0])], 1, 1, 0.1, Loss::Diff).unwrap();
+    assert_eq!(loss, zeros.fit(&[(vec![0.0; 3], vec![1.0])], 1, 1, 0.1, Loss::Diff).unwrap());
+}
+
+
+#[test]
+fn synthetic_data_is_reproducible() {
+    use radiate::data::synthetic;
+
+    assert_eq!(synthetic::two_spirals(50, 0.05, 7), synthetic::two_spirals(50, 0.05, 7));
+    assert_eq!(synthetic::mackey_glass(100, 4, 3), synthetic::mackey_glass(100, 4, 3));
+    assert_ne!(synthetic::sine_forecast(100, 4, 1), synthetic::sine_forecast(100, 4, 2));
+
+    let parity = synthetic::parity(3);
+    assert_eq!(parity.len(), 8);
+    assert_eq!(parity.classes()[&1].len(), 4);
+}
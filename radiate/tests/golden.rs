@@ -0,0 +1,23 @@
+use radiate::prelude::*;
+
+
+#[test]
+fn test_golden_champion() {
+    Golden::load("tests/golden/champion.json").unwrap().assert(1e-6);
+}
+
+
+#[test]
+fn test_golden_detects_changes() {
+    let net = Neat::new()
+        .input_size(2)
+        .dense_pool(1, Activation::Sigmoid);
+    let golden = Golden::record(&net, vec![vec![0.0, 1.0], vec![1.0, 1.0]]).unwrap();
+    assert!(golden.check(0.0).is_ok());
+
+    let mut changed = golden.clone();
+    changed.expected[1][0] += 0.1;
+    let mismatch = changed.check(0.01).unwrap_err();
+    assert_eq!((mismatch.probe, mismatch.output), (1, 0));
+    assert!(changed.check(0.2).is_ok());
+}
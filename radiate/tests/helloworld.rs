@@ -107,7 +107,7 @@ impl Hello {
 
 impl Genome<Hello, HelloEnv> for Hello {
 
-    fn crossover(parent_one: &Hello, parent_two: &Hello, env: Arc<RwLock<HelloEnv>>, crossover_rate: f32) -> Option<Hello> {
+    fn crossover(parent_one: &Hello, parent_two: &Hello, env: Arc<RwLock<HelloEnv>>, crossover_rate: f32) -> Result<Hello, Box<dyn Error>> {
         let params = env.read().unwrap();
         let mut r = rand::thread_rng();
         let mut new_data = Vec::new();
@@ -125,7 +125,7 @@ impl Genome<Hello, HelloEnv> for Hello {
             let swap_index = r.gen_range(0, new_data.len());
             new_data[swap_index] = params.alph[r.gen_range(0, params.alph.len())];
         }
-        Some(Hello { data: new_data })
+        Ok(Hello { data: new_data })
     }
 
 
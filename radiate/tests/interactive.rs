@@ -0,0 +1,126 @@
+extern crate radiate;
+extern crate rand;
+
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+use rand::Rng;
+use radiate::prelude::*;
+
+
+
+#[test]
+fn test_judgement_fitness() {
+    // ranked members are ahead of everyone left out, best first
+    let fitness = Judgement::Ranked(vec![3, 0]).fitness(5);
+    assert_eq!(fitness, vec![2.0, 1.0, 1.0, 3.0, 1.0]);
+
+    // scores are shifted so the lowest given, and every member left out, is 1
+    let fitness = Judgement::Scored(vec![(1, -2.0), (2, 3.0), (7, 10.0)]).fitness(4);
+    assert_eq!(fitness, vec![1.0, 1.0, 6.0, 1.0]);
+    assert_eq!(Judgement::Ranked(Vec::new()).fitness(3), vec![1.0; 3]);
+    assert_eq!(Judgement::Scored(Vec::new()).fitness(2), vec![1.0; 2]);
+}
+
+
+#[test]
+fn test_interactive_evolution() -> Result<(), Box<dyn Error>> {
+    let mut population = Population::<Shade, ShadeEnv, Judged>::new()
+        .interactive()
+        .seed(11)
+        .populate_base();
+    assert_eq!(population.get_size(), 12);
+
+    // someone who likes shades close to 0.8 picks out their three favorites each generation
+    let mut seen = 0;
+    let mut favorite = None;
+    for _ in 0..40 {
+        favorite = population.train_interactive(|shades, generation| {
+            assert_eq!(generation, seen);
+            seen += 1;
+            let mut order = (0..shades.len()).collect::<Vec<_>>();
+            order.sort_by(|a, b| (shades[*a].value - 0.8).abs().partial_cmp(&(shades[*b].value - 0.8).abs()).unwrap());
+            Judgement::Ranked(order[..3].to_vec())
+        });
+    }
+    let (fitness, shade) = favorite.ok_or("failed to train")?;
+    assert_eq!(fitness, 4.0);
+    assert!((shade.value - 0.8).abs() < 0.05);
+    assert_eq!(population.get_generation(), 40);
+    Ok(())
+}
+
+
+
+#[test]
+fn test_surrogate() {
+    let mut surrogate = Surrogate::new().neighbors(3).bandwidth(0.1).capacity(4);
+    assert!(surrogate.predict(&[0.5]).is_none());
+    for (value, fitness) in [(0.0, 9.0), (0.1, 1.0), (0.2, 1.0), (0.3, 1.0), (0.4, 1.0)].iter() {
+        surrogate.record(vec![*value], *fitness);
+    }
+    // the oldest judgement has been pushed out of the buffer
+    assert_eq!(surrogate.len(), 4);
+    let (near, certain) = surrogate.predict(&[0.25]).unwrap();
+    let (_, unsure) = surrogate.predict(&[0.9]).unwrap();
+    assert!((near - 1.0).abs() < 1e-4);
+    assert!(certain < unsure);
+    assert_eq!(surrogate.most_uncertain(&[vec![0.3], vec![0.9], vec![0.2], vec![0.7]], 2), vec![1, 3]);
+}
+
+
+
+#[test]
+fn test_assisted_evolution() -> Result<(), Box<dyn Error>> {
+    let mut population = Population::<Shade, ShadeEnv, Judged>::new()
+        .interactive()
+        .seed(11)
+        .populate_base();
+    let mut surrogate = Surrogate::new().bandwidth(0.05);
+
+    // the same person only has to look at four shades of each generation
+    let mut favorite = None;
+    for _ in 0..40 {
+        favorite = population.train_assisted(&mut surrogate, |shade| vec![shade.value], 4, |shades, _| {
+            assert_eq!(shades.len(), 4);
+            let mut order = (0..shades.len()).collect::<Vec<_>>();
+            order.sort_by(|a, b| (shades[*a].value - 0.8).abs().partial_cmp(&(shades[*b].value - 0.8).abs()).unwrap());
+            Judgement::Ranked(order[..2].to_vec())
+        });
+    }
+    let (_, shade) = favorite.ok_or("failed to train")?;
+    assert_eq!(surrogate.len(), 160);
+    assert!((shade.value - 0.8).abs() < 0.1);
+    Ok(())
+}
+
+
+
+#[derive(Debug, Clone, Default)]
+pub struct ShadeEnv;
+
+impl Envionment for ShadeEnv {}
+
+
+/// a gray, as something a person would look at and pick their favorite from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shade {
+    pub value: f32
+}
+
+
+impl Genome<Shade, ShadeEnv> for Shade {
+
+    fn crossover(one: &Shade, two: &Shade, _: Arc<RwLock<ShadeEnv>>, crossover_rate: f32) -> Result<Shade, Box<dyn Error>> {
+        let mut r = radiate::rng::thread();
+        let value = if r.gen::<f32>() < crossover_rate { (one.value + two.value) / 2.0 } else { one.value };
+        Ok(Shade { value: (value + r.gen_range(-0.05, 0.05)).clamp(0.0, 1.0) })
+    }
+
+    fn distance(one: &Shade, two: &Shade, _: Arc<RwLock<ShadeEnv>>) -> f32 {
+        (one.value - two.value).abs()
+    }
+
+    fn base(_: &mut ShadeEnv) -> Shade {
+        Shade { value: radiate::rng::thread().gen_range(0.0, 1.0) }
+    }
+}
@@ -6,6 +6,8 @@ use test::Bencher;
 use radiate::prelude::*;
 use radiate::models::neat::direction::NeuronDirection;
 
+mod common;
+
 fn add_extra_nodes(neat: &mut Neat, count: usize) {
   // Create a few extra hidden nodes
   let dense: &mut Dense = neat.layers.last_mut().unwrap().as_mut();
@@ -53,6 +55,16 @@ fn test_create_neat() {
   println!("outputs = {:?}", outputs);
 }
 
+#[test]
+fn test_forward_wrong_input_size() {
+  let mut neat = create_neat(16, 0, 2, false);
+
+  let inputs = create_inputs(12);
+  let err = neat.forward(&inputs).unwrap_err();
+  assert_eq!(err, NeatError::InputSize { expected: 16, actual: 12 });
+  assert_eq!(err.to_string(), "input length 12 does not match expected 16");
+}
+
 #[bench]
 fn bench_neat_dense_pool(b: &mut Bencher) {
   const INPUT_SIZE: usize = 25;
@@ -121,3 +133,1860 @@ fn bench_large_neat_dense_with_hidden(b: &mut Bencher) {
   });
 }
 
+
+#[test]
+fn test_training_session_resumes() {
+  let data = radiate::data::synthetic::parity(2);
+  let model = Neat::new()
+      .input_size(2)
+      .dense(4, Activation::Sigmoid)
+      .dense(1, Activation::Sigmoid);
+
+  let mut session = TrainingSession::new(model)
+      .learning_rate(LearningRate::Exponential { initial: 0.1, decay: 0.9 })
+      .input_scaler(Scaler::min_max(&data.inputs));
+  session.train(&data, 3).expect("failed to train session");
+
+  let path = std::env::temp_dir().join("radiate_session_test.json");
+  session.save(path.to_str().unwrap()).expect("failed to save session");
+  let mut loaded = TrainingSession::load(path.to_str().unwrap()).expect("failed to load session");
+  std::fs::remove_file(path).ok();
+
+  assert_eq!(loaded.epoch, 3);
+  assert_eq!(loaded.history, session.history);
+  assert_eq!(loaded.predict(&data.inputs[1]), session.predict(&data.inputs[1]));
+
+  // the moments of an adam optimizer are saved with the model, so a reloaded session
+  // takes the same next step as one which was never interrupted
+  let model = radiate::rng::scoped(4, || Neat::new()
+      .input_size(2)
+      .dense(4, Activation::Sigmoid)
+      .dense(1, Activation::Sigmoid)
+      .optimizer(Optimizer::adam()));
+  let mut uninterrupted = TrainingSession::new(model).input_scaler(Scaler::min_max(&data.inputs));
+  uninterrupted.train(&data, 3).expect("failed to train session");
+  let path = std::env::temp_dir().join("radiate_session_adam_test.json");
+  uninterrupted.save(path.to_str().unwrap()).expect("failed to save session");
+  let mut resumed = TrainingSession::load(path.to_str().unwrap()).expect("failed to load session");
+  std::fs::remove_file(path).ok();
+  uninterrupted.train(&data, 1).expect("failed to train session");
+  resumed.train(&data, 1).expect("failed to train session");
+  assert_eq!(resumed.history, uninterrupted.history);
+  for input in data.inputs.iter() {
+    assert_eq!(resumed.predict(input), uninterrupted.predict(input));
+  }
+}
+
+
+#[test]
+fn test_model_migration() {
+  let net = Neat::new()
+      .input_size(2)
+      .dense_pool(1, Activation::Sigmoid);
+  let mut saved = serde_json::to_value(&net).unwrap();
+  saved.as_object_mut().unwrap().remove("shuffle");
+
+  let path = std::env::temp_dir().join("radiate_migration_test.json");
+  std::fs::write(&path, saved.to_string()).unwrap();
+  assert_eq!(radiate::models::neat::migrate::upgrade_file(path.to_str().unwrap()).unwrap(), 0);
+  let mut loaded = Neat::load(path.to_str().unwrap()).expect("failed to load migrated model");
+  assert_eq!(loaded.forward(&vec![1.0, 0.0]), net.clone().forward(&vec![1.0, 0.0]));
+
+  let mut future = net.to_versioned().unwrap();
+  future["format_version"] = (MODEL_VERSION + 1).into();
+  std::fs::write(&path, future.to_string()).unwrap();
+  assert!(Neat::load(path.to_str().unwrap()).is_err());
+  std::fs::remove_file(path).ok();
+}
+
+
+#[test]
+fn test_profile_report() {
+  let data = radiate::data::synthetic::parity(2);
+  let mut net = Neat::new()
+      .input_size(2)
+      .dense(3, Activation::Tanh)
+      .dense(1, Activation::Sigmoid)
+      .profile(true);
+  net.train(&data.inputs, &data.targets, 0.01, Loss::MSE, |epoch, _| epoch == 1).unwrap();
+
+  let report = net.profile_report().expect("profiling is enabled");
+  assert_eq!(report.layers.len(), 2);
+  assert_eq!(report.layers[0].0, LayerType::Dense);
+  assert!(report.layers.iter().all(|(_, timing)| timing.forward_calls == 8 && timing.backward_calls == 8));
+  assert!(Neat::new().profile_report().is_none());
+}
+
+
+#[test]
+fn test_fast_precision() {
+  for act in [Activation::Sigmoid, Activation::Tanh].iter() {
+    for i in -10000..10000 {
+      let x = i as f32 / 1000.0;
+      assert!((act.activate_with(x, Precision::Fast) - act.activate(x)).abs() < 1e-4);
+      assert!((act.deactivate_with(x, Precision::Fast) - act.deactivate(x)).abs() < 2e-4);
+    }
+  }
+
+  let mut exact = Neat::new()
+      .input_size(3)
+      .dense(5, Activation::Tanh)
+      .lstm(4, 3, Activation::Tanh)
+      .dense_pool(2, Activation::Sigmoid);
+  let mut fast = exact.clone().precision(Precision::Fast);
+  let inputs = create_inputs(3);
+  for _ in 0..5 {
+    let expected = exact.forward(&inputs).unwrap();
+    let actual = fast.forward(&inputs).unwrap();
+    assert!(expected.iter().zip(actual.iter()).all(|(a, b)| (a - b).abs() < 1e-3));
+  }
+}
+
+
+#[test]
+fn test_strict_precision() {
+  // within a couple of ulps of the platform's math library, apart from where exact loses digits to
+  // cancellation (ie: e^x - 1 for exp relu) which strict avoids
+  let ulps = |a: f32, b: f32| if a == b { 0 } else { (a.to_bits() as i64 - b.to_bits() as i64).abs() };
+  let acts = [Activation::Sigmoid, Activation::Tanh, Activation::ExpRelu(0.5), Activation::Sine, Activation::Gaussian, Activation::Swish];
+  for act in acts.iter() {
+    for i in -20000..20000 {
+      let x = i as f32 / 1000.0;
+      let (strict, exact) = (act.activate_with(x, Precision::Strict), act.activate(x));
+      assert!(ulps(strict, exact) <= 2 || (strict - exact).abs() < 1e-6, "{:?} {}", act, x);
+      let (strict, exact) = (act.deactivate_with(x, Precision::Strict), act.deactivate(x));
+      assert!(ulps(strict, exact) <= 2 || (strict - exact).abs() < 1e-6, "{:?}' {}", act, x);
+    }
+  }
+
+  // the bits are pinned, these have to come out the same on every platform
+  let bits = [0.3f32, -2.5, 7.0].iter()
+    .map(|x| [Activation::Sigmoid, Activation::Tanh, Activation::Sine].map(|act| act.activate_with(*x, Precision::Strict).to_bits()))
+    .collect::<Vec<_>>();
+  assert_eq!(bits, vec![
+    [1062216839, 1049962222, 1050103405],
+    [916492198, 3212612289, 3206100344],
+    [1065353216, 1065353188, 1059598406]
+  ]);
+
+  let mut exact = radiate::rng::scoped(3, || Neat::new()
+      .input_size(3)
+      .dense(5, Activation::Tanh)
+      .lstm(4, 3, Activation::Sigmoid)
+      .dense(2, Activation::Softmax));
+  let mut strict = exact.clone().precision(Precision::Strict);
+  let inputs = create_inputs(3);
+  for _ in 0..5 {
+    let expected = exact.forward(&inputs).unwrap();
+    let actual = strict.forward(&inputs).unwrap();
+    assert!(expected.iter().zip(actual.iter()).all(|(a, b)| (a - b).abs() < 1e-5));
+  }
+}
+
+
+#[test]
+fn test_compiled_matches_neat() {
+  let mut neat = create_neat(5, 8, 3, true)
+      .lstm(4, 2, Activation::Tanh)
+      .dense(2, Activation::Softmax);
+  let mut compiled = neat.compile().unwrap();
+  assert_eq!(compiled.layers.len(), neat.layers.len());
+  for i in 0..5 {
+    let inputs = create_inputs(5).iter().map(|x| x * 0.1 * i as f32).collect::<Vec<_>>();
+    let expected = neat.forward(&inputs).unwrap();
+    let actual = compiled.forward(&inputs).unwrap();
+    assert!(expected.iter().zip(actual.iter()).all(|(a, b)| (a - b).abs() < 1e-5));
+  }
+}
+
+
+#[test]
+fn test_clone_shares_edges_until_mutated() {
+  let neat = create_neat(3, 0, 2, true);
+  let parent: &Dense = neat.layers.last().unwrap().as_ref();
+  let mut child = parent.clone();
+  assert!(child.shares_edges(parent));
+  assert!(std::sync::Arc::ptr_eq(&child.nodes, &parent.nodes));
+
+  // what a layer is fed is held apart from its neurons, so running it copies nothing
+  let mut fed = parent.clone();
+  fed.forward(&create_inputs(3)).unwrap();
+  fed.reset();
+  assert!(std::sync::Arc::ptr_eq(&fed.nodes, &parent.nodes));
+  assert!(fed.shares_edges(parent));
+
+  child.add_node(Activation::Sigmoid, NeuronDirection::Forward);
+  assert!(!child.shares_edges(parent));
+  assert!(child.edges.len() > parent.edges.len());
+}
+
+
+#[test]
+fn test_within_distance_matches_distance() {
+  use std::sync::{Arc, RwLock};
+
+  let env = Arc::new(RwLock::new(NeatEnvironment::new()));
+  let one = create_neat(3, 4, 2, true);
+  let two = create_neat(3, 4, 2, true);
+  let distance = Neat::distance(&one, &two, Arc::clone(&env));
+  assert!(distance > 0.0);
+  assert!(Neat::within_distance(&one, &two, Arc::clone(&env), distance + 0.01));
+  assert!(!Neat::within_distance(&one, &two, Arc::clone(&env), distance));
+  assert!(!Neat::within_distance(&one, &two, Arc::clone(&env), 0.0));
+}
+
+
+#[test]
+fn test_dense_within_distance_matches_distance() {
+  use std::sync::{Arc, RwLock};
+
+  let env = Arc::new(RwLock::new(NeatEnvironment::new()));
+  let net = create_neat(3, 4, 2, true);
+  let parent: &Dense = net.layers[0].as_ref();
+  let mut child = parent.clone();
+  for _ in 0..3 {
+    child.add_node(Activation::Sigmoid, NeuronDirection::Forward);
+  }
+  let stranger = create_neat(3, 4, 2, true);
+  let stranger: &Dense = stranger.layers[0].as_ref();
+  for (one, two) in [(parent, &child), (&child, parent), (parent, stranger)] {
+    let distance = Dense::distance(one, two, Arc::clone(&env));
+    assert!(distance > 0.0);
+    for threshold in [0.0, distance / 4.0, distance / 2.0, distance, distance + 0.01, 2.0] {
+      assert_eq!(Dense::within_distance(one, two, Arc::clone(&env), threshold), distance < threshold);
+    }
+  }
+  assert!(Dense::within_distance(parent, parent, env, 0.01));
+}
+
+
+#[test]
+fn test_serde_round_trip() {
+  let mut net = create_neat(3, 4, 2, true)
+      .lstm(3, 2, Activation::Tanh)
+      .gru(3, 2, Activation::Tanh)
+      .dense(2, Activation::Softmax);
+  let mut loaded: Neat = serde_json::from_str(&serde_json::to_string(&net).unwrap()).unwrap();
+  let inputs = create_inputs(3);
+  assert_eq!(loaded.forward(&inputs), net.forward(&inputs));
+
+  let env = radiate::default_neat_env();
+  let loaded_env: NeatEnvironment = serde_json::from_str(&serde_json::to_string(&env).unwrap()).unwrap();
+  assert_eq!(loaded_env.input_size, env.input_size);
+  assert_eq!(loaded_env.activation_functions, env.activation_functions);
+}
+
+
+#[test]
+fn test_gru_backprop_through_time() {
+  let data = radiate::data::synthetic::sine_forecast(24, 1, 3);
+  // the starting weights are drawn from a fixed stream so the test can't land on an unlucky start
+  let mut net = radiate::rng::scoped(3, || {
+    Neat::new()
+      .input_size(1)
+      .batch_size(8)
+      .gru(4, 1, Activation::Sigmoid)
+  });
+  let error = |net: &mut Neat| {
+    net.reset();
+    data.inputs.iter().zip(data.targets.iter())
+      .map(|(input, target)| (net.forward(input).unwrap()[0] - target[0]).powi(2))
+      .sum::<f32>()
+  };
+
+  let before = error(&mut net);
+  net.train(&data.inputs, &data.targets, 0.05, Loss::Diff, |epoch, _| epoch == 50).unwrap();
+  let after = error(&mut net);
+  assert!(after.is_finite());
+  assert!(after < before);
+}
+
+
+#[test]
+fn test_fit_steps_once_per_batch() {
+  let mut layer = Dense::new(2, 1, LayerType::Dense, Activation::Sigmoid);
+  let weights = |layer: &Dense| layer.edges.iter().map(|edge| edge.weight).collect::<Vec<_>>();
+  let before = weights(&layer);
+  layer.add_tracer();
+  layer.accumulate_gradients(true);
+  layer.forward(&vec![1.0, 0.5]).unwrap();
+  layer.backward(&vec![0.5], 0.1).unwrap();
+  assert_eq!(weights(&layer), before);
+  layer.apply_gradients(1.0);
+  assert_ne!(weights(&layer), before);
+
+  let data = vec![
+    (vec![0.0, 0.0], vec![0.0]),
+    (vec![1.0, 1.0], vec![0.0]),
+    (vec![1.0, 0.0], vec![1.0]),
+    (vec![0.0, 1.0], vec![1.0])
+  ];
+  let mut net = radiate::rng::scoped(3, || {
+    Neat::new()
+      .input_size(2)
+      .dense(4, Activation::Tanh)
+      .dense(1, Activation::Sigmoid)
+  });
+  let error = |net: &mut Neat| {
+    data.iter()
+      .map(|(input, target)| (net.forward(input).unwrap()[0] - target[0]).powi(2))
+      .sum::<f32>()
+  };
+  let before = error(&mut net);
+  net.fit(&data, 4, 200, 0.1, Loss::Diff).unwrap();
+  assert!(error(&mut net) < before);
+}
+
+
+#[test]
+fn test_initial_topologies() {
+  let sparse = Dense::with_topology(6, 3, LayerType::DensePool, Activation::Sigmoid, &Topology::Sparse(0.0));
+  assert_eq!(sparse.edges.len(), 3);
+  let full = Dense::with_topology(6, 3, LayerType::DensePool, Activation::Sigmoid, &Topology::Sparse(1.0));
+  assert_eq!(full.edges.len(), 18);
+
+  let layered = Topology::Layered { hidden: vec![4, 3], activation: Activation::Tanh };
+  let env = radiate::default_neat_env().set_input_size(2).set_output_size(1).set_topology(layered);
+  env.validate().unwrap();
+  let mut net = Neat::base(&mut env.clone());
+  let dense: &Dense = net.layers[0].as_ref();
+  assert_eq!(dense.nodes.len(), 2 + 1 + 4 + 3);
+  assert_eq!(dense.edges.len(), 2 * 4 + 4 * 3 + 3);
+  assert_eq!(net.forward(&vec![0.5, -0.5]).unwrap().len(), 1);
+
+  let bad = env.set_topology(Topology::Sparse(1.5));
+  assert!(bad.validate().is_err());
+}
+
+
+#[test]
+fn test_optimizers_reduce_error() {
+  let data = vec![
+    (vec![0.0, 0.0], vec![0.0]),
+    (vec![1.0, 1.0], vec![0.0]),
+    (vec![1.0, 0.0], vec![1.0]),
+    (vec![0.0, 1.0], vec![1.0])
+  ];
+  let error = |net: &mut Neat| {
+    data.iter()
+      .map(|(input, target)| (net.forward(input).unwrap()[0] - target[0]).powi(2))
+      .sum::<f32>()
+  };
+  for optimizer in [Optimizer::Sgd, Optimizer::momentum(), Optimizer::rms_prop(), Optimizer::adam()] {
+    let mut net = radiate::rng::scoped(3, || {
+      Neat::new()
+        .input_size(2)
+        .optimizer(optimizer)
+        .dense(4, Activation::Tanh)
+        .dense(1, Activation::Sigmoid)
+    });
+    let before = error(&mut net);
+    net.fit(&data, 4, 200, 0.01, Loss::Diff).unwrap();
+    assert!(error(&mut net) < before, "{:?} did not reduce the error", optimizer);
+  }
+}
+
+
+#[test]
+fn test_crossover_alignment() {
+  let one = Dense::new(2, 1, LayerType::DensePool, Activation::Sigmoid);
+  let mut two = one.clone();
+  two.add_node(Activation::Sigmoid, NeuronDirection::Forward);
+  let alignment = Dense::alignment(&one, &two).unwrap();
+  assert_eq!(alignment, Alignment { matched: 2, disjoint: 0, excess: 2 });
+  assert_eq!(Dense::alignment(&two, &one), Some(alignment));
+  assert_eq!(alignment.matched_ratio(), 4.0 / 6.0);
+
+  let stats = CrossoverStats { matings: vec![alignment, Alignment { matched: 3, disjoint: 0, excess: 0 }] };
+  assert_eq!(stats.total(), Alignment { matched: 5, disjoint: 0, excess: 2 });
+  assert_eq!(stats.poorly_aligned(0.9), 1);
+}
+
+
+#[test]
+fn test_loss_functions() {
+  let (loss, errors) = Loss::MSE.loss(&[1.0, 0.0], &[0.5, 0.5]);
+  assert_eq!(loss, 0.25);
+  assert_eq!(errors, vec![0.5, -0.5]);
+  let (loss, errors) = Loss::MAE.loss(&[1.0, 0.0], &[0.5, 0.5]);
+  assert_eq!(loss, 0.5);
+  assert_eq!(errors, vec![0.5, -0.5]);
+  let (loss, errors) = Loss::Huber(0.1).loss(&[1.0], &[0.5]);
+  assert!((loss - 0.045).abs() < 1e-6);
+  assert_eq!(errors, vec![0.1]);
+
+  let data = vec![
+    (vec![1.0, 0.0], vec![1.0, 0.0]),
+    (vec![0.0, 1.0], vec![0.0, 1.0])
+  ];
+  let mut net = radiate::rng::scoped(3, || {
+    Neat::new()
+      .input_size(2)
+      .dense(2, Activation::Softmax)
+  });
+  let losses = net.fit(&data, 2, 50, 0.1, Loss::CrossEntropy).unwrap();
+  assert_eq!(losses.len(), 50);
+  assert!(losses.iter().all(|loss| *loss >= 0.0));
+  assert!(losses[49] < losses[0]);
+}
+
+
+#[test]
+fn test_visit_weights_mut() {
+  let mut net = create_neat(3, 4, 2, true).lstm(3, 2, Activation::Tanh);
+  let untouched = net.clone();
+  let mut visited = vec![0; net.layers.len()];
+  net.visit_weights_mut(|layer, _, _| visited[layer] += 1);
+  assert!(visited.iter().all(|count| *count > 0));
+  let dense: &Dense = net.layers[0].as_ref();
+  assert!(dense.shares_edges(untouched.layers[0].as_ref()));
+
+  // pruning every weight of the first layer leaves its outputs independent of the inputs
+  net.visit_weights_mut(|layer, _, weight| if layer == 0 { *weight = 0.0 });
+  let dense: &Dense = net.layers[0].as_ref();
+  assert!(dense.edges.iter().all(|edge| edge.weight == 0.0));
+  let mut first = net.clone();
+  first.layers.truncate(1);
+  assert_eq!(first.forward(&vec![1.0, 2.0, 3.0]), first.forward(&vec![-1.0, 0.5, 0.0]));
+}
+
+
+#[test]
+fn test_softmax_cross_entropy_gradient() {
+  let mut layer = Dense::new(2, 3, LayerType::Dense, Activation::Softmax);
+  let inputs = vec![1.0, 0.5];
+  let targets = vec![0.0, 1.0, 0.0];
+  let before = layer.edges.iter().map(|edge| edge.weight).collect::<Vec<_>>();
+  let outputs = layer.forward(&inputs).unwrap();
+  assert!((outputs.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+  // through the softmax the step of each weight is rate * (target - output) * input
+  let (_, errors) = Loss::CrossEntropy.loss_for(&targets, &outputs, Activation::Softmax);
+  layer.backward(&errors, 0.1).unwrap();
+  for (edge, weight) in layer.edges.iter().zip(before) {
+    let src = layer.inputs.iter().position(|id| *id == edge.src).unwrap();
+    let dst = layer.outputs.iter().position(|id| *id == edge.dst).unwrap();
+    let expected = 0.1 * (targets[dst] - outputs[dst]) * inputs[src];
+    assert!((edge.weight - weight - expected).abs() < 1e-5);
+  }
+}
+
+
+#[test]
+fn test_sigmoid_cross_entropy() {
+  // the binary form on anything but softmax, so the target 0 outputs get pushed down as well
+  let (loss, errors) = Loss::CrossEntropy.loss(&[1.0, 0.0], &[0.5, 0.5]);
+  assert!((loss - 2.0 * 2f32.ln()).abs() < 1e-6);
+  assert_eq!(errors, vec![2.0, -2.0]);
+
+  let data = vec![
+    (vec![1.0, 0.0], vec![1.0]),
+    (vec![0.0, 1.0], vec![0.0]),
+    (vec![1.0, 1.0], vec![0.0])
+  ];
+  let mut net = radiate::rng::scoped(5, || {
+    Neat::new()
+      .input_size(2)
+      .dense(1, Activation::Sigmoid)
+  });
+  assert_eq!(net.output_activation(), Some(Activation::Sigmoid));
+  let zeros = |net: &mut Neat| data.iter()
+    .filter(|(_, target)| target[0] == 0.0)
+    .map(|(input, _)| { net.reset(); net.forward(input).unwrap()[0] })
+    .collect::<Vec<_>>();
+  let before = zeros(&mut net);
+  let losses = net.fit(&data, 3, 50, 0.5, Loss::CrossEntropy).unwrap();
+  assert!(losses[49] < losses[0]);
+  let after = zeros(&mut net);
+  assert!(after.iter().zip(before.iter()).all(|(after, before)| after < before));
+}
+
+
+#[test]
+fn test_weight_report() {
+  let stats = WeightStats::new(&[0.0, 1.0, -1.0, 0.5]);
+  assert_eq!(stats.count, 4);
+  assert_eq!((stats.min, stats.max, stats.mean), (-1.0, 1.0, 0.125));
+  assert_eq!(stats.near_zero, 0.25);
+  assert_eq!(stats.histogram.iter().sum::<usize>(), 4);
+  assert_eq!(stats.histogram[0], 1);
+  assert_eq!(*stats.histogram.last().unwrap(), 1);
+  assert_eq!(WeightStats::new(&[]), WeightStats::default());
+
+  let net = create_neat(3, 4, 2, true).lstm(3, 2, Activation::Tanh);
+  let report = net.weight_report();
+  assert_eq!(report.layers.len(), net.layers.len());
+  assert_eq!(report.layers[2].0, LayerType::LSTM);
+  assert!(report.layers.iter().all(|(_, stats)| stats.count > 0));
+  assert!(report.to_string().lines().count() == net.layers.len() + 1);
+}
+
+
+#[test]
+fn test_prune_dead_neurons() {
+  let mut net = radiate::rng::scoped(3, || create_neat(3, 0, 2, true));
+  let dense: &mut Dense = net.layers[0].as_mut();
+  let saturated = std::sync::Arc::make_mut(&mut dense.nodes).last_mut().unwrap();
+  saturated.bias = 100.0;
+  let saturated = saturated.id;
+
+  let inputs = (0..8).map(|i| create_inputs(3).iter().map(|x| x * i as f32 * 0.1).collect()).collect::<Vec<Vec<f32>>>();
+  let before = inputs.iter().map(|input| net.forward(input).unwrap()).collect::<Vec<_>>();
+  net.reset();
+  let report = net.dead_neurons(&inputs, 1e-4).unwrap();
+  assert_eq!(report.samples, 8);
+  assert_eq!(report.hidden, 2);
+  assert_eq!(report.dead.len(), 1);
+  assert_eq!(report.dead[0].neuron, saturated);
+
+  assert!(net.prune_dead(&report).unwrap() >= 2);
+  for (input, expected) in inputs.iter().zip(before) {
+    let actual = net.forward(input).unwrap();
+    assert!(actual.iter().zip(expected.iter()).all(|(a, b)| (a - b).abs() < 1e-4));
+  }
+}
+
+
+#[test]
+fn test_lstm_bptt_window() {
+  let mut layer = LSTM::new(2, 3, 1, Activation::Tanh);
+  layer.set_bptt_window(3);
+  let mut unrolled = layer.clone();
+  for lstm in [&mut layer, &mut unrolled] {
+    lstm.add_tracer();
+    for i in 0..10 {
+      lstm.forward(&vec![i as f32 * 0.1, 0.5]).unwrap();
+    }
+  }
+  assert_eq!(layer.states.memory_states.len(), 3);
+  assert_eq!(layer.v_gate.read().unwrap().trace_states.as_ref().unwrap().index, 3);
+
+  // each backward steps back through one step, newest first, so the error of an older output goes
+  // into its own step and the layer before is given that step's input error
+  let weights = |lstm: &LSTM| serde_json::to_string(&*lstm.f_gate.read().unwrap().edges).unwrap();
+  let before = weights(&layer);
+  assert!(layer.backward(&vec![0.0], 0.1).unwrap().iter().all(|e| *e == 0.0));
+  assert_eq!(weights(&layer), before);
+  assert!(layer.backward(&vec![0.5], 0.1).unwrap().iter().any(|e| *e != 0.0));
+  assert_ne!(weights(&layer), before);
+  assert_eq!(layer.states.memory_states.len(), 1);
+
+  // the error of the newest output is carried back to the oldest step in the window
+  unrolled.backward(&vec![0.5], 0.1).unwrap();
+  unrolled.backward(&vec![0.0], 0.1).unwrap();
+  assert!(unrolled.backward(&vec![0.0], 0.1).unwrap().iter().any(|e| *e != 0.0));
+  assert_eq!(unrolled.v_gate.read().unwrap().trace_states.as_ref().unwrap().index, 0);
+
+  // outputs older than the window have nothing left to step back through
+  assert!(unrolled.backward(&vec![0.5], 0.1).unwrap().iter().all(|e| *e == 0.0));
+}
+
+
+#[test]
+fn test_response_curve() {
+  let neat = radiate::rng::scoped(3, || create_neat(3, 2, 2, false));
+  let mut compiled = neat.compile().unwrap();
+  let sweep = Sweep::new(1, -1.0, 1.0, 5);
+  assert_eq!(sweep.values(), vec![-1.0, -0.5, 0.0, 0.5, 1.0]);
+
+  let fixed = analysis::response_curve(&mut compiled, &sweep, &[vec![0.2, 0.0, 0.4]]).unwrap();
+  assert_eq!(fixed.values.len(), 5);
+  for (i, value) in fixed.values.iter().enumerate() {
+    let expected = compiled.forward(&[0.2, *value, 0.4]).unwrap();
+    compiled.reset();
+    assert!(fixed.mean[i].iter().zip(expected.iter()).all(|(a, b)| (a - b).abs() < 1e-5));
+    assert_eq!(fixed.min[i], fixed.mean[i]);
+    assert_eq!(fixed.max[i], fixed.mean[i]);
+  }
+
+  let sampled = analysis::response_curve(&mut compiled, &sweep, &[vec![0.2, 0.0, 0.4], vec![-0.6, 0.0, 0.9]]).unwrap();
+  for i in 0..sampled.values.len() {
+    assert!(sampled.mean[i].iter().zip(sampled.min[i].iter().zip(sampled.max[i].iter())).all(|(m, (lo, hi))| lo <= m && m <= hi));
+  }
+  assert!(analysis::response_curve(&mut compiled, &Sweep::new(3, 0.0, 1.0, 2), &[vec![0.0; 3]]).is_err());
+  assert!(analysis::response_curve(&mut compiled, &sweep, &[]).is_err());
+}
+
+
+#[test]
+fn test_output_bounds() {
+  let neat = radiate::rng::scoped(3, || create_neat(3, 4, 2, true).dense(3, Activation::Softmax));
+  let mut compiled = neat.compile().unwrap();
+  let ranges = vec![Interval::new(-1.0, 1.0), Interval::new(0.0, 0.5), Interval::new(-2.0, 0.0)];
+  let bounds = analysis::output_bounds(&compiled, &ranges).unwrap();
+  assert_eq!(bounds.len(), 3);
+  assert!(bounds.iter().all(|bound| bound.within(&Interval::new(0.0, 1.0))));
+
+  // every input inside the ranges lands inside the bounds
+  for i in 0..5 {
+    for j in 0..5 {
+      for k in 0..5 {
+        let inputs = ranges.iter().zip([i, j, k].iter())
+          .map(|(range, step)| range.low + range.width() * *step as f32 / 4.0)
+          .collect::<Vec<_>>();
+        let outputs = compiled.forward(&inputs).unwrap();
+        assert!(outputs.iter().zip(bounds.iter()).all(|(output, bound)| bound.low - 1e-5 <= *output && *output <= bound.high + 1e-5));
+      }
+    }
+  }
+
+  // a single point is bounded tightly around the network's output
+  let point = analysis::output_bounds(&compiled, &[Interval::point(0.3), Interval::point(0.1), Interval::point(-0.5)]).unwrap();
+  let outputs = compiled.forward(&[0.3, 0.1, -0.5]).unwrap();
+  assert!(outputs.iter().zip(point.iter()).all(|(output, bound)| bound.width() < 1e-5 && (output - bound.low).abs() < 1e-5));
+
+  let recurrent = create_neat(3, 4, 2, false).lstm(3, 2, Activation::Tanh).compile().unwrap();
+  assert!(analysis::output_bounds(&recurrent, &ranges).is_err());
+}
+
+
+#[test]
+fn test_output_bounds_round_outward() {
+  // 0.1 + 0.2 rounds up past the exact sum of the two f32s, which the bounds still have to hold
+  let mut net = Neat::new().input_size(2).dense(1, Activation::Linear(1.0));
+  net.visit_weights_mut(|_, _, weight| *weight = 1.0);
+  let dense: &mut Dense = net.layers[0].as_mut();
+  std::sync::Arc::make_mut(&mut dense.nodes).last_mut().unwrap().bias = 0.0;
+  let exact = 0.1f32 as f64 + 0.2f32 as f64;
+  assert!(((0.1f32 + 0.2f32) as f64) > exact);
+  let bounds = analysis::output_bounds(&net.compile().unwrap(), &[Interval::point(0.1), Interval::point(0.2)]).unwrap();
+  assert!(bounds[0].low as f64 <= exact && exact <= bounds[0].high as f64);
+  assert!(bounds[0].width() < 1e-6);
+}
+
+
+#[test]
+fn test_model_card() {
+  let net = Neat::new()
+      .input_size(2)
+      .dense_pool(1, Activation::Sigmoid);
+  let card = ModelCard::new()
+      .description("xor champion")
+      .data("radiate::data::synthetic::parity(2)")
+      .seed(42)
+      .commit("0af1a20")
+      .metric("accuracy", 0.98)
+      .extra("generations", 120);
+
+  let path = std::env::temp_dir().join("radiate_card_test.json");
+  let path = path.to_str().unwrap();
+  net.save_with_card(path, &card).unwrap();
+  assert_eq!(ModelCard::read(path).unwrap(), Some(card.clone()));
+  let (mut loaded, loaded_card) = Neat::load_with_card(path).unwrap();
+  assert_eq!(loaded_card, Some(card));
+  assert_eq!(loaded.forward(&vec![1.0, 0.0]), net.clone().forward(&vec![1.0, 0.0]));
+
+  // cards are ignored by a plain load and upgraded along with the model
+  assert!(Neat::load(path).is_ok());
+  radiate::models::neat::migrate::upgrade_file(path).unwrap();
+  assert!(ModelCard::read(path).unwrap().is_some());
+
+  net.save(path).unwrap();
+  assert_eq!(Neat::load_with_card(path).unwrap().1, None);
+  std::fs::remove_file(path).ok();
+}
+
+
+#[test]
+fn test_supervised_problem() {
+  let inputs = vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]];
+  let targets = vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0]];
+  let problem = Supervised::new(DataSet::new(inputs.clone(), targets.clone()), Loss::MSE);
+  let mut net = radiate::rng::scoped(3, || create_neat(2, 3, 2, false));
+
+  let report = problem.report(&mut net).unwrap();
+  assert_eq!(report.samples, 4);
+  assert_eq!(report.targets.len(), 2);
+  let outputs = inputs.iter().map(|input| net.forward(input).unwrap()).collect::<Vec<_>>();
+  net.reset();
+  for column in 0..2 {
+    let mse = outputs.iter().zip(targets.iter()).map(|(o, t)| (o[column] - t[column]).powi(2)).sum::<f32>() / 4.0;
+    assert!((report.targets[column].mse - mse).abs() < 1e-6);
+    assert!(report.targets[column].max_error >= report.targets[column].mae);
+  }
+  let loss = (report.targets[0].mse + report.targets[1].mse) / 2.0;
+  assert!((report.loss - loss).abs() < 1e-6);
+  assert!((Problem::<Neat>::solve(&problem, &mut net) - 1.0 / (1.0 + loss)).abs() < 1e-6);
+
+  // any network can be scored, a compiled copy scores the same
+  let mut compiled = net.compile().unwrap();
+  assert_eq!(problem.report(&mut compiled).unwrap(), report);
+
+  let mut population = Population::<Neat, NeatEnvironment, Supervised>::new()
+    .size(10)
+    .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(2))
+    .impose(problem)
+    .configure(Config { inbreed_rate: 0.001, crossover_rate: 0.75, distance: 0.5, species_target: 5 })
+    .populate_clone(net);
+  let (fitness, _) = population.train().unwrap();
+  assert!(fitness > 0.0 && fitness <= 1.0);
+}
+
+
+
+#[test]
+fn test_lifetime_learning() {
+  let data = DataSet::new(vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]], vec![vec![0.0], vec![1.0], vec![1.0], vec![0.0]]);
+  let problem = Supervised::new(data, Loss::MSE);
+  let population = |learning: Learning<Neat>| common::xor_population(10, problem.clone())
+    .learning(learning)
+    .parallel_eval(false)
+    .seed(8)
+    .populate_base();
+  let snapshot = |population: &mut Population<Neat, NeatEnvironment, Supervised>| population.members_mut()
+    .iter()
+    .map(|cont| serde_json::to_string(&*cont.member.read().unwrap()).unwrap())
+    .collect::<Vec<_>>();
+
+  // lamarckian members keep the weights they learned and are scored on them
+  let mut lamarckian = population(problem.fine_tune(20, 2, 0.3, Inheritance::Lamarckian));
+  let before = snapshot(&mut lamarckian);
+  lamarckian.evaluate();
+  let after = snapshot(&mut lamarckian);
+  assert!(before.iter().zip(after.iter()).all(|(before, after)| before != after));
+  for cont in lamarckian.members_mut().iter() {
+    let mut member = cont.member.read().unwrap().clone();
+    assert_eq!(Problem::<Neat>::solve(&problem, &mut member), cont.fitness_score);
+  }
+
+  // baldwinian members are scored on what they could learn but stay as they were
+  let mut baldwinian = population(problem.fine_tune(20, 2, 0.3, Inheritance::Baldwinian));
+  let before = snapshot(&mut baldwinian);
+  baldwinian.evaluate();
+  assert_eq!(snapshot(&mut baldwinian), before);
+  for cont in baldwinian.members_mut().iter() {
+    let mut member = cont.member.read().unwrap().clone();
+    assert_ne!(Problem::<Neat>::solve(&problem, &mut member), cont.fitness_score);
+  }
+  assert!(baldwinian.train().is_some());
+}
+
+
+#[test]
+fn test_snapshot_restore_weights() {
+  let data = vec![(vec![0.0, 1.0, 0.5], vec![1.0, 0.0]), (vec![1.0, 0.0, 0.5], vec![0.0, 1.0])];
+  let mut net = create_neat(3, 4, 2, true)
+      .lstm(3, 2, Activation::Tanh)
+      .dense(2, Activation::Sigmoid);
+  let original = serde_json::to_string(&net).unwrap();
+  let inputs = create_inputs(3);
+  let outputs = net.clone().forward(&inputs).unwrap();
+
+  // training moves the weights of every layer, restoring puts back exactly what was there
+  let snapshot = net.snapshot_weights().unwrap();
+  net.fit(&data, 2, 5, 0.3, Loss::MSE).unwrap();
+  assert_ne!(serde_json::to_string(&net).unwrap(), original);
+  net.restore_weights(&snapshot).unwrap();
+  assert_eq!(serde_json::to_string(&net).unwrap(), original);
+  assert_eq!(net.forward(&inputs).unwrap(), outputs);
+
+  // a snapshot only fits the network it was taken of
+  let pool: &mut Dense = net.layers[1].as_mut();
+  pool.add_node(Activation::Sigmoid, NeuronDirection::Forward);
+  assert_eq!(net.restore_weights(&snapshot), Err(NeatError::SnapshotMismatch));
+  let other = create_neat(3, 4, 2, false);
+  assert_eq!(net.restore_weights(&other.snapshot_weights().unwrap()), Err(NeatError::SnapshotMismatch));
+}
+
+
+#[test]
+fn test_cppn_activations() {
+  for activation in [Activation::Sine, Activation::Gaussian, Activation::Abs, Activation::Swish, Activation::LeakyRelu(0.1), Activation::ExpRelu(0.5)].iter() {
+    for x in [-1.3_f32, -0.4, 0.7, 2.1].iter() {
+      let numeric = (activation.activate(x + 1e-3) - activation.activate(x - 1e-3)) / 2e-3;
+      assert!((activation.deactivate(*x) - numeric).abs() < 1e-2);
+    }
+  }
+
+  // a sine neuron is bounded by its peaks, not just the ends of its input range
+  let mut net = Neat::new().input_size(1).dense(1, Activation::Sine);
+  let dense: &mut Dense = net.layers[0].as_mut();
+  std::sync::Arc::make_mut(&mut dense.nodes).last_mut().unwrap().bias = 0.0;
+  let weights = dense.edges.len();
+  net.visit_weights_mut(|_, _, weight| *weight = 1.0);
+  assert_eq!(weights, 1);
+  let bounds = analysis::output_bounds(&net.compile().unwrap(), &[Interval::new(0.0, 3.0)]).unwrap();
+  assert_eq!(bounds[0].high, 1.0);
+  assert!((bounds[0].low - 0.0).abs() < 1e-6);
+}
+
+
+#[test]
+fn test_substrate() {
+  let substrate = Substrate::new(vec![Substrate::grid(3, 3), Substrate::grid(4, 1), Substrate::grid(2, 1)]).threshold(0.1);
+  assert_eq!(Substrate::grid(3, 1), vec![vec![-1.0, 0.0], vec![0.0, 0.0], vec![1.0, 0.0]]);
+  assert_eq!(substrate.dimensions(), 2);
+
+  let mut cppn = radiate::rng::scoped(3, || substrate.cppn());
+  assert_eq!(cppn.input_size, 4);
+  let network = substrate.build(&mut cppn).unwrap();
+  assert_eq!(network.weights.len(), 2);
+  assert_eq!((network.weights[0].len(), network.weights[0][0].len()), (4, 9));
+  assert_eq!(network.biases[1].len(), 2);
+  assert!(network.connections() <= 9 * 4 + 4 * 2);
+  assert!(network.weights.iter().flatten().flatten().all(|weight| weight.abs() <= 3.0));
+
+  // each weight is the cppn queried with the coordinates of the two neurons
+  let output = cppn.forward(&vec![-1.0, -1.0, 1.0 / 3.0, 0.0]).unwrap()[0].clamp(-1.0, 1.0);
+  let expected = if output.abs() <= 0.1 { 0.0 } else { output.signum() * (output.abs() - 0.1) / 0.9 * 3.0 };
+  assert!((network.weights[0][2][0] - expected).abs() < 1e-5);
+
+  // a compiled cppn builds the same network, which can be scored like any other
+  let mut compiled = cppn.compile().unwrap();
+  let mut rebuilt = substrate.build(&mut compiled).unwrap();
+  assert_eq!(rebuilt, network);
+  assert_eq!(rebuilt.forward(&[0.5; 9]).unwrap().len(), 2);
+  assert!(rebuilt.forward(&[0.5; 3]).is_err());
+  let problem = Supervised::new(DataSet::new(vec![vec![0.5; 9]], vec![vec![1.0, 0.0]]), Loss::MSE);
+  assert!(problem.report(&mut rebuilt).unwrap().loss.is_finite());
+}
+
+
+#[test]
+fn test_fitness_terms() {
+  let data = radiate::data::synthetic::parity(2);
+  let fitness = terms::accuracy(data.clone())
+    .minus(terms::complexity(0.01))
+    .plus(Fitness::term("small outputs", |net: &mut Neat| 1.0 - net.forward(&vec![0.0, 0.0]).unwrap()[0]).scale(0.5));
+  assert_eq!(fitness.names(), vec!["accuracy", "complexity", "small outputs"]);
+
+  let mut net = radiate::rng::scoped(3, || create_neat(2, 2, 1, false));
+  let connections = net.weight_report().layers.iter().map(|(_, stats)| stats.count).sum::<usize>();
+  let breakdown = fitness.breakdown(&mut net);
+  net.reset();
+  assert!((breakdown[1].1 + 0.01 * connections as f32).abs() < 1e-6);
+  assert!(breakdown[0].1 >= 0.0 && breakdown[0].1 <= 1.0);
+  let total = breakdown.iter().map(|(_, score)| score).sum::<f32>();
+  assert!((fitness.solve(&mut net) - total).abs() < 1e-6);
+
+  let mut population = common::xor_population(10, terms::fit(data, Loss::MSE).minus(terms::complexity(0.001)))
+    .populate_clone(net);
+  assert!(population.train().is_some());
+}
+
+
+#[test]
+fn test_conv1d() {
+  use std::sync::{Arc, RwLock};
+
+  // 6 steps of 2 channels padded to 8 and read 3 steps at a time, 2 steps apart
+  let mut layer = radiate::rng::scoped(3, || Conv1D::new(6, 2, 3, 3, 2, 1, Activation::Tanh));
+  assert_eq!(layer.output_length(), 3);
+  assert_eq!(layer.shape(), (12, 9));
+  let inputs = (0..12).map(|i| (i as f32 * 0.7).sin()).collect::<Vec<_>>();
+  let outputs = layer.forward(&inputs).unwrap();
+  assert_eq!(outputs.len(), 9);
+  // the first position reads the padding then the first two steps
+  let state = layer.biases[1] + (0..4).map(|i| layer.weights[1][2 + i] * inputs[i]).sum::<f32>();
+  assert!((outputs[1] - state.tanh()).abs() < 1e-6);
+  assert!(layer.forward(&vec![0.0; 10]).is_err());
+
+  // stepping back an error of one on every output moves each weight by the gradient of their sum
+  let sum = |layer: &mut Conv1D, inputs: &Vec<f32>| layer.forward(inputs).unwrap().iter().sum::<f32>();
+  let mut nudged = layer.clone();
+  nudged.weights[2][3] += 1e-3;
+  let weight_gradient = (sum(&mut nudged, &inputs) - sum(&mut layer, &inputs)) / 1e-3;
+  let mut moved = inputs.clone();
+  moved[5] += 1e-3;
+  let input_gradient = (sum(&mut layer, &moved) - sum(&mut layer, &inputs)) / 1e-3;
+
+  let before = layer.weights[2][3];
+  layer.forward(&inputs).unwrap();
+  let errors = layer.backward(&vec![1.0; 9], 1e-3).unwrap();
+  assert_eq!(errors.len(), 12);
+  assert!(((layer.weights[2][3] - before) / 1e-3 - weight_gradient).abs() < 1e-2);
+  assert!((errors[5] - input_gradient).abs() < 1e-2);
+
+  // a traced layer has to be fed forward before it can step back
+  layer.add_tracer();
+  assert!(layer.backward(&vec![1.0; 9], 0.1).is_err());
+
+  // crossing over mixes filter weights, and a layer is no distance from itself
+  let env = Arc::new(RwLock::new(radiate::default_neat_env()));
+  let other = Conv1D::new(6, 2, 3, 3, 2, 1, Activation::Tanh);
+  let child = Conv1D::crossover(&layer, &other, Arc::clone(&env), 1.0).unwrap();
+  for (filter, weights) in child.weights.iter().enumerate() {
+    for (index, weight) in weights.iter().enumerate() {
+      assert!(*weight == layer.weights[filter][index] || *weight == other.weights[filter][index]);
+    }
+  }
+  assert_eq!(Conv1D::distance(&layer, &layer, Arc::clone(&env)), 0.0);
+  assert!(Conv1D::distance(&layer, &other, Arc::clone(&env)) > 0.0);
+  assert_eq!(Conv1D::alignment(&layer, &other).unwrap().matched, 21);
+
+  // the network sizes the convolution from its inputs and the next layer from the convolution
+  let mut net = Neat::new()
+    .input_size(12)
+    .conv1d(2, 3, 3, 2, 1, Activation::Tanh)
+    .dense(1, Activation::Sigmoid);
+  assert_eq!(net.layers[1].layer.shape(), (9, 1));
+  assert_eq!(net.forward(&inputs).unwrap().len(), 1);
+  let child = Neat::crossover(&net, &net.clone(), env, 0.5).unwrap();
+  assert_eq!(child.layers[0].layer.shape(), (12, 9));
+}
+
+
+#[test]
+fn test_conv2d_and_pooling() {
+  // a 4x4 image of 2 channels, padded so a 3x3 kernel keeps the same size
+  let image = Flatten::new(4, 4, 2);
+  let pixels = (0..4)
+    .map(|row| (0..4).map(|column| vec![(row * 4 + column) as f32 * 0.1, (row as f32 - column as f32).sin()]).collect())
+    .collect::<Vec<Vec<_>>>();
+  let inputs = image.image(&pixels).unwrap();
+  assert_eq!(inputs.len(), image.size());
+  assert_eq!(inputs[image.index(2, 1, 0)], pixels[2][1][0]);
+  assert!(image.image(&pixels[..3]).is_err());
+
+  let mut layer = radiate::rng::scoped(3, || Conv2D::new(image.shape(), 3, 3, 1, 1, Activation::Tanh));
+  assert_eq!(layer.output_shape(), (4, 4, 3));
+  let outputs = layer.forward(&inputs).unwrap();
+  // the top left pixel reads the padding then the top left 2x2 corner of the image
+  let state = layer.biases[2] + [(0, 0), (0, 1), (1, 0), (1, 1)].iter()
+    .flat_map(|(row, column)| (0..2).map(move |channel| (((row + 1) * 3 + column + 1) * 2 + channel, image.index(*row, *column, channel))))
+    .map(|(weight, input)| layer.weights[2][weight] * inputs[input])
+    .sum::<f32>();
+  assert!((outputs[2] - state.tanh()).abs() < 1e-6);
+
+  // stepping back an error of one on every output moves each weight by the gradient of their sum
+  let sum = |layer: &mut Conv2D, inputs: &Vec<f32>| layer.forward(inputs).unwrap().iter().sum::<f32>();
+  let mut nudged = layer.clone();
+  nudged.weights[1][7] += 1e-3;
+  let weight_gradient = (sum(&mut nudged, &inputs) - sum(&mut layer, &inputs)) / 1e-3;
+  let mut moved = inputs.clone();
+  moved[13] += 1e-3;
+  let input_gradient = (sum(&mut layer, &moved) - sum(&mut layer, &inputs)) / 1e-3;
+  let before = layer.weights[1][7];
+  layer.forward(&inputs).unwrap();
+  let errors = layer.backward(&vec![1.0; 48], 1e-3).unwrap();
+  assert!(((layer.weights[1][7] - before) / 1e-3 - weight_gradient).abs() < 2e-2);
+  assert!((errors[13] - input_gradient).abs() < 2e-2);
+
+  // pooling takes the biggest of each channel in every window and passes errors back to it
+  let mut pool = MaxPool2D::new((4, 4, 3), 2, 2);
+  assert_eq!(pool.output_shape(), (2, 2, 3));
+  let pooled = pool.forward(&outputs).unwrap();
+  let window = [(2, 0), (2, 1), (3, 0), (3, 1)].iter().map(|(row, column)| outputs[(row * 4 + column) * 3 + 1]).fold(f32::MIN, f32::max);
+  assert_eq!(pooled[2 * 3 + 1], window);
+  let errors = pool.backward(&vec![1.0; 12], 0.1).unwrap();
+  assert_eq!(errors.iter().sum::<f32>(), 12.0);
+  assert!(errors.iter().enumerate().filter(|(_, error)| **error != 0.0).all(|(index, _)| pooled.contains(&outputs[index])));
+
+  // the pooling layer reads the shape of the convolution before it, and the whole stack can be trained
+  let images = (0..8)
+    .map(|i| (0..32).map(|j| if (j / 8 < 2) == (i % 2 == 0) { 1.0 } else { 0.0 }).collect::<Vec<f32>>())
+    .collect::<Vec<_>>();
+  let targets = (0..8).map(|i| vec![(i % 2) as f32]).collect::<Vec<_>>();
+  let mut net = radiate::rng::scoped(3, || {
+    Neat::new()
+      .input_size(32)
+      .conv2d(Some(image.shape()), 3, 3, 1, 1, Activation::Tanh)
+      .max_pool2d(2, 2)
+      .dense(1, Activation::Sigmoid)
+  });
+  assert_eq!(net.layers[1].layer.shape(), (48, 12));
+  let error = |net: &mut Neat| images.iter().zip(targets.iter())
+    .map(|(image, target)| (net.forward(image).unwrap()[0] - target[0]).powi(2))
+    .sum::<f32>();
+  let before = error(&mut net);
+  net.train(&images, &targets, 0.1, Loss::Diff, |epoch, _| epoch == 20).unwrap();
+  assert!(error(&mut net) < before);
+
+  let child = Neat::crossover(&net, &net.clone(), std::sync::Arc::new(std::sync::RwLock::new(radiate::default_neat_env())), 0.5).unwrap();
+  assert_eq!(child.layers.iter().map(|wrap| wrap.layer_type).collect::<Vec<_>>(), vec![LayerType::Conv2D, LayerType::MaxPool2D, LayerType::Dense]);
+}
+
+
+#[test]
+fn test_dropout() {
+  let inputs = (0..1000).map(|i| i as f32 / 1000.0 + 0.5).collect::<Vec<_>>();
+  let mut layer = Dropout::new(1000, 0.25);
+  assert_eq!(layer.forward(&inputs).unwrap(), inputs);
+
+  // while training about a quarter of the inputs are dropped and the rest scaled up to make up for them
+  layer.set_training(true);
+  let outputs = radiate::rng::scoped(3, || layer.forward(&inputs).unwrap());
+  let dropped = outputs.iter().filter(|output| **output == 0.0).count();
+  assert!(dropped > 200 && dropped < 300);
+  assert!(outputs.iter().zip(inputs.iter()).all(|(output, input)| *output == 0.0 || (output - input / 0.75).abs() < 1e-5));
+  let errors = layer.backward(&vec![1.0; 1000], 0.1).unwrap();
+  assert!(errors.iter().zip(outputs.iter()).all(|(error, output)| (*error == 0.0) == (*output == 0.0)));
+  layer.set_training(false);
+  assert_eq!(layer.backward(&vec![1.0; 1000], 0.1).unwrap(), vec![1.0; 1000]);
+
+  // the network only drops while it is training, inference is the same as without the layer
+  let data = radiate::data::synthetic::parity(3);
+  let mut net = radiate::rng::scoped(3, || {
+    Neat::new()
+      .input_size(3)
+      .dense(8, Activation::Tanh)
+      .dropout(0.2)
+      .dense(1, Activation::Sigmoid)
+  });
+  assert_eq!(net.layers[1].layer.shape(), (8, 8));
+  let error = |net: &mut Neat| data.inputs.iter().zip(data.targets.iter())
+    .map(|(input, target)| (net.forward(input).unwrap()[0] - target[0]).powi(2))
+    .sum::<f32>();
+  let before = error(&mut net);
+  net.train(&data.inputs, &data.targets, 0.1, Loss::Diff, |epoch, _| epoch == 100).unwrap();
+  assert!(error(&mut net) < before);
+  assert_eq!(error(&mut net), error(&mut net));
+  let loaded: Neat = serde_json::from_str(&serde_json::to_string(&net).unwrap()).unwrap();
+  assert_eq!(loaded.layers[1].as_ref::<Dropout>().probability, 0.2);
+  assert!(!loaded.layers[1].as_ref::<Dropout>().is_training());
+}
+
+
+#[test]
+fn test_norm_layers() {
+  use std::sync::{Arc, RwLock};
+
+  // layer norm gives each sample zero mean and unit variance before gamma and beta
+  let inputs = vec![0.5, -1.0, 2.0, 3.5];
+  let mut layer = Norm::new(4, NormType::Layer);
+  layer.gamma = vec![1.5, 0.5, -1.0, 2.0];
+  layer.beta = vec![0.1, 0.2, 0.3, 0.4];
+  let mut plain = Norm::new(4, NormType::Layer);
+  let normalized = plain.forward(&inputs).unwrap();
+  assert!(normalized.iter().sum::<f32>().abs() < 1e-5);
+  assert!((normalized.iter().map(|x| x * x).sum::<f32>() / 4.0 - 1.0).abs() < 1e-3);
+
+  // stepping back an error of one on every output moves gamma by the gradient of their sum,
+  // and the input errors are that gradient through the sample's mean and variance
+  let sum = |layer: &mut Norm, inputs: &Vec<f32>| layer.forward(inputs).unwrap().iter().sum::<f32>();
+  let weighted = |layer: &mut Norm, inputs: &Vec<f32>| layer.forward(inputs).unwrap().iter().enumerate().map(|(i, x)| x * i as f32).sum::<f32>();
+  let mut nudged = layer.clone();
+  nudged.gamma[2] += 1e-3;
+  let gamma_gradient = (sum(&mut nudged, &inputs) - sum(&mut layer, &inputs)) / 1e-3;
+  let mut moved = inputs.clone();
+  moved[1] += 1e-3;
+  let input_gradient = (weighted(&mut layer, &moved) - weighted(&mut layer, &inputs)) / 1e-3;
+  let before = layer.gamma[2];
+  layer.forward(&inputs).unwrap();
+  layer.backward(&vec![1.0; 4], 1e-3).unwrap();
+  assert!(((layer.gamma[2] - before) / 1e-3 - gamma_gradient).abs() < 1e-2);
+  let mut frozen = layer.clone();
+  frozen.forward(&inputs).unwrap();
+  let errors = frozen.backward(&vec![0.0, 1.0, 2.0, 3.0], 0.0).unwrap();
+  assert!((errors[1] - input_gradient).abs() < 2e-2);
+
+  // batch norm learns running statistics while training and uses them frozen otherwise
+  let mut batch = Norm::new(2, NormType::Batch).momentum(0.05);
+  batch.set_training(true);
+  let samples = (0..2000).map(|i| vec![3.0 + (i as f32).sin(), -2.0 + 2.0 * (i as f32 * 0.7).cos()]).collect::<Vec<_>>();
+  for sample in samples.iter() {
+    batch.forward(sample).unwrap();
+  }
+  assert!((batch.running_mean[0] - 3.0).abs() < 0.2 && (batch.running_mean[1] + 2.0).abs() < 0.3);
+  assert!((batch.running_variance[0] - 0.5).abs() < 0.2 && (batch.running_variance[1] - 2.0).abs() < 0.6);
+  batch.set_training(false);
+  let mean = batch.running_mean.clone();
+  let output = batch.forward(&vec![10.0, 10.0]).unwrap();
+  assert_eq!(batch.running_mean, mean);
+  assert!((output[0] - (10.0 - mean[0]) / (batch.running_variance[0] + 1e-5).sqrt()).abs() < 1e-4);
+
+  // crossing over averages what both parents learned
+  let env = Arc::new(RwLock::new(radiate::default_neat_env()));
+  let child = Norm::crossover(&layer, &plain, Arc::clone(&env), 1.0).unwrap();
+  assert!(child.gamma.iter().zip(layer.gamma.iter()).all(|(child, gamma)| (child - (gamma + 1.0) / 2.0).abs() < 1e-6));
+  assert!(Norm::distance(&layer, &plain, Arc::clone(&env)) > 0.0);
+  assert_eq!(Norm::distance(&layer, &layer, env), 0.0);
+
+  // normalized stacks still train
+  let data = radiate::data::synthetic::parity(3);
+  for norm_type in [NormType::Batch, NormType::Layer] {
+    let mut net = radiate::rng::scoped(3, || {
+      let net = Neat::new().input_size(3);
+      let net = if norm_type == NormType::Batch { net.batch_norm() } else { net.layer_norm() };
+      net.dense(8, Activation::Tanh).dense(1, Activation::Sigmoid)
+    });
+    assert_eq!(net.layers[0].layer_type, LayerType::Norm);
+    let error = |net: &mut Neat| data.inputs.iter().zip(data.targets.iter())
+      .map(|(input, target)| (net.forward(input).unwrap()[0] - target[0]).powi(2))
+      .sum::<f32>();
+    let before = error(&mut net);
+    net.train(&data.inputs, &data.targets, 0.05, Loss::Diff, |epoch, _| epoch == 400).unwrap();
+    let after = error(&mut net);
+    assert!(after.is_finite() && after < before);
+  }
+}
+
+#[test]
+fn test_forward_batch() {
+  let mut neat = Neat::new()
+    .input_size(3)
+    .dense_pool(4, Activation::Sigmoid);
+  add_extra_nodes(&mut neat, 2);
+  let mut neat = neat
+    .lstm(3, 2, Activation::Tanh)
+    .dense(2, Activation::Sigmoid);
+  let mut stepped = neat.clone();
+  let inputs = (0..6).map(|i| vec![i as f32 * 0.1, 0.5, 1.0 - i as f32 * 0.2]).collect::<Vec<_>>();
+
+  // the lstm carries its state through the batch the same as it does through single passes
+  let batch = neat.forward_batch(&inputs).unwrap();
+  let single = inputs.iter().map(|input| stepped.forward(input).unwrap()).collect::<Vec<_>>();
+  assert_eq!(batch.len(), inputs.len());
+  for (one, two) in batch.iter().zip(single.iter()) {
+    assert!(one.iter().zip(two.iter()).all(|(a, b)| (a - b).abs() < 1e-6));
+  }
+  assert!(neat.forward_batch(&[vec![0.0, 1.0]]).is_err());
+}
+
+#[bench]
+fn bench_neat_forward_batch(b: &mut Bencher) {
+  let mut neat = create_neat(50, 20, 10, true);
+  let inputs = (0..100).map(|_| create_inputs(50)).collect::<Vec<_>>();
+  b.iter(|| {
+    neat.forward_batch(&inputs).unwrap();
+  });
+}
+
+#[test]
+fn test_forward_batch_fixed_topology() {
+  let mut neat = Neat::new()
+    .input_size(4)
+    .dense(6, Activation::Relu)
+    .dense(3, Activation::Softmax);
+  let mut stepped = neat.clone();
+  let inputs = (0..5).map(|i| vec![i as f32 * 0.3, -0.5, 1.0, 0.2 * i as f32]).collect::<Vec<_>>();
+
+  // with the ndarray feature this runs through the matrix product, either way it matches single passes
+  let batch = neat.forward_batch(&inputs).unwrap();
+  let single = inputs.iter().map(|input| stepped.forward(input).unwrap()).collect::<Vec<_>>();
+  for (one, two) in batch.iter().zip(single.iter()) {
+    assert!(one.iter().zip(two.iter()).all(|(a, b)| (a - b).abs() < 1e-5));
+  }
+
+  // the neurons are left holding the last input so backprop after a batch still works
+  let error = vec![0.5, -0.25, 0.1];
+  let one = neat.layers[1].layer.backward(&error, 0.1).unwrap();
+  let two = stepped.layers[1].layer.backward(&error, 0.1).unwrap();
+  assert!(one.iter().zip(two.iter()).all(|(a, b)| (a - b).abs() < 1e-5));
+
+  // the weights the batch was multiplied by aren't used again once backprop has stepped them
+  let batch = neat.forward_batch(&inputs).unwrap();
+  let single = inputs.iter().map(|input| stepped.forward(input).unwrap()).collect::<Vec<_>>();
+  for (one, two) in batch.iter().zip(single.iter()) {
+    assert!(one.iter().zip(two.iter()).all(|(a, b)| (a - b).abs() < 1e-5));
+  }
+}
+
+#[bench]
+fn bench_neat_forward_batch_fixed_topology(b: &mut Bencher) {
+  let mut neat = create_neat(50, 20, 10, false);
+  let inputs = (0..100).map(|_| create_inputs(50)).collect::<Vec<_>>();
+  b.iter(|| {
+    neat.forward_batch(&inputs).unwrap();
+  });
+}
+
+#[test]
+fn test_mutation_preview() {
+  let neat = create_neat(3, 0, 2, true);
+  let nodes = neat.layers[0].as_ref::<Dense>().nodes.len();
+  let add_node = Mutation::AddNode { layer: 0, activation: Activation::Relu, direction: NeuronDirection::Forward };
+
+  // the same seed previews the same mutation and the network itself is left alone
+  let preview = neat.preview_mutation(&add_node, 7).unwrap();
+  assert_eq!(preview.changes, neat.preview_mutation(&add_node, 7).unwrap().changes);
+  assert_eq!(neat.layers[0].as_ref::<Dense>().nodes.len(), nodes);
+  assert!(preview.is_structural());
+  let count = |f: fn(&Change) -> bool| preview.changes.iter().filter(|change| f(change)).count();
+  assert_eq!(count(|change| matches!(change, Change::NodeAdded { activation: Activation::Relu, .. })), 1);
+  assert_eq!(count(|change| matches!(change, Change::EdgeAdded { .. })), 2);
+  assert_eq!(count(|change| matches!(change, Change::EdgeDisabled { .. })), 1);
+  assert_eq!(preview.commit().layers[0].as_ref::<Dense>().nodes.len(), nodes + 1);
+
+  let edit = neat.preview_mutation(&Mutation::EditWeights { layer: 0, editable: 0.5, perturb: 2.0 }, 3).unwrap();
+  assert!(!edit.is_empty() && !edit.is_structural());
+
+  let env = NeatEnvironment::new()
+    .set_weight_mutate_rate(1.0)
+    .set_edit_weights(0.1)
+    .set_weight_perturb(1.5)
+    .set_new_node_rate(1.0)
+    .set_new_edge_rate(0.0)
+    .set_reactivate(0.2)
+    .set_activation_functions(vec![Activation::Tanh]);
+  let mutated = neat.preview_mutation(&Mutation::Environment(Box::new(env)), 11).unwrap();
+  assert!(mutated.changes.iter().any(|change| matches!(change, Change::NodeAdded { activation: Activation::Tanh, .. })));
+  assert!(mutated.changes.iter().any(|change| matches!(change, Change::WeightChanged { .. })));
+
+  assert!(neat.preview_mutation(&Mutation::AddEdge { layer: 4 }, 1).is_err());
+  assert!(create_neat(3, 0, 2, false).preview_mutation(&Mutation::AddEdge { layer: 0 }, 1).is_err());
+}
+
+#[test]
+fn test_probe_landscape() {
+  let problem = BenchmarkProblem::new(Benchmark::Xor, 0);
+  let neat = Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid);
+  let original = serde_json::to_string(&neat).unwrap();
+  let probe = LandscapeProbe::new(30).walk(20).seed(9);
+
+  // replacing every weight lands somewhere else nearly every time
+  let replace = Mutation::EditWeights { layer: 0, editable: 1.0, perturb: 1.0 };
+  let report = neat.probe_landscape(&problem, &replace, &probe).unwrap();
+  assert_eq!(report.deltas.len(), 30);
+  assert_eq!(report.walk.len(), 21);
+  assert_eq!(report.walk[0], report.fitness);
+  assert!(report.neutral < 0.1 && report.unchanged == 0.0);
+  assert!(report.improving > 0.0 && report.best_delta > 0.0);
+  assert!(report.ruggedness().is_some());
+  assert!(report.to_string().contains("ruggedness"));
+  // the same seed probes the same mutations and the network is left alone
+  assert_eq!(neat.probe_landscape(&problem, &replace, &probe).unwrap(), report);
+  assert_eq!(serde_json::to_string(&neat).unwrap(), original);
+
+  // the inputs are already connected to the output, so there is no edge to add
+  let flat = neat.probe_landscape(&problem, &Mutation::AddEdge { layer: 0 }, &probe).unwrap();
+  assert_eq!((flat.neutral, flat.unchanged, flat.improving), (1.0, 1.0, 0.0));
+  assert_eq!(flat.ruggedness(), None);
+}
+
+#[test]
+fn test_to_dot() {
+  let mut neat = Neat::new()
+    .input_size(2)
+    .dense_pool(3, Activation::Sigmoid);
+  add_extra_nodes(&mut neat, 1);
+  let neat = neat
+    .lstm(3, 2, Activation::Tanh)
+    .dense(1, Activation::Relu);
+  let dense = neat.layers[0].as_ref::<Dense>();
+  let dot = neat.to_dot(&DotOptions::new());
+
+  // every neuron of the dense layers is drawn, the lstm is a box between them
+  assert!(dot.starts_with("digraph neat {") && dot.trim_end().ends_with('}'));
+  assert_eq!(dot.matches("subgraph cluster_").count(), 3);
+  assert!(dot.matches("l0_n").count() > dense.nodes.len());
+  assert!(dot.contains("hidden 5\\nSigmoid"));
+  assert!(dot.contains("l1_ [shape=box, label=\"LSTM\\n3 -> 2\"]"));
+  assert!(dot.contains("fillcolor=lightblue"));
+
+  // the split edge only shows up when asked for, and nothing is labeled without weights
+  let active = dense.edges.iter().filter(|edge| edge.active).count();
+  let layer = dense.to_dot(&DotOptions::new().weights(false).color_types(false));
+  assert_eq!(layer.matches(" -> ").count(), active);
+  assert!(!layer.contains("label=\"0") && !layer.contains("b=") && !layer.contains("fillcolor"));
+  let layer = dense.to_dot(&DotOptions::new().disabled_edges(true));
+  assert_eq!(layer.matches(" -> ").count(), dense.edges.len());
+  assert_eq!(layer.matches("style=dashed").count(), dense.edges.len() - active);
+}
+
+
+#[test]
+fn test_serialization_is_reproducible() {
+  // two copies built from the same seed and fed the same inputs should serialize to the same
+  // bytes, which only holds if nothing in the layer is kept in a map with a random iteration order
+  let build = || {
+    let mut net = radiate::rng::scoped(5, || create_neat(3, 4, 2, false));
+    for wrap in net.layers.iter_mut() {
+      wrap.layer.add_tracer();
+    }
+    for i in 0..3 {
+      net.forward(&vec![i as f32, 0.5, -0.5]).unwrap();
+    }
+    serde_json::to_string(&net).unwrap()
+  };
+  assert_eq!(build(), build());
+}
+
+
+
+#[test]
+fn test_wiring_cost() {
+  let mut net = Neat::new().input_size(2).dense_pool(2, Activation::Sigmoid);
+  let placement = Placement::line(2, 2);
+  assert_eq!(placement.inputs, vec![vec![-1.0, 0.0], vec![1.0, 0.0]]);
+  let cost = net.wiring_cost(std::slice::from_ref(&placement)).unwrap();
+  assert!((cost - (2.0 + 2.0 * 5f32.sqrt())).abs() < 1e-5);
+
+  // a hidden neuron splitting a connection sits halfway along it, so the wiring is just as long
+  let dense: &mut Dense = net.layers[0].as_mut();
+  dense.add_node(Activation::Sigmoid, NeuronDirection::Forward);
+  let positions = placement.positions(dense).unwrap();
+  let hidden = dense.nodes.iter().find(|node| node.neuron_type == NeuronType::Hidden).unwrap();
+  let split = dense.edges.iter().find(|edge| !edge.active).unwrap();
+  let middle = positions[split.src.index()].iter().zip(positions[split.dst.index()].iter()).map(|(a, b)| (a + b) / 2.0).collect::<Vec<_>>();
+  assert!(positions[hidden.id.index()].iter().zip(middle.iter()).all(|(a, b)| (a - b).abs() < 1e-5));
+  assert!((dense.wiring_cost(&placement).unwrap() - cost).abs() < 1e-5);
+
+  // every input is wired to every output, so swapping the outputs doesn't change anything but bringing them closer does
+  let crossed = Placement::new(placement.inputs.clone(), vec![vec![1.0, 1.0], vec![-1.0, 1.0]]);
+  assert!((crossed.wiring_cost(dense).unwrap() - cost).abs() < 1e-4);
+  let narrow = Placement::new(vec![vec![0.0, 0.0], vec![0.1, 0.0]], vec![vec![0.0, 1.0], vec![0.1, 1.0]]);
+  assert!(narrow.wiring_cost(dense).unwrap() < cost);
+
+  assert_eq!(net.wiring_cost(&[]), Err(NeatError::PlacementMismatch));
+  assert_eq!(net.wiring_cost(&[Placement::line(3, 2)]), Err(NeatError::PlacementMismatch));
+  assert_eq!(net.wiring_cost(&[Placement::new(vec![vec![0.0], vec![1.0, 0.0]], vec![vec![0.0, 1.0]; 2])]), Err(NeatError::PlacementMismatch));
+}
+
+
+
+/// every id is the index of what it names and every edge is linked into the neurons it connects
+fn assert_linked(dense: &Dense) {
+  for (index, node) in dense.nodes.iter().enumerate() {
+    assert_eq!(node.id.index(), index);
+  }
+  assert_eq!(dense.edge_innov_map.len(), dense.edges.len());
+  for (index, edge) in dense.edges.iter().enumerate() {
+    assert_eq!(edge.id.index(), index);
+    assert_eq!(dense.get_edge_by_innov(&edge.innov), Some(edge));
+    assert_eq!(dense.nodes[edge.src.index()].outgoing_edges().contains(&edge.id), edge.active);
+    assert!(dense.nodes[edge.dst.index()].incoming_edges().iter().any(|link| link.id == edge.id && link.src == edge.src));
+  }
+  let links = dense.nodes.iter().map(|node| node.incoming_edges().len()).sum::<usize>();
+  assert_eq!(links, dense.edges.len());
+}
+
+
+#[test]
+fn test_structural_removal() {
+  let neat = radiate::rng::scoped(13, || create_neat(3, 0, 2, true));
+  let dense = neat.layers[0].as_ref::<Dense>();
+  let inputs = create_inputs(3);
+
+  // removing a node takes its edges with it and renumbers the rest
+  let preview = neat.preview_mutation(&Mutation::RemoveNode { layer: 0 }, 5).unwrap();
+  let removed = preview.changes.iter().filter(|change| matches!(change, Change::NodeRemoved { .. })).count();
+  assert!(removed >= 1);
+  assert!(preview.changes.iter().any(|change| matches!(change, Change::EdgeRemoved { .. })));
+  assert!(!preview.changes.iter().any(|change| matches!(change, Change::NodeAdded { .. } | Change::BiasChanged { .. })));
+  let mut shrunk = preview.commit();
+  let after = shrunk.layers[0].as_ref::<Dense>();
+  assert_eq!(after.nodes.len(), dense.nodes.len() - removed);
+  assert_eq!(after.inputs.len(), 3);
+  assert_eq!(after.outputs.len(), 2);
+  assert_linked(after);
+  assert!(shrunk.forward(&inputs).is_ok());
+
+  // removing a connection which was a hidden neuron's last one going out takes the neuron and its
+  // connections in with it. Inputs and outputs are never removed
+  for seed in 0..20 {
+    let mut child = neat.preview_mutation(&Mutation::RemoveEdge { layer: 0 }, seed).unwrap().commit();
+    let after = child.layers[0].as_ref::<Dense>();
+    assert_linked(after);
+    assert_eq!((after.inputs.len(), after.outputs.len()), (3, 2));
+    assert!(after.edges.len() < dense.edges.len());
+    assert_eq!(after.edges.len() == dense.edges.len() - 1, after.nodes.len() == dense.nodes.len());
+    assert_eq!(child.forward(&inputs).unwrap().len(), 2);
+  }
+
+  // a network can be shrunk down to its last edge but no further, and grows back from it
+  let mut net = Neat::new().input_size(1).dense_pool(1, Activation::Sigmoid);
+  let dense: &mut Dense = net.layers[0].as_mut();
+  dense.add_node(Activation::Sigmoid, NeuronDirection::Forward);
+  for _ in 0..5 {
+    dense.remove_edge();
+    dense.remove_node();
+  }
+  assert_eq!(dense.edges.len(), 1);
+  assert_eq!(dense.nodes.len(), 2);
+  assert_linked(dense);
+  dense.add_node(Activation::Sigmoid, NeuronDirection::Forward);
+  assert_eq!(dense.nodes.len(), 3);
+  assert_linked(dense);
+
+  // the environment only removes anything when it is asked to
+  let env = NeatEnvironment::new()
+    .set_weight_mutate_rate(0.0)
+    .set_edit_weights(0.1)
+    .set_weight_perturb(1.5)
+    .set_new_node_rate(0.0)
+    .set_new_edge_rate(0.0)
+    .set_reactivate(0.2)
+    .set_remove_node_rate(1.0)
+    .set_remove_edge_rate(1.0);
+  let mutated = neat.preview_mutation(&Mutation::Environment(Box::new(env.clone())), 3).unwrap();
+  assert!(mutated.changes.iter().any(|change| matches!(change, Change::NodeRemoved { .. })));
+  let mut env = env;
+  env.remove_node_rate = None;
+  env.remove_edge_rate = None;
+  assert!(neat.preview_mutation(&Mutation::Environment(Box::new(env)), 3).unwrap().is_empty());
+}
+
+
+#[test]
+fn test_prune() {
+  let mut neat = radiate::rng::scoped(21, || create_neat(3, 0, 2, true));
+  let inputs = create_inputs(3);
+  let expected = neat.forward(&inputs).unwrap();
+
+  // a hidden neuron whose only connection going out is disabled can't reach an output
+  let dense: &mut Dense = neat.layers[0].as_mut();
+  let hidden = dense.nodes.iter().filter(|node| node.neuron_type == NeuronType::Hidden).count();
+  let dead_end = dense.edges.iter()
+    .find(|edge| edge.active && dense.nodes[edge.src.index()].neuron_type == NeuronType::Hidden)
+    .map(|edge| edge.id)
+    .unwrap();
+  std::sync::Arc::make_mut(&mut dense.edges)[dead_end.index()].disable(std::sync::Arc::make_mut(&mut dense.nodes).as_mut_slice());
+  let disabled = dense.edges.iter().filter(|edge| !edge.active).count();
+  let expected_cut = neat.forward(&inputs).unwrap();
+  assert_ne!(expected, expected_cut);
+
+  // a threshold of 0 leaves the outputs exactly where they were
+  let mut pruned = neat.clone();
+  let first = pruned.prune(0.0);
+  let report = first;
+  let dense = pruned.layers[0].as_ref::<Dense>();
+  assert_linked(dense);
+  assert!(report.nodes >= 1);
+  assert!(report.edges > disabled);
+  assert!(dense.edges.iter().all(|edge| edge.active));
+  assert_eq!(dense.nodes.len(), neat.layers[0].as_ref::<Dense>().nodes.len() - report.nodes);
+  assert_eq!(dense.edges.len(), neat.layers[0].as_ref::<Dense>().edges.len() - report.edges);
+  assert_eq!(pruned.forward(&inputs).unwrap(), expected_cut);
+  assert_eq!(pruned.prune(0.0), PruneReport::default());
+
+  // a disabled edge edited since it was disabled still feeds its weight forward, so it is kept
+  let mut edited = neat.clone();
+  let dense: &mut Dense = edited.layers[0].as_mut();
+  let innov = dense.edges.iter().find(|edge| !edge.active && edge.id != dead_end).map(|edge| edge.innov).unwrap();
+  let edge = dense.edges.iter().position(|edge| edge.innov == innov).unwrap();
+  std::sync::Arc::make_mut(&mut dense.edges)[edge].update_weight(0.5, std::sync::Arc::make_mut(&mut dense.nodes).as_mut_slice());
+  let expected_edited = edited.forward(&inputs).unwrap();
+  edited.prune(0.0);
+  let dense = edited.layers[0].as_ref::<Dense>();
+  assert_linked(dense);
+  assert!(dense.get_edge_by_innov(&innov).is_some_and(|edge| edge.active && edge.weight == 0.5));
+  assert_eq!(edited.forward(&inputs).unwrap(), expected_edited);
+
+  // cutting every connection leaves the outputs running on their biases alone
+  let report = pruned.prune(f32::MAX);
+  let dense = pruned.layers[0].as_ref::<Dense>();
+  assert_linked(dense);
+  assert!(dense.edges.is_empty());
+  assert_eq!(dense.nodes.len(), 5);
+  assert_eq!(report.nodes, hidden - first.nodes);
+  assert_eq!(pruned.forward(&inputs).unwrap().len(), 2);
+}
+
+
+fn graph_of(nodes: usize, edges: &[(usize, usize)]) -> Graph {
+  Graph {
+    nodes: vec![GraphNode { neuron_type: NeuronType::Hidden, activation: Activation::Sigmoid, bias: 0.0 }; nodes],
+    edges: edges.iter().map(|(src, dst)| GraphEdge { src: *src, dst: *dst, weight: 1.0 }).collect()
+  }
+}
+
+
+#[test]
+fn test_graph_stats() {
+  // two triangles joined by a single edge split into a community each
+  let stats = graph_of(6, &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (2, 3)]).stats();
+  assert_eq!(stats.communities, vec![0, 0, 0, 1, 1, 1]);
+  assert_eq!(stats.community_count, 2);
+  assert!((stats.modularity - (6.0 / 7.0 - 0.5)).abs() < 1e-5);
+  assert!((stats.average_path_length - 27.0 / 15.0).abs() < 1e-5);
+  assert_eq!(stats.degrees, vec![2, 2, 3, 3, 2, 2]);
+  assert_eq!(stats.max_degree, 3);
+  assert!(stats.hubs.is_empty());
+
+  // the middle of a star is a hub, and a graph without edges has nothing to measure
+  let stats = graph_of(6, &[(0, 1), (0, 2), (0, 3), (4, 0), (5, 0)]).stats();
+  assert_eq!(stats.hubs, vec![0]);
+  assert!((stats.average_path_length - 25.0 / 15.0).abs() < 1e-5);
+  let stats = graph_of(3, &[]).stats();
+  assert_eq!((stats.modularity, stats.average_path_length, stats.community_count), (0.0, 0.0, 3));
+
+  // a network's report has the active topology of each dense layer and survives being exported
+  let net = radiate::rng::scoped(3, || create_neat(3, 4, 2, true));
+  let report = net.graph_report();
+  assert_eq!(report.layers.iter().map(|layer| layer.layer).collect::<Vec<_>>(), vec![0, 1]);
+  for layer in report.layers.iter() {
+    let dense = net.layers[layer.layer].as_ref::<Dense>();
+    assert_eq!(layer.graph.nodes.len(), dense.nodes.len());
+    assert_eq!(layer.graph.edges.len(), dense.edges.iter().filter(|edge| edge.active).count());
+    assert_eq!(layer.stats, layer.graph.stats());
+  }
+  let json = serde_json::to_string(&report).unwrap();
+  assert_eq!(serde_json::from_str::<GraphReport>(&json).unwrap(), report);
+  assert_eq!(report.to_string().lines().count(), 3);
+}
+
+
+#[test]
+fn test_recurrent_edges() {
+  let mut net = radiate::rng::scoped(8, || {
+    let mut net = Neat::new().input_size(2).dense_pool(1, Activation::Tanh);
+    let dense: &mut Dense = net.layers[0].as_mut();
+    for _ in 0..3 {
+      dense.add_node(Activation::Tanh, NeuronDirection::Forward);
+    }
+    // a feed forward layer gives up on connections closing a cycle, one allowing recurrent edges makes them
+    let mut forward = dense.clone();
+    for _ in 0..50 {
+      forward.add_edge();
+      dense.add_recurrent_edge();
+    }
+    assert!(forward.edges.iter().all(|edge| !edge.recurrent));
+    net
+  });
+  let dense = net.layers[0].as_ref::<Dense>();
+  assert!(dense.edges.iter().any(|edge| edge.recurrent && edge.active));
+  assert!(!dense.to_dot(&DotOptions::default()).is_empty());
+
+  // the recurrent edges carry the values of the pass before until the network is reset
+  let inputs = vec![0.5, -0.3];
+  let first = net.forward(&inputs).unwrap();
+  assert_ne!(net.forward(&inputs).unwrap(), first);
+  net.reset();
+  assert_eq!(net.forward(&inputs).unwrap(), first);
+
+  // the compiled network carries the same values over
+  let sequence = (0..6).map(|step| vec![step as f32 * 0.2 - 0.5, 0.3]).collect::<Vec<_>>();
+  let mut compiled = net.compile().unwrap();
+  for _ in 0..2 {
+    net.reset();
+    compiled.reset();
+    for inputs in sequence.iter() {
+      let (expected, actual) = (net.forward(inputs).unwrap(), compiled.forward(inputs).unwrap());
+      assert!((expected[0] - actual[0]).abs() < 1e-5);
+    }
+  }
+  assert_eq!(analysis::output_bounds(&compiled, &[Interval::new(0.0, 1.0); 2]).unwrap_err(), NeatError::Recurrent);
+
+  // splitting edges and backprop don't follow the recurrent edges around their cycles
+  let dense: &mut Dense = net.layers[0].as_mut();
+  for _ in 0..10 {
+    dense.add_node(Activation::Tanh, NeuronDirection::Forward);
+  }
+  for inputs in sequence.iter() {
+    dense.forward(inputs).unwrap();
+    dense.backward(&vec![0.5], 0.1).unwrap();
+  }
+
+  // previews say which new edges are recurrent
+  let recurrent = (0..20)
+    .filter_map(|seed| net.preview_mutation(&Mutation::AddRecurrentEdge { layer: 0 }, seed).ok())
+    .flat_map(|preview| preview.changes)
+    .any(|change| matches!(change, Change::EdgeAdded { recurrent: true, .. }));
+  assert!(recurrent);
+}
+
+
+#[test]
+fn test_robustness() {
+  let inputs = vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]];
+  let targets = vec![vec![0.0], vec![1.0], vec![1.0], vec![0.0]];
+  let data = DataSet::new(inputs.clone(), targets);
+  let problem = Supervised::new(data.clone(), Loss::MSE);
+  let mut net = radiate::rng::scoped(3, || create_neat(2, 3, 1, false));
+
+  // the same seed always makes the same copies
+  let robustness = Robustness::new(4).augment(Augmentation::Jitter(0.3)).augment(Augmentation::Scale(0.8, 1.2)).seed(7);
+  let copies = robustness.copies(&inputs);
+  assert_eq!(copies, robustness.copies(&inputs));
+  assert_eq!((copies.len(), copies[0].len(), copies[0][0].len()), (4, 4, 2));
+  assert!(copies.iter().all(|copy| *copy != inputs));
+
+  // worst takes the worst of each input and its copies, mean the mean over all of them
+  let report = RobustnessReport::new(&[1.0, 2.0], &[vec![3.0, 0.0], vec![0.0, 0.0]]);
+  assert_eq!((report.clean, report.mean, report.worst), (1.5, 1.0, 2.5));
+  let report = problem.robustness_report(&mut net, &robustness).unwrap();
+  assert_eq!((report.samples, report.copies), (4, 4));
+  assert!((report.clean - problem.loss(&mut net).unwrap()).abs() < 1e-6);
+  assert!(report.worst >= report.mean && report.worst >= report.clean);
+  let unchanged = problem.robustness_report(&mut net, &Robustness::new(3)).unwrap();
+  assert!((unchanged.mean - unchanged.clean).abs() < 1e-6 && (unchanged.worst - unchanged.clean).abs() < 1e-6);
+
+  // the aggregated loss is the fitness selected on
+  let worst = problem.clone().robust(robustness.clone().aggregate(Aggregate::Worst));
+  assert!((Problem::<Neat>::solve(&worst, &mut net) - 1.0 / (1.0 + report.worst)).abs() < 1e-6);
+  let fitness = terms::robust::<Neat>(data, Loss::MSE, robustness);
+  assert!((Problem::<Neat>::solve(&fitness, &mut net) - 1.0 / (1.0 + report.mean)).abs() < 1e-6);
+}
+
+
+
+#[test]
+fn test_adversarial_search() {
+  let inputs = (0..5).map(|i| vec![i as f32 / 4.0, 1.0 - i as f32 / 4.0]).collect::<Vec<_>>();
+  let mut net = radiate::rng::scoped(3, || create_neat(2, 3, 1, false));
+
+  // a tiny box around each input can't change anything, a large one flips at least one of them
+  let report = AdversarialSearch::new(1e-6).run(&mut net, &inputs).unwrap();
+  assert!(report.found.is_empty() && report.robust_fraction() == 1.0 && report.smallest().is_none());
+  for search in [AdversarialSearch::new(10.0).random(200), AdversarialSearch::new(10.0).gradient(50, 0.5)].iter() {
+    let report = search.run(&mut net, &inputs).unwrap();
+    assert!(!report.found.is_empty(), "{}", report);
+    assert_eq!(report, search.run(&mut net, &inputs).unwrap());
+    for adversary in report.found.iter() {
+      let perturbed = inputs[adversary.sample].iter().zip(adversary.perturbation.iter()).map(|(x, d)| x + d).collect::<Vec<_>>();
+      assert!(adversary.size <= 10.0 && adversary.size > 0.0);
+      assert_ne!(radiate::data::dataset::class_label(&adversary.before), radiate::data::dataset::class_label(&net.predict(&perturbed).unwrap()));
+      assert_eq!(adversary.after, net.predict(&perturbed).unwrap());
+    }
+    assert!(report.smallest().unwrap().size <= report.found[0].size);
+  }
+
+  // a controller is destabilized once an output moves more than the tolerance, compiled networks work the same
+  let mut compiled = net.compile().unwrap();
+  let deviation = AdversarialSearch::new(10.0).gradient(50, 0.5).target(Target::Deviation(0.01));
+  let report = deviation.run(&mut compiled, &inputs).unwrap();
+  assert!(!report.found.is_empty());
+  assert!(report.found.iter().all(|a| a.before.iter().zip(a.after.iter()).any(|(b, c)| (b - c).abs() > 0.01)));
+}
+
+
+
+#[test]
+fn test_bias() {
+  use std::sync::{Arc, RwLock};
+  let biases = |net: &Neat| net.layers.iter().flat_map(|wrap| wrap.as_ref::<Dense>().nodes.iter().map(|node| node.bias)).collect::<Vec<_>>();
+  let one = radiate::rng::scoped(1, || create_neat(2, 0, 2, true));
+  let two = radiate::rng::scoped(2, || create_neat(2, 0, 2, true));
+  assert_eq!(one.bias, Bias::Neuron);
+  assert!(biases(&one).iter().any(|bias| *bias != 0.0));
+
+  // without biases a layer of sigmoids gives exactly 0.5 for inputs of 0, even after training
+  let mut off = radiate::rng::scoped(1, || create_neat(2, 0, 2, false)).bias(Bias::Off);
+  assert!(biases(&off).iter().all(|bias| *bias == 0.0));
+  assert_eq!(off.forward(&vec![0.0, 0.0]).unwrap(), vec![0.5, 0.5]);
+  off.fit(&[(vec![1.0, 0.0], vec![1.0, 0.0]), (vec![0.0, 1.0], vec![0.0, 1.0])], 1, 5, 0.1, Loss::MSE).unwrap();
+  assert!(biases(&off).iter().all(|bias| *bias == 0.0));
+  assert_eq!(off.forward(&vec![0.0, 0.0]).unwrap(), vec![0.5, 0.5]);
+
+  // neurons added later start without a bias too, and can't be pruned into the biases
+  let mut pool = radiate::rng::scoped(1, || create_neat(2, 0, 2, true)).bias(Bias::Off);
+  add_extra_nodes(&mut pool, 1);
+  assert!(biases(&pool).iter().all(|bias| *bias == 0.0));
+  let layer: &mut Dense = pool.layers[0].as_mut();
+  let hidden = layer.nodes.last().unwrap().id;
+  assert!(matches!(layer.prune_neuron(hidden, 0.5), Err(NeatError::Unsupported(_))));
+  let loaded: Neat = serde_json::from_str(&serde_json::to_string(&off).unwrap()).unwrap();
+  assert_eq!(loaded.bias, Bias::Off);
+
+  // crossing over takes the bias of each output from either parent
+  let env = Arc::new(RwLock::new(radiate::default_neat_env()));
+  let child = radiate::rng::scoped(3, || Neat::crossover(&one, &two, Arc::clone(&env), 1.0)).unwrap();
+  let dense = child.layers[0].as_ref::<Dense>();
+  for id in dense.outputs.iter() {
+    let bias = dense.nodes[id.index()].bias;
+    assert!(bias == one.layers[0].as_ref::<Dense>().nodes[id.index()].bias || bias == two.layers[0].as_ref::<Dense>().nodes[id.index()].bias);
+  }
+
+  // the environment turns the biases of every network it evolves off
+  let env = Arc::new(RwLock::new(radiate::default_neat_env().set_bias(Bias::Off)));
+  let child = Neat::crossover(&one, &two, env, 0.0).unwrap();
+  assert_eq!(child.bias, Bias::Off);
+  assert!(biases(&child).iter().all(|bias| *bias == 0.0));
+}
+
+
+
+#[test]
+fn test_approximate_scoring() {
+  let data = DataSet::new(vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]], vec![vec![0.0], vec![1.0], vec![1.0], vec![0.0]]);
+  let problem = Supervised::new(data, Loss::MSE);
+
+  // the best score is always a cutoff, the line between the survivors is one when they're kept by rank
+  let scores = [1.0, 0.95, 0.72, 0.3, 0.0];
+  assert_eq!(ApproximateScoring::<Neat>::new(0.12, |_| Box::new(|_| {})).near(&scores, Some(2)), vec![0, 1, 2]);
+  assert_eq!(ApproximateScoring::<Neat>::new(0.12, |_| Box::new(|_| {})).near(&scores, None), vec![0, 1]);
+  assert_eq!(ApproximateScoring::<Neat>::new(0.0, |_| Box::new(|_| {})).near(&scores, Some(2)), vec![0]);
+
+  // keeping the best of each species, the line between each species' best and its runner-up is a cutoff
+  let scores = [1.0, 0.9, 0.5, 0.45, 0.0];
+  assert_eq!(ApproximateScoring::<Neat>::new(0.06, |_| Box::new(|_| {})).near_species(&scores, &[0, 0, 1, 1, 2]), vec![0, 1, 2, 3]);
+  assert_eq!(ApproximateScoring::<Neat>::new(0.06, |_| Box::new(|_| {})).near_species(&scores, &[0, 1, 1, 2, 2]), vec![0]);
+  assert_eq!(ApproximateScoring::<Neat>::new(0.06, |_| Box::new(|_| {})).near_species(&scores, &[0, 1, 2, 3, 4]), vec![0]);
+
+  // members are scored with fast activations and put back, the ones near a cutoff are scored exactly
+  let mut population = common::xor_population(20, problem.clone())
+    .survivor_criteria(SurvivalCriteria::TopNumber(5))
+    .approximate_scoring(Neat::approximate_scoring(Precision::Fast, 0.05))
+    .parallel_eval(false)
+    .seed(4)
+    .populate_base();
+  population.evaluate();
+  let (mut exact, mut fast) = (0, 0);
+  let mut best = (f32::NEG_INFINITY, 0.0);
+  for cont in population.members_mut().iter() {
+    let member = cont.member.read().unwrap().clone();
+    assert_eq!(member.precision, Precision::Exact);
+    let exact_score = Problem::<Neat>::solve(&problem, &mut member.clone());
+    let fast_score = Problem::<Neat>::solve(&problem, &mut member.precision(Precision::Fast));
+    assert!(cont.fitness_score == exact_score || cont.fitness_score == fast_score);
+    exact += (cont.fitness_score == exact_score) as usize;
+    fast += (cont.fitness_score == fast_score && exact_score != fast_score) as usize;
+    if cont.fitness_score > best.0 {
+      best = (cont.fitness_score, exact_score);
+    }
+  }
+  assert_eq!(best.0, best.1);
+  assert!(exact > 0 && fast > 0, "{} {}", exact, fast);
+  assert!(population.train().is_some());
+
+  // keeping the best of each species, the cutoffs come from the species the members are placed in
+  let mut population = common::xor_population(20, problem.clone())
+    .survivor_criteria(SurvivalCriteria::Fittest)
+    .approximate_scoring(Neat::approximate_scoring(Precision::Fast, 0.05))
+    .parallel_eval(false)
+    .seed(4)
+    .populate_base();
+  for _ in 0..3 {
+    population.evaluate();
+    let best = population.members_mut().iter()
+      .max_by(|a, b| a.fitness_score.total_cmp(&b.fitness_score))
+      .map(|cont| (cont.fitness_score, Problem::<Neat>::solve(&problem, &mut cont.member.read().unwrap().clone())))
+      .unwrap();
+    assert_eq!(best.0, best.1);
+    assert!(population.end_generation().is_some());
+  }
+}
+
+
+
+#[test]
+fn test_activation_mutation() {
+  let neat = radiate::rng::scoped(5, || create_neat(3, 0, 2, true));
+  let hidden = |net: &Neat| net.layers[0].as_ref::<Dense>().nodes.iter()
+    .filter(|node| node.neuron_type == NeuronType::Hidden)
+    .map(|node| node.activation())
+    .collect::<Vec<_>>();
+  assert!(hidden(&neat).iter().all(|activation| *activation == Activation::Sigmoid));
+
+  // a hidden neuron is given one of the other functions, never softmax
+  let mutation = Mutation::MutateActivation { layer: 0, activations: vec![Activation::Sigmoid, Activation::Swish, Activation::Softmax] };
+  let preview = neat.preview_mutation(&mutation, 2).unwrap();
+  assert!(preview.is_structural());
+  assert!(matches!(preview.changes.as_slice(), [Change::ActivationChanged { before: Activation::Sigmoid, after: Activation::Swish, .. }]));
+  let changed = preview.commit();
+  assert_eq!(hidden(&changed).iter().filter(|activation| **activation == Activation::Swish).count(), 1);
+  let unchanged = Mutation::MutateActivation { layer: 0, activations: vec![Activation::Sigmoid, Activation::Softmax] };
+  assert!(neat.preview_mutation(&unchanged, 2).unwrap().is_empty());
+
+  // the environment swaps activations at its rate, which is never by default
+  let env = radiate::default_neat_env().set_new_node_rate(0.0).set_new_edge_rate(0.0).set_weight_mutate_rate(0.0);
+  assert_eq!(env.activation_mutate_rate, None);
+  assert!(neat.preview_mutation(&Mutation::Environment(Box::new(env.clone())), 4).unwrap().is_empty());
+  let env = env.set_activation_mutate_rate(1.0).set_activation_functions(vec![Activation::Gaussian]);
+  let preview = neat.preview_mutation(&Mutation::Environment(Box::new(env)), 4).unwrap();
+  assert!(matches!(preview.changes.as_slice(), [Change::ActivationChanged { after: Activation::Gaussian, .. }]));
+
+  // swish bottoms out below zero, so its bounds reach past the ends of the inputs
+  let mut net = Neat::new().input_size(1).dense(1, Activation::Swish);
+  net.visit_weights_mut(|_, _, weight| *weight = 1.0);
+  let dense: &mut Dense = net.layers[0].as_mut();
+  std::sync::Arc::make_mut(&mut dense.nodes).last_mut().unwrap().bias = 0.0;
+  let bounds = analysis::output_bounds(&net.compile().unwrap(), &[Interval::new(-3.0, 1.0)]).unwrap();
+  assert!((bounds[0].low - Activation::Swish.activate(-1.278_464_5)).abs() < 1e-6);
+  assert!((bounds[0].high - Activation::Swish.activate(1.0)).abs() < 1e-6);
+}
+
+
+
+#[test]
+fn test_weight_mutation() {
+  let weights = |net: &Neat| {
+    let dense = net.layers[0].as_ref::<Dense>();
+    dense.edges.iter().map(|edge| edge.weight).chain(dense.nodes.iter().map(|node| node.bias)).collect::<Vec<_>>()
+  };
+  let neat = radiate::rng::scoped(7, || create_neat(3, 0, 2, false));
+  let before = weights(&neat);
+  let env = radiate::default_neat_env().set_new_node_rate(0.0).set_new_edge_rate(0.0).set_weight_mutate_rate(1.0);
+  let mutate = |env: NeatEnvironment, seed: u64| weights(&neat.preview_mutation(&Mutation::Environment(Box::new(env)), seed).unwrap().commit());
+
+  // perturbing nudges every weight by a little gaussian noise, replacing throws each one away
+  let perturbed = mutate(env.clone().set_weight_mutation(WeightMutation::new(1.0, 0.01, 0.0)), 1);
+  assert!(perturbed.iter().zip(before.iter()).all(|(after, before)| (after - before).abs() < 0.1));
+  assert!(perturbed.iter().zip(before.iter()).any(|(after, before)| after != before));
+  let replaced = mutate(env.clone().set_weight_mutation(WeightMutation::new(0.0, 0.01, 1.0).replace_range(5.0)), 1);
+  assert!(replaced.iter().all(|weight| (-5.0..5.0).contains(weight)));
+  assert!(replaced.iter().any(|weight| weight.abs() > 1.0));
+  assert_eq!(mutate(env.clone().set_weight_mutation(WeightMutation::new(0.0, 0.01, 0.0)), 1), before);
+
+  // either way of editing is kept within the clamp
+  let clamped = mutate(env.clone().set_weight_mutation(WeightMutation::new(0.0, 0.01, 1.0).replace_range(5.0)).set_weight_clamp(-0.5, 0.5), 1);
+  assert!(clamped.iter().all(|weight| (-0.5..=0.5).contains(weight)));
+  let clamped = mutate(env.clone().set_weight_perturb(10.0).set_weight_clamp(-0.5, 0.5), 1);
+  assert!(clamped.iter().all(|weight| (-0.5..=0.5).contains(weight)));
+
+  // the split mutation stands in for edit_weights and weight_perturb, which can be left out
+  let mut split = env.set_weight_mutation(WeightMutation::new(0.9, 0.1, 0.05));
+  split.edit_weights = None;
+  split.weight_perturb = None;
+  assert!(split.validate().is_ok());
+  assert_ne!(mutate(split.clone(), 2), before);
+  split.weight_mutation = None;
+  assert_eq!(split.validate().unwrap_err().key(), Some("edit_weights"));
+}
+
+
+
+#[test]
+fn test_innovation_counter() {
+  use std::sync::{Arc, RwLock};
+  let innovs = |net: &Neat| net.layers[0].as_ref::<Dense>().edges.iter().map(|edge| edge.innov).collect::<Vec<_>>();
+  let base = radiate::rng::scoped(3, || Neat::new().input_size(1).dense_pool(1, Activation::Sigmoid));
+  assert_eq!(innovs(&base).len(), 1);
+  let env = radiate::default_neat_env().set_new_node_rate(1.0).set_new_edge_rate(0.0).set_weight_mutate_rate(0.0);
+  let split = |env: &NeatEnvironment, seed: u64| {
+    let env = Arc::new(RwLock::new(env.clone()));
+    radiate::rng::scoped(seed, || Neat::crossover(&base, &base, env, 0.0)).unwrap()
+  };
+
+  // without a counter every new edge is a new innovation, with one splitting the same edge marks the new edges the same
+  let (one, two) = (split(&env, 1), split(&env, 2));
+  assert_eq!(innovs(&one).len(), 3);
+  assert_ne!(innovs(&one)[1..], innovs(&two)[1..]);
+  let counter = Arc::new(InnovationCounter::new(7));
+  let mut marked = env.clone().set_innovation_counter(Arc::clone(&counter));
+  let (one, two) = (split(&marked, 1), split(&marked, 2));
+  assert_eq!(innovs(&one), innovs(&two));
+  assert_eq!(counter.issued(), 2);
+  let edge = innovs(&base)[0];
+  assert_eq!(counter.lookup(&Structure::SplitIn { edge }), Some(innovs(&one)[1]));
+  assert_eq!(counter.markings().len(), 2);
+  // the new neuron is known by the edge made into it wherever it sits in the layer
+  let hidden = |net: &Neat| {
+    let dense = net.layers[0].as_ref::<Dense>();
+    let node = dense.nodes.iter().find(|node| node.neuron_type == NeuronType::Hidden).unwrap();
+    dense.node_key(node.id)
+  };
+  assert_eq!(hidden(&one), NodeKey::Split(innovs(&one)[1]));
+  assert_eq!(hidden(&one), hidden(&two));
+  // counters with the same salt mark the same structure the same, a different salt doesn't
+  let output = NodeKey::Output(0);
+  let connect = Structure::Connect { src: hidden(&one), dst: output, recurrent: false };
+  assert_eq!(InnovationCounter::new(7).innovation(connect), InnovationCounter::new(7).innovation(connect));
+  assert_ne!(InnovationCounter::new(7).innovation(connect), InnovationCounter::new(8).innovation(connect));
+
+  // splitting different edges in the same generation gives different neurons, so connections out of them aren't matched up
+  let wide = radiate::rng::scoped(3, || Neat::new().input_size(4).dense_pool(1, Activation::Sigmoid));
+  let wide_env = Arc::new(RwLock::new(env.clone().set_innovation_counter(Arc::new(InnovationCounter::new(7)))));
+  let children = (0..8)
+    .map(|seed| radiate::rng::scoped(seed, || Neat::crossover(&wide, &wide, Arc::clone(&wide_env), 0.0)).unwrap())
+    .collect::<Vec<_>>();
+  let split_edge = |net: &Neat| net.layers[0].as_ref::<Dense>().edges.iter().find(|edge| !edge.active).unwrap().innov;
+  for one in children.iter() {
+    for two in children.iter() {
+      assert_eq!(split_edge(one) == split_edge(two), hidden(one) == hidden(two));
+    }
+  }
+  assert!(children.iter().any(|child| split_edge(child) != split_edge(&children[0])));
+
+  // the next generation the same split is a new innovation
+  marked.advance(1);
+  assert_eq!(counter.generation(), 1);
+  assert_eq!(counter.lookup(&Structure::SplitIn { edge }), None);
+  let three = split(&marked, 1);
+  assert_ne!(innovs(&three)[1..], innovs(&one)[1..]);
+  assert_eq!(counter.issued(), 4);
+
+  // threads marking the same structure all get the one innovation number
+  let marks = (0..8)
+    .map(|_| {
+      let counter = Arc::clone(&counter);
+      std::thread::spawn(move || counter.innovation(connect))
+    })
+    .map(|handle| handle.join().unwrap())
+    .collect::<Vec<_>>();
+  assert!(marks.iter().all(|innov| *innov == marks[0]));
+  assert_eq!(counter.issued(), 5);
+  counter.reset();
+  assert_eq!((counter.issued(), counter.generation(), counter.lookup(&connect)), (0, 0, None));
+}
@@ -0,0 +1,93 @@
+extern crate radiate;
+extern crate rand;
+
+use rand::Rng;
+use radiate::NoveltyArchive;
+
+
+
+fn brute_force(points: &[Vec<f32>], target: &[f32], k: usize) -> Vec<f32> {
+    let mut distances = points.iter()
+        .map(|point| point.iter().zip(target.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f32>().sqrt())
+        .collect::<Vec<_>>();
+    distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    distances.truncate(k);
+    distances
+}
+
+
+#[test]
+fn test_nearest_matches_brute_force() {
+    let mut r = rand::thread_rng();
+    let mut archive = NoveltyArchive::new(5, 0.0);
+    for _ in 0..2000 {
+        archive.insert((0..3).map(|_| r.gen_range(-10.0, 10.0)).collect());
+    }
+    assert_eq!(archive.len(), 2000);
+
+    for _ in 0..100 {
+        let target = (0..3).map(|_| r.gen_range(-12.0, 12.0)).collect::<Vec<f32>>();
+        let expected = brute_force(archive.behaviors(), &target, 5);
+        let actual = archive.nearest(&target, 5);
+        assert_eq!(actual.len(), 5);
+        for ((index, distance), expected) in actual.iter().zip(expected.iter()) {
+            assert!((distance - expected).abs() < 1e-4);
+            assert!((brute_force(&[archive.behaviors()[*index].clone()], &target, 1)[0] - distance).abs() < 1e-4);
+        }
+        let novelty = expected.iter().sum::<f32>() / 5.0;
+        assert!((archive.novelty(&target) - novelty).abs() < 1e-3);
+    }
+}
+
+
+#[test]
+fn test_evaluate_archives_novel_behaviors() {
+    let mut archive = NoveltyArchive::new(1, 1.0);
+    assert!(archive.novelty(&[0.0, 0.0]).is_infinite());
+    archive.evaluate(vec![0.0, 0.0]);
+    assert_eq!(archive.evaluate(vec![0.5, 0.0]), 0.5);
+    assert_eq!(archive.len(), 1);
+    assert_eq!(archive.evaluate(vec![3.0, 4.0]), 5.0);
+    assert_eq!(archive.len(), 2);
+}
+
+
+#[test]
+#[should_panic(expected = "Behavior descriptors must all be the same length")]
+fn test_novelty_of_mismatched_behavior() {
+    let mut archive = NoveltyArchive::new(1, 0.0);
+    for i in 0..40 {
+        archive.insert(vec![i as f32, 0.0, 1.0]);
+    }
+    archive.novelty(&[1.0, 2.0]);
+}
+
+
+#[test]
+fn test_save_load_and_merge() {
+    let mut one = NoveltyArchive::new(1, 1.0);
+    for i in 0..50 {
+        one.insert(vec![i as f32 * 2.0, 0.0]);
+    }
+    let path = std::env::temp_dir().join("radiate_novelty_test.json");
+    one.save(path.to_str().unwrap()).unwrap();
+    let loaded = NoveltyArchive::load(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).ok();
+    assert_eq!(loaded.behaviors(), one.behaviors());
+    assert_eq!(loaded.nearest(&[7.5, 0.0], 2), one.nearest(&[7.5, 0.0], 2));
+
+    // half of these are already in the first archive
+    let mut two = NoveltyArchive::new(1, 1.0);
+    for i in 0..50 {
+        two.insert(vec![i as f32 * 2.0, if i % 2 == 0 { 0.0 } else { 5.0 }]);
+    }
+    assert_eq!(one.merge(&two).unwrap(), 25);
+    assert_eq!(one.len(), 75);
+
+    let three = {
+        let mut three = NoveltyArchive::new(1, 1.0);
+        three.insert(vec![0.0]);
+        three
+    };
+    assert!(one.merge(&three).is_err());
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "onnx")]
+
+extern crate radiate;
+extern crate prost;
+
+use std::collections::HashMap;
+use prost::Message;
+use radiate::prelude::*;
+use radiate::models::neat::direction::NeuronDirection;
+use radiate::onnx::proto::{ModelProto, NodeProto, AttributeProto};
+
+
+
+/// a row major tensor of up to two dimensions
+#[derive(Debug, Clone)]
+struct Tensor {
+    shape: Vec<usize>,
+    data: Vec<f32>
+}
+
+
+impl Tensor {
+    fn rows(&self) -> usize {
+        if self.shape.len() == 2 { self.shape[0] } else { 1 }
+    }
+
+    fn cols(&self) -> usize {
+        self.shape.last().copied().unwrap_or(1)
+    }
+
+    /// the value at (row, col) broadcast the way numpy does for up to two dimensions
+    fn at(&self, row: usize, col: usize) -> f32 {
+        let row = if self.rows() == 1 { 0 } else { row };
+        let col = if self.cols() == 1 { 0 } else { col };
+        self.data[row * self.cols() + col]
+    }
+
+    fn zip(&self, other: &Tensor, f: impl Fn(f32, f32) -> f32) -> Tensor {
+        let (rows, cols) = (self.rows().max(other.rows()), self.cols().max(other.cols()));
+        let data = (0..rows).flat_map(|r| (0..cols).map(move |c| (r, c))).map(|(r, c)| f(self.at(r, c), other.at(r, c))).collect();
+        Tensor { shape: vec![rows, cols], data }
+    }
+
+    fn map(&self, f: impl Fn(f32) -> f32) -> Tensor {
+        Tensor { shape: self.shape.clone(), data: self.data.iter().map(|x| f(*x)).collect() }
+    }
+
+    fn by_rows(&self, f: impl Fn(&[f32]) -> Vec<f32>) -> Tensor {
+        let rows = self.data.chunks(self.cols()).map(f).collect::<Vec<_>>();
+        Tensor { shape: vec![self.rows(), rows[0].len()], data: rows.concat() }
+    }
+}
+
+
+fn attribute<'a>(node: &'a NodeProto, name: &str) -> Option<&'a AttributeProto> {
+    node.attribute.iter().find(|attribute| attribute.name == name)
+}
+
+
+/// evaluate the ops the export writes
+fn run(model: &ModelProto, feeds: Vec<(&str, Tensor)>) -> HashMap<String, Tensor> {
+    let graph = model.graph.as_ref().unwrap();
+    let mut values = feeds.into_iter().map(|(name, tensor)| (name.to_string(), tensor)).collect::<HashMap<_, _>>();
+    for init in graph.initializer.iter() {
+        let data = match init.data_type {
+            7 => init.int64_data.iter().map(|x| *x as f32).collect(),
+            _ => init.float_data.clone()
+        };
+        values.insert(init.name.clone(), Tensor { shape: init.dims.iter().map(|d| *d as usize).collect(), data });
+    }
+    for node in graph.node.iter() {
+        let input = |i: usize| values[&node.input[i]].clone();
+        let x = input(0);
+        let result = match node.op_type.as_str() {
+            "MatMul" => {
+                let w = input(1);
+                let (n, k, m) = (x.rows(), x.cols(), w.cols());
+                let data = (0..n).flat_map(|r| (0..m).map(move |c| (r, c)))
+                    .map(|(r, c)| (0..k).map(|i| x.at(r, i) * w.data[i * m + c]).sum())
+                    .collect();
+                Tensor { shape: vec![n, m], data }
+            },
+            "Add" => x.zip(&input(1), |a, b| a + b),
+            "Sub" => x.zip(&input(1), |a, b| a - b),
+            "Mul" => x.zip(&input(1), |a, b| a * b),
+            "Div" => x.zip(&input(1), |a, b| a / b),
+            "Max" => x.zip(&input(1), f32::max),
+            "Sigmoid" => x.map(|a| 1.0 / (1.0 + (-a).exp())),
+            "Tanh" => x.map(f32::tanh),
+            "Relu" => x.map(|a| a.max(0.0)),
+            "Elu" => {
+                let alpha = attribute(node, "alpha").unwrap().f;
+                x.map(|a| if a >= 0.0 { a } else { alpha * (a.exp() - 1.0) })
+            },
+            "Sin" => x.map(f32::sin),
+            "Exp" => x.map(f32::exp),
+            "Neg" => x.map(|a| -a),
+            "Abs" => x.map(f32::abs),
+            "Sqrt" => x.map(f32::sqrt),
+            "Identity" => x,
+            "Concat" => {
+                let parts = (0..node.input.len()).map(input).collect::<Vec<_>>();
+                let cols = parts.iter().map(Tensor::cols).sum();
+                let data = (0..x.rows()).flat_map(|r| parts.iter().flat_map(move |p| (0..p.cols()).map(move |c| p.at(r, c)))).collect();
+                Tensor { shape: vec![x.rows(), cols], data }
+            },
+            "Gather" => {
+                let indices = input(1);
+                x.by_rows(|row| indices.data.iter().map(|i| row[*i as usize]).collect())
+            },
+            "Softmax" => x.by_rows(|row| {
+                let max = row.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                let exp = row.iter().map(|a| (a - max).exp()).collect::<Vec<_>>();
+                let sum = exp.iter().sum::<f32>();
+                exp.iter().map(|a| a / sum).collect()
+            }),
+            "ReduceMean" => x.by_rows(|row| vec![row.iter().sum::<f32>() / row.len() as f32]),
+            other => panic!("unexpected op {}", other)
+        };
+        values.insert(node.output[0].clone(), result);
+    }
+    values
+}
+
+
+fn batch(inputs: &[Vec<f32>]) -> Tensor {
+    Tensor { shape: vec![inputs.len(), inputs[0].len()], data: inputs.concat() }
+}
+
+
+fn close(one: &[f32], two: &[f32]) -> bool {
+    one.len() == two.len() && one.iter().zip(two.iter()).all(|(a, b)| (a - b).abs() < 1e-4)
+}
+
+
+#[test]
+fn test_evolved_dense_layers_export() {
+    let mut neat = Neat::new()
+        .input_size(3)
+        .dense_pool(4, Activation::Sigmoid);
+    {
+        let dense: &mut Dense = neat.layers.last_mut().unwrap().as_mut();
+        for activation in [Activation::Relu, Activation::Gaussian, Activation::Sine, Activation::LeakyRelu(0.2), Activation::Swish].iter() {
+            dense.add_node(*activation, NeuronDirection::Forward);
+        }
+        for _ in 0..4 {
+            dense.add_edge();
+        }
+    }
+    let mut neat = neat
+        .batch_norm()
+        .dropout(0.5)
+        .layer_norm()
+        .dense(3, Activation::Softmax);
+    {
+        let norm: &mut Norm = neat.layers[1].as_mut();
+        norm.running_mean = vec![0.1, 0.5, -0.2, 0.3];
+        norm.running_variance = vec![0.5, 2.0, 1.0, 0.25];
+    }
+    let inputs = vec![vec![0.0, 1.0, -1.0], vec![0.5, 0.25, 2.0], vec![-0.3, 0.9, 0.1]];
+
+    // a batch through the exported graph matches the network one input at a time
+    let model = ModelProto::decode(&neat.to_onnx().unwrap()[..]).unwrap();
+    assert_eq!(model.opset_import[0].version, radiate::onnx::OPSET);
+    let outputs = &run(&model, vec![("input", batch(&inputs))])["output"];
+    for (row, input) in outputs.data.chunks(3).zip(inputs.iter()) {
+        assert!(close(row, &neat.forward(input).unwrap()));
+    }
+}
+
+
+#[test]
+fn test_recurrent_layers_export_one_step() {
+    let mut neat = Neat::new()
+        .input_size(2)
+        .lstm(3, 2, Activation::Tanh)
+        .gru(2, 2, Activation::Sigmoid)
+        .dense(1, Activation::Sigmoid);
+    let model = ModelProto::decode(&neat.to_onnx().unwrap()[..]).unwrap();
+    let names = model.graph.as_ref().unwrap().input.iter().map(|input| input.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["input", "state_0_hidden", "state_0_memory", "state_1_output", "state_1_memory"]);
+
+    // the state coming out of one step is fed back in for the next, starting from zeros
+    let zeros = |size: usize| Tensor { shape: vec![1, size], data: vec![0.0; size] };
+    let mut state = vec![zeros(3), zeros(3), zeros(2), zeros(2)];
+    for input in [vec![1.0, 0.0], vec![0.5, -0.5], vec![0.0, 1.0]].iter() {
+        let feeds = vec![
+            ("input", batch(&[input.clone()])),
+            ("state_0_hidden", state[0].clone()),
+            ("state_0_memory", state[1].clone()),
+            ("state_1_output", state[2].clone()),
+            ("state_1_memory", state[3].clone())
+        ];
+        let values = run(&model, feeds);
+        assert!(close(&values["output"].data, &neat.forward(input).unwrap()));
+        state = ["state_0_hidden_out", "state_0_memory_out", "state_1_output_out", "state_1_memory_out"]
+            .iter()
+            .map(|name| values[*name].clone())
+            .collect();
+    }
+}
+
+
+#[test]
+fn test_unsupported_layers() {
+    let neat = Neat::new()
+        .input_size(16)
+        .conv1d(2, 3, 3, 1, 0, Activation::Relu)
+        .dense(1, Activation::Sigmoid);
+    assert!(matches!(neat.to_onnx(), Err(NeatError::Unsupported(_))));
+}
@@ -0,0 +1,316 @@
+extern crate radiate;
+extern crate rand;
+#[macro_use]
+extern crate serde_derive;
+
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+use rand::Rng;
+use radiate::prelude::*;
+
+
+
+#[test]
+fn test_non_dominated_sort() {
+  let scores = vec![vec![1.0, 4.0], vec![2.0, 2.0], vec![1.0, 1.0], vec![4.0, 1.0], vec![0.5, 0.5], vec![2.0, 2.0]];
+  assert!(pareto::dominates(&scores[1], &scores[2]));
+  assert!(!pareto::dominates(&scores[1], &scores[5]));
+  assert!(!pareto::dominates(&scores[0], &scores[3]));
+
+  let fronts = pareto::non_dominated_sort(&scores);
+  assert_eq!(fronts, vec![vec![0, 1, 3, 5], vec![2], vec![4]]);
+}
+
+
+#[test]
+fn test_crowding_distance() {
+  let scores = vec![vec![0.0, 4.0], vec![1.0, 3.0], vec![3.0, 1.0], vec![4.0, 0.0]];
+  let distances = pareto::crowding_distance(&scores, &[0, 1, 2, 3]);
+  assert!(distances[0].is_infinite() && distances[3].is_infinite());
+  assert!((distances[1] - 1.5).abs() < 1e-6);
+  assert!((distances[2] - 1.5).abs() < 1e-6);
+
+  // earlier fronts are always fitter, the ends of a front are fitter than its middle
+  let scores = vec![vec![0.0, 4.0], vec![1.0, 3.0], vec![4.0, 0.0], vec![0.5, 0.5]];
+  let fitness = pareto::pareto_fitness(&scores);
+  assert!(fitness[..3].iter().all(|fit| *fit > fitness[3]));
+  assert!(fitness[0] > fitness[1] && fitness[2] > fitness[1]);
+}
+
+
+#[test]
+fn test_population_pareto_front() -> Result<(), Box<dyn Error>> {
+  let mut population = Population::<Point, PointEnv, Pareto<Tradeoff>>::new()
+    .size(60)
+    .seed(7)
+    .populate_base()
+    .impose(Pareto(Tradeoff))
+    .configure(Config {
+      inbreed_rate: 0.001,
+      crossover_rate: 0.5,
+      distance: 0.5,
+      species_target: 5
+    });
+  for _ in 0..30 {
+    population.train().ok_or("failed to train")?;
+  }
+
+  // every member of the front trades one objective for the other somewhere between the two optima
+  let front = population.pareto_front();
+  assert!(front.len() > 5);
+  for (scores, point) in front.iter() {
+    assert!(point.x > -0.1 && point.x < 2.1);
+    assert!(front.iter().all(|(other, _)| !pareto::dominates(other, scores)));
+  }
+  let (low, high) = front.iter().fold((f32::MAX, f32::MIN), |(low, high), (_, point)| (low.min(point.x), high.max(point.x)));
+  assert!(high - low > 1.0);
+  Ok(())
+}
+
+
+#[test]
+fn test_pareto_indicators() {
+  // the union of the boxes from the origin to each point, a dominated point adds nothing
+  let front = vec![vec![1.0, 3.0], vec![2.0, 2.0], vec![3.0, 1.0], vec![1.0, 1.0]];
+  assert!((pareto::hypervolume(&front, &[0.0, 0.0]) - 6.0).abs() < 1e-6);
+  assert!((pareto::hypervolume(&front, &[1.0, 0.0]) - 3.0).abs() < 1e-6);
+  assert_eq!(pareto::hypervolume(&[], &[0.0, 0.0]), 0.0);
+  let cube = vec![vec![2.0, 1.0, 1.0], vec![1.0, 2.0, 1.0], vec![1.0, 1.0, 2.0]];
+  assert!((pareto::hypervolume(&cube, &[0.0, 0.0, 0.0]) - 4.0).abs() < 1e-6);
+
+  assert_eq!(pareto::igd(&front, &front), 0.0);
+  assert!((pareto::igd(&[vec![0.0, 0.0]], &[vec![3.0, 4.0], vec![0.0, 1.0]]) - 3.0).abs() < 1e-6);
+  assert!(pareto::igd(&[], &front).is_infinite());
+}
+
+
+#[test]
+fn test_pareto_history_and_export() -> Result<(), Box<dyn Error>> {
+  // samples of the true front, every point between the two optima
+  let reference_front = (0..=20)
+    .map(|i| Tradeoff.objectives(&mut Point { x: i as f32 / 10.0 }))
+    .collect::<Vec<_>>();
+  let mut population = Population::<Point, PointEnv, Pareto<Tradeoff>>::new()
+    .size(60)
+    .seed(7)
+    .populate_base()
+    .impose(Pareto(Tradeoff))
+    .hypervolume_reference(vec![-50.0, -50.0])
+    .igd_reference(reference_front)
+    .configure(Config {
+      inbreed_rate: 0.001,
+      crossover_rate: 0.5,
+      distance: 0.5,
+      species_target: 5
+    });
+  for _ in 0..20 {
+    population.train().ok_or("failed to train")?;
+  }
+
+  let history = population.pareto_history();
+  assert_eq!(history.len(), 20);
+  assert_eq!(history.iter().map(|stats| stats.generation).collect::<Vec<_>>(), (0..20).collect::<Vec<_>>());
+  let (first, last) = (&history[0], &history[19]);
+  assert_eq!(last.size, population.pareto_front().len());
+  assert!(last.hypervolume.unwrap() > first.hypervolume.unwrap());
+  assert!(last.igd.unwrap() < first.igd.unwrap());
+  let resumed = Population::<Point, PointEnv, Pareto<Tradeoff>>::new().from_checkpoint(population.to_checkpoint());
+  assert_eq!(resumed.pareto_history(), history);
+
+  let path = std::env::temp_dir().join("radiate_pareto_front_test.json");
+  population.save_pareto_front(path.to_str().unwrap())?;
+  let front = ParetoFront::<Point>::load(path.to_str().unwrap())?;
+  std::fs::remove_file(path).ok();
+  assert_eq!(front.generation, 19);
+  assert_eq!(front.objectives(), population.pareto_front().iter().map(|(scores, _)| scores.clone()).collect::<Vec<_>>());
+  assert!(front.members.iter().zip(population.pareto_front()).all(|(member, (_, point))| member.genome == *point));
+  Ok(())
+}
+
+
+
+#[test]
+fn test_reference_points() {
+  let points = pareto::reference_points(3, 4);
+  assert_eq!(points.len(), 15);
+  assert!(points.iter().all(|point| (point.iter().sum::<f32>() - 1.0).abs() < 1e-6));
+  assert!(points.contains(&vec![0.25, 0.25, 0.5]));
+  let layered = pareto::layered_reference_points(8, 2, 1);
+  assert_eq!(layered.len(), 36 + 8);
+  assert!(layered[36..].iter().all(|point| point.iter().all(|value| *value > 0.0)));
+
+  // normalized scores are 0 at the best and 1 at the worst of each objective
+  let scores = vec![vec![4.0, -1.0], vec![2.0, -3.0], vec![0.0, -2.0]];
+  assert_eq!(pareto::normalize(&scores), vec![vec![0.0, 0.0], vec![0.5, 1.0], vec![1.0, 0.5]]);
+  let associations = pareto::associate(&[vec![0.9, 0.1], vec![0.4, 0.6]], &pareto::reference_points(2, 1));
+  assert_eq!(associations.iter().map(|(reference, _)| *reference).collect::<Vec<_>>(), vec![1, 0]);
+  assert!((associations[0].1 - 0.1).abs() < 1e-6);
+
+  // a crowded corner of the front is niched behind the lone member of the other corner
+  let scores = vec![vec![0.0, 4.0], vec![0.1, 3.9], vec![0.2, 3.8], vec![4.0, 0.0], vec![-1.0, -1.0]];
+  let fitness = pareto::reference_fitness(&scores, &pareto::reference_points(2, 2));
+  assert!(fitness[..4].iter().all(|fit| *fit > fitness[4]));
+  assert!(fitness[3] > fitness[1] && fitness[3] > fitness[2]);
+}
+
+
+#[test]
+fn test_many_objective_population() -> Result<(), Box<dyn Error>> {
+  let mut population = Population::<Point, PointEnv, Pareto<Spread>>::new()
+    .size(60)
+    .seed(7)
+    .populate_base()
+    .impose(Pareto(Spread))
+    .configure(Config {
+      inbreed_rate: 0.001,
+      crossover_rate: 0.5,
+      distance: 0.5,
+      species_target: 5
+    });
+  for _ in 0..30 {
+    population.train().ok_or("failed to train")?;
+  }
+
+  // the niches keep the front covering the whole stretch between the five optima
+  let front = population.pareto_front();
+  assert!(front.iter().all(|(_, point)| point.x > -0.1 && point.x < 2.1));
+  let (low, high) = front.iter().fold((f32::MAX, f32::MIN), |(low, high), (_, point)| (low.min(point.x), high.max(point.x)));
+  assert!(high - low > 1.0);
+  Ok(())
+}
+
+
+
+#[test]
+fn test_preferences() {
+  let scores = vec![vec![5.0, 5.0], vec![1.0, 2.0], vec![3.0, 1.0], vec![2.0, 4.0]];
+
+  // the first member misses the constraint on the second objective, so it is behind every feasible one
+  let preferences = Preferences::new().constraint(1, 5.5);
+  assert_eq!(preferences.violation(&scores[0]), 0.5);
+  let preferences = Preferences::new().constraint(0, 1.5);
+  assert!(!preferences.feasible(&scores[1]));
+  let fitness = pareto::preferred_fitness(&scores, &Preferences::new().constraint(0, 1.5).constraint(1, 1.5), None);
+  assert!(fitness[0] > fitness[3] && fitness[3] > fitness[1] && fitness[3] > fitness[2]);
+  assert!(fitness[2] > fitness[1]);
+
+  // past the goal the first objective doesn't matter, so the last member is as good as the first
+  let preferences = Preferences::new().goal(0, 2.0).goal(1, 4.0);
+  assert_eq!(preferences.attained(&scores[0]), vec![2.0, 4.0]);
+  let fitness = pareto::preferred_fitness(&scores, &preferences, None);
+  assert_eq!(fitness[0], fitness[3]);
+
+  // prioritizing the first objective orders members by it, ties within the tolerance broken by rank
+  let preferences = Preferences::new().priority(vec![0]).tolerance(1.5);
+  let fitness = pareto::preferred_fitness(&scores[1..], &preferences, None);
+  assert!(fitness[1] > fitness[0] && fitness[1] > fitness[2]);
+  assert!(fitness[2] > fitness[0]);
+}
+
+
+#[test]
+fn test_constrained_population() -> Result<(), Box<dyn Error>> {
+  let mut population = Population::<Point, PointEnv, Pareto<Constrained>>::new()
+    .size(60)
+    .seed(7)
+    .populate_base()
+    .impose(Pareto(Constrained))
+    .configure(Config {
+      inbreed_rate: 0.001,
+      crossover_rate: 0.5,
+      distance: 0.5,
+      species_target: 5
+    });
+  for _ in 0..30 {
+    population.train().ok_or("failed to train")?;
+  }
+
+  // only the feasible end of the trade off survives
+  let front = population.pareto_front();
+  assert!(!front.is_empty());
+  assert!(front.iter().all(|(_, point)| point.x > -0.1 && point.x < 0.6));
+  Ok(())
+}
+
+
+
+/// the same trade off as Tradeoff, but points have to stay within 0.5 of 0
+pub struct Constrained;
+
+
+impl MultiObjective<Point> for Constrained {
+
+  fn empty() -> Self { Constrained }
+
+  fn objectives(&self, point: &mut Point) -> Vec<f32> {
+    Tradeoff.objectives(point)
+  }
+
+  fn preferences(&self) -> Option<Preferences> {
+    Some(Preferences::new().constraint(0, -0.25))
+  }
+}
+
+
+
+/// get close to five points at once, niched around reference points like NSGA-III
+pub struct Spread;
+
+
+impl MultiObjective<Point> for Spread {
+
+  fn empty() -> Self { Spread }
+
+  fn objectives(&self, point: &mut Point) -> Vec<f32> {
+    [0.0, 0.5, 1.0, 1.5, 2.0].iter().map(|target| -(point.x - target) * (point.x - target)).collect()
+  }
+
+  fn reference_points(&self) -> Option<Vec<Vec<f32>>> {
+    Some(pareto::reference_points(5, 3))
+  }
+}
+
+
+
+/// minimize the distance to 0 and to 2 at the same time, any point between the two is pareto optimal
+pub struct Tradeoff;
+
+
+impl MultiObjective<Point> for Tradeoff {
+
+  fn empty() -> Self { Tradeoff }
+
+  fn objectives(&self, point: &mut Point) -> Vec<f32> {
+    vec![-point.x * point.x, -(point.x - 2.0) * (point.x - 2.0)]
+  }
+}
+
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PointEnv;
+
+impl Envionment for PointEnv {}
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+  pub x: f32
+}
+
+
+impl Genome<Point, PointEnv> for Point {
+
+  fn crossover(one: &Point, two: &Point, _: Arc<RwLock<PointEnv>>, crossover_rate: f32) -> Result<Point, Box<dyn Error>> {
+    let mut r = radiate::rng::thread();
+    let x = if r.gen::<f32>() < crossover_rate { (one.x + two.x) / 2.0 } else { one.x };
+    Ok(Point { x: x + r.gen_range(-0.2, 0.2) })
+  }
+
+  fn distance(one: &Point, two: &Point, _: Arc<RwLock<PointEnv>>) -> f32 {
+    (one.x - two.x).abs()
+  }
+
+  fn base(_: &mut PointEnv) -> Point {
+    Point { x: radiate::rng::thread().gen_range(-5.0, 5.0) }
+  }
+}
@@ -0,0 +1,764 @@
+extern crate radiate;
+extern crate serde_json;
+
+use std::thread;
+use std::time::{Duration, Instant};
+use radiate::prelude::*;
+
+mod common;
+
+
+
+fn xor_population(seed: u64) -> Population<Neat, NeatEnvironment, BenchmarkProblem> {
+    common::xor_population(30, BenchmarkProblem::new(Benchmark::Xor, 0))
+        .dynamic_distance(true)
+        .stagnation(3, vec![Genocide::KillWorst(0.9)])
+        .seed(seed)
+        .populate_base()
+}
+
+
+/// xor which takes a couple of milliseconds to score each member
+struct SlowXor(BenchmarkProblem);
+
+
+impl Problem<Neat> for SlowXor {
+    fn empty() -> Self { SlowXor(BenchmarkProblem::new(Benchmark::Xor, 0)) }
+
+    fn solve(&self, model: &mut Neat) -> f32 {
+        thread::sleep(Duration::from_millis(2));
+        self.0.solve(model)
+    }
+}
+
+
+fn budgeted_population(overrun: Overrun) -> Population<Neat, NeatEnvironment, SlowXor> {
+    Population::<Neat, NeatEnvironment, SlowXor>::new()
+        .size(30)
+        .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(1))
+        .parallel_eval(false)
+        .time_budget(Duration::from_millis(15), overrun)
+        .seed(5)
+        .populate_base()
+}
+
+
+#[test]
+fn test_step_matches_train() {
+    let mut stepped = xor_population(3);
+    let mut trained = xor_population(3);
+
+    // a scheduler driving the population one generation at a time sees the same run as train
+    for generation in 0..8 {
+        let stats = stepped.step().unwrap();
+        let (fitness, top) = trained.train().unwrap();
+        assert_eq!(stats.generation, generation);
+        assert_eq!(stats.fitness, fitness);
+        assert_eq!(serde_json::to_value(&stats.top).unwrap(), serde_json::to_value(&top).unwrap());
+        assert!(stats.mean_fitness <= stats.fitness);
+        assert_eq!(stats.species, stepped.get_species_count());
+        assert_eq!(stats.distance, stepped.get_distance());
+        assert!(stats.stagnation <= 3);
+    }
+    assert_eq!(stepped.get_generation(), 8);
+}
+
+
+
+#[test]
+fn test_time_budget_drops_members() {
+    let mut population = budgeted_population(Overrun::Drop);
+
+    // only the members scored within the budget are selected from, the rest are thrown away
+    population.evaluate();
+    let scored = population.members_mut().len();
+    assert!(scored > 0 && scored < 30);
+    let stats = population.step().unwrap();
+    assert_eq!(stats.over_budget, 30 - scored);
+    assert_eq!(population.members_mut().len(), 30);
+}
+
+
+#[test]
+fn test_time_budget_defers_members() {
+    let mut population = budgeted_population(Overrun::Defer);
+    let members = population.members_mut()
+        .iter()
+        .map(|cont| serde_json::to_value(&*cont.member.read().unwrap()).unwrap())
+        .collect::<Vec<_>>();
+
+    // scored one at a time so the members which ran over are the last ones, and they
+    // lead the next generation so they're scored before any of its offspring
+    population.evaluate();
+    let scored = population.members_mut().len();
+    assert!(scored < 30);
+    // suspended between evaluation and selection, the checkpoint still holds them
+    let saved = serde_json::to_string(&population.to_checkpoint()).unwrap();
+    let mut population = budgeted_population(Overrun::Defer).from_checkpoint(serde_json::from_str(&saved).unwrap());
+    population.end_generation().unwrap();
+    let next = population.members_mut();
+    assert_eq!(next.len(), 30);
+    for (cont, member) in next.iter().zip(members[scored..].iter()) {
+        assert_eq!(&serde_json::to_value(&*cont.member.read().unwrap()).unwrap(), member);
+    }
+}
+
+
+#[test]
+fn test_evolver_swaps_champions() {
+    let handle = Evolver::new(xor_population(7)).spawn();
+    let champion = handle.champion();
+
+    // the game thread polls the champion without waiting on evolution
+    let mut seen = Vec::new();
+    let start = Instant::now();
+    while seen.len() < 5 && start.elapsed() < Duration::from_secs(10) {
+        if let Some(current) = champion.load() {
+            if seen.last() != Some(&current.generation) {
+                seen.push(current.generation);
+            }
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(!seen.is_empty() && handle.is_running());
+    let last = champion.load().unwrap();
+    let population = handle.stop().unwrap();
+
+    // champions only get fitter unless always_swap is set, and held ones outlive the swap
+    assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(population.get_generation() > last.generation);
+    assert!(champion.load().unwrap().fitness >= last.fitness);
+    assert!(last.member.clone().forward(&vec![1.0, 0.0]).is_ok());
+}
+
+
+#[test]
+fn test_generation_hooks() {
+    use std::sync::{Arc, Mutex};
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let record = Arc::clone(&seen);
+    let mut population = xor_population(3)
+        .on_generation(move |ctx| {
+            let members = ctx.stats.niches.iter().map(|niche| niche.members).sum::<usize>();
+            record.lock().unwrap().push((ctx.stats.generation, ctx.stats.niches.len(), members));
+            // widen the species as the run goes on
+            ctx.config.distance = 0.5 + ctx.stats.generation as f32;
+        })
+        .on_generation(|ctx| {
+            if ctx.stats.generation == 4 {
+                ctx.stop();
+            }
+        });
+
+    // the run ends when a hook stops it, not when the runner says so
+    let (top, _) = population.run(|_, _, _| false).unwrap();
+    assert!(population.is_stopped());
+    assert_eq!(population.get_generation(), 5);
+    assert!(top.clone().forward(&vec![1.0, 0.0]).is_ok());
+
+    // every member of a generation is in one of its species, and each generation is seen once in order
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.iter().map(|(generation, _, _)| *generation).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    assert!(seen.iter().all(|(_, species, members)| *species > 0 && *members == 30));
+    assert_eq!(population.get_distance(), 4.5);
+}
+
+
+/// every member scores the same, so the top fitness never improves
+struct Flat;
+
+
+impl Problem<Neat> for Flat {
+    fn empty() -> Self { Flat }
+
+    fn solve(&self, _: &mut Neat) -> f32 {
+        1.0
+    }
+}
+
+
+fn flat_population(criteria: StoppingCriteria) -> Population<Neat, NeatEnvironment, Flat> {
+    Population::<Neat, NeatEnvironment, Flat>::new()
+        .size(10)
+        .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(1))
+        .stop_when(criteria)
+        .seed(5)
+        .populate_base()
+}
+
+
+#[test]
+fn test_stopping_criteria() {
+    // the first generation sets the best fitness, the next three don't beat it
+    let mut patient = flat_population(StoppingCriteria::new().patience(3));
+    assert!(patient.run(|_, _, _| false).is_ok());
+    assert_eq!(patient.stop_reason(), Some(StopReason::Patience));
+    assert_eq!(patient.get_generation(), 4);
+
+    // reaching the target wins over running out of patience in the same generation
+    let mut targeted = flat_population(StoppingCriteria::new().patience(1).target(1.0));
+    assert!(targeted.run(|_, _, _| false).is_ok());
+    assert_eq!(targeted.stop_reason(), Some(StopReason::Target));
+    assert_eq!(targeted.get_generation(), 1);
+
+    // 30 members taking 2ms each run out of 100ms in a few generations
+    let mut timed = Population::<Neat, NeatEnvironment, SlowXor>::new()
+        .size(30)
+        .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(1))
+        .parallel_eval(false)
+        .stop_when(StoppingCriteria::new().time_limit(Duration::from_millis(100)))
+        .populate_base();
+    let start = Instant::now();
+    assert!(timed.run(|_, _, _| false).is_ok());
+    assert_eq!(timed.stop_reason(), Some(StopReason::TimeLimit));
+    assert!(start.elapsed() >= Duration::from_millis(100) && timed.get_generation() < 10);
+
+    // without criteria nothing stops the run but the runner
+    let mut unbounded = flat_population(StoppingCriteria::new());
+    assert!(unbounded.run(|_, _, generation| generation == 9).is_ok());
+    assert!(!unbounded.is_stopped());
+    assert_eq!(unbounded.get_generation(), 10);
+}
+
+
+
+#[test]
+fn test_run_history() {
+    let mut population = xor_population(13);
+    for _ in 0..5 {
+        assert!(population.step().is_some());
+    }
+    let history = population.history();
+    assert_eq!(history.len(), 5);
+    for (index, record) in history.generations.iter().enumerate() {
+        assert_eq!(record.generation, index);
+        assert!(record.best_fitness >= record.mean_fitness && record.std_fitness >= 0.0);
+        assert_eq!(record.species, record.species_sizes.len());
+        assert_eq!(record.species_sizes.iter().sum::<usize>(), 30);
+        // xor networks have at least their output neuron and its connections to the two inputs
+        let (nodes, edges) = record.mean_complexity.unwrap();
+        assert!(nodes >= 1.0 && edges >= 2.0);
+        assert!(record.top_complexity.is_some());
+        assert!(record.evaluation <= record.elapsed);
+    }
+
+    let csv = history.to_csv();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 6);
+    assert!(lines[0].starts_with("generation,best_fitness,mean_fitness,std_fitness,species,species_sizes"));
+    assert!(lines.iter().all(|line| line.split(',').count() == 12));
+
+    // resuming from a checkpoint carries on the history
+    let checkpoint = population.to_checkpoint();
+    assert_eq!(checkpoint.history.len(), 5);
+    let resumed = xor_population(13).from_checkpoint(checkpoint);
+    assert_eq!(resumed.history(), history);
+}
+
+
+
+#[test]
+fn test_history_queries() {
+    use std::sync::{Arc, Mutex};
+    let path = std::env::temp_dir().join("radiate_test_history_queries.csv");
+    let path = path.to_str().unwrap().to_string();
+    let _ = std::fs::remove_file(&path);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let hooked = seen.clone();
+    let mut population = xor_population(17)
+        .history_file(&path)
+        .on_generation(move |ctx| hooked.lock().unwrap().push((ctx.history.len(), ctx.history.last().unwrap().generation)));
+    let result = population.run_result(|_, _, generation| generation == 7).unwrap();
+    assert_eq!(result.generations, 8);
+    assert_eq!(result.stop_reason, None);
+    assert_eq!(&result.history, population.history());
+    // hooks see the history up to and including the generation they're called for
+    assert_eq!(*seen.lock().unwrap(), (0..8).map(|generation| (generation + 1, generation)).collect::<Vec<_>>());
+
+    let history = &result.history;
+    let curve = history.best_fitness_curve();
+    assert_eq!(curve.iter().map(|(generation, _)| *generation).collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+    assert_eq!(curve.iter().map(|(_, fitness)| *fitness).collect::<Vec<_>>(), history.best_fitness());
+    assert!(history.mean_fitness_curve().iter().zip(curve.iter()).all(|(mean, best)| mean.1 <= best.1));
+    let best = history.best_generation().unwrap();
+    assert!(curve.iter().all(|(_, fitness)| *fitness <= best.best_fitness));
+    let reached = history.first_generation_reaching(best.best_fitness).unwrap();
+    assert!(reached <= best.generation);
+    assert!(curve.iter().take_while(|(generation, _)| *generation < reached).all(|(_, fitness)| *fitness < best.best_fitness));
+    assert_eq!(history.first_generation_reaching(f32::MAX), None);
+    assert_eq!(history.record(3), Some(&history.generations[3]));
+    assert_eq!(history.record(8), None);
+    assert_eq!(history.between(2, 5).map(|record| record.generation).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+    // the file kept as the run went reads back the same as the history written at the end
+    let written = RunHistory::load_csv(&path).unwrap();
+    assert_eq!(written, RunHistory::from_csv(&history.to_csv()).unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert!(population.write_failures().is_empty());
+
+    // a history file which can't be written is kept with the population and doesn't end the run
+    let missing = std::env::temp_dir().join("radiate_missing_dir").join("history.csv");
+    let missing = missing.to_str().unwrap();
+    let mut population = xor_population(17).history_file(missing);
+    let stats = population.step().unwrap();
+    assert_eq!(stats.write_failures.len(), 1);
+    assert_eq!((stats.write_failures[0].generation, stats.write_failures[0].path.as_str()), (0, missing));
+    population.step().unwrap();
+    assert_eq!(population.write_failures().len(), 2);
+    assert!(!population.is_stopped());
+}
+
+
+
+#[test]
+fn test_hall_of_fame() {
+    let mut population = xor_population(21).hall_of_fame(HallOfFame::new(5).distance(0.5));
+    for _ in 0..15 {
+        assert!(population.step().is_some());
+    }
+    let hall = population.get_hall_of_fame().unwrap();
+    assert_eq!(hall.len(), 5);
+    assert!(hall.inductees().windows(2).all(|pair| pair[0].fitness >= pair[1].fitness));
+    // nothing the run scored is better than the best inductee
+    let best = population.history().best_fitness().into_iter().fold(f32::MIN, f32::max);
+    assert_eq!(hall.best().unwrap().fitness, best);
+    // no two inductees are the same solution
+    let env = std::sync::Arc::new(std::sync::RwLock::new(radiate::default_neat_env().set_input_size(2).set_output_size(1)));
+    for (index, one) in hall.inductees().iter().enumerate() {
+        for two in hall.inductees()[index + 1..].iter() {
+            assert!(!Neat::within_distance(&one.member, &two.member, env.clone(), 0.5));
+        }
+    }
+
+    // a flat problem stagnates right away, the best of the hall come back after each purge
+    let mut flat = Population::<Neat, NeatEnvironment, Flat>::new()
+        .size(10)
+        .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(1))
+        .stagnation(2, vec![Genocide::KillWorst(0.5)])
+        .hall_of_fame(HallOfFame::new(3).reinject(2))
+        .seed(5)
+        .populate_base();
+    let mut purges = 0;
+    for _ in 0..12 {
+        let stats = flat.step().unwrap();
+        if stats.stagnation == 0 && stats.generation > 0 {
+            purges += 1;
+            let hall = flat.get_hall_of_fame().unwrap().inductees().iter()
+                .take(2)
+                .map(|inductee| serde_json::to_value(&inductee.member).unwrap())
+                .collect::<Vec<_>>();
+            let members = flat.members_mut().iter()
+                .rev()
+                .take(2)
+                .map(|cont| serde_json::to_value(&*cont.member.read().unwrap()).unwrap())
+                .collect::<Vec<_>>();
+            assert!(hall.iter().all(|member| members.contains(member)));
+        }
+    }
+    assert!(purges > 0);
+}
+
+
+
+#[test]
+fn test_run_comparison() {
+    // completely separated samples, worked out by hand from the normal approximation
+    let test = compare::mann_whitney(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0], &[9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0]).unwrap();
+    assert_eq!(test.u, 0.0);
+    assert_eq!(test.a12, 0.0);
+    assert!((test.z + 3.3082).abs() < 1e-3);
+    assert!((test.p - 0.000939).abs() < 1e-5);
+    // identical samples are all ties, nothing to tell apart
+    let test = compare::mann_whitney(&[1.0, 1.0, 1.0], &[1.0, 1.0]).unwrap();
+    assert_eq!((test.p, test.a12), (1.0, 0.5));
+    assert!(compare::mann_whitney(&[], &[1.0]).is_none());
+    assert_eq!(compare::quartiles(&[4.0, 1.0, 3.0, 2.0, 5.0]), Some((2.0, 3.0, 4.0)));
+
+    // runs go through the csv they're saved as on the way in
+    let runs = |seed: u64, generations: usize| (0..3)
+        .map(|run| {
+            let mut population = xor_population(seed + run);
+            for _ in 0..generations {
+                population.step();
+            }
+            RunHistory::from_csv(&population.history().to_csv()).unwrap()
+        })
+        .collect::<Vec<_>>();
+    let (short, long) = (runs(40, 1), runs(40, 4));
+    assert_eq!(long[0].best_fitness(), runs(40, 4)[0].best_fitness());
+    let comparison = RunComparison::new(RunMetric::BestFitness)
+        .group("short", &short)
+        .group("long", &long)
+        .group("empty", &[]);
+    // the same seeds run for longer can only have found better members
+    let values = comparison.values("long").unwrap();
+    assert!(values.iter().zip(comparison.values("short").unwrap()).all(|(long, short)| long >= short));
+
+    let report = comparison.report();
+    assert_eq!(report.groups.len(), 2);
+    assert_eq!(report.pairs.len(), 1);
+    assert!(report.groups.iter().all(|group| group.runs == 3 && group.q1 <= group.median && group.median <= group.q3));
+    assert!(report.pairs[0].2.a12 <= 0.5);
+    assert!(report.significant(0.0).is_empty());
+    let table = report.to_string();
+    assert!(table.contains("short") && table.contains("against"));
+}
+
+
+
+#[test]
+fn test_selection() {
+    let fitness = [1.0, 5.0, 3.0, 2.0];
+    let picks = |selection: &dyn Selection| radiate::rng::scoped(4, || {
+        let mut r = radiate::rng::thread();
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            counts[selection.select(&mut r, &fitness)] += 1;
+        }
+        counts
+    });
+    // roulette picks in proportion to fitness, rank in proportion to rank (1, 4, 3, 2 out of 10)
+    let roulette = picks(&RouletteWheel);
+    assert!(roulette[1] > roulette[2] && roulette[2] > roulette[3] && roulette[3] > roulette[0]);
+    let rank = picks(&Rank);
+    assert!((rank[0] as f32 / 4000.0 - 0.1).abs() < 0.03 && (rank[1] as f32 / 4000.0 - 0.4).abs() < 0.03);
+    // the best member is in nine out of ten tournaments of 8, a tournament of one is a random pick
+    assert!(picks(&Tournament { size: 8 })[1] > 3400);
+    assert!(picks(&Tournament { size: 1 }).iter().all(|count| (*count as f32 - 1000.0).abs() < 150.0));
+    assert_eq!(picks(&Truncation { fraction: 0.25 }), [0, 4000, 0, 0]);
+    assert_eq!(picks(&Truncation { fraction: 0.5 })[0] + picks(&Truncation { fraction: 0.5 })[3], 0);
+
+    // the population breeds with its selection and keeps it in its checkpoints
+    let mut population = xor_population(5).selection(Tournament { size: 3 });
+    for _ in 0..3 {
+        assert!(population.step().is_some());
+    }
+    assert_eq!(format!("{:?}", population.get_selection()), "Tournament { size: 3 }");
+    let json = serde_json::to_string(&population.to_checkpoint()).unwrap();
+    let checkpoint = serde_json::from_str::<Checkpoint<Neat, NeatEnvironment>>(&json).unwrap();
+    let resumed = xor_population(5).from_checkpoint(checkpoint);
+    assert_eq!(format!("{:?}", resumed.get_selection()), "Tournament { size: 3 }");
+}
+
+
+
+#[test]
+fn test_elitism() {
+    // without any survivors the elite is all that carries the best member over, so the best fitness never drops
+    let elitism = Elitism::new().global(1).per_species(1);
+    let mut population = xor_population(9)
+        .survivor_criteria(SurvivalCriteria::TopNumber(0))
+        .elitism(elitism);
+    let mut best = f32::MIN;
+    for _ in 0..12 {
+        let stats = population.step().unwrap();
+        assert!(stats.fitness >= best);
+        best = stats.fitness;
+    }
+    assert_eq!(population.get_elitism(), elitism);
+
+    let json = serde_json::to_string(&population.to_checkpoint()).unwrap();
+    let checkpoint = serde_json::from_str::<Checkpoint<Neat, NeatEnvironment>>(&json).unwrap();
+    assert_eq!(checkpoint.elitism, elitism);
+    assert_eq!(xor_population(9).from_checkpoint(checkpoint).get_elitism(), elitism);
+}
+
+
+#[test]
+fn test_neutral_drift() {
+    // every member of a flat problem is on the level, the variants are carried in turn at the front of each generation
+    let mut flat = Population::<Neat, NeatEnvironment, Flat>::new()
+        .size(10)
+        .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(1))
+        .neutral_drift(NeutralDrift::new(4).carry(2))
+        .seed(5)
+        .populate_base();
+    for _ in 0..6 {
+        assert!(flat.step().is_some());
+        let drift = flat.get_neutral_drift().unwrap();
+        assert_eq!(drift.level(), Some(1.0));
+        assert_eq!(drift.len(), 4);
+        let variants = drift.variants().iter().map(|variant| serde_json::to_value(variant).unwrap()).collect::<Vec<_>>();
+        let carried = flat.members_mut().iter()
+            .take(2)
+            .map(|cont| serde_json::to_value(&*cont.member.read().unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert!(carried.iter().all(|member| variants.contains(member)));
+        assert_ne!(carried[0], carried[1]);
+    }
+    let json = serde_json::to_string(&flat.to_checkpoint()).unwrap();
+    let checkpoint = serde_json::from_str::<Checkpoint<Neat, NeatEnvironment>>(&json).unwrap();
+    assert_eq!(checkpoint.neutral_drift.as_ref().map(|drift| drift.len()), Some(4));
+
+    // the archive starts over each time the run climbs off its plateau
+    let mut population = xor_population(13).neutral_drift(NeutralDrift::new(3).tolerance(0.001));
+    for _ in 0..10 {
+        assert!(population.step().is_some());
+    }
+    let best = population.history().best_fitness().into_iter().fold(f32::MIN, f32::max);
+    let drift = population.get_neutral_drift().unwrap();
+    assert!((drift.level().unwrap() - best).abs() <= 0.001);
+    assert!(!drift.is_empty() && drift.len() <= 3);
+}
+
+
+
+#[test]
+fn test_species_aging() {
+    use std::sync::{Arc, RwLock};
+    use radiate::engine::niche::NicheMember;
+
+    let aging = SpeciesAging::new();
+    assert_eq!(aging.multiplier(3, 0), 1.2);
+    assert_eq!(aging.multiplier(20, 15), 1.0);
+    assert_eq!(aging.multiplier(20, 16), 0.01);
+
+    // boosted while young, then penalized once it goes past the dropoff age without improving
+    let member = Arc::new(RwLock::new(Neat::new().input_size(2).dense_pool(1, Activation::Sigmoid)));
+    let mut niche = Niche::<Neat, NeatEnvironment>::new(&member, 2.0);
+    let mut shared = Vec::new();
+    for age in 0..24 {
+        niche.age = age;
+        niche.members = vec![NicheMember(if age == 5 { 3.0 } else { 2.0 }, Arc::downgrade(&member))];
+        niche.share_fitness(Some(&aging));
+        shared.push(niche.get_total_adjusted_fitness());
+    }
+    assert_eq!(niche.best_fitness, Some(3.0));
+    assert_eq!(niche.last_improvement, 5);
+    assert_eq!(shared[0], 2.4);
+    assert_eq!(shared[5], 3.0 * 1.2);
+    assert_eq!(shared[10..=20], [2.0; 11]);
+    assert_eq!(shared[21..], [2.0 * 0.01; 3]);
+    assert_eq!(niche.stats().top_fitness, 2.0);
+
+    // a population picks the aging up from its environment and keeps each species' progress in its checkpoints
+    let mut population = xor_population(17)
+        .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(1).set_species_aging(aging.dropoff(2, 0.5)));
+    for _ in 0..6 {
+        assert!(population.step().is_some());
+    }
+    let checkpoint = population.to_checkpoint();
+    assert!(checkpoint.species.iter().all(|spec| spec.best_fitness.is_some() && spec.last_improvement <= spec.age));
+}
+
+
+
+#[test]
+fn test_speciation_history() {
+    let mut population = xor_population(23).configure(Config {
+        inbreed_rate: 0.001,
+        crossover_rate: 0.75,
+        distance: 2.0,
+        species_target: 5
+    });
+    for _ in 0..12 {
+        assert!(population.step().is_some());
+    }
+    let speciation = population.speciation_history();
+    assert_eq!(speciation.len(), 12);
+    for (record, generation) in speciation.generations.iter().zip(population.history().generations.iter()) {
+        assert_eq!(record.species.iter().map(|species| species.members).collect::<Vec<_>>(), generation.species_sizes);
+    }
+
+    // the first species come from nowhere, every later one split from a species of the generation before
+    // and every species which died out merged into one which didn't, if any didn't
+    let first = &speciation.generations[0];
+    assert!(first.species.iter().all(|species| species.split_from.is_none()) && first.extinct.is_empty());
+    for pair in speciation.generations.windows(2) {
+        let before = pair[0].species.iter().map(|species| species.niche_id).collect::<Vec<_>>();
+        let after = pair[1].species.iter().map(|species| species.niche_id).collect::<Vec<_>>();
+        for species in pair[1].species.iter() {
+            assert_eq!(species.split_from.is_some(), !before.contains(&species.niche_id));
+            assert!(species.split_from.is_none_or(|parent| before.contains(&parent)));
+        }
+        let survived = after.iter().any(|species| before.contains(species));
+        for extinct in pair[1].extinct.iter() {
+            assert!(before.contains(&extinct.niche_id) && !after.contains(&extinct.niche_id));
+            assert_eq!(extinct.merged_into.is_some(), survived);
+            assert!(extinct.merged_into.is_none_or(|species| after.contains(&species) && before.contains(&species)));
+        }
+    }
+    assert!(!speciation.splits().is_empty());
+
+    // the bands of the stack plot add up to the population in every generation
+    let stack = speciation.stack();
+    for generation in 0..12 {
+        assert_eq!(stack.iter().map(|(_, sizes)| sizes[generation]).sum::<usize>(), 30);
+    }
+
+    let json = speciation.to_json().unwrap();
+    assert_eq!(&serde_json::from_str::<SpeciationHistory>(&json).unwrap(), speciation);
+    assert_eq!(&population.to_checkpoint().speciation, speciation);
+}
+
+
+
+#[test]
+fn test_breeder_pool() {
+    let fitness = [1.0, 5.0, 3.0, 2.0, 4.0];
+    // every member in the order they came by default, so a selection sees the whole species as before
+    assert_eq!(BreederPool::new().eligible(&fitness, Some(1)), vec![0, 1, 2, 3, 4]);
+    // the fittest fraction rounded up, still in the order they came
+    assert_eq!(BreederPool::new().fraction(0.4).eligible(&fitness, None), vec![1, 4]);
+    assert_eq!(BreederPool::new().fraction(0.01).eligible(&fitness, None), vec![1]);
+    // without replacement the first parent is left out of the pool for the second, unless it's all there is
+    let without = BreederPool::new().fraction(0.4).replacement(false);
+    assert_eq!(without.eligible(&fitness, Some(1)), vec![4]);
+    assert_eq!(without.fraction(0.2).eligible(&fitness, Some(1)), vec![1]);
+
+    // the population breeds from its pool and keeps it in its checkpoints
+    let mut population = xor_population(29).breeders(without);
+    for _ in 0..3 {
+        assert!(population.step().is_some());
+    }
+    let json = serde_json::to_string(&population.to_checkpoint()).unwrap();
+    let checkpoint = serde_json::from_str::<Checkpoint<Neat, NeatEnvironment>>(&json).unwrap();
+    assert_eq!(xor_population(29).from_checkpoint(checkpoint).get_breeders(), without);
+}
+
+
+
+#[test]
+fn test_species_stagnation() {
+    use std::sync::{Arc, Mutex};
+
+    let removed = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&removed);
+    let mut population = xor_population(31)
+        .configure(Config {
+            inbreed_rate: 0.001,
+            crossover_rate: 0.75,
+            distance: 2.0,
+            species_target: 5
+        })
+        .species_stagnation(StagnationPolicy::new(2))
+        .on_generation(move |context| seen.lock().unwrap().extend(context.stats.removed_species.clone()));
+    for _ in 0..15 {
+        let stats = population.step().unwrap();
+        // the species with the best member of the generation is always kept
+        let best = stats.niches.iter().max_by(|a, b| a.top_fitness.total_cmp(&b.top_fitness)).unwrap();
+        assert!(stats.removed_species.iter().all(|species| species.niche_id != best.niche_id));
+        assert_eq!(stats.species + stats.removed_species.len(), stats.niches.len());
+    }
+    let removed = removed.lock().unwrap();
+    assert!(!removed.is_empty());
+    assert!(removed.iter().all(|species| species.stagnation > 2 && species.members > 0));
+}
+
+
+
+#[test]
+fn test_mutation_schedule() {
+    use std::sync::{Arc, Mutex};
+
+    let decay = RateSchedule::Linear { start: 0.9, end: 0.1, generations: 8 };
+    let nodes = RateSchedule::Exponential { initial: 0.1, decay: 0.5, floor: 0.02 };
+    assert_eq!(decay.rate(0), 0.9);
+    assert!((decay.rate(4) - 0.5).abs() < 1e-6);
+    assert_eq!(decay.rate(20), 0.1);
+    assert_eq!(nodes.rate(1), 0.05);
+    assert_eq!(nodes.rate(10), 0.02);
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let record = Arc::clone(&seen);
+    let env = radiate::default_neat_env()
+        .set_input_size(2)
+        .set_output_size(1)
+        .set_schedule(MutationSchedule::new().weight_mutate_rate(decay).new_node_rate(nodes));
+    let mut population = xor_population(37)
+        .constrain(env)
+        .on_generation(move |ctx| {
+            record.lock().unwrap().push((ctx.stats.generation, ctx.environment.weight_mutate_rate, ctx.environment.new_node_rate, ctx.environment.reactivate));
+        });
+    for _ in 0..10 {
+        population.step().unwrap();
+    }
+
+    // each generation is bred with the scheduled rates, rates without a schedule are left alone
+    let reactivate = radiate::default_neat_env().reactivate;
+    for (generation, weights, nodes_rate, reactivated) in seen.lock().unwrap().iter() {
+        assert_eq!(*weights, Some(decay.rate(*generation)));
+        assert_eq!(*nodes_rate, Some(nodes.rate(*generation)));
+        assert_eq!(*reactivated, reactivate);
+    }
+}
+
+
+#[test]
+fn test_self_adaptive_rates() {
+    let env = radiate::default_neat_env()
+        .set_input_size(2)
+        .set_output_size(1)
+        .set_self_adaptive(SelfAdaptation::new(0.3).bounds(0.01, 0.9));
+    let base = env.weight_mutate_rate;
+    let mut population = xor_population(41)
+        .constrain(env)
+        .configure(Config {
+            inbreed_rate: 0.001,
+            crossover_rate: 0.75,
+            distance: 2.0,
+            species_target: 5
+        });
+    for _ in 0..6 {
+        population.step().unwrap();
+    }
+
+    // the children carry their own rates within bounds, and the environment's are untouched
+    let rates = population.members_mut()
+        .iter()
+        .filter_map(|cont| cont.member.read().unwrap().mutation_rates)
+        .collect::<Vec<_>>();
+    assert!(!rates.is_empty());
+    for rate in rates.iter() {
+        for probability in [rate.weight_mutate_rate, rate.new_node_rate, rate.new_edge_rate, rate.edit_weights, rate.reactivate] {
+            assert!((0.01..=0.9).contains(&probability));
+        }
+        assert!(rate.weight_perturb >= 0.01);
+    }
+    assert!(rates.iter().any(|rate| Some(rate.weight_mutate_rate) != base));
+    population = population.on_generation(move |ctx| assert_eq!(ctx.environment.weight_mutate_rate, base));
+    population.step().unwrap();
+
+    // the rates are saved with the network
+    let network = population.members_mut()[0].member.read().unwrap().clone();
+    let json = serde_json::to_string(&network).unwrap();
+    assert_eq!(serde_json::from_str::<Neat>(&json).unwrap().mutation_rates, network.mutation_rates);
+}
+
+
+#[test]
+fn test_mutation_boost() {
+    let boost = MutationBoost::new(2).multiplier(2.0).cap(3.0);
+    assert_eq!(boost.factor(2), 1.0);
+    assert_eq!(boost.factor(3), 2.0);
+    assert_eq!(boost.factor(10), 3.0);
+
+    // the probabilities are capped and the perturbation scaled freely
+    let env = radiate::default_neat_env();
+    let boosted = env.boost_mutation(2.0).unwrap();
+    assert_eq!(boosted.weight_mutate_rate, Some(1.0));
+    assert_eq!(boosted.new_node_rate, Some(0.06));
+    assert_eq!(boosted.weight_perturb, Some(3.0));
+
+    // nothing ever improves on a flat problem, so every species is boosted once it is old enough
+    let mut population = flat_population(StoppingCriteria::new())
+        .configure(Config {
+            inbreed_rate: 0.001,
+            crossover_rate: 0.75,
+            distance: 2.0,
+            species_target: 5
+        })
+        .mutation_boost(boost);
+    let mut boosted = 0;
+    for _ in 0..8 {
+        let stats = population.step().unwrap();
+        for species in stats.boosted_species.iter() {
+            assert!(species.stagnation > 2);
+            assert_eq!(species.factor, boost.factor(species.stagnation));
+            assert!(stats.niches.iter().any(|niche| niche.niche_id == species.niche_id));
+        }
+        boosted += stats.boosted_species.len();
+    }
+    assert!(boosted > 0);
+}
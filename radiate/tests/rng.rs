@@ -0,0 +1,135 @@
+extern crate radiate;
+extern crate rand;
+extern crate serde_json;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use radiate::prelude::*;
+use radiate::rng::{self, Distribution};
+
+mod common;
+
+
+
+fn mean(samples: &[f32]) -> f32 {
+    samples.iter().sum::<f32>() / samples.len() as f32
+}
+
+
+#[test]
+fn test_seeded_streams_repeat() {
+    rng::seed(42);
+    let first = (0..10).map(|_| rng::thread().gen::<u64>()).collect::<Vec<_>>();
+    rng::seed(42);
+    let second = (0..10).map(|_| rng::thread().gen::<u64>()).collect::<Vec<_>>();
+    assert_eq!(first, second);
+    rng::seed(43);
+    assert_ne!(rng::thread().gen::<u64>(), first[0]);
+}
+
+
+#[test]
+fn test_distributions() {
+    let mut r = StdRng::seed_from_u64(7);
+    let count = 20000;
+
+    let weights = (0..count).map(|_| rng::weight(&mut r)).collect::<Vec<_>>();
+    assert!(weights.iter().all(|w| (-1.0..1.0).contains(w)));
+    assert!(mean(&weights).abs() < 0.02);
+
+    let normal = Distribution::Normal { mean: 2.0, std: 0.5 };
+    let samples = (0..count).map(|_| normal.sample(&mut r)).collect::<Vec<_>>();
+    let std = (samples.iter().map(|x| (x - 2.0).powi(2)).sum::<f32>() / count as f32).sqrt();
+    assert!((mean(&samples) - 2.0).abs() < 0.02);
+    assert!((std - 0.5).abs() < 0.02);
+
+    // the cauchy has no mean, but half its mass is within one scale of the location
+    let cauchy = Distribution::Cauchy { location: 1.0, scale: 0.1 };
+    let inside = (0..count).filter(|_| (cauchy.sample(&mut r) - 1.0).abs() < 0.1).count();
+    assert!((inside as f32 / count as f32 - 0.5).abs() < 0.02);
+
+    let uniform = Distribution::Uniform { low: 3.0, high: 5.0 };
+    assert!((0..count).map(|_| uniform.sample(&mut r)).all(|x| (3.0..5.0).contains(&x)));
+}
+
+
+fn seeded_run(seed: u64) -> (Vec<f32>, serde_json::Value) {
+    seeded_run_with(seed, true)
+}
+
+
+fn seeded_population(seed: u64, parallel: bool) -> Population<Neat, NeatEnvironment, BenchmarkProblem> {
+    common::xor_population(30, BenchmarkProblem::new(Benchmark::Xor, 0))
+        .stagnation(3, vec![Genocide::KillWorst(0.9)])
+        .seed(seed)
+        .parallel_eval(parallel)
+        .populate_base()
+}
+
+
+fn seeded_run_with(seed: u64, parallel: bool) -> (Vec<f32>, serde_json::Value) {
+    let mut pop = seeded_population(seed, parallel);
+    let scores = (0..10).map(|_| pop.train().unwrap().0).collect();
+    (scores, serde_json::to_value(&pop.to_checkpoint().members).unwrap())
+}
+
+
+#[test]
+fn test_seeded_population_repeats() {
+    let (scores, members) = seeded_run(11);
+    let (again_scores, again_members) = seeded_run(11);
+    assert_eq!(scores, again_scores);
+    assert_eq!(members, again_members);
+    let (_, other_members) = seeded_run(12);
+    assert_ne!(members, other_members);
+}
+
+
+#[test]
+fn test_serial_eval_matches_parallel() {
+    let (scores, members) = seeded_run_with(11, true);
+    let (serial_scores, serial_members) = seeded_run_with(11, false);
+    assert_eq!(scores, serial_scores);
+    assert_eq!(members, serial_members);
+}
+
+
+#[test]
+fn test_determinism_audit() {
+    // threads scoring the members in any order shouldn't change a seeded run
+    assert_eq!(audit::determinism(|run| seeded_population(11, run == 0), 6), Ok(6));
+
+    // a different seed is caught before anything is evaluated
+    let divergence = audit::determinism(|run| seeded_population(11 + run as u64, true), 6).unwrap_err();
+    assert_eq!(divergence.generation, 0);
+    assert_eq!(divergence.member, Some(0));
+    match divergence.diverged {
+        Diverged::Genome { path } => assert!(path.starts_with("layers[0]")),
+        other => panic!("expected the genomes to differ, got {:?}", other)
+    }
+
+    // and a problem which keeps state between runs is caught when it's evaluated
+    let drifting = |_| Population::<Neat, NeatEnvironment, Drifting>::new()
+        .size(10)
+        .constrain(radiate::default_neat_env().set_input_size(2).set_output_size(1))
+        .seed(3)
+        .populate_base();
+    let divergence = audit::determinism(drifting, 6).unwrap_err();
+    assert_eq!((divergence.generation, divergence.member), (0, Some(0)));
+    assert!(matches!(divergence.diverged, Diverged::Fitness(_, _)));
+}
+
+
+/// scores each member by how many members were scored before it
+struct Drifting;
+
+static SCORED: AtomicUsize = AtomicUsize::new(0);
+
+impl Problem<Neat> for Drifting {
+    fn empty() -> Self { Drifting }
+
+    fn solve(&self, _: &mut Neat) -> f32 {
+        SCORED.fetch_add(1, Ordering::Relaxed) as f32
+    }
+}
@@ -0,0 +1,108 @@
+#![feature(test)]
+
+extern crate test;
+extern crate radiate;
+
+use test::Bencher;
+use radiate::prelude::*;
+use radiate::models::neat::layers::vectorops;
+
+
+
+fn values(len: usize, offset: f32) -> Vec<f32> {
+    (0..len).map(|i| ((i as f32 + offset) * 0.37).sin() * 3.0).collect()
+}
+
+
+fn close(one: &[f32], two: &[f32], tolerance: f32) -> bool {
+    one.len() == two.len() && one.iter().zip(two.iter()).all(|(a, b)| (a - b).abs() <= tolerance)
+}
+
+
+#[test]
+fn test_vectorops_match_scalar() {
+    // lengths on either side of a chunk so the padded tails are covered
+    for len in [0, 1, 7, 8, 9, 16, 37] {
+        let (one, two) = (values(len, 0.0), values(len, 5.0));
+
+        let mut multiplied = one.clone();
+        vectorops::element_multiply(&mut multiplied, &two);
+        assert_eq!(multiplied, one.iter().zip(two.iter()).map(|(a, b)| a * b).collect::<Vec<_>>());
+        assert_eq!(vectorops::product(&one, &two), multiplied);
+
+        let mut added = one.clone();
+        vectorops::element_add(&mut added, &two);
+        assert_eq!(added, one.iter().zip(two.iter()).map(|(a, b)| a + b).collect::<Vec<_>>());
+        assert_eq!(vectorops::subtract(&one, &two), one.iter().zip(two.iter()).map(|(a, b)| a - b).collect::<Vec<_>>());
+
+        let mut inverted = one.clone();
+        vectorops::element_invert(&mut inverted);
+        assert_eq!(inverted, one.iter().map(|a| 1.0 - a).collect::<Vec<_>>());
+        assert_eq!(vectorops::d_softmax(&one), one.iter().map(|a| a * (1.0 - a)).collect::<Vec<_>>());
+
+        for func in [Activation::Sigmoid, Activation::Tanh, Activation::Relu] {
+            for precision in [Precision::Exact, Precision::Fast, Precision::Strict] {
+                let activated = one.iter().map(|x| func.activate_with(*x, precision)).collect::<Vec<_>>();
+                let deactivated = one.iter().map(|x| func.deactivate_with(*x, precision)).collect::<Vec<_>>();
+                assert!(close(&vectorops::element_activate_with(&one, func, precision), &activated, 1e-6));
+                assert!(close(&vectorops::element_deactivate_with(&one, func, precision), &deactivated, 1e-6));
+            }
+        }
+    }
+}
+
+
+#[bench]
+fn bench_scalar_multiply_add(b: &mut Bencher) {
+    let (mut one, two) = (values(256, 0.0), values(256, 5.0));
+    b.iter(|| {
+        one.iter_mut().zip(two.iter()).for_each(|(a, b)| *a *= b);
+        one.iter_mut().zip(two.iter()).for_each(|(a, b)| *a += b);
+        test::black_box(&one);
+    });
+}
+
+
+#[bench]
+fn bench_vectorops_multiply_add(b: &mut Bencher) {
+    let (mut one, two) = (values(256, 0.0), values(256, 5.0));
+    b.iter(|| {
+        vectorops::element_multiply(&mut one, &two);
+        vectorops::element_add(&mut one, &two);
+        test::black_box(&one);
+    });
+}
+
+
+#[bench]
+fn bench_scalar_fast_tanh(b: &mut Bencher) {
+    let one = values(256, 0.0);
+    // the layers only know their activation and precision at runtime
+    let (func, precision) = test::black_box((Activation::Tanh, Precision::Fast));
+    b.iter(|| {
+        test::black_box(one.iter().map(|x| func.activate_with(*x, precision)).collect::<Vec<_>>());
+    });
+}
+
+
+#[bench]
+fn bench_vectorops_fast_tanh(b: &mut Bencher) {
+    let one = values(256, 0.0);
+    let (func, precision) = test::black_box((Activation::Tanh, Precision::Fast));
+    b.iter(|| {
+        test::black_box(vectorops::element_activate_with(&one, func, precision));
+    });
+}
+
+
+#[bench]
+fn bench_lstm_forward(b: &mut Bencher) {
+    let mut layer = LSTM::new(16, 64, 8, Activation::Tanh);
+    layer.set_precision(Precision::Fast);
+    let inputs = (0..32).map(|i| values(16, i as f32)).collect::<Vec<_>>();
+    b.iter(|| {
+        test::black_box(layer.forward_batch(&inputs).unwrap());
+        layer.reset();
+    });
+}
+
@@ -0,0 +1,47 @@
+extern crate radiate;
+extern crate serde;
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+pub mod spec;
+pub mod problem;
+pub mod run;
+pub mod sweep;
+
+use std::env;
+use std::error::Error;
+
+use spec::ExperimentSpec;
+use sweep::Sweep;
+
+
+
+const USAGE: &str = "usage: radiate-cli [run] [--resume] <experiment.toml|yaml|json>\n       radiate-cli sweep <sweep.toml|yaml|json>\n       radiate-cli audit <experiment.toml|yaml|json>";
+
+
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.iter().map(|x| x.as_str()).collect::<Vec<_>>().as_slice() {
+        ["run", path] | [path] => {
+            run::run(&ExperimentSpec::from_file(path)?)?;
+        },
+        ["run", "--resume", path] | ["--resume", path] => {
+            let mut spec = ExperimentSpec::from_file(path)?;
+            spec.output.resume = true;
+            run::run(&spec)?;
+        },
+        ["sweep", path] => {
+            Sweep::from_file(path)?.run()?;
+        },
+        ["audit", path] => {
+            run::audit(&ExperimentSpec::from_file(path)?)?;
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
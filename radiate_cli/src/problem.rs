@@ -0,0 +1,94 @@
+extern crate libloading;
+
+use std::error::Error;
+use std::os::raw::c_void;
+use std::slice;
+
+use libloading::Library;
+use radiate::prelude::*;
+
+
+
+/// Callback handed to a library problem so it can run the network being scored.
+/// Copies `input_len` inputs through the network and writes at most `output_len`
+/// outputs back, returning false if the network couldn't produce an output.
+pub type ForwardFn = extern "C" fn(net: *mut c_void, inputs: *const f32, input_len: usize, outputs: *mut f32, output_len: usize) -> bool;
+
+
+/// Signature of the evaluation function a dynamic library must export. The library is
+/// given the forward callback and an opaque pointer to the network and returns its fitness.
+pub type EvaluateFn = unsafe extern "C" fn(forward: ForwardFn, net: *mut c_void) -> f32;
+
+
+
+/// A problem defined outside of rust (or at least outside of this binary) in a
+/// dynamic library, the library is kept loaded for as long as the problem lives
+#[derive(Debug)]
+pub struct LibraryProblem {
+    evaluate: EvaluateFn,
+    _library: Library
+}
+
+
+
+/// The problems the cli is able to run
+#[derive(Debug)]
+pub enum CliProblem {
+    Benchmark(BenchmarkProblem),
+    Library(LibraryProblem)
+}
+
+
+
+impl LibraryProblem {
+
+    pub fn load(path: &str, symbol: &str) -> Result<Self, Box<dyn Error>> {
+        let library = Library::new(path)?;
+        let evaluate = unsafe { *library.get::<EvaluateFn>(symbol.as_bytes())? };
+        Ok(LibraryProblem {
+            evaluate,
+            _library: library
+        })
+    }
+}
+
+
+
+impl Problem<Neat> for CliProblem {
+
+    fn empty() -> Self {
+        CliProblem::Benchmark(BenchmarkProblem::empty())
+    }
+
+    fn solve(&self, model: &mut Neat) -> f32 {
+        match self {
+            CliProblem::Benchmark(problem) => problem.solve(model),
+            CliProblem::Library(problem) => {
+                model.reset();
+                let score = unsafe { (problem.evaluate)(forward, model as *mut Neat as *mut c_void) };
+                model.reset();
+                score
+            }
+        }
+    }
+}
+
+
+
+extern "C" fn forward(net: *mut c_void, inputs: *const f32, input_len: usize, outputs: *mut f32, output_len: usize) -> bool {
+    if net.is_null() || inputs.is_null() || outputs.is_null() {
+        return false;
+    }
+    let model = unsafe { &mut *(net as *mut Neat) };
+    let inputs = unsafe { slice::from_raw_parts(inputs, input_len) }.to_vec();
+    match model.forward(&inputs) {
+        Ok(guess) => {
+            let outputs = unsafe { slice::from_raw_parts_mut(outputs, output_len) };
+            for (out, val) in outputs.iter_mut().zip(guess.iter()) {
+                *out = *val;
+            }
+            true
+        },
+        Err(_) => false
+    }
+}
@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::error::Error;
+use std::path::Path;
+use std::time::Instant;
+
+use radiate::prelude::*;
+use radiate::rng;
+use super::spec::{ExperimentSpec, ProblemSpec};
+use super::problem::{CliProblem, LibraryProblem};
+
+
+
+/// What a finished run is summarized as, written next to the metrics as summary.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub generations: usize,
+    pub best_fitness: f32,
+    pub millis: u128
+}
+
+
+
+/// Run a single experiment to completion, writing metrics, the weight stats of each generation's
+/// top member, checkpoints, the champion and its graph report, and the run summary into the spec's output directory.
+/// A resumed run carries on from the directory's checkpoint (or starts over without one), the rows
+/// written after the checkpoint are dropped and the champion is picked back up from its dump
+pub fn run(spec: &ExperimentSpec) -> Result<RunSummary, Box<dyn Error>> {
+    let output = Path::new(&spec.output.directory);
+    fs::create_dir_all(output)?;
+    let checkpoint = output.join("checkpoint.json");
+    let checkpoint = checkpoint.to_str().ok_or("invalid output directory")?;
+
+    let mut population = population(spec, problem(spec)?);
+    if let Some(every) = spec.output.checkpoint_every.filter(|every| *every > 0) {
+        population = population.checkpoint(checkpoint, every);
+    }
+    let resumed = spec.output.resume && Path::new(checkpoint).exists();
+    if resumed {
+        population = population.resume(checkpoint)?;
+    }
+    let mut generation = population.get_generation();
+    let mut champion = if resumed { resumed_champion(output, generation)? } else { None };
+    let resumed = resumed.then_some(generation);
+    let mut metrics = open_csv(&output.join("metrics.csv"), "generation,fitness,best_fitness,millis,species,distance", resumed)?;
+    let mut weights = open_csv(&output.join("weights.csv"), "generation,layer,type,count,min,max,mean,std,near_zero,histogram", resumed)?;
+
+    let start = Instant::now();
+    loop {
+        let (fitness, member) = population.train().ok_or("population failed to produce a generation")?;
+        generation += 1;
+        write_weights(&mut weights, generation, &member)?;
+
+        if champion.as_ref().is_none_or(|(best, _)| fitness > *best) {
+            champion = Some((fitness, member));
+        }
+        let best = champion.as_ref().map(|(best, _)| *best).unwrap_or(fitness);
+        writeln!(metrics, "{},{},{},{},{},{}", generation, fitness, best, start.elapsed().as_millis(), population.get_species_count(), population.get_distance())?;
+        println!("Generation: {} score: {} best: {}", generation, fitness, best);
+
+        // the champion is dumped alongside each checkpoint so a resumed run can pick it back up
+        if let (Some(every), Some((_, net))) = (spec.output.checkpoint_every, champion.as_ref()) {
+            if every > 0 && generation.is_multiple_of(every) {
+                let file = output.join(format!("champion-{}.json", generation));
+                net.save(file.to_str().ok_or("invalid output directory")?)?;
+            }
+        }
+
+        let solved = spec.stop.target_fitness.is_some_and(|target| best >= target);
+        let exhausted = spec.stop.generations.is_some_and(|max| generation >= max);
+        if solved || exhausted || population.is_stopped() {
+            break;
+        }
+    }
+
+    let (best_fitness, net) = champion.ok_or("run finished without a champion")?;
+    net.save(output.join("champion.json").to_str().ok_or("invalid output directory")?)?;
+    fs::write(output.join("graph.json"), serde_json::to_string_pretty(&net.graph_report())?)?;
+    let summary = RunSummary {
+        generations: generation,
+        best_fitness,
+        millis: start.elapsed().as_millis()
+    };
+    fs::write(output.join("summary.json"), serde_json::to_string_pretty(&summary)?)?;
+    println!("Finished after {} generations, best score: {}", generation, best_fitness);
+    Ok(summary)
+}
+
+
+
+/// Run the experiment twice from its seed side by side and report the first place the runs differ,
+/// for as many generations as the spec stops at (or 50 without a limit). Nothing is written out
+pub fn audit(spec: &ExperimentSpec) -> Result<(), Box<dyn Error>> {
+    if spec.population.seed.is_none() {
+        return Err("an audit needs a seed in [population] to repeat the run from".into());
+    }
+    let problems = RefCell::new(vec![problem(spec)?, problem(spec)?]);
+    let generations = spec.stop.generations.unwrap_or(50);
+    match audit::determinism(|_| population(spec, problems.borrow_mut().pop().unwrap()), generations) {
+        Ok(matched) => {
+            println!("Both runs matched for {} generations", matched);
+            Ok(())
+        },
+        Err(divergence) => {
+            println!("{}", divergence);
+            Err("the run is not deterministic".into())
+        }
+    }
+}
+
+
+
+/// open a csv in the output directory. A fresh run starts it over with the header, a run resumed
+/// from generation keeps the header and the rows up to and including that generation
+fn open_csv(path: &Path, header: &str, resumed: Option<usize>) -> Result<File, Box<dyn Error>> {
+    let kept = match (resumed, fs::read_to_string(path)) {
+        (Some(generation), Ok(contents)) => contents.lines()
+            .skip(1)
+            .filter(|row| row.split(',').next().and_then(|x| x.parse::<usize>().ok()).is_some_and(|row| row <= generation))
+            .map(|row| format!("{}\n", row))
+            .collect::<String>(),
+        _ => String::new()
+    };
+    fs::write(path, format!("{}\n{}", header, kept))?;
+    Ok(OpenOptions::new().append(true).open(path)?)
+}
+
+
+
+/// the champion as of the checkpoint at generation, from its dump and the best fitness
+/// in the metrics row of that generation. None if either is missing
+fn resumed_champion(output: &Path, generation: usize) -> Result<Option<(f32, Neat)>, Box<dyn Error>> {
+    let dump = output.join(format!("champion-{}.json", generation));
+    let best = fs::read_to_string(output.join("metrics.csv")).ok().and_then(|contents| contents.lines()
+        .map(|row| row.split(',').collect::<Vec<_>>())
+        .find(|row| row[0].parse::<usize>().ok() == Some(generation))
+        .and_then(|row| row.get(2).and_then(|best| best.parse::<f32>().ok())));
+    match best {
+        Some(best) if dump.exists() => Ok(Some((best, Neat::load(dump.to_str().ok_or("invalid output directory")?)?))),
+        _ => Ok(None)
+    }
+}
+
+
+
+fn problem(spec: &ExperimentSpec) -> Result<CliProblem, Box<dyn Error>> {
+    Ok(match &spec.problem {
+        ProblemSpec::Benchmark { benchmark, seed } => CliProblem::Benchmark(BenchmarkProblem::new(benchmark.clone(), *seed)),
+        ProblemSpec::Library { path, symbol, .. } => CliProblem::Library(LibraryProblem::load(path, symbol)?)
+    })
+}
+
+
+
+/// a seeded population starts from a network built from the same seed, otherwise the
+/// innovation ids of its edges would be different every run
+fn population(spec: &ExperimentSpec, problem: CliProblem) -> Population<Neat, NeatEnvironment, CliProblem> {
+    let network = match spec.population.seed {
+        Some(seed) => rng::scoped(seed, || spec.network()),
+        None => spec.network()
+    };
+    Population::<Neat, NeatEnvironment, CliProblem>::new()
+        .constrain(spec.environment())
+        .population_config(spec.population.clone())
+        .populate_clone(network)
+        .impose(problem)
+}
+
+
+
+/// one row per layer of the top member's weight stats, the histogram buckets are split by ;
+fn write_weights(file: &mut File, generation: usize, net: &Neat) -> Result<(), Box<dyn Error>> {
+    for (index, (layer_type, stats)) in net.weight_report().layers.iter().enumerate() {
+        let histogram = stats.histogram.iter().map(|count| count.to_string()).collect::<Vec<_>>().join(";");
+        writeln!(file, "{},{},{:?},{},{},{},{},{},{},{}",
+            generation, index, layer_type, stats.count, stats.min, stats.max, stats.mean, stats.std, stats.near_zero, histogram)?;
+    }
+    Ok(())
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(directory: &str, generations: usize, resume: bool) -> ExperimentSpec {
+        ExperimentSpec::from_value(json!({
+            "problem": { "type": "benchmark", "benchmark": { "Parity": 3 } },
+            "network": { "hidden": [4] },
+            "environment": {
+                "weight_mutate_rate": 0.8,
+                "edit_weights": 0.1,
+                "weight_perturb": 1.5,
+                "new_node_rate": 0.08,
+                "new_edge_rate": 0.08,
+                "reactivate": 0.2,
+                "activation_functions": ["Sigmoid", "Relu"]
+            },
+            "population": {
+                "size": 20,
+                "seed": 7,
+                "config": { "inbreed_rate": 0.001, "crossover_rate": 0.75, "distance": 0.5, "species_target": 5 }
+            },
+            "stop": { "generations": generations },
+            "output": { "directory": directory, "checkpoint_every": 2, "resume": resume }
+        })).unwrap()
+    }
+
+    #[test]
+    fn run_resumes_from_its_checkpoint() {
+        let directory = std::env::temp_dir().join(format!("radiate-cli-resume-{}", std::process::id()));
+        let directory = directory.to_str().unwrap();
+        run(&spec(directory, 5, false)).unwrap();
+        assert!(Path::new(directory).join("checkpoint.json").exists());
+        let (checkpointed, _) = resumed_champion(Path::new(directory), 4).unwrap().unwrap();
+
+        let resumed = run(&spec(directory, 7, true)).unwrap();
+        let metrics = fs::read_to_string(Path::new(directory).join("metrics.csv")).unwrap();
+        let generations = metrics.lines().skip(1).map(|row| row.split(',').next().unwrap().parse::<usize>().unwrap()).collect::<Vec<_>>();
+        fs::remove_dir_all(directory).unwrap();
+
+        assert_eq!(resumed.generations, 7);
+        assert_eq!(generations, (1..=7).collect::<Vec<_>>());
+        assert!(resumed.best_fitness >= checkpointed);
+    }
+}
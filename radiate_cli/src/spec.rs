@@ -0,0 +1,293 @@
+extern crate toml;
+extern crate serde_yaml;
+
+use std::fs;
+use std::error::Error;
+use std::path::Path;
+
+use radiate::prelude::*;
+use serde_json::Value;
+
+
+
+/// An experiment spec is everything needed to launch a run without writing any rust.
+/// Specs are read from toml, yaml, or json files, picked by the file extension.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentSpec {
+    pub problem: ProblemSpec,
+    pub network: NetworkSpec,
+    pub environment: NeatEnvironment,
+    pub population: PopulationConfig,
+    #[serde(default)]
+    pub stop: StopSpec,
+    #[serde(default)]
+    pub output: OutputSpec
+}
+
+
+
+/// Which problem to evolve against. Either one of the built in benchmarks or a
+/// dynamic library exposing an evaluation function, see `problem::LibraryProblem`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ProblemSpec {
+    Benchmark { 
+        benchmark: Benchmark,
+        #[serde(default)]
+        seed: u64 
+    },
+    Library { 
+        path: String, 
+        #[serde(default = "default_symbol")]
+        symbol: String,
+        input_size: u32,
+        output_size: u32 
+    }
+}
+
+
+
+/// The starting network every member of the population is cloned from.
+/// Hidden dense layers are stacked in order before the output dense pool layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkSpec {
+    #[serde(default)]
+    pub hidden: Vec<u32>,
+    #[serde(default = "default_activation")]
+    pub hidden_activation: Activation,
+    #[serde(default = "default_activation")]
+    pub output_activation: Activation
+}
+
+
+
+/// When to stop the run, whichever is hit first. A target fitness might never be reached so
+/// a spec has to give generations, or a patience or time limit in its population's stopping
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StopSpec {
+    pub generations: Option<usize>,
+    pub target_fitness: Option<f32>
+}
+
+
+
+/// Where to write the run's output. Every checkpoint_every generations (if set) the population
+/// is saved to checkpoint.json and the champion so far to champion-<generation>.json, the final
+/// champion is written once the run finishes and metrics are written as a csv. With resume set a
+/// run picks up from the checkpoint in the directory if there is one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputSpec {
+    pub directory: String,
+    pub checkpoint_every: Option<usize>,
+    #[serde(default)]
+    pub resume: bool
+}
+
+
+
+impl ExperimentSpec {
+
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_value(read_value(path)?)
+    }
+
+
+    pub fn from_value(value: Value) -> Result<Self, Box<dyn Error>> {
+        let spec: ExperimentSpec = serde_json::from_value(value)?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+
+    /// check the environment and population settings with the same rules
+    /// used when they are read in from their own toml files, and that the run will end
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.environment().validate()?;
+        self.population.validate()?;
+        let stopping = &self.population.stopping;
+        if self.stop.generations.is_none() && stopping.patience.is_none() && stopping.time_limit.is_none() {
+            return Err(ConfigError::MissingKey(String::from("stop.generations")));
+        }
+        Ok(())
+    }
+
+
+    /// the (input size, output size) of the network the problem expects
+    pub fn shape(&self) -> (u32, u32) {
+        match &self.problem {
+            ProblemSpec::Benchmark { benchmark, .. } => benchmark.shape(),
+            ProblemSpec::Library { input_size, output_size, .. } => (*input_size, *output_size)
+        }
+    }
+
+
+    /// the environment for the population, sized to the problem. A missing recurrent
+    /// neuron rate is treated the same as NeatEnvironment::new treats it, as 0
+    pub fn environment(&self) -> NeatEnvironment {
+        let (input_size, output_size) = self.shape();
+        let mut environment = self.environment.clone()
+            .set_input_size(input_size)
+            .set_output_size(output_size);
+        if environment.recurrent_neuron_rate.is_none() {
+            environment.recurrent_neuron_rate = Some(0.0);
+        }
+        environment
+    }
+
+
+    /// build the starting network for the population
+    pub fn network(&self) -> Neat {
+        let (input_size, output_size) = self.shape();
+        let mut net = Neat::new().input_size(input_size);
+        for size in self.network.hidden.iter() {
+            net = net.dense(*size, self.network.hidden_activation);
+        }
+        net.dense_pool(output_size, self.network.output_activation)
+    }
+}
+
+
+
+impl Default for OutputSpec {
+    fn default() -> Self {
+        OutputSpec {
+            directory: String::from("radiate-run"),
+            checkpoint_every: None,
+            resume: false
+        }
+    }
+}
+
+
+
+/// Read a spec file of any supported format into a generic value so it can be
+/// edited (ie: by a sweep) before being turned into an experiment
+pub fn read_value(path: &str) -> Result<Value, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+    let value = match Path::new(path).extension().and_then(|x| x.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)?,
+        Some("json") => serde_json::from_str(&contents)?,
+        _ => toml::from_str(&contents)?
+    };
+    Ok(value)
+}
+
+
+
+fn default_symbol() -> String {
+    String::from("radiate_evaluate")
+}
+
+
+fn default_activation() -> Activation {
+    Activation::Sigmoid
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TOML: &str = r#"
+[problem]
+type = "benchmark"
+benchmark = { Parity = 3 }
+
+[network]
+hidden = [4]
+
+[environment]
+weight_mutate_rate = 0.8
+edit_weights = 0.1
+weight_perturb = 1.5
+new_node_rate = 0.08
+new_edge_rate = 0.08
+reactivate = 0.2
+activation_functions = ["Sigmoid", "Relu"]
+
+[population]
+size = 40
+
+[population.config]
+inbreed_rate = 0.001
+crossover_rate = 0.75
+distance = 0.5
+species_target = 5
+
+[stop]
+generations = 25
+target_fitness = 0.99
+"#;
+
+    const YAML: &str = r#"
+problem:
+  type: benchmark
+  benchmark:
+    Parity: 3
+network:
+  hidden: [4]
+environment:
+  weight_mutate_rate: 0.8
+  edit_weights: 0.1
+  weight_perturb: 1.5
+  new_node_rate: 0.08
+  new_edge_rate: 0.08
+  reactivate: 0.2
+  activation_functions: [Sigmoid, Relu]
+population:
+  size: 40
+  config:
+    inbreed_rate: 0.001
+    crossover_rate: 0.75
+    distance: 0.5
+    species_target: 5
+stop:
+  generations: 25
+  target_fitness: 0.99
+"#;
+
+    /// write the spec into a file with the given extension and read it back in
+    fn read(name: &str, contents: &str) -> Result<ExperimentSpec, Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("radiate-cli-{}-{}", std::process::id(), name));
+        fs::write(&path, contents)?;
+        let spec = ExperimentSpec::from_file(path.to_str().unwrap());
+        fs::remove_file(&path)?;
+        spec
+    }
+
+    #[test]
+    fn toml_and_yaml_specs_match() {
+        let toml = read("spec.toml", TOML).unwrap();
+        let yaml = read("spec.yaml", YAML).unwrap();
+        for spec in [&toml, &yaml].iter() {
+            assert_eq!(spec.shape(), (3, 1));
+            assert_eq!(spec.network.hidden, vec![4]);
+            assert_eq!(spec.population.size, 40);
+            assert_eq!(spec.stop.generations, Some(25));
+            assert_eq!(spec.stop.target_fitness, Some(0.99));
+            assert_eq!(spec.output.directory, "radiate-run");
+        }
+        assert_eq!(serde_json::to_value(&toml).unwrap(), serde_json::to_value(&yaml).unwrap());
+    }
+
+    #[test]
+    fn spec_needs_a_stop_the_run_reaches() {
+        let target_only = TOML.replace("generations = 25\n", "");
+        match read("target.toml", &target_only).unwrap_err().downcast::<ConfigError>() {
+            Ok(err) => assert_eq!(err.key(), Some("stop.generations")),
+            Err(err) => panic!("expected a config error, got {}", err)
+        }
+        let without_stop = TOML.split("[stop]").next().unwrap();
+        assert!(read("none.toml", without_stop).is_err());
+        let patience = format!("{}[population.stopping]\npatience = 10\n", without_stop);
+        assert_eq!(read("patience.toml", &patience).unwrap().population.stopping.patience, Some(10));
+    }
+
+    #[test]
+    fn spec_rejects_invalid_settings() {
+        let err = read("size.toml", &TOML.replace("size = 40", "size = 0")).unwrap_err();
+        assert!(err.downcast_ref::<ConfigError>().is_some_and(|err| err.key() == Some("size")), "{}", err);
+        assert!(read("problem.toml", &TOML.replace("Parity = 3", "Unknown = 3")).is_err());
+    }
+}
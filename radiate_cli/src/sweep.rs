@@ -0,0 +1,360 @@
+extern crate rand;
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::env;
+use std::thread;
+use std::error::Error;
+use std::time::Duration;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Child, Stdio};
+
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::{Map, Value};
+
+use super::run::RunSummary;
+use super::spec::{self, ExperimentSpec};
+
+
+
+/// How the parameter lists of a sweep are turned into runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SweepMode {
+    /// every combination of every parameter
+    Grid,
+    /// `samples` runs, each picking a random value from each parameter's list
+    Random
+}
+
+
+
+/// The [sweep] section of a sweep file. Parameters are keyed by their dotted path in
+/// the experiment spec (ie: environment.new_node_rate) and list the values to try
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepSpec {
+    #[serde(default = "SweepSpec::default_mode")]
+    pub mode: SweepMode,
+    #[serde(default)]
+    pub samples: usize,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default = "SweepSpec::default_parallel")]
+    pub parallel: usize,
+    pub parameters: BTreeMap<String, Vec<Value>>
+}
+
+
+
+/// A sweep is an experiment spec with a [sweep] section, each run is the base
+/// experiment with one set of parameters written over it. Runs are executed as
+/// separate radiate-cli processes, `parallel` at a time, each writing into its
+/// own run-<n> directory under the base experiment's output directory.
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    pub base: Value,
+    pub spec: SweepSpec
+}
+
+
+
+/// One row of the sweep report, a run which exited with an error has no summary
+#[derive(Debug, Clone)]
+pub struct SweepResult {
+    pub run: usize,
+    pub parameters: BTreeMap<String, Value>,
+    pub summary: Option<RunSummary>
+}
+
+
+
+impl SweepSpec {
+
+    fn default_mode() -> SweepMode {
+        SweepMode::Grid
+    }
+
+    fn default_parallel() -> usize {
+        1
+    }
+
+
+    /// a random sweep has to be given how many runs to sample
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.mode == SweepMode::Random && self.samples == 0 {
+            return Err("a random sweep needs `samples` set to the number of runs".into());
+        }
+        Ok(())
+    }
+}
+
+
+
+impl Sweep {
+
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut base = spec::read_value(path)?;
+        let sweep = base.as_object_mut()
+            .and_then(|table| table.remove("sweep"))
+            .ok_or("sweep file is missing a [sweep] section")?;
+        let spec: SweepSpec = serde_json::from_value(sweep)?;
+        spec.validate()?;
+        Ok(Sweep { base, spec })
+    }
+
+
+    /// the parameter assignment of every run in the sweep
+    pub fn expand(&self) -> Vec<BTreeMap<String, Value>> {
+        match self.spec.mode {
+            SweepMode::Grid => {
+                let mut runs = vec![BTreeMap::new()];
+                for (key, values) in self.spec.parameters.iter() {
+                    runs = runs.into_iter()
+                        .flat_map(|run| values.iter().map(move |val| {
+                            let mut next = run.clone();
+                            next.insert(key.clone(), val.clone());
+                            next
+                        }))
+                        .collect();
+                }
+                runs
+            },
+            SweepMode::Random => {
+                let mut r = StdRng::seed_from_u64(self.spec.seed);
+                (0..self.spec.samples)
+                    .map(|_| {
+                        self.spec.parameters.iter()
+                            .filter(|(_, values)| !values.is_empty())
+                            .map(|(key, values)| (key.clone(), values[r.gen_range(0, values.len())].clone()))
+                            .collect()
+                    })
+                    .collect()
+            }
+        }
+    }
+
+
+    /// the experiment for a single run, checked so a bad parameter
+    /// fails the sweep before any runs are started
+    pub fn experiment(&self, run: usize, parameters: &BTreeMap<String, Value>) -> Result<Value, Box<dyn Error>> {
+        let mut experiment = self.base.clone();
+        for (key, val) in parameters.iter() {
+            set_path(&mut experiment, key, val.clone())?;
+        }
+        let directory = self.directory().join(format!("run-{}", run));
+        set_path(&mut experiment, "output.directory", Value::from(directory.to_string_lossy().to_string()))?;
+        ExperimentSpec::from_value(experiment.clone())?;
+        Ok(experiment)
+    }
+
+
+    /// execute every run and write the comparison report, returning the results
+    pub fn run(&self) -> Result<Vec<SweepResult>, Box<dyn Error>> {
+        let runs = self.expand();
+        let directory = self.directory();
+        fs::create_dir_all(&directory)?;
+
+        let mut files = Vec::with_capacity(runs.len());
+        for (run, parameters) in runs.iter().enumerate() {
+            let experiment = self.experiment(run, parameters)?;
+            let run_dir = directory.join(format!("run-{}", run));
+            fs::create_dir_all(&run_dir)?;
+            // a summary left over from an earlier sweep would be read as this run's
+            match fs::remove_file(run_dir.join("summary.json")) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+            let file = run_dir.join("experiment.json");
+            fs::write(&file, serde_json::to_string_pretty(&experiment)?)?;
+            files.push(file);
+        }
+
+        let exe = env::current_exe()?;
+        let mut running: Vec<(usize, Child)> = Vec::new();
+        let mut succeeded = vec![false; files.len()];
+        for (run, file) in files.iter().enumerate() {
+            while running.len() >= self.spec.parallel.max(1) {
+                reap(&mut running, &mut succeeded)?;
+            }
+            println!("Starting run {} of {}", run + 1, files.len());
+            let log = File::create(file.with_file_name("log.txt"))?;
+            running.push((run, Command::new(&exe)
+                .arg("run")
+                .arg(file)
+                .stdout(Stdio::from(log))
+                .spawn()?));
+        }
+        while !running.is_empty() {
+            reap(&mut running, &mut succeeded)?;
+        }
+
+        let results = runs.into_iter()
+            .enumerate()
+            .map(|(run, parameters)| SweepResult {
+                run,
+                parameters,
+                summary: match succeeded[run] {
+                    true => read_summary(&directory.join(format!("run-{}", run))),
+                    false => None
+                }
+            })
+            .collect::<Vec<_>>();
+        self.report(&results)?;
+        Ok(results)
+    }
+
+
+    /// write report.csv into the sweep directory, one row per run keyed 
+    /// by the run's parameters and ordered from best to worst fitness
+    pub fn report(&self, results: &[SweepResult]) -> Result<(), Box<dyn Error>> {
+        let mut sorted = results.iter().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| {
+            let a = a.summary.as_ref().map_or(f32::MIN, |s| s.best_fitness);
+            let b = b.summary.as_ref().map_or(f32::MIN, |s| s.best_fitness);
+            b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let keys = self.spec.parameters.keys().cloned().collect::<Vec<_>>();
+        let mut report = File::create(self.directory().join("report.csv"))?;
+        writeln!(report, "run,{},generations,best_fitness,millis", keys.join(","))?;
+        for result in sorted {
+            let params = keys.iter()
+                .map(|key| result.parameters.get(key).map_or(String::new(), |val| val.to_string().replace(',', ";")))
+                .collect::<Vec<_>>();
+            let summary = match &result.summary {
+                Some(s) => format!("{},{},{}", s.generations, s.best_fitness, s.millis),
+                None => String::from("failed,,")
+            };
+            writeln!(report, "{},{},{}", result.run, params.join(","), summary)?;
+            println!("run {}: {} => {}", result.run, params.join(" "), summary);
+        }
+        Ok(())
+    }
+
+
+    fn directory(&self) -> PathBuf {
+        let directory = self.base.pointer("/output/directory")
+            .and_then(|dir| dir.as_str())
+            .unwrap_or("radiate-run");
+        PathBuf::from(directory)
+    }
+}
+
+
+
+/// set a dotted path in the spec, creating any tables along the way
+fn set_path(value: &mut Value, path: &str, val: Value) -> Result<(), Box<dyn Error>> {
+    let mut curr = value;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let table = curr.as_object_mut().ok_or_else(|| format!("sweep parameter `{}` does not point into a table", path))?;
+        if keys.peek().is_none() {
+            table.insert(key.to_string(), val);
+            return Ok(());
+        }
+        curr = table.entry(key).or_insert_with(|| Value::Object(Map::new()));
+    }
+    Ok(())
+}
+
+
+
+/// wait for at least one of the running runs to exit, whichever finishes first, and
+/// record whether each run that has exited did so successfully
+fn reap(running: &mut Vec<(usize, Child)>, succeeded: &mut [bool]) -> io::Result<()> {
+    loop {
+        let before = running.len();
+        let mut i = 0;
+        while i < running.len() {
+            match running[i].1.try_wait()? {
+                Some(status) => {
+                    let (run, _) = running.remove(i);
+                    succeeded[run] = status.success();
+                    if !status.success() {
+                        println!("run {} failed: {}", run, status);
+                    }
+                },
+                None => i += 1
+            }
+        }
+        if running.len() < before {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+
+
+fn read_summary(directory: &Path) -> Option<RunSummary> {
+    let contents = fs::read_to_string(directory.join("summary.json")).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn sweep(mode: SweepMode, samples: usize) -> Sweep {
+        let mut parameters = BTreeMap::new();
+        parameters.insert(String::from("environment.new_node_rate"), vec![json!(0.01), json!(0.03), json!(0.05)]);
+        parameters.insert(String::from("population.size"), vec![json!(50), json!(100)]);
+        Sweep {
+            base: json!({ "output": { "directory": "sweep-test" } }),
+            spec: SweepSpec { mode, samples, seed: 3, parallel: 1, parameters }
+        }
+    }
+
+    #[test]
+    fn grid_expands_every_combination() {
+        let runs = sweep(SweepMode::Grid, 0).expand();
+        assert_eq!(runs.len(), 6);
+        for rate in [0.01, 0.03, 0.05].iter() {
+            for size in [50, 100].iter() {
+                assert!(runs.iter().any(|run| run["environment.new_node_rate"] == json!(rate) && run["population.size"] == json!(size)));
+            }
+        }
+    }
+
+    #[test]
+    fn random_expands_samples() {
+        let sweep = sweep(SweepMode::Random, 10);
+        let runs = sweep.expand();
+        assert_eq!(runs.len(), 10);
+        for run in runs.iter() {
+            assert_eq!(run.len(), 2);
+            for (key, val) in run.iter() {
+                assert!(sweep.spec.parameters[key].contains(val));
+            }
+        }
+        assert_eq!(runs, sweep.expand());
+    }
+
+    #[test]
+    fn random_without_samples_is_rejected() {
+        assert!(sweep(SweepMode::Random, 0).spec.validate().is_err());
+        assert!(sweep(SweepMode::Random, 1).spec.validate().is_ok());
+        assert!(sweep(SweepMode::Grid, 0).spec.validate().is_ok());
+    }
+
+    #[test]
+    fn set_path_creates_and_overwrites() {
+        let mut value = json!({ "population": { "size": 10 }, "name": "xor" });
+        set_path(&mut value, "population.size", json!(20)).unwrap();
+        set_path(&mut value, "environment.weight_mutate_rate", json!(0.8)).unwrap();
+        set_path(&mut value, "stop.generations", json!(5)).unwrap();
+        assert_eq!(value, json!({
+            "population": { "size": 20 },
+            "environment": { "weight_mutate_rate": 0.8 },
+            "stop": { "generations": 5 },
+            "name": "xor"
+        }));
+        assert!(set_path(&mut value, "name.first", json!("x")).is_err());
+    }
+}
@@ -4,6 +4,7 @@ extern crate simple_matrix;
 extern crate radiate;
 
 use std::sync::{Arc, RwLock};
+use std::error::Error;
 use rand::rngs::ThreadRng;
 use rand::Rng;
 use simple_matrix::Matrix;
@@ -125,7 +126,7 @@ impl Genome<Evtree, TreeEnvionment> for Evtree {
     /// This function should attemp to produce a Evtree which is no higher than the 
     /// specified max height of a Evtree.
     #[inline]
-    fn crossover(one: &Evtree, two: &Evtree, settings: Arc<RwLock<TreeEnvionment>>, crossover_rate: f32) -> Option<Evtree> {
+    fn crossover(one: &Evtree, two: &Evtree, settings: Arc<RwLock<TreeEnvionment>>, crossover_rate: f32) -> Result<Evtree, Box<dyn Error>> {
         let set = &*(*settings).read().unwrap();
         // make a complete copy of the more fit tree and declare a random 
         // ThreadRng type to be used for random mutations
@@ -136,7 +137,7 @@ impl Genome<Evtree, TreeEnvionment> for Evtree {
         // specified max height of a tree in a config type
         let mut node_one = one.get_biased_random_node();
         let mut node_two = two.get_biased_random_node();
-        while node_one.depth() + node_two.height() > set.max_height? {
+        while node_one.depth() + node_two.height() > set.max_height.ok_or("max_height is not set")? {
             node_one = one.get_biased_random_node();
             node_two = two.get_biased_random_node();
         }
@@ -149,22 +150,22 @@ impl Genome<Evtree, TreeEnvionment> for Evtree {
             result.replace(node_index, node_two.deepcopy());
         } else {
             if r.gen::<f32>() < set.get_network_mutation_rate() {
-                result.edit_random_node_networks(set.weight_mutate_rate?, set.weight_transform_rate?, set.layer_mutate_rate?);
+                result.edit_random_node_networks(set.weight_mutate_rate.ok_or("weight_mutate_rate is not set")?, set.weight_transform_rate.ok_or("weight_transform_rate is not set")?, set.layer_mutate_rate.ok_or("layer_mutate_rate is not set")?);
             }
-            if r.gen::<f32>() < set.node_add_rate? {
-                result.insert_random(NetNode::new(set.input_size?, set.get_outputs()));
+            if r.gen::<f32>() < set.node_add_rate.ok_or("node_add_rate is not set")? {
+                result.insert_random(NetNode::new(set.input_size.ok_or("input_size is not set")?, set.get_outputs()));
             }
-            if r.gen::<f32>() < set.shuffle_rate? {
+            if r.gen::<f32>() < set.shuffle_rate.ok_or("shuffle_rate is not set")? {
                 result.shuffle_tree(&mut r);
             }
-            if r.gen::<f32>() < set.gut_rate? {
+            if r.gen::<f32>() < set.gut_rate.ok_or("gut_rate is not set")? {
                 result.gut_random_node(&mut r);
             }
             result.update_size();
         }
 
         // return the new tree
-        Some(result)
+        Ok(result)
     }
 
     /// Implement the base trait for the tree
@@ -7,7 +7,7 @@ pub mod web;
 
 pub use web::{
     dtos::{
-        populationdto::NeatPopulationBuilder,
+        populationdto::{NeatPopulationBuilder, VerifyDto, BatchDto},
         radiatedto::{
             RadiateDto,
             TrainDto,
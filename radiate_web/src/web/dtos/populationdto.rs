@@ -9,6 +9,33 @@ use radiate::engine::{
 };
 
 
+/// Check the fitness workers send back by scoring a random rate (0 to 1) of the members of each
+/// generation on two different workers. Results within tolerance of each other are taken, otherwise
+/// the server scores the member itself and counts a mismatch against whichever worker was wrong.
+/// A worker with quarantine_after mismatches gets no more work and its results are ignored (once
+/// every worker is quarantined the server scores the members itself), without it mismatches are
+/// only flagged in the simulation's status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyDto {
+    pub rate: f32,
+    pub tolerance: f32,
+    pub quarantine_after: Option<usize>
+}
+
+
+/// Hand workers batches of members sized by the throughput they report (members scored a second),
+/// enough to keep each one busy for about seconds but never more than max_size or its share of
+/// what's left of the generation. With steal a worker which runs out of work takes over the tasks
+/// still running on slower workers and whichever result comes back first is used. Without batching
+/// every worker gets one member at a time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchDto {
+    pub seconds: f32,
+    pub max_size: usize,
+    pub steal: bool
+}
+
+
 #[derive(Debug, Clone, Serialize, Deserialize)] 
 pub struct NeatPopulationBuilder {
     pub num_evolve: Option<i32>,
@@ -20,7 +47,9 @@ pub struct NeatPopulationBuilder {
     pub stagnation: Option<usize>,
     pub genocide: Option<Vec<Genocide>>,
     pub survivor_criteria: Option<SurvivalCriteria>,
-    pub parental_criteria: Option<ParentalCriteria>
+    pub parental_criteria: Option<ParentalCriteria>,
+    pub verify: Option<VerifyDto>,
+    pub batch: Option<BatchDto>
 }
 
 impl NeatPopulationBuilder {
@@ -36,7 +65,9 @@ impl NeatPopulationBuilder {
             stagnation: None,
             genocide: None,
             survivor_criteria: Some(SurvivalCriteria::Fittest),
-            parental_criteria: Some(ParentalCriteria::BiasedRandom)
+            parental_criteria: Some(ParentalCriteria::BiasedRandom),
+            verify: None,
+            batch: None
         }
     }
 
@@ -89,5 +120,15 @@ impl NeatPopulationBuilder {
         self.parental_criteria = Some(par);
         self
     }
+
+    pub fn verify(mut self, rate: f32, tolerance: f32, quarantine_after: Option<usize>) -> Self {
+        self.verify = Some(VerifyDto { rate, tolerance, quarantine_after });
+        self
+    }
+
+    pub fn batch(mut self, seconds: f32, max_size: usize, steal: bool) -> Self {
+        self.batch = Some(BatchDto { seconds, max_size, steal });
+        self
+    }
     
 }